@@ -0,0 +1,25 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // The runtime embeds the vendored axe-core; fail here rather than ship without it
+    println!("cargo:rerun-if-changed=vendor/axe-core/axe.min.js");
+    if std::env::var_os("CARGO_FEATURE_RUNTIME").is_some()
+        && !std::path::Path::new("vendor/axe-core/axe.min.js").is_file()
+    {
+        panic!(
+            "vendor/axe-core/axe.min.js is missing; accessibility audits embed it. \
+             See vendor/axe-core/README.md to add it."
+        );
+    }
+
+    // Generate the gRPC service from proto/ (uses a bundled protoc unless PROTOC is set)
+    #[cfg(feature = "grpc")]
+    {
+        if std::env::var_os("PROTOC").is_none() {
+            let protoc = protoc_bin_vendored::protoc_bin_path().expect("No bundled protoc");
+            std::env::set_var("PROTOC", protoc);
+        }
+        tonic_build::compile_protos("proto/robert_webdriver.proto")
+            .expect("Failed to compile proto/robert_webdriver.proto");
+    }
+}
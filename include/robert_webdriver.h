@@ -0,0 +1,64 @@
+/*
+ * C interface to robert-webdriver
+ *
+ * Build the shared library with:
+ *   cargo rustc --release --lib --features ffi --crate-type cdylib
+ *
+ * Every call blocks until the browser work is done; a session must only be used
+ * from one thread at a time. Functions returning int return 0 on success and -1
+ * on error; functions returning char* return NULL on error. robert_last_error()
+ * describes the last error on the calling thread. Strings are UTF-8; strings
+ * returned by the library must be released with robert_string_free().
+ */
+
+#ifndef ROBERT_WEBDRIVER_H
+#define ROBERT_WEBDRIVER_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A Chrome session */
+typedef struct RobertSession RobertSession;
+
+/*
+ * Launch a Chrome session configured from config_path (NULL = $ROBERT_CONFIG or
+ * the default config location, plus ROBERT_* environment overrides).
+ * Returns NULL on error.
+ */
+RobertSession *robert_session_create(const char *config_path);
+
+/* Close the session's Chrome and free the session (NULL is ignored) */
+void robert_session_free(RobertSession *session);
+
+/* Navigate the current page to url and wait for it to load */
+int robert_navigate(RobertSession *session, const char *url);
+
+/*
+ * Execute a CDP script (JSON, as accepted by `robert-webdriver run`).
+ * Returns 0 if every command succeeded, 1 if a command failed, -1 if the script
+ * could not be run.
+ */
+int robert_run_script(RobertSession *session, const char *script_json);
+
+/* Execution report of the last robert_run_script() as JSON (NULL if none) */
+char *robert_report_json(RobertSession *session);
+
+/*
+ * Capture a step frame of the current page into output_dir (NULL = the config's
+ * artifacts.root) and return it as JSON. instruction may be NULL.
+ */
+char *robert_capture_frame(RobertSession *session, const char *output_dir,
+                           const char *instruction);
+
+/* Message of the last error on the calling thread (NULL if none) */
+char *robert_last_error(void);
+
+/* Free a string returned by the library (NULL is ignored) */
+void robert_string_free(char *value);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ROBERT_WEBDRIVER_H */
@@ -0,0 +1,328 @@
+//! Artifact Disk Usage
+//!
+//! Step frames are the bulk of what runs write: a screenshot, the DOM, and maybe a
+//! VisualDom snapshot per step, in `screenshots/`, `dom/`, and `visualdom/`
+//! directories under `artifacts.root`. Left alone they fill the disk, and a capture
+//! that runs out of space halfway leaves frames with missing files.
+//!
+//! An [`ArtifactQuota`] guards captures: it measures the artifacts once, counts what
+//! each captured frame adds, and before a capture fails it with
+//! [`BrowserError::DiskSpace`] if the artifacts are over `artifacts.max_mb` or the
+//! disk has less than `artifacts.min_free_mb` free. [`purge`] deletes frame files
+//! older than a given age (`robert-webdriver purge --older-than-days 7`); run
+//! history, cached scripts, and other outputs are left alone.
+
+use crate::config::ArtifactsConfig;
+use crate::error::{BrowserError, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+use utoipa::ToSchema;
+
+/// Directories step frame files are written to, at any depth below the root
+pub const FRAME_DIRS: &[&str] = &["screenshots", "dom", "visualdom"];
+
+const MIB: u64 = 1024 * 1024;
+
+/// Size of the files under an artifact root
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ArtifactUsage {
+    pub total_bytes: u64,
+    pub files: u64,
+
+    /// Bytes in each of the [`FRAME_DIRS`], and `other` for the rest
+    pub by_kind: BTreeMap<String, u64>,
+}
+
+/// What [`purge`] deleted
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct PurgeReport {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Disk limits for the artifacts under a root, with their usage
+///
+/// Shared (in an `Arc`) by everything capturing into the root, so the usage it
+/// counts covers all of them.
+#[derive(Debug)]
+pub struct ArtifactQuota {
+    root: PathBuf,
+    max_bytes: Option<u64>,
+    min_free_bytes: u64,
+    used: AtomicU64,
+}
+
+impl ArtifactQuota {
+    /// Quota for the artifacts under `root`, starting from their current size
+    pub async fn open(
+        root: impl Into<PathBuf>,
+        max_bytes: Option<u64>,
+        min_free_bytes: u64,
+    ) -> Self {
+        let root = root.into();
+        let used = disk_usage(&root).await.total_bytes;
+        Self {
+            root,
+            max_bytes,
+            min_free_bytes,
+            used: AtomicU64::new(used),
+        }
+    }
+
+    /// Quota for `artifacts.root` with the configured limits
+    pub async fn from_config(artifacts: &ArtifactsConfig) -> Self {
+        Self::open(
+            &artifacts.root,
+            artifacts.max_mb.map(|mb| mb * MIB),
+            artifacts.min_free_mb * MIB,
+        )
+        .await
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Bytes the artifacts are counted as using
+    pub fn used_bytes(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Count `bytes` more written under the root
+    pub fn record(&self, bytes: u64) {
+        self.used.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Count `bytes` fewer (deleted or moved elsewhere)
+    pub fn release(&self, bytes: u64) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                Some(used.saturating_sub(bytes))
+            });
+    }
+
+    /// Fail if there's no room to write more artifacts into `dir`
+    pub async fn check(&self, dir: &Path) -> Result<()> {
+        let used = self.used_bytes();
+        if let Some(max) = self.max_bytes.filter(|&max| used >= max) {
+            return Err(BrowserError::DiskSpace(format!(
+                "artifacts under {} use {} MiB of their {} MiB quota (artifacts.max_mb); \
+                 purge old ones with `robert-webdriver purge --older-than-days <N>`",
+                self.root.display(),
+                used / MIB,
+                max / MIB
+            )));
+        }
+        if self.min_free_bytes == 0 {
+            return Ok(());
+        }
+        let dir = dir.to_path_buf();
+        let free = tokio::task::spawn_blocking(move || free_space(&dir))
+            .await
+            .ok()
+            .flatten();
+        match free {
+            Some(free) if free < self.min_free_bytes => Err(BrowserError::DiskSpace(format!(
+                "only {} MiB free on the artifact disk, below the {} MiB minimum \
+                 (artifacts.min_free_mb)",
+                free / MIB,
+                self.min_free_bytes / MIB
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// [`purge`] the root, counting the deleted bytes off the usage
+    pub async fn purge(&self, older_than: Duration) -> std::io::Result<PurgeReport> {
+        let report = purge(&self.root, older_than).await?;
+        self.release(report.bytes);
+        Ok(report)
+    }
+}
+
+/// Size of the files under `root` (zero if it doesn't exist)
+pub async fn disk_usage(root: &Path) -> ArtifactUsage {
+    let root = root.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut usage = ArtifactUsage::default();
+        walk(&root, &root, &mut |relative, metadata| {
+            usage.total_bytes += metadata.len();
+            usage.files += 1;
+            *usage.by_kind.entry(kind(relative).to_string()).or_default() += metadata.len();
+            false
+        });
+        usage
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Delete step frame files (those in [`FRAME_DIRS`]) under `root` last modified more
+/// than `older_than` ago, and the directories that leaves empty
+pub async fn purge(root: &Path, older_than: Duration) -> std::io::Result<PurgeReport> {
+    let cutoff = SystemTime::now()
+        .checked_sub(older_than)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let root = root.to_path_buf();
+    let report = tokio::task::spawn_blocking(move || {
+        let mut report = PurgeReport::default();
+        walk(&root, &root, &mut |relative, metadata| {
+            let old = metadata.modified().is_ok_and(|modified| modified < cutoff);
+            let purge = old && kind(relative) != "other";
+            if purge {
+                report.files += 1;
+                report.bytes += metadata.len();
+            }
+            purge
+        });
+        report
+    })
+    .await
+    .map_err(std::io::Error::other)?;
+    tracing::info!(
+        files = report.files,
+        bytes = report.bytes,
+        "Purged old artifacts"
+    );
+    Ok(report)
+}
+
+/// Free bytes on the disk holding `path` (or its nearest existing ancestor), if
+/// they can be determined
+///
+/// Asks `df`, so this blocks for a moment; there is no answer on Windows.
+pub fn free_space(path: &Path) -> Option<u64> {
+    if cfg!(target_os = "windows") {
+        return None;
+    }
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    let output = std::process::Command::new("df")
+        .arg("-Pk")
+        .arg(existing)
+        .output()
+        .ok()?;
+    parse_df(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Available bytes from POSIX `df -Pk` output
+fn parse_df(output: &str) -> Option<u64> {
+    let line = output.lines().nth(1)?;
+    let available: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+    Some(available * 1024)
+}
+
+/// Which of the [`FRAME_DIRS`] a file (relative to the root) is in, or `other`
+fn kind(relative: &Path) -> &str {
+    relative
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .filter_map(|component| component.as_os_str().to_str())
+        .find(|name| FRAME_DIRS.contains(name))
+        .unwrap_or("other")
+}
+
+/// Visit the files below `dir` with their path relative to `root`, deleting those
+/// `visit` returns true for and frame directories left empty
+fn walk<F>(root: &Path, dir: &Path, visit: &mut F)
+where
+    F: FnMut(&Path, &std::fs::Metadata) -> bool,
+{
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut deleted = false;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            walk(root, &path, visit);
+        } else if metadata.is_file() {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            if visit(relative, &metadata) {
+                match std::fs::remove_file(&path) {
+                    Ok(()) => deleted = true,
+                    Err(e) => {
+                        tracing::warn!(path = %path.display(), error = %e, "Failed to delete artifact")
+                    }
+                }
+            }
+        }
+    }
+    let relative = dir.strip_prefix(root).unwrap_or(dir);
+    let in_frame_dir = relative
+        .components()
+        .any(|component| FRAME_DIRS.contains(&component.as_os_str().to_str().unwrap_or_default()));
+    if deleted && in_frame_dir {
+        // Fails (harmlessly) unless the directory is now empty
+        let _ = std::fs::remove_dir(dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(kind(Path::new("screenshots/frame_0001.png")), "screenshots");
+        assert_eq!(kind(Path::new("jobs/abc/dom/frame_0001.html")), "dom");
+        assert_eq!(kind(Path::new("runs/abc.json")), "other");
+        assert_eq!(kind(Path::new("dom")), "other");
+    }
+
+    #[test]
+    fn test_parse_df() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/sda1        102400000  52428800  49971200      52% /\n";
+        assert_eq!(parse_df(output), Some(49971200 * 1024));
+        assert_eq!(parse_df(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_quota_and_purge() {
+        let root = std::env::temp_dir().join(format!("robert-artifacts-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let frames = root.join("jobs/abc/screenshots");
+        std::fs::create_dir_all(&frames).unwrap();
+        std::fs::create_dir_all(root.join("runs")).unwrap();
+        std::fs::write(frames.join("frame_0001.png"), vec![0u8; 1000]).unwrap();
+        std::fs::write(root.join("runs/abc.json"), b"{}").unwrap();
+
+        let usage = disk_usage(&root).await;
+        assert_eq!(usage.total_bytes, 1002);
+        assert_eq!(usage.files, 2);
+        assert_eq!(usage.by_kind["screenshots"], 1000);
+
+        let quota = ArtifactQuota::open(&root, Some(1500), 0).await;
+        assert!(quota.check(&frames).await.is_ok());
+        quota.record(600);
+        let err = quota.check(&frames).await.unwrap_err();
+        assert!(matches!(err, BrowserError::DiskSpace(_)), "{}", err);
+
+        // Nothing is old enough, then everything is
+        assert_eq!(
+            quota.purge(Duration::from_secs(3600)).await.unwrap().files,
+            0
+        );
+        let purged = quota.purge(Duration::ZERO).await.unwrap();
+        assert_eq!(
+            purged,
+            PurgeReport {
+                files: 1,
+                bytes: 1000
+            }
+        );
+        assert_eq!(quota.used_bytes(), 602);
+        assert!(!frames.exists());
+        assert!(root.join("runs/abc.json").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
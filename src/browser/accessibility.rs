@@ -0,0 +1,288 @@
+//! Accessibility Audits (axe-core)
+//!
+//! Runs [axe-core](https://github.com/dequelabs/axe-core) in the current page and
+//! returns its violations as typed results, so CI can fail a run on accessibility
+//! regressions (e.g. anything `serious` or worse).
+//!
+//! axe-core is bundled: `vendor/axe-core/axe.min.js` is embedded in the binary at
+//! build time (build.rs fails without it), so audits work offline without a Node
+//! install. `DriverOptions::axe_core_path` (`chrome.axe_core_path`,
+//! `ROBERT_AXE_CORE_PATH`) audits with another axe-core release instead.
+//!
+//! Pages that already load axe-core themselves are audited with their copy.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::Path;
+
+/// axe-core embedded from `vendor/axe-core/axe.min.js`
+const BUNDLED_AXE: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/vendor/axe-core/axe.min.js"
+));
+
+/// How serious a violation is, as axe-core rates it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Impact {
+    Minor,
+    Moderate,
+    Serious,
+    Critical,
+}
+
+impl std::str::FromStr for Impact {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "minor" => Ok(Self::Minor),
+            "moderate" => Ok(Self::Moderate),
+            "serious" => Ok(Self::Serious),
+            "critical" => Ok(Self::Critical),
+            other => Err(format!(
+                "Unknown impact '{}' (expected minor, moderate, serious, or critical)",
+                other
+            )),
+        }
+    }
+}
+
+/// What an audit checks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AccessibilityOptions {
+    /// Only run rules with one of these tags (e.g. `wcag2a`, `wcag2aa`, `best-practice`; empty = all)
+    pub tags: Vec<String>,
+
+    /// Rule ids to skip (e.g. `color-contrast`)
+    pub disable_rules: Vec<String>,
+
+    /// CSS selectors of the parts of the page to audit (empty = the whole document)
+    pub include: Vec<String>,
+
+    /// CSS selectors of parts of the page to leave out
+    pub exclude: Vec<String>,
+}
+
+/// Result of an audit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessibilityReport {
+    /// Audited page URL
+    pub url: String,
+
+    /// axe-core version that ran the audit
+    pub axe_version: String,
+
+    /// Rules the page fails
+    pub violations: Vec<Violation>,
+
+    /// Number of rules the page passes
+    pub passes: usize,
+
+    /// Number of rules axe-core could not decide (need manual review)
+    pub incomplete: usize,
+
+    /// Number of rules that don't apply to the page
+    pub inapplicable: usize,
+}
+
+impl AccessibilityReport {
+    /// Violations rated `impact` or worse
+    pub fn violations_at_least(&self, impact: Impact) -> impl Iterator<Item = &Violation> {
+        self.violations
+            .iter()
+            .filter(move |v| v.impact.is_some_and(|i| i >= impact))
+    }
+}
+
+/// A failed rule and the elements that fail it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Violation {
+    /// Rule id (e.g. `image-alt`)
+    pub id: String,
+
+    pub impact: Option<Impact>,
+
+    /// What the rule checks
+    pub description: String,
+
+    /// How to fix it, in one sentence
+    pub help: String,
+
+    /// Rule documentation
+    pub help_url: String,
+
+    /// Rule tags (e.g. `wcag2a`, `wcag111`)
+    pub tags: Vec<String>,
+
+    pub nodes: Vec<ViolationNode>,
+}
+
+/// An element failing a rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViolationNode {
+    /// Selector path to the element; elements in iframes or shadow roots have one
+    /// selector per level, joined with ` >>> `
+    pub target: Vec<String>,
+
+    /// The element's outer HTML (truncated by axe-core)
+    pub html: String,
+
+    /// What is wrong with this element
+    pub failure_summary: Option<String>,
+
+    pub impact: Option<Impact>,
+}
+
+/// Audit the page with axe-core, injecting it (from `axe_core_path`, or the bundled
+/// copy) unless the page already has it
+pub async fn audit_accessibility(
+    page: &Page,
+    axe_core_path: Option<&Path>,
+    options: &AccessibilityOptions,
+) -> Result<AccessibilityReport> {
+    let loaded: bool = page
+        .evaluate("typeof window.axe === 'object'")
+        .await?
+        .into_value()
+        .unwrap_or(false);
+    if !loaded {
+        let (source, origin) = axe_script(axe_core_path).await?;
+        // Evaluate to a plain value rather than whatever the bundle's last statement returns
+        page.evaluate(format!("{}\n;true", source)).await?;
+        tracing::debug!(from = %origin, "Injected axe-core");
+    }
+
+    let script = run_script(options);
+    let report: AccessibilityReport = page
+        .evaluate(script)
+        .await
+        .map_err(|e| BrowserError::Other(format!("axe-core audit failed: {}", e)))?
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Unexpected axe-core result: {}", e)))?;
+    tracing::info!(
+        url = %report.url,
+        violations = report.violations.len(),
+        "Accessibility audit finished"
+    );
+    Ok(report)
+}
+
+/// The axe-core script to inject, with where it came from
+async fn axe_script(configured: Option<&Path>) -> Result<(Cow<'static, str>, String)> {
+    let Some(path) = configured else {
+        return Ok((Cow::Borrowed(BUNDLED_AXE), "bundled".to_string()));
+    };
+    let source = tokio::fs::read_to_string(path).await.map_err(|e| {
+        BrowserError::Other(format!(
+            "Failed to read axe-core from {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    Ok((Cow::Owned(source), path.display().to_string()))
+}
+
+/// Expression running axe-core and reducing its results to an [`AccessibilityReport`]
+fn run_script(options: &AccessibilityOptions) -> String {
+    let context = if options.include.is_empty() && options.exclude.is_empty() {
+        serde_json::json!(null)
+    } else {
+        let selectors = |list: &[String]| list.iter().map(|s| vec![s.clone()]).collect::<Vec<_>>();
+        let mut context = serde_json::json!({ "exclude": selectors(&options.exclude) });
+        if !options.include.is_empty() {
+            context["include"] = serde_json::json!(selectors(&options.include));
+        }
+        context
+    };
+
+    let mut run_options = serde_json::json!({ "resultTypes": ["violations"] });
+    if !options.tags.is_empty() {
+        run_options["runOnly"] = serde_json::json!({ "type": "tag", "values": options.tags });
+    }
+    if !options.disable_rules.is_empty() {
+        let rules: serde_json::Map<String, serde_json::Value> = options
+            .disable_rules
+            .iter()
+            .map(|id| (id.clone(), serde_json::json!({ "enabled": false })))
+            .collect();
+        run_options["rules"] = serde_json::Value::Object(rules);
+    }
+
+    format!(
+        r#"(async () => {{
+    const context = {context};
+    const results = await axe.run(context || document, {options});
+    const node = (n) => ({{
+        target: n.target.map((t) => (Array.isArray(t) ? t.join(' >>> ') : String(t))),
+        html: n.html,
+        failureSummary: n.failureSummary || null,
+        impact: n.impact || null,
+    }});
+    return {{
+        url: results.url,
+        axeVersion: results.testEngine.version,
+        violations: results.violations.map((v) => ({{
+            id: v.id,
+            impact: v.impact || null,
+            description: v.description,
+            help: v.help,
+            helpUrl: v.helpUrl,
+            tags: v.tags,
+            nodes: v.nodes.map(node),
+        }})),
+        passes: results.passes.length,
+        incomplete: results.incomplete.length,
+        inapplicable: results.inapplicable.length,
+    }};
+}})()"#,
+        context = context,
+        options = run_options,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations_at_least() {
+        let violation = |id: &str, impact: Option<Impact>| Violation {
+            id: id.to_string(),
+            impact,
+            description: String::new(),
+            help: String::new(),
+            help_url: String::new(),
+            tags: Vec::new(),
+            nodes: Vec::new(),
+        };
+        let report = AccessibilityReport {
+            url: "https://example.com/".to_string(),
+            axe_version: "4.10.0".to_string(),
+            violations: vec![
+                violation("region", Some(Impact::Moderate)),
+                violation("image-alt", Some(Impact::Critical)),
+                violation("color-contrast", Some(Impact::Serious)),
+                violation("custom", None),
+            ],
+            passes: 10,
+            incomplete: 0,
+            inapplicable: 40,
+        };
+
+        let serious: Vec<&str> = report
+            .violations_at_least(Impact::Serious)
+            .map(|v| v.id.as_str())
+            .collect();
+        assert_eq!(serious, vec!["image-alt", "color-contrast"]);
+        assert_eq!(report.violations_at_least(Impact::Minor).count(), 3);
+        assert_eq!("serious".parse::<Impact>(), Ok(Impact::Serious));
+        assert!("severe".parse::<Impact>().is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! Captcha Detection
+//!
+//! Detects visible reCAPTCHA, hCaptcha, and Cloudflare challenges on a page and
+//! hands them to a [`CaptchaHandler`] as a [`CaptchaEvent`], so automation stops
+//! with a clear reason (or gets help) instead of timing out on a blocked page.
+//!
+//! Two handlers are provided: [`ChatCaptchaHandler`] asks the person watching the
+//! browser to solve the challenge through the chat UI, and [`FailOnCaptcha`] stops
+//! immediately. External solving services plug in by implementing
+//! [`CaptchaHandler`] and returning [`CaptchaResolution::Token`].
+
+use super::chat::ChatUI;
+use crate::error::{BrowserError, Result};
+use chromiumoxide::page::Page;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// How long to wait for a challenge to disappear after it was solved
+const CLEAR_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between detection checks while waiting
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Kind of challenge detected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptchaKind {
+    /// Google reCAPTCHA (checkbox or image challenge)
+    Recaptcha,
+    /// hCaptcha
+    Hcaptcha,
+    /// Cloudflare interstitial or Turnstile widget
+    Cloudflare,
+}
+
+impl std::fmt::Display for CaptchaKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CaptchaKind::Recaptcha => "reCAPTCHA",
+            CaptchaKind::Hcaptcha => "hCaptcha",
+            CaptchaKind::Cloudflare => "Cloudflare challenge",
+        })
+    }
+}
+
+/// A challenge found on the page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptchaEvent {
+    /// Kind of challenge
+    pub kind: CaptchaKind,
+
+    /// Page URL
+    pub url: String,
+
+    /// Widget site key, needed by external solving services
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_key: Option<String>,
+
+    /// URL of the challenge iframe, if the widget is framed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_url: Option<String>,
+}
+
+/// What a handler did about a challenge
+#[derive(Debug, Clone)]
+pub enum CaptchaResolution {
+    /// The challenge was solved in the page (e.g. by a person)
+    Solved,
+    /// A response token from a solving service, to be injected into the page
+    Token(String),
+    /// Give up; the message becomes the error
+    Abort(String),
+}
+
+/// Decides what to do when a challenge blocks the page
+pub trait CaptchaHandler: Send + Sync {
+    /// Handle `event` on `page`
+    fn handle<'a>(
+        &'a self,
+        page: &'a Page,
+        event: &'a CaptchaEvent,
+    ) -> BoxFuture<'a, anyhow::Result<CaptchaResolution>>;
+}
+
+/// Handler that aborts on any challenge
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FailOnCaptcha;
+
+impl CaptchaHandler for FailOnCaptcha {
+    fn handle<'a>(
+        &'a self,
+        _page: &'a Page,
+        event: &'a CaptchaEvent,
+    ) -> BoxFuture<'a, anyhow::Result<CaptchaResolution>> {
+        Box::pin(async move {
+            Ok(CaptchaResolution::Abort(format!(
+                "{} at {}",
+                event.kind, event.url
+            )))
+        })
+    }
+}
+
+/// Human-in-the-loop handler: asks via the chat UI and waits for the challenge to clear
+///
+/// Only useful with a visible browser window.
+pub struct ChatCaptchaHandler {
+    chat: ChatUI,
+    timeout: Duration,
+}
+
+impl ChatCaptchaHandler {
+    /// Wait up to `timeout` for the user to solve each challenge
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            chat: ChatUI::new(),
+            timeout,
+        }
+    }
+}
+
+impl CaptchaHandler for ChatCaptchaHandler {
+    fn handle<'a>(
+        &'a self,
+        page: &'a Page,
+        event: &'a CaptchaEvent,
+    ) -> BoxFuture<'a, anyhow::Result<CaptchaResolution>> {
+        Box::pin(async move {
+            self.chat.inject(page).await?;
+            self.chat
+                .send_agent_message(
+                    page,
+                    &format!(
+                        "A {} is blocking this page. Please solve it in the browser window; \
+                        I'll continue once it's gone.",
+                        event.kind
+                    ),
+                )
+                .await?;
+
+            if wait_until_clear(page, self.timeout).await {
+                Ok(CaptchaResolution::Solved)
+            } else {
+                Ok(CaptchaResolution::Abort(format!(
+                    "{} was not solved within {}s",
+                    event.kind,
+                    self.timeout.as_secs()
+                )))
+            }
+        })
+    }
+}
+
+/// Look for a visible challenge on `page`
+///
+/// Invisible widgets (e.g. reCAPTCHA v3 badges) are ignored since they don't block
+/// the page.
+pub async fn detect_captcha(page: &Page) -> Result<Option<CaptchaEvent>> {
+    let result = page
+        .evaluate(DETECT_SCRIPT)
+        .await
+        .map_err(|e| BrowserError::Other(format!("Captcha detection failed: {}", e)))?;
+    result
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Invalid captcha detection result: {}", e)))
+}
+
+/// Hand a detected challenge to `handler` and apply its resolution
+///
+/// Returns `BrowserError::CaptchaDetected` if the handler aborts or fails, or the
+/// challenge is still present after the handler reported it solved.
+pub async fn resolve_captcha(
+    page: &Page,
+    event: &CaptchaEvent,
+    handler: &dyn CaptchaHandler,
+) -> Result<()> {
+    tracing::warn!(kind = %event.kind, url = %event.url, "Captcha detected");
+
+    let resolution = handler.handle(page, event).await.map_err(|e| {
+        BrowserError::CaptchaDetected(format!("{} (handler failed: {:#})", event.kind, e))
+    })?;
+
+    match resolution {
+        CaptchaResolution::Abort(reason) => Err(BrowserError::CaptchaDetected(reason)),
+        // The widget stays on the page until the form using the token is submitted
+        CaptchaResolution::Token(token) => {
+            inject_token(page, event, &token).await?;
+            tracing::info!(kind = %event.kind, "Captcha token injected");
+            Ok(())
+        }
+        CaptchaResolution::Solved if wait_until_clear(page, CLEAR_TIMEOUT).await => {
+            tracing::info!(kind = %event.kind, "Captcha resolved");
+            Ok(())
+        }
+        CaptchaResolution::Solved => Err(BrowserError::CaptchaDetected(format!(
+            "{} still present after it was reported solved",
+            event.kind
+        ))),
+    }
+}
+
+/// Fill the widget's response field with `token` and invoke its callback
+async fn inject_token(page: &Page, event: &CaptchaEvent, token: &str) -> Result<()> {
+    let (field, widget) = match event.kind {
+        CaptchaKind::Recaptcha => ("g-recaptcha-response", ".g-recaptcha"),
+        CaptchaKind::Hcaptcha => ("h-captcha-response", ".h-captcha"),
+        CaptchaKind::Cloudflare => ("cf-turnstile-response", ".cf-turnstile"),
+    };
+    let script = format!(
+        r#"(() => {{
+            const token = {token};
+            document.querySelectorAll('[name="{field}"]').forEach(el => {{ el.value = token; }});
+            const widget = document.querySelector('{widget}');
+            const callback = widget && widget.dataset.callback;
+            if (callback && typeof window[callback] === 'function') window[callback](token);
+        }})()"#,
+        token = serde_json::to_string(token).unwrap_or_default(),
+    );
+
+    page.evaluate(script)
+        .await
+        .map_err(|e| BrowserError::Other(format!("Failed to inject captcha token: {}", e)))?;
+    Ok(())
+}
+
+/// Poll until no challenge is detected; false on timeout
+async fn wait_until_clear(page: &Page, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        // Detection errors are expected while the page reloads after a challenge
+        if let Ok(None) = detect_captcha(page).await {
+            return true;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    false
+}
+
+const DETECT_SCRIPT: &str = r#"(() => {
+    const visible = (el) => {
+        const rect = el.getBoundingClientRect();
+        const style = window.getComputedStyle(el);
+        return rect.width > 1 && rect.height > 1 && style.visibility !== 'hidden' && style.display !== 'none';
+    };
+    const siteKey = (selector) => {
+        const el = document.querySelector(selector);
+        return el ? el.getAttribute('data-sitekey') : null;
+    };
+    const url = window.location.href;
+
+    for (const frame of document.querySelectorAll('iframe[src]')) {
+        const src = frame.src;
+        if (!visible(frame)) continue;
+        if (/(google\.com|recaptcha\.net)\/recaptcha\//.test(src) && !/size=invisible/.test(src)) {
+            return { kind: 'recaptcha', url, site_key: siteKey('.g-recaptcha[data-sitekey]'), frame_url: src };
+        }
+        if (/hcaptcha\.com/.test(src) && !/size=invisible/.test(src)) {
+            return { kind: 'hcaptcha', url, site_key: siteKey('.h-captcha[data-sitekey]'), frame_url: src };
+        }
+        if (/challenges\.cloudflare\.com/.test(src)) {
+            return { kind: 'cloudflare', url, site_key: siteKey('.cf-turnstile[data-sitekey]'), frame_url: src };
+        }
+    }
+
+    // Cloudflare interstitial ("Just a moment...") before any frame has loaded
+    if (document.querySelector('#challenge-form, #cf-challenge-running, #challenge-running')
+        || document.title === 'Just a moment...') {
+        return { kind: 'cloudflare', url, site_key: siteKey('.cf-turnstile[data-sitekey]'), frame_url: null };
+    }
+
+    return null;
+})()"#;
@@ -2,42 +2,156 @@
 //!
 //! Provides functionality to inject a chat interface into web pages
 //! for real-time user feedback during agent operations.
+//!
+//! The chat UI lives in the page, so a navigation removes it along with its
+//! messages. In persistent mode ([`ChatUI::set_persistent`]) the driver keeps the
+//! message history on the Rust side and re-injects the UI, history included, after
+//! every main-frame navigation.
+//!
+//! Agent messages can carry rich content ([`ChatMessage::agent`]): markdown, inline
+//! images such as step-frame thumbnails, action buttons, and form fields. What the
+//! user does with a message comes back as typed [`UserFeedback`] (a reply, a button
+//! choice, or submitted form values) carrying the message's ID as its correlation
+//! ID, so [`ChatUI::await_feedback`] can wait for the answer to a specific prompt.
 
 use crate::error::{BrowserError, Result};
+use base64::Engine;
+use chromiumoxide::cdp::browser_protocol::page::EventFrameNavigated;
+use chromiumoxide::cdp::js_protocol::runtime::{self, AddBindingParams, EventBindingCalled};
+use futures::StreamExt;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// The JavaScript code for the chat UI
 /// This is embedded at compile time from chat_ui.js
 const CHAT_UI_SCRIPT: &str = include_str!("../chat_ui.js");
 
+/// Page binding the chat UI reports new messages through in persistent mode
+const RECORD_BINDING: &str = "__robertChatRecord";
+
+/// Page binding the chat UI reports feedback through in persistent mode
+const FEEDBACK_BINDING: &str = "__robertFeedbackRecord";
+
+/// Attempts to re-inject after a navigation, while the new document's context appears
+const REINJECT_ATTEMPTS: u32 = 5;
+
+/// How often [`ChatUI::await_feedback`] checks for feedback
+const FEEDBACK_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 /// Chat UI manager for injecting and interacting with the chat interface
 pub struct ChatUI {
-    enabled: bool,
+    /// Shared by the driver's handles, so any of them can toggle injection
+    enabled: AtomicBool,
+    persistent: AtomicBool,
+    history: Arc<Mutex<Vec<ChatMessage>>>,
+    /// Feedback recorded in persistent mode, which outlives the page's own copy
+    feedback: Arc<Mutex<Vec<UserFeedback>>>,
 }
 
 impl ChatUI {
     /// Create a new ChatUI instance
     pub fn new() -> Self {
-        Self { enabled: true }
+        Self::with_enabled(true)
     }
 
     /// Create a ChatUI instance with enabled/disabled state
     pub fn with_enabled(enabled: bool) -> Self {
-        Self { enabled }
+        Self {
+            enabled: AtomicBool::new(enabled),
+            persistent: AtomicBool::new(false),
+            history: Arc::default(),
+            feedback: Arc::default(),
+        }
     }
 
     /// Check if chat UI injection is enabled
     pub fn is_enabled(&self) -> bool {
-        self.enabled
+        self.enabled.load(Ordering::Relaxed)
     }
 
     /// Enable chat UI injection
-    pub fn enable(&mut self) {
-        self.enabled = true;
+    pub fn enable(&self) {
+        self.enabled.store(true, Ordering::Relaxed);
     }
 
     /// Disable chat UI injection
-    pub fn disable(&mut self) {
-        self.enabled = false;
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::Relaxed);
+    }
+
+    /// Check if the chat UI is kept across navigations
+    pub fn is_persistent(&self) -> bool {
+        self.persistent.load(Ordering::Relaxed)
+    }
+
+    /// Keep the chat UI and its messages across navigations
+    ///
+    /// Takes effect for pages the driver starts using afterwards; see [`ChatUI::attach`].
+    pub fn set_persistent(&self, persistent: bool) {
+        self.persistent.store(persistent, Ordering::Relaxed);
+    }
+
+    /// Messages recorded in persistent mode, oldest first
+    pub fn history(&self) -> Vec<ChatMessage> {
+        self.history.lock().unwrap().clone()
+    }
+
+    /// Inject the chat UI into `page` and re-inject it after every main-frame navigation
+    ///
+    /// Messages and feedback are recorded on the Rust side as the page reports them,
+    /// and the re-injected UI shows the messages again. Attach each page once;
+    /// injecting into a page that already shows the UI does nothing.
+    pub async fn attach(&self, page: &chromiumoxide::page::Page) -> Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        page.execute(runtime::EnableParams::default()).await?;
+        page.execute(AddBindingParams::new(RECORD_BINDING)).await?;
+        page.execute(AddBindingParams::new(FEEDBACK_BINDING))
+            .await?;
+        let mut navigated = page.event_listener::<EventFrameNavigated>().await?;
+        let mut recorded = page.event_listener::<EventBindingCalled>().await?;
+
+        let history = self.history.clone();
+        let feedback = self.feedback.clone();
+        let task_page = page.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    event = navigated.next() => match event {
+                        // Subframes get their own documents; the UI only lives in the main frame
+                        Some(event) if event.frame.parent_id.is_none() => {
+                            let history = history.lock().unwrap().clone();
+                            reinject(&task_page, &history).await;
+                        }
+                        Some(_) => {}
+                        None => break,
+                    },
+                    event = recorded.next() => match event {
+                        Some(event) if event.name == RECORD_BINDING => {
+                            match serde_json::from_str::<ChatMessage>(&event.payload) {
+                                Ok(message) => history.lock().unwrap().push(message),
+                                Err(e) => tracing::debug!(error = %e, "Ignoring malformed chat message"),
+                            }
+                        }
+                        Some(event) if event.name == FEEDBACK_BINDING => {
+                            match serde_json::from_str::<UserFeedback>(&event.payload) {
+                                Ok(item) => feedback.lock().unwrap().push(item),
+                                Err(e) => tracing::debug!(error = %e, "Ignoring malformed chat feedback"),
+                            }
+                        }
+                        Some(_) => {}
+                        None => break,
+                    },
+                }
+            }
+        });
+
+        self.inject(page).await
     }
 
     /// Get the chat UI injection script
@@ -47,11 +161,12 @@ impl ChatUI {
 
     /// Inject the chat UI into a page
     pub async fn inject(&self, page: &chromiumoxide::page::Page) -> Result<()> {
-        if !self.enabled {
+        if !self.is_enabled() {
             return Ok(());
         }
 
-        page.evaluate(CHAT_UI_SCRIPT)
+        let history = self.history();
+        page.evaluate(injection_script(&history).as_str())
             .await
             .map_err(|e| BrowserError::Other(format!("Failed to inject chat UI: {}", e)))?;
 
@@ -64,7 +179,7 @@ impl ChatUI {
         page: &chromiumoxide::page::Page,
         message: &str,
     ) -> Result<()> {
-        if !self.enabled {
+        if !self.is_enabled() {
             return Ok(());
         }
 
@@ -90,9 +205,98 @@ impl ChatUI {
         Ok(())
     }
 
+    /// Send a message with rich content (markdown, images, action buttons) to the chat UI
+    pub async fn send_rich_message(
+        &self,
+        page: &chromiumoxide::page::Page,
+        message: &ChatMessage,
+    ) -> Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let message = serde_json::to_string(message)
+            .map_err(|e| BrowserError::Other(format!("Failed to encode chat message: {}", e)))?;
+        let script = format!(
+            r#"
+            if (window.__ROBERT_CHAT_API__) {{
+                window.__ROBERT_CHAT_API__.sendRichMessage({});
+            }}
+            "#,
+            message
+        );
+
+        page.evaluate(script.as_str())
+            .await
+            .map_err(|e| BrowserError::Other(format!("Failed to send agent message: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Wait up to `timeout` for the user's answer to the agent message `correlation_id`
+    /// (a [`ChatMessage::id`]): a reply, a button choice, or submitted form values
+    ///
+    /// Returns None on timeout (or if the chat UI is disabled).
+    pub async fn await_feedback(
+        &self,
+        page: &chromiumoxide::page::Page,
+        correlation_id: &str,
+        timeout: Duration,
+    ) -> Result<Option<UserFeedback>> {
+        self.poll_feedback(page, timeout, |feedback| {
+            feedback.correlation_id() == Some(correlation_id)
+        })
+        .await
+    }
+
+    /// Wait up to `timeout` for the user to click one of the action buttons in `action_ids`
+    ///
+    /// Returns the clicked action's ID, or None on timeout (or if the chat UI is disabled).
+    pub async fn wait_for_action(
+        &self,
+        page: &chromiumoxide::page::Page,
+        action_ids: &[&str],
+        timeout: Duration,
+    ) -> Result<Option<String>> {
+        let clicked = self
+            .poll_feedback(page, timeout, |feedback| {
+                matches!(feedback, UserFeedback::Choice { action_id, .. }
+                    if action_ids.contains(&action_id.as_str()))
+            })
+            .await?;
+        Ok(clicked.and_then(|feedback| match feedback {
+            UserFeedback::Choice { action_id, .. } => Some(action_id),
+            _ => None,
+        }))
+    }
+
+    /// The first feedback matching `wanted`, checking until `timeout`
+    async fn poll_feedback<F>(
+        &self,
+        page: &chromiumoxide::page::Page,
+        timeout: Duration,
+        wanted: F,
+    ) -> Result<Option<UserFeedback>>
+    where
+        F: Fn(&UserFeedback) -> bool,
+    {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let found = self.get_feedback(page).await?.into_iter().find(&wanted);
+            if found.is_some() || Instant::now() >= deadline {
+                return Ok(found);
+            }
+            tokio::time::sleep(FEEDBACK_POLL_INTERVAL).await;
+        }
+    }
+
     /// Retrieve all messages from the chat UI
     pub async fn get_messages(&self, page: &chromiumoxide::page::Page) -> Result<Vec<ChatMessage>> {
-        if !self.enabled {
+        if !self.is_enabled() {
             return Ok(Vec::new());
         }
 
@@ -114,7 +318,7 @@ impl ChatUI {
 
     /// Clear all messages from the chat UI
     pub async fn clear_messages(&self, page: &chromiumoxide::page::Page) -> Result<()> {
-        if !self.enabled {
+        if !self.is_enabled() {
             return Ok(());
         }
 
@@ -127,13 +331,14 @@ impl ChatUI {
         page.evaluate(script)
             .await
             .map_err(|e| BrowserError::Other(format!("Failed to clear chat messages: {}", e)))?;
+        self.history.lock().unwrap().clear();
 
         Ok(())
     }
 
     /// Collapse the chat sidebar
     pub async fn collapse(&self, page: &chromiumoxide::page::Page) -> Result<()> {
-        if !self.enabled {
+        if !self.is_enabled() {
             return Ok(());
         }
 
@@ -152,7 +357,7 @@ impl ChatUI {
 
     /// Expand the chat sidebar
     pub async fn expand(&self, page: &chromiumoxide::page::Page) -> Result<()> {
-        if !self.enabled {
+        if !self.is_enabled() {
             return Ok(());
         }
 
@@ -174,7 +379,7 @@ impl ChatUI {
         &self,
         page: &chromiumoxide::page::Page,
     ) -> Result<Vec<ChatMessage>> {
-        if !self.enabled {
+        if !self.is_enabled() {
             return Ok(Vec::new());
         }
 
@@ -195,7 +400,7 @@ impl ChatUI {
 
     /// Clear unprocessed messages after they have been handled
     pub async fn clear_unprocessed_messages(&self, page: &chromiumoxide::page::Page) -> Result<()> {
-        if !self.enabled {
+        if !self.is_enabled() {
             return Ok(());
         }
 
@@ -211,11 +416,13 @@ impl ChatUI {
     }
 
     /// Get feedback submissions from users
+    ///
+    /// In persistent mode, this includes feedback given on pages navigated away from.
     pub async fn get_feedback(
         &self,
         page: &chromiumoxide::page::Page,
     ) -> Result<Vec<UserFeedback>> {
-        if !self.enabled {
+        if !self.is_enabled() {
             return Ok(Vec::new());
         }
 
@@ -228,16 +435,22 @@ impl ChatUI {
             .await
             .map_err(|e| BrowserError::Other(format!("Failed to get feedback: {}", e)))?;
 
-        let feedback: Vec<UserFeedback> = result
+        let on_page: Vec<UserFeedback> = result
             .into_value()
             .map_err(|e| BrowserError::Other(format!("Failed to parse feedback: {}", e)))?;
 
+        let mut feedback = self.feedback.lock().unwrap().clone();
+        for item in on_page {
+            if !feedback.contains(&item) {
+                feedback.push(item);
+            }
+        }
         Ok(feedback)
     }
 
     /// Clear feedback after it has been processed
     pub async fn clear_feedback(&self, page: &chromiumoxide::page::Page) -> Result<()> {
-        if !self.enabled {
+        if !self.is_enabled() {
             return Ok(());
         }
 
@@ -248,6 +461,7 @@ impl ChatUI {
         page.evaluate(script)
             .await
             .map_err(|e| BrowserError::Other(format!("Failed to clear feedback: {}", e)))?;
+        self.feedback.lock().unwrap().clear();
 
         Ok(())
     }
@@ -259,25 +473,338 @@ impl Default for ChatUI {
     }
 }
 
+/// The chat UI script, showing `history` instead of the welcome message
+///
+/// Waits for the document's body if it hasn't been parsed yet (as when injected
+/// right after a navigation commits), and does nothing if the UI is already there.
+fn injection_script(history: &[ChatMessage]) -> String {
+    let history = serde_json::to_string(history).unwrap_or_else(|_| "[]".to_string());
+    format!(
+        r#"(() => {{
+            if (window.__ROBERT_CHAT_UI_INJECTED__) return;
+            const inject = () => {{
+                window.__ROBERT_CHAT_HISTORY__ = {};
+                {}
+            }};
+            if (document.body) {{
+                inject();
+            }} else {{
+                document.addEventListener('DOMContentLoaded', inject, {{ once: true }});
+            }}
+        }})()"#,
+        history, CHAT_UI_SCRIPT
+    )
+}
+
+/// Inject the chat UI into the document `page` just navigated to
+///
+/// Right after the navigation commits, the new document may not have an execution
+/// context yet, so evaluating can fail against the old one; retry briefly.
+async fn reinject(page: &chromiumoxide::page::Page, history: &[ChatMessage]) {
+    let script = injection_script(history);
+    for attempt in 1..=REINJECT_ATTEMPTS {
+        match page.evaluate(script.as_str()).await {
+            Ok(_) => return,
+            Err(e) if attempt == REINJECT_ATTEMPTS => {
+                tracing::warn!(error = %e, "Failed to re-inject chat UI after navigation");
+            }
+            Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    }
+}
+
 /// Represents a message in the chat
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ChatMessage {
+    /// Correlation ID that feedback on this message carries (empty for user messages)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+
     pub text: String,
     pub sender: String,
     pub timestamp: u64,
+
+    /// Render `text` as markdown (code, emphasis, links, headings, and lists)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub markdown: bool,
+
+    /// Images shown below the text
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ChatImage>,
+
+    /// Buttons shown below the text; a click is reported as [`UserFeedback::Choice`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<ChatAction>,
+
+    /// Form fields shown below the text; submitting is reported as [`UserFeedback::Form`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub form: Vec<ChatFormField>,
+
+    /// The user's next chat message answers this one ([`UserFeedback::Message`])
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub awaits_reply: bool,
 }
 
-/// Represents user feedback on an agent action
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct UserFeedback {
-    pub action_id: String,
-    pub positive: bool,
-    pub comment: Option<String>,
-    pub agent_name: String,
-    pub original_request: String,
-    pub error_description: Option<String>,
-    pub timestamp: u64,
+impl ChatMessage {
+    /// A plain-text agent message with a fresh ID, timestamped now
+    pub fn agent(text: impl Into<String>) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default();
+        Self {
+            id: format!(
+                "msg-{}-{}",
+                timestamp,
+                NEXT_ID.fetch_add(1, Ordering::Relaxed)
+            ),
+            text: text.into(),
+            sender: "agent".to_string(),
+            timestamp,
+            markdown: false,
+            images: Vec::new(),
+            actions: Vec::new(),
+            form: Vec::new(),
+            awaits_reply: false,
+        }
+    }
+
+    /// Render the text as markdown
+    pub fn markdown(mut self) -> Self {
+        self.markdown = true;
+        self
+    }
+
+    /// Attach an image
+    pub fn with_image(mut self, image: ChatImage) -> Self {
+        self.images.push(image);
+        self
+    }
+
+    /// Add an action button
+    pub fn with_action(mut self, action: ChatAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// Add a form field; the form gets a submit button
+    pub fn with_field(mut self, field: ChatFormField) -> Self {
+        self.form.push(field);
+        self
+    }
+
+    /// Treat the user's next chat message as the reply to this one
+    pub fn expect_reply(mut self) -> Self {
+        self.awaits_reply = true;
+        self
+    }
+}
+
+/// An image in a chat message
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChatImage {
+    /// `http(s)` URL or `data:image/...;base64,` URL; other sources aren't shown
+    pub src: String,
+
+    /// Alternative text
+    #[serde(default)]
+    pub alt: String,
+}
+
+impl ChatImage {
+    /// An image loaded from a URL
+    pub fn new(src: impl Into<String>, alt: impl Into<String>) -> Self {
+        Self {
+            src: src.into(),
+            alt: alt.into(),
+        }
+    }
+
+    /// An image embedded as a data URL, e.g. a screenshot from `driver.screenshot()`
+    pub fn from_bytes(data: &[u8], mime_type: &str, alt: impl Into<String>) -> Self {
+        let data = base64::engine::general_purpose::STANDARD.encode(data);
+        Self::new(format!("data:{};base64,{}", mime_type, data), alt)
+    }
+
+    /// An image file embedded as a data URL, e.g. a step frame's thumbnail
+    /// (`frame.screenshot.thumbnail`); the type is taken from the extension
+    pub async fn from_file(path: impl AsRef<Path>, alt: impl Into<String>) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let mime_type = match extension.as_str() {
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "webp" => "image/webp",
+            "gif" => "image/gif",
+            _ => {
+                return Err(BrowserError::Other(format!(
+                    "Unsupported chat image type: {}",
+                    path.display()
+                )))
+            }
+        };
+        let data = tokio::fs::read(path).await.map_err(|e| {
+            BrowserError::Other(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        Ok(Self::from_bytes(&data, mime_type, alt))
+    }
+}
+
+/// A button in a chat message
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChatAction {
+    /// Reported back in [`UserFeedback::Choice`]
+    pub id: String,
+
+    /// Button text
+    pub label: String,
+}
+
+impl ChatAction {
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+        }
+    }
+}
+
+/// A field in a chat message's form
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChatFormField {
+    /// Key of the field's value in [`UserFeedback::Form`]
+    pub name: String,
+    pub label: String,
+    #[serde(default)]
+    pub kind: ChatFieldKind,
+
+    /// Choices of a select field
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+
+    /// Initial value (`true` or `false` for checkboxes)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl ChatFormField {
+    fn new(kind: ChatFieldKind, name: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            label: label.into(),
+            kind,
+            options: Vec::new(),
+            value: None,
+        }
+    }
+
+    /// A single-line text input
+    pub fn text(name: impl Into<String>, label: impl Into<String>) -> Self {
+        Self::new(ChatFieldKind::Text, name, label)
+    }
+
+    /// A multi-line text input
+    pub fn textarea(name: impl Into<String>, label: impl Into<String>) -> Self {
+        Self::new(ChatFieldKind::Textarea, name, label)
+    }
+
+    /// A checkbox, submitted as `true` or `false`
+    pub fn checkbox(name: impl Into<String>, label: impl Into<String>) -> Self {
+        Self::new(ChatFieldKind::Checkbox, name, label)
+    }
+
+    /// A dropdown of `options`
+    pub fn select<I, S>(name: impl Into<String>, label: impl Into<String>, options: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            options: options.into_iter().map(Into::into).collect(),
+            ..Self::new(ChatFieldKind::Select, name, label)
+        }
+    }
+
+    /// Set the initial value
+    pub fn with_value(mut self, value: impl Into<String>) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+}
+
+/// Input type of a [`ChatFormField`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatFieldKind {
+    #[default]
+    Text,
+    Textarea,
+    Checkbox,
+    Select,
+}
+
+/// Represents user feedback from the chat UI
+///
+/// Everything but ratings carries the ID of the agent message it answers, if any.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum UserFeedback {
+    /// Thumbs up or down on an agent action
+    #[serde(rename_all = "camelCase")]
+    Rating {
+        action_id: String,
+        positive: bool,
+        comment: Option<String>,
+        agent_name: String,
+        original_request: String,
+        error_description: Option<String>,
+        timestamp: u64,
+    },
+
+    /// A chat message typed by the user, answering the latest message that
+    /// [expects a reply](ChatMessage::expect_reply)
+    #[serde(rename_all = "camelCase")]
+    Message {
+        correlation_id: Option<String>,
+        text: String,
+        timestamp: u64,
+    },
+
+    /// A click on one of a message's action buttons
+    #[serde(rename_all = "camelCase")]
+    Choice {
+        correlation_id: Option<String>,
+        /// The clicked [`ChatAction`]'s ID
+        action_id: String,
+        label: String,
+        timestamp: u64,
+    },
+
+    /// Values submitted from a message's form, by field name
+    #[serde(rename_all = "camelCase")]
+    Form {
+        correlation_id: Option<String>,
+        values: BTreeMap<String, String>,
+        timestamp: u64,
+    },
+}
+
+impl UserFeedback {
+    /// ID of the agent message this answers (the rated action's ID for ratings)
+    pub fn correlation_id(&self) -> Option<&str> {
+        match self {
+            Self::Rating { action_id, .. } => Some(action_id),
+            Self::Message { correlation_id, .. }
+            | Self::Choice { correlation_id, .. }
+            | Self::Form { correlation_id, .. } => correlation_id.as_deref(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -295,7 +822,7 @@ mod tests {
 
     #[test]
     fn test_chat_ui_enable_disable() {
-        let mut chat_ui = ChatUI::new();
+        let chat_ui = ChatUI::new();
         assert!(chat_ui.is_enabled());
 
         chat_ui.disable();
@@ -312,4 +839,110 @@ mod tests {
         assert!(!script.is_empty());
         assert!(script.contains("robert-chat-container"));
     }
+
+    #[test]
+    fn test_injection_script_restores_history() {
+        let history = vec![ChatMessage {
+            id: String::new(),
+            text: "Say \"hi\"\n</script>".to_string(),
+            sender: "user".to_string(),
+            timestamp: 1_700_000_000_000,
+            markdown: false,
+            images: Vec::new(),
+            actions: Vec::new(),
+            form: Vec::new(),
+            awaits_reply: false,
+        }];
+        let script = injection_script(&history);
+        assert!(script.contains(
+            r#"window.__ROBERT_CHAT_HISTORY__ = [{"text":"Say \"hi\"\n</script>","sender":"user","timestamp":1700000000000}];"#
+        ));
+        assert!(script.contains(CHAT_UI_SCRIPT));
+        assert!(injection_script(&[]).contains("window.__ROBERT_CHAT_HISTORY__ = [];"));
+
+        let chat_ui = ChatUI::new();
+        assert!(!chat_ui.is_persistent());
+        chat_ui.set_persistent(true);
+        assert!(chat_ui.is_persistent());
+        assert!(chat_ui.history().is_empty());
+    }
+
+    #[test]
+    fn test_rich_message() {
+        let message = ChatMessage::agent("**Submit** the form?")
+            .markdown()
+            .with_image(ChatImage::from_bytes(b"\x89PNG", "image/png", "Step 3"))
+            .with_action(ChatAction::new("step-3:approve", "Approve"));
+        assert_eq!(message.images[0].src, "data:image/png;base64,iVBORw==");
+
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["markdown"], true);
+        assert_eq!(json["actions"][0]["id"], "step-3:approve");
+
+        // Plain messages keep the original shape
+        let plain: ChatMessage =
+            serde_json::from_str(r#"{"text":"hi","sender":"user","timestamp":1}"#).unwrap();
+        assert!(!plain.markdown && plain.images.is_empty() && plain.actions.is_empty());
+        assert_eq!(
+            serde_json::to_string(&plain).unwrap(),
+            r#"{"text":"hi","sender":"user","timestamp":1}"#
+        );
+    }
+
+    #[test]
+    fn test_user_feedback_variants() {
+        let feedback: Vec<UserFeedback> = serde_json::from_str(
+            r#"[
+                {"type":"rating","actionId":"a1","positive":false,"comment":"wrong field",
+                 "agentName":"robert","originalRequest":"fill the form","errorDescription":null,
+                 "timestamp":1},
+                {"type":"choice","correlationId":"msg-1","actionId":"step-3:reject",
+                 "label":"Reject","timestamp":2},
+                {"type":"message","correlationId":null,"text":"wait","timestamp":3},
+                {"type":"form","correlationId":"msg-2","values":{"email":"a@b.c","remember":"true"},
+                 "timestamp":4}
+            ]"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            &feedback[0],
+            UserFeedback::Rating { positive: false, comment: Some(comment), .. } if comment == "wrong field"
+        ));
+        assert_eq!(
+            feedback[1],
+            UserFeedback::Choice {
+                correlation_id: Some("msg-1".to_string()),
+                action_id: "step-3:reject".to_string(),
+                label: "Reject".to_string(),
+                timestamp: 2,
+            }
+        );
+        match &feedback[3] {
+            UserFeedback::Form { values, .. } => assert_eq!(values["remember"], "true"),
+            other => panic!("expected form feedback, got {:?}", other),
+        }
+        let ids: Vec<_> = feedback.iter().map(UserFeedback::correlation_id).collect();
+        assert_eq!(ids, [Some("a1"), Some("msg-1"), None, Some("msg-2")]);
+    }
+
+    #[test]
+    fn test_prompt_messages() {
+        let first = ChatMessage::agent("Which account?")
+            .expect_reply()
+            .with_field(ChatFormField::select(
+                "account",
+                "Account",
+                ["work", "personal"],
+            ))
+            .with_field(ChatFormField::checkbox("remember", "Remember").with_value("true"));
+        let second = ChatMessage::agent("Thanks");
+        assert!(first.id.starts_with("msg-"));
+        assert_ne!(first.id, second.id);
+
+        let json = serde_json::to_value(&first).unwrap();
+        assert_eq!(json["awaits_reply"], true);
+        assert_eq!(json["form"][0]["kind"], "select");
+        assert_eq!(json["form"][0]["options"][1], "personal");
+        assert_eq!(json["form"][1]["value"], "true");
+    }
 }
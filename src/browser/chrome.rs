@@ -1,17 +1,119 @@
 // spider_chrome re-exports chromiumoxide API
+use super::consent::CookieConsent;
+use super::container;
+use super::crash::{CrashMonitor, CrashReport, DEFAULT_CRASH_HISTORY};
+use super::detect::{detect_browsers, launch_args};
+use super::determinism::DeterministicRendering;
+use super::display::{self, Xvfb};
+use super::install::{ChromeInstaller, VersionPin};
+use super::navigation::{DocumentResponses, HistoryNavigation, NavigationResult};
+use super::overlay::OverlayHandling;
+use super::page_lock::{PageLock, PageLocks};
+use super::tunnel::SshTunnel;
 use crate::error::{BrowserError, Result};
+use crate::model::browser::{BrowserInfo, BrowserKind, VersionCheck};
+use crate::model::fingerprint::ElementFingerprint;
+use crate::step_frame::ImageBudget;
 use chromiumoxide::browser::{Browser, BrowserConfig};
-use chromiumoxide_fetcher::{BrowserFetcher, BrowserFetcherOptions};
+use chromiumoxide::cdp::browser_protocol::network::LoaderId;
+use chromiumoxide::cdp::browser_protocol::target::{
+    EventTargetCrashed, EventTargetDestroyed, TargetId,
+};
+use chromiumoxide::handler::HandlerConfig;
+use chromiumoxide::page::Page;
 use futures::StreamExt;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+/// Handle to a Chrome session
+///
+/// Clones are cheap and share the session, so a handle can be moved into each task
+/// that drives the browser; concurrent calls are serialized per page (see
+/// [`page_lock`](super::page_lock)). [`close`](Self::close) closes the session for
+/// every handle. Chrome, its temporary profile, and any virtual display or tunnel
+/// are released when the last handle is dropped.
+#[derive(Clone)]
 pub struct ChromeDriver {
+    inner: Arc<DriverState>,
+}
+
+/// State shared by the handles of a session
+struct DriverState {
     browser: Browser,
     temp_dir: Option<PathBuf>,
     chat_ui: super::chat::ChatUI,
+    /// Pages the persistent chat UI is attached to
+    chat_pages: Mutex<HashSet<TargetId>>,
+    session_id: String,
+    navigation_timeout: Duration,
+    fail_on_http_error: bool,
+    tabs: Mutex<TabState>,
+    /// Command locks serializing operations on each page
+    page_locks: PageLocks,
+    crash_history: usize,
+    crash_monitors: CrashMonitors,
+    deterministic: Option<DeterministicRendering>,
+    /// Pages that already have the deterministic rendering overrides
+    deterministic_pages: Mutex<HashSet<TargetId>>,
+    cookie_consent: Option<CookieConsent>,
+    overlays: Option<OverlayHandling>,
+    axe_core_path: Option<PathBuf>,
+    widgets: super::widgets::WidgetRegistry,
+    browser_info: BrowserInfo,
+    /// Likely cause of page crashes, added to crash reports
+    crash_hint: Option<String>,
+    shutdown: Shutdown,
+    /// Virtual display a headed Chrome runs on; declared last so it outlives the browser
+    xvfb: Option<Xvfb>,
+    /// SSH tunnel the browser is reached through; also outlives the browser
+    tunnel: Option<SshTunnel>,
+}
+
+/// Closes the browser once for all handles of a session
+#[derive(Default)]
+struct Shutdown {
+    /// Cancelled when a handle starts closing the session
+    closing: CancellationToken,
+    /// Outcome of closing the browser, shared by every handle that closes it
+    closed: tokio::sync::OnceCell<std::result::Result<(), String>>,
+}
+
+/// Crash monitors of the pages the driver has used, by target
+type CrashMonitors = Arc<Mutex<HashMap<TargetId, Arc<CrashMonitor>>>>;
+
+/// Popup tabs the driver has adopted, and which tab page methods act on
+#[derive(Debug, Default)]
+struct TabState {
+    /// Popups returned by `wait_for_popup`, oldest first
+    adopted: Vec<TargetId>,
+    /// Adopted popup that page methods act on (None = the main page)
+    active: Option<TargetId>,
 }
 
+impl TabState {
+    /// Forget tabs that are no longer open
+    fn prune(&mut self, pages: &[Page]) {
+        let open = |id: &TargetId| pages.iter().any(|p| p.target_id() == id);
+        self.adopted.retain(|id| open(id));
+        if self.active.as_ref().is_some_and(|id| !open(id)) {
+            tracing::info!("Active popup was closed, switching back to the main page");
+            self.active = None;
+        }
+    }
+}
+
+/// Interval between target list checks while waiting for a popup
+const POPUP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a failed command waits for the crash event that may explain it
+const CRASH_GRACE: Duration = Duration::from_millis(500);
+
 /// Connection mode for Chrome browser
+#[derive(Debug)]
 pub enum ConnectionMode {
     /// Sandboxed mode - launches Chrome using system installation
     Sandboxed {
@@ -19,14 +121,341 @@ pub enum ConnectionMode {
         no_sandbox: bool,
         headless: bool,
     },
+    /// Launches headless Chrome with the flags containers need (no sandbox, no GPU,
+    /// shared memory in `/tmp`), checking that `/dev/shm` is big enough
+    Container { chrome_path: Option<String> },
     /// Advanced mode - connects to existing Chrome on debug port
     DebugPort(u16),
+    /// Connects to a remote or containerized Chrome (browserless, Docker) by its
+    /// DevTools WebSocket URL (`ws://host:3000/devtools/browser/...`), or by its
+    /// HTTP endpoint (`http://host:9222`), from which the WebSocket URL is read
+    WebSocketUrl(String),
+}
+
+impl ConnectionMode {
+    /// Launch the installed browser of the given kind, found with
+    /// [`detect_browsers`](super::detect::detect_browsers)
+    pub async fn installed(kind: BrowserKind, headless: bool) -> Result<Self> {
+        let detected = tokio::task::spawn_blocking(detect_browsers)
+            .await
+            .unwrap_or_default();
+        let browser = detected
+            .into_iter()
+            .find(|browser| browser.kind == kind)
+            .ok_or_else(|| {
+                BrowserError::LaunchFailed(format!("{} is not installed", kind.name()))
+            })?;
+        Ok(Self::Sandboxed {
+            chrome_path: Some(browser.executable.to_string_lossy().into_owned()),
+            no_sandbox: false,
+            headless,
+        })
+    }
+
+    /// Launch the installed Microsoft Edge
+    pub async fn edge(headless: bool) -> Result<Self> {
+        Self::installed(BrowserKind::Edge, headless).await
+    }
+
+    /// Launch the installed Brave
+    pub async fn brave(headless: bool) -> Result<Self> {
+        Self::installed(BrowserKind::Brave, headless).await
+    }
+}
+
+/// How [`ChromeDriver::launch_local`] launches Chrome
+#[derive(Debug, Clone, Default)]
+struct LocalLaunch {
+    chrome_path: Option<String>,
+    no_sandbox: bool,
+    headless: bool,
+    /// Add the container flags (which include `--no-sandbox`) and run headless
+    container: bool,
+    /// Proxy server for all traffic (`--proxy-server`)
+    proxy: Option<String>,
+    /// Profile directory to use and keep (None = a temporary one, deleted on close)
+    user_data_dir: Option<PathBuf>,
+    /// Extra command-line flags
+    args: Vec<String>,
+}
+
+/// Where [`ChromeDriver::start`] gets its browser from
+#[derive(Debug)]
+enum BrowserSource {
+    Launch(LocalLaunch),
+    DebugPort(u16),
+    WebSocketUrl(String),
+}
+
+impl From<ConnectionMode> for BrowserSource {
+    fn from(mode: ConnectionMode) -> Self {
+        match mode {
+            ConnectionMode::Sandboxed {
+                chrome_path,
+                no_sandbox,
+                headless,
+            } => Self::Launch(LocalLaunch {
+                chrome_path,
+                no_sandbox,
+                headless,
+                ..LocalLaunch::default()
+            }),
+            ConnectionMode::Container { chrome_path } => Self::Launch(LocalLaunch {
+                chrome_path,
+                no_sandbox: true,
+                headless: true,
+                container: true,
+                ..LocalLaunch::default()
+            }),
+            ConnectionMode::DebugPort(port) => Self::DebugPort(port),
+            ConnectionMode::WebSocketUrl(url) => Self::WebSocketUrl(url),
+        }
+    }
+}
+
+/// Driver settings that apply regardless of connection mode
+#[derive(Debug, Clone)]
+pub struct DriverOptions {
+    /// Cache directory for downloaded Chrome (None = `~/.cache/robert/chrome`)
+    pub download_dir: Option<PathBuf>,
+
+    /// Chrome for Testing version pin for auto-download (e.g. `128.0.x`; None = any)
+    pub chrome_version: Option<String>,
+
+    /// Maximum time to launch or connect to Chrome
+    pub launch_timeout: Duration,
+
+    /// Maximum time to wait for the page load event after navigating
+    pub navigation_timeout: Duration,
+
+    /// Make `navigate` fail with `BrowserError::HttpStatus` on 4xx/5xx responses
+    pub fail_on_http_error: bool,
+
+    /// Console/network events kept per page for crash reports (0 = don't watch for crashes)
+    pub crash_history: usize,
+
+    /// Render pages deterministically so screenshots are stable across runs (None = off)
+    pub deterministic: Option<DeterministicRendering>,
+
+    /// Load pages despite certificate errors (self-signed or expired certs in test
+    /// environments); by default such pages fail to load
+    pub ignore_cert_errors: bool,
+
+    /// axe-core script (`axe.min.js`) for accessibility audits (None = the bundled copy)
+    pub axe_core_path: Option<PathBuf>,
+
+    /// When a headed launch finds no display (no `DISPLAY` or `WAYLAND_DISPLAY` on
+    /// Linux), run Chrome on an Xvfb virtual display instead of falling back to
+    /// headless; falls back to headless anyway if Xvfb can't be started
+    pub xvfb: bool,
+
+    /// What to do when the browser is older than the executor supports
+    /// ([`BrowserInfo::compatibility_issue`]); warns by default
+    pub version_check: VersionCheck,
+
+    /// Show the chat UI on every page and re-inject it, message history included,
+    /// after each navigation ([`ChatUI::set_persistent`](super::chat::ChatUI::set_persistent))
+    pub persistent_chat: bool,
+
+    /// Answer cookie consent banners after each navigation (None = leave them)
+    pub cookie_consent: Option<CookieConsent>,
+
+    /// Dismiss, hide, or report pop-ups and interstitials covering the page after
+    /// each navigation (None = leave them)
+    pub overlays: Option<OverlayHandling>,
+}
+
+impl Default for DriverOptions {
+    fn default() -> Self {
+        Self {
+            download_dir: None,
+            chrome_version: None,
+            launch_timeout: Duration::from_secs(60),
+            navigation_timeout: Duration::from_secs(30),
+            fail_on_http_error: false,
+            crash_history: DEFAULT_CRASH_HISTORY,
+            deterministic: None,
+            ignore_cert_errors: false,
+            axe_core_path: None,
+            xvfb: false,
+            version_check: VersionCheck::Warn,
+            persistent_chat: false,
+            cookie_consent: None,
+            overlays: None,
+        }
+    }
+}
+
+/// Settings for launching a local Chrome with [`ChromeDriver::launch`]
+///
+/// Starts from a headed, sandboxed Chrome found or downloaded as usual; every setting
+/// not given keeps its default.
+///
+/// ```no_run
+/// use robert_webdriver::{ChromeDriver, DriverConfig};
+/// use std::time::Duration;
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let driver = ChromeDriver::launch(
+///     DriverConfig::new()
+///         .headless(true)
+///         .proxy("http://proxy.internal:3128")
+///         .arg("--lang=de-DE")
+///         .navigation_timeout(Duration::from_secs(60)),
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DriverConfig {
+    launch: LocalLaunch,
+    options: DriverOptions,
+}
+
+impl DriverConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Launch the Chrome (or Chromium-based browser) at `path` instead of finding one
+    pub fn chrome_path(mut self, path: impl Into<String>) -> Self {
+        self.launch.chrome_path = Some(path.into());
+        self
+    }
+
+    /// Run without a window
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.launch.headless = headless;
+        self
+    }
+
+    /// Keep Chrome's sandbox on (the default); turn it off where AppArmor or a
+    /// container forbids it
+    pub fn sandbox(mut self, sandbox: bool) -> Self {
+        self.launch.no_sandbox = !sandbox;
+        self
+    }
+
+    /// Launch with the flags containers need (no sandbox, no GPU, shared memory in
+    /// `/tmp`), headless
+    pub fn container(mut self, container: bool) -> Self {
+        self.launch.container = container;
+        self
+    }
+
+    /// Send all traffic through the proxy at `url` (`http://host:port`, `socks5://host:port`)
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.launch.proxy = Some(url.into());
+        self
+    }
+
+    /// Use the profile in `dir`, keeping it after the session, instead of a fresh
+    /// temporary profile
+    pub fn user_data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.launch.user_data_dir = Some(dir.into());
+        self
+    }
+
+    /// Add a command-line flag
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.launch.args.push(arg.into());
+        self
+    }
+
+    /// Add command-line flags
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.launch.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Cache directory for downloaded Chrome
+    pub fn download_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.options.download_dir = Some(dir.into());
+        self
+    }
+
+    /// Maximum time to launch Chrome
+    pub fn launch_timeout(mut self, timeout: Duration) -> Self {
+        self.options.launch_timeout = timeout;
+        self
+    }
+
+    /// Maximum time to wait for the page load event after navigating
+    pub fn navigation_timeout(mut self, timeout: Duration) -> Self {
+        self.options.navigation_timeout = timeout;
+        self
+    }
+
+    /// Use `options` for the driver, replacing the download dir and timeouts set so far
+    pub fn options(mut self, options: DriverOptions) -> Self {
+        self.options = options;
+        self
+    }
 }
 
 impl ChromeDriver {
     /// Helper method to get the current active page, excluding Chrome's new-tab-page
+    ///
+    /// Starts watching the page for crashes (and applies deterministic rendering and
+    /// attaches the persistent chat UI, if enabled) the first time it is used.
     async fn get_active_page(&self) -> Result<chromiumoxide::page::Page> {
-        let pages = self.browser.pages().await?;
+        let page = self.find_active_page().await?;
+        self.watch_for_crashes(&page).await;
+        self.apply_deterministic_rendering(&page).await;
+        self.attach_persistent_chat(&page).await;
+        Ok(page)
+    }
+
+    /// Run `operation` on the active page while holding its [`PageLock`]
+    async fn with_page<T, F, Fut>(&self, operation: F) -> Result<T>
+    where
+        F: FnOnce(Page) -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let page = self.get_active_page().await?;
+        let lock = self.inner.page_locks.get(page.target_id());
+        lock.run(operation(page)).await
+    }
+
+    /// Error for calls made after a handle closed the session
+    fn closed_error(&self) -> BrowserError {
+        BrowserError::ConnectionFailed(format!(
+            "Browser session {} was closed",
+            self.inner.session_id
+        ))
+    }
+
+    async fn find_active_page(&self) -> Result<chromiumoxide::page::Page> {
+        if self.is_closed() {
+            return Err(self.closed_error());
+        }
+        let pages = self.inner.browser.pages().await?;
+
+        // Prefer the popup the caller switched to, if it is still open
+        let active = {
+            let mut tabs = self.inner.tabs.lock().unwrap();
+            tabs.prune(&pages);
+            tabs.active.clone()
+        };
+        if let Some(page) = active.and_then(|id| pages.iter().find(|p| p.target_id() == &id)) {
+            return Ok(page.clone());
+        }
+
+        self.main_page(pages).await
+    }
+
+    /// The first real page that isn't an adopted popup
+    async fn main_page(&self, pages: Vec<Page>) -> Result<Page> {
+        let adopted = self.inner.tabs.lock().unwrap().adopted.clone();
+        let pages: Vec<_> = pages
+            .into_iter()
+            .filter(|p| !adopted.contains(p.target_id()))
+            .collect();
 
         // Filter out chrome://new-tab-page/ and return the first real page
         // If no real pages exist, return the last page (most recently created)
@@ -44,20 +473,21 @@ impl ChromeDriver {
         }
 
         // No pages at all, create one
-        self.browser
+        self.inner
+            .browser
             .new_page("about:blank")
             .await
             .map_err(|e| BrowserError::Other(format!("Failed to create page: {}", e)))
     }
 
+    /// Launch a local Chrome as `config` describes
+    pub async fn launch(config: DriverConfig) -> Result<Self> {
+        Self::start(BrowserSource::Launch(config.launch), config.options, None).await
+    }
+
     /// Launch Chrome in sandboxed mode (uses system Chrome)
     pub async fn launch_sandboxed() -> Result<Self> {
-        Self::new(ConnectionMode::Sandboxed {
-            chrome_path: None,
-            no_sandbox: false,
-            headless: false,
-        })
-        .await
+        Self::launch(DriverConfig::new()).await
     }
 
     /// Launch Chrome in sandboxed mode with custom path
@@ -66,22 +496,18 @@ impl ChromeDriver {
         no_sandbox: bool,
         headless: bool,
     ) -> Result<Self> {
-        Self::new(ConnectionMode::Sandboxed {
-            chrome_path: Some(chrome_path),
-            no_sandbox,
-            headless,
-        })
+        Self::launch(
+            DriverConfig::new()
+                .chrome_path(chrome_path)
+                .sandbox(!no_sandbox)
+                .headless(headless),
+        )
         .await
     }
 
     /// Launch Chrome with no-sandbox flag (Linux workaround for AppArmor restrictions)
     pub async fn launch_no_sandbox() -> Result<Self> {
-        Self::new(ConnectionMode::Sandboxed {
-            chrome_path: None,
-            no_sandbox: true,
-            headless: false,
-        })
-        .await
+        Self::launch(DriverConfig::new().sandbox(false)).await
     }
 
     /// Launch Chrome with auto-detection for CI environments
@@ -92,12 +518,8 @@ impl ChromeDriver {
             || std::env::var("JENKINS_HOME").is_ok()
             || std::env::var("CIRCLECI").is_ok();
 
-        Self::new(ConnectionMode::Sandboxed {
-            chrome_path: None,
-            no_sandbox: is_ci, // CI environments typically need --no-sandbox
-            headless: is_ci,   // CI environments should run headless
-        })
-        .await
+        // CI environments typically need --no-sandbox and should run headless
+        Self::launch(DriverConfig::new().sandbox(!is_ci).headless(is_ci)).await
     }
 
     /// Connect to existing Chrome on debug port (advanced mode)
@@ -105,77 +527,285 @@ impl ChromeDriver {
         Self::new(ConnectionMode::DebugPort(port)).await
     }
 
+    /// Connect to a remote or containerized Chrome by its DevTools WebSocket or HTTP URL
+    pub async fn connect_ws_url(url: impl Into<String>) -> Result<Self> {
+        Self::new(ConnectionMode::WebSocketUrl(url.into())).await
+    }
+
+    /// Connect to Chrome's debug port on another machine through an SSH tunnel
+    ///
+    /// `destination` is passed to `ssh` (`user@host` or a host alias). The tunnel is
+    /// closed when the driver is dropped.
+    pub async fn connect_ssh(destination: &str, remote_port: u16) -> Result<Self> {
+        Self::connect_ssh_with_options(destination, remote_port, DriverOptions::default()).await
+    }
+
+    /// [`connect_ssh`](Self::connect_ssh) with driver options
+    pub async fn connect_ssh_with_options(
+        destination: &str,
+        remote_port: u16,
+        options: DriverOptions,
+    ) -> Result<Self> {
+        let tunnel = SshTunnel::open(destination, remote_port).await?;
+        Self::start(tunnel.connection_mode().into(), options, Some(tunnel)).await
+    }
+
     /// Create new ChromeDriver with specified connection mode
     pub async fn new(mode: ConnectionMode) -> Result<Self> {
-        let (browser, temp_dir) = match mode {
-            ConnectionMode::Sandboxed {
-                chrome_path,
-                no_sandbox,
-                headless,
-            } => {
-                // Create a unique temporary directory for this browser instance
-                // This ensures parallel tests don't share profile data
-                // Using timestamp in nanoseconds ensures uniqueness across threads
+        Self::new_with_options(mode, DriverOptions::default()).await
+    }
+
+    /// Create a ChromeDriver from a loaded [`Config`](crate::config::Config)
+    pub async fn from_config(config: &crate::config::Config) -> Result<Self> {
+        Self::new_with_options(config.connection_mode(), config.driver_options()).await
+    }
+
+    /// Create new ChromeDriver with specified connection mode and driver options
+    pub async fn new_with_options(mode: ConnectionMode, options: DriverOptions) -> Result<Self> {
+        Self::start(mode.into(), options, None).await
+    }
+
+    /// Launch or connect within `options.launch_timeout`, keeping `tunnel` open for
+    /// the session
+    #[tracing::instrument(name = "launch", skip_all, fields(session_id, mode = ?source))]
+    async fn start(
+        source: BrowserSource,
+        options: DriverOptions,
+        tunnel: Option<SshTunnel>,
+    ) -> Result<Self> {
+        let launch_timeout = options.launch_timeout;
+        let mut state = tokio::time::timeout(launch_timeout, Self::launch_state(source, options))
+            .await
+            .map_err(|_| {
+                BrowserError::LaunchFailed(format!(
+                    "Timed out after {}s waiting for Chrome to start",
+                    launch_timeout.as_secs()
+                ))
+            })??;
+        state.tunnel = tunnel;
+        Ok(Self {
+            inner: Arc::new(state),
+        })
+    }
+
+    async fn launch_state(source: BrowserSource, options: DriverOptions) -> Result<DriverState> {
+        let session_id = format!(
+            "session-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        );
+        tracing::Span::current().record("session_id", session_id.as_str());
+
+        let crash_hint = match &source {
+            BrowserSource::Launch(launch) if launch.container => container::shm_hint(),
+            _ => None,
+        };
+        if let Some(hint) = &crash_hint {
+            tracing::warn!("{}", hint);
+        }
+
+        let mut xvfb = None;
+        let (browser, temp_dir, launched_kind) = match source {
+            BrowserSource::Launch(launch) => {
+                Self::launch_local(launch, &options, &mut xvfb).await?
+            }
+            BrowserSource::DebugPort(port) => {
+                let url = format!("http://localhost:{}", port);
+                let browser = Self::connect(&url, &options).await.map_err(|e| {
+                    BrowserError::ConnectionFailed(format!(
+                        "Failed to connect to Chrome on port {}. \
+                         Make sure Chrome is running with --remote-debugging-port={}: {}",
+                        port, port, e
+                    ))
+                })?;
+                (browser, None, None)
+            }
+            BrowserSource::WebSocketUrl(url) => {
+                let browser = Self::connect(&url, &options).await.map_err(|e| {
+                    BrowserError::ConnectionFailed(format!(
+                        "Failed to connect to Chrome at {}: {}",
+                        url, e
+                    ))
+                })?;
+                (browser, None, None)
+            }
+        };
+
+        let version = browser.version().await?;
+        let kind =
+            launched_kind.unwrap_or_else(|| BrowserKind::from_user_agent(&version.user_agent));
+        let browser_info =
+            BrowserInfo::from_version(kind, &version.product, &version.protocol_version);
+        tracing::info!(
+            browser = kind.name(),
+            version = %browser_info.version,
+            protocol = %browser_info.protocol_version,
+            "Browser ready"
+        );
+        if let Some(issue) = browser_info.compatibility_issue() {
+            match options.version_check {
+                VersionCheck::Off => {}
+                VersionCheck::Warn => tracing::warn!("{}", issue),
+                VersionCheck::Fail => return Err(BrowserError::UnsupportedBrowser(issue)),
+            }
+        }
+
+        let crash_monitors = CrashMonitors::default();
+        if options.crash_history > 0 {
+            route_target_crashes(&browser, crash_monitors.clone()).await;
+        }
+
+        let chat_ui = super::chat::ChatUI::new();
+        chat_ui.set_persistent(options.persistent_chat);
+
+        Ok(DriverState {
+            browser,
+            temp_dir,
+            chat_ui,
+            chat_pages: Mutex::new(HashSet::new()),
+            session_id,
+            navigation_timeout: options.navigation_timeout,
+            fail_on_http_error: options.fail_on_http_error,
+            tabs: Mutex::new(TabState::default()),
+            page_locks: PageLocks::default(),
+            crash_history: options.crash_history,
+            crash_monitors,
+            deterministic: options.deterministic,
+            deterministic_pages: Mutex::new(HashSet::new()),
+            cookie_consent: options.cookie_consent,
+            overlays: options.overlays,
+            axe_core_path: options.axe_core_path,
+            widgets: super::widgets::WidgetRegistry::default(),
+            browser_info,
+            crash_hint,
+            shutdown: Shutdown::default(),
+            xvfb,
+            tunnel: None,
+        })
+    }
+
+    /// Launch a local Chrome (sandboxed or container mode)
+    async fn launch_local(
+        launch: LocalLaunch,
+        options: &DriverOptions,
+        xvfb: &mut Option<Xvfb>,
+    ) -> Result<(Browser, Option<PathBuf>, Option<BrowserKind>)> {
+        let LocalLaunch {
+            chrome_path,
+            no_sandbox,
+            headless,
+            container,
+            proxy,
+            user_data_dir,
+            args,
+        } = launch;
+
+        // Without a profile to use, create a unique temporary directory for this
+        // browser instance. This ensures parallel tests don't share profile data
+        // Using timestamp in nanoseconds ensures uniqueness across threads
+        let temp_dir = match &user_data_dir {
+            Some(_) => None,
+            None => {
                 let unique_id = std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap()
                     .as_nanos();
-                let temp_dir = std::env::temp_dir().join(format!("chromiumoxide-{}", unique_id));
-                std::fs::create_dir_all(&temp_dir).map_err(|e| {
-                    BrowserError::LaunchFailed(format!("Failed to create temp directory: {}", e))
-                })?;
+                Some(std::env::temp_dir().join(format!("chromiumoxide-{}", unique_id)))
+            }
+        };
+        let profile_dir = user_data_dir
+            .or_else(|| temp_dir.clone())
+            .unwrap_or_default();
+        std::fs::create_dir_all(&profile_dir).map_err(|e| {
+            BrowserError::LaunchFailed(format!(
+                "Failed to create profile directory {}: {}",
+                profile_dir.display(),
+                e
+            ))
+        })?;
 
-                // Launch Chrome with visible UI or headless
-                let mut config = if headless {
-                    BrowserConfig::builder()
-                } else {
-                    BrowserConfig::builder().with_head()
-                };
+        let headless = headless || container || !Self::prepare_display(options, xvfb).await;
 
-                // Set unique user data directory for test isolation
-                config = config.user_data_dir(&temp_dir);
+        // Launch Chrome with visible UI or headless
+        let mut config = if headless {
+            BrowserConfig::builder()
+        } else {
+            BrowserConfig::builder().with_head()
+        };
 
-                // Add no-sandbox flag if requested (Linux AppArmor workaround)
-                if no_sandbox {
-                    config = config.arg("--no-sandbox");
-                }
+        // Set the profile directory (unique, for test isolation, unless one was given)
+        config = config.user_data_dir(&profile_dir);
 
-                // Use custom Chrome path if provided, otherwise try auto-download
-                if let Some(path) = chrome_path {
-                    config = config.chrome_executable(path);
-                } else {
-                    // Try to auto-download Chrome if not found
-                    match Self::ensure_chrome_installed().await {
-                        Ok(path) => {
-                            config = config.chrome_executable(path);
-                        }
-                        Err(e) => {
-                            // If auto-download fails, let chromiumoxide try to find system Chrome
-                            eprintln!(
-                                "Note: Auto-download failed ({}), trying system Chrome...",
-                                e
-                            );
-                        }
+        if let Some(xvfb) = xvfb.as_ref() {
+            config = config.env("DISPLAY", xvfb.display());
+        }
+
+        // Add no-sandbox flag if requested (Linux AppArmor workaround)
+        if container {
+            config = config.args(container::CONTAINER_ARGS.iter().copied());
+        } else if no_sandbox {
+            config = config.arg("--no-sandbox");
+        }
+
+        if !options.ignore_cert_errors {
+            config = config.respect_https_errors();
+        }
+
+        if let Some(deterministic) = &options.deterministic {
+            config = config.args(deterministic.launch_args());
+        }
+
+        if let Some(proxy) = proxy {
+            config = config.arg(format!("--proxy-server={}", proxy));
+        }
+        config = config.args(args);
+
+        // Use custom Chrome path if provided, otherwise try auto-download
+        let mut executable = chrome_path.map(PathBuf::from);
+        if executable.is_none() {
+            // Try to auto-download Chrome if not found
+            match Self::ensure_chrome_installed(
+                options.download_dir.as_deref(),
+                options.chrome_version.as_deref(),
+            )
+            .await
+            {
+                Ok(path) => executable = Some(path),
+                Err(e) => {
+                    tracing::warn!(
+                        error = %e,
+                        "Auto-download failed, trying system Chrome..."
+                    );
+                    // Use an installed browser; failing that, let chromiumoxide look
+                    let detected = tokio::task::spawn_blocking(detect_browsers)
+                        .await
+                        .unwrap_or_default();
+                    if let Some(browser) = detected.into_iter().next() {
+                        tracing::info!(
+                            kind = ?browser.kind,
+                            executable = %browser.executable.display(),
+                            version = browser.version.as_deref().unwrap_or("unknown"),
+                            "Using installed browser"
+                        );
+                        executable = Some(browser.executable);
                     }
                 }
+            }
+        }
 
-                let (browser, mut handler) = Browser::launch(config.build().map_err(|e| {
-                    BrowserError::LaunchFailed(format!(
-                        "{}. \n\n\
-                                 Chrome not found. You can:\n\
-                                 - Install Chrome: https://www.google.com/chrome/\n\
-                                 - Ubuntu/Debian: sudo apt install chromium-browser\n\
-                                 - Fedora: sudo dnf install chromium\n\
-                                 - macOS: brew install --cask google-chrome\n\
-                                 - Or specify path: --chrome-path /path/to/chrome\n\
-                                 - Linux sandbox issue? Try: --no-sandbox",
-                        e
-                    ))
-                })?)
-                .await
-                .map_err(|e| {
-                    BrowserError::LaunchFailed(format!(
-                        "{}. \n\n\
+        let kind = executable.as_deref().map_or(BrowserKind::Chrome, |path| {
+            BrowserKind::from_executable(&path.to_string_lossy())
+        });
+        config = config.args(launch_args(kind).iter().copied());
+        if let Some(path) = executable {
+            config = config.chrome_executable(path);
+        }
+
+        let (browser, mut handler) = Browser::launch(config.build().map_err(|e| {
+            BrowserError::LaunchFailed(format!(
+                "{}. \n\n\
                          Chrome not found. You can:\n\
                          - Install Chrome: https://www.google.com/chrome/\n\
                          - Ubuntu/Debian: sudo apt install chromium-browser\n\
@@ -183,50 +813,126 @@ impl ChromeDriver {
                          - macOS: brew install --cask google-chrome\n\
                          - Or specify path: --chrome-path /path/to/chrome\n\
                          - Linux sandbox issue? Try: --no-sandbox",
-                        e
-                    ))
-                })?;
+                e
+            ))
+        })?)
+        .await
+        .map_err(|e| {
+            BrowserError::LaunchFailed(format!(
+                "{}. \n\n\
+                 Chrome not found. You can:\n\
+                 - Install Chrome: https://www.google.com/chrome/\n\
+                 - Ubuntu/Debian: sudo apt install chromium-browser\n\
+                 - Fedora: sudo dnf install chromium\n\
+                 - macOS: brew install --cask google-chrome\n\
+                 - Or specify path: --chrome-path /path/to/chrome\n\
+                 - Linux sandbox issue? Try: --no-sandbox",
+                e
+            ))
+        })?;
 
-                // Spawn handler task
-                tokio::spawn(async move {
-                    while (handler.next().await).is_some() {
-                        // Handle browser events
-                    }
-                });
+        // Spawn handler task
+        tokio::spawn(async move {
+            while (handler.next().await).is_some() {
+                // Handle browser events
+            }
+        });
+
+        Ok((browser, temp_dir, Some(kind)))
+    }
+
+    /// Connect to a running Chrome by WebSocket or HTTP debug URL
+    async fn connect(url: &str, options: &DriverOptions) -> std::result::Result<Browser, String> {
+        let handler_config = HandlerConfig {
+            ignore_https_errors: options.ignore_cert_errors,
+            ..Default::default()
+        };
+        let (browser, mut handler) = Browser::connect_with_config(url, handler_config)
+            .await
+            .map_err(|e| e.to_string())?;
 
-                (browser, Some(temp_dir))
+        // Spawn handler task
+        tokio::spawn(async move {
+            while (handler.next().await).is_some() {
+                // Handle browser events
             }
-            ConnectionMode::DebugPort(port) => {
-                let url = format!("http://localhost:{}", port);
-                let (browser, mut handler) = Browser::connect(&url).await.map_err(|e| {
-                    BrowserError::ConnectionFailed(format!(
-                        "Failed to connect to Chrome on port {}. \
-                             Make sure Chrome is running with --remote-debugging-port={}: {}",
-                        port, port, e
-                    ))
-                })?;
+        });
 
-                // Spawn handler task
-                tokio::spawn(async move {
-                    while (handler.next().await).is_some() {
-                        // Handle browser events
-                    }
-                });
+        Ok(browser)
+    }
 
-                (browser, None)
+    /// Make sure a headed Chrome has a display, returning false if it must run headless
+    ///
+    /// Without a display, starts Xvfb into `xvfb` if `options.xvfb` is set, and
+    /// otherwise (or if Xvfb fails) warns that the launch falls back to headless.
+    async fn prepare_display(options: &DriverOptions, xvfb: &mut Option<Xvfb>) -> bool {
+        if display::has_display() {
+            return true;
+        }
+        if !options.xvfb {
+            tracing::warn!(
+                "No display found (DISPLAY and WAYLAND_DISPLAY are unset), launching \
+                 Chrome headless; enable `xvfb` to run it headed on a virtual display"
+            );
+            return false;
+        }
+        match Xvfb::start().await {
+            Ok(server) => {
+                *xvfb = Some(server);
+                true
             }
-        };
+            Err(e) => {
+                tracing::warn!(error = %e, "No display found and Xvfb failed, launching Chrome headless");
+                false
+            }
+        }
+    }
 
-        Ok(Self {
-            browser,
-            temp_dir,
-            chat_ui: super::chat::ChatUI::new(),
-        })
+    /// Unique identifier of this browser session (used in logs and traces)
+    pub fn session_id(&self) -> &str {
+        &self.inner.session_id
     }
 
-    /// Navigate to a URL
-    pub async fn navigate(&self, url: &str) -> Result<()> {
-        use chromiumoxide::cdp::browser_protocol::page::NavigateParams;
+    /// Flavor, version, and DevTools protocol version of the browser this driver
+    /// controls, read with `Browser.getVersion` when it was launched or connected
+    pub fn browser_info(&self) -> &BrowserInfo {
+        &self.inner.browser_info
+    }
+
+    /// `DISPLAY` of the Xvfb virtual display Chrome runs on, if the driver started one
+    pub fn xvfb_display(&self) -> Option<&str> {
+        self.inner.xvfb.as_ref().map(Xvfb::display)
+    }
+
+    /// Navigate to a URL, returning the main document's HTTP status
+    ///
+    /// A 4xx/5xx response is only an error with `DriverOptions::fail_on_http_error`.
+    /// The load wait stops with `BrowserError::Cancelled` if a handle closes the session.
+    pub async fn navigate(&self, url: &str) -> Result<NavigationResult> {
+        self.navigate_cancellable(url, &self.inner.shutdown.closing)
+            .await
+    }
+
+    /// Navigate to a URL, aborting the load wait if `cancel_token` is cancelled
+    ///
+    /// Returns `BrowserError::Cancelled` if the token fires before navigation starts
+    /// or while waiting for the page load event.
+    #[tracing::instrument(
+        name = "navigate",
+        skip(self, cancel_token),
+        fields(session_id = %self.inner.session_id)
+    )]
+    pub async fn navigate_cancellable(
+        &self,
+        url: &str,
+        cancel_token: &CancellationToken,
+    ) -> Result<NavigationResult> {
+        if cancel_token.is_cancelled() {
+            return Err(BrowserError::Cancelled);
+        }
+        if self.is_closed() {
+            return Err(self.closed_error());
+        }
 
         // Normalize URL - add https:// if no protocol specified
         let normalized_url = if !url.starts_with("http://")
@@ -235,22 +941,34 @@ impl ChromeDriver {
             && !url.starts_with("about:")
             && !url.starts_with("data:")
         {
-            eprintln!("🔧 Normalizing URL: {} -> https://{}", url, url);
+            tracing::debug!("Normalizing URL: {} -> https://{}", url, url);
             format!("https://{}", url)
         } else {
             url.to_string()
         };
 
-        eprintln!("🌐 Starting navigation to: {}", normalized_url);
+        tracing::info!(url = %normalized_url, "Starting navigation");
 
         // Always get all pages and work with the first one (or create if none exist)
-        let mut pages = self.browser.pages().await?;
-        eprintln!("📄 Found {} browser page(s)", pages.len());
+        let mut pages = self.inner.browser.pages().await?;
+        tracing::debug!("Found {} browser page(s)", pages.len());
+
+        // Adopted popups stay open; navigate the active one if the caller switched to it
+        let (adopted, active) = {
+            let mut tabs = self.inner.tabs.lock().unwrap();
+            tabs.prune(&pages);
+            (tabs.adopted.clone(), tabs.active.clone())
+        };
+        pages
+            .retain(|p| !adopted.contains(p.target_id()) || active.as_ref() == Some(p.target_id()));
+        if let Some(active) = &active {
+            pages.sort_by_key(|p| p.target_id() != active);
+        }
 
         // Close all but the first page to ensure we only have one page
         for (i, p) in pages.iter().enumerate() {
-            if i > 0 {
-                eprintln!("🗑️  Closing extra page {}", i);
+            if i > 0 && !adopted.contains(p.target_id()) {
+                tracing::debug!("Closing extra page {}", i);
                 let _ = p
                     .execute(
                         chromiumoxide::cdp::browser_protocol::target::CloseTargetParams::new(
@@ -262,24 +980,44 @@ impl ChromeDriver {
         }
 
         // Refresh page list after closing
-        pages = self.browser.pages().await?;
+        pages = self.inner.browser.pages().await?;
+        pages
+            .retain(|p| !adopted.contains(p.target_id()) || active.as_ref() == Some(p.target_id()));
+        if let Some(active) = &active {
+            pages.sort_by_key(|p| p.target_id() != active);
+        }
 
         let page = if let Some(page) = pages.first() {
-            eprintln!("✓ Using existing page");
+            tracing::debug!("Using existing page");
             // Use the first (and now only) page
             page.clone()
         } else {
-            eprintln!("➕ Creating new page");
+            tracing::debug!("Creating new page");
             // No page exists, create a new one
-            self.browser
+            self.inner
+                .browser
                 .new_page("about:blank")
                 .await
                 .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?
         };
 
+        let lock = self.inner.page_locks.get(page.target_id());
+        lock.run(self.load(page, normalized_url, cancel_token))
+            .await
+    }
+
+    /// Navigate `page` to `normalized_url` and wait for it to load, holding its lock
+    async fn load(
+        &self,
+        page: Page,
+        normalized_url: String,
+        cancel_token: &CancellationToken,
+    ) -> Result<NavigationResult> {
+        use chromiumoxide::cdp::browser_protocol::page::NavigateParams;
+
         // Use CDP Page.navigate command directly (more reliable than goto())
         // This is what the working headless_integration tests use
-        eprintln!("🚀 Executing CDP Navigate command...");
+        tracing::debug!("Executing CDP Navigate command...");
         let params = NavigateParams::builder()
             .url(&normalized_url)
             .build()
@@ -287,58 +1025,96 @@ impl ChromeDriver {
                 BrowserError::NavigationFailed(format!("Invalid URL {}: {}", normalized_url, e))
             })?;
 
-        let response = page.execute(params).await.map_err(|e| {
-            eprintln!("❌ CDP Navigate failed: {}", e);
-            let error_str = e.to_string();
-
-            // Detect "oneshot canceled" error which indicates browser connection is dead
-            if error_str.contains("oneshot canceled") {
-                BrowserError::NavigationFailed(
-                    "Browser connection lost. The browser may have been closed or crashed. Please launch the browser again.".to_string()
-                )
-            } else {
-                BrowserError::NavigationFailed(format!(
-                    "Failed to navigate to {}: {}",
-                    normalized_url, e
-                ))
+        self.watch_for_crashes(&page).await;
+        self.attach_persistent_chat(&page).await;
+        let documents = DocumentResponses::listen(&page).await?;
+        let response = match page.execute(params).await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::error!(error = %e, "CDP Navigate failed");
+                let error_str = e.to_string();
+
+                // Detect "oneshot canceled" error which indicates browser connection is dead
+                let error = if error_str.contains("oneshot canceled") {
+                    BrowserError::NavigationFailed(
+                        "Browser connection lost. The browser may have been closed or crashed. Please launch the browser again.".to_string()
+                    )
+                } else {
+                    BrowserError::NavigationFailed(format!(
+                        "Failed to navigate to {}: {}",
+                        normalized_url, e
+                    ))
+                };
+                return Err(self.crash_or(&page, error).await);
             }
-        })?;
+        };
 
         // Check if navigation was successful
         let nav_result = response.result;
         if let Some(error_text) = nav_result.error_text {
-            eprintln!("❌ Navigation error from browser: {}", error_text);
+            tracing::error!(error = %error_text, "Navigation error from browser");
             return Err(BrowserError::NavigationFailed(format!(
                 "Navigation error: {}",
                 error_text
             )));
         }
 
-        eprintln!("📡 Frame ID: {:?}", nav_result.frame_id);
+        tracing::debug!(frame_id = ?nav_result.frame_id, "Navigation committed");
         if let Some(loader_id) = &nav_result.loader_id {
-            eprintln!("📦 Loader ID: {:?}", loader_id);
+            tracing::debug!(loader_id = ?loader_id, "Navigation loader");
         }
 
+        self.wait_for_load(
+            &page,
+            documents,
+            nav_result.loader_id.as_ref(),
+            &normalized_url,
+            cancel_token,
+        )
+        .await
+    }
+
+    /// Wait for a committed navigation of `page` (loading `target`, by URL or name) to
+    /// load, then answer cookie banners and overlays and read its result
+    async fn wait_for_load(
+        &self,
+        page: &Page,
+        documents: DocumentResponses,
+        loader_id: Option<&LoaderId>,
+        target: &str,
+        cancel_token: &CancellationToken,
+    ) -> Result<NavigationResult> {
         // Wait for the page to load using Page.loadEventFired with timeout
         // This is more reliable than arbitrary sleeps
-        eprintln!("⏳ Waiting for page load event (30s timeout)...");
+        tracing::debug!(
+            timeout_secs = self.inner.navigation_timeout.as_secs(),
+            "Waiting for page load event..."
+        );
         use chromiumoxide::cdp::browser_protocol::page::EventLoadEventFired;
 
-        let load_result = tokio::time::timeout(
-            tokio::time::Duration::from_secs(30),
-            page.event_listener::<EventLoadEventFired>(),
-        )
-        .await;
+        let load_result = tokio::select! {
+            result = tokio::time::timeout(
+                self.inner.navigation_timeout,
+                page.event_listener::<EventLoadEventFired>(),
+            ) => result,
+            _ = cancel_token.cancelled() => {
+                tracing::info!("Navigation cancelled while waiting for load event");
+                return Err(BrowserError::Cancelled);
+            }
+        };
 
         match load_result {
             Ok(Ok(_)) => {
-                eprintln!("✓ Page load event fired successfully");
+                tracing::debug!("Page load event fired");
             }
             Ok(Err(e)) => {
-                eprintln!("⚠️  Warning: Could not wait for load event: {}", e);
+                tracing::warn!(error = %e, "Could not wait for load event");
             }
             Err(_) => {
-                eprintln!("❌ Timeout waiting for page load event after 30s");
+                tracing::error!(
+                    "Timeout waiting for page load event after {}s",
+                    self.inner.navigation_timeout.as_secs()
+                );
                 return Err(BrowserError::NavigationFailed(format!(
                     "Request timed out. \n\
                     Possible causes:\n\
@@ -348,18 +1124,95 @@ impl ChromeDriver {
                     - Browser unable to resolve DNS\n\
                     \n\
                     Debug: Check if you can access {} in your regular browser.",
-                    normalized_url, normalized_url
+                    target, target
                 )));
             }
         }
 
         // Additional small delay for page state to stabilize
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        eprintln!("✓ Navigation completed successfully");
+        tokio::select! {
+            _ = tokio::time::sleep(tokio::time::Duration::from_millis(500)) => {}
+            _ = cancel_token.cancelled() => return Err(BrowserError::Cancelled),
+        }
+        let result = documents.finish(page, loader_id).await;
+        tracing::info!(status = ?result.status, url = %result.url, "Navigation completed");
+        if result.is_error_page() {
+            tracing::warn!(url = %target, "Chrome is showing an error page");
+        } else if let Some(consent) = &self.inner.cookie_consent {
+            tokio::select! {
+                dismissed = super::consent::dismiss_cookie_banner(page, consent) => {
+                    if let Err(e) = dismissed {
+                        tracing::warn!(error = %e, "Failed to dismiss cookie banner");
+                    }
+                }
+                _ = cancel_token.cancelled() => return Err(BrowserError::Cancelled),
+            }
+        }
+        if let (Some(overlays), false) = (&self.inner.overlays, result.is_error_page()) {
+            if let Err(e) = super::overlay::handle_overlays(page, overlays).await {
+                tracing::warn!(error = %e, "Failed to handle overlays");
+            }
+        }
 
-        // NOTE: Chat UI injection disabled - chat is now in the Tauri app
+        // In persistent mode the chat UI re-injects itself on `Page.frameNavigated`
+        // (see `ChatUI::attach`); otherwise chat lives in the Tauri app
 
-        Ok(())
+        if self.inner.fail_on_http_error && result.is_http_error() {
+            return Err(BrowserError::HttpStatus {
+                url: result.url,
+                status: result.status.unwrap_or_default(),
+            });
+        }
+        Ok(result)
+    }
+
+    /// Go back to the previous entry in the tab's history and wait for it to load,
+    /// the way [`navigate`](Self::navigate) does
+    ///
+    /// Fails with `BrowserError::NavigationFailed` if there is no previous entry.
+    pub async fn go_back(&self) -> Result<NavigationResult> {
+        self.navigate_history(HistoryNavigation::Back).await
+    }
+
+    /// Go forward to the next entry in the tab's history and wait for it to load
+    ///
+    /// Fails with `BrowserError::NavigationFailed` if there is no next entry.
+    pub async fn go_forward(&self) -> Result<NavigationResult> {
+        self.navigate_history(HistoryNavigation::Forward).await
+    }
+
+    /// Reload the page, bypassing the browser cache if `ignore_cache`, and wait for
+    /// it to load
+    pub async fn reload(&self, ignore_cache: bool) -> Result<NavigationResult> {
+        self.navigate_history(HistoryNavigation::Reload { ignore_cache })
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "navigate_history",
+        skip(self),
+        fields(session_id = %self.inner.session_id)
+    )]
+    async fn navigate_history(&self, navigation: HistoryNavigation) -> Result<NavigationResult> {
+        self.with_page(|page| async move {
+            let documents = DocumentResponses::listen(&page).await?;
+            let timeout = self.inner.navigation_timeout;
+            let loader_id =
+                match super::navigation::navigate_history(&page, navigation, timeout).await {
+                    Ok(loader_id) => loader_id,
+                    Err(e) => return Err(self.crash_or(&page, e).await),
+                };
+            let url = page.url().await.ok().flatten().unwrap_or_default();
+            self.wait_for_load(
+                &page,
+                documents,
+                loader_id.as_ref(),
+                &url,
+                &self.inner.shutdown.closing,
+            )
+            .await
+        })
+        .await
     }
 
     /// Get current URL
@@ -400,48 +1253,394 @@ impl ChromeDriver {
         Ok(html)
     }
 
-    /// Get visible page text
-    pub async fn get_page_text(&self) -> Result<String> {
+    /// Compact outline of the rendered page for a language model, in at most
+    /// `max_tokens` (estimated) tokens
+    ///
+    /// Invisible nodes are left out, bare wrappers collapsed, and only salient
+    /// attributes (role, aria-label, href, name, ...) kept; long texts are shortened
+    /// to fit. See [`crate::model::llm_dom`].
+    pub async fn serialize_dom_for_llm(&self, max_tokens: usize) -> Result<String> {
+        let json: String = self
+            .execute_script_typed(crate::html::PAGE_TREE_SCRIPT)
+            .await?;
+        let tree: crate::model::llm_dom::DomNode = serde_json::from_str(&json)
+            .map_err(|e| BrowserError::Other(format!("Failed to read page tree: {}", e)))?;
+        Ok(crate::model::llm_dom::serialize(&tree, max_tokens))
+    }
+
+    /// Get visible page text
+    pub async fn get_page_text(&self) -> Result<String> {
+        let page = self.get_active_page().await?;
+
+        let text = page
+            .find_element("body")
+            .await
+            .map_err(|_e| BrowserError::ElementNotFound("body".to_string()))?
+            .inner_text()
+            .await
+            .map_err(|_e| BrowserError::ElementNotFound("body".to_string()))?
+            .ok_or(BrowserError::ElementNotFound("body".to_string()))?;
+
+        Ok(text)
+    }
+
+    /// Get text from specific element
+    pub async fn get_element_text(&self, selector: &str) -> Result<String> {
+        let page = self.get_active_page().await?;
+
+        let text = page
+            .find_element(selector)
+            .await
+            .map_err(|_e| BrowserError::ElementNotFound(selector.to_string()))?
+            .inner_text()
+            .await
+            .map_err(|_e| BrowserError::ElementNotFound(selector.to_string()))?
+            .ok_or(BrowserError::ElementNotFound(selector.to_string()))?;
+
+        Ok(text)
+    }
+
+    /// Get the bounding box of the first element matching `selector`
+    ///
+    /// Returns viewport and page coordinates and whether the element is visible.
+    pub async fn get_element_rect(&self, selector: &str) -> Result<super::geometry::ElementRect> {
+        let page = self.get_active_page().await?;
+        super::geometry::element_rect(&page, selector).await
+    }
+
+    /// Get the bounding boxes of all elements matching `selector`, in document order
+    pub async fn get_elements_rects(
+        &self,
+        selector: &str,
+    ) -> Result<Vec<super::geometry::ElementRect>> {
+        let page = self.get_active_page().await?;
+        super::geometry::element_rects(&page, selector).await
+    }
+
+    /// Element rendered at viewport point `(x, y)` in CSS pixels, with a selector for it
+    ///
+    /// Grounds coordinate outputs ("click at 412,305") before acting on them; use
+    /// `StepFrame::element_at` for screenshot pixels of a captured frame.
+    pub async fn node_at_point(
+        &self,
+        x: f64,
+        y: f64,
+    ) -> Result<Option<super::geometry::NodeAtPoint>> {
+        let page = self.get_active_page().await?;
+        super::geometry::node_at_point(&page, x, y).await
+    }
+
+    /// Style the first element matching `selector` as if `states` applied (e.g. `:hover`)
+    ///
+    /// Lets screenshots and VisualDom snapshots capture hover, focus, and active styling.
+    /// Replaces states previously forced on the element; they last until
+    /// [`clear_forced_states`](Self::clear_forced_states) or the next navigation.
+    pub async fn force_element_state(
+        &self,
+        selector: &str,
+        states: &[super::emulation::PseudoState],
+    ) -> Result<()> {
+        self.with_page(|page| async move {
+            super::emulation::force_pseudo_state(&page, selector, states).await
+        })
+        .await
+    }
+
+    /// Remove all pseudo states forced with [`force_element_state`](Self::force_element_state)
+    pub async fn clear_forced_states(&self) -> Result<()> {
+        self.with_page(|page| async move { super::emulation::clear_forced_states(&page).await })
+            .await
+    }
+
+    /// Emulate dark mode, reduced motion, and/or print media on the current page
+    ///
+    /// Each flag is applied explicitly, so `dark_mode: false` emulates a light color
+    /// scheme. Lasts across navigations until [`clear_emulated_media`](Self::clear_emulated_media).
+    pub async fn emulate_media(
+        &self,
+        dark_mode: bool,
+        reduced_motion: bool,
+        print: bool,
+    ) -> Result<()> {
+        self.with_page(|page| async move {
+            super::emulation::emulate_media(&page, dark_mode, reduced_motion, print).await
+        })
+        .await
+    }
+
+    /// Stop emulating media set with [`emulate_media`](Self::emulate_media)
+    pub async fn clear_emulated_media(&self) -> Result<()> {
+        self.with_page(|page| async move { super::emulation::clear_emulated_media(&page).await })
+            .await
+    }
+
+    /// List the service worker registrations of the current page's origin
+    pub async fn service_workers(
+        &self,
+    ) -> Result<Vec<super::service_worker::ServiceWorkerRegistration>> {
+        let page = self.get_active_page().await?;
+        super::service_worker::registrations(&page).await
+    }
+
+    /// Stop all running service workers (they restart when they next handle a request)
+    pub async fn stop_service_workers(&self) -> Result<()> {
+        self.with_page(|page| async move { super::service_worker::stop_all(&page).await })
+            .await
+    }
+
+    /// Unregister the current origin's service workers; returns how many were removed
+    pub async fn unregister_service_workers(&self) -> Result<usize> {
+        self.with_page(|page| async move { super::service_worker::unregister_all(&page).await })
+            .await
+    }
+
+    /// Route the current page's requests around its service workers (or stop doing so)
+    pub async fn bypass_service_workers(&self, bypass: bool) -> Result<()> {
+        self.with_page(|page| async move { super::service_worker::set_bypass(&page, bypass).await })
+            .await
+    }
+
+    /// Clear the Cache Storage of the current origin, where service workers keep responses
+    pub async fn clear_service_worker_caches(&self) -> Result<()> {
+        self.with_page(|page| async move { super::service_worker::clear_caches(&page).await })
+            .await
+    }
+
+    /// Emulate having no network connection on the current page (or restore it)
+    pub async fn set_offline(&self, offline: bool) -> Result<()> {
+        self.with_page(
+            |page| async move { super::service_worker::set_offline(&page, offline).await },
+        )
+        .await
+    }
+
+    /// Watch the current page's XHR/fetch responses whose URL matches `url_pattern`
+    ///
+    /// `url_pattern` is a glob (`*` and `?`), e.g. `*/graphql*`. JSON bodies are
+    /// narrowed with `filter`, a JSONPath such as `$.data.products[*].name`, when given.
+    pub async fn tap_responses(
+        &self,
+        url_pattern: &str,
+        filter: Option<&str>,
+    ) -> Result<super::response_tap::ResponseTap> {
+        self.with_page(|page| async move {
+            super::response_tap::ResponseTap::attach(&page, url_pattern, filter).await
+        })
+        .await
+    }
+
+    /// Rewrite the bodies (and optionally status) of the current page's matching responses
+    ///
+    /// Rules stay active until the returned rewriter is dropped or stopped.
+    pub async fn rewrite_responses(
+        &self,
+        rules: Vec<super::rewrite::RewriteRule>,
+    ) -> Result<super::rewrite::ResponseRewriter> {
+        self.with_page(|page| async move {
+            super::rewrite::ResponseRewriter::attach(&page, rules).await
+        })
+        .await
+    }
+
+    /// Security state and certificate details of the current page
+    pub async fn get_security_info(&self) -> Result<super::security::SecurityInfo> {
+        let page = self.get_active_page().await?;
+        super::security::security_info(&page).await
+    }
+
+    /// Load `url` and report its broken subresources and anchor targets
+    ///
+    /// Subresources are checked through the network events of the load; anchor
+    /// targets (unless `options.check_anchors` is off) with HEAD requests.
+    pub async fn audit_links(
+        &self,
+        url: &str,
+        options: &super::link_check::LinkCheckOptions,
+    ) -> Result<super::link_check::LinkReport> {
+        let page = self.get_active_page().await?;
+        let auditor = super::link_check::LinkAuditor::attach(&page).await?;
+        let navigation = self.navigate(url).await?;
+        auditor.finish(&page, &navigation, options).await
+    }
+
+    /// Run an axe-core accessibility audit of the current page
+    pub async fn audit_accessibility(
+        &self,
+        options: &super::accessibility::AccessibilityOptions,
+    ) -> Result<super::accessibility::AccessibilityReport> {
+        self.with_page(|page| async move {
+            super::accessibility::audit_accessibility(
+                &page,
+                self.inner.axe_core_path.as_deref(),
+                options,
+            )
+            .await
+        })
+        .await
+    }
+
+    /// Summarize how the current page loaded, with a Lighthouse-style 0-100 score
+    pub async fn audit_performance(&self) -> Result<super::performance::PerformanceReport> {
+        let page = self.get_active_page().await?;
+        super::performance::audit_performance(&page).await
+    }
+
+    /// Title, meta description, canonical, robots, Open Graph/Twitter tags, and
+    /// JSON-LD of the current page (check it with `SeoMetadata::validate`)
+    pub async fn extract_seo(&self) -> Result<super::seo::SeoMetadata> {
+        let page = self.get_active_page().await?;
+        super::seo::extract_seo(&page).await
+    }
+
+    /// Find how the current page is paginated: a next link or button, a numbered
+    /// pager, a "Load more" button, or infinite scroll (None if it isn't)
+    pub async fn detect_pagination(&self) -> Result<Option<super::pagination::Pagination>> {
+        let page = self.get_active_page().await?;
+        super::pagination::detect_pagination(&page).await
+    }
+
+    /// Run `extract` on the current page and each next page, up to `max_pages`
+    /// pages, collecting what it returns (see [`pagination`](super::pagination))
+    pub async fn for_each_page<T, F, Fut>(
+        &self,
+        max_pages: usize,
+        extract: F,
+    ) -> Result<super::pagination::PagedResults<T>>
+    where
+        F: FnMut(usize, Page) -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        self.with_page(|page| async move {
+            super::pagination::for_each_page(&page, max_pages, extract).await
+        })
+        .await
+    }
+
+    /// Describe a form of the current page (the first `<form>` if `selector` is None):
+    /// its fields' types, labels, required flags, constraints, and options
+    pub async fn infer_form_schema(
+        &self,
+        selector: Option<&str>,
+    ) -> Result<super::form::FormSchema> {
+        let page = self.get_active_page().await?;
+        super::form::infer_form_schema(&page, selector).await
+    }
+
+    /// Fill form fields of the current page, matched by name, id, label, or placeholder
+    pub async fn fill_form<K: AsRef<str>>(
+        &self,
+        fields: impl IntoIterator<Item = (K, super::form::FormValue)>,
+    ) -> Result<super::form::FillResult> {
+        let fields: Vec<(String, super::form::FormValue)> = fields
+            .into_iter()
+            .map(|(key, value)| (key.as_ref().to_string(), value))
+            .collect();
+        self.with_page(|page| async move {
+            super::form::fill_form(&page, &fields, &self.inner.widgets).await
+        })
+        .await
+    }
+
+    /// Replace the value of the element matching `selector` with `text`
+    ///
+    /// Date pickers, comboboxes, and rich text editors are filled through the
+    /// widget strategy that handles them (see [`widgets`](Self::widgets)); other
+    /// elements get plain typing. Returns the name of the strategy used, if any.
+    pub async fn type_text(&self, selector: &str, text: &str) -> Result<Option<String>> {
+        self.with_page(|page| async move {
+            let element = page
+                .find_element(selector)
+                .await
+                .map_err(|_| BrowserError::ElementNotFound(selector.to_string()))?;
+            self.inner.widgets.type_text(&page, &element, text).await
+        })
+        .await
+    }
+
+    /// Widget strategies used by `type_text` and `fill_form`; register custom ones here
+    pub fn widgets(&self) -> &super::widgets::WidgetRegistry {
+        &self.inner.widgets
+    }
+
+    /// Find a selector for an element, trying fallback selectors, its fingerprint, and
+    /// then its text when the primary selector matches nothing
+    pub async fn resolve_selector(
+        &self,
+        candidates: &super::selector::SelectorCandidates,
+    ) -> Result<super::selector::SelectorMatch> {
+        let page = self.get_active_page().await?;
+        super::selector::resolve(&page, candidates).await
+    }
+
+    /// Record how the first element matching `selector` looks, so script steps can
+    /// find it again if the selector stops matching
+    pub async fn element_fingerprint(&self, selector: &str) -> Result<ElementFingerprint> {
         let page = self.get_active_page().await?;
+        super::selector::fingerprint(&page, selector).await
+    }
 
-        let text = page
-            .find_element("body")
-            .await
-            .map_err(|_e| BrowserError::ElementNotFound("body".to_string()))?
-            .inner_text()
+    /// Tap the center of the first element matching `selector` with a touch event
+    ///
+    /// Touch events are emulated on the page from then on; pair with mobile device
+    /// metrics (`Emulation.setDeviceMetricsOverride`) for touch-only UI.
+    pub async fn tap(&self, selector: &str) -> Result<()> {
+        self.with_page(|page| async move { super::touch::tap(&page, selector).await })
             .await
-            .map_err(|_e| BrowserError::ElementNotFound("body".to_string()))?
-            .ok_or(BrowserError::ElementNotFound("body".to_string()))?;
-
-        Ok(text)
     }
 
-    /// Get text from specific element
-    pub async fn get_element_text(&self, selector: &str) -> Result<String> {
-        let page = self.get_active_page().await?;
-
-        let text = page
-            .find_element(selector)
+    /// Drag one finger between two viewport points (CSS pixels) over `duration`
+    pub async fn swipe(&self, from: (f64, f64), to: (f64, f64), duration: Duration) -> Result<()> {
+        self.with_page(|page| async move { super::touch::swipe(&page, from, to, duration).await })
             .await
-            .map_err(|_e| BrowserError::ElementNotFound(selector.to_string()))?
-            .inner_text()
+    }
+
+    /// Pinch two fingers around the viewport center (`scale` > 1 zooms in, < 1 out)
+    pub async fn pinch_zoom(&self, scale: f64) -> Result<()> {
+        self.with_page(|page| async move { super::touch::pinch_zoom(&page, scale).await })
             .await
-            .map_err(|_e| BrowserError::ElementNotFound(selector.to_string()))?
-            .ok_or(BrowserError::ElementNotFound(selector.to_string()))?;
+    }
 
-        Ok(text)
+    /// axe-core script used by accessibility audits, if configured
+    pub fn axe_core_path(&self) -> Option<&Path> {
+        self.inner.axe_core_path.as_deref()
     }
 
     /// Take a screenshot of the current page
     pub async fn screenshot(&self) -> Result<Vec<u8>> {
-        let page = self.get_active_page().await?;
-
-        let screenshot = page
-            .screenshot(chromiumoxide::page::ScreenshotParams::default())
-            .await
-            .map_err(|e| BrowserError::Other(format!("Failed to take screenshot: {}", e)))?;
+        self.with_page(|page| async move {
+            match page
+                .screenshot(chromiumoxide::page::ScreenshotParams::default())
+                .await
+            {
+                Ok(screenshot) => Ok(screenshot),
+                Err(e) => {
+                    let error = BrowserError::Other(format!("Failed to take screenshot: {}", e));
+                    Err(self.crash_or(&page, error).await)
+                }
+            }
+        })
+        .await
+    }
 
-        Ok(screenshot)
+    /// Take a screenshot with a numbered label on each interactive element in view
+    ///
+    /// Returns the image and what each label marks (set-of-marks prompting, see
+    /// [`super::marks`]). The labels are removed again once the screenshot is taken.
+    /// Text of elements matching `redacted` is masked in the marks.
+    pub async fn screenshot_with_marks(
+        &self,
+        redacted: &[String],
+    ) -> Result<(Vec<u8>, Vec<super::marks::Mark>)> {
+        self.with_page(|page| async move {
+            let marks = super::marks::add_marks(&page, redacted).await?;
+            let screenshot = self.screenshot().await;
+            // Take the labels off even if the screenshot failed
+            if let Err(e) = super::marks::remove_marks(&page).await {
+                tracing::warn!(error = %e, "Failed to remove set-of-marks overlay");
+            }
+            Ok((screenshot?, marks))
+        })
+        .await
     }
 
     /// Take a screenshot and save to file
@@ -455,6 +1654,58 @@ impl ChromeDriver {
         Ok(())
     }
 
+    /// Serialize the page as an MHTML archive
+    ///
+    /// The archive holds the HTML with its stylesheets, images, fonts, and frames, so it
+    /// opens offline as a faithful replica of the page.
+    pub async fn get_mhtml(&self) -> Result<String> {
+        self.with_page(|page| async move {
+            match page
+                .execute(
+                    chromiumoxide::cdp::browser_protocol::page::CaptureSnapshotParams {
+                        format: Some(
+                            chromiumoxide::cdp::browser_protocol::page::CaptureSnapshotFormat::Mhtml,
+                        ),
+                    },
+                )
+                .await
+            {
+                Ok(result) => Ok(result.result.data),
+                Err(e) => {
+                    let error = BrowserError::Other(format!("Failed to capture MHTML: {}", e));
+                    Err(self.crash_or(&page, error).await)
+                }
+            }
+        })
+        .await
+    }
+
+    /// Save the page as an MHTML archive (`.mhtml`)
+    pub async fn capture_mhtml(&self, path: &Path) -> Result<()> {
+        let mhtml = self.get_mhtml().await?;
+
+        tokio::fs::write(path, mhtml)
+            .await
+            .map_err(|e| BrowserError::Other(format!("Failed to write MHTML archive: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Interactive elements of the page, most salient first
+    ///
+    /// Scores size, position, background contrast, whether the element is covered,
+    /// and semantic hints, so an agent can prefer the primary call to action when an
+    /// instruction is ambiguous. See [`crate::model::salience`].
+    pub async fn rank_elements(&self) -> Result<Vec<crate::model::salience::SalientElement>> {
+        let page = self.get_active_page().await?;
+        let viewport = crate::step_frame::capture_viewport(&page).await?;
+        let snapshot = self.capture_visual_dom(&[], false, true, false).await?;
+        let snapshot: crate::model::frame::CaptureSnapshotResponse =
+            serde_json::from_value(snapshot)
+                .map_err(|e| BrowserError::Other(format!("Invalid VisualDom snapshot: {}", e)))?;
+        Ok(snapshot.rank_elements(&viewport))
+    }
+
     /// Capture a VisualDom snapshot with layout, style, and image information
     ///
     /// VisualDom is a custom format we created that combines Chrome DevTools Protocol's
@@ -480,110 +1731,167 @@ impl ChromeDriver {
         include_paint_order: bool,
         include_images: bool,
     ) -> Result<serde_json::Value> {
-        let page = self.get_active_page().await?;
+        self.capture_visual_dom_with_budget(
+            computed_styles,
+            include_dom_rects,
+            include_paint_order,
+            include_images,
+            &ImageBudget::default(),
+        )
+        .await
+    }
 
-        // Execute CDP DOMSnapshot.captureSnapshot command
-        let result = page
-            .execute(
-                chromiumoxide::cdp::browser_protocol::dom_snapshot::CaptureSnapshotParams {
-                    computed_styles: computed_styles.to_vec(),
-                    include_dom_rects: Some(include_dom_rects),
-                    include_paint_order: Some(include_paint_order),
-                    include_blended_background_colors: Some(false),
-                    include_text_color_opacities: Some(false),
-                },
-            )
-            .await
-            .map_err(|e| BrowserError::Other(format!("Failed to capture DOM snapshot: {}", e)))?;
+    /// Capture a VisualDom snapshot, embedding images only within `budget`
+    ///
+    /// Images over `budget.max_pixels` are skipped, large ones are downscaled, and
+    /// embedding stops once the byte or time budget runs out. Entries that were left
+    /// out have `data: null` and a `skipped` reason (`too_large`, `byte_budget`,
+    /// `time_budget`); downscaled entries have `downscaled: true` and their natural size.
+    pub async fn capture_visual_dom_with_budget(
+        &self,
+        computed_styles: &[String],
+        include_dom_rects: bool,
+        include_paint_order: bool,
+        include_images: bool,
+        budget: &ImageBudget,
+    ) -> Result<serde_json::Value> {
+        self.with_page(|page| async move {
+            // Execute CDP DOMSnapshot.captureSnapshot command
+            let result = page
+                .execute(
+                    chromiumoxide::cdp::browser_protocol::dom_snapshot::CaptureSnapshotParams {
+                        computed_styles: computed_styles.to_vec(),
+                        include_dom_rects: Some(include_dom_rects),
+                        include_paint_order: Some(include_paint_order),
+                        // Backgrounds as painted, for contrast in salience ranking
+                        include_blended_background_colors: Some(true),
+                        include_text_color_opacities: Some(false),
+                    },
+                )
+                .await
+                .map_err(|e| {
+                    BrowserError::Other(format!("Failed to capture DOM snapshot: {}", e))
+                })?;
 
-        // Extract the inner result and serialize to JSON
-        let mut snapshot = serde_json::to_value(result.result)
-            .map_err(|e| BrowserError::Other(format!("Failed to serialize snapshot: {}", e)))?;
+            // Extract the inner result and serialize to JSON
+            let mut snapshot = serde_json::to_value(result.result)
+                .map_err(|e| BrowserError::Other(format!("Failed to serialize snapshot: {}", e)))?;
 
-        // If images requested, extract and embed them as base64
-        if include_images {
-            let images = self.extract_images_as_base64().await?;
-            if let Some(obj) = snapshot.as_object_mut() {
-                obj.insert("images".to_string(), images);
+            // If images requested, extract and embed them as base64
+            if include_images {
+                let images = self.extract_images_as_base64(budget).await?;
+                if let Some(obj) = snapshot.as_object_mut() {
+                    obj.insert("images".to_string(), images);
+                }
             }
-        }
 
-        Ok(snapshot)
+            Ok(snapshot)
+        })
+        .await
     }
 
-    /// Extract all images from the page and convert to base64
+    /// Extract all images from the page and convert to base64, within `budget`
     ///
     /// Returns an array of objects with {src, data, width, height, alt}
-    async fn extract_images_as_base64(&self) -> Result<serde_json::Value> {
-        let js_code = r#"
-            (async () => {
+    async fn extract_images_as_base64(&self, budget: &ImageBudget) -> Result<serde_json::Value> {
+        let js_code = format!(
+            r#"
+            (async () => {{
+                const maxPixels = {max_pixels};
+                const maxDimension = {max_dimension};
+                const maxTotalBytes = {max_total_bytes};
+                const deadline = performance.now() + {time_budget_ms};
+
                 const images = Array.from(document.querySelectorAll('img'));
                 const results = [];
-
-                for (const img of images) {
-                    try {
-                        // Skip invisible images
-                        const rect = img.getBoundingClientRect();
-                        if (rect.width === 0 || rect.height === 0) continue;
-
-                        // Create a canvas to convert image to base64
+                let totalBytes = 0;
+
+                for (const img of images) {{
+                    // Skip invisible images
+                    const rect = img.getBoundingClientRect();
+                    if (rect.width === 0 || rect.height === 0) continue;
+
+                    const naturalWidth = img.naturalWidth || img.width;
+                    const naturalHeight = img.naturalHeight || img.height;
+                    const entry = {{
+                        src: img.src || img.currentSrc,
+                        data: null,
+                        width: naturalWidth,
+                        height: naturalHeight,
+                        alt: img.alt || '',
+                        x: rect.x,
+                        y: rect.y,
+                        displayWidth: rect.width,
+                        displayHeight: rect.height,
+                    }};
+                    results.push(entry);
+
+                    if (performance.now() > deadline) {{
+                        entry.skipped = 'time_budget';
+                        continue;
+                    }}
+                    if (naturalWidth * naturalHeight > maxPixels) {{
+                        entry.skipped = 'too_large';
+                        continue;
+                    }}
+
+                    try {{
+                        // Create a canvas to convert image to base64, downscaling if needed
+                        const scale = Math.min(1, maxDimension / Math.max(naturalWidth, naturalHeight, 1));
                         const canvas = document.createElement('canvas');
-                        canvas.width = img.naturalWidth || img.width;
-                        canvas.height = img.naturalHeight || img.height;
+                        canvas.width = Math.max(1, Math.round(naturalWidth * scale));
+                        canvas.height = Math.max(1, Math.round(naturalHeight * scale));
 
                         const ctx = canvas.getContext('2d');
-                        ctx.drawImage(img, 0, 0);
+                        ctx.drawImage(img, 0, 0, canvas.width, canvas.height);
 
                         // Convert to base64 (will be data URI format)
                         const dataUrl = canvas.toDataURL('image/png');
-
-                        results.push({
-                            src: img.src || img.currentSrc,
-                            data: dataUrl,
-                            width: canvas.width,
-                            height: canvas.height,
-                            alt: img.alt || '',
-                            x: rect.x,
-                            y: rect.y,
-                            displayWidth: rect.width,
-                            displayHeight: rect.height,
-                        });
-                    } catch (e) {
+                        if (totalBytes + dataUrl.length > maxTotalBytes) {{
+                            entry.skipped = 'byte_budget';
+                            continue;
+                        }}
+                        totalBytes += dataUrl.length;
+
+                        entry.data = dataUrl;
+                        entry.width = canvas.width;
+                        entry.height = canvas.height;
+                        if (scale < 1) {{
+                            entry.downscaled = true;
+                            entry.naturalWidth = naturalWidth;
+                            entry.naturalHeight = naturalHeight;
+                        }}
+                    }} catch (e) {{
                         // Skip images that can't be converted (CORS, etc.)
                         // But still record their metadata
-                        const rect = img.getBoundingClientRect();
-                        results.push({
-                            src: img.src || img.currentSrc,
-                            data: null,
-                            width: img.naturalWidth || img.width,
-                            height: img.naturalHeight || img.height,
-                            alt: img.alt || '',
-                            x: rect.x,
-                            y: rect.y,
-                            displayWidth: rect.width,
-                            displayHeight: rect.height,
-                            error: 'CORS or load error',
-                        });
-                    }
-                }
+                        entry.error = 'CORS or load error';
+                    }}
+                }}
 
                 return results;
-            })()
-        "#;
+            }})()
+        "#,
+            max_pixels = budget.max_pixels,
+            max_dimension = budget.max_dimension,
+            max_total_bytes = budget.max_total_bytes,
+            time_budget_ms = budget.time_budget.as_millis(),
+        );
 
-        self.execute_script(js_code).await
+        self.execute_script(&js_code).await
     }
 
     /// Execute arbitrary JavaScript in the page context
     pub async fn execute_script(&self, script: &str) -> Result<serde_json::Value> {
-        let page = self.get_active_page().await?;
-
-        let result = page
-            .evaluate(script)
-            .await
-            .map_err(|e| BrowserError::Other(format!("Script execution failed: {}", e)))?;
-
-        Ok(result.into_value().unwrap_or(serde_json::Value::Null))
+        self.with_page(|page| async move {
+            match page.evaluate(script).await {
+                Ok(result) => Ok(result.into_value().unwrap_or(serde_json::Value::Null)),
+                Err(e) => {
+                    let error = BrowserError::Other(format!("Script execution failed: {}", e));
+                    Err(self.crash_or(&page, error).await)
+                }
+            }
+        })
+        .await
     }
 
     /// Execute JavaScript and return a specific type
@@ -591,16 +1899,17 @@ impl ChromeDriver {
         &self,
         script: &str,
     ) -> Result<T> {
-        let page = self.get_active_page().await?;
-
-        let result = page
-            .evaluate(script)
-            .await
-            .map_err(|e| BrowserError::Other(format!("Script execution failed: {}", e)))?;
+        self.with_page(|page| async move {
+            let result = page
+                .evaluate(script)
+                .await
+                .map_err(|e| BrowserError::Other(format!("Script execution failed: {}", e)))?;
 
-        result
-            .into_value()
-            .map_err(|e| BrowserError::Other(format!("Failed to deserialize result: {}", e)))
+            result
+                .into_value()
+                .map_err(|e| BrowserError::Other(format!("Failed to deserialize result: {}", e)))
+        })
+        .await
     }
 
     /// Send a raw CDP (Chrome DevTools Protocol) command using JSON
@@ -685,7 +1994,7 @@ impl ChromeDriver {
 
     /// Get access to the underlying Browser for advanced CDP usage
     pub fn browser(&self) -> &Browser {
-        &self.browser
+        &self.inner.browser
     }
 
     /// Get access to the current page for advanced operations
@@ -694,11 +2003,245 @@ impl ChromeDriver {
         self.get_active_page().await
     }
 
+    /// Command lock of the current page (see [`page_lock`](super::page_lock))
+    ///
+    /// Run a sequence of driver calls or direct [`Page`] commands in
+    /// [`PageLock::run`] to keep other operations off the page until it finishes,
+    /// or reads in [`PageLock::parallel`] to skip waiting for the lock.
+    pub async fn page_lock(&self) -> Result<PageLock> {
+        let page = self.get_active_page().await?;
+        Ok(self.page_lock_for(&page))
+    }
+
+    /// Command lock of `page`
+    pub fn page_lock_for(&self, page: &Page) -> PageLock {
+        self.inner.page_locks.get(page.target_id())
+    }
+
+    /// Wait for a popup (window or tab) opened by the current page
+    ///
+    /// Call after the action that opens it, e.g. clicking a `target="_blank"` link.
+    /// The popup is adopted into the driver's tab list, so navigation no longer
+    /// closes it, but page methods keep acting on the current page until
+    /// [`switch_to_page`](Self::switch_to_page) is called with the returned handle.
+    ///
+    /// Once the popup opens, it gets up to `timeout` again to leave `about:blank` and
+    /// finish loading; popups that stay blank are returned when that runs out.
+    ///
+    /// Returns `BrowserError::NoPopup` if nothing opens within `timeout`.
+    pub async fn wait_for_popup(&self, timeout: Duration) -> Result<Page> {
+        let opener = self.get_active_page().await?;
+        self.wait_for_popup_from(&opener, timeout).await
+    }
+
+    /// Wait for a popup opened by `opener` and adopt it (see [`wait_for_popup`](Self::wait_for_popup))
+    pub async fn wait_for_popup_from(&self, opener: &Page, timeout: Duration) -> Result<Page> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let pages = self.inner.browser.pages().await?;
+            let popup = {
+                let mut tabs = self.inner.tabs.lock().unwrap();
+                tabs.prune(&pages);
+                let popup = pages.into_iter().find(|p| {
+                    p.opener_id().as_ref() == Some(opener.target_id())
+                        && !tabs.adopted.contains(p.target_id())
+                });
+                if let Some(popup) = &popup {
+                    tabs.adopted.push(popup.target_id().clone());
+                }
+                popup
+            };
+
+            if let Some(popup) = popup {
+                tracing::info!(target_id = ?popup.target_id(), "Adopted popup");
+                wait_for_popup_load(&popup, Instant::now() + timeout).await;
+                return Ok(popup);
+            }
+            if Instant::now() >= deadline {
+                return Err(BrowserError::NoPopup(timeout));
+            }
+            tokio::time::sleep(POPUP_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Make `page` the tab that page methods (navigate, screenshot, ...) act on
+    ///
+    /// Pass an adopted popup to continue in it, or any other page to switch back to
+    /// the main page. The driver also switches back on its own once the active
+    /// popup closes.
+    pub async fn switch_to_page(&self, page: &Page) -> Result<()> {
+        {
+            let mut tabs = self.inner.tabs.lock().unwrap();
+            tabs.active = tabs
+                .adopted
+                .contains(page.target_id())
+                .then(|| page.target_id().clone());
+        }
+        page.bring_to_front().await?;
+        Ok(())
+    }
+
+    /// Open tabs: the main page followed by adopted popups
+    pub async fn tabs(&self) -> Result<Vec<Page>> {
+        let pages = self.inner.browser.pages().await?;
+        let adopted = {
+            let mut tabs = self.inner.tabs.lock().unwrap();
+            tabs.prune(&pages);
+            tabs.adopted.clone()
+        };
+        let main = self.main_page(pages.clone()).await?;
+
+        let mut tabs = vec![main];
+        tabs.extend(
+            adopted
+                .iter()
+                .filter_map(|id| pages.iter().find(|p| p.target_id() == id).cloned()),
+        );
+        Ok(tabs)
+    }
+
+    /// Crash monitor for `page`, if the driver has used it and crash watching is on
+    pub fn crash_monitor(&self, page: &Page) -> Option<Arc<CrashMonitor>> {
+        self.inner
+            .crash_monitors
+            .lock()
+            .unwrap()
+            .get(page.target_id())
+            .cloned()
+    }
+
+    /// Crash details for the current page, or None if it hasn't crashed
+    pub async fn crash_report(&self) -> Result<Option<CrashReport>> {
+        let page = self.get_active_page().await?;
+        Ok(self
+            .crash_monitor(&page)
+            .and_then(|monitor| monitor.report()))
+    }
+
+    /// Start a crash monitor for `page` unless it already has one
+    async fn watch_for_crashes(&self, page: &Page) {
+        if self.inner.crash_history == 0 || self.crash_monitor(page).is_some() {
+            return;
+        }
+        match CrashMonitor::attach(page, self.inner.crash_history).await {
+            Ok(monitor) => {
+                let monitor = monitor.with_hint(self.inner.crash_hint.clone());
+                self.inner
+                    .crash_monitors
+                    .lock()
+                    .unwrap()
+                    .insert(page.target_id().clone(), Arc::new(monitor));
+            }
+            Err(e) => tracing::warn!(error = %e, "Failed to watch page for crashes"),
+        }
+    }
+
+    async fn apply_deterministic_rendering(&self, page: &Page) {
+        let Some(deterministic) = &self.inner.deterministic else {
+            return;
+        };
+        if !self
+            .inner
+            .deterministic_pages
+            .lock()
+            .unwrap()
+            .insert(page.target_id().clone())
+        {
+            return;
+        }
+        if let Err(e) = deterministic.apply(page).await {
+            tracing::warn!(error = %e, "Failed to apply deterministic rendering");
+        }
+    }
+
+    async fn attach_persistent_chat(&self, page: &Page) {
+        if !self.inner.chat_ui.is_persistent()
+            || !self
+                .inner
+                .chat_pages
+                .lock()
+                .unwrap()
+                .insert(page.target_id().clone())
+        {
+            return;
+        }
+        if let Err(e) = self.inner.chat_ui.attach(page).await {
+            tracing::warn!(error = %e, "Failed to attach persistent chat UI");
+        }
+    }
+
+    /// `BrowserError::TargetCrashed` if `page` crashed, otherwise `error`
+    async fn crash_or(&self, page: &Page, error: BrowserError) -> BrowserError {
+        match self.crash_monitor(page) {
+            Some(monitor) => monitor.crash_error(CRASH_GRACE).await.unwrap_or(error),
+            None => error,
+        }
+    }
+
+    /// Settings navigations answer cookie consent banners with (None = they don't)
+    pub fn cookie_consent(&self) -> Option<&CookieConsent> {
+        self.inner.cookie_consent.as_ref()
+    }
+
+    /// Settings navigations handle overlays with (None = they don't)
+    pub fn overlay_handling(&self) -> Option<&OverlayHandling> {
+        self.inner.overlays.as_ref()
+    }
+
+    /// Pop-ups, interstitials, and other layers covering the current page, topmost first
+    pub async fn detect_overlays(&self) -> Result<Vec<super::overlay::Overlay>> {
+        let page = self.get_active_page().await?;
+        let settings = self.inner.overlays.clone().unwrap_or_default();
+        super::overlay::detect_overlays(&page, &settings).await
+    }
+
+    /// Dismiss, hide, or report the layers covering the current page, as `settings` says
+    pub async fn handle_overlays(
+        &self,
+        settings: &OverlayHandling,
+    ) -> Result<Vec<super::overlay::HandledOverlay>> {
+        self.with_page(|page| async move { super::overlay::handle_overlays(&page, settings).await })
+            .await
+    }
+
+    /// Elements of the current page whose text matches `query`, best first
+    ///
+    /// Text is compared after normalization (case, diacritics, nbsp, typographic
+    /// punctuation), and fuzzily if the query's matcher has a threshold.
+    pub async fn find_text(
+        &self,
+        query: &super::text::TextQuery,
+    ) -> Result<Vec<super::text::TextHit>> {
+        let page = self.get_active_page().await?;
+        super::text::find_text(&page, query).await
+    }
+
+    /// Answer a cookie consent banner on the current page, if one appears
+    ///
+    /// Uses `DriverOptions::cookie_consent`, or the defaults (reject optional cookies,
+    /// wait up to 2 seconds) if that is off.
+    pub async fn dismiss_cookie_banner(&self) -> Result<Option<super::consent::DismissedBanner>> {
+        self.with_page(|page| async move {
+            let settings = self.inner.cookie_consent.clone().unwrap_or_default();
+            super::consent::dismiss_cookie_banner(&page, &settings).await
+        })
+        .await
+    }
+
+    /// Check the current page for a visible reCAPTCHA, hCaptcha, or Cloudflare challenge
+    pub async fn detect_captcha(&self) -> Result<Option<super::captcha::CaptchaEvent>> {
+        let page = self.get_active_page().await?;
+        super::captcha::detect_captcha(&page).await
+    }
+
     /// Check if the browser is still alive and responsive
     /// Returns true if the browser connection is healthy, false otherwise
     pub async fn is_alive(&self) -> bool {
+        if self.is_closed() {
+            return false;
+        }
         // Try to get pages - if this fails, the browser is dead
-        match self.browser.pages().await {
+        match self.inner.browser.pages().await {
             Ok(pages) => {
                 // If we can get pages, try a simple operation to verify connection
                 if let Some(page) = pages.first() {
@@ -716,79 +2259,50 @@ impl ChromeDriver {
         }
     }
 
-    /// Close the browser connection
+    /// Close the browser connection for every handle of the session
+    ///
+    /// The browser is closed once; handles closing it concurrently or later get the
+    /// same outcome. Other handles' calls fail from then on.
     pub async fn close(self) -> Result<()> {
-        self.browser
-            .close()
+        let shutdown = &self.inner.shutdown;
+        shutdown.closing.cancel();
+        shutdown
+            .closed
+            .get_or_init(|| async {
+                tracing::info!(session_id = %self.inner.session_id, "Closing browser session");
+                self.inner
+                    .browser
+                    .close()
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
             .await
-            .map_err(|e| BrowserError::Other(e.to_string()))?;
-        Ok(())
+            .clone()
+            .map_err(BrowserError::Other)
     }
 
-    /// Ensure Chrome is installed, downloading if necessary
-    async fn ensure_chrome_installed() -> Result<PathBuf> {
-        let cache_dir = dirs::cache_dir()
-            .ok_or_else(|| BrowserError::Other("Cannot determine cache directory".to_string()))?
-            .join("robert")
-            .join("chrome");
-
-        // Create cache directory if it doesn't exist
-        tokio::fs::create_dir_all(&cache_dir)
-            .await
-            .map_err(|e| BrowserError::Other(format!("Failed to create cache dir: {}", e)))?;
-
-        // Check if Chrome already downloaded
-        let revision_info_path = cache_dir.join(".downloaded");
-        if revision_info_path.exists() {
-            // Chrome already downloaded, find the executable
-            if let Some(executable) = Self::find_chrome_in_cache(&cache_dir).await {
-                return Ok(executable);
-            }
-        }
-
-        // Download Chrome
-        eprintln!("📥 Downloading Chrome for Testing (first time only, ~150MB)...");
-        let fetcher = BrowserFetcher::new(
-            BrowserFetcherOptions::builder()
-                .with_path(&cache_dir)
-                .build()
-                .map_err(|e| BrowserError::Other(format!("Fetcher config failed: {}", e)))?,
-        );
-
-        let info = fetcher
-            .fetch()
-            .await
-            .map_err(|e| BrowserError::Other(format!("Chrome download failed: {}", e)))?;
-
-        // Mark as downloaded
-        tokio::fs::write(&revision_info_path, "downloaded")
-            .await
-            .map_err(|e| BrowserError::Other(format!("Failed to write marker: {}", e)))?;
-
-        eprintln!("✅ Chrome downloaded successfully!");
-
-        Ok(info.executable_path)
+    /// Whether a handle has closed the session
+    pub fn is_closed(&self) -> bool {
+        self.inner.shutdown.closing.is_cancelled()
     }
 
-    /// Find Chrome executable in cache directory
-    async fn find_chrome_in_cache(cache_dir: &Path) -> Option<PathBuf> {
-        // Look for Chrome executable in various possible locations
-        let possible_paths = vec![
-            cache_dir.join("chrome"),
-            cache_dir.join("chrome.exe"),
-            cache_dir.join("Google Chrome.app/Contents/MacOS/Google Chrome"),
-            cache_dir.join("chrome-linux/chrome"),
-            cache_dir.join("chrome-mac/Chromium.app/Contents/MacOS/Chromium"),
-            cache_dir.join("chrome-win/chrome.exe"),
-        ];
-
-        for path in possible_paths {
-            if path.exists() {
-                return Some(path);
-            }
-        }
-
-        None
+    /// Ensure Chrome is installed, downloading if necessary
+    ///
+    /// Uses the per-version cache under `download_dir` (default `~/.cache/robert/chrome`),
+    /// picking the newest install that satisfies `version` and verifying its integrity.
+    async fn ensure_chrome_installed(
+        download_dir: Option<&Path>,
+        version: Option<&str>,
+    ) -> Result<PathBuf> {
+        let pin = version
+            .map(str::parse::<VersionPin>)
+            .transpose()
+            .map_err(BrowserError::Other)?;
+        let installer = ChromeInstaller::for_download_dir(download_dir)
+            .ok_or_else(|| BrowserError::Other("Cannot determine cache directory".to_string()))?;
+        let chrome = installer.ensure(pin.as_ref()).await?;
+        Ok(chrome.executable)
     }
 
     /// Execute a CDP script from a JSON file
@@ -830,9 +2344,13 @@ impl ChromeDriver {
         let page = self.current_page().await?;
 
         // Create executor and run script
-        let executor = crate::cdp::CdpExecutor::new(page);
+        let executor = crate::cdp::CdpExecutor::new(page).with_driver(self);
         executor
             .execute_script(&script)
+            .instrument(tracing::info_span!(
+                "execute_cdp_script",
+                session_id = %self.inner.session_id
+            ))
             .await
             .map_err(|e| BrowserError::Other(format!("Script execution failed: {}", e)))
     }
@@ -846,9 +2364,13 @@ impl ChromeDriver {
         script: &crate::cdp::CdpScript,
     ) -> Result<crate::cdp::ExecutionReport> {
         let page = self.current_page().await?;
-        let executor = crate::cdp::CdpExecutor::new(page);
+        let executor = crate::cdp::CdpExecutor::new(page).with_driver(self);
         executor
             .execute_script(script)
+            .instrument(tracing::info_span!(
+                "execute_cdp_script",
+                session_id = %self.inner.session_id
+            ))
             .await
             .map_err(|e| BrowserError::Other(format!("Script execution failed: {}", e)))
     }
@@ -857,48 +2379,75 @@ impl ChromeDriver {
 
     /// Get a reference to the ChatUI manager
     pub fn chat_ui(&self) -> &super::chat::ChatUI {
-        &self.chat_ui
-    }
-
-    /// Get a mutable reference to the ChatUI manager
-    pub fn chat_ui_mut(&mut self) -> &mut super::chat::ChatUI {
-        &mut self.chat_ui
+        &self.inner.chat_ui
     }
 
     /// Send a message from the agent to the chat UI
     pub async fn send_chat_message(&self, message: &str) -> Result<()> {
         let page = self.current_page().await?;
-        self.chat_ui.send_agent_message(&page, message).await
+        self.inner.chat_ui.send_agent_message(&page, message).await
+    }
+
+    /// Send a message with markdown, images, or action buttons to the chat UI
+    pub async fn send_rich_chat_message(&self, message: &super::chat::ChatMessage) -> Result<()> {
+        let page = self.current_page().await?;
+        self.inner.chat_ui.send_rich_message(&page, message).await
+    }
+
+    /// Wait up to `timeout` for the user's answer to the chat message `correlation_id`
+    pub async fn await_chat_feedback(
+        &self,
+        correlation_id: &str,
+        timeout: Duration,
+    ) -> Result<Option<super::chat::UserFeedback>> {
+        let page = self.current_page().await?;
+        self.inner
+            .chat_ui
+            .await_feedback(&page, correlation_id, timeout)
+            .await
+    }
+
+    /// Wait up to `timeout` for a click on one of the chat action buttons in `action_ids`
+    pub async fn wait_for_chat_action(
+        &self,
+        action_ids: &[&str],
+        timeout: Duration,
+    ) -> Result<Option<String>> {
+        let page = self.current_page().await?;
+        self.inner
+            .chat_ui
+            .wait_for_action(&page, action_ids, timeout)
+            .await
     }
 
     /// Get all messages from the chat UI
     pub async fn get_chat_messages(&self) -> Result<Vec<super::chat::ChatMessage>> {
         let page = self.current_page().await?;
-        self.chat_ui.get_messages(&page).await
+        self.inner.chat_ui.get_messages(&page).await
     }
 
     /// Clear all messages from the chat UI
     pub async fn clear_chat_messages(&self) -> Result<()> {
         let page = self.current_page().await?;
-        self.chat_ui.clear_messages(&page).await
+        self.inner.chat_ui.clear_messages(&page).await
     }
 
     /// Manually inject the chat UI (useful if it was disabled during construction)
     pub async fn inject_chat_ui(&self) -> Result<()> {
         let page = self.current_page().await?;
-        self.chat_ui.inject(&page).await
+        self.inner.chat_ui.inject(&page).await
     }
 
     /// Collapse the chat UI sidebar
     pub async fn collapse_chat(&self) -> Result<()> {
         let page = self.current_page().await?;
-        self.chat_ui.collapse(&page).await
+        self.inner.chat_ui.collapse(&page).await
     }
 
     /// Expand the chat UI sidebar
     pub async fn expand_chat(&self) -> Result<()> {
         let page = self.current_page().await?;
-        self.chat_ui.expand(&page).await
+        self.inner.chat_ui.expand(&page).await
     }
 
     /// Position the browser window
@@ -941,16 +2490,19 @@ impl ChromeDriver {
             .await
             .map_err(|e| BrowserError::Other(format!("Failed to set window bounds: {}", e)))?;
 
-        eprintln!(
-            "✓ Browser window positioned: {}x{} at ({}, {})",
-            browser_width, browser_height, browser_x, browser_y
+        tracing::debug!(
+            "Browser window positioned: {}x{} at ({}, {})",
+            browser_width,
+            browser_height,
+            browser_x,
+            browser_y
         );
 
         Ok(())
     }
 }
 
-impl Drop for ChromeDriver {
+impl Drop for DriverState {
     fn drop(&mut self) {
         // Clean up temporary directory if it exists
         if let Some(temp_dir) = &self.temp_dir {
@@ -960,3 +2512,147 @@ impl Drop for ChromeDriver {
         }
     }
 }
+
+/// Give a new popup until `deadline` to navigate away from `about:blank` and load
+async fn wait_for_popup_load(popup: &Page, deadline: Instant) {
+    const LOADED: &str =
+        "document.readyState === 'complete' && window.location.href !== 'about:blank'";
+
+    while Instant::now() < deadline {
+        let loaded = match popup.evaluate(LOADED).await {
+            Ok(result) => result.into_value::<bool>().unwrap_or(false),
+            // The first navigation can tear down the context mid-evaluation
+            Err(_) => false,
+        };
+        if loaded {
+            return;
+        }
+        tokio::time::sleep(POPUP_POLL_INTERVAL).await;
+    }
+    tracing::debug!(target_id = ?popup.target_id(), "Popup still loading, continuing anyway");
+}
+
+/// Forward browser-level crash events (which carry the termination status) to the
+/// page monitors, and drop monitors of closed pages
+async fn route_target_crashes(browser: &Browser, monitors: CrashMonitors) {
+    let (mut crashed, mut destroyed) = match futures::try_join!(
+        browser.event_listener::<EventTargetCrashed>(),
+        browser.event_listener::<EventTargetDestroyed>()
+    ) {
+        Ok(listeners) => listeners,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to subscribe to target crash events");
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                event = crashed.next() => match event {
+                    Some(event) => {
+                        tracing::error!(
+                            target_id = ?event.target_id,
+                            status = %event.status,
+                            error_code = event.error_code,
+                            "Target crashed"
+                        );
+                        let monitor = monitors.lock().unwrap().get(&event.target_id).cloned();
+                        if let Some(monitor) = monitor {
+                            monitor.mark_crashed(event.status.clone(), event.error_code);
+                        }
+                    }
+                    None => break,
+                },
+                event = destroyed.next() => match event {
+                    Some(event) => {
+                        monitors.lock().unwrap().remove(&event.target_id);
+                    }
+                    None => break,
+                },
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_driver_handles_are_shareable() {
+        fn shareable<T: Clone + Send + Sync + 'static>() {}
+        shareable::<ChromeDriver>();
+    }
+
+    #[test]
+    fn test_driver_config() {
+        let config = DriverConfig::new()
+            .chrome_path("/opt/chrome/chrome")
+            .headless(true)
+            .sandbox(false)
+            .proxy("socks5://localhost:1080")
+            .user_data_dir("/tmp/profile")
+            .arg("--lang=de-DE")
+            .args(["--mute-audio", "--disable-extensions"])
+            .download_dir("/tmp/chrome-cache")
+            .navigation_timeout(Duration::from_secs(5));
+        let launch = &config.launch;
+        assert_eq!(launch.chrome_path.as_deref(), Some("/opt/chrome/chrome"));
+        assert!(launch.headless && launch.no_sandbox && !launch.container);
+        assert_eq!(launch.proxy.as_deref(), Some("socks5://localhost:1080"));
+        assert_eq!(launch.user_data_dir, Some(PathBuf::from("/tmp/profile")));
+        assert_eq!(
+            launch.args,
+            ["--lang=de-DE", "--mute-audio", "--disable-extensions"]
+        );
+        assert_eq!(
+            config.options.download_dir,
+            Some(PathBuf::from("/tmp/chrome-cache"))
+        );
+        assert_eq!(config.options.navigation_timeout, Duration::from_secs(5));
+        assert_eq!(config.options.launch_timeout, Duration::from_secs(60));
+
+        // Options replace the ones set before them, and nothing else
+        let config = config.options(DriverOptions::default());
+        assert_eq!(config.options.download_dir, None);
+        assert!(config.launch.headless);
+
+        let defaults = DriverConfig::new().launch;
+        assert!(!defaults.headless && !defaults.no_sandbox && defaults.args.is_empty());
+    }
+
+    #[test]
+    fn test_connection_modes_map_to_sources() {
+        let container = BrowserSource::from(ConnectionMode::Container {
+            chrome_path: Some("chromium".to_string()),
+        });
+        assert!(matches!(
+            container,
+            BrowserSource::Launch(LocalLaunch {
+                container: true,
+                headless: true,
+                no_sandbox: true,
+                ..
+            })
+        ));
+        let sandboxed = BrowserSource::from(ConnectionMode::Sandboxed {
+            chrome_path: None,
+            no_sandbox: false,
+            headless: true,
+        });
+        assert!(matches!(
+            sandboxed,
+            BrowserSource::Launch(LocalLaunch {
+                container: false,
+                headless: true,
+                no_sandbox: false,
+                ..
+            })
+        ));
+        assert!(matches!(
+            BrowserSource::from(ConnectionMode::DebugPort(9222)),
+            BrowserSource::DebugPort(9222)
+        ));
+    }
+}
@@ -0,0 +1,455 @@
+//! Cookie Consent Banners
+//!
+//! Most European sites cover the page with a consent banner until it's answered,
+//! which hides content in screenshots and intercepts clicks meant for the page.
+//! With [`DriverOptions::cookie_consent`](super::chrome::DriverOptions::cookie_consent)
+//! set, every navigation looks for a banner and answers it, rejecting optional
+//! cookies where the banner allows that.
+//!
+//! Banners of common consent managers (OneTrust, Cookiebot, Didomi, Usercentrics,
+//! Quantcast, ...) are found by their selectors. Other banners are found by
+//! heuristics: a fixed or modal element that mentions cookies or consent, with a
+//! button whose text is a reject or accept phrase in one of a dozen languages.
+//! Banners inside cross-origin iframes (some Sourcepoint and TrustArc setups) are
+//! out of reach and left alone.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Time between checks for a banner
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Time given to a banner to animate away after its button is clicked
+const CLOSE_DELAY: Duration = Duration::from_millis(300);
+
+/// Buttons of consent managers: manager, reject button, accept button
+const MANAGERS: &[(&str, &str, &str)] = &[
+    (
+        "OneTrust",
+        "#onetrust-reject-all-handler",
+        "#onetrust-accept-btn-handler",
+    ),
+    (
+        "Cookiebot",
+        "#CybotCookiebotDialogBodyButtonDecline",
+        "#CybotCookiebotDialogBodyLevelButtonLevelOptinAllowAll, #CybotCookiebotDialogBodyButtonAccept",
+    ),
+    (
+        "Didomi",
+        "#didomi-notice-disagree-button",
+        "#didomi-notice-agree-button",
+    ),
+    (
+        "Usercentrics",
+        "[data-testid=uc-deny-all-button]",
+        "[data-testid=uc-accept-all-button]",
+    ),
+    (
+        "Quantcast",
+        ".qc-cmp2-summary-buttons button[mode=secondary]",
+        ".qc-cmp2-summary-buttons button[mode=primary]",
+    ),
+    (
+        "TrustArc",
+        "#truste-consent-required",
+        "#truste-consent-button",
+    ),
+    (
+        "Google",
+        ".fc-cta-do-not-consent",
+        ".fc-cta-consent",
+    ),
+    ("CookieYes", ".cky-btn-reject", ".cky-btn-accept"),
+    ("Complianz", ".cmplz-btn.cmplz-deny", ".cmplz-btn.cmplz-accept"),
+    ("Osano", ".osano-cm-denyAll", ".osano-cm-accept-all"),
+    ("iubenda", ".iubenda-cs-reject-btn", ".iubenda-cs-accept-btn"),
+    ("Axeptio", "#axeptio_btn_dismiss", "#axeptio_btn_acceptAll"),
+    ("Klaro", ".klaro .cn-decline", ".klaro .cm-btn-accept-all"),
+];
+
+/// Button texts that refuse optional cookies (English, German, French, Spanish,
+/// Italian, Dutch, Portuguese, Polish, Swedish, Danish, Norwegian, Finnish)
+const REJECT_PHRASES: &[&str] = &[
+    "reject all",
+    "reject",
+    "reject optional cookies",
+    "decline",
+    "decline all",
+    "deny",
+    "deny all",
+    "refuse",
+    "refuse all",
+    "necessary only",
+    "only necessary",
+    "only necessary cookies",
+    "essential only",
+    "only essential cookies",
+    "use necessary cookies only",
+    "alle ablehnen",
+    "ablehnen",
+    "nur notwendige",
+    "nur notwendige cookies",
+    "nur essenzielle cookies",
+    "tout refuser",
+    "refuser",
+    "refuser tout",
+    "continuer sans accepter",
+    "rechazar todo",
+    "rechazar todas",
+    "rechazar",
+    "rifiuta tutto",
+    "rifiuta",
+    "alles weigeren",
+    "weigeren",
+    "rejeitar tudo",
+    "rejeitar",
+    "recusar",
+    "odrzuć wszystkie",
+    "odrzuć",
+    "avvisa alla",
+    "neka alla",
+    "afvis alle",
+    "avvis alle",
+    "hylkää kaikki",
+];
+
+/// Button texts that accept cookies
+const ACCEPT_PHRASES: &[&str] = &[
+    "accept all",
+    "accept",
+    "accept cookies",
+    "allow all",
+    "allow cookies",
+    "agree",
+    "i agree",
+    "i accept",
+    "got it",
+    "ok",
+    "okay",
+    "alle akzeptieren",
+    "akzeptieren",
+    "alle zulassen",
+    "zustimmen",
+    "alle cookies akzeptieren",
+    "einverstanden",
+    "tout accepter",
+    "accepter",
+    "accepter tout",
+    "j'accepte",
+    "aceptar todo",
+    "aceptar todas",
+    "aceptar",
+    "accetta tutto",
+    "accetta",
+    "accetto",
+    "alles accepteren",
+    "accepteren",
+    "akkoord",
+    "aceitar tudo",
+    "aceitar",
+    "akceptuj wszystkie",
+    "akceptuję",
+    "zaakceptuj",
+    "acceptera alla",
+    "godkänn alla",
+    "godkend alle",
+    "accepter alle",
+    "godta alle",
+    "hyväksy kaikki",
+];
+
+/// Answer to give a consent banner
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsentChoice {
+    /// Refuse optional cookies
+    #[default]
+    Reject,
+    /// Accept all cookies
+    Accept,
+}
+
+/// Settings for dismissing cookie consent banners
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CookieConsent {
+    /// Answer to give
+    pub prefer: ConsentChoice,
+
+    /// Accept when rejecting is preferred but the banner has no reject button
+    /// (otherwise such banners stay up)
+    pub accept_if_no_reject: bool,
+
+    /// How long to wait for a banner to appear (many are added after the load event)
+    pub wait: Duration,
+}
+
+impl Default for CookieConsent {
+    fn default() -> Self {
+        Self {
+            prefer: ConsentChoice::Reject,
+            accept_if_no_reject: true,
+            wait: Duration::from_secs(2),
+        }
+    }
+}
+
+/// A banner that was answered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DismissedBanner {
+    /// Consent manager the banner belongs to (None if it was found by heuristics)
+    pub manager: Option<String>,
+
+    /// Answer given
+    pub choice: ConsentChoice,
+
+    /// Text of the button that was clicked
+    pub button: String,
+}
+
+/// A button of a banner on the page
+#[derive(Debug, Clone, Default, Deserialize)]
+struct BannerButton {
+    text: String,
+    /// Consent manager whose button it is
+    manager: Option<String>,
+    /// What the consent manager's button does
+    choice: Option<ConsentChoice>,
+}
+
+/// Find a consent banner on `page` and answer it, waiting up to `settings.wait` for
+/// one to appear
+///
+/// Returns None if no banner appeared, or none of its buttons gives an acceptable
+/// answer.
+pub async fn dismiss_cookie_banner(
+    page: &Page,
+    settings: &CookieConsent,
+) -> Result<Option<DismissedBanner>> {
+    let managers: Vec<serde_json::Value> = MANAGERS
+        .iter()
+        .map(|(manager, reject, accept)| serde_json::json!([manager, reject, accept]))
+        .collect();
+    let script = format!("({})({})", FIND_SCRIPT, serde_json::Value::from(managers));
+
+    let deadline = Instant::now() + settings.wait;
+    loop {
+        let buttons: Vec<BannerButton> = page
+            .evaluate(script.as_str())
+            .await?
+            .into_value()
+            .map_err(|e| BrowserError::Other(format!("Failed to read consent banner: {}", e)))?;
+        if let Some((index, choice)) = choose(&buttons, settings) {
+            let button = &buttons[index];
+            page.evaluate(format!("({})({})", CLICK_SCRIPT, index))
+                .await?;
+            tokio::time::sleep(CLOSE_DELAY).await;
+            let dismissed = DismissedBanner {
+                manager: button.manager.clone(),
+                choice,
+                button: button.text.clone(),
+            };
+            tracing::info!(
+                manager = ?dismissed.manager,
+                choice = ?dismissed.choice,
+                button = %dismissed.button,
+                "Dismissed cookie banner"
+            );
+            return Ok(Some(dismissed));
+        }
+        if !buttons.is_empty() {
+            tracing::debug!(
+                buttons = buttons.len(),
+                "Cookie banner has no acceptable answer"
+            );
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Index of the button to click and the answer it gives: the preferred answer if a
+/// button gives it, consent managers' buttons first
+fn choose(buttons: &[BannerButton], settings: &CookieConsent) -> Option<(usize, ConsentChoice)> {
+    let find = |wanted: ConsentChoice| {
+        let choices = || {
+            buttons
+                .iter()
+                .enumerate()
+                .filter(move |(_, button)| button_choice(button) == Some(wanted))
+        };
+        choices()
+            .find(|(_, button)| button.manager.is_some())
+            .or_else(|| choices().next())
+            .map(|(index, _)| (index, wanted))
+    };
+    match settings.prefer {
+        ConsentChoice::Accept => find(ConsentChoice::Accept),
+        ConsentChoice::Reject if settings.accept_if_no_reject => {
+            find(ConsentChoice::Reject).or_else(|| find(ConsentChoice::Accept))
+        }
+        ConsentChoice::Reject => find(ConsentChoice::Reject),
+    }
+}
+
+/// What clicking `button` answers, from its consent manager or its text
+fn button_choice(button: &BannerButton) -> Option<ConsentChoice> {
+    if button.choice.is_some() {
+        return button.choice;
+    }
+    let text = button
+        .text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    let text = text.trim_matches(|c: char| !c.is_alphanumeric());
+    let is = |phrases: &[&str]| {
+        phrases.iter().any(|phrase| {
+            text == *phrase
+                || text
+                    .strip_prefix(phrase)
+                    .is_some_and(|rest| rest == " cookies" || rest == " all cookies")
+        })
+    };
+    if is(REJECT_PHRASES) {
+        Some(ConsentChoice::Reject)
+    } else if is(ACCEPT_PHRASES) {
+        Some(ConsentChoice::Accept)
+    } else {
+        None
+    }
+}
+
+/// Lists the visible buttons of consent managers and of banner-like elements that
+/// mention cookies (including in open shadow roots), keeping them in
+/// `window.__robert_consent` for [`CLICK_SCRIPT`]
+const FIND_SCRIPT: &str = r#"(managers) => {
+    const roots = [document];
+    for (let i = 0; i < roots.length; i++) {
+        for (const el of roots[i].querySelectorAll('*')) if (el.shadowRoot) roots.push(el.shadowRoot);
+    }
+    const all = (selector) => roots.flatMap((root) => Array.from(root.querySelectorAll(selector)));
+    const visible = (el) => {
+        const box = el.getBoundingClientRect();
+        const style = window.getComputedStyle(el);
+        return box.width >= 1 && box.height >= 1 && style.visibility !== 'hidden' && style.opacity !== '0';
+    };
+    const textOf = (el) => (el.getAttribute('aria-label') || el.innerText || el.value || '')
+        .replace(/\s+/g, ' ').trim();
+
+    const elements = [];
+    const buttons = [];
+    const add = (el, manager, choice) => {
+        if (elements.includes(el) || !visible(el)) return;
+        elements.push(el);
+        buttons.push({ text: textOf(el), manager, choice });
+    };
+    for (const [manager, reject, accept] of managers) {
+        all(reject).forEach((el) => add(el, manager, 'reject'));
+        all(accept).forEach((el) => add(el, manager, 'accept'));
+    }
+
+    // Other banners: fixed, sticky, or modal elements that talk about cookies
+    const mentions = /cookie|consent|gdpr|dsgvo|rgpd|datenschutz|privacy|ciasteczk|evästee/i;
+    const banners = all([
+        '[id*=cookie i]', '[class*=cookie i]', '[id*=consent i]', '[class*=consent i]',
+        '[id*=gdpr i]', '[class*=gdpr i]', '[aria-label*=cookie i]', '[aria-label*=consent i]',
+        '[role=dialog]', '[role=alertdialog]', '[aria-modal=true]',
+    ].join(', '));
+    const floating = (el) => {
+        for (let e = el; e && e.nodeType === 1; e = e.parentElement || e.getRootNode().host) {
+            const position = window.getComputedStyle(e).position;
+            if (position === 'fixed' || position === 'sticky') return true;
+            if (['dialog', 'alertdialog'].includes(e.getAttribute('role')) || e.getAttribute('aria-modal') === 'true') return true;
+        }
+        return false;
+    };
+    for (const banner of banners) {
+        if (!visible(banner) || !mentions.test(banner.innerText || '') || !floating(banner)) continue;
+        const controls = banner.querySelectorAll('button, a, [role=button], input[type=button], input[type=submit]');
+        for (const el of controls) {
+            if (textOf(el).length <= 40) add(el, null, null);
+        }
+    }
+    window.__robert_consent = elements;
+    return buttons;
+}"#;
+
+/// Clicks a button listed by [`FIND_SCRIPT`]
+const CLICK_SCRIPT: &str = r#"(index) => {
+    const el = (window.__robert_consent || [])[index];
+    delete window.__robert_consent;
+    if (el) el.click();
+    return !!el;
+}"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button(text: &str) -> BannerButton {
+        BannerButton {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_button_choice() {
+        assert_eq!(
+            button_choice(&button("Reject All")),
+            Some(ConsentChoice::Reject)
+        );
+        assert_eq!(
+            button_choice(&button(" Alle  ablehnen ")),
+            Some(ConsentChoice::Reject)
+        );
+        assert_eq!(
+            button_choice(&button("Accept all cookies")),
+            Some(ConsentChoice::Accept)
+        );
+        assert_eq!(
+            button_choice(&button("Tout accepter")),
+            Some(ConsentChoice::Accept)
+        );
+        assert_eq!(button_choice(&button("OK!")), Some(ConsentChoice::Accept));
+        assert_eq!(button_choice(&button("Cookie settings")), None);
+        assert_eq!(button_choice(&button("Accept the terms of service")), None);
+    }
+
+    #[test]
+    fn test_choose() {
+        let buttons = [
+            button("Manage preferences"),
+            button("Accept all"),
+            button("Reject all"),
+            BannerButton {
+                text: "Deny".to_string(),
+                manager: Some("Usercentrics".to_string()),
+                choice: Some(ConsentChoice::Reject),
+            },
+        ];
+        let reject = CookieConsent::default();
+        assert_eq!(choose(&buttons, &reject), Some((3, ConsentChoice::Reject)));
+        let accept = CookieConsent {
+            prefer: ConsentChoice::Accept,
+            ..Default::default()
+        };
+        assert_eq!(choose(&buttons, &accept), Some((1, ConsentChoice::Accept)));
+
+        // Without a reject button, accept only if allowed
+        let buttons = [button("Settings"), button("Got it")];
+        assert_eq!(choose(&buttons, &reject), Some((1, ConsentChoice::Accept)));
+        let strict = CookieConsent {
+            accept_if_no_reject: false,
+            ..Default::default()
+        };
+        assert_eq!(choose(&buttons, &strict), None);
+        assert_eq!(choose(&[], &reject), None);
+    }
+}
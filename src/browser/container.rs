@@ -0,0 +1,86 @@
+//! Running Chrome in Containers
+//!
+//! Chrome in Docker needs a few flags it doesn't need on a desktop: no sandbox
+//! (containers usually lack the namespaces it uses), no GPU, and shared memory in
+//! `/tmp` rather than `/dev/shm`, which Docker limits to 64 MiB by default. A
+//! `/dev/shm` that small is the usual cause of tabs crashing in containers, so
+//! [`ConnectionMode::Container`](super::chrome::ConnectionMode::Container) checks it
+//! at launch and adds a fix to the errors of pages that crash.
+
+/// Launch flags for Chrome in a container
+pub const CONTAINER_ARGS: &[&str] = &["--no-sandbox", "--disable-dev-shm-usage", "--disable-gpu"];
+
+/// Smallest `/dev/shm` Chrome reliably runs with
+pub const MIN_SHM_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Size limit of `/dev/shm`, or None if it isn't a mount with an explicit size
+pub fn shm_size() -> Option<u64> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    parse_shm_size(&mounts)
+}
+
+/// What to do about a `/dev/shm` smaller than [`MIN_SHM_BYTES`], if it is
+pub fn shm_hint() -> Option<String> {
+    shm_size().and_then(hint_for)
+}
+
+/// What to do about a `/dev/shm` of `size` bytes, if it's too small
+pub(crate) fn hint_for(size: u64) -> Option<String> {
+    (size < MIN_SHM_BYTES).then(|| {
+        format!(
+            "/dev/shm is only {} MiB, which crashes Chrome tabs; give the container more \
+             shared memory (docker run --shm-size=2g, or shm_size: 2gb in Compose)",
+            size / (1024 * 1024)
+        )
+    })
+}
+
+/// The `size=` option of the `/dev/shm` mount in `/proc/self/mounts` content
+fn parse_shm_size(mounts: &str) -> Option<u64> {
+    let options = mounts.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let mount_point = fields.nth(1)?;
+        (mount_point == "/dev/shm").then(|| fields.nth(1))?
+    })?;
+    let size = options
+        .split(',')
+        .find_map(|option| option.strip_prefix("size="))?;
+
+    let unit_start = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (digits, unit) = size.split_at(unit_start);
+    let multiplier = match unit.to_ascii_lowercase().as_str() {
+        "" => 1,
+        "k" => 1024,
+        "m" => 1024 * 1024,
+        "g" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    digits.parse::<u64>().ok().map(|n| n * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shm_size() {
+        let docker = "overlay / overlay rw,relatime 0 0\n\
+                      shm /dev/shm tmpfs rw,nosuid,nodev,noexec,relatime,size=65536k 0 0\n";
+        assert_eq!(parse_shm_size(docker), Some(64 * 1024 * 1024));
+        assert!(hint_for(64 * 1024 * 1024).unwrap().contains("only 64 MiB"));
+        assert_eq!(hint_for(2 * 1024 * 1024 * 1024), None);
+
+        let host = "tmpfs /dev/shm tmpfs rw,nosuid,nodev,inode64 0 0\n";
+        assert_eq!(parse_shm_size(host), None);
+        assert_eq!(
+            parse_shm_size("shm /dev/shm tmpfs rw,size=2g 0 0"),
+            Some(2 * 1024 * 1024 * 1024)
+        );
+        assert_eq!(
+            parse_shm_size("shm /dev/shm tmpfs rw,size=1048576 0 0"),
+            Some(1024 * 1024)
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! Page Crash Detection
+//!
+//! A renderer that crashes or runs out of memory otherwise surfaces as an opaque
+//! "oneshot canceled" or a load timeout. [`CrashMonitor`] listens for
+//! `Inspector.targetCrashed` on a page (and the driver feeds it the browser-level
+//! `Target.targetCrashed`, which carries the termination status, e.g. `oom`), while
+//! keeping the last few console and network events and a screenshot of the last
+//! loaded page, so a crash becomes a [`BrowserError::TargetCrashed`] with a
+//! [`CrashReport`] explaining what the page was doing.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::cdp::browser_protocol::inspector;
+use chromiumoxide::cdp::browser_protocol::network;
+use chromiumoxide::cdp::browser_protocol::page::{CaptureScreenshotFormat, EventLoadEventFired};
+use chromiumoxide::cdp::js_protocol::runtime;
+use chromiumoxide::page::{Page, ScreenshotParams};
+use futures::stream::{BoxStream, StreamExt};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Number of recent events kept per page by default
+pub const DEFAULT_CRASH_HISTORY: usize = 50;
+
+/// Events included in the error message (the full list is in the report)
+const SUMMARY_EVENTS: usize = 3;
+
+/// Quality of the JPEG snapshot taken after each page load
+const SNAPSHOT_QUALITY: i64 = 40;
+
+/// Kind of event recorded before a crash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageEventKind {
+    /// `console.*` call
+    Console,
+    /// Uncaught exception
+    Exception,
+    /// Request sent
+    Request,
+    /// Response received
+    Response,
+    /// Request failed (network error, blocked, cancelled)
+    RequestFailed,
+}
+
+/// A console or network event recorded before a crash
+#[derive(Debug, Clone, Serialize)]
+pub struct PageEvent {
+    /// Kind of event
+    pub kind: PageEventKind,
+
+    /// One-line description (e.g. `error: Uncaught RangeError`, `GET https://...`)
+    pub message: String,
+
+    /// ISO 8601 timestamp when the event was received
+    pub timestamp: String,
+}
+
+impl PageEvent {
+    fn new(kind: PageEventKind, message: String) -> Self {
+        Self {
+            kind,
+            message,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// What is known about a crashed page
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReport {
+    /// URL of the last page load
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Termination status from Chrome (e.g. `crashed`, `oom`, `killed`), if reported
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+
+    /// Termination error code, if reported
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<i64>,
+
+    /// Most recent console and network events, oldest first
+    pub recent_events: Vec<PageEvent>,
+
+    /// JPEG screenshot taken after the last page load, if one succeeded
+    #[serde(skip)]
+    pub screenshot: Option<Vec<u8>>,
+
+    /// Likely cause and fix, if the driver knows one (e.g. a small `/dev/shm`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl CrashReport {
+    /// Whether Chrome reported the renderer ran out of memory
+    pub fn is_oom(&self) -> bool {
+        self.status.as_deref() == Some("oom")
+    }
+}
+
+impl std::fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.status {
+            Some(_) if self.is_oom() => f.write_str("renderer ran out of memory")?,
+            Some(status) => write!(f, "renderer terminated ({})", status)?,
+            None => f.write_str("renderer crashed")?,
+        }
+        if let Some(url) = &self.url {
+            write!(f, " at {}", url)?;
+        }
+        let start = self.recent_events.len().saturating_sub(SUMMARY_EVENTS);
+        let last: Vec<_> = self.recent_events[start..]
+            .iter()
+            .map(|e| e.message.as_str())
+            .collect();
+        if !last.is_empty() {
+            write!(f, "; last events: {}", last.join(" | "))?;
+        }
+        if let Some(hint) = &self.hint {
+            write!(f, "; {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    events: VecDeque<PageEvent>,
+    url: Option<String>,
+    screenshot: Option<Vec<u8>>,
+    status: Option<String>,
+    error_code: Option<i64>,
+}
+
+enum Signal {
+    Event(PageEvent),
+    Loaded,
+    Crashed,
+}
+
+/// Watches one page for crashes while recording its recent activity
+///
+/// Listening stops when the monitor is dropped.
+pub struct CrashMonitor {
+    state: Arc<Mutex<MonitorState>>,
+    crashed: CancellationToken,
+    task: JoinHandle<()>,
+    hint: Option<String>,
+}
+
+impl CrashMonitor {
+    /// Start watching `page`, keeping the last `history` console/network events
+    pub async fn attach(page: &Page, history: usize) -> Result<Self> {
+        page.execute(inspector::EnableParams::default()).await?;
+        page.execute(network::EnableParams::default()).await?;
+        page.execute(runtime::EnableParams::default()).await?;
+
+        let streams: Vec<BoxStream<'static, Signal>> = vec![
+            page.event_listener::<inspector::EventTargetCrashed>()
+                .await?
+                .map(|_| Signal::Crashed)
+                .boxed(),
+            page.event_listener::<EventLoadEventFired>()
+                .await?
+                .map(|_| Signal::Loaded)
+                .boxed(),
+            page.event_listener::<runtime::EventConsoleApiCalled>()
+                .await?
+                .map(|e| Signal::Event(console_event(&e)))
+                .boxed(),
+            page.event_listener::<runtime::EventExceptionThrown>()
+                .await?
+                .map(|e| Signal::Event(exception_event(&e)))
+                .boxed(),
+            page.event_listener::<network::EventRequestWillBeSent>()
+                .await?
+                .map(|e| {
+                    Signal::Event(PageEvent::new(
+                        PageEventKind::Request,
+                        format!("{} {}", e.request.method, e.request.url),
+                    ))
+                })
+                .boxed(),
+            page.event_listener::<network::EventResponseReceived>()
+                .await?
+                .map(|e| {
+                    Signal::Event(PageEvent::new(
+                        PageEventKind::Response,
+                        format!("{} {}", e.response.status, e.response.url),
+                    ))
+                })
+                .boxed(),
+            page.event_listener::<network::EventLoadingFailed>()
+                .await?
+                .map(|e| {
+                    Signal::Event(PageEvent::new(
+                        PageEventKind::RequestFailed,
+                        format!("{} (request {})", e.error_text, e.request_id.as_ref()),
+                    ))
+                })
+                .boxed(),
+        ];
+
+        let state = Arc::new(Mutex::new(MonitorState::default()));
+        let crashed = CancellationToken::new();
+        let task = tokio::spawn(watch(
+            page.clone(),
+            futures::stream::select_all(streams),
+            state.clone(),
+            crashed.clone(),
+            history,
+        ));
+
+        Ok(Self {
+            state,
+            crashed,
+            task,
+            hint: None,
+        })
+    }
+
+    /// Add `hint` (a likely cause and fix) to crash reports
+    pub(crate) fn with_hint(mut self, hint: Option<String>) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    /// Whether the page has crashed
+    pub fn is_crashed(&self) -> bool {
+        self.crashed.is_cancelled()
+    }
+
+    /// Resolves once the page crashes
+    pub async fn crashed(&self) {
+        self.crashed.cancelled().await
+    }
+
+    /// Most recent console and network events, oldest first
+    pub fn recent_events(&self) -> Vec<PageEvent> {
+        self.state.lock().unwrap().events.iter().cloned().collect()
+    }
+
+    /// Crash details, or None if the page hasn't crashed
+    pub fn report(&self) -> Option<CrashReport> {
+        if !self.is_crashed() {
+            return None;
+        }
+        let state = self.state.lock().unwrap();
+        Some(CrashReport {
+            url: state.url.clone(),
+            status: state.status.clone(),
+            error_code: state.error_code,
+            recent_events: state.events.iter().cloned().collect(),
+            screenshot: state.screenshot.clone(),
+            hint: self.hint.clone(),
+        })
+    }
+
+    /// `BrowserError::TargetCrashed` if the page crashes within `grace`
+    ///
+    /// Crash events can arrive just after the command that failed because of them.
+    pub async fn crash_error(&self, grace: Duration) -> Option<BrowserError> {
+        let _ = tokio::time::timeout(grace, self.crashed()).await;
+        self.report()
+            .map(|report| BrowserError::TargetCrashed(Box::new(report)))
+    }
+
+    /// Record a browser-level `Target.targetCrashed` for this page
+    pub(crate) fn mark_crashed(&self, status: String, error_code: i64) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.status = Some(status);
+            state.error_code = Some(error_code);
+        }
+        self.crashed.cancel();
+    }
+}
+
+impl Drop for CrashMonitor {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn watch(
+    page: Page,
+    mut signals: futures::stream::SelectAll<BoxStream<'static, Signal>>,
+    state: Arc<Mutex<MonitorState>>,
+    crashed: CancellationToken,
+    history: usize,
+) {
+    while let Some(signal) = signals.next().await {
+        match signal {
+            Signal::Event(event) => {
+                let mut state = state.lock().unwrap();
+                if state.events.len() >= history {
+                    state.events.pop_front();
+                }
+                if history > 0 {
+                    state.events.push_back(event);
+                }
+            }
+            Signal::Loaded => {
+                let url = page.url().await.ok().flatten();
+                let screenshot = page
+                    .screenshot(
+                        ScreenshotParams::builder()
+                            .format(CaptureScreenshotFormat::Jpeg)
+                            .quality(SNAPSHOT_QUALITY)
+                            .build(),
+                    )
+                    .await
+                    .ok();
+                let mut state = state.lock().unwrap();
+                state.url = url.or(state.url.take());
+                if screenshot.is_some() {
+                    state.screenshot = screenshot;
+                }
+            }
+            Signal::Crashed => {
+                tracing::error!(target_id = ?page.target_id(), "Page crashed");
+                crashed.cancel();
+            }
+        }
+    }
+}
+
+fn console_event(event: &runtime::EventConsoleApiCalled) -> PageEvent {
+    let args: Vec<String> = event.args.iter().map(describe).collect();
+    PageEvent::new(
+        PageEventKind::Console,
+        format!("{}: {}", event.r#type.as_ref(), args.join(" ")),
+    )
+}
+
+fn exception_event(event: &runtime::EventExceptionThrown) -> PageEvent {
+    let details = &event.exception_details;
+    let message = details
+        .exception
+        .as_ref()
+        .and_then(|e| e.description.clone())
+        .unwrap_or_else(|| details.text.clone());
+    PageEvent::new(
+        PageEventKind::Exception,
+        message.lines().next().unwrap_or_default().to_string(),
+    )
+}
+
+/// Short text for a console argument
+fn describe(object: &runtime::RemoteObject) -> String {
+    match &object.value {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+        None => object
+            .description
+            .clone()
+            .unwrap_or_else(|| object.r#type.as_ref().to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_display() {
+        let mut report = CrashReport {
+            url: Some("https://example.com/big".to_string()),
+            status: Some("oom".to_string()),
+            error_code: Some(-1),
+            recent_events: (1..=5)
+                .map(|i| PageEvent::new(PageEventKind::Console, format!("log: chunk {}", i)))
+                .collect(),
+            screenshot: None,
+            hint: None,
+        };
+        assert!(report.is_oom());
+        assert_eq!(
+            report.to_string(),
+            "renderer ran out of memory at https://example.com/big; \
+            last events: log: chunk 3 | log: chunk 4 | log: chunk 5"
+        );
+
+        report.status = None;
+        report.recent_events.clear();
+        assert!(!report.is_oom());
+        assert_eq!(
+            report.to_string(),
+            "renderer crashed at https://example.com/big"
+        );
+    }
+}
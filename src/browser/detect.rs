@@ -0,0 +1,261 @@
+//! Installed Browser Discovery
+//!
+//! Finds Chromium-based browsers installed on the system: Chrome, Chromium, Edge,
+//! and Brave. Linux looks in `PATH` and the vendors' `/opt` directories, macOS in
+//! the app bundles under `/Applications` and `~/Applications`, and Windows in the
+//! registry's App Paths and the Program Files and local app data directories.
+//!
+//! The driver falls back to the first browser found when Chrome for Testing can't
+//! be downloaded.
+
+pub use crate::model::browser::BrowserKind;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// A browser found on the system
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetectedBrowser {
+    pub kind: BrowserKind,
+
+    /// Path to the browser executable
+    pub executable: PathBuf,
+
+    /// Full version (e.g. `128.0.6613.119`), if it could be determined
+    pub version: Option<String>,
+}
+
+/// Executable names looked up in `PATH` on Linux
+const LINUX_NAMES: &[(BrowserKind, &str)] = &[
+    (BrowserKind::Chrome, "google-chrome"),
+    (BrowserKind::Chrome, "google-chrome-stable"),
+    (BrowserKind::Chromium, "chromium"),
+    (BrowserKind::Chromium, "chromium-browser"),
+    (BrowserKind::Edge, "microsoft-edge"),
+    (BrowserKind::Edge, "microsoft-edge-stable"),
+    (BrowserKind::Brave, "brave-browser"),
+];
+
+/// Install locations outside `PATH` on Linux
+const LINUX_PATHS: &[(BrowserKind, &str)] = &[
+    (BrowserKind::Chrome, "/opt/google/chrome/chrome"),
+    (BrowserKind::Chromium, "/snap/bin/chromium"),
+    (BrowserKind::Edge, "/opt/microsoft/msedge/msedge"),
+    (BrowserKind::Brave, "/opt/brave.com/brave/brave"),
+];
+
+/// Executables inside `Applications` directories on macOS
+const MACOS_BUNDLES: &[(BrowserKind, &str)] = &[
+    (
+        BrowserKind::Chrome,
+        "Google Chrome.app/Contents/MacOS/Google Chrome",
+    ),
+    (
+        BrowserKind::Chromium,
+        "Chromium.app/Contents/MacOS/Chromium",
+    ),
+    (
+        BrowserKind::Edge,
+        "Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+    ),
+    (
+        BrowserKind::Brave,
+        "Brave Browser.app/Contents/MacOS/Brave Browser",
+    ),
+];
+
+/// Executables under Program Files and local app data on Windows
+const WINDOWS_INSTALLS: &[(BrowserKind, &str)] = &[
+    (BrowserKind::Chrome, r"Google\Chrome\Application\chrome.exe"),
+    (BrowserKind::Chromium, r"Chromium\Application\chrome.exe"),
+    (BrowserKind::Edge, r"Microsoft\Edge\Application\msedge.exe"),
+    (
+        BrowserKind::Brave,
+        r"BraveSoftware\Brave-Browser\Application\brave.exe",
+    ),
+];
+
+/// App Paths registry entries on Windows
+const WINDOWS_APP_PATHS: &[(BrowserKind, &str)] = &[
+    (BrowserKind::Chrome, "chrome.exe"),
+    (BrowserKind::Edge, "msedge.exe"),
+    (BrowserKind::Brave, "brave.exe"),
+];
+
+/// Extra launch flags a browser needs for automation, on top of chromiumoxide's defaults
+///
+/// Edge and Brave would otherwise ask to become the default browser, and Brave would
+/// start its own updater.
+pub fn launch_args(kind: BrowserKind) -> &'static [&'static str] {
+    match kind {
+        BrowserKind::Chrome | BrowserKind::Chromium => &[],
+        BrowserKind::Edge => &["--no-default-browser-check"],
+        BrowserKind::Brave => &["--no-default-browser-check", "--disable-brave-update"],
+    }
+}
+
+/// Find the Chromium-based browsers installed on this system, Chrome first
+///
+/// Versions are read by running each browser with `--version` (on Windows, from
+/// the version directory next to the executable), so this blocks for a moment; call
+/// it from `spawn_blocking` in async code.
+pub fn detect_browsers() -> Vec<DetectedBrowser> {
+    let mut seen = HashSet::new();
+    let mut found: Vec<DetectedBrowser> = candidates()
+        .into_iter()
+        .filter(|(_, path)| path.is_file())
+        // Linux launchers are often symlinks to the same install
+        .filter(|(_, path)| seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())))
+        .map(|(kind, executable)| DetectedBrowser {
+            kind,
+            version: browser_version(&executable),
+            executable,
+        })
+        .collect();
+    found.sort_by_key(|browser| browser.kind as u8);
+    found
+}
+
+/// Possible browser executables for this platform, which may not exist
+fn candidates() -> Vec<(BrowserKind, PathBuf)> {
+    if cfg!(target_os = "windows") {
+        let mut paths = windows_registry_candidates();
+        let bases = ["ProgramFiles", "ProgramFiles(x86)", "LOCALAPPDATA"]
+            .iter()
+            .filter_map(|key| std::env::var_os(key).map(PathBuf::from));
+        for base in bases {
+            paths.extend(
+                WINDOWS_INSTALLS
+                    .iter()
+                    .map(|(kind, path)| (*kind, base.join(path))),
+            );
+        }
+        paths
+    } else if cfg!(target_os = "macos") {
+        let mut dirs = vec![PathBuf::from("/Applications")];
+        dirs.extend(dirs::home_dir().map(|home| home.join("Applications")));
+        dirs.iter()
+            .flat_map(|dir| {
+                MACOS_BUNDLES
+                    .iter()
+                    .map(move |(kind, path)| (*kind, dir.join(path)))
+            })
+            .collect()
+    } else {
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let dirs: Vec<PathBuf> = std::env::split_paths(&path).collect();
+        LINUX_NAMES
+            .iter()
+            .flat_map(|(kind, name)| dirs.iter().map(move |dir| (*kind, dir.join(name))))
+            .chain(
+                LINUX_PATHS
+                    .iter()
+                    .map(|(kind, path)| (*kind, PathBuf::from(path))),
+            )
+            .collect()
+    }
+}
+
+/// Executables registered under App Paths, for the machine and the current user
+fn windows_registry_candidates() -> Vec<(BrowserKind, PathBuf)> {
+    let mut paths = Vec::new();
+    for hive in ["HKLM", "HKCU"] {
+        for (kind, exe) in WINDOWS_APP_PATHS {
+            let key = format!(
+                r"{}\SOFTWARE\Microsoft\Windows\CurrentVersion\App Paths\{}",
+                hive, exe
+            );
+            let output = Command::new("reg")
+                .args(["query", &key, "/ve"])
+                .stderr(Stdio::null())
+                .output();
+            if let Some(path) = output
+                .ok()
+                .filter(|output| output.status.success())
+                .and_then(|output| parse_reg_default(&String::from_utf8_lossy(&output.stdout)))
+            {
+                paths.push((*kind, path));
+            }
+        }
+    }
+    paths
+}
+
+/// The default value in `reg query <key> /ve` output
+fn parse_reg_default(output: &str) -> Option<PathBuf> {
+    output.lines().find_map(|line| {
+        let (_, value) = line.split_once("REG_SZ")?;
+        let value = value.trim().trim_matches('"');
+        (!value.is_empty()).then(|| PathBuf::from(value))
+    })
+}
+
+/// Version of the browser at `executable`
+fn browser_version(executable: &Path) -> Option<String> {
+    if cfg!(target_os = "windows") {
+        // Windows builds don't print a version; installs keep one directory per version
+        let dir = executable.parent()?;
+        return std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| parse_version(name).is_some())
+            .max_by_key(|name| version_key(name));
+    }
+    let output = Command::new(executable)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    parse_version(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The first dotted version number in `text` (`Google Chrome 128.0.6613.119` → `128.0.6613.119`)
+fn parse_version(text: &str) -> Option<String> {
+    text.split_whitespace()
+        .find(|word| word.contains('.') && word.split('.').all(|part| part.parse::<u32>().is_ok()))
+        .map(str::to_string)
+}
+
+fn version_key(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("Google Chrome 128.0.6613.119 \n").as_deref(),
+            Some("128.0.6613.119")
+        );
+        assert_eq!(
+            parse_version("Chromium 127.0.6533.88 snap").as_deref(),
+            Some("127.0.6533.88")
+        );
+        assert_eq!(
+            parse_version("Brave Browser 127.1.68.137").as_deref(),
+            Some("127.1.68.137")
+        );
+        assert_eq!(parse_version("no version here"), None);
+        assert!(version_key("128.0.10.1") > version_key("128.0.9.1"));
+    }
+
+    #[test]
+    fn test_parse_reg_default() {
+        let output = "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\App Paths\\chrome.exe\r\n    (Default)    REG_SZ    C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe\r\n\r\n";
+        assert_eq!(
+            parse_reg_default(output),
+            Some(PathBuf::from(
+                r"C:\Program Files\Google\Chrome\Application\chrome.exe"
+            ))
+        );
+        assert_eq!(parse_reg_default("ERROR: not found"), None);
+    }
+}
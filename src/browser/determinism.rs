@@ -0,0 +1,163 @@
+//! Deterministic Rendering
+//!
+//! Screenshots of the same page differ between runs because of animations,
+//! blinking carets, font rasterization, display scaling, the current time, and
+//! `Math.random`. [`DeterministicRendering`] removes those sources so screenshot
+//! hashes stay stable for visual regression checks:
+//!
+//! - launch flags turn off smooth scrolling, threaded animation, LCD text and
+//!   subpixel font positioning, and fix the device scale factor and color profile
+//! - an init script, run before any page script, freezes `Date` at a fixed instant,
+//!   seeds `Math.random`, and zeroes CSS animation and transition durations
+//!
+//! Launch flags only apply when the driver launches Chrome; when connecting to a
+//! debug port only the per-page overrides (init script, device scale factor) apply.
+
+use crate::error::Result;
+use chromiumoxide::cdp::browser_protocol::emulation::SetDeviceMetricsOverrideParams;
+use chromiumoxide::cdp::browser_protocol::page::AddScriptToEvaluateOnNewDocumentParams;
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+
+/// Settings for the deterministic rendering preset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeterministicRendering {
+    /// Instant `Date` is frozen at, in milliseconds since the Unix epoch
+    pub frozen_time_ms: i64,
+
+    /// Seed for `Math.random`
+    pub random_seed: u32,
+
+    /// Device scale factor pages are rendered at
+    pub device_scale_factor: f64,
+}
+
+impl Default for DeterministicRendering {
+    fn default() -> Self {
+        Self {
+            // 2024-01-01T00:00:00Z
+            frozen_time_ms: 1_704_067_200_000,
+            random_seed: 42,
+            device_scale_factor: 1.0,
+        }
+    }
+}
+
+impl DeterministicRendering {
+    /// Chrome flags added at launch
+    pub(crate) fn launch_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = [
+            "--disable-smooth-scrolling",
+            "--disable-threaded-animation",
+            "--disable-threaded-scrolling",
+            "--disable-checker-imaging",
+            "--disable-partial-raster",
+            "--disable-lcd-text",
+            "--disable-font-subpixel-positioning",
+            "--font-render-hinting=none",
+            "--force-color-profile=srgb",
+            "--hide-scrollbars",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        args.push(format!(
+            "--force-device-scale-factor={}",
+            self.device_scale_factor
+        ));
+        args
+    }
+
+    /// Script run in every document before its own scripts
+    pub(crate) fn init_script(&self) -> String {
+        INIT_SCRIPT
+            .replace("__FROZEN_TIME__", &self.frozen_time_ms.to_string())
+            .replace("__RANDOM_SEED__", &self.random_seed.to_string())
+    }
+
+    /// Install the per-page overrides on `page` (future documents and the current one)
+    pub(crate) async fn apply(&self, page: &Page) -> Result<()> {
+        let script = self.init_script();
+        page.execute(AddScriptToEvaluateOnNewDocumentParams::new(script.clone()))
+            .await?;
+        page.execute(SetDeviceMetricsOverrideParams::new(
+            0,
+            0,
+            self.device_scale_factor,
+            false,
+        ))
+        .await?;
+        page.evaluate(script).await?;
+        Ok(())
+    }
+}
+
+/// Freezes time and randomness and disables animations; safe to run twice
+const INIT_SCRIPT: &str = r#"(() => {
+    if (window.__robertDeterministic) return;
+    Object.defineProperty(window, '__robertDeterministic', { value: true });
+
+    const frozen = __FROZEN_TIME__;
+    const RealDate = Date;
+    function FrozenDate(...args) {
+        if (!new.target) return new RealDate(frozen).toString();
+        return args.length ? new RealDate(...args) : new RealDate(frozen);
+    }
+    FrozenDate.prototype = RealDate.prototype;
+    FrozenDate.now = () => frozen;
+    FrozenDate.parse = RealDate.parse;
+    FrozenDate.UTC = RealDate.UTC;
+    window.Date = FrozenDate;
+
+    // mulberry32
+    let seed = __RANDOM_SEED__ >>> 0;
+    Math.random = () => {
+        seed = (seed + 0x6D2B79F5) >>> 0;
+        let t = seed;
+        t = Math.imul(t ^ (t >>> 15), t | 1);
+        t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+        return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+    };
+
+    const css = '*, *::before, *::after { animation-duration: 0s !important; animation-delay: 0s !important; '
+        + 'transition-duration: 0s !important; transition-delay: 0s !important; '
+        + 'scroll-behavior: auto !important; caret-color: transparent !important; }';
+    const install = () => {
+        const style = document.createElement('style');
+        style.id = 'robert-deterministic';
+        style.textContent = css;
+        (document.head || document.documentElement).appendChild(style);
+    };
+    if (document.documentElement) install();
+    else document.addEventListener('DOMContentLoaded', install);
+})()"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_script_substitutes_settings() {
+        let settings = DeterministicRendering {
+            frozen_time_ms: 1_000,
+            random_seed: 7,
+            ..Default::default()
+        };
+        let script = settings.init_script();
+        assert!(script.contains("const frozen = 1000;"));
+        assert!(script.contains("let seed = 7 >>> 0;"));
+        assert!(!script.contains("__FROZEN_TIME__") && !script.contains("__RANDOM_SEED__"));
+    }
+
+    #[test]
+    fn test_launch_args() {
+        let args = DeterministicRendering {
+            device_scale_factor: 2.0,
+            ..Default::default()
+        }
+        .launch_args();
+        assert!(args.contains(&"--force-device-scale-factor=2".to_string()));
+        assert!(args.contains(&"--disable-smooth-scrolling".to_string()));
+    }
+}
@@ -0,0 +1,135 @@
+//! Display Detection
+//!
+//! A headed Chrome needs a display server. On Linux without X11 or Wayland (CI
+//! runners, containers, SSH sessions) it fails to start with an error that doesn't
+//! mention the display, so the driver checks for one first. Without a display it
+//! launches headless with a warning, or, with `DriverOptions::xvfb`, starts Chrome
+//! on a virtual display under [Xvfb](https://www.x.org/releases/current/doc/man/man1/Xvfb.1.xhtml).
+
+use crate::error::{BrowserError, Result};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long Xvfb gets to create its display socket
+const XVFB_START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Display numbers tried for Xvfb, skipping low ones a desktop session might use
+const XVFB_DISPLAYS: std::ops::Range<u32> = 99..199;
+
+/// Whether a headed browser has a display to open its window on
+///
+/// Always true outside Linux and the BSDs, where windows don't need a display server.
+pub fn has_display() -> bool {
+    has_display_in(|key| std::env::var(key).ok())
+}
+
+/// [`has_display`] with environment variables looked up by `lookup`
+fn has_display_in<F>(lookup: F) -> bool
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        return true;
+    }
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|key| lookup(key).is_some_and(|value| !value.is_empty()))
+}
+
+/// An Xvfb virtual display, stopped when dropped
+#[derive(Debug)]
+pub struct Xvfb {
+    process: Child,
+    display: String,
+}
+
+impl Xvfb {
+    /// Start Xvfb on the first free display number
+    pub async fn start() -> Result<Self> {
+        let number = XVFB_DISPLAYS
+            .clone()
+            .find(|n| !lock_file(*n).exists() && !socket(*n).exists())
+            .ok_or_else(|| BrowserError::LaunchFailed("No free display for Xvfb".to_string()))?;
+        let display = format!(":{}", number);
+
+        let process = Command::new("Xvfb")
+            .args([
+                display.as_str(),
+                "-screen",
+                "0",
+                "1920x1080x24",
+                "-nolisten",
+                "tcp",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| BrowserError::LaunchFailed(format!("Failed to start Xvfb: {}", e)))?;
+        let mut xvfb = Self { process, display };
+
+        let deadline = Instant::now() + XVFB_START_TIMEOUT;
+        while !socket(number).exists() {
+            if let Ok(Some(status)) = xvfb.process.try_wait() {
+                return Err(BrowserError::LaunchFailed(format!(
+                    "Xvfb exited on display {} ({})",
+                    xvfb.display, status
+                )));
+            }
+            if Instant::now() >= deadline {
+                return Err(BrowserError::LaunchFailed(format!(
+                    "Xvfb didn't open display {} within {}s",
+                    xvfb.display,
+                    XVFB_START_TIMEOUT.as_secs()
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        tracing::info!(display = %xvfb.display, "Started Xvfb");
+        Ok(xvfb)
+    }
+
+    /// Value of `DISPLAY` for programs that should use this display (e.g. `:99`)
+    pub fn display(&self) -> &str {
+        &self.display
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+fn lock_file(display: u32) -> PathBuf {
+    PathBuf::from(format!("/tmp/.X{}-lock", display))
+}
+
+fn socket(display: u32) -> PathBuf {
+    PathBuf::from(format!("/tmp/.X11-unix/X{}", display))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_display() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |key: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        let desktop = cfg!(any(target_os = "macos", target_os = "windows"));
+
+        assert!(has_display_in(env(&[("DISPLAY", ":0")])));
+        assert!(has_display_in(env(&[("WAYLAND_DISPLAY", "wayland-0")])));
+        assert_eq!(has_display_in(env(&[])), desktop);
+        assert_eq!(has_display_in(env(&[("DISPLAY", "")])), desktop);
+    }
+}
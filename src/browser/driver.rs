@@ -0,0 +1,59 @@
+//! Driver Trait
+//!
+//! The core browser operations (navigate, read the page, run JavaScript, take a
+//! screenshot) as a trait, so code that only needs those can take a `&dyn Driver`
+//! and be unit-tested against [`MockDriver`](crate::testing::MockDriver) instead of
+//! launching Chrome.
+
+use super::chrome::ChromeDriver;
+use super::navigation::NavigationResult;
+use crate::error::Result;
+use futures::future::BoxFuture;
+use serde_json::Value;
+
+/// A browser that can be driven one page at a time
+pub trait Driver: Send + Sync {
+    /// Navigate to `url`, returning the main document's HTTP status
+    fn navigate<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<NavigationResult>>;
+
+    /// URL of the current page
+    fn current_url(&self) -> BoxFuture<'_, Result<String>>;
+
+    /// Title of the current page
+    fn title(&self) -> BoxFuture<'_, Result<String>>;
+
+    /// HTML of the current page
+    fn get_page_source(&self) -> BoxFuture<'_, Result<String>>;
+
+    /// Evaluate `script` in the current page, returning its value as JSON
+    fn execute_script<'a>(&'a self, script: &'a str) -> BoxFuture<'a, Result<Value>>;
+
+    /// PNG screenshot of the current page
+    fn screenshot(&self) -> BoxFuture<'_, Result<Vec<u8>>>;
+}
+
+impl Driver for ChromeDriver {
+    fn navigate<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<NavigationResult>> {
+        Box::pin(ChromeDriver::navigate(self, url))
+    }
+
+    fn current_url(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(ChromeDriver::current_url(self))
+    }
+
+    fn title(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(ChromeDriver::title(self))
+    }
+
+    fn get_page_source(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(ChromeDriver::get_page_source(self))
+    }
+
+    fn execute_script<'a>(&'a self, script: &'a str) -> BoxFuture<'a, Result<Value>> {
+        Box::pin(ChromeDriver::execute_script(self, script))
+    }
+
+    fn screenshot(&self) -> BoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(ChromeDriver::screenshot(self))
+    }
+}
@@ -0,0 +1,169 @@
+//! Page Emulation
+//!
+//! Overrides that change how a page is styled without interacting with it, so
+//! automated captures can show states a headless browser never reaches on its own.
+//! Forced pseudo-classes ([`PseudoState`]) make an element render as hovered,
+//! focused, or pressed in screenshots and VisualDom snapshots, without moving the
+//! mouse or changing focus. Emulated media ([`emulate_media`]) switches the page to
+//! dark mode, reduced motion, or its print stylesheet.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::cdp::browser_protocol::emulation::{MediaFeature, SetEmulatedMediaParams};
+use chromiumoxide::cdp::browser_protocol::{css, dom};
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+
+/// A CSS pseudo-class that can be forced on an element
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PseudoState {
+    /// `:hover`
+    Hover,
+    /// `:focus`
+    Focus,
+    /// `:focus-within`
+    FocusWithin,
+    /// `:focus-visible`
+    FocusVisible,
+    /// `:active`
+    Active,
+    /// `:visited`
+    Visited,
+    /// `:target`
+    Target,
+}
+
+impl PseudoState {
+    /// Pseudo-class name without the colon, as CDP expects it
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PseudoState::Hover => "hover",
+            PseudoState::Focus => "focus",
+            PseudoState::FocusWithin => "focus-within",
+            PseudoState::FocusVisible => "focus-visible",
+            PseudoState::Active => "active",
+            PseudoState::Visited => "visited",
+            PseudoState::Target => "target",
+        }
+    }
+}
+
+impl std::fmt::Display for PseudoState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, ":{}", self.as_str())
+    }
+}
+
+/// Style the first element matching `selector` as if `states` applied
+///
+/// Replaces the states previously forced on that element. Forced states last until
+/// [`clear_forced_states`] or the next navigation.
+pub async fn force_pseudo_state(page: &Page, selector: &str, states: &[PseudoState]) -> Result<()> {
+    if states.is_empty() {
+        return Err(BrowserError::Other(
+            "No pseudo states to force; use clear_forced_states to remove them".to_string(),
+        ));
+    }
+
+    // The CSS domain only accepts forced states while it is enabled, which needs DOM
+    page.execute(dom::EnableParams::default()).await?;
+    page.execute(css::EnableParams::default()).await?;
+
+    let element = page
+        .find_element(selector)
+        .await
+        .map_err(|_| BrowserError::ElementNotFound(selector.to_string()))?;
+    page.execute(css::ForcePseudoStateParams::new(
+        element.node_id,
+        states.iter().map(|s| s.as_str().to_string()).collect(),
+    ))
+    .await
+    .map_err(|e| BrowserError::Other(format!("Failed to force pseudo state: {}", e)))?;
+
+    tracing::debug!(selector, ?states, "Forced pseudo state");
+    Ok(())
+}
+
+/// Remove all forced pseudo states on the page
+pub async fn clear_forced_states(page: &Page) -> Result<()> {
+    // Disabling the CSS domain drops every forced state and restyles the page
+    page.execute(css::DisableParams::default()).await?;
+    Ok(())
+}
+
+/// Emulate `prefers-color-scheme`, `prefers-reduced-motion`, and the `print` media type
+///
+/// Each flag is applied explicitly (e.g. `dark_mode: false` emulates a light color
+/// scheme). Lasts until [`clear_emulated_media`], including across navigations.
+pub async fn emulate_media(
+    page: &Page,
+    dark_mode: bool,
+    reduced_motion: bool,
+    print: bool,
+) -> Result<()> {
+    page.execute(media_params(dark_mode, reduced_motion, print))
+        .await
+        .map_err(|e| BrowserError::Other(format!("Failed to emulate media: {}", e)))?;
+    tracing::debug!(dark_mode, reduced_motion, print, "Emulating media");
+    Ok(())
+}
+
+/// Go back to the browser's own media type and features
+pub async fn clear_emulated_media(page: &Page) -> Result<()> {
+    page.execute(SetEmulatedMediaParams {
+        media: Some(String::new()),
+        features: Some(Vec::new()),
+    })
+    .await?;
+    Ok(())
+}
+
+fn media_params(dark_mode: bool, reduced_motion: bool, print: bool) -> SetEmulatedMediaParams {
+    SetEmulatedMediaParams {
+        // An empty media type leaves it unchanged (screen)
+        media: Some(if print { "print" } else { "" }.to_string()),
+        features: Some(vec![
+            MediaFeature::new(
+                "prefers-color-scheme",
+                if dark_mode { "dark" } else { "light" },
+            ),
+            MediaFeature::new(
+                "prefers-reduced-motion",
+                if reduced_motion {
+                    "reduce"
+                } else {
+                    "no-preference"
+                },
+            ),
+        ]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pseudo_state_names() {
+        assert_eq!(PseudoState::FocusWithin.as_str(), "focus-within");
+        assert_eq!(PseudoState::Hover.to_string(), ":hover");
+        let state: PseudoState = serde_json::from_str("\"focus-visible\"").unwrap();
+        assert_eq!(state, PseudoState::FocusVisible);
+    }
+
+    #[test]
+    fn test_media_params() {
+        let params = serde_json::to_value(media_params(true, false, true)).unwrap();
+        assert_eq!(
+            params,
+            serde_json::json!({
+                "media": "print",
+                "features": [
+                    {"name": "prefers-color-scheme", "value": "dark"},
+                    {"name": "prefers-reduced-motion", "value": "no-preference"},
+                ],
+            })
+        );
+        assert_eq!(media_params(false, true, false).media.as_deref(), Some(""));
+    }
+}
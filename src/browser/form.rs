@@ -0,0 +1,815 @@
+//! Form Autofill
+//!
+//! Fills a form from field-name → value mappings without knowing its selectors.
+//! Each name is matched against the page's enabled inputs, textareas, and selects
+//! by, in order: `name` attribute, `id`, label text (`<label>`, `aria-labelledby`,
+//! or a radio group's `<legend>`), `aria-label`, and `placeholder`. Label and
+//! placeholder matches ignore case, extra whitespace, and a trailing `*` or `:`.
+//!
+//! Values are entered the way a user would enter them, so frameworks that listen
+//! for input see the change: text is typed with `Input.insertText` (replacing any
+//! existing text, through the [widget strategy](super::widgets) that handles the
+//! field, if any), checkboxes and radios are clicked, and selects and date/range/
+//! color inputs get their value set through the native setter followed by `input`
+//! and `change` events.
+//!
+//! [`infer_form_schema`] goes the other way: it describes a form's fields (types,
+//! labels, required flags, constraints, options) and the key [`fill_form`] matches
+//! each by, so an agent can plan the values before filling, and
+//! [`FormSchema::check`] catches values the form would reject without a browser.
+//!
+//! ```no_run
+//! use robert_webdriver::browser::form::FormValue;
+//! # async fn example(driver: &robert_webdriver::ChromeDriver) -> robert_webdriver::error::Result<()> {
+//! let result = driver
+//!     .fill_form([
+//!         ("Email", FormValue::from("alice@example.com")),
+//!         ("country", FormValue::from("Canada")),
+//!         ("Subscribe to newsletter", FormValue::from(true)),
+//!     ])
+//!     .await?;
+//! assert!(result.unmatched.is_empty());
+//! # Ok(())
+//! # }
+//! ```
+
+use super::widgets::WidgetRegistry;
+use crate::error::{BrowserError, Result};
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Attribute marking matched elements while a form is filled
+const MARKER: &str = "data-robert-fill";
+
+/// Finds the controls for each field name and marks them with [`MARKER`]
+const LOCATE_SCRIPT: &str = r#"(keys) => {
+    const norm = (s) => (s || '').replace(/\s+/g, ' ').trim().replace(/\s*[*:]$/, '').toLowerCase();
+    const skipped = ['hidden', 'submit', 'button', 'reset', 'image', 'file'];
+    const controls = Array.from(document.querySelectorAll('input, textarea, select'))
+        .filter((el) => !el.disabled && !skipped.includes(el.type));
+    // Text of a label without the options of a select (or text of a textarea) it wraps
+    const text = (label) => {
+        const copy = label.cloneNode(true);
+        copy.querySelectorAll('select, textarea').forEach((c) => c.remove());
+        return norm(copy.textContent);
+    };
+    const labels = (el) => {
+        const texts = Array.from(el.labels || [], text);
+        const ids = el.getAttribute('aria-labelledby');
+        if (ids) {
+            texts.push(norm(ids.split(/\s+/)
+                .map((id) => (document.getElementById(id) || {}).textContent || '').join(' ')));
+        }
+        return texts;
+    };
+    const legend = (el) => {
+        const fieldset = el.closest('fieldset');
+        const element = fieldset && fieldset.querySelector('legend');
+        return element ? norm(element.textContent) : null;
+    };
+    const matchers = [
+        (el, k) => el.name === k,
+        (el, k) => el.id === k,
+        (el, k) => labels(el).includes(norm(k)),
+        (el, k) => (el.type === 'radio' || el.type === 'checkbox') && legend(el) === norm(k),
+        (el, k) => norm(el.getAttribute('aria-label')) === norm(k),
+        (el, k) => norm(el.placeholder) === norm(k),
+    ];
+    const valueTypes = ['date', 'time', 'datetime-local', 'month', 'week', 'color', 'range'];
+    const kind = (el) => {
+        if (el.tagName === 'SELECT') return el.multiple ? 'multiselect' : 'select';
+        if (el.tagName === 'TEXTAREA') return 'text';
+        if (el.type === 'checkbox' || el.type === 'radio') return el.type;
+        return valueTypes.includes(el.type) ? 'value' : 'text';
+    };
+    let next = 0;
+    return keys.map((key) => {
+        for (const matches of matchers) {
+            let found = controls.filter((el) => matches(el, key));
+            if (!found.length) continue;
+            // Checkboxes and radios sharing a name form a group; other fields take the first match
+            const first = kind(found[0]);
+            found = first === 'checkbox' || first === 'radio'
+                ? found.filter((el) => el.type === first)
+                : [found[0]];
+            const ids = found.map((el) => {
+                const id = String(next++);
+                el.setAttribute('MARKER', id);
+                return id;
+            });
+            return { kind: first, ids };
+        }
+        return { kind: null, ids: [] };
+    });
+}"#;
+
+/// Sets a non-text field; returns an error message, or null once the value is set
+const SET_SCRIPT: &str = r#"(ids, kind, value) => {
+    const norm = (s) => (s || '').replace(/\s+/g, ' ').trim().toLowerCase();
+    const elements = ids.map((id) => document.querySelector(`[MARKER="${id}"]`));
+    const label = (el) => Array.from(el.labels || [], (l) => norm(l.textContent).replace(/\s*[*:]$/, ''));
+    const is = (el, wanted) => el.value === String(wanted) || label(el).includes(norm(String(wanted)));
+    const fire = (el) => {
+        el.dispatchEvent(new Event('input', { bubbles: true }));
+        el.dispatchEvent(new Event('change', { bubbles: true }));
+    };
+    const el = elements[0];
+    switch (kind) {
+        case 'value': {
+            const proto = Object.getPrototypeOf(el);
+            Object.getOwnPropertyDescriptor(proto, 'value').set.call(el, String(value));
+            fire(el);
+            return el.value === String(value) ? null : `value '${value}' was rejected`;
+        }
+        case 'select':
+        case 'multiselect': {
+            const wanted = Array.isArray(value) ? value : [value];
+            const options = Array.from(el.options);
+            const missing = wanted.filter((w) =>
+                !options.some((o) => o.value === String(w) || norm(o.textContent) === norm(String(w))));
+            if (missing.length) return `no option ${missing.map((m) => `'${m}'`).join(', ')}`;
+            for (const option of options) {
+                option.selected = wanted.some((w) =>
+                    option.value === String(w) || norm(option.textContent) === norm(String(w)));
+            }
+            fire(el);
+            return null;
+        }
+        case 'checkbox': {
+            let want;
+            if (typeof value === 'boolean') {
+                if (elements.length > 1) return 'several checkboxes match; give the values to check';
+                want = () => value;
+            } else {
+                const wanted = Array.isArray(value) ? value : [value];
+                const missing = wanted.filter((w) => !elements.some((e) => is(e, w)));
+                if (missing.length) return `no checkbox ${missing.map((m) => `'${m}'`).join(', ')}`;
+                want = (e) => wanted.some((w) => is(e, w));
+            }
+            for (const e of elements) if (e.checked !== want(e)) e.click();
+            return null;
+        }
+        case 'radio': {
+            if (typeof value !== 'string') return 'radio groups take the value or label to pick';
+            const choice = elements.find((e) => is(e, value));
+            if (!choice) return `no option '${value}'`;
+            if (!choice.checked) choice.click();
+            return null;
+        }
+    }
+    return `cannot set a ${kind} field`;
+}"#;
+
+/// Describes the form matching a selector (the page's first form if null), or
+/// returns null if there is none
+const SCHEMA_SCRIPT: &str = r#"(selector) => {
+    const norm = (s) => (s || '').replace(/\s+/g, ' ').trim().replace(/\s*[*:]$/, '');
+    const root = document.querySelector(selector || 'form');
+    if (!root) return null;
+    const form = root.tagName === 'FORM';
+    const skipped = ['hidden', 'submit', 'button', 'reset', 'image'];
+    const controls = Array.from(form ? root.elements : root.querySelectorAll('input, textarea, select'))
+        .filter((el) => ['INPUT', 'TEXTAREA', 'SELECT'].includes(el.tagName))
+        .filter((el) => !el.disabled && !skipped.includes(el.type));
+
+    const selectorOf = (target) => {
+        const parts = [];
+        for (let el = target; el && el !== document.documentElement; el = el.parentElement) {
+            if (el.id && /^[A-Za-z][\w-]*$/.test(el.id) && document.querySelectorAll('#' + el.id).length === 1) {
+                parts.unshift('#' + el.id);
+                break;
+            }
+            const tag = el.tagName.toLowerCase();
+            const same = el.parentElement
+                ? Array.from(el.parentElement.children).filter((c) => c.tagName === el.tagName)
+                : [el];
+            parts.unshift(same.length > 1 ? `${tag}:nth-of-type(${same.indexOf(el) + 1})` : tag);
+        }
+        if (!parts.length || !parts[0].startsWith('#')) parts.unshift('html');
+        return parts.join(' > ');
+    };
+    // Text of a label without the options of a select (or text of a textarea) it wraps
+    const labelText = (label) => {
+        const copy = label.cloneNode(true);
+        copy.querySelectorAll('select, textarea').forEach((c) => c.remove());
+        return norm(copy.textContent);
+    };
+    const labelOf = (el) => {
+        const ids = el.getAttribute('aria-labelledby');
+        const labelled = ids && norm(ids.split(/\s+/)
+            .map((id) => (document.getElementById(id) || {}).textContent || '').join(' '));
+        return labelled || Array.from(el.labels || [], labelText).find(Boolean)
+            || norm(el.getAttribute('aria-label')) || null;
+    };
+    const legendOf = (el) => {
+        const legend = el.closest('fieldset')?.querySelector('legend');
+        return legend ? norm(legend.textContent) : null;
+    };
+    const length = (n) => (n >= 0 ? n : null);
+    const field = (el, type) => ({
+        key: el.name || el.id || labelOf(el) || norm(el.placeholder) || null,
+        selector: selectorOf(el),
+        type,
+        label: labelOf(el),
+        name: el.name || null,
+        id: el.id || null,
+        required: el.required,
+        readonly: !!el.readOnly,
+        pattern: el.getAttribute('pattern'),
+        min_length: length(el.minLength),
+        max_length: length(el.maxLength),
+        min: el.getAttribute('min'),
+        max: el.getAttribute('max'),
+        step: el.getAttribute('step'),
+        placeholder: el.placeholder || null,
+        autocomplete: el.getAttribute('autocomplete'),
+        value: ['password', 'file', 'checkbox', 'radio'].includes(type) || !el.value ? null : el.value,
+        checked: null,
+        options: [],
+    });
+
+    const fields = [];
+    const groups = new Map();
+    for (const el of controls) {
+        const tag = el.tagName.toLowerCase();
+        const type = tag === 'select' ? (el.multiple ? 'multiselect' : 'select')
+            : tag === 'textarea' ? 'textarea' : el.type;
+        // Radios are picked from a group (those sharing a name), and checkboxes sharing
+        // a name form one too
+        const grouped = type === 'radio'
+            || (type === 'checkbox' && el.name && controls.filter((c) => c.type === type && c.name === el.name).length > 1);
+        if (grouped) {
+            const id = el.name ? type + ':' + el.name : el;
+            let group = groups.get(id);
+            if (!group) {
+                group = field(el, type);
+                group.id = null;
+                group.label = legendOf(el);
+                group.key = el.name || group.label;
+                if (el.name) group.selector = `${selectorOf(root)} input[name="${CSS.escape(el.name)}"]`;
+                group.required = false;
+                groups.set(id, group);
+                fields.push(group);
+            }
+            group.required = group.required || el.required;
+            group.options.push({ value: el.value, label: labelOf(el), selected: el.checked });
+            continue;
+        }
+        const described = field(el, type);
+        if (tag === 'select') {
+            described.options = Array.from(el.options)
+                .filter((o) => !o.disabled)
+                .map((o) => ({ value: o.value, label: norm(o.textContent), selected: o.selected }));
+        }
+        if (type === 'checkbox') described.checked = el.checked;
+        fields.push(described);
+    }
+
+    const submit = Array.from(form ? root.elements : root.querySelectorAll('button, input'))
+        .find((el) => (el.tagName === 'BUTTON' && (el.type || 'submit') === 'submit')
+            || (el.tagName === 'INPUT' && (el.type === 'submit' || el.type === 'image')));
+    return {
+        selector: selectorOf(root),
+        action: form ? root.action || null : null,
+        method: form ? root.method || 'get' : null,
+        fields,
+        submit: submit
+            ? { selector: selectorOf(submit), label: norm(submit.textContent || submit.value || submit.alt) }
+            : null,
+    };
+}"#;
+
+/// Value to enter in a form field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum FormValue {
+    /// Text to type, the option to select (value or text), the radio to pick (value
+    /// or label), or the checkbox of a group to check
+    Text(String),
+
+    /// Check or uncheck a single checkbox
+    Checked(bool),
+
+    /// Options of a multi-select, or checkboxes of a group, to select (others are cleared)
+    Many(Vec<String>),
+}
+
+impl FormValue {
+    /// Value from JSON (strings, numbers, booleans, or arrays of strings and numbers)
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let text = |value: &Value| match value {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        };
+        match value {
+            Value::Bool(checked) => Some(Self::Checked(*checked)),
+            Value::Array(items) => items
+                .iter()
+                .map(text)
+                .collect::<Option<_>>()
+                .map(Self::Many),
+            other => text(other).map(Self::Text),
+        }
+    }
+}
+
+impl From<&str> for FormValue {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<String> for FormValue {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<bool> for FormValue {
+    fn from(checked: bool) -> Self {
+        Self::Checked(checked)
+    }
+}
+
+impl From<Vec<String>> for FormValue {
+    fn from(values: Vec<String>) -> Self {
+        Self::Many(values)
+    }
+}
+
+/// Outcome of [`fill_form`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FillResult {
+    /// Fields that were filled, in the order given
+    pub filled: Vec<String>,
+
+    /// Fields no control matched
+    pub unmatched: Vec<String>,
+
+    /// Fields that matched a control but could not take the value
+    pub invalid: Vec<InvalidField>,
+}
+
+impl FillResult {
+    /// Whether every field was filled
+    pub fn is_complete(&self) -> bool {
+        self.unmatched.is_empty() && self.invalid.is_empty()
+    }
+}
+
+/// A matched field whose value could not be entered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvalidField {
+    pub field: String,
+
+    /// Why (e.g. `no option 'Mars'`)
+    pub reason: String,
+}
+
+/// Structure of a form, as [`infer_form_schema`] found it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormSchema {
+    /// CSS selector of the form (or of the container the fields were read from)
+    pub selector: String,
+
+    /// Absolute URL the form submits to (None for containers other than `<form>`)
+    pub action: Option<String>,
+
+    /// `get` or `post` (None for containers other than `<form>`)
+    pub method: Option<String>,
+
+    /// Enabled fields in document order, with radio groups (and checkboxes sharing
+    /// a name) as one field each
+    pub fields: Vec<FormField>,
+
+    /// The button that submits the form, if it has one
+    pub submit: Option<SubmitButton>,
+}
+
+/// A field of a [`FormSchema`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FormField {
+    /// Name to give [`fill_form`] for this field: its `name`, `id`, label, or
+    /// placeholder, whichever it has first (None if it has none)
+    pub key: Option<String>,
+
+    /// CSS selector of the field (of all its inputs, for a group)
+    pub selector: String,
+
+    /// Input type (`text`, `email`, `date`, `checkbox`, ...), or `select`,
+    /// `multiselect`, or `textarea`
+    #[serde(rename = "type")]
+    pub field_type: String,
+
+    /// Label text (a group's `<legend>`), without a trailing `*` or `:`
+    pub label: Option<String>,
+
+    pub name: Option<String>,
+    pub id: Option<String>,
+    pub required: bool,
+
+    /// Read-only, as date pickers often are (they're filled through their widget)
+    pub readonly: bool,
+
+    /// Regular expression the whole value must match (`pattern` attribute)
+    pub pattern: Option<String>,
+
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+
+    /// `min`, `max`, and `step` attributes, as written (numbers or dates)
+    pub min: Option<String>,
+    pub max: Option<String>,
+    pub step: Option<String>,
+
+    pub placeholder: Option<String>,
+
+    /// Autofill hint (`email`, `given-name`, `postal-code`, ...)
+    pub autocomplete: Option<String>,
+
+    /// Current value (never reported for passwords)
+    pub value: Option<String>,
+
+    /// Whether a single checkbox (one not sharing its name) is checked
+    pub checked: Option<bool>,
+
+    /// Options of a select, or the inputs of a radio or checkbox group
+    #[serde(default)]
+    pub options: Vec<FieldOption>,
+}
+
+/// An option of a select, radio group, or checkbox group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldOption {
+    pub value: String,
+    pub label: Option<String>,
+    pub selected: bool,
+}
+
+/// The button that submits a form
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubmitButton {
+    pub selector: String,
+    pub label: String,
+}
+
+/// Problems [`FormSchema::check`] found with values for a form
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormCheck {
+    /// Keys of required fields no value was given for
+    pub missing: Vec<String>,
+
+    /// Given names no field matches
+    pub unmatched: Vec<String>,
+
+    /// Given values their field can't take
+    pub invalid: Vec<InvalidField>,
+}
+
+impl FormCheck {
+    /// Whether the values fill every required field and fit their fields
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.unmatched.is_empty() && self.invalid.is_empty()
+    }
+}
+
+impl FormSchema {
+    /// Check `fields` against the form before filling it: required fields left out,
+    /// names no field matches, and values a field can't take (unknown options, the
+    /// wrong kind of value, lengths and numbers out of range)
+    ///
+    /// Names are matched like [`fill_form`] matches them. `pattern`s aren't checked.
+    pub fn check(&self, fields: &[(String, FormValue)]) -> FormCheck {
+        let mut check = FormCheck::default();
+        let mut given = vec![false; self.fields.len()];
+        for (key, value) in fields {
+            let Some(index) = self.fields.iter().position(|field| field.matches(key)) else {
+                check.unmatched.push(key.clone());
+                continue;
+            };
+            given[index] = true;
+            if let Some(reason) = self.fields[index].reject(value) {
+                check.invalid.push(InvalidField {
+                    field: key.clone(),
+                    reason,
+                });
+            }
+        }
+        for (field, given) in self.fields.iter().zip(given) {
+            let filled = field.value.is_some()
+                || field.checked == Some(true)
+                || field
+                    .options
+                    .iter()
+                    .any(|o| o.selected && !o.value.is_empty());
+            if field.required && !given && !filled {
+                let key = field.key.as_deref().unwrap_or(&field.selector);
+                check.missing.push(key.to_string());
+            }
+        }
+        check
+    }
+}
+
+impl FormField {
+    /// Whether [`fill_form`] would match `key` to this field
+    pub fn matches(&self, key: &str) -> bool {
+        let norm = |s: &str| normalize_label(s);
+        self.name.as_deref() == Some(key)
+            || self.id.as_deref() == Some(key)
+            || self.key.as_deref() == Some(key)
+            || self
+                .label
+                .as_deref()
+                .is_some_and(|label| norm(label) == norm(key))
+            || self
+                .placeholder
+                .as_deref()
+                .is_some_and(|placeholder| norm(placeholder) == norm(key))
+    }
+
+    /// Why the field can't take `value`, if it can't
+    pub fn reject(&self, value: &FormValue) -> Option<String> {
+        let choices: Vec<&str> = match value {
+            FormValue::Text(choice) => vec![choice.as_str()],
+            FormValue::Many(choices) => choices.iter().map(String::as_str).collect(),
+            FormValue::Checked(_) => Vec::new(),
+        };
+        match (self.field_type.as_str(), value) {
+            // A single checkbox is checked by its own value or label too
+            ("checkbox", _) if self.checked.is_some() => None,
+            ("checkbox", FormValue::Checked(_)) => {
+                Some("several checkboxes match; give the values to check".to_string())
+            }
+            ("checkbox", _) => self.missing_options("checkbox", &choices),
+            ("radio", FormValue::Text(_)) => self.missing_options("option", &choices),
+            ("radio", _) => Some("radio groups take the value or label to pick".to_string()),
+            ("select" | "multiselect", FormValue::Checked(_)) => {
+                Some("selects take the value or text of an option".to_string())
+            }
+            ("select" | "multiselect", _) => self.missing_options("option", &choices),
+            ("file", _) => Some("file inputs can't be filled".to_string()),
+            (_, FormValue::Text(text)) => self.reject_text(text),
+            _ => Some("text fields take a string".to_string()),
+        }
+    }
+
+    /// Error naming the `choices` that aren't options, as [`fill_form`] reports them
+    fn missing_options(&self, noun: &str, choices: &[&str]) -> Option<String> {
+        let missing: Vec<String> = choices
+            .iter()
+            .filter(|choice| {
+                !self.options.iter().any(|option| {
+                    option.value == **choice
+                        || option
+                            .label
+                            .as_deref()
+                            .is_some_and(|label| normalize_label(label) == normalize_label(choice))
+                })
+            })
+            .map(|choice| format!("'{}'", choice))
+            .collect();
+        (!missing.is_empty()).then(|| format!("no {} {}", noun, missing.join(", ")))
+    }
+
+    fn reject_text(&self, text: &str) -> Option<String> {
+        let length = text.chars().count();
+        if let Some(min) = self.min_length.filter(|&min| length < min) {
+            return Some(format!("shorter than {} characters", min));
+        }
+        if let Some(max) = self.max_length.filter(|&max| length > max) {
+            return Some(format!("longer than {} characters", max));
+        }
+        if matches!(self.field_type.as_str(), "number" | "range") {
+            let Ok(number) = text.trim().parse::<f64>() else {
+                return Some(format!("'{}' is not a number", text));
+            };
+            let bound = |b: &Option<String>| b.as_deref().and_then(|b| b.parse::<f64>().ok());
+            if let Some(min) = bound(&self.min).filter(|&min| number < min) {
+                return Some(format!("below the minimum of {}", min));
+            }
+            if let Some(max) = bound(&self.max).filter(|&max| number > max) {
+                return Some(format!("above the maximum of {}", max));
+            }
+        }
+        if self.field_type == "email" && !text.is_empty() && !text.contains('@') {
+            return Some(format!("'{}' is not an email address", text));
+        }
+        None
+    }
+}
+
+/// Label text compared the way [`fill_form`] compares it: case, extra whitespace, and
+/// a trailing `*` or `:` ignored
+fn normalize_label(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['*', ':'])
+        .trim_end()
+        .to_lowercase()
+}
+
+/// Describe the form matching `selector` (the page's first `<form>` if None)
+///
+/// `selector` may also match a container that isn't a `<form>`; its inputs,
+/// textareas, and selects are described then. Fails if nothing matches.
+pub async fn infer_form_schema(page: &Page, selector: Option<&str>) -> Result<FormSchema> {
+    let schema: Option<FormSchema> = page
+        .evaluate(format!("({})({})", SCHEMA_SCRIPT, Value::from(selector)))
+        .await?
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Failed to read form schema: {}", e)))?;
+    let schema = schema
+        .ok_or_else(|| BrowserError::ElementNotFound(selector.unwrap_or("form").to_string()))?;
+    tracing::debug!(form = %schema.selector, fields = schema.fields.len(), "Inferred form schema");
+    Ok(schema)
+}
+
+#[derive(Debug, Deserialize)]
+struct Located {
+    kind: Option<String>,
+    ids: Vec<String>,
+}
+
+/// Fill the form fields of `page` named in `fields`, typing text through `widgets`
+pub async fn fill_form(
+    page: &Page,
+    fields: &[(String, FormValue)],
+    widgets: &WidgetRegistry,
+) -> Result<FillResult> {
+    let keys: Vec<&str> = fields.iter().map(|(key, _)| key.as_str()).collect();
+    let located: Vec<Located> = page
+        .evaluate(format!(
+            "({})({})",
+            LOCATE_SCRIPT.replace("MARKER", MARKER),
+            Value::from(keys)
+        ))
+        .await?
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Failed to locate form fields: {}", e)))?;
+
+    let mut result = FillResult::default();
+    let filled = fill_located(page, fields, widgets, located, &mut result).await;
+    let _ = page
+        .evaluate(format!(
+            "document.querySelectorAll('[{0}]').forEach((el) => el.removeAttribute('{0}'))",
+            MARKER
+        ))
+        .await;
+    filled?;
+
+    tracing::debug!(
+        filled = result.filled.len(),
+        unmatched = result.unmatched.len(),
+        invalid = result.invalid.len(),
+        "Filled form"
+    );
+    Ok(result)
+}
+
+async fn fill_located(
+    page: &Page,
+    fields: &[(String, FormValue)],
+    widgets: &WidgetRegistry,
+    located: Vec<Located>,
+    result: &mut FillResult,
+) -> Result<()> {
+    for ((key, value), found) in fields.iter().zip(located) {
+        let Some(kind) = found.kind else {
+            result.unmatched.push(key.clone());
+            continue;
+        };
+        let error = match (kind.as_str(), value) {
+            ("text", FormValue::Text(text)) => {
+                match type_text(page, widgets, &found.ids[0], text).await {
+                    Ok(()) => None,
+                    // A widget strategy rejected the value (e.g. no matching combobox option)
+                    Err(BrowserError::Other(reason)) => Some(reason),
+                    Err(e) => return Err(e),
+                }
+            }
+            ("text", _) => Some("text fields take a string".to_string()),
+            (kind, value) => {
+                let script = format!(
+                    "({})({}, {}, {})",
+                    SET_SCRIPT.replace("MARKER", MARKER),
+                    Value::from(found.ids),
+                    Value::from(kind),
+                    serde_json::json!(value)
+                );
+                page.evaluate(script)
+                    .await?
+                    .into_value::<Option<String>>()
+                    .map_err(|e| BrowserError::Other(e.to_string()))?
+            }
+        };
+        match error {
+            Some(reason) => result.invalid.push(InvalidField {
+                field: key.clone(),
+                reason,
+            }),
+            None => result.filled.push(key.clone()),
+        }
+    }
+    Ok(())
+}
+
+/// Replace the text of the marked field, using the widget strategy that handles it
+async fn type_text(page: &Page, widgets: &WidgetRegistry, id: &str, text: &str) -> Result<()> {
+    let selector = format!("[{}=\"{}\"]", MARKER, id);
+    let element = page
+        .find_element(&selector)
+        .await
+        .map_err(|_| BrowserError::ElementNotFound(selector.clone()))?;
+    widgets.type_text(page, &element, text).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_form_value_from_json() {
+        assert_eq!(
+            FormValue::from_json(&json!("alice")),
+            Some(FormValue::Text("alice".to_string()))
+        );
+        assert_eq!(
+            FormValue::from_json(&json!(42)),
+            Some(FormValue::Text("42".to_string()))
+        );
+        assert_eq!(
+            FormValue::from_json(&json!(true)),
+            Some(FormValue::Checked(true))
+        );
+        assert_eq!(
+            FormValue::from_json(&json!(["red", 2])),
+            Some(FormValue::Many(vec!["red".to_string(), "2".to_string()]))
+        );
+        assert_eq!(FormValue::from_json(&json!({"a": 1})), None);
+        assert_eq!(FormValue::from_json(&json!([true])), None);
+    }
+
+    #[test]
+    fn test_check_schema() {
+        let schema: FormSchema = serde_json::from_value(json!({
+            "selector": "#signup",
+            "action": "https://example.com/signup",
+            "method": "post",
+            "submit": {"selector": "#signup > button", "label": "Create account"},
+            "fields": [
+                {"key": "email", "selector": "#email", "type": "email", "label": "Email address",
+                 "name": "email", "id": "email", "required": true, "readonly": false},
+                {"key": "age", "selector": "#age", "type": "number", "label": "Age", "name": "age",
+                 "required": false, "readonly": false, "min": "18", "max": "120"},
+                {"key": "username", "selector": "#username", "type": "text", "label": "Username",
+                 "name": "username", "required": true, "readonly": false, "min_length": 3,
+                 "max_length": 8, "value": "alice"},
+                {"key": "plan", "selector": "#signup input[name=\"plan\"]", "type": "radio",
+                 "label": "Plan", "name": "plan", "required": true, "readonly": false,
+                 "options": [{"value": "free", "label": "Free", "selected": false},
+                             {"value": "pro", "label": "Pro", "selected": false}]},
+                {"key": "terms", "selector": "#terms", "type": "checkbox",
+                 "label": "I accept the terms", "name": "terms", "required": true,
+                 "readonly": false, "checked": false}
+            ]
+        }))
+        .unwrap();
+        let field = |key: &str, value: FormValue| (key.to_string(), value);
+
+        let check = schema.check(&[
+            field("Email address:", FormValue::from("alice@example.com")),
+            field("plan", FormValue::from("PRO")),
+            field("I accept the terms", FormValue::from(true)),
+        ]);
+        assert!(check.is_ok(), "{:?}", check);
+
+        let check = schema.check(&[
+            field("email", FormValue::from("alice")),
+            field("age", FormValue::from("12")),
+            field("username", FormValue::from("al")),
+            field("Plan", FormValue::from(true)),
+            field("Nickname", FormValue::from("Al")),
+        ]);
+        assert!(!check.is_ok());
+        assert_eq!(check.missing, ["terms"]);
+        assert_eq!(check.unmatched, ["Nickname"]);
+        let reasons: Vec<&str> = check.invalid.iter().map(|i| i.reason.as_str()).collect();
+        assert_eq!(
+            reasons,
+            [
+                "'alice' is not an email address",
+                "below the minimum of 18",
+                "shorter than 3 characters",
+                "radio groups take the value or label to pick",
+            ]
+        );
+        let check = schema.check(&[field("plan", FormValue::from("Enterprise"))]);
+        assert_eq!(check.invalid[0].reason, "no option 'Enterprise'");
+    }
+}
@@ -0,0 +1,319 @@
+//! Element Geometry
+//!
+//! Bounding boxes of elements in viewport and page coordinates, for anything that
+//! works with positions rather than selectors (mouse input, overlay annotations,
+//! coordinate-based agents). Boxes come from `DOM.getContentQuads`, so transformed
+//! elements and inline elements wrapping across lines get the box around all of
+//! their fragments. [`node_at_point`] goes the other way, from a viewport point to
+//! the element rendered there (`DOM.getNodeForLocation`).
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::cdp::browser_protocol::dom::{
+    GetContentQuadsParams, GetNodeForLocationParams, Quad, ResolveNodeParams,
+};
+use chromiumoxide::cdp::browser_protocol::page::GetLayoutMetricsParams;
+use chromiumoxide::cdp::js_protocol::runtime::CallFunctionOnParams;
+use chromiumoxide::element::Element;
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+
+pub use crate::model::geometry::Rect;
+
+impl Rect {
+    /// Smallest rectangle containing all `quads` (None if there are none)
+    fn bounding(quads: &[Quad]) -> Option<Self> {
+        let points: Vec<(f64, f64)> = quads
+            .iter()
+            .flat_map(|quad| quad.inner().chunks_exact(2).map(|p| (p[0], p[1])))
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+        let (mut left, mut top) = (f64::INFINITY, f64::INFINITY);
+        let (mut right, mut bottom) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (x, y) in points {
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
+        }
+        Some(Self {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        })
+    }
+
+    /// The same rectangle moved by `(dx, dy)`
+    fn offset(&self, dx: f64, dy: f64) -> Self {
+        Self {
+            x: self.x + dx,
+            y: self.y + dy,
+            ..*self
+        }
+    }
+}
+
+/// Where an element is on the page
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ElementRect {
+    /// Bounding box relative to the viewport (what mouse events use)
+    pub viewport: Rect,
+
+    /// Bounding box relative to the top-left of the document (stable across scrolling)
+    pub page: Rect,
+
+    /// Whether the element is rendered with a non-empty box and isn't hidden by
+    /// `visibility` or `opacity: 0`
+    ///
+    /// Elements that aren't rendered at all (e.g. `display: none`) have empty rects
+    /// at the origin.
+    pub visible: bool,
+
+    /// Whether any part of the box lies inside the viewport
+    pub in_viewport: bool,
+}
+
+/// Bounding box of the first element matching `selector`
+pub async fn element_rect(page: &Page, selector: &str) -> Result<ElementRect> {
+    let element = page
+        .find_element(selector)
+        .await
+        .map_err(|_| BrowserError::ElementNotFound(selector.to_string()))?;
+    let viewport = layout_viewport(page).await?;
+    measure(page, &element, &viewport).await
+}
+
+/// Scroll the first element matching `selector` into view and return its center in
+/// viewport coordinates, failing if it isn't visible
+pub async fn visible_center(page: &Page, selector: &str) -> Result<(f64, f64)> {
+    let element = page
+        .find_element(selector)
+        .await
+        .map_err(|_| BrowserError::ElementNotFound(selector.to_string()))?;
+    element.scroll_into_view().await?;
+    let rect = element_rect(page, selector).await?;
+    if !rect.visible {
+        return Err(BrowserError::Other(format!(
+            "Element '{}' is not visible",
+            selector
+        )));
+    }
+    Ok(rect.viewport.center())
+}
+
+/// Bounding boxes of all elements matching `selector`, in document order
+pub async fn element_rects(page: &Page, selector: &str) -> Result<Vec<ElementRect>> {
+    let elements = page
+        .find_elements(selector)
+        .await
+        .map_err(|e| BrowserError::Other(format!("Invalid selector '{}': {}", selector, e)))?;
+    let viewport = layout_viewport(page).await?;
+    let mut rects = Vec::with_capacity(elements.len());
+    for element in &elements {
+        rects.push(measure(page, element, &viewport).await?);
+    }
+    Ok(rects)
+}
+
+/// The layout viewport in page coordinates
+pub(crate) async fn layout_viewport(page: &Page) -> Result<Rect> {
+    let metrics = page
+        .execute(GetLayoutMetricsParams::default())
+        .await
+        .map_err(|e| BrowserError::Other(format!("Failed to get layout metrics: {}", e)))?;
+    let viewport = &metrics.result.css_layout_viewport;
+    Ok(Rect {
+        x: viewport.page_x as f64,
+        y: viewport.page_y as f64,
+        width: viewport.client_width as f64,
+        height: viewport.client_height as f64,
+    })
+}
+
+async fn measure(page: &Page, element: &Element, viewport: &Rect) -> Result<ElementRect> {
+    // Fails for elements without a layout box, which simply aren't visible
+    let quads = page
+        .execute(
+            GetContentQuadsParams::builder()
+                .backend_node_id(element.backend_node_id)
+                .build(),
+        )
+        .await
+        .map(|r| r.result.quads)
+        .unwrap_or_default();
+    let Some(rect) = Rect::bounding(&quads) else {
+        return Ok(ElementRect::default());
+    };
+
+    let shown = element
+        .call_js_fn(SHOWN_SCRIPT, false)
+        .await
+        .map_err(|e| BrowserError::Other(format!("Failed to check element visibility: {}", e)))?
+        .result
+        .value
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+
+    let in_viewport = rect.intersects(&Rect {
+        x: 0.0,
+        y: 0.0,
+        ..*viewport
+    });
+    Ok(ElementRect {
+        viewport: rect,
+        page: rect.offset(viewport.x, viewport.y),
+        visible: shown && !rect.is_empty(),
+        in_viewport,
+    })
+}
+
+/// The element rendered at a viewport point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeAtPoint {
+    /// Backend DOM node id (stable for the lifetime of the document)
+    pub backend_node_id: i64,
+
+    /// CSS selector of the element: the nearest unique `#id` ancestor, then
+    /// `tag:nth-of-type(n)` steps (the same form `StepFrame::element_at` produces)
+    pub selector: String,
+
+    /// Lowercase tag name
+    pub tag: String,
+
+    /// Trimmed text content (first 100 characters)
+    pub text: String,
+
+    /// Bounding box relative to the viewport
+    pub rect: Rect,
+}
+
+/// Element at viewport point `(x, y)` (CSS pixels), or None if there is none
+///
+/// Text nodes resolve to their parent element. Elements with `pointer-events: none`
+/// are skipped, as they would be by a click at that point.
+pub async fn node_at_point(page: &Page, x: f64, y: f64) -> Result<Option<NodeAtPoint>> {
+    let location = page
+        .execute(
+            GetNodeForLocationParams::builder()
+                .x(x.round() as i64)
+                .y(y.round() as i64)
+                .ignore_pointer_events_none(true)
+                .build()
+                .map_err(BrowserError::Other)?,
+        )
+        .await;
+    // Points outside the page (or over nothing) have no node
+    let Ok(location) = location else {
+        return Ok(None);
+    };
+    let backend_node_id = location.result.backend_node_id;
+
+    let object = page
+        .execute(
+            ResolveNodeParams::builder()
+                .backend_node_id(backend_node_id)
+                .build(),
+        )
+        .await?;
+    let Some(object_id) = object.result.object.object_id.clone() else {
+        return Ok(None);
+    };
+    let described = page
+        .execute(
+            CallFunctionOnParams::builder()
+                .object_id(object_id)
+                .function_declaration(DESCRIBE_SCRIPT)
+                .return_by_value(true)
+                .build()
+                .map_err(BrowserError::Other)?,
+        )
+        .await?;
+    let Some(value) = described
+        .result
+        .result
+        .value
+        .clone()
+        .filter(|v| !v.is_null())
+    else {
+        return Ok(None);
+    };
+
+    #[derive(Deserialize)]
+    struct Described {
+        selector: String,
+        tag: String,
+        text: String,
+        rect: Rect,
+    }
+    let described: Described = serde_json::from_value(value)
+        .map_err(|e| BrowserError::Other(format!("Failed to describe node: {}", e)))?;
+    Ok(Some(NodeAtPoint {
+        backend_node_id: *backend_node_id.inner(),
+        selector: described.selector,
+        tag: described.tag,
+        text: described.text,
+        rect: described.rect,
+    }))
+}
+
+/// Selector, tag, text, and viewport box of the element `this` is (or is in)
+const DESCRIBE_SCRIPT: &str = r#"function() {
+    const target = this.nodeType === Node.ELEMENT_NODE ? this : this.parentElement;
+    if (!target) return null;
+    const parts = [];
+    for (let el = target; el && el !== document.documentElement; el = el.parentElement) {
+        if (el.id && /^[A-Za-z][\w-]*$/.test(el.id) && document.querySelectorAll('#' + el.id).length === 1) {
+            parts.unshift('#' + el.id);
+            break;
+        }
+        const tag = el.tagName.toLowerCase();
+        const same = el.parentElement
+            ? Array.from(el.parentElement.children).filter((c) => c.tagName === el.tagName)
+            : [el];
+        parts.unshift(same.length > 1 ? `${tag}:nth-of-type(${same.indexOf(el) + 1})` : tag);
+    }
+    if (!parts.length || !parts[0].startsWith('#')) parts.unshift('html');
+    const box = target.getBoundingClientRect();
+    return {
+        selector: parts.join(' > '),
+        tag: target.tagName.toLowerCase(),
+        text: (target.textContent || '').trim().replace(/\s+/g, ' ').slice(0, 100),
+        rect: { x: box.x, y: box.y, width: box.width, height: box.height },
+    };
+}"#;
+
+/// Whether CSS leaves the element visible (`this` is the element)
+const SHOWN_SCRIPT: &str = r#"function() {
+    const style = window.getComputedStyle(this);
+    return style.visibility !== 'hidden' && style.visibility !== 'collapse' && style.opacity !== '0';
+}"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounding_rect_of_quads() {
+        assert_eq!(Rect::bounding(&[]), None);
+
+        // Two line fragments of a wrapped inline element
+        let quads = vec![
+            Quad::new(vec![100.0, 10.0, 300.0, 10.0, 300.0, 30.0, 100.0, 30.0]),
+            Quad::new(vec![0.0, 30.0, 50.0, 30.0, 50.0, 50.0, 0.0, 50.0]),
+        ];
+        let rect = Rect::bounding(&quads).unwrap();
+        assert_eq!(
+            rect,
+            Rect {
+                x: 0.0,
+                y: 10.0,
+                width: 300.0,
+                height: 40.0
+            }
+        );
+        assert_eq!(rect.center(), (150.0, 30.0));
+        assert_eq!(rect.offset(0.0, 500.0).y, 510.0);
+    }
+}
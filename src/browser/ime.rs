@@ -0,0 +1,147 @@
+//! IME Text Composition
+//!
+//! Chinese, Japanese, and Korean text is entered through an input method editor:
+//! the page sees `compositionstart`, a series of `compositionupdate` events while
+//! the text is being composed, and `compositionend` once it is committed. Typing
+//! such text key by key corrupts it, and inserting it in one go skips the
+//! composition events that search boxes and editors wait for before reacting.
+//!
+//! [`insert_text`] replays what an IME does: text is split into segments, CJK
+//! segments are composed one character at a time with `Input.imeSetComposition`
+//! and committed with `Input.insertText`, and everything else (Latin, Cyrillic,
+//! Arabic, emoji, ...) is inserted directly. Hangul is composed per syllable, Han
+//! and kana per run.
+
+use crate::error::Result;
+use chromiumoxide::cdp::browser_protocol::input::{ImeSetCompositionParams, InsertTextParams};
+use chromiumoxide::page::Page;
+
+/// A run of text entered the same way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// Inserted as is
+    Direct(String),
+
+    /// Composed, then committed
+    Composed(String),
+}
+
+/// How a character is entered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Direct,
+    /// Han, kana, bopomofo, and CJK punctuation: composed in runs
+    Cjk,
+    /// Hangul: composed per syllable
+    Hangul,
+}
+
+fn script(c: char) -> Script {
+    match u32::from(c) {
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xA960..=0xA97F | 0xAC00..=0xD7AF => Script::Hangul,
+        0x3000..=0x30FF // CJK punctuation, hiragana, katakana
+        | 0x3100..=0x312F // bopomofo
+        | 0x31F0..=0x31FF // katakana extensions
+        | 0x3400..=0x4DBF // CJK extension A
+        | 0x4E00..=0x9FFF // CJK unified ideographs
+        | 0xF900..=0xFAFF // CJK compatibility ideographs
+        | 0xFF00..=0xFFEF // fullwidth and halfwidth forms
+        | 0x20000..=0x3134F => Script::Cjk,
+        _ => Script::Direct,
+    }
+}
+
+/// Whether any of `text` needs composing
+pub fn needs_ime(text: &str) -> bool {
+    text.chars().any(|c| script(c) != Script::Direct)
+}
+
+/// Split `text` into the segments an IME would produce
+pub fn segments(text: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut current_script = Script::Direct;
+
+    for c in text.chars() {
+        let c_script = script(c);
+        // Each Hangul syllable is its own composition
+        let split = c_script != current_script || c_script == Script::Hangul;
+        if split && !current.is_empty() {
+            segments.push(segment(current_script, std::mem::take(&mut current)));
+        }
+        current_script = c_script;
+        current.push(c);
+    }
+    if !current.is_empty() {
+        segments.push(segment(current_script, current));
+    }
+    segments
+}
+
+fn segment(script: Script, text: String) -> Segment {
+    match script {
+        Script::Direct => Segment::Direct(text),
+        Script::Cjk | Script::Hangul => Segment::Composed(text),
+    }
+}
+
+/// Insert `text` at the focused element's caret, composing CJK text like an IME
+pub async fn insert_text(page: &Page, text: &str) -> Result<()> {
+    for segment in segments(text) {
+        match segment {
+            Segment::Direct(text) => {
+                page.execute(InsertTextParams::new(text)).await?;
+            }
+            Segment::Composed(text) => compose(page, &text).await?,
+        }
+    }
+    Ok(())
+}
+
+/// Compose `text` one character at a time, then commit it
+pub async fn compose(page: &Page, text: &str) -> Result<()> {
+    let mut composition = String::new();
+    for c in text.chars() {
+        composition.push(c);
+        // Selection offsets count UTF-16 code units; the caret sits at the end
+        let caret = composition.encode_utf16().count() as i64;
+        page.execute(ImeSetCompositionParams::new(
+            composition.as_str(),
+            caret,
+            caret,
+        ))
+        .await?;
+    }
+    // Inserting text while composing commits the composition
+    page.execute(InsertTextParams::new(text)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments() {
+        let direct = |s: &str| Segment::Direct(s.to_string());
+        let composed = |s: &str| Segment::Composed(s.to_string());
+
+        assert_eq!(segments("hello"), vec![direct("hello")]);
+        assert_eq!(segments("Привет 👋"), vec![direct("Привет 👋")]);
+        assert_eq!(
+            segments("Tokyo 東京タワー、ok"),
+            vec![direct("Tokyo "), composed("東京タワー、"), direct("ok")]
+        );
+        assert_eq!(
+            segments("한국어 ok"),
+            vec![
+                composed("한"),
+                composed("국"),
+                composed("어"),
+                direct(" ok")
+            ]
+        );
+        assert!(needs_ime("中文"));
+        assert!(!needs_ime("Ελληνικά"));
+    }
+}
@@ -0,0 +1,434 @@
+//! Chrome for Testing Installation Management
+//!
+//! Downloaded Chrome builds are cached per version under the download directory
+//! (default `~/.cache/robert/chrome/<version>/`). Each version directory holds an
+//! `install.json` manifest recording the executable path and its SHA-256 hash, which
+//! is re-checked before the build is used so a corrupted or tampered cache is caught
+//! instead of launched.
+//!
+//! Versions can be pinned with patterns such as `128`, `128.0.x`, or `128.0.6613.119`.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide_fetcher::{BrowserFetcher, BrowserFetcherOptions};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Name of the per-version manifest file
+const MANIFEST_FILE: &str = "install.json";
+
+/// A Chrome version requirement, e.g. `128.0.x`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionPin {
+    /// Required components; None matches any value (`x` or `*`)
+    components: Vec<Option<u32>>,
+}
+
+impl std::str::FromStr for VersionPin {
+    type Err = String;
+
+    /// Parse a version pattern (`128`, `128.0.x`, `128.0.6613.119`)
+    fn from_str(pattern: &str) -> std::result::Result<Self, Self::Err> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Err("Empty Chrome version".to_string());
+        }
+
+        let components = pattern
+            .split('.')
+            .map(|part| match part {
+                "x" | "X" | "*" => Ok(None),
+                n => n
+                    .parse::<u32>()
+                    .map(Some)
+                    .map_err(|_| format!("Invalid Chrome version pattern '{}'", pattern)),
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(Self { components })
+    }
+}
+
+impl VersionPin {
+    /// Whether a concrete version string (e.g. `128.0.6613.119`) satisfies this pin
+    ///
+    /// Components not mentioned by the pin match anything, so `128` matches `128.0.1.2`.
+    pub fn matches(&self, version: &str) -> bool {
+        let parts: Vec<&str> = version.split('.').collect();
+        self.components.iter().enumerate().all(|(i, want)| {
+            match (want, parts.get(i).and_then(|p| p.parse::<u32>().ok())) {
+                (None, Some(_)) => true,
+                (Some(want), Some(have)) => *want == have,
+                (_, None) => false,
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for VersionPin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self
+            .components
+            .iter()
+            .map(|c| c.map_or_else(|| "x".to_string(), |n| n.to_string()))
+            .collect();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+/// An installed Chrome build, as recorded in its manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledChrome {
+    /// Full Chrome version (e.g. `128.0.6613.119`)
+    pub version: String,
+
+    /// Path to the Chrome executable
+    pub executable: PathBuf,
+
+    /// SHA-256 of the executable at install time
+    pub sha256: String,
+
+    /// ISO 8601 timestamp of installation
+    pub installed_at: String,
+}
+
+/// Manages per-version Chrome for Testing installs in a cache directory
+pub struct ChromeInstaller {
+    root: PathBuf,
+}
+
+impl ChromeInstaller {
+    /// Installer rooted at `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Installer rooted at `download_dir`, or `~/.cache/robert/chrome` when None
+    ///
+    /// Returns None if no download dir is given and the cache directory is unknown.
+    pub fn for_download_dir(download_dir: Option<&Path>) -> Option<Self> {
+        let root = match download_dir {
+            Some(dir) => dir.to_path_buf(),
+            None => dirs::cache_dir()?.join("robert").join("chrome"),
+        };
+        Some(Self::new(root))
+    }
+
+    /// Cache root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// List installed versions, newest first
+    ///
+    /// Directories without a readable manifest are skipped.
+    pub async fn list(&self) -> Result<Vec<InstalledChrome>> {
+        let mut installed = Vec::new();
+
+        let mut entries = match tokio::fs::read_dir(&self.root).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(installed),
+            Err(e) => {
+                return Err(BrowserError::Other(format!(
+                    "Failed to read Chrome cache {}: {}",
+                    self.root.display(),
+                    e
+                )))
+            }
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let manifest = entry.path().join(MANIFEST_FILE);
+            let Ok(content) = tokio::fs::read_to_string(&manifest).await else {
+                continue;
+            };
+            match serde_json::from_str::<InstalledChrome>(&content) {
+                Ok(chrome) => installed.push(chrome),
+                Err(e) => {
+                    tracing::warn!(path = %manifest.display(), error = %e, "Ignoring invalid Chrome manifest")
+                }
+            }
+        }
+
+        installed.sort_by(|a, b| compare_versions(&b.version, &a.version));
+        Ok(installed)
+    }
+
+    /// Find the newest installed version satisfying `pin` (any version when None)
+    pub async fn find(&self, pin: Option<&VersionPin>) -> Result<Option<InstalledChrome>> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .find(|chrome| pin.is_none_or(|pin| pin.matches(&chrome.version))))
+    }
+
+    /// Return a verified install satisfying `pin`, downloading one if needed
+    pub async fn ensure(&self, pin: Option<&VersionPin>) -> Result<InstalledChrome> {
+        if let Some(chrome) = self.find(pin).await? {
+            match verify(&chrome).await {
+                Ok(()) => return Ok(chrome),
+                Err(e) => {
+                    tracing::warn!(version = %chrome.version, error = %e, "Cached Chrome failed verification, reinstalling");
+                    self.remove(&chrome.version).await?;
+                }
+            }
+        }
+
+        self.install(pin).await
+    }
+
+    /// Download Chrome for Testing into a per-version directory
+    ///
+    /// The fetcher provides a single bundled version; if it doesn't satisfy `pin`
+    /// the download is discarded and an error is returned.
+    pub async fn install(&self, pin: Option<&VersionPin>) -> Result<InstalledChrome> {
+        let staging = self.root.join(format!(
+            ".staging-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        tokio::fs::create_dir_all(&staging)
+            .await
+            .map_err(|e| BrowserError::Other(format!("Failed to create cache dir: {}", e)))?;
+
+        let result = self.install_from_staging(&staging, pin).await;
+        let _ = tokio::fs::remove_dir_all(&staging).await;
+        result
+    }
+
+    async fn install_from_staging(
+        &self,
+        staging: &Path,
+        pin: Option<&VersionPin>,
+    ) -> Result<InstalledChrome> {
+        tracing::info!("Downloading Chrome for Testing (~150MB)...");
+        let fetcher = BrowserFetcher::new(
+            BrowserFetcherOptions::builder()
+                .with_path(staging)
+                .build()
+                .map_err(|e| BrowserError::Other(format!("Fetcher config failed: {}", e)))?,
+        );
+        let info = fetcher
+            .fetch()
+            .await
+            .map_err(|e| BrowserError::Other(format!("Chrome download failed: {}", e)))?;
+
+        let version = match (detect_version(&info.executable_path).await, pin) {
+            (Ok(version), _) => version,
+            (Err(e), Some(_)) => return Err(e),
+            // Without a pin, an unreadable version (e.g. on Windows) isn't fatal
+            (Err(e), None) => {
+                tracing::warn!(error = %e, "Could not determine downloaded Chrome version");
+                "unknown".to_string()
+            }
+        };
+        if let Some(pin) = pin {
+            if !pin.matches(&version) {
+                return Err(BrowserError::Other(format!(
+                    "Downloaded Chrome {} does not satisfy pinned version {}. \
+                     Install a matching build manually and set chrome.path instead.",
+                    version, pin
+                )));
+            }
+        }
+
+        let version_dir = self.root.join(&version);
+        if tokio::fs::try_exists(&version_dir).await.unwrap_or(false) {
+            tokio::fs::remove_dir_all(&version_dir).await.map_err(|e| {
+                BrowserError::Other(format!("Failed to replace {}: {}", version, e))
+            })?;
+        }
+        tokio::fs::rename(staging, &version_dir)
+            .await
+            .map_err(|e| BrowserError::Other(format!("Failed to move Chrome into cache: {}", e)))?;
+
+        let relative = info
+            .executable_path
+            .strip_prefix(staging)
+            .unwrap_or(&info.executable_path);
+        let executable = version_dir.join(relative);
+
+        let chrome = InstalledChrome {
+            sha256: hash_file(&executable).await?,
+            executable,
+            version,
+            installed_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        let manifest = serde_json::to_string_pretty(&chrome)
+            .map_err(|e| BrowserError::Other(format!("Failed to serialize manifest: {}", e)))?;
+        tokio::fs::write(version_dir.join(MANIFEST_FILE), manifest)
+            .await
+            .map_err(|e| BrowserError::Other(format!("Failed to write manifest: {}", e)))?;
+
+        tracing::info!(version = %chrome.version, path = %chrome.executable.display(), "Chrome installed");
+        Ok(chrome)
+    }
+
+    /// Remove an installed version; returns false if it wasn't installed
+    pub async fn remove(&self, version: &str) -> Result<bool> {
+        let dir = self.root.join(version);
+        if version.is_empty() || version.starts_with('.') || !dir.join(MANIFEST_FILE).exists() {
+            return Ok(false);
+        }
+
+        tokio::fs::remove_dir_all(&dir).await.map_err(|e| {
+            BrowserError::Other(format!("Failed to remove Chrome {}: {}", version, e))
+        })?;
+        Ok(true)
+    }
+}
+
+/// Check that an installed executable still matches its recorded hash
+pub async fn verify(chrome: &InstalledChrome) -> Result<()> {
+    let actual = hash_file(&chrome.executable).await?;
+    if actual != chrome.sha256 {
+        return Err(BrowserError::Other(format!(
+            "Chrome {} failed integrity check (expected sha256 {}, found {})",
+            chrome.version, chrome.sha256, actual
+        )));
+    }
+    Ok(())
+}
+
+/// Read the version from `chrome --version` (e.g. "Google Chrome for Testing 128.0.6613.119")
+async fn detect_version(executable: &Path) -> Result<String> {
+    let output = tokio::process::Command::new(executable)
+        .arg("--version")
+        .output()
+        .await
+        .map_err(|e| BrowserError::Other(format!("Failed to run Chrome --version: {}", e)))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_version_output(&stdout).ok_or_else(|| {
+        BrowserError::Other(format!(
+            "Could not determine Chrome version from '{}'",
+            stdout.trim()
+        ))
+    })
+}
+
+fn parse_version_output(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .find(|word| {
+            word.contains('.')
+                && word
+                    .split('.')
+                    .all(|p| !p.is_empty() && p.parse::<u32>().is_ok())
+        })
+        .map(str::to_string)
+}
+
+/// Compare dotted version strings numerically
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parse = |v: &str| -> Vec<u32> { v.split('.').filter_map(|p| p.parse().ok()).collect() };
+    parse(a).cmp(&parse(b))
+}
+
+async fn hash_file(path: &Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let contents = tokio::fs::read(path)
+        .await
+        .map_err(|e| BrowserError::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(&contents);
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_pin_matching() {
+        let pin: VersionPin = "128.0.x".parse().unwrap();
+        assert!(pin.matches("128.0.6613.119"));
+        assert!(!pin.matches("129.0.6613.119"));
+        assert_eq!(pin.to_string(), "128.0.x");
+
+        assert!("128".parse::<VersionPin>().unwrap().matches("128.1.2.3"));
+        assert!("128.0.6613.119"
+            .parse::<VersionPin>()
+            .unwrap()
+            .matches("128.0.6613.119"));
+        assert!(!"128.0.6613.120"
+            .parse::<VersionPin>()
+            .unwrap()
+            .matches("128.0.6613.119"));
+
+        assert!("latest".parse::<VersionPin>().is_err());
+        assert!("".parse::<VersionPin>().is_err());
+    }
+
+    #[test]
+    fn test_parse_version_output() {
+        assert_eq!(
+            parse_version_output("Google Chrome for Testing 128.0.6613.119 \n"),
+            Some("128.0.6613.119".to_string())
+        );
+        assert_eq!(parse_version_output("garbage"), None);
+        assert_eq!(
+            compare_versions("128.0.10.0", "128.0.9.0"),
+            std::cmp::Ordering::Greater
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_verify_and_remove() {
+        let root = std::env::temp_dir().join(format!(
+            "robert-chrome-test-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let installer = ChromeInstaller::new(&root);
+        assert!(installer.list().await.unwrap().is_empty());
+
+        // Fake two installs
+        for version in ["127.0.1.0", "128.0.2.0"] {
+            let dir = root.join(version);
+            tokio::fs::create_dir_all(&dir).await.unwrap();
+            let executable = dir.join("chrome");
+            tokio::fs::write(&executable, version).await.unwrap();
+            let chrome = InstalledChrome {
+                version: version.to_string(),
+                sha256: hash_file(&executable).await.unwrap(),
+                executable,
+                installed_at: chrono::Utc::now().to_rfc3339(),
+            };
+            tokio::fs::write(
+                dir.join(MANIFEST_FILE),
+                serde_json::to_string(&chrome).unwrap(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let installed = installer.list().await.unwrap();
+        assert_eq!(installed.len(), 2);
+        assert_eq!(installed[0].version, "128.0.2.0");
+
+        let pin: VersionPin = "127.x".parse().unwrap();
+        let found = installer.find(Some(&pin)).await.unwrap().unwrap();
+        assert_eq!(found.version, "127.0.1.0");
+        assert!(verify(&found).await.is_ok());
+
+        // Tampering is detected
+        tokio::fs::write(&found.executable, "modified")
+            .await
+            .unwrap();
+        assert!(verify(&found).await.is_err());
+
+        assert!(installer.remove("127.0.1.0").await.unwrap());
+        assert!(!installer.remove("127.0.1.0").await.unwrap());
+        assert_eq!(installer.list().await.unwrap().len(), 1);
+
+        let _ = tokio::fs::remove_dir_all(&root).await;
+    }
+}
@@ -0,0 +1,383 @@
+//! Broken Link and Asset Checker
+//!
+//! Audits one page load for things that are broken: subresources (images,
+//! scripts, stylesheets, fonts, XHR, frames) that answered with a 4xx/5xx status
+//! or failed to load, seen through `Network` events while the page loads; and
+//! anchor targets, checked with `HEAD` requests (falling back to `GET` for
+//! servers that reject `HEAD`) outside the browser.
+//!
+//! A [`LinkAuditor`] is attached before navigating and finished after the load:
+//!
+//! ```ignore
+//! let auditor = LinkAuditor::attach(&page).await?;
+//! let navigation = driver.navigate(url).await?;
+//! let report = auditor.finish(&page, &navigation, &LinkCheckOptions::default()).await?;
+//! ```
+//!
+//! `ChromeDriver::audit_links` does all three steps.
+
+use super::navigation::NavigationResult;
+use crate::crawl::origin;
+use crate::error::{BrowserError, Result};
+use chromiumoxide::cdp::browser_protocol::network::{
+    self, EventLoadingFailed, EventRequestWillBeSent, EventResponseReceived, RequestId,
+    ResourceType,
+};
+use chromiumoxide::page::Page;
+use futures::stream::{BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Anchors on the page as `{href, text}`; `a.href` is already absolute
+const ANCHORS_SCRIPT: &str = "Array.from(document.querySelectorAll('a[href]'), \
+     (a) => ({ href: a.href, text: a.textContent.trim().slice(0, 100) }))";
+
+/// How long to keep listening after the load event for late subresources
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// User agent of the anchor target checks
+const CHECK_USER_AGENT: &str = "robert-webdriver";
+
+/// What a link audit checks
+#[derive(Debug, Clone)]
+pub struct LinkCheckOptions {
+    /// Check anchor targets with HEAD requests (subresources are always checked)
+    pub check_anchors: bool,
+
+    /// Only check anchors on the page's own origin
+    pub same_origin_only: bool,
+
+    /// Anchor checks in flight at once
+    pub concurrency: usize,
+
+    /// Timeout of each anchor check
+    pub timeout: Duration,
+}
+
+impl Default for LinkCheckOptions {
+    fn default() -> Self {
+        Self {
+            check_anchors: true,
+            same_origin_only: false,
+            concurrency: 8,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// A subresource that failed while the page loaded
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BrokenResource {
+    pub url: String,
+
+    /// Resource type as Chrome reports it (e.g. `Image`, `Script`, `Stylesheet`)
+    pub resource_type: String,
+
+    /// HTTP status, for 4xx/5xx responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i64>,
+
+    /// Network error (e.g. `net::ERR_NAME_NOT_RESOLVED`), for failed loads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// An anchor whose target answered with a 4xx/5xx status or couldn't be reached
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BrokenLink {
+    /// Target URL (without fragment)
+    pub url: String,
+
+    /// Link text of the first anchor pointing at the target
+    pub text: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of auditing one page
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkReport {
+    /// Page URL once loaded
+    pub url: String,
+
+    /// HTTP status of the page itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i64>,
+
+    /// Failed subresources, in the order they failed
+    pub resources: Vec<BrokenResource>,
+
+    /// Broken anchor targets, sorted by URL
+    pub links: Vec<BrokenLink>,
+
+    /// Distinct anchor targets checked
+    pub links_checked: usize,
+}
+
+impl LinkReport {
+    /// Whether the page, its subresources, and its anchor targets all loaded
+    pub fn is_clean(&self) -> bool {
+        self.resources.is_empty()
+            && self.links.is_empty()
+            && self.status.is_none_or(|status| status < 400)
+    }
+}
+
+/// Records failed subresources of a page until finished
+///
+/// Recording stops when the auditor is dropped.
+pub struct LinkAuditor {
+    failures: Arc<Mutex<Vec<BrokenResource>>>,
+    task: JoinHandle<()>,
+}
+
+impl LinkAuditor {
+    /// Start recording failed requests of `page`; attach before navigating
+    pub async fn attach(page: &Page) -> Result<Self> {
+        page.execute(network::EnableParams::default()).await?;
+
+        let events: BoxStream<'static, Event> = futures::stream::select_all([
+            page.event_listener::<EventRequestWillBeSent>()
+                .await?
+                .map(Event::Request)
+                .boxed(),
+            page.event_listener::<EventResponseReceived>()
+                .await?
+                .map(Event::Response)
+                .boxed(),
+            page.event_listener::<EventLoadingFailed>()
+                .await?
+                .map(Event::Failed)
+                .boxed(),
+        ])
+        .boxed();
+
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let task = tokio::spawn(record(events, failures.clone()));
+        Ok(Self { failures, task })
+    }
+
+    /// Stop recording and check the page's anchors; `navigation` is the load being audited
+    pub async fn finish(
+        self,
+        page: &Page,
+        navigation: &NavigationResult,
+        options: &LinkCheckOptions,
+    ) -> Result<LinkReport> {
+        tokio::time::sleep(SETTLE).await;
+        self.task.abort();
+        let resources: Vec<BrokenResource> = self
+            .failures
+            .lock()
+            .unwrap()
+            .drain(..)
+            // The page itself is reported as the navigation status
+            .filter(|failure| {
+                !(failure.resource_type == ResourceType::Document.as_ref()
+                    && failure.url == navigation.url)
+            })
+            .collect();
+
+        let mut report = LinkReport {
+            url: navigation.url.clone(),
+            status: navigation.status,
+            resources,
+            links: Vec::new(),
+            links_checked: 0,
+        };
+        if !options.check_anchors {
+            return Ok(report);
+        }
+
+        let anchors: Vec<Anchor> = page
+            .evaluate(ANCHORS_SCRIPT)
+            .await?
+            .into_value()
+            .map_err(|e| BrowserError::Other(format!("Failed to read anchors: {}", e)))?;
+        let targets = anchor_targets(anchors, &navigation.url, options.same_origin_only);
+        report.links_checked = targets.len();
+        report.links = check_targets(targets, options).await;
+        Ok(report)
+    }
+}
+
+impl Drop for LinkAuditor {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Anchor {
+    href: String,
+    text: String,
+}
+
+enum Event {
+    Request(Arc<EventRequestWillBeSent>),
+    Response(Arc<EventResponseReceived>),
+    Failed(Arc<EventLoadingFailed>),
+}
+
+async fn record(mut events: BoxStream<'static, Event>, failures: Arc<Mutex<Vec<BrokenResource>>>) {
+    // URLs of requests in flight, for naming failed loads
+    let mut requests: HashMap<RequestId, String> = HashMap::new();
+
+    while let Some(event) = events.next().await {
+        match event {
+            Event::Request(event) => {
+                requests.insert(event.request_id.clone(), event.request.url.clone());
+            }
+            Event::Response(event) => {
+                requests.remove(&event.request_id);
+                if event.response.status >= 400 {
+                    failures.lock().unwrap().push(BrokenResource {
+                        url: event.response.url.clone(),
+                        resource_type: event.r#type.as_ref().to_string(),
+                        status: Some(event.response.status),
+                        error: None,
+                    });
+                }
+            }
+            Event::Failed(event) => {
+                let url = requests.remove(&event.request_id);
+                if event.canceled == Some(true) {
+                    continue;
+                }
+                if let Some(url) = url {
+                    failures.lock().unwrap().push(BrokenResource {
+                        url,
+                        resource_type: event.r#type.as_ref().to_string(),
+                        status: None,
+                        error: Some(event.error_text.clone()),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Distinct http(s) anchor targets without fragments, with the first anchor's text
+fn anchor_targets(
+    anchors: Vec<Anchor>,
+    page_url: &str,
+    same_origin_only: bool,
+) -> Vec<(String, String)> {
+    let page_origin = origin(page_url);
+    let mut seen = HashSet::new();
+    anchors
+        .into_iter()
+        .filter_map(|anchor| {
+            let url = anchor
+                .href
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let url_origin = origin(&url)?;
+            if same_origin_only && Some(url_origin) != page_origin {
+                return None;
+            }
+            seen.insert(url.clone()).then_some((url, anchor.text))
+        })
+        .collect()
+}
+
+async fn check_targets(
+    targets: Vec<(String, String)>,
+    options: &LinkCheckOptions,
+) -> Vec<BrokenLink> {
+    let client = match reqwest::Client::builder()
+        .timeout(options.timeout)
+        .user_agent(CHECK_USER_AGENT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to build link check client");
+            return Vec::new();
+        }
+    };
+
+    let mut broken: Vec<BrokenLink> = futures::stream::iter(targets)
+        .map(|(url, text)| {
+            let client = client.clone();
+            async move {
+                let (status, error) = match check_target(&client, &url).await {
+                    Ok(status) if status < 400 => return None,
+                    Ok(status) => (Some(status), None),
+                    Err(e) => (None, Some(e.to_string())),
+                };
+                tracing::debug!(url, ?status, ?error, "Broken link");
+                Some(BrokenLink {
+                    url,
+                    text,
+                    status,
+                    error,
+                })
+            }
+        })
+        .buffer_unordered(options.concurrency.max(1))
+        .filter_map(futures::future::ready)
+        .collect()
+        .await;
+    broken.sort_by(|a, b| a.url.cmp(&b.url));
+    broken
+}
+
+/// Status of `url`, asking with GET when the server doesn't support HEAD
+async fn check_target(client: &reqwest::Client, url: &str) -> reqwest::Result<u16> {
+    let status = client.head(url).send().await?.status();
+    if status == reqwest::StatusCode::METHOD_NOT_ALLOWED
+        || status == reqwest::StatusCode::NOT_IMPLEMENTED
+    {
+        return Ok(client.get(url).send().await?.status().as_u16());
+    }
+    Ok(status.as_u16())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchor_targets() {
+        let anchor = |href: &str, text: &str| Anchor {
+            href: href.to_string(),
+            text: text.to_string(),
+        };
+        let anchors = vec![
+            anchor("https://example.com/a#top", "A"),
+            anchor("https://example.com/a", "A again"),
+            anchor("mailto:team@example.com", "Mail"),
+            anchor("javascript:void(0)", "Menu"),
+            anchor("https://other.example/b", "B"),
+        ];
+
+        let targets = anchor_targets(anchors, "https://example.com/", false);
+        assert_eq!(
+            targets,
+            vec![
+                ("https://example.com/a".to_string(), "A".to_string()),
+                ("https://other.example/b".to_string(), "B".to_string()),
+            ]
+        );
+
+        let same_origin = anchor_targets(
+            vec![
+                anchor("https://example.com/a", "A"),
+                anchor("https://other.example/b", "B"),
+            ],
+            "https://example.com/page",
+            true,
+        );
+        assert_eq!(same_origin.len(), 1);
+    }
+}
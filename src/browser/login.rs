@@ -0,0 +1,414 @@
+//! Login Flow Helper
+//!
+//! Fills and submits a username/password form, optionally answers a 2FA prompt
+//! with a code from a caller-supplied hook, and waits for a success condition.
+//! Every step auto-waits for its element to be visible and enabled, and
+//! credentials and codes are never logged.
+//!
+//! ```no_run
+//! use robert_webdriver::browser::login::{LoginFlow, SuccessCondition};
+//! # async fn example(driver: &robert_webdriver::ChromeDriver) -> robert_webdriver::error::Result<()> {
+//! let outcome = LoginFlow::new("alice", std::env::var("APP_PASSWORD").unwrap_or_default())
+//!     .url("https://app.example.com/login")
+//!     .username_selector("#email")
+//!     .password_selector("#password")
+//!     .submit_selector("button[type=submit]")
+//!     .failure_selector(".login-error")
+//!     .two_factor("input[name=otp]", || async { Ok("123456".to_string()) })
+//!     .success(SuccessCondition::UrlContains("/dashboard".to_string()))
+//!     .execute(driver)
+//!     .await?;
+//! println!("Logged in at {}", outcome.url);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::chrome::ChromeDriver;
+use crate::error::{BrowserError, Result};
+use chromiumoxide::page::Page;
+use futures::future::BoxFuture;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Placeholder logged instead of secret values
+const MASK: &str = "********";
+
+/// Hook that supplies a one-time code (TOTP, SMS, email) when a 2FA prompt appears
+pub type TwoFactorHook = Arc<dyn Fn() -> BoxFuture<'static, anyhow::Result<String>> + Send + Sync>;
+
+/// How to tell that the login succeeded
+#[derive(Debug, Clone)]
+pub enum SuccessCondition {
+    /// The page URL contains this text
+    UrlContains(String),
+    /// An element matching this selector is visible
+    ElementVisible(String),
+    /// A JavaScript expression evaluates to a truthy value
+    Expression(String),
+}
+
+/// Result of a successful login
+#[derive(Debug, Clone)]
+pub struct LoginOutcome {
+    /// Page URL once the success condition was met
+    pub url: String,
+
+    /// Whether a 2FA code was requested and submitted
+    pub two_factor_used: bool,
+
+    /// Time from filling the form to success
+    pub duration: Duration,
+}
+
+struct TwoFactorStep {
+    code_selector: String,
+    submit_selector: Option<String>,
+    hook: TwoFactorHook,
+}
+
+/// Builder for a username/password login
+pub struct LoginFlow {
+    url: Option<String>,
+    username: String,
+    password: String,
+    username_selector: String,
+    password_selector: String,
+    submit_selector: Option<String>,
+    failure_selector: Option<String>,
+    success: Option<SuccessCondition>,
+    two_factor: Option<TwoFactorStep>,
+    timeout: Duration,
+    poll_interval: Duration,
+}
+
+impl std::fmt::Debug for LoginFlow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoginFlow")
+            .field("url", &self.url)
+            .field("username", &self.username)
+            .field("password", &MASK)
+            .field("username_selector", &self.username_selector)
+            .field("password_selector", &self.password_selector)
+            .field("submit_selector", &self.submit_selector)
+            .field("failure_selector", &self.failure_selector)
+            .field("success", &self.success)
+            .field(
+                "two_factor",
+                &self.two_factor.as_ref().map(|t| &t.code_selector),
+            )
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl LoginFlow {
+    /// Login with these credentials, using common selectors until overridden
+    ///
+    /// Defaults: username `input[type=email], input[name=username], input[name=email]`,
+    /// password `input[type=password]`, submit by pressing Enter in the password field,
+    /// 30 second timeout per step.
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            url: None,
+            username: username.into(),
+            password: password.into(),
+            username_selector: "input[type=email], input[name=username], input[name=email]"
+                .to_string(),
+            password_selector: "input[type=password]".to_string(),
+            submit_selector: None,
+            failure_selector: None,
+            success: None,
+            two_factor: None,
+            timeout: Duration::from_secs(30),
+            poll_interval: Duration::from_millis(100),
+        }
+    }
+
+    /// Navigate to this URL before filling the form (default: use the current page)
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// Selector for the username/email field
+    pub fn username_selector(mut self, selector: impl Into<String>) -> Self {
+        self.username_selector = selector.into();
+        self
+    }
+
+    /// Selector for the password field
+    pub fn password_selector(mut self, selector: impl Into<String>) -> Self {
+        self.password_selector = selector.into();
+        self
+    }
+
+    /// Selector for the submit button (default: press Enter in the password field)
+    pub fn submit_selector(mut self, selector: impl Into<String>) -> Self {
+        self.submit_selector = Some(selector.into());
+        self
+    }
+
+    /// Selector for an error message; the login fails fast if it becomes visible
+    pub fn failure_selector(mut self, selector: impl Into<String>) -> Self {
+        self.failure_selector = Some(selector.into());
+        self
+    }
+
+    /// Condition that marks the login as successful
+    ///
+    /// Without one, the login succeeds once the password field is gone.
+    pub fn success(mut self, condition: SuccessCondition) -> Self {
+        self.success = Some(condition);
+        self
+    }
+
+    /// Answer a 2FA prompt: when `code_selector` becomes visible, `hook` is called
+    /// for a code, which is typed in and submitted with Enter
+    pub fn two_factor<F, Fut>(mut self, code_selector: impl Into<String>, hook: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<String>> + Send + 'static,
+    {
+        self.two_factor = Some(TwoFactorStep {
+            code_selector: code_selector.into(),
+            submit_selector: None,
+            hook: Arc::new(move || Box::pin(hook())),
+        });
+        self
+    }
+
+    /// Selector for the 2FA submit button (default: press Enter in the code field)
+    ///
+    /// Has no effect unless [`two_factor`](Self::two_factor) was called first.
+    pub fn two_factor_submit_selector(mut self, selector: impl Into<String>) -> Self {
+        if let Some(step) = &mut self.two_factor {
+            step.submit_selector = Some(selector.into());
+        }
+        self
+    }
+
+    /// Maximum time to wait for each element and for the success condition
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run the login on `driver`'s current page
+    ///
+    /// Fails with `BrowserError::ElementNotFound` if a form field never becomes
+    /// usable, or `BrowserError::LoginFailed` if the failure selector appears, the
+    /// 2FA hook fails, or the success condition isn't met within the timeout.
+    #[tracing::instrument(
+        name = "login",
+        skip(self, driver),
+        fields(session_id = %driver.session_id(), username = %self.username)
+    )]
+    pub async fn execute(&self, driver: &ChromeDriver) -> Result<LoginOutcome> {
+        if let Some(url) = &self.url {
+            driver.navigate(url).await?;
+        }
+        let page = driver.current_page().await?;
+        let start = Instant::now();
+
+        tracing::info!(password = MASK, "Filling login form");
+        self.fill(&page, &self.username_selector, &self.username)
+            .await?;
+        self.fill(&page, &self.password_selector, &self.password)
+            .await?;
+        self.submit(
+            &page,
+            self.submit_selector.as_deref(),
+            &self.password_selector,
+        )
+        .await?;
+
+        let mut two_factor_used = false;
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            if self.succeeded(&page).await {
+                let url = driver.current_url().await.unwrap_or_default();
+                tracing::info!(url = %url, two_factor_used, "Login succeeded");
+                return Ok(LoginOutcome {
+                    url,
+                    two_factor_used,
+                    duration: start.elapsed(),
+                });
+            }
+
+            if let Some(selector) = &self.failure_selector {
+                if element_state(&page, selector).await == Some(ElementState::Ready) {
+                    let message = driver.get_element_text(selector).await.unwrap_or_default();
+                    tracing::warn!(message = %message.trim(), "Login rejected");
+                    return Err(BrowserError::LoginFailed(format!(
+                        "page reported an error: {}",
+                        message.trim()
+                    )));
+                }
+            }
+
+            if let Some(step) = self.two_factor.as_ref().filter(|_| !two_factor_used) {
+                if element_state(&page, &step.code_selector).await == Some(ElementState::Ready) {
+                    tracing::info!(code = MASK, "2FA code requested");
+                    let code = (step.hook)().await.map_err(|e| {
+                        BrowserError::LoginFailed(format!("2FA code hook failed: {:#}", e))
+                    })?;
+                    self.fill(&page, &step.code_selector, &code).await?;
+                    self.submit(&page, step.submit_selector.as_deref(), &step.code_selector)
+                        .await?;
+                    two_factor_used = true;
+                    continue;
+                }
+            }
+
+            if Instant::now() >= deadline {
+                return Err(BrowserError::LoginFailed(format!(
+                    "success condition not met within {}s",
+                    self.timeout.as_secs()
+                )));
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Wait for `selector` to be usable, then replace its value with `value`
+    async fn fill(&self, page: &Page, selector: &str, value: &str) -> Result<()> {
+        self.wait_ready(page, selector).await?;
+        tracing::debug!(selector, "Filling field");
+
+        let element = page
+            .find_element(selector)
+            .await
+            .map_err(|_| BrowserError::ElementNotFound(selector.to_string()))?;
+        element.click().await?;
+        // Select any existing text so the inserted value replaces it
+        element
+            .call_js_fn(
+                "function() { this.focus(); if (this.select) this.select(); }",
+                false,
+            )
+            .await?;
+        super::ime::insert_text(page, value).await?;
+        Ok(())
+    }
+
+    /// Click `submit_selector`, or press Enter in `field_selector` if there is none
+    async fn submit(
+        &self,
+        page: &Page,
+        submit_selector: Option<&str>,
+        field_selector: &str,
+    ) -> Result<()> {
+        let selector = submit_selector.unwrap_or(field_selector);
+        self.wait_ready(page, selector).await?;
+        tracing::debug!(selector, "Submitting");
+
+        let element = page
+            .find_element(selector)
+            .await
+            .map_err(|_| BrowserError::ElementNotFound(selector.to_string()))?;
+        if submit_selector.is_some() {
+            element.click().await?;
+        } else {
+            element.press_key("Enter").await?;
+        }
+        Ok(())
+    }
+
+    /// Poll until `selector` is attached, visible, and enabled
+    async fn wait_ready(&self, page: &Page, selector: &str) -> Result<()> {
+        let deadline = Instant::now() + self.timeout;
+        let mut state = None;
+        while Instant::now() < deadline {
+            state = element_state(page, selector).await;
+            if state == Some(ElementState::Ready) {
+                return Ok(());
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+
+        let reason = match state {
+            Some(ElementState::Hidden) => "not visible",
+            Some(ElementState::Disabled) => "disabled",
+            _ => "not found",
+        };
+        Err(BrowserError::ElementNotFound(format!(
+            "{} ({} after {}s)",
+            selector,
+            reason,
+            self.timeout.as_secs()
+        )))
+    }
+
+    /// Whether the success condition currently holds
+    async fn succeeded(&self, page: &Page) -> bool {
+        let expression = match &self.success {
+            Some(SuccessCondition::UrlContains(text)) => format!(
+                "window.location.href.includes({})",
+                serde_json::to_string(text).unwrap_or_default()
+            ),
+            Some(SuccessCondition::ElementVisible(selector)) => {
+                return element_state(page, selector).await == Some(ElementState::Ready);
+            }
+            Some(SuccessCondition::Expression(expression)) => format!("!!({})", expression),
+            None => format!(
+                "!document.querySelector({})",
+                serde_json::to_string(&self.password_selector).unwrap_or_default()
+            ),
+        };
+
+        match page.evaluate(expression).await {
+            Ok(result) => result.into_value::<bool>().unwrap_or(false),
+            // The page may be mid-navigation after submit; try again next poll
+            Err(e) => {
+                tracing::debug!(error = %e, "Success check failed");
+                false
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ElementState {
+    Missing,
+    Hidden,
+    Disabled,
+    Ready,
+}
+
+/// Current state of the first element matching `selector` (None if the page
+/// couldn't be queried, e.g. during navigation)
+async fn element_state(page: &Page, selector: &str) -> Option<ElementState> {
+    let expression = format!(
+        r#"(() => {{
+            const el = document.querySelector({});
+            if (!el) return 'missing';
+            const rect = el.getBoundingClientRect();
+            const style = window.getComputedStyle(el);
+            if (rect.width === 0 || rect.height === 0 || style.visibility === 'hidden' || style.display === 'none') return 'hidden';
+            if (el.disabled) return 'disabled';
+            return 'ready';
+        }})()"#,
+        serde_json::to_string(selector).ok()?
+    );
+
+    page.evaluate(expression).await.ok()?.into_value().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_masks_password() {
+        let flow = LoginFlow::new("alice", "hunter2")
+            .password_selector("#pw")
+            .two_factor("#otp", || async { Ok("123456".to_string()) });
+        let debug = format!("{:?}", flow);
+        assert!(debug.contains("alice"));
+        assert!(debug.contains("#otp"));
+        assert!(!debug.contains("hunter2"));
+        assert!(debug.contains(MASK));
+    }
+}
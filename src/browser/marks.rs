@@ -0,0 +1,138 @@
+//! Set-of-Marks Overlays
+//!
+//! Vision models are bad at pointing: asked where the "Sign in" button is, they
+//! guess coordinates, or describe an element that several on the page match.
+//! Set-of-marks prompting draws a numbered box on every interactive element before
+//! the screenshot is taken, so the model can answer with a label instead, and the
+//! [`Mark`]s returned with the overlay map each label back to a selector and a box.
+//!
+//! The overlay is a single fixed-position element above the page that ignores
+//! pointer events, so it doesn't change layout and [`remove_marks`] takes it away
+//! without a trace. Only elements that are visible, in the viewport, and not covered
+//! by something else get a label.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::page::Page;
+
+pub use crate::model::frame::{marks_legend, Mark};
+
+/// Most elements labelled in one overlay (beyond this, labels crowd each other out)
+pub const MAX_MARKS: usize = 200;
+
+/// Draw labels on the interactive elements in view, returning them in label order
+///
+/// Text of elements inside (or containing) a match of `redacted` is masked in the
+/// returned marks. Replaces any overlay already on the page.
+pub async fn add_marks(page: &Page, redacted: &[String]) -> Result<Vec<Mark>> {
+    let redacted = serde_json::to_string(redacted).unwrap_or_else(|_| "[]".to_string());
+    let script = format!("{}({}, {})", MARK_SCRIPT.trim_end(), redacted, MAX_MARKS);
+    page.evaluate(script)
+        .await
+        .map_err(|e| BrowserError::Other(format!("Failed to draw marks: {}", e)))?
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Failed to read marks: {}", e)))
+}
+
+/// Take the overlay drawn by [`add_marks`] off the page (a no-op if there is none)
+pub async fn remove_marks(page: &Page) -> Result<()> {
+    page.evaluate(UNMARK_SCRIPT)
+        .await
+        .map_err(|e| BrowserError::Other(format!("Failed to remove marks: {}", e)))?;
+    Ok(())
+}
+
+/// Draws the overlay; called with the redacted selectors and the label limit
+const MARK_SCRIPT: &str = r#"
+((redacted, limit) => {
+    document.getElementById('__robert_marks')?.remove();
+    const candidates = document.querySelectorAll([
+        'a[href]', 'button', 'input:not([type=hidden])', 'select', 'textarea', 'summary',
+        '[role=button]', '[role=link]', '[role=checkbox]', '[role=radio]', '[role=switch]',
+        '[role=tab]', '[role=menuitem]', '[role=option]', '[role=combobox]', '[role=textbox]',
+        '[contenteditable=""]', '[contenteditable=true]', '[onclick]', '[tabindex]:not([tabindex="-1"])',
+    ].join(','));
+    const colors = ['#e6194b', '#3cb44b', '#4363d8', '#f58231', '#911eb4', '#008080', '#9a6324', '#800000'];
+    const width = window.innerWidth;
+    const height = window.innerHeight;
+    const mask = (s) => s.replace(/\S/g, '█');
+    const selectorOf = (target) => {
+        const parts = [];
+        for (let el = target; el && el !== document.documentElement; el = el.parentElement) {
+            if (el.id && /^[A-Za-z][\w-]*$/.test(el.id) && document.querySelectorAll('#' + el.id).length === 1) {
+                parts.unshift('#' + el.id);
+                break;
+            }
+            const tag = el.tagName.toLowerCase();
+            const same = el.parentElement
+                ? Array.from(el.parentElement.children).filter((c) => c.tagName === el.tagName)
+                : [el];
+            parts.unshift(same.length > 1 ? `${tag}:nth-of-type(${same.indexOf(el) + 1})` : tag);
+        }
+        if (!parts.length || !parts[0].startsWith('#')) parts.unshift('html');
+        return parts.join(' > ');
+    };
+    const nameOf = (el, tag) => {
+        const field = tag === 'input' || tag === 'textarea' || tag === 'select';
+        const button = tag === 'input' && ['submit', 'button', 'reset'].includes(el.type);
+        const name = el.getAttribute('aria-label')
+            || (button ? el.value : field ? el.placeholder || el.name : el.innerText)
+            || el.title
+            || el.querySelector?.('img[alt]')?.alt
+            || '';
+        return name.trim().replace(/\s+/g, ' ').slice(0, 100);
+    };
+
+    const overlay = document.createElement('div');
+    overlay.id = '__robert_marks';
+    overlay.style.cssText = 'position:fixed;inset:0;pointer-events:none;z-index:2147483647;';
+    const marked = new Set();
+    const marks = [];
+    for (const el of candidates) {
+        if (marks.length >= limit) break;
+        if (el.disabled) continue;
+        // A clickable child of a labelled element (an icon in a link) needs no label of its own
+        let inside = false;
+        for (let p = el.parentElement; p && !inside; p = p.parentElement) inside = marked.has(p);
+        if (inside) continue;
+
+        const box = el.getBoundingClientRect();
+        if (box.width < 1 || box.height < 1) continue;
+        if (box.right <= 0 || box.bottom <= 0 || box.left >= width || box.top >= height) continue;
+        const style = window.getComputedStyle(el);
+        if (style.visibility === 'hidden' || style.visibility === 'collapse' || style.opacity === '0') continue;
+        // Skip elements something else is drawn over
+        const x = Math.min(Math.max(box.left + box.width / 2, 0), width - 1);
+        const y = Math.min(Math.max(box.top + box.height / 2, 0), height - 1);
+        const top = document.elementFromPoint(x, y);
+        if (top && !el.contains(top) && !top.contains(el)) continue;
+
+        marked.add(el);
+        const label = marks.length + 1;
+        const tag = el.tagName.toLowerCase();
+        const hidden = redacted.some((s) => el.closest(s) || el.querySelector(s));
+        const text = nameOf(el, tag);
+        marks.push({
+            label,
+            selector: selectorOf(el),
+            tag,
+            text: hidden ? mask(text) : text,
+            bounds: { x: box.x, y: box.y, width: box.width, height: box.height },
+        });
+
+        const color = colors[label % colors.length];
+        const frame = document.createElement('div');
+        frame.style.cssText = `position:absolute;left:${box.left}px;top:${box.top}px;`
+            + `width:${box.width}px;height:${box.height}px;box-sizing:border-box;border:2px solid ${color};`;
+        const tab = document.createElement('span');
+        tab.textContent = String(label);
+        tab.style.cssText = `position:absolute;left:-2px;top:${box.top >= 16 ? -16 : -2}px;`
+            + `background:${color};color:#fff;font:bold 11px/14px monospace;padding:0 3px;border-radius:2px;`;
+        frame.appendChild(tab);
+        overlay.appendChild(frame);
+    }
+    document.documentElement.appendChild(overlay);
+    return marks;
+})
+"#;
+
+const UNMARK_SCRIPT: &str = "document.getElementById('__robert_marks')?.remove(); true";
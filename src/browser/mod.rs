@@ -1,5 +1,79 @@
+pub mod accessibility;
+pub mod captcha;
 pub mod chat;
 pub mod chrome;
+pub mod consent;
+pub mod container;
+pub mod crash;
+pub mod detect;
+pub mod determinism;
+pub mod display;
+pub mod driver;
+pub mod emulation;
+pub mod form;
+pub mod geometry;
+pub mod ime;
+pub mod install;
+pub mod link_check;
+pub mod login;
+pub mod marks;
+pub mod navigation;
+pub mod overlay;
+pub mod page_lock;
+pub mod pagination;
+pub mod performance;
+pub mod pool;
+pub mod response_tap;
+pub mod rewrite;
+pub mod security;
+pub mod selector;
+pub mod seo;
+pub mod service_worker;
+pub mod text;
+pub mod touch;
+pub mod tunnel;
+pub mod widgets;
 
-pub use chat::{ChatMessage, ChatUI};
+pub use accessibility::{
+    AccessibilityOptions, AccessibilityReport, Impact, Violation, ViolationNode,
+};
+pub use captcha::{
+    CaptchaEvent, CaptchaHandler, CaptchaKind, CaptchaResolution, ChatCaptchaHandler, FailOnCaptcha,
+};
+pub use chat::{
+    ChatAction, ChatFieldKind, ChatFormField, ChatImage, ChatMessage, ChatUI, UserFeedback,
+};
 pub use chrome::ChromeDriver;
+pub use consent::{ConsentChoice, CookieConsent, DismissedBanner};
+pub use crash::{CrashMonitor, CrashReport, PageEvent, PageEventKind};
+pub use detect::{detect_browsers, BrowserKind, DetectedBrowser};
+pub use determinism::DeterministicRendering;
+pub use driver::Driver;
+pub use emulation::PseudoState;
+pub use form::{
+    FieldOption, FillResult, FormCheck, FormField, FormSchema, FormValue, InvalidField,
+};
+pub use geometry::{ElementRect, NodeAtPoint, Rect};
+pub use install::{ChromeInstaller, InstalledChrome, VersionPin};
+pub use link_check::{BrokenLink, BrokenResource, LinkAuditor, LinkCheckOptions, LinkReport};
+pub use login::{LoginFlow, LoginOutcome, SuccessCondition};
+pub use marks::{marks_legend, Mark};
+pub use navigation::{HistoryNavigation, NavigationResult};
+pub use overlay::{HandledOverlay, Overlay, OverlayHandling, OverlayOutcome, OverlayPolicy};
+pub use page_lock::PageLock;
+pub use pagination::{PagedResults, Pagination, PaginationEnd, PaginationKind};
+pub use performance::{
+    MetricScore, PageTimings, PerformanceReport, ResourceSummary, RuntimeMetrics,
+};
+pub use pool::{BrowserPool, PooledDriver, ScriptOutcome};
+pub use response_tap::{ResponseTap, TappedResponse};
+pub use rewrite::{BodyRewrite, PatchOperation, ResponseRewriter, RewriteRule};
+pub use security::{CertificateInfo, SecurityInfo};
+pub use selector::{MatchSource, SelectorCandidates, SelectorMatch};
+pub use seo::{SeoIssue, SeoMetadata, SeoRules};
+pub use service_worker::ServiceWorkerRegistration;
+pub use text::{TextHit, TextQuery};
+pub use tunnel::SshTunnel;
+pub use widgets::{
+    ComboboxStrategy, ContentEditableStrategy, ScriptStrategy, WidgetRegistry, WidgetStrategy,
+};
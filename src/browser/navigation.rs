@@ -0,0 +1,287 @@
+//! Navigation Results
+//!
+//! `Page.navigate` succeeds whenever a document commits, including a 404 page, a
+//! 500 error from the server, or Chrome's own error page. To tell those apart the
+//! driver watches `Network.responseReceived` for the navigation's main document
+//! (matched by loader id) and reports its status in a [`NavigationResult`].
+//!
+//! Back, forward, and reload ([`HistoryNavigation`]) start with commands that return
+//! before anything commits (`Page.navigateToHistoryEntry`, `Page.reload`), so
+//! [`navigate_history`] also waits for the main frame to navigate, which gives the
+//! loader id of the new document.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::cdp::browser_protocol::network::{
+    self, EventResponseReceived, LoaderId, ResourceType,
+};
+use chromiumoxide::cdp::browser_protocol::page::{
+    EventFrameNavigated, EventNavigatedWithinDocument, GetNavigationHistoryParams,
+    GetNavigationHistoryReturns, NavigateToHistoryEntryParams, NavigationEntry, ReloadParams,
+};
+use chromiumoxide::listeners::EventStream;
+use chromiumoxide::page::Page;
+use futures::{FutureExt, StreamExt};
+use serde::Serialize;
+use std::time::Duration;
+
+/// URL prefix of the page Chrome shows when a document fails to load
+const ERROR_PAGE_PREFIX: &str = "chrome-error://";
+
+/// How long to wait for a main-document response that hasn't arrived by the load event
+const RESPONSE_GRACE: Duration = Duration::from_secs(1);
+
+/// Outcome of a navigation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NavigationResult {
+    /// Page URL once loaded (after redirects)
+    pub url: String,
+
+    /// HTTP status of the main document (None for `about:`, `data:`, `file:`, and
+    /// same-document navigations)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i64>,
+
+    /// HTTP status text of the main document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_text: Option<String>,
+
+    /// MIME type of the main document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+impl NavigationResult {
+    /// Whether the server answered with a 4xx or 5xx status
+    pub fn is_http_error(&self) -> bool {
+        self.status.is_some_and(|status| status >= 400)
+    }
+
+    /// Whether Chrome is showing its own error page instead of the document
+    pub fn is_error_page(&self) -> bool {
+        self.url.starts_with(ERROR_PAGE_PREFIX)
+    }
+}
+
+/// Main-document responses seen while a navigation is in flight
+pub(crate) struct DocumentResponses {
+    responses: EventStream<EventResponseReceived>,
+}
+
+impl DocumentResponses {
+    /// Start listening; call before `Page.navigate` so the response isn't missed
+    pub(crate) async fn listen(page: &Page) -> Result<Self> {
+        page.execute(network::EnableParams::default()).await?;
+        Ok(Self {
+            responses: page.event_listener::<EventResponseReceived>().await?,
+        })
+    }
+
+    /// Result for the navigation started by `loader_id`, once the page has loaded
+    pub(crate) async fn finish(
+        mut self,
+        page: &Page,
+        loader_id: Option<&LoaderId>,
+    ) -> NavigationResult {
+        let url = page.url().await.ok().flatten().unwrap_or_default();
+        let mut result = NavigationResult {
+            url,
+            status: None,
+            status_text: None,
+            mime_type: None,
+        };
+        let Some(loader_id) = loader_id else {
+            return result;
+        };
+
+        let mut document = None;
+        while let Some(Some(event)) = self.responses.next().now_or_never() {
+            if is_document(&event, loader_id) {
+                document = Some(event);
+            }
+        }
+        if document.is_none() && result.url.starts_with("http") {
+            let wait = async {
+                while let Some(event) = self.responses.next().await {
+                    if is_document(&event, loader_id) {
+                        return Some(event);
+                    }
+                }
+                None
+            };
+            document = tokio::time::timeout(RESPONSE_GRACE, wait)
+                .await
+                .ok()
+                .flatten();
+        }
+
+        if let Some(event) = document {
+            result.status = Some(event.response.status);
+            result.status_text = Some(event.response.status_text.clone());
+            result.mime_type = Some(event.response.mime_type.clone());
+        }
+        result
+    }
+}
+
+fn is_document(event: &EventResponseReceived, loader_id: &LoaderId) -> bool {
+    event.r#type == ResourceType::Document && &event.loader_id == loader_id
+}
+
+/// A move through a tab's session history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryNavigation {
+    Back,
+    Forward,
+    /// Load the current entry again, bypassing the cache if `ignore_cache`
+    Reload {
+        ignore_cache: bool,
+    },
+}
+
+impl HistoryNavigation {
+    /// Position of the entry it moves to, relative to the current one
+    fn offset(self) -> i64 {
+        match self {
+            Self::Back => -1,
+            Self::Forward => 1,
+            Self::Reload { .. } => 0,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Back => "back",
+            Self::Forward => "forward",
+            Self::Reload { .. } => "reload",
+        }
+    }
+}
+
+/// History entry `offset` entries from the current one, if there is one
+fn entry_at(history: &GetNavigationHistoryReturns, offset: i64) -> Option<&NavigationEntry> {
+    let index = usize::try_from(history.current_index + offset).ok()?;
+    history.entries.get(index)
+}
+
+/// Start `navigation` on `page` and wait up to `timeout` for the main frame to commit it
+///
+/// Returns the loader id of the committed document, or None if the navigation stayed
+/// in the same document (an entry added by a fragment link or `history.pushState`).
+/// Fails with `BrowserError::NavigationFailed` if there is no entry to go back or
+/// forward to.
+pub(crate) async fn navigate_history(
+    page: &Page,
+    navigation: HistoryNavigation,
+    timeout: Duration,
+) -> Result<Option<LoaderId>> {
+    let main_frame = page.mainframe().await?;
+    let mut documents = page.event_listener::<EventFrameNavigated>().await?;
+    let mut within_document = page
+        .event_listener::<EventNavigatedWithinDocument>()
+        .await?;
+
+    match navigation {
+        HistoryNavigation::Reload { ignore_cache } => {
+            let params = ReloadParams::builder().ignore_cache(ignore_cache).build();
+            page.execute(params).await?;
+        }
+        HistoryNavigation::Back | HistoryNavigation::Forward => {
+            let history = page.execute(GetNavigationHistoryParams::default()).await?;
+            let entry = entry_at(&history, navigation.offset()).ok_or_else(|| {
+                BrowserError::NavigationFailed(format!(
+                    "Can't go {}: no {} entry in the tab's history",
+                    navigation.name(),
+                    if navigation == HistoryNavigation::Back {
+                        "previous"
+                    } else {
+                        "next"
+                    }
+                ))
+            })?;
+            tracing::info!(url = %entry.url, entry_id = entry.id, "Navigating through history");
+            page.execute(NavigateToHistoryEntryParams::new(entry.id))
+                .await?;
+        }
+    }
+
+    let committed = async {
+        loop {
+            tokio::select! {
+                Some(event) = documents.next() => {
+                    if event.frame.parent_id.is_none() {
+                        return Some(event.frame.loader_id.clone());
+                    }
+                }
+                Some(event) = within_document.next() => {
+                    if main_frame.as_ref().is_none_or(|id| id == &event.frame_id) {
+                        return None;
+                    }
+                }
+                else => return None,
+            }
+        }
+    };
+    tokio::time::timeout(timeout, committed).await.map_err(|_| {
+        BrowserError::NavigationFailed(format!(
+            "Timed out after {}s waiting for the {} navigation to start",
+            timeout.as_secs(),
+            navigation.name()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_classification() {
+        let mut result = NavigationResult {
+            url: "https://example.com/missing".to_string(),
+            status: Some(404),
+            status_text: Some("Not Found".to_string()),
+            mime_type: Some("text/html".to_string()),
+        };
+        assert!(result.is_http_error());
+        assert!(!result.is_error_page());
+
+        result.status = Some(302);
+        assert!(!result.is_http_error());
+        result.status = None;
+        result.url = "chrome-error://chromewebdata/".to_string();
+        assert!(!result.is_http_error());
+        assert!(result.is_error_page());
+    }
+
+    #[test]
+    fn test_history_entries() {
+        let entry = |id: i64, url: &str| NavigationEntry {
+            id,
+            url: url.to_string(),
+            user_typed_url: url.to_string(),
+            title: String::new(),
+            transition_type: chromiumoxide::cdp::browser_protocol::page::TransitionType::Link,
+        };
+        let history = GetNavigationHistoryReturns::new(
+            1,
+            vec![
+                entry(3, "https://shop.test/"),
+                entry(7, "https://shop.test/cart"),
+                entry(9, "https://shop.test/checkout"),
+            ],
+        );
+        let id = |navigation: HistoryNavigation| {
+            entry_at(&history, navigation.offset()).map(|entry| entry.id)
+        };
+        assert_eq!(id(HistoryNavigation::Back), Some(3));
+        assert_eq!(id(HistoryNavigation::Forward), Some(9));
+        assert_eq!(
+            id(HistoryNavigation::Reload { ignore_cache: true }),
+            Some(7)
+        );
+
+        let first = GetNavigationHistoryReturns::new(0, history.entries[..1].to_vec());
+        assert!(entry_at(&first, HistoryNavigation::Back.offset()).is_none());
+        assert!(entry_at(&first, HistoryNavigation::Forward.offset()).is_none());
+    }
+}
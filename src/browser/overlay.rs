@@ -0,0 +1,550 @@
+//! Overlays
+//!
+//! Newsletter pop-ups, interstitial ads, app-install prompts, and login walls sit
+//! above the page in a fixed-position layer: screenshots show them instead of the
+//! page, and clicks land on them instead of their target. [`detect_overlays`] finds
+//! such layers by sampling what is drawn on top across the viewport, keeping
+//! fixed-position ones that cover at least half of it with a high z-index.
+//! [`handle_overlays`] then applies an [`OverlayPolicy`]: click the overlay's close
+//! button (or press Escape), hide it with CSS, or only report it.
+//!
+//! Driver overlays (set-of-marks labels, the chat UI) are never reported.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::cdp::browser_protocol::input::{DispatchKeyEventParams, DispatchKeyEventType};
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// How long a dismissed overlay gets to go away (close animations)
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Time between checks for a dismissed overlay
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Texts and labels of buttons that close an overlay, compared whole
+const CLOSE_TEXTS: &[&str] = &[
+    "×",
+    "✕",
+    "✖",
+    "x",
+    "close",
+    "dismiss",
+    "no thanks",
+    "no, thanks",
+    "not now",
+    "maybe later",
+    "skip",
+    "continue to site",
+    "schließen",
+    "fermer",
+    "cerrar",
+    "chiudi",
+    "sluiten",
+    "fechar",
+    "zamknij",
+    "stäng",
+    "luk",
+    "lukk",
+    "sulje",
+];
+
+/// Words in labels of buttons that close an overlay ("Close dialog", "Dismiss ad")
+const CLOSE_WORDS: &[&str] = &[
+    "close",
+    "dismiss",
+    "schließen",
+    "fermer",
+    "cerrar",
+    "chiudi",
+    "sluiten",
+    "fechar",
+    "zamknij",
+];
+
+/// What to do with overlays
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlayPolicy {
+    /// Click the overlay's close button, or press Escape if it has none
+    #[default]
+    Dismiss,
+    /// Hide the overlay with CSS (and let the page scroll again)
+    Hide,
+    /// Leave the overlay, only report it
+    Report,
+}
+
+impl std::str::FromStr for OverlayPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "dismiss" => Ok(Self::Dismiss),
+            "hide" => Ok(Self::Hide),
+            "report" => Ok(Self::Report),
+            other => Err(format!(
+                "Unknown overlay policy '{}' (expected: dismiss, hide, report)",
+                other
+            )),
+        }
+    }
+}
+
+/// Settings for finding and handling overlays
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlayHandling {
+    pub policy: OverlayPolicy,
+
+    /// Smallest fraction of the viewport a layer must cover to count as an overlay
+    pub min_coverage: f64,
+
+    /// Lowest z-index a layer must have to count as an overlay (fixed app layouts
+    /// that fill the viewport usually have none)
+    pub min_z_index: i64,
+
+    /// With [`OverlayPolicy::Dismiss`], hide overlays that are still open afterwards
+    pub hide_if_not_dismissed: bool,
+}
+
+impl Default for OverlayHandling {
+    fn default() -> Self {
+        Self {
+            policy: OverlayPolicy::Dismiss,
+            min_coverage: 0.5,
+            min_z_index: 10,
+            hide_if_not_dismissed: true,
+        }
+    }
+}
+
+/// A layer covering the page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Overlay {
+    /// CSS selector of the layer
+    pub selector: String,
+
+    /// Lowercase tag name
+    pub tag: String,
+
+    /// Start of its visible text (and that of layers drawn above it)
+    pub text: String,
+
+    /// Fraction of the viewport it covers
+    pub coverage: f64,
+
+    pub z_index: i64,
+
+    /// Text or label of the button that would close it
+    pub close_button: Option<String>,
+}
+
+/// What happened to an overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayOutcome {
+    /// Its close button was clicked and it went away
+    Closed,
+    /// Escape was pressed and it went away
+    Escaped,
+    /// It was hidden with CSS
+    Hidden,
+    /// Dismissing it didn't make it go away
+    StillOpen,
+    /// It was left as it was ([`OverlayPolicy::Report`])
+    Reported,
+}
+
+/// An overlay and what [`handle_overlays`] did with it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HandledOverlay {
+    pub overlay: Overlay,
+    pub outcome: OverlayOutcome,
+}
+
+/// A layer as found on the page, with the buttons that may close it
+#[derive(Debug, Clone, Deserialize)]
+struct Layer {
+    selector: String,
+    tag: String,
+    text: String,
+    coverage: f64,
+    z_index: i64,
+    buttons: Vec<LayerButton>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct LayerButton {
+    /// Index in the page's list of overlay buttons
+    index: usize,
+    text: String,
+    /// `aria-label` or `title`
+    label: String,
+    /// Whether its id, class, or `data-dismiss` attribute says it closes something
+    close_hint: bool,
+}
+
+impl Layer {
+    fn overlay(&self) -> (Overlay, Option<usize>) {
+        let close = close_button(&self.buttons);
+        let overlay = Overlay {
+            selector: self.selector.clone(),
+            tag: self.tag.clone(),
+            text: self.text.clone(),
+            coverage: self.coverage,
+            z_index: self.z_index,
+            close_button: close.map(|button| {
+                let name = if button.label.is_empty() {
+                    &button.text
+                } else {
+                    &button.label
+                };
+                name.clone()
+            }),
+        };
+        (overlay, close.map(|button| button.index))
+    }
+}
+
+/// Overlays covering the page, topmost first
+pub async fn detect_overlays(page: &Page, settings: &OverlayHandling) -> Result<Vec<Overlay>> {
+    Ok(find_layers(page, settings, None)
+        .await?
+        .iter()
+        .map(|layer| layer.overlay().0)
+        .collect())
+}
+
+/// Find the overlays covering the page and apply `settings.policy` to each
+pub async fn handle_overlays(
+    page: &Page,
+    settings: &OverlayHandling,
+) -> Result<Vec<HandledOverlay>> {
+    apply(page, settings, find_layers(page, settings, None).await?).await
+}
+
+/// Apply `settings.policy` to an overlay that would catch a click at (`x`, `y`)
+///
+/// Overlays are left alone if the point is on a link, button, or form control of
+/// theirs: clicking inside a dialog is more likely meant than intercepted.
+pub async fn handle_overlays_at(
+    page: &Page,
+    settings: &OverlayHandling,
+    x: f64,
+    y: f64,
+) -> Result<Vec<HandledOverlay>> {
+    apply(
+        page,
+        settings,
+        find_layers(page, settings, Some((x, y))).await?,
+    )
+    .await
+}
+
+async fn apply(
+    page: &Page,
+    settings: &OverlayHandling,
+    layers: Vec<Layer>,
+) -> Result<Vec<HandledOverlay>> {
+    let mut handled = Vec::with_capacity(layers.len());
+    for (index, layer) in layers.iter().enumerate() {
+        let (overlay, close) = layer.overlay();
+        let outcome = match settings.policy {
+            OverlayPolicy::Report => OverlayOutcome::Reported,
+            OverlayPolicy::Hide => {
+                hide(page, index).await?;
+                OverlayOutcome::Hidden
+            }
+            OverlayPolicy::Dismiss => {
+                let outcome = dismiss(page, index, close).await?;
+                if outcome == OverlayOutcome::StillOpen && settings.hide_if_not_dismissed {
+                    hide(page, index).await?;
+                    OverlayOutcome::Hidden
+                } else {
+                    outcome
+                }
+            }
+        };
+        tracing::info!(
+            overlay = %overlay.selector,
+            coverage = overlay.coverage,
+            outcome = ?outcome,
+            "Handled overlay"
+        );
+        handled.push(HandledOverlay { overlay, outcome });
+    }
+    Ok(handled)
+}
+
+/// Overlays on the page (only the one catching clicks at `point`, if given)
+async fn find_layers(
+    page: &Page,
+    settings: &OverlayHandling,
+    point: Option<(f64, f64)>,
+) -> Result<Vec<Layer>> {
+    let point = point.map_or(serde_json::Value::Null, |(x, y)| serde_json::json!([x, y]));
+    page.evaluate(format!(
+        "({})({}, {}, {})",
+        DETECT_SCRIPT, settings.min_coverage, settings.min_z_index, point
+    ))
+    .await?
+    .into_value()
+    .map_err(|e| BrowserError::Other(format!("Failed to read overlays: {}", e)))
+}
+
+/// Click the close button (or press Escape) and wait for the overlay to go away
+async fn dismiss(page: &Page, layer: usize, close: Option<usize>) -> Result<OverlayOutcome> {
+    match close {
+        Some(button) => {
+            page.evaluate(format!("({})({})", CLICK_SCRIPT, button))
+                .await?;
+            if closed(page, layer).await {
+                return Ok(OverlayOutcome::Closed);
+            }
+        }
+        None => {
+            for kind in [DispatchKeyEventType::KeyDown, DispatchKeyEventType::KeyUp] {
+                page.execute(
+                    DispatchKeyEventParams::builder()
+                        .r#type(kind)
+                        .key("Escape")
+                        .code("Escape")
+                        .windows_virtual_key_code(27)
+                        .build()
+                        .map_err(BrowserError::Other)?,
+                )
+                .await?;
+            }
+            if closed(page, layer).await {
+                return Ok(OverlayOutcome::Escaped);
+            }
+        }
+    }
+    Ok(OverlayOutcome::StillOpen)
+}
+
+/// Whether the layer went away within [`CLOSE_TIMEOUT`]
+async fn closed(page: &Page, layer: usize) -> bool {
+    let deadline = Instant::now() + CLOSE_TIMEOUT;
+    while Instant::now() < deadline {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let open = match page.evaluate(format!("({})({})", OPEN_SCRIPT, layer)).await {
+            Ok(open) => open.into_value::<bool>().unwrap_or(true),
+            // The close button navigated
+            Err(_) => return true,
+        };
+        if !open {
+            return true;
+        }
+    }
+    false
+}
+
+async fn hide(page: &Page, layer: usize) -> Result<()> {
+    page.evaluate(format!("({})({})", HIDE_SCRIPT, layer))
+        .await?;
+    Ok(())
+}
+
+/// The button most likely to close a layer: one named like a close button, or
+/// failing that, one whose class or id says so
+fn close_button(buttons: &[LayerButton]) -> Option<&LayerButton> {
+    let normalize = |s: &str| {
+        s.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    let named = |button: &&LayerButton| {
+        let text = normalize(&button.text);
+        let label = normalize(&button.label);
+        CLOSE_TEXTS.contains(&text.as_str())
+            || CLOSE_TEXTS.contains(&label.as_str())
+            || label
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|word| CLOSE_WORDS.contains(&word))
+    };
+    buttons
+        .iter()
+        .find(named)
+        .or_else(|| buttons.iter().find(|button| button.close_hint))
+}
+
+/// Samples the topmost element across the viewport and returns the fixed-position
+/// layers above the minimum coverage and z-index, with the buttons in them and in
+/// the layers drawn above them; keeps the elements in `window.__robert_overlays`.
+/// Given a point, returns only the layer drawn there, unless the point is on one of
+/// its controls.
+const DETECT_SCRIPT: &str = r#"(minCoverage, minZ, point) => {
+    const width = window.innerWidth;
+    const height = window.innerHeight;
+    const ours = (el) => /^(__)?robert[-_]/.test(el.id || '');
+    const zOf = (el) => parseInt(window.getComputedStyle(el).zIndex, 10) || 0;
+    // Outermost fixed-position ancestor of what is drawn at a point
+    const layerAt = (x, y) => {
+        let layer = null;
+        for (let el = document.elementFromPoint(x, y); el && el !== document.body && el !== document.documentElement; el = el.parentElement) {
+            if (ours(el)) return null;
+            if (window.getComputedStyle(el).position === 'fixed') layer = el;
+        }
+        return layer;
+    };
+    const coverage = (el) => {
+        const box = el.getBoundingClientRect();
+        const w = Math.max(0, Math.min(box.right, width) - Math.max(box.left, 0));
+        const h = Math.max(0, Math.min(box.bottom, height) - Math.max(box.top, 0));
+        return (w * h) / (width * height);
+    };
+    const selectorOf = (target) => {
+        const parts = [];
+        for (let el = target; el && el !== document.documentElement; el = el.parentElement) {
+            if (el.id && /^[A-Za-z][\w-]*$/.test(el.id) && document.querySelectorAll('#' + el.id).length === 1) {
+                parts.unshift('#' + el.id);
+                break;
+            }
+            const tag = el.tagName.toLowerCase();
+            const same = el.parentElement
+                ? Array.from(el.parentElement.children).filter((c) => c.tagName === el.tagName)
+                : [el];
+            parts.unshift(same.length > 1 ? `${tag}:nth-of-type(${same.indexOf(el) + 1})` : tag);
+        }
+        if (!parts.length || !parts[0].startsWith('#')) parts.unshift('html');
+        return parts.join(' > ');
+    };
+    const visible = (el) => {
+        const box = el.getBoundingClientRect();
+        const style = window.getComputedStyle(el);
+        return box.width >= 1 && box.height >= 1 && style.visibility !== 'hidden' && style.opacity !== '0';
+    };
+
+    const layers = [];
+    for (const [fx, fy] of [[0.5, 0.5], [0.1, 0.1], [0.9, 0.1], [0.1, 0.9], [0.9, 0.9], [0.5, 0.15], [0.5, 0.85]]) {
+        const layer = layerAt(width * fx, height * fy);
+        if (layer && !layers.includes(layer)) layers.push(layer);
+    }
+    let overlays = layers
+        .filter((layer) => coverage(layer) >= minCoverage && zOf(layer) >= minZ)
+        .sort((a, b) => zOf(b) - zOf(a));
+    if (point) {
+        const target = document.elementFromPoint(point[0], point[1]);
+        const control = target?.closest('a, button, input, select, textarea, label, summary, [role=button], [role=link], [onclick], [contenteditable]');
+        const hit = layerAt(point[0], point[1]);
+        overlays = overlays.filter((overlay) => !control && (hit === overlay
+            || (hit && !overlays.includes(hit) && zOf(hit) >= zOf(overlay))));
+    }
+
+    const groups = [];
+    const buttons = [];
+    const result = overlays.map((overlay) => {
+        // A modal's dialog is often a sibling drawn above its backdrop
+        const group = [overlay, ...layers.filter((l) => l !== overlay && !overlays.includes(l) && zOf(l) >= zOf(overlay))];
+        groups.push(group);
+        const found = [];
+        for (const layer of group) {
+            for (const el of layer.querySelectorAll('button, a, [role=button], [aria-label], [class*=close i], [id*=close i], [data-dismiss], [data-bs-dismiss]')) {
+                if (!visible(el) || buttons.includes(el)) continue;
+                const href = el.tagName === 'A' ? el.getAttribute('href') || '' : '';
+                if (href && !href.startsWith('#') && !href.startsWith('javascript:')) continue;
+                const text = (el.innerText || '').replace(/\s+/g, ' ').trim();
+                if (text.length > 40) continue;
+                const names = `${el.id} ${typeof el.className === 'string' ? el.className : ''}`;
+                found.push({
+                    index: buttons.length,
+                    text,
+                    label: el.getAttribute('aria-label') || el.getAttribute('title') || '',
+                    close_hint: /close|dismiss/i.test(names) || el.hasAttribute('data-dismiss') || el.hasAttribute('data-bs-dismiss'),
+                });
+                buttons.push(el);
+            }
+        }
+        return {
+            selector: selectorOf(overlay),
+            tag: overlay.tagName.toLowerCase(),
+            text: group.map((l) => l.innerText || '').join(' ').replace(/\s+/g, ' ').trim().slice(0, 200),
+            coverage: Math.round(coverage(overlay) * 100) / 100,
+            z_index: zOf(overlay),
+            buttons: found,
+        };
+    });
+    window.__robert_overlays = { groups, buttons };
+    return result;
+}"#;
+
+/// Clicks a button listed by [`DETECT_SCRIPT`]
+const CLICK_SCRIPT: &str = r#"(index) => {
+    const el = window.__robert_overlays?.buttons[index];
+    if (el) el.click();
+    return !!el;
+}"#;
+
+/// Whether a layer listed by [`DETECT_SCRIPT`] is still shown
+const OPEN_SCRIPT: &str = r#"(index) => {
+    const el = window.__robert_overlays?.groups[index]?.[0];
+    if (!el || !el.isConnected) return false;
+    const box = el.getBoundingClientRect();
+    const style = window.getComputedStyle(el);
+    return box.width >= 1 && box.height >= 1 && style.visibility !== 'hidden' && style.display !== 'none'
+        && style.opacity !== '0';
+}"#;
+
+/// Hides a layer listed by [`DETECT_SCRIPT`] (and the layers above it), and undoes
+/// the scroll lock overlays put on the page
+const HIDE_SCRIPT: &str = r#"(index) => {
+    for (const el of window.__robert_overlays?.groups[index] || []) {
+        el.style.setProperty('display', 'none', 'important');
+    }
+    for (const el of [document.documentElement, document.body]) {
+        if (el && window.getComputedStyle(el).overflowY === 'hidden') {
+            el.style.setProperty('overflow', 'auto', 'important');
+        }
+    }
+    return true;
+}"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button(index: usize, text: &str, label: &str) -> LayerButton {
+        LayerButton {
+            index,
+            text: text.to_string(),
+            label: label.to_string(),
+            close_hint: false,
+        }
+    }
+
+    #[test]
+    fn test_close_button() {
+        let buttons = [
+            button(0, "Subscribe", ""),
+            button(1, "", "Close dialog"),
+            button(2, "No thanks", ""),
+        ];
+        assert_eq!(close_button(&buttons).unwrap().index, 1);
+        assert_eq!(close_button(&buttons[2..]).unwrap().index, 2);
+        assert_eq!(close_button(&[button(0, " × ", "")]).unwrap().index, 0);
+        assert_eq!(close_button(&[button(0, "Fermer", "")]).unwrap().index, 0);
+
+        // Icon buttons are found by their class
+        let icon = LayerButton {
+            close_hint: true,
+            ..button(3, "", "")
+        };
+        assert_eq!(
+            close_button(&[button(0, "Sign up", ""), icon])
+                .unwrap()
+                .index,
+            3
+        );
+        assert!(close_button(&[button(0, "Closed on Sundays", "")]).is_none());
+        assert!(close_button(&[]).is_none());
+    }
+
+    #[test]
+    fn test_policy_from_str() {
+        assert_eq!("hide".parse::<OverlayPolicy>(), Ok(OverlayPolicy::Hide));
+        assert!("close".parse::<OverlayPolicy>().is_err());
+    }
+}
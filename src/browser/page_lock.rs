@@ -0,0 +1,194 @@
+//! Serializing Commands per Page
+//!
+//! Most driver operations are sequences of CDP commands: a set-of-marks screenshot
+//! labels the page, captures it, and removes the labels; typing focuses, clears,
+//! and dispatches keys; navigation waits for the load and then answers banners.
+//! Run two of them on the same page at once (`tokio::join!` on one driver, or two
+//! tasks sharing it) and their commands interleave, so one screenshots the other's
+//! half-typed text or scrolls the page out from under it.
+//!
+//! Each page therefore has a [`PageLock`], and the driver runs every operation that
+//! changes the page (navigation, input, script evaluation, emulation, captures)
+//! under it. Waiters are served first come, first served. Operations that only read
+//! (`title`, `current_url`, `get_page_source`, element rects, detection, ...) don't
+//! take it and run alongside.
+//!
+//! Holding a page's lock is reentrant within a task: driver methods called inside
+//! [`PageLock::run`] on the same page don't wait on it again, so callers can make
+//! their own multi-step sequence atomic. [`PageLock::parallel`] is the opt-out: the
+//! driver methods called inside skip the lock for that page, for reads the caller
+//! knows are safe to run concurrently with whatever holds it.
+//!
+//! Commands sent through a [`Page`](chromiumoxide::page::Page) handle directly don't
+//! go through the lock; wrap them in [`ChromeDriver::page_lock`](super::ChromeDriver::page_lock)
+//! `.run(...)` to queue them with the driver's operations. Scripts run by a
+//! [`CdpExecutor`](crate::cdp::CdpExecutor) with a driver take the lock of the page
+//! each command runs against (a popup, once the script switches to it) for the command
+//! and its overlay and cookie banner handling, but not while a captcha handler waits
+//! for the captcha the command surfaced.
+//!
+//! Two things deadlock:
+//!
+//! - Reentrancy is per task. A task spawned inside [`PageLock::run`] doesn't hold
+//!   the lock, so if the operation awaits it and it calls a driver method on the
+//!   same page, each waits for the other. Await such work outside `run`, or have the
+//!   spawned task use [`PageLock::parallel`].
+//! - Locks are not ordered. Two operations that each hold one page's lock and then
+//!   take the other's wait for each other forever; take several pages' locks in
+//!   the same order everywhere (the driver's own methods only take one).
+
+use chromiumoxide::cdp::browser_protocol::target::TargetId;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+tokio::task_local! {
+    /// Pages the current task holds (or has opted out of) the lock of
+    static HELD: Vec<TargetId>;
+}
+
+/// Command lock of one page; clones share the lock
+#[derive(Debug, Clone)]
+pub struct PageLock {
+    target: TargetId,
+    // tokio's mutex queues waiters in order, which makes the lock fair
+    mutex: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl PageLock {
+    /// Target id of the page
+    pub fn target(&self) -> &TargetId {
+        &self.target
+    }
+
+    /// Whether the current task holds this lock (or is inside [`parallel`](Self::parallel))
+    pub fn is_held(&self) -> bool {
+        HELD.try_with(|held| held.contains(&self.target))
+            .unwrap_or(false)
+    }
+
+    /// Whether some task is running an operation on the page
+    pub fn is_locked(&self) -> bool {
+        self.mutex.try_lock().is_err()
+    }
+
+    /// Run `operation` once every operation queued on the page before it has finished,
+    /// keeping the page to itself until it completes
+    ///
+    /// Driver methods called inside on the same page go ahead without waiting.
+    pub async fn run<F: Future>(&self, operation: F) -> F::Output {
+        if self.is_held() {
+            return operation.await;
+        }
+        let _guard = self.mutex.lock().await;
+        self.scope(operation).await
+    }
+
+    /// Run `operation` with the driver's methods ignoring this lock, even while
+    /// another task holds it
+    ///
+    /// For reads that can safely interleave with other commands. The lock is not
+    /// taken, so nothing else waits for `operation` either.
+    pub async fn parallel<F: Future>(&self, operation: F) -> F::Output {
+        if self.is_held() {
+            return operation.await;
+        }
+        self.scope(operation).await
+    }
+
+    /// Run `operation` with this page marked as held by the task
+    async fn scope<F: Future>(&self, operation: F) -> F::Output {
+        let mut held = HELD.try_with(Vec::clone).unwrap_or_default();
+        held.push(self.target.clone());
+        HELD.scope(held, operation).await
+    }
+}
+
+/// The [`PageLock`]s of a driver's pages
+#[derive(Debug, Default)]
+pub struct PageLocks {
+    locks: Mutex<HashMap<TargetId, PageLock>>,
+}
+
+impl PageLocks {
+    /// Lock of the page with `target` id
+    pub fn get(&self, target: &TargetId) -> PageLock {
+        let mut locks = self.locks.lock().unwrap();
+        // A lock nobody else holds a handle to is free and has no waiters, so it
+        // can be dropped and recreated when needed; this forgets closed pages
+        locks.retain(|id, lock| id == target || Arc::strong_count(&lock.mutex) > 1);
+        locks
+            .entry(target.clone())
+            .or_insert_with(|| PageLock {
+                target: target.clone(),
+                mutex: Arc::new(tokio::sync::Mutex::new(())),
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_operations_do_not_interleave() {
+        let locks = PageLocks::default();
+        let page = TargetId::new("page-1");
+        let log = Mutex::new(Vec::new());
+
+        let operation = |name: &'static str| {
+            let lock = locks.get(&page);
+            let log = &log;
+            async move {
+                lock.run(async {
+                    log.lock().unwrap().push(format!("{} start", name));
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    log.lock().unwrap().push(format!("{} end", name));
+                })
+                .await
+            }
+        };
+        let read = async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            // Reads opting out run while `a` holds the lock
+            locks
+                .get(&page)
+                .parallel(async { log.lock().unwrap().push("read".to_string()) })
+                .await
+        };
+        tokio::join!(operation("a"), operation("b"), operation("c"), read);
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["a start", "read", "a end", "b start", "b end", "c start", "c end"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reentrant_and_per_page() {
+        let locks = PageLocks::default();
+        let (first, second) = (TargetId::new("page-1"), TargetId::new("page-2"));
+        let lock = locks.get(&first);
+        assert!(!lock.is_held());
+
+        let nested = tokio::time::timeout(
+            Duration::from_secs(1),
+            lock.run(async {
+                assert!(lock.is_held() && lock.is_locked());
+                // Same page again in the same task, and another page
+                locks.get(&first).run(async {}).await;
+                locks.get(&second).run(async { 42 }).await
+            }),
+        )
+        .await;
+        assert_eq!(nested.ok(), Some(42));
+        assert!(!lock.is_locked());
+
+        // Unused locks are forgotten
+        drop(lock);
+        locks.get(&second);
+        assert_eq!(locks.locks.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,451 @@
+//! Pagination
+//!
+//! Scraping a listing usually means extracting a page, finding the "Next" link,
+//! clicking it, waiting for the new results, and doing it again until there is no
+//! next page. [`detect_pagination`] finds how the current page is paginated (a next
+//! link or button, a numbered pager, a "Load more" button, or infinite scroll), and
+//! [`for_each_page`] runs an extraction on each page in turn and collects the results:
+//!
+//! ```no_run
+//! # async fn example(driver: &robert_webdriver::ChromeDriver) -> robert_webdriver::error::Result<()> {
+//! let titles = driver
+//!     .for_each_page(5, |_, page| async move {
+//!         let titles: Vec<String> = page
+//!             .evaluate("Array.from(document.querySelectorAll('h2'), (h) => h.innerText)")
+//!             .await?
+//!             .into_value()
+//!             .unwrap_or_default();
+//!         Ok(titles)
+//!     })
+//!     .await?;
+//! println!("{} pages, ended by {:?}", titles.urls.len(), titles.end);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A page counts as turned once its URL or text changes, whether the control
+//! navigated or re-rendered the results in place. "Load more" buttons and infinite
+//! scroll add to the results already shown, so extractions on those pages see the
+//! earlier items again.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the next page once its control is clicked (or the page scrolled)
+const PAGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time between checks for the next page
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Time given to a changed page to finish rendering before it is extracted
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// Labels of controls that go to the next page
+const NEXT_LABELS: &[&str] = &[
+    "next",
+    "next page",
+    "next »",
+    "next ›",
+    "next >",
+    "older",
+    "older posts",
+    "›",
+    "»",
+    "→",
+    ">",
+];
+
+/// Labels of controls that add more results to the page
+const MORE_LABELS: &[&str] = &[
+    "load more",
+    "show more",
+    "view more",
+    "see more",
+    "more results",
+    "more",
+];
+
+/// How a page is paginated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaginationKind {
+    /// A "Next" link or button (`rel="next"`, or labelled so)
+    NextButton,
+    /// Page number links, with or without a "Next" control
+    NumberedPager,
+    /// A "Load more" button that adds results to the page
+    LoadMore,
+    /// No controls, but the page scrolls (more results may load at the bottom)
+    InfiniteScroll,
+}
+
+/// Pagination of the current page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pagination {
+    pub kind: PaginationKind,
+
+    /// Selector of the control that goes to the next page (None on the last page of
+    /// a numbered pager, and for infinite scroll)
+    pub next: Option<String>,
+
+    /// Number of the current page, if a numbered pager shows it
+    pub current_page: Option<u32>,
+
+    /// Highest page number a numbered pager links to
+    pub last_page: Option<u32>,
+}
+
+/// Why [`for_each_page`] stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaginationEnd {
+    /// `max_pages` pages were extracted
+    MaxPages,
+    /// There is no control for a next page
+    LastPage,
+    /// The control was used (or the page scrolled), but nothing changed in time
+    NoChange,
+}
+
+/// Results of [`for_each_page`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PagedResults<T> {
+    /// What each page's extraction returned, in page order
+    pub results: Vec<T>,
+
+    /// URL of each page when it was extracted
+    pub urls: Vec<String>,
+
+    pub end: PaginationEnd,
+}
+
+/// A control found on the page that may be part of its pagination
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+struct Control {
+    selector: String,
+    /// Visible text
+    text: String,
+    /// `aria-label` or `title`
+    label: String,
+    rel: String,
+    /// Whether the class of the control (or its list item) mentions "next"
+    next_class: bool,
+    disabled: bool,
+    /// Whether the control is inside a pagination container
+    in_pager: bool,
+    /// The page number it shows, if its text is one
+    number: Option<u32>,
+    /// Whether it marks the current page
+    current: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Controls {
+    controls: Vec<Control>,
+    scrollable: bool,
+}
+
+/// Find how the current page is paginated (None if it isn't)
+pub async fn detect_pagination(page: &Page) -> Result<Option<Pagination>> {
+    let found: Controls = page
+        .evaluate(CONTROLS_SCRIPT)
+        .await?
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Failed to read pagination controls: {}", e)))?;
+    Ok(classify(&found.controls, found.scrollable))
+}
+
+/// Run `extract` on the current page and the pages after it, up to `max_pages` pages
+///
+/// `extract` gets the page's index (from 0) and the page. Pagination is detected
+/// again on every page, so pagers that re-render keep working. Stops early on the
+/// last page, or when the next page doesn't appear within 10 seconds; an error from
+/// `extract` stops it and is returned.
+pub async fn for_each_page<T, F, Fut>(
+    page: &Page,
+    max_pages: usize,
+    mut extract: F,
+) -> Result<PagedResults<T>>
+where
+    F: FnMut(usize, Page) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut results = Vec::new();
+    let mut urls = Vec::new();
+    if max_pages == 0 {
+        return Ok(finish(results, urls, PaginationEnd::MaxPages));
+    }
+    loop {
+        urls.push(page.url().await?.unwrap_or_default());
+        results.push(extract(results.len(), page.clone()).await?);
+        if results.len() >= max_pages {
+            return Ok(finish(results, urls, PaginationEnd::MaxPages));
+        }
+
+        let Some(pagination) = detect_pagination(page).await? else {
+            return Ok(finish(results, urls, PaginationEnd::LastPage));
+        };
+        if pagination.kind != PaginationKind::InfiniteScroll && pagination.next.is_none() {
+            return Ok(finish(results, urls, PaginationEnd::LastPage));
+        }
+        if !next_page(page, &pagination).await? {
+            return Ok(finish(results, urls, PaginationEnd::NoChange));
+        }
+    }
+}
+
+fn finish<T>(results: Vec<T>, urls: Vec<String>, end: PaginationEnd) -> PagedResults<T> {
+    tracing::debug!(pages = results.len(), end = ?end, "Paginated");
+    PagedResults { results, urls, end }
+}
+
+/// Go to the page after the current one, returning whether it appeared in time
+///
+/// Clicks the pagination's next control, or for infinite scroll, scrolls to the
+/// bottom of the page.
+pub async fn next_page(page: &Page, pagination: &Pagination) -> Result<bool> {
+    let before = signature(page).await.unwrap_or_default();
+    match &pagination.next {
+        Some(selector) => {
+            tracing::debug!(selector = %selector, kind = ?pagination.kind, "Turning page");
+            page.find_element(selector.as_str())
+                .await
+                .map_err(|_| BrowserError::ElementNotFound(selector.clone()))?
+                .click()
+                .await?;
+        }
+        None => {
+            tracing::debug!("Scrolling for more results");
+            page.evaluate("window.scrollTo(0, document.documentElement.scrollHeight)")
+                .await?;
+        }
+    }
+
+    let deadline = Instant::now() + PAGE_TIMEOUT;
+    while Instant::now() < deadline {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        // None while a navigation is in flight
+        if signature(page).await.is_some_and(|now| now != before) {
+            tokio::time::sleep(SETTLE).await;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// What changes when a new page of results appears: the URL, the length and a hash
+/// of the text, and the scroll height (None until the document has loaded)
+async fn signature(page: &Page) -> Option<String> {
+    page.evaluate(SIGNATURE_SCRIPT)
+        .await
+        .ok()?
+        .into_value::<Option<String>>()
+        .ok()?
+}
+
+/// Pagination described by the `controls` on a page
+fn classify(controls: &[Control], scrollable: bool) -> Option<Pagination> {
+    let usable = || controls.iter().filter(|c| !c.disabled);
+    let next = usable()
+        .filter(|c| is_next(c))
+        // One inside the pager beats a "next" elsewhere (a carousel arrow)
+        .min_by_key(|c| !c.in_pager);
+
+    let numbered: Vec<&Control> = controls
+        .iter()
+        .filter(|c| c.in_pager && c.number.is_some())
+        .collect();
+    if numbered.len() >= 2 {
+        let current_page = numbered.iter().find(|c| c.current).and_then(|c| c.number);
+        let following = current_page.unwrap_or(1) + 1;
+        let next = next
+            .or_else(|| usable().find(|c| c.in_pager && !c.current && c.number == Some(following)));
+        return Some(Pagination {
+            kind: PaginationKind::NumberedPager,
+            next: next.map(|c| c.selector.clone()),
+            current_page,
+            last_page: numbered.iter().filter_map(|c| c.number).max(),
+        });
+    }
+
+    let (kind, next) = if let Some(next) = next {
+        (PaginationKind::NextButton, Some(next))
+    } else if let Some(more) = usable().find(|c| MORE_LABELS.contains(&name(c).as_str())) {
+        (PaginationKind::LoadMore, Some(more))
+    } else if scrollable {
+        (PaginationKind::InfiniteScroll, None)
+    } else {
+        return None;
+    };
+    Some(Pagination {
+        kind,
+        next: next.map(|c| c.selector.clone()),
+        current_page: None,
+        last_page: None,
+    })
+}
+
+fn is_next(control: &Control) -> bool {
+    let name = name(control);
+    control.rel.split_whitespace().any(|rel| rel == "next")
+        || NEXT_LABELS.contains(&name.as_str())
+        || name.starts_with("next page")
+        || (control.next_class && control.in_pager)
+}
+
+/// Lowercase label, or text if it has none, with whitespace collapsed
+fn name(control: &Control) -> String {
+    let name = if control.label.trim().is_empty() {
+        &control.text
+    } else {
+        &control.label
+    };
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Collects visible links, buttons, and pager items with short texts, and whether
+/// the page scrolls
+const CONTROLS_SCRIPT: &str = r#"(() => {
+    const pager = 'nav, [role=navigation], .pagination, .pager, [class*=paginat], [class*=pager]';
+    const candidates = document.querySelectorAll(
+        'a, button, [role=button], [role=link], input[type=button], input[type=submit], [aria-current]'
+        + `, :is(${pager}) :is(span, em, strong, li)`);
+    const selectorOf = (target) => {
+        const parts = [];
+        for (let el = target; el && el !== document.documentElement; el = el.parentElement) {
+            if (el.id && /^[A-Za-z][\w-]*$/.test(el.id) && document.querySelectorAll('#' + el.id).length === 1) {
+                parts.unshift('#' + el.id);
+                break;
+            }
+            const tag = el.tagName.toLowerCase();
+            const same = el.parentElement
+                ? Array.from(el.parentElement.children).filter((c) => c.tagName === el.tagName)
+                : [el];
+            parts.unshift(same.length > 1 ? `${tag}:nth-of-type(${same.indexOf(el) + 1})` : tag);
+        }
+        if (!parts.length || !parts[0].startsWith('#')) parts.unshift('html');
+        return parts.join(' > ');
+    };
+    const classOf = (el) => (typeof el.className === 'string' ? el.className : '').toLowerCase();
+    const item = (el) => el.closest('li') || el;
+    const controls = [];
+    for (const el of candidates) {
+        const text = (el.innerText || el.value || '').replace(/\s+/g, ' ').trim();
+        if (text.length > 30) continue;
+        const box = el.getBoundingClientRect();
+        if (box.width < 1 || box.height < 1) continue;
+        const classes = classOf(el) + ' ' + classOf(item(el));
+        const current = ['page', 'true'].includes(el.getAttribute('aria-current'))
+            || /\b(active|current|selected)\b/.test(classes);
+        controls.push({
+            selector: selectorOf(el),
+            text,
+            label: el.getAttribute('aria-label') || el.getAttribute('title') || '',
+            rel: el.getAttribute('rel') || '',
+            next_class: /next/.test(classes),
+            disabled: !!el.disabled || el.getAttribute('aria-disabled') === 'true'
+                || /\bdisabled\b/.test(classes),
+            in_pager: !!el.closest(pager),
+            number: /^\d{1,5}$/.test(text) ? Number(text) : null,
+            current,
+        });
+    }
+    const scroller = document.scrollingElement || document.documentElement;
+    return { controls, scrollable: scroller.scrollHeight > window.innerHeight + 100 };
+})()"#;
+
+const SIGNATURE_SCRIPT: &str = r#"(() => {
+    if (document.readyState !== 'complete' || !document.body) return null;
+    const text = document.body.innerText;
+    let hash = 0;
+    for (let i = 0; i < text.length; i++) hash = (hash * 31 + text.charCodeAt(i)) | 0;
+    return [location.href, text.length, hash, document.documentElement.scrollHeight].join('|');
+})()"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn control(selector: &str, text: &str) -> Control {
+        Control {
+            selector: selector.to_string(),
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn pager(selector: &str, number: u32, current: bool) -> Control {
+        Control {
+            in_pager: true,
+            number: Some(number),
+            current,
+            ..control(selector, &number.to_string())
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        let next = Control {
+            label: "Next page".to_string(),
+            in_pager: true,
+            ..control("#next", "›")
+        };
+        let pages = [
+            pager("#p1", 1, false),
+            pager("#p2", 2, true),
+            pager("#p3", 3, false),
+        ];
+
+        // Numbered pager with a next control
+        let mut controls = pages.to_vec();
+        controls.push(next.clone());
+        let pagination = classify(&controls, true).unwrap();
+        assert_eq!(pagination.kind, PaginationKind::NumberedPager);
+        assert_eq!(pagination.next.as_deref(), Some("#next"));
+        assert_eq!(pagination.current_page, Some(2));
+        assert_eq!(pagination.last_page, Some(3));
+
+        // Without one, the link after the current page; on the last page, none
+        assert_eq!(
+            classify(&pages, false).unwrap().next.as_deref(),
+            Some("#p3")
+        );
+        let last = [pager("#p1", 1, false), pager("#p2", 2, true)];
+        assert_eq!(classify(&last, false).unwrap().next, None);
+        let disabled = Control {
+            disabled: true,
+            ..next.clone()
+        };
+        assert_eq!(
+            classify(
+                &[pager("#p1", 1, false), pager("#p2", 2, true), disabled],
+                false
+            )
+            .unwrap()
+            .next,
+            None
+        );
+
+        // Lone next links, load-more buttons, and scrolling pages
+        let rel = Control {
+            rel: "next nofollow".to_string(),
+            ..control("a.older", "Older entries")
+        };
+        let pagination = classify(&[control("#home", "Home"), rel], true).unwrap();
+        assert_eq!(pagination.kind, PaginationKind::NextButton);
+        assert_eq!(pagination.next.as_deref(), Some("a.older"));
+        let pagination = classify(&[control("#more", " Load  more ")], true).unwrap();
+        assert_eq!(pagination.kind, PaginationKind::LoadMore);
+        assert_eq!(pagination.next.as_deref(), Some("#more"));
+        let pagination = classify(&[control("#home", "Home")], true).unwrap();
+        assert_eq!(pagination.kind, PaginationKind::InfiniteScroll);
+        assert_eq!(pagination.next, None);
+        assert_eq!(classify(&[control("#home", "Home")], false), None);
+    }
+}
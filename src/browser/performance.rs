@@ -0,0 +1,326 @@
+//! Performance Audits
+//!
+//! A Lighthouse-style summary of how the current page loaded, computed from what
+//! Chrome already records (no Node or Lighthouse install needed):
+//!
+//! - the page's Performance Timeline (navigation and paint timing, largest
+//!   contentful paint, layout shifts, long tasks, resource timing)
+//! - `Performance.getMetrics` (script, layout, and style time, heap size, DOM size)
+//!
+//! Each lab metric is scored 0-1 on Lighthouse's log-normal curves and the
+//! weighted mean becomes the 0-100 performance score. Weights follow Lighthouse,
+//! with time to first byte taking the place of Speed Index. CLS is approximated as
+//! the sum of all layout shifts without recent input (no session windows), and
+//! TBT as the blocking time of long tasks after first contentful paint.
+//!
+//! Audit right after navigating: metrics cover the page load, not later activity.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::cdp::browser_protocol::performance;
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+
+/// Scored metrics: (id, weight, p10, median); a value at p10 scores 0.9, at the median 0.5
+const SCORING: [(&str, f64, f64, f64); 5] = [
+    ("ttfb", 0.10, 800.0, 1800.0),
+    ("fcp", 0.10, 1800.0, 3000.0),
+    ("lcp", 0.25, 2500.0, 4000.0),
+    ("tbt", 0.30, 200.0, 600.0),
+    ("cls", 0.25, 0.1, 0.25),
+];
+
+/// Reads the Performance Timeline; buffered observers deliver past entries asynchronously
+const TIMELINE_SCRIPT: &str = r#"(async () => {
+    const observe = (type) => new Promise((resolve) => {
+        const entries = [];
+        try {
+            const observer = new PerformanceObserver((list) => entries.push(...list.getEntries()));
+            observer.observe({ type, buffered: true });
+            setTimeout(() => { observer.disconnect(); resolve(entries); }, 50);
+        } catch (e) {
+            resolve(entries);
+        }
+    });
+    const [lcp, shifts, longTasks] = await Promise.all(
+        ['largest-contentful-paint', 'layout-shift', 'longtask'].map(observe));
+    const nav = performance.getEntriesByType('navigation')[0];
+    const fcp = performance.getEntriesByName('first-contentful-paint')[0];
+    const fcpTime = fcp ? fcp.startTime : 0;
+
+    const kind = (e) => {
+        const path = e.name.split(/[?#]/)[0].toLowerCase();
+        if (['fetch', 'xmlhttprequest', 'beacon'].includes(e.initiatorType)) return 'xhr';
+        if (e.initiatorType === 'script' || path.endsWith('.js')) return 'script';
+        if (e.initiatorType === 'css' || path.endsWith('.css')) return 'stylesheet';
+        if (/\.(woff2?|ttf|otf|eot)$/.test(path)) return 'font';
+        if (e.initiatorType === 'img' || e.initiatorType === 'image'
+            || /\.(png|jpe?g|gif|webp|avif|svg|ico)$/.test(path)) return 'image';
+        if (['video', 'audio'].includes(e.initiatorType)) return 'media';
+        if (['iframe', 'frame'].includes(e.initiatorType)) return 'document';
+        return 'other';
+    };
+    // transferSize is 0 for cached and opaque cross-origin responses
+    const size = (e) => e.transferSize || e.encodedBodySize || 0;
+    const resources = {};
+    const add = (type, bytes) => {
+        const summary = resources[type] || (resources[type] = { resource_type: type, count: 0, bytes: 0 });
+        summary.count += 1;
+        summary.bytes += bytes;
+    };
+    if (nav) add('document', size(nav));
+    for (const e of performance.getEntriesByType('resource')) add(kind(e), size(e));
+
+    return {
+        url: location.href,
+        ttfb: nav ? nav.responseStart - nav.startTime : null,
+        fcp: fcp ? fcp.startTime : null,
+        lcp: lcp.length ? lcp[lcp.length - 1].startTime : null,
+        cls: shifts.filter((s) => !s.hadRecentInput).reduce((sum, s) => sum + s.value, 0),
+        tbt: longTasks.filter((t) => t.startTime >= fcpTime)
+            .reduce((sum, t) => sum + Math.max(0, t.duration - 50), 0),
+        dom_content_loaded: nav && nav.domContentLoadedEventEnd ? nav.domContentLoadedEventEnd : null,
+        load: nav && nav.loadEventEnd ? nav.loadEventEnd : null,
+        resources: Object.values(resources),
+    };
+})()"#;
+
+/// Performance summary of a page load
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PerformanceReport {
+    /// Audited page URL
+    pub url: String,
+
+    /// Weighted performance score, 0-100
+    pub score: u32,
+
+    /// Page load timings
+    pub timings: PageTimings,
+
+    /// Score of each metric that was measured
+    pub metrics: Vec<MetricScore>,
+
+    /// Requests and bytes transferred, by resource type, largest first
+    pub resources: Vec<ResourceSummary>,
+
+    /// Total bytes transferred
+    pub total_bytes: u64,
+
+    /// Total requests, including the document
+    pub request_count: u64,
+
+    /// Main-thread work and memory from `Performance.getMetrics`
+    pub runtime: RuntimeMetrics,
+}
+
+/// Load timings in milliseconds since navigation start (None = not recorded)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageTimings {
+    /// Time to first byte of the document
+    pub ttfb_ms: Option<f64>,
+
+    /// First contentful paint
+    pub fcp_ms: Option<f64>,
+
+    /// Largest contentful paint
+    pub lcp_ms: Option<f64>,
+
+    /// Cumulative layout shift (unitless; sum of all shifts)
+    pub cls: f64,
+
+    /// Total blocking time of long tasks after first contentful paint
+    pub tbt_ms: f64,
+
+    pub dom_content_loaded_ms: Option<f64>,
+    pub load_ms: Option<f64>,
+}
+
+/// A scored metric
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricScore {
+    /// `ttfb`, `fcp`, `lcp`, `tbt`, or `cls`
+    pub id: String,
+
+    /// Measured value (milliseconds, or unitless for `cls`)
+    pub value: f64,
+
+    /// Score from 0 (poor) to 1 (good)
+    pub score: f64,
+
+    /// Share of the overall score
+    pub weight: f64,
+}
+
+/// Requests of one resource type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceSummary {
+    /// `document`, `script`, `stylesheet`, `image`, `font`, `xhr`, `media`, or `other`
+    pub resource_type: String,
+    pub count: u64,
+    pub bytes: u64,
+}
+
+/// Main-thread and memory metrics of the page
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RuntimeMetrics {
+    pub script_duration_ms: f64,
+    pub layout_duration_ms: f64,
+    pub style_duration_ms: f64,
+    pub task_duration_ms: f64,
+    pub js_heap_used_bytes: u64,
+    pub dom_nodes: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Timeline {
+    url: String,
+    ttfb: Option<f64>,
+    fcp: Option<f64>,
+    lcp: Option<f64>,
+    cls: f64,
+    tbt: f64,
+    dom_content_loaded: Option<f64>,
+    load: Option<f64>,
+    resources: Vec<ResourceSummary>,
+}
+
+/// Audit the load of the page currently shown in `page`
+pub async fn audit_performance(page: &Page) -> Result<PerformanceReport> {
+    let timeline: Timeline = page
+        .evaluate(TIMELINE_SCRIPT)
+        .await?
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Unexpected performance timeline: {}", e)))?;
+    let runtime = runtime_metrics(page).await?;
+
+    let timings = PageTimings {
+        ttfb_ms: timeline.ttfb,
+        fcp_ms: timeline.fcp,
+        lcp_ms: timeline.lcp,
+        cls: timeline.cls,
+        tbt_ms: timeline.tbt,
+        dom_content_loaded_ms: timeline.dom_content_loaded,
+        load_ms: timeline.load,
+    };
+    let metrics = score_metrics(&timings);
+    let mut resources = timeline.resources;
+    resources.sort_by_key(|r| std::cmp::Reverse(r.bytes));
+
+    let report = PerformanceReport {
+        url: timeline.url,
+        score: overall_score(&metrics),
+        total_bytes: resources.iter().map(|r| r.bytes).sum(),
+        request_count: resources.iter().map(|r| r.count).sum(),
+        timings,
+        metrics,
+        resources,
+        runtime,
+    };
+    tracing::info!(url = %report.url, score = report.score, "Performance audit finished");
+    Ok(report)
+}
+
+async fn runtime_metrics(page: &Page) -> Result<RuntimeMetrics> {
+    page.execute(performance::EnableParams::default()).await?;
+    let response = page.execute(performance::GetMetricsParams::default()).await;
+    let _ = page.execute(performance::DisableParams::default()).await;
+
+    let mut runtime = RuntimeMetrics::default();
+    for metric in &response?.result.metrics {
+        match metric.name.as_str() {
+            "ScriptDuration" => runtime.script_duration_ms = metric.value * 1000.0,
+            "LayoutDuration" => runtime.layout_duration_ms = metric.value * 1000.0,
+            "RecalcStyleDuration" => runtime.style_duration_ms = metric.value * 1000.0,
+            "TaskDuration" => runtime.task_duration_ms = metric.value * 1000.0,
+            "JSHeapUsedSize" => runtime.js_heap_used_bytes = metric.value as u64,
+            "Nodes" => runtime.dom_nodes = metric.value as u64,
+            _ => {}
+        }
+    }
+    Ok(runtime)
+}
+
+/// Scores of the measured metrics, with weights normalized over them
+fn score_metrics(timings: &PageTimings) -> Vec<MetricScore> {
+    let value = |id: &str| match id {
+        "ttfb" => timings.ttfb_ms,
+        "fcp" => timings.fcp_ms,
+        "lcp" => timings.lcp_ms,
+        "tbt" => Some(timings.tbt_ms),
+        "cls" => Some(timings.cls),
+        _ => None,
+    };
+    let measured: Vec<_> = SCORING
+        .iter()
+        .filter_map(|&(id, weight, p10, median)| Some((id, weight, p10, median, value(id)?)))
+        .collect();
+    let total_weight: f64 = measured.iter().map(|m| m.1).sum();
+
+    measured
+        .into_iter()
+        .map(|(id, weight, p10, median, value)| MetricScore {
+            id: id.to_string(),
+            value,
+            score: log_normal_score(value, p10, median),
+            weight: weight / total_weight,
+        })
+        .collect()
+}
+
+fn overall_score(metrics: &[MetricScore]) -> u32 {
+    let score: f64 = metrics.iter().map(|m| m.score * m.weight).sum();
+    (score * 100.0).round() as u32
+}
+
+/// Lighthouse's log-normal scoring: 0.9 at `p10`, 0.5 at `median`, toward 0 above
+fn log_normal_score(value: f64, p10: f64, median: f64) -> f64 {
+    // erfc⁻¹(0.2), which places p10 at a score of 0.9
+    const INVERSE_ERFC_ONE_FIFTH: f64 = 0.906_193_802_436_823_2;
+    if value <= 0.0 {
+        return 1.0;
+    }
+    let standardized = (value / median).ln() * INVERSE_ERFC_ONE_FIFTH / (median / p10).ln();
+    ((1.0 - erf(standardized)) / 2.0).clamp(0.0, 1.0)
+}
+
+/// Error function (Abramowitz and Stegun 7.1.26, max error 1.5e-7)
+fn erf(x: f64) -> f64 {
+    let sign = x.signum();
+    let x = x.abs();
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let y = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    sign * (1.0 - y * (-x * x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_normal_score() {
+        assert!((log_normal_score(2500.0, 2500.0, 4000.0) - 0.9).abs() < 0.001);
+        assert!((log_normal_score(4000.0, 2500.0, 4000.0) - 0.5).abs() < 0.001);
+        assert!(log_normal_score(20_000.0, 2500.0, 4000.0) < 0.01);
+        assert_eq!(log_normal_score(0.0, 0.1, 0.25), 1.0);
+    }
+
+    #[test]
+    fn test_score_metrics_skips_unmeasured() {
+        let timings = PageTimings {
+            ttfb_ms: Some(100.0),
+            fcp_ms: None,
+            lcp_ms: None,
+            cls: 0.0,
+            tbt_ms: 0.0,
+            ..Default::default()
+        };
+        let metrics = score_metrics(&timings);
+        let ids: Vec<&str> = metrics.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["ttfb", "tbt", "cls"]);
+        let weights: f64 = metrics.iter().map(|m| m.weight).sum();
+        assert!((weights - 1.0).abs() < 1e-9);
+        assert!(overall_score(&metrics) >= 99);
+    }
+}
@@ -0,0 +1,256 @@
+//! Browser Pool for Parallel Script Execution
+//!
+//! Keeps N warm [`ChromeDriver`] instances (each its own Chrome process and profile)
+//! and schedules CDP scripts across them, so large scraping or test suites don't
+//! have to run serially through a single browser.
+
+use super::chrome::ChromeDriver;
+use crate::cdp::{CdpExecutor, CdpScript, ExecutionReport};
+use crate::config::Config;
+use crate::error::{BrowserError, Result};
+use serde::Serialize;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::Mutex;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+/// Pool of warm Chrome sessions
+pub struct BrowserPool {
+    idle: Mutex<Vec<ChromeDriver>>,
+    permits: Semaphore,
+    size: usize,
+    /// Used to replace sessions that died while idle (None = no relaunch)
+    config: Option<Config>,
+}
+
+/// A driver checked out of the pool; returned to the pool when dropped
+pub struct PooledDriver<'a> {
+    driver: Option<ChromeDriver>,
+    pool: &'a BrowserPool,
+    _permit: SemaphorePermit<'a>,
+}
+
+/// Outcome of one script from [`BrowserPool::run_scripts_parallel`]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScriptOutcome {
+    /// Position of the script in the input
+    pub index: usize,
+
+    /// Script name
+    pub script: String,
+
+    /// Execution report (None if the script could not be run)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<ExecutionReport>,
+
+    /// Error that prevented the script from running or completing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ScriptOutcome {
+    /// Whether the script ran and every command succeeded
+    pub fn is_success(&self) -> bool {
+        self.error.is_none() && self.report.as_ref().is_some_and(|r| r.is_success())
+    }
+}
+
+impl BrowserPool {
+    /// Launch `size` Chrome sessions concurrently using `config`
+    ///
+    /// Sessions that are found dead when checked out are relaunched with the same config.
+    pub async fn launch(size: usize, config: &Config) -> Result<Self> {
+        if size == 0 {
+            return Err(BrowserError::Other(
+                "Browser pool size must be at least 1".to_string(),
+            ));
+        }
+
+        tracing::info!(size, "Launching browser pool");
+        let drivers =
+            futures::future::try_join_all((0..size).map(|_| ChromeDriver::from_config(config)))
+                .await?;
+
+        let mut pool = Self::from_drivers(drivers);
+        pool.config = Some(config.clone());
+        Ok(pool)
+    }
+
+    /// Build a pool from already-connected drivers (dead sessions are not relaunched)
+    pub fn from_drivers(drivers: Vec<ChromeDriver>) -> Self {
+        let size = drivers.len();
+        Self {
+            idle: Mutex::new(drivers),
+            permits: Semaphore::new(size),
+            size,
+            config: None,
+        }
+    }
+
+    /// Number of sessions in the pool
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of sessions currently idle
+    pub fn available(&self) -> usize {
+        self.permits.available_permits()
+    }
+
+    /// Check out a session, waiting until one is free
+    ///
+    /// Dead sessions are relaunched when the pool was created with [`BrowserPool::launch`].
+    pub async fn acquire(&self) -> Result<PooledDriver<'_>> {
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .map_err(|_| BrowserError::Other("Browser pool closed".to_string()))?;
+
+        let driver = self
+            .idle
+            .lock()
+            .unwrap()
+            .pop()
+            .ok_or_else(|| BrowserError::Other("Browser pool is empty".to_string()))?;
+
+        let driver = if driver.is_alive().await {
+            driver
+        } else if let Some(config) = &self.config {
+            tracing::warn!(session_id = %driver.session_id(), "Pooled Chrome session dead, relaunching");
+            drop(driver);
+            match ChromeDriver::from_config(config).await {
+                Ok(driver) => driver,
+                Err(e) => {
+                    // The session is gone; retire its permit so later callers
+                    // wait for a live session instead of finding the pool empty
+                    permit.forget();
+                    return Err(e);
+                }
+            }
+        } else {
+            driver
+        };
+
+        Ok(PooledDriver {
+            driver: Some(driver),
+            pool: self,
+            _permit: permit,
+        })
+    }
+
+    /// Run scripts across the pool, at most one per session at a time
+    ///
+    /// Returns one outcome per script, in input order. A failing script does not
+    /// stop the others.
+    pub async fn run_scripts_parallel(&self, scripts: &[CdpScript]) -> Vec<ScriptOutcome> {
+        self.run_scripts_parallel_cancellable(scripts, &CancellationToken::new())
+            .await
+    }
+
+    /// Like [`run_scripts_parallel`](Self::run_scripts_parallel), stopping when `cancel_token` fires
+    ///
+    /// Running scripts stop at their next command boundary; scripts that haven't
+    /// started are reported with a "cancelled" error.
+    pub async fn run_scripts_parallel_cancellable(
+        &self,
+        scripts: &[CdpScript],
+        cancel_token: &CancellationToken,
+    ) -> Vec<ScriptOutcome> {
+        let runs = scripts
+            .iter()
+            .enumerate()
+            .map(|(index, script)| async move {
+                let (report, error) = match self.run_script(script, cancel_token).await {
+                    Ok(report) => (Some(report), None),
+                    Err(e) => (None, Some(e.to_string())),
+                };
+                ScriptOutcome {
+                    index,
+                    script: script.name.clone(),
+                    report,
+                    error,
+                }
+            });
+
+        futures::future::join_all(runs).await
+    }
+
+    /// Run one script on the next free session
+    ///
+    /// Returns `BrowserError::Cancelled` if `cancel_token` fires before a session frees up.
+    pub async fn run_script(
+        &self,
+        script: &CdpScript,
+        cancel_token: &CancellationToken,
+    ) -> Result<ExecutionReport> {
+        self.run(script, None, cancel_token).await
+    }
+
+    /// Like [`run_script`](Self::run_script), writing the script's `save_as` files
+    /// under `output_dir`
+    ///
+    /// Steps whose `save_as` is absolute or leaves `output_dir` through `..` fail.
+    pub async fn run_script_in(
+        &self,
+        script: &CdpScript,
+        output_dir: &Path,
+        cancel_token: &CancellationToken,
+    ) -> Result<ExecutionReport> {
+        self.run(script, Some(output_dir), cancel_token).await
+    }
+
+    async fn run(
+        &self,
+        script: &CdpScript,
+        output_dir: Option<&Path>,
+        cancel_token: &CancellationToken,
+    ) -> Result<ExecutionReport> {
+        let driver = tokio::select! {
+            driver = self.acquire() => driver?,
+            _ = cancel_token.cancelled() => return Err(BrowserError::Cancelled),
+        };
+        let page = driver.current_page().await?;
+        let mut executor = CdpExecutor::new(page)
+            .with_driver(&driver)
+            .with_cancellation(cancel_token.child_token());
+        if let Some(dir) = output_dir {
+            executor = executor.with_output_dir(dir);
+        }
+        executor
+            .execute_script(script)
+            .await
+            .map_err(|e| BrowserError::Other(e.to_string()))
+    }
+
+    /// Close every idle session
+    ///
+    /// Call after all checked-out drivers have been returned.
+    pub async fn close(self) -> Result<()> {
+        let drivers = std::mem::take(&mut *self.idle.lock().unwrap());
+        for driver in drivers {
+            if let Err(e) = driver.close().await {
+                tracing::warn!(error = %e, "Failed to close pooled Chrome session");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Deref for PooledDriver<'_> {
+    type Target = ChromeDriver;
+
+    fn deref(&self) -> &ChromeDriver {
+        self.driver.as_ref().expect("driver is present until drop")
+    }
+}
+
+impl Drop for PooledDriver<'_> {
+    fn drop(&mut self) {
+        if let Some(driver) = self.driver.take() {
+            self.pool.idle.lock().unwrap().push(driver);
+        }
+    }
+}
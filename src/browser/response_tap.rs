@@ -0,0 +1,258 @@
+//! JSON Response Tap
+//!
+//! Single-page apps render from the JSON their XHR/fetch calls return, so reading
+//! those responses is more robust than scraping the rendered HTML. A
+//! [`ResponseTap`] watches a page's XHR and fetch responses whose URL matches a
+//! glob pattern, parses their bodies as JSON, optionally narrows them with a
+//! [`JsonPath`] filter, and hands the results over as a [`Stream`].
+//!
+//! Only responses that finish loading after the tap is created are seen. Bodies
+//! that aren't JSON, or where the filter selects nothing, are skipped.
+
+use crate::error::{BrowserError, Result};
+use crate::json_path::JsonPath;
+use base64::Engine;
+use chromiumoxide::cdp::browser_protocol::network::{
+    self, EventLoadingFinished, EventResponseReceived, GetResponseBodyParams, RequestId,
+    ResourceType,
+};
+use chromiumoxide::page::Page;
+use futures::stream::{BoxStream, Stream, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Responses buffered before the tap stops reading new ones until the consumer catches up
+const TAP_BUFFER: usize = 256;
+
+/// A JSON response seen by a [`ResponseTap`]
+#[derive(Debug, Clone, Serialize)]
+pub struct TappedResponse {
+    /// Request URL
+    pub url: String,
+
+    /// HTTP status code
+    pub status: i64,
+
+    /// The parsed body, or the values the filter selected from it (at least one)
+    pub values: Vec<Value>,
+}
+
+/// Stream of JSON responses from one page
+///
+/// Watching stops when the tap is dropped.
+pub struct ResponseTap {
+    receiver: mpsc::Receiver<TappedResponse>,
+    task: JoinHandle<()>,
+}
+
+impl ResponseTap {
+    /// Start watching `page` for XHR/fetch responses whose URL matches `url_pattern`
+    ///
+    /// `url_pattern` is a glob where `*` matches any run of characters and `?` any
+    /// single one (e.g. `*/graphql*`). `filter` is a JSONPath (or jq-style) path.
+    pub async fn attach(page: &Page, url_pattern: &str, filter: Option<&str>) -> Result<Self> {
+        let filter = filter
+            .map(|f| f.parse::<JsonPath>())
+            .transpose()
+            .map_err(BrowserError::Other)?;
+
+        page.execute(network::EnableParams::default()).await?;
+
+        let events: BoxStream<'static, Event> = futures::stream::select(
+            page.event_listener::<EventResponseReceived>()
+                .await?
+                .map(Event::Response),
+            page.event_listener::<EventLoadingFinished>()
+                .await?
+                .map(Event::Finished),
+        )
+        .boxed();
+
+        let (sender, receiver) = mpsc::channel(TAP_BUFFER);
+        let task = tokio::spawn(watch(
+            page.clone(),
+            events,
+            url_pattern.to_string(),
+            filter,
+            sender,
+        ));
+        Ok(Self { receiver, task })
+    }
+
+    /// Next matching response, or None once the page is gone
+    pub async fn next_response(&mut self) -> Option<TappedResponse> {
+        self.receiver.recv().await
+    }
+
+    /// Next matching response, or None if none arrives within `timeout`
+    pub async fn next_within(&mut self, timeout: Duration) -> Option<TappedResponse> {
+        tokio::time::timeout(timeout, self.receiver.recv())
+            .await
+            .ok()
+            .flatten()
+    }
+}
+
+impl Stream for ResponseTap {
+    type Item = TappedResponse;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for ResponseTap {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+enum Event {
+    Response(Arc<EventResponseReceived>),
+    Finished(Arc<EventLoadingFinished>),
+}
+
+async fn watch(
+    page: Page,
+    mut events: BoxStream<'static, Event>,
+    url_pattern: String,
+    filter: Option<JsonPath>,
+    sender: mpsc::Sender<TappedResponse>,
+) {
+    // Matching responses waiting for their body to finish loading
+    let mut pending: HashMap<RequestId, (String, i64)> = HashMap::new();
+
+    while let Some(event) = events.next().await {
+        match event {
+            Event::Response(event) => {
+                let api_call = matches!(event.r#type, ResourceType::Xhr | ResourceType::Fetch);
+                if api_call && glob_match(&url_pattern, &event.response.url) {
+                    pending.insert(
+                        event.request_id.clone(),
+                        (event.response.url.clone(), event.response.status),
+                    );
+                }
+            }
+            Event::Finished(event) => {
+                let Some((url, status)) = pending.remove(&event.request_id) else {
+                    continue;
+                };
+                let Some(body) = response_json(&page, &event.request_id, &url).await else {
+                    continue;
+                };
+                let values: Vec<Value> = match &filter {
+                    Some(filter) => filter.select(&body).into_iter().cloned().collect(),
+                    None => vec![body],
+                };
+                if values.is_empty() {
+                    continue;
+                }
+                if sender
+                    .send(TappedResponse {
+                        url,
+                        status,
+                        values,
+                    })
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// The response body parsed as JSON, or None if it can't be read or isn't JSON
+async fn response_json(page: &Page, request_id: &RequestId, url: &str) -> Option<Value> {
+    let body = match page
+        .execute(GetResponseBodyParams::new(request_id.clone()))
+        .await
+    {
+        Ok(response) => response.result,
+        Err(e) => {
+            tracing::debug!(url, error = %e, "Response body unavailable");
+            return None;
+        }
+    };
+    let bytes = if body.base64_encoded {
+        base64::engine::general_purpose::STANDARD
+            .decode(&body.body)
+            .ok()?
+    } else {
+        body.body.into_bytes()
+    };
+    match serde_json::from_slice(&bytes) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::debug!(url, error = %e, "Tapped response is not JSON");
+            None
+        }
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` is any run of characters and `?` one
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", "https://example.com/api"));
+        assert!(glob_match(
+            "*/graphql*",
+            "https://example.com/graphql?op=Products"
+        ));
+        assert!(glob_match(
+            "https://*.example.com/api/v?/items",
+            "https://shop.example.com/api/v2/items"
+        ));
+        assert!(!glob_match(
+            "*/graphql",
+            "https://example.com/graphql?op=Products"
+        ));
+        assert!(!glob_match(
+            "https://example.com/api/*",
+            "https://example.com/static/app.js"
+        ));
+        assert!(glob_match("*a*b*c", "xxaxxbxxbxxc"));
+        assert!(!glob_match("", "x"));
+    }
+}
@@ -0,0 +1,490 @@
+//! Response Rewriting
+//!
+//! QA runs against real environments often need a feature flag forced on or a
+//! backend failure simulated without touching the server. A [`ResponseRewriter`]
+//! intercepts the page's responses whose URL matches a rule (via the `Fetch`
+//! domain, at the response stage) and rewrites the body before the page sees it:
+//!
+//! - [`BodyRewrite::Regex`] replaces regex matches in the body text
+//! - [`BodyRewrite::JsonPatch`] applies JSON Patch (RFC 6902) `add`, `remove`,
+//!   and `replace` operations to a JSON body
+//! - [`BodyRewrite::Body`] replaces the body outright
+//!
+//! A rule can also override the status code (e.g. serve an error payload as a 500).
+//! Every rule whose pattern matches a response is applied, in order. A page has one
+//! set of interception patterns, so attaching a second rewriter replaces the first.
+
+use super::response_tap::glob_match;
+use crate::error::{BrowserError, Result};
+use base64::Engine;
+use chromiumoxide::cdp::browser_protocol::fetch::{
+    self, ContinueRequestParams, EventRequestPaused, FulfillRequestParams, GetResponseBodyParams,
+    HeaderEntry, RequestPattern, RequestStage,
+};
+use chromiumoxide::page::Page;
+use futures::stream::StreamExt;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// Headers that no longer describe the rewritten (decoded) body
+const STALE_HEADERS: &[&str] = &["content-length", "content-encoding"];
+
+/// How a matching response body is rewritten
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BodyRewrite {
+    /// Replace every match of `pattern` with `replacement` (`$1`, `$name` expand captures)
+    Regex {
+        pattern: String,
+        replacement: String,
+    },
+    /// Apply JSON Patch operations to the body parsed as JSON
+    JsonPatch { patch: Vec<PatchOperation> },
+    /// Serve this body instead
+    Body { body: String },
+}
+
+/// A JSON Patch (RFC 6902) operation; `path` is a JSON Pointer such as `/flags/new_checkout`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PatchOperation {
+    /// Set an object member or insert an array element (`-` appends)
+    Add { path: String, value: Value },
+    /// Remove an object member or array element
+    Remove { path: String },
+    /// Replace an existing value
+    Replace { path: String, value: Value },
+}
+
+/// Rewrites the bodies of responses whose URL matches `url_pattern`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewriteRule {
+    /// URL glob where `*` matches any run of characters and `?` any single one
+    pub url_pattern: String,
+
+    /// Body rewrite
+    pub rewrite: BodyRewrite,
+
+    /// Status code to serve instead of the original
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+}
+
+impl RewriteRule {
+    /// Replace regex matches in the body of responses matching `url_pattern`
+    pub fn regex(
+        url_pattern: impl Into<String>,
+        pattern: impl Into<String>,
+        replacement: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            url_pattern,
+            BodyRewrite::Regex {
+                pattern: pattern.into(),
+                replacement: replacement.into(),
+            },
+        )
+    }
+
+    /// Patch the JSON body of responses matching `url_pattern`
+    pub fn json_patch(url_pattern: impl Into<String>, patch: Vec<PatchOperation>) -> Self {
+        Self::new(url_pattern, BodyRewrite::JsonPatch { patch })
+    }
+
+    /// Serve `body` for responses matching `url_pattern`
+    pub fn body(url_pattern: impl Into<String>, body: impl Into<String>) -> Self {
+        Self::new(url_pattern, BodyRewrite::Body { body: body.into() })
+    }
+
+    /// Serve matching responses with this status code
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    fn new(url_pattern: impl Into<String>, rewrite: BodyRewrite) -> Self {
+        Self {
+            url_pattern: url_pattern.into(),
+            rewrite,
+            status: None,
+        }
+    }
+}
+
+/// A rule with its regex compiled
+struct CompiledRule {
+    rule: RewriteRule,
+    regex: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn new(rule: RewriteRule) -> std::result::Result<Self, String> {
+        let regex = match &rule.rewrite {
+            BodyRewrite::Regex { pattern, .. } => Some(
+                Regex::new(pattern)
+                    .map_err(|e| format!("Invalid rewrite regex '{}': {}", pattern, e))?,
+            ),
+            _ => None,
+        };
+        Ok(Self { rule, regex })
+    }
+
+    /// Rewrite `body`, or explain why this rule doesn't apply to it
+    fn apply(&self, body: &[u8]) -> std::result::Result<Vec<u8>, String> {
+        match &self.rule.rewrite {
+            BodyRewrite::Regex { replacement, .. } => {
+                let text = std::str::from_utf8(body).map_err(|_| "body is not UTF-8")?;
+                let regex = self.regex.as_ref().expect("regex compiled with the rule");
+                Ok(regex
+                    .replace_all(text, replacement.as_str())
+                    .into_owned()
+                    .into_bytes())
+            }
+            BodyRewrite::JsonPatch { patch } => {
+                let mut document: Value =
+                    serde_json::from_slice(body).map_err(|e| format!("body is not JSON: {}", e))?;
+                apply_patch(&mut document, patch)?;
+                serde_json::to_vec(&document).map_err(|e| e.to_string())
+            }
+            BodyRewrite::Body { body } => Ok(body.clone().into_bytes()),
+        }
+    }
+}
+
+/// Rewrites one page's matching responses
+///
+/// Interception stops when the rewriter is dropped.
+pub struct ResponseRewriter {
+    page: Page,
+    rewritten: Arc<AtomicUsize>,
+    task: JoinHandle<()>,
+    /// Interception was already turned off by [`ResponseRewriter::stop`]
+    stopped: bool,
+}
+
+impl ResponseRewriter {
+    /// Start rewriting `page`'s responses according to `rules`
+    pub async fn attach(page: &Page, rules: Vec<RewriteRule>) -> Result<Self> {
+        if rules.is_empty() {
+            return Err(BrowserError::Other(
+                "At least one rewrite rule is required".to_string(),
+            ));
+        }
+        let rules = rules
+            .into_iter()
+            .map(CompiledRule::new)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(BrowserError::Other)?;
+
+        let events = page.event_listener::<EventRequestPaused>().await?;
+        page.execute(
+            fetch::EnableParams::builder()
+                .patterns(rules.iter().map(|compiled| {
+                    RequestPattern::builder()
+                        .url_pattern(compiled.rule.url_pattern.clone())
+                        .request_stage(RequestStage::Response)
+                        .build()
+                }))
+                .build(),
+        )
+        .await?;
+
+        let rewritten = Arc::new(AtomicUsize::new(0));
+        let task = tokio::spawn(watch(
+            page.clone(),
+            events.boxed(),
+            rules,
+            rewritten.clone(),
+        ));
+        Ok(Self {
+            page: page.clone(),
+            rewritten,
+            task,
+            stopped: false,
+        })
+    }
+
+    /// Number of responses rewritten so far
+    pub fn rewritten(&self) -> usize {
+        self.rewritten.load(Ordering::Relaxed)
+    }
+
+    /// Stop intercepting and wait until Chrome has been told
+    pub async fn stop(mut self) -> Result<()> {
+        self.task.abort();
+        self.stopped = true;
+        self.page.execute(fetch::DisableParams::default()).await?;
+        Ok(())
+    }
+}
+
+impl Drop for ResponseRewriter {
+    fn drop(&mut self) {
+        if self.stopped {
+            return;
+        }
+        self.task.abort();
+        // Paused requests would hang without a handler, so interception must be turned off
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let page = self.page.clone();
+            runtime.spawn(async move {
+                let _ = page.execute(fetch::DisableParams::default()).await;
+            });
+        }
+    }
+}
+
+async fn watch(
+    page: Page,
+    mut events: futures::stream::BoxStream<'static, Arc<EventRequestPaused>>,
+    rules: Vec<CompiledRule>,
+    rewritten: Arc<AtomicUsize>,
+) {
+    while let Some(event) = events.next().await {
+        let result = match fulfill_params(&page, &event, &rules).await {
+            Some(params) => {
+                rewritten.fetch_add(1, Ordering::Relaxed);
+                page.execute(params).await.map(|_| ())
+            }
+            None => page
+                .execute(ContinueRequestParams::new(event.request_id.clone()))
+                .await
+                .map(|_| ()),
+        };
+        if let Err(e) = result {
+            tracing::warn!(url = %event.request.url, error = %e, "Failed to resume intercepted response");
+        }
+    }
+}
+
+/// The rewritten response for a paused request, or None to let it through unchanged
+async fn fulfill_params(
+    page: &Page,
+    event: &EventRequestPaused,
+    rules: &[CompiledRule],
+) -> Option<FulfillRequestParams> {
+    let url = &event.request.url;
+    let status = event.response_status_code?;
+    let matching: Vec<&CompiledRule> = rules
+        .iter()
+        .filter(|compiled| glob_match(&compiled.rule.url_pattern, url))
+        .collect();
+    // Redirects and failed loads have no body to rewrite
+    if matching.is_empty() || (300..400).contains(&status) {
+        return None;
+    }
+
+    let response = match page
+        .execute(GetResponseBodyParams::new(event.request_id.clone()))
+        .await
+    {
+        Ok(response) => response.result,
+        Err(e) => {
+            tracing::warn!(url = %url, error = %e, "Intercepted response body unavailable");
+            return None;
+        }
+    };
+    let mut body = if response.base64_encoded {
+        base64::engine::general_purpose::STANDARD
+            .decode(&response.body)
+            .ok()?
+    } else {
+        response.body.into_bytes()
+    };
+
+    let mut response_code = status;
+    let mut applied = false;
+    for compiled in matching {
+        match compiled.apply(&body) {
+            Ok(new_body) => {
+                body = new_body;
+                applied = true;
+                if let Some(status) = compiled.rule.status {
+                    response_code = status.into();
+                }
+            }
+            Err(e) => {
+                tracing::warn!(url = %url, pattern = %compiled.rule.url_pattern, "Rewrite rule skipped: {}", e)
+            }
+        }
+    }
+    if !applied {
+        return None;
+    }
+    tracing::debug!(url = %url, status = response_code, "Rewrote response");
+
+    let headers = event
+        .response_headers
+        .iter()
+        .flatten()
+        .filter(|header| !STALE_HEADERS.contains(&header.name.to_ascii_lowercase().as_str()))
+        .map(|header| HeaderEntry::new(header.name.clone(), header.value.clone()))
+        .collect();
+    let mut params = FulfillRequestParams::new(event.request_id.clone(), response_code);
+    params.response_headers = Some(headers);
+    params.body = Some(
+        base64::engine::general_purpose::STANDARD
+            .encode(&body)
+            .into(),
+    );
+    Some(params)
+}
+
+/// Apply JSON Patch operations to `document` in order
+pub(crate) fn apply_patch(
+    document: &mut Value,
+    patch: &[PatchOperation],
+) -> std::result::Result<(), String> {
+    for operation in patch {
+        match operation {
+            PatchOperation::Add { path, value } => {
+                let (parent, key) = split_pointer(document, path)?;
+                match parent {
+                    Value::Object(members) => {
+                        members.insert(key, value.clone());
+                    }
+                    Value::Array(items) => {
+                        let index = if key == "-" {
+                            items.len()
+                        } else {
+                            array_index(&key, items.len() + 1, path)?
+                        };
+                        items.insert(index, value.clone());
+                    }
+                    _ => return Err(format!("Cannot add at '{}'", path)),
+                }
+            }
+            PatchOperation::Remove { path } => {
+                let (parent, key) = split_pointer(document, path)?;
+                let removed = match parent {
+                    Value::Object(members) => members.remove(&key).is_some(),
+                    Value::Array(items) => {
+                        let index = array_index(&key, items.len(), path)?;
+                        items.remove(index);
+                        true
+                    }
+                    _ => false,
+                };
+                if !removed {
+                    return Err(format!("Nothing to remove at '{}'", path));
+                }
+            }
+            PatchOperation::Replace { path, value } => {
+                let target = document
+                    .pointer_mut(path)
+                    .ok_or_else(|| format!("Nothing to replace at '{}'", path))?;
+                *target = value.clone();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The container a pointer refers into and the (unescaped) last token
+fn split_pointer<'a>(
+    document: &'a mut Value,
+    path: &str,
+) -> std::result::Result<(&'a mut Value, String), String> {
+    let (parent, key) = path
+        .rsplit_once('/')
+        .ok_or_else(|| format!("Invalid JSON Pointer '{}'", path))?;
+    let parent = document
+        .pointer_mut(parent)
+        .ok_or_else(|| format!("No parent for '{}'", path))?;
+    Ok((parent, key.replace("~1", "/").replace("~0", "~")))
+}
+
+fn array_index(key: &str, len: usize, path: &str) -> std::result::Result<usize, String> {
+    key.parse::<usize>()
+        .ok()
+        .filter(|index| *index < len)
+        .ok_or_else(|| format!("Invalid array index in '{}'", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn apply(rule: RewriteRule, body: &str) -> std::result::Result<String, String> {
+        let compiled = CompiledRule::new(rule)?;
+        compiled
+            .apply(body.as_bytes())
+            .map(|bytes| String::from_utf8(bytes).unwrap())
+    }
+
+    #[test]
+    fn test_regex_rewrite() {
+        let rule = RewriteRule::regex("*", r#""(\w+)_enabled":\s*false"#, r#""${1}_enabled":true"#);
+        assert_eq!(
+            apply(rule, r#"{"checkout_enabled": false, "beta_enabled":false}"#).unwrap(),
+            r#"{"checkout_enabled":true, "beta_enabled":true}"#
+        );
+        assert!(CompiledRule::new(RewriteRule::regex("*", "(", "")).is_err());
+    }
+
+    #[test]
+    fn test_json_patch() {
+        let mut document = json!({"flags": {"old": true}, "items": [1, 2]});
+        apply_patch(
+            &mut document,
+            &[
+                PatchOperation::Add {
+                    path: "/flags/new~1checkout".to_string(),
+                    value: json!(true),
+                },
+                PatchOperation::Remove {
+                    path: "/flags/old".to_string(),
+                },
+                PatchOperation::Replace {
+                    path: "/items/0".to_string(),
+                    value: json!(10),
+                },
+                PatchOperation::Add {
+                    path: "/items/-".to_string(),
+                    value: json!(3),
+                },
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            document,
+            json!({"flags": {"new/checkout": true}, "items": [10, 2, 3]})
+        );
+
+        let missing = PatchOperation::Replace {
+            path: "/missing".to_string(),
+            value: json!(1),
+        };
+        assert!(apply_patch(&mut document, &[missing]).is_err());
+        let rule = RewriteRule::json_patch("*", vec![]);
+        assert!(apply(rule, "<html>").is_err());
+    }
+
+    #[test]
+    fn test_rules_deserialize() {
+        let rules: Vec<RewriteRule> = serde_json::from_value(json!([
+            {
+                "url_pattern": "*/api/flags",
+                "rewrite": {"type": "json_patch", "patch": [
+                    {"op": "replace", "path": "/new_checkout", "value": true}
+                ]}
+            },
+            {
+                "url_pattern": "*/api/cart",
+                "rewrite": {"type": "body", "body": "{\"error\":\"unavailable\"}"},
+                "status": 503
+            }
+        ]))
+        .unwrap();
+        assert_eq!(
+            rules[1],
+            RewriteRule::body("*/api/cart", "{\"error\":\"unavailable\"}").with_status(503)
+        );
+        assert!(matches!(rules[0].rewrite, BodyRewrite::JsonPatch { .. }));
+    }
+}
@@ -0,0 +1,180 @@
+//! Page Security State
+//!
+//! Reads what the address bar would show for the current page: the overall
+//! security state and, for HTTPS pages, the connection and certificate details.
+//! Enabling the `Security` domain makes Chrome report the page's visible security
+//! state right away, so the state is read from that first event.
+//!
+//! Certificate errors are respected unless `DriverOptions::ignore_cert_errors` is
+//! set; with it, pages behind self-signed or expired certificates load, and this
+//! is where the problem still shows (`state` is `insecure-broken` and
+//! `certificate.network_error` names the error).
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::cdp::browser_protocol::network::TimeSinceEpoch;
+use chromiumoxide::cdp::browser_protocol::security::{
+    self, CertificateSecurityState, EventVisibleSecurityStateChanged,
+};
+use chromiumoxide::page::Page;
+use futures::StreamExt;
+use serde::Serialize;
+use std::time::Duration;
+
+/// How long to wait for Chrome to report the security state
+const STATE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Security state of a page
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SecurityInfo {
+    /// `secure`, `insecure`, `insecure-broken`, `neutral`, `info`, or `unknown`
+    pub state: String,
+
+    /// Connection and certificate details (None for pages not loaded over TLS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<CertificateInfo>,
+
+    /// Reasons for the state (e.g. `scheme-is-not-cryptographic`)
+    pub issues: Vec<String>,
+}
+
+impl SecurityInfo {
+    /// Whether Chrome considers the page secure
+    pub fn is_secure(&self) -> bool {
+        self.state == "secure"
+    }
+}
+
+/// TLS connection and certificate details
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CertificateInfo {
+    /// Certificate subject name
+    pub subject_name: String,
+
+    /// Name of the issuing CA
+    pub issuer: String,
+
+    /// Start of the validity period (RFC 3339)
+    pub valid_from: String,
+
+    /// End of the validity period (RFC 3339)
+    pub valid_to: String,
+
+    /// Protocol (e.g. `TLS 1.3`, `QUIC`)
+    pub protocol: String,
+
+    /// Key exchange (empty if not applicable)
+    pub key_exchange: String,
+
+    /// Cipher name
+    pub cipher: String,
+
+    /// Certificate error (e.g. `net::ERR_CERT_AUTHORITY_INVALID`), if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network_error: Option<String>,
+
+    /// Whether the certificate or its chain uses a weak (e.g. SHA-1) signature
+    pub weak_signature: bool,
+
+    /// Whether the connection uses an obsolete protocol, key exchange, cipher, or signature
+    pub obsolete_tls: bool,
+
+    /// Certificate chain as base64-encoded DER, leaf first
+    pub chain: Vec<String>,
+}
+
+impl From<&CertificateSecurityState> for CertificateInfo {
+    fn from(state: &CertificateSecurityState) -> Self {
+        Self {
+            subject_name: state.subject_name.clone(),
+            issuer: state.issuer.clone(),
+            valid_from: rfc3339(&state.valid_from),
+            valid_to: rfc3339(&state.valid_to),
+            protocol: state.protocol.clone(),
+            key_exchange: state.key_exchange.clone(),
+            cipher: state.cipher.clone(),
+            network_error: state.certificate_network_error.clone(),
+            weak_signature: state.certificate_has_weak_signature
+                || state.certificate_has_sha1_signature,
+            obsolete_tls: state.obsolete_ssl_protocol
+                || state.obsolete_ssl_key_exchange
+                || state.obsolete_ssl_cipher
+                || state.obsolete_ssl_signature,
+            chain: state.certificate.clone(),
+        }
+    }
+}
+
+/// Security state of `page` as Chrome currently shows it
+pub async fn security_info(page: &Page) -> Result<SecurityInfo> {
+    let mut events = page
+        .event_listener::<EventVisibleSecurityStateChanged>()
+        .await?;
+    page.execute(security::EnableParams::default()).await?;
+    let event = tokio::time::timeout(STATE_TIMEOUT, events.next()).await;
+    // Disabling makes the next enable report the state again
+    let _ = page.execute(security::DisableParams::default()).await;
+
+    let state = match event {
+        Ok(Some(event)) => event.visible_security_state.clone(),
+        _ => {
+            return Err(BrowserError::Other(format!(
+                "Chrome did not report the page's security state within {:?}",
+                STATE_TIMEOUT
+            )))
+        }
+    };
+    Ok(SecurityInfo {
+        state: state.security_state.as_ref().to_string(),
+        certificate: state
+            .certificate_security_state
+            .as_ref()
+            .map(CertificateInfo::from),
+        issues: state.security_state_issue_ids,
+    })
+}
+
+fn rfc3339(time: &TimeSinceEpoch) -> String {
+    let seconds = *time.inner();
+    chrono::DateTime::from_timestamp(seconds.trunc() as i64, 0)
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_certificate_info_from_state() {
+        let state: CertificateSecurityState = serde_json::from_value(serde_json::json!({
+            "protocol": "TLS 1.3",
+            "keyExchange": "",
+            "keyExchangeGroup": "X25519",
+            "cipher": "AES_128_GCM",
+            "certificate": ["MIIB"],
+            "subjectName": "localhost",
+            "issuer": "localhost",
+            "validFrom": 1_704_067_200.0,
+            "validTo": 1_735_689_600.0,
+            "certificateNetworkError": "net::ERR_CERT_AUTHORITY_INVALID",
+            "certificateHasWeakSignature": false,
+            "certificateHasSha1Signature": false,
+            "modernSSL": true,
+            "obsoleteSslProtocol": false,
+            "obsoleteSslKeyExchange": false,
+            "obsoleteSslCipher": false,
+            "obsoleteSslSignature": false
+        }))
+        .unwrap();
+
+        let info = CertificateInfo::from(&state);
+        assert_eq!(info.valid_from, "2024-01-01T00:00:00+00:00");
+        assert_eq!(info.valid_to, "2025-01-01T00:00:00+00:00");
+        assert_eq!(
+            info.network_error.as_deref(),
+            Some("net::ERR_CERT_AUTHORITY_INVALID")
+        );
+        assert!(!info.weak_signature && !info.obsolete_tls);
+        assert_eq!(info.chain, vec!["MIIB".to_string()]);
+    }
+}
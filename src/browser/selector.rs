@@ -0,0 +1,388 @@
+//! Fallback Selectors
+//!
+//! Element-based script steps (`Form.typeText`, `Touch.tap`) can list fallback
+//! selectors, a recorded [`ElementFingerprint`], and the text of the element, to use
+//! when their selector stops matching after a frontend change. [`resolve`] tries them
+//! in order and returns a selector that matches along with where it came from, so
+//! reports show which scripts need updating.
+
+use crate::error::{BrowserError, Result};
+use crate::model::fingerprint::{
+    ElementFingerprint, DEFAULT_MATCH_THRESHOLD, FINGERPRINT_ATTRIBUTES,
+};
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Attribute marking the element found by its text
+const MARKER: &str = "data-robert-text-match";
+
+/// Attribute numbering the candidates for a fingerprint match
+const FINGERPRINT_MARKER: &str = "data-robert-fingerprint";
+
+/// Most elements compared against a fingerprint
+const MAX_FINGERPRINT_CANDIDATES: usize = 1000;
+
+/// Describes an element as an [`ElementFingerprint`], plus a selector that would find
+/// it (None if it has nothing unique to select by)
+const DESCRIBE_FUNCTION: &str = r#"(el) => {
+    const rect = el.getBoundingClientRect();
+    const attributes = {};
+    for (const name of ATTRIBUTES) {
+        const value = el.getAttribute(name);
+        if (value) attributes[name] = value;
+    }
+    const unique = (selector) => document.querySelectorAll(selector).length === 1 ? selector : null;
+    const tag = el.tagName.toLowerCase();
+    const suggested = (el.id && unique('#' + CSS.escape(el.id)))
+        || (attributes['data-testid'] && unique(`[data-testid="${CSS.escape(attributes['data-testid'])}"]`))
+        || (attributes.name && unique(`${tag}[name="${CSS.escape(attributes.name)}"]`))
+        || (attributes['aria-label'] && unique(`${tag}[aria-label="${CSS.escape(attributes['aria-label'])}"]`))
+        || null;
+    return {
+        fingerprint: {
+            tag,
+            text: (el.innerText || el.value || '').replace(/\s+/g, ' ').trim().slice(0, 200),
+            attributes,
+            rect: { x: rect.x + scrollX, y: rect.y + scrollY, width: rect.width, height: rect.height },
+        },
+        suggested,
+    };
+}"#;
+
+/// Numbers the visible elements with the fingerprint's tag (or, if there are none,
+/// the visible interactive elements) with [`FINGERPRINT_MARKER`] and describes them
+const COLLECT_SCRIPT: &str = r#"(tag, limit) => {
+    const describe = DESCRIBE;
+    document.querySelectorAll('[MARKER]').forEach((el) => el.removeAttribute('MARKER'));
+    const visible = (el) => {
+        const rect = el.getBoundingClientRect();
+        return rect.width > 0 && rect.height > 0;
+    };
+    let elements = Array.from(document.getElementsByTagName(tag)).filter(visible);
+    if (elements.length === 0) {
+        elements = Array.from(document.querySelectorAll(
+            'a, button, input, select, textarea, label, summary, [role], [contenteditable]'
+        )).filter(visible);
+    }
+    return elements.slice(0, limit).map((el, index) => {
+        el.setAttribute('MARKER', index);
+        return describe(el);
+    });
+}"#;
+
+/// Finds the visible element whose text (or value, label, or placeholder) is `text`,
+/// preferring interactive elements and exact matches, and marks it with [`MARKER`]
+///
+/// Texts are compared the way [`TextMatcher`](crate::model::text_match::TextMatcher)
+/// does by default: without case, diacritics, invisible characters, or nbsp.
+const FIND_BY_TEXT_SCRIPT: &str = r#"(text) => {
+    const norm = (s) => (s || '').normalize('NFKD').replace(/[\u0300-\u036f\u00ad\u200b-\u200d\u2060\ufeff]/g, '')
+        .replace(/[\u2018\u2019\u201a\u201b\u2032]/g, "'").replace(/[\u201c-\u201f\u2033]/g, '"')
+        .replace(/[\u2010-\u2015\u2212]/g, '-').replace(/\s+/g, ' ').trim().toLowerCase()
+        .replace(/\u00df/g, 'ss');
+    const wanted = norm(text);
+    document.querySelectorAll('[MARKER]').forEach((el) => el.removeAttribute('MARKER'));
+    const visible = (el) => {
+        const rect = el.getBoundingClientRect();
+        return rect.width > 0 && rect.height > 0;
+    };
+    const label = (el) => norm(el.innerText || el.value || el.getAttribute('aria-label')
+        || el.getAttribute('placeholder'));
+    const interactive = Array.from(document.querySelectorAll(
+        'a, button, input, select, textarea, label, summary, [role=button], [role=link], '
+        + '[role=menuitem], [role=tab], [role=checkbox], [contenteditable]'
+    )).filter(visible);
+    const match = interactive.find((el) => label(el) === wanted)
+        || interactive.find((el) => label(el).includes(wanted))
+        // Otherwise the innermost element with exactly that text
+        || Array.from(document.body.querySelectorAll('*'))
+            .filter((el) => visible(el) && label(el) === wanted)
+            .pop();
+    if (!match) return false;
+    match.setAttribute('MARKER', '');
+    return true;
+}"#;
+
+/// Ways to find a step's element, tried in order
+#[derive(Debug, Clone, Default)]
+pub struct SelectorCandidates {
+    /// The step's selector
+    pub selector: String,
+
+    /// Selectors to try when `selector` matches nothing
+    pub fallbacks: Vec<String>,
+
+    /// How the element looked when the script was written, matched against the page
+    /// when no selector matches
+    pub fingerprint: Option<ElementFingerprint>,
+
+    /// Lowest similarity accepted for a fingerprint match (None =
+    /// [`DEFAULT_MATCH_THRESHOLD`])
+    pub fingerprint_threshold: Option<f64>,
+
+    /// Text of the element, tried last
+    pub text: Option<String>,
+}
+
+impl SelectorCandidates {
+    /// Candidates from a command's `selector`, `fallbackSelectors`, `fingerprint`,
+    /// `fingerprintThreshold`, and `fallbackText` parameters
+    pub fn from_params(params: &Value) -> Option<Self> {
+        Some(Self {
+            selector: params.get("selector")?.as_str()?.to_string(),
+            fallbacks: params
+                .get("fallbackSelectors")
+                .and_then(Value::as_array)
+                .map(|selectors| {
+                    selectors
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            fingerprint: params
+                .get("fingerprint")
+                .and_then(|fingerprint| serde_json::from_value(fingerprint.clone()).ok()),
+            fingerprint_threshold: params.get("fingerprintThreshold").and_then(Value::as_f64),
+            text: params
+                .get("fallbackText")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        })
+    }
+}
+
+/// Which candidate found the element
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MatchSource {
+    /// The step's selector
+    Selector,
+    /// A fallback selector (index into the fallbacks)
+    Fallback { index: usize },
+    /// The element most similar to the recorded fingerprint
+    Fingerprint {
+        /// Similarity to the fingerprint (0 to 1)
+        confidence: f64,
+        /// A selector that finds the element, to repair the script with
+        #[serde(skip_serializing_if = "Option::is_none")]
+        suggested: Option<String>,
+    },
+    /// The element's text
+    Text,
+}
+
+/// A selector that matches the step's element
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SelectorMatch {
+    /// The selector to use (for text matches, one addressing the element found)
+    pub selector: String,
+
+    /// Which candidate found the element
+    pub source: MatchSource,
+}
+
+/// Find the first of `candidates` that matches an element on `page`
+pub async fn resolve(page: &Page, candidates: &SelectorCandidates) -> Result<SelectorMatch> {
+    let selectors = std::iter::once((MatchSource::Selector, &candidates.selector)).chain(
+        candidates
+            .fallbacks
+            .iter()
+            .enumerate()
+            .map(|(index, selector)| (MatchSource::Fallback { index }, selector)),
+    );
+    for (source, selector) in selectors {
+        if page.find_element(selector.as_str()).await.is_ok() {
+            if source != MatchSource::Selector {
+                tracing::info!(
+                    selector = %candidates.selector,
+                    fallback = %selector,
+                    "Selector matched nothing, used a fallback"
+                );
+            }
+            return Ok(SelectorMatch {
+                selector: selector.clone(),
+                source,
+            });
+        }
+    }
+
+    if let Some(fingerprint) = &candidates.fingerprint {
+        let threshold = candidates
+            .fingerprint_threshold
+            .unwrap_or(DEFAULT_MATCH_THRESHOLD);
+        if let Some(matched) = match_fingerprint(page, fingerprint, threshold).await? {
+            if let MatchSource::Fingerprint {
+                confidence,
+                suggested,
+            } = &matched.source
+            {
+                tracing::warn!(
+                    selector = %candidates.selector,
+                    confidence,
+                    suggested = suggested.as_deref(),
+                    "Selector matched nothing, healed it from the element fingerprint"
+                );
+            }
+            return Ok(matched);
+        }
+    }
+
+    if let Some(text) = &candidates.text {
+        let found: bool = page
+            .evaluate(format!(
+                "({})({})",
+                FIND_BY_TEXT_SCRIPT.replace("MARKER", MARKER),
+                Value::from(text.as_str())
+            ))
+            .await?
+            .into_value()
+            .map_err(|e| BrowserError::Other(format!("Failed to search by text: {}", e)))?;
+        if found {
+            tracing::info!(
+                selector = %candidates.selector,
+                text = %text,
+                "Selector matched nothing, found the element by its text"
+            );
+            return Ok(SelectorMatch {
+                selector: format!("[{}]", MARKER),
+                source: MatchSource::Text,
+            });
+        }
+    }
+
+    let mut tried = vec![candidates.selector.clone()];
+    tried.extend(candidates.fallbacks.iter().cloned());
+    if let Some(fingerprint) = &candidates.fingerprint {
+        tried.push(format!("fingerprint of <{}>", fingerprint.tag));
+    }
+    if let Some(text) = &candidates.text {
+        tried.push(format!("text '{}'", text));
+    }
+    Err(BrowserError::ElementNotFound(tried.join(", ")))
+}
+
+/// A described element
+#[derive(Debug, Deserialize)]
+struct Described {
+    fingerprint: ElementFingerprint,
+    suggested: Option<String>,
+}
+
+/// Record how the first element matching `selector` looks, for self-healing steps
+pub async fn fingerprint(page: &Page, selector: &str) -> Result<ElementFingerprint> {
+    let element = page
+        .find_element(selector)
+        .await
+        .map_err(|_| BrowserError::ElementNotFound(selector.to_string()))?;
+    let described: Described = element
+        .call_js_fn(
+            format!("function() {{ return ({})(this); }}", describe_function()),
+            false,
+        )
+        .await?
+        .result
+        .value
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| BrowserError::Other(format!("Failed to fingerprint element: {}", e)))?
+        .ok_or_else(|| BrowserError::Other("Failed to fingerprint element".to_string()))?;
+    Ok(described.fingerprint)
+}
+
+/// The element on `page` most similar to `fingerprint`, if it is at least `threshold`
+/// similar
+async fn match_fingerprint(
+    page: &Page,
+    fingerprint: &ElementFingerprint,
+    threshold: f64,
+) -> Result<Option<SelectorMatch>> {
+    let described: Vec<Described> = page
+        .evaluate(format!(
+            "({})({}, {})",
+            COLLECT_SCRIPT
+                .replace("DESCRIBE", &describe_function())
+                .replace("MARKER", FINGERPRINT_MARKER),
+            Value::from(fingerprint.tag.as_str()),
+            MAX_FINGERPRINT_CANDIDATES
+        ))
+        .await?
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Failed to read page elements: {}", e)))?;
+
+    let best = described
+        .into_iter()
+        .enumerate()
+        .map(|(index, candidate)| {
+            let confidence = fingerprint.similarity(&candidate.fingerprint);
+            (index, confidence, candidate.suggested)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    let best_confidence = best.as_ref().map(|best| best.1);
+    let Some((index, confidence, suggested)) = best.filter(|best| best.1 >= threshold) else {
+        tracing::debug!(
+            best = best_confidence,
+            threshold,
+            "No element close enough to the fingerprint"
+        );
+        return Ok(None);
+    };
+
+    // Only the chosen element keeps its number
+    page.evaluate(format!(
+        "document.querySelectorAll('[{0}]').forEach((el) => {{ \
+            if (el.getAttribute('{0}') !== '{1}') el.removeAttribute('{0}'); }})",
+        FINGERPRINT_MARKER, index
+    ))
+    .await?;
+    Ok(Some(SelectorMatch {
+        selector: format!("[{}=\"{}\"]", FINGERPRINT_MARKER, index),
+        source: MatchSource::Fingerprint {
+            confidence,
+            suggested,
+        },
+    }))
+}
+
+fn describe_function() -> String {
+    DESCRIBE_FUNCTION.replace(
+        "ATTRIBUTES",
+        &Value::from(FINGERPRINT_ATTRIBUTES).to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_candidates_from_params() {
+        let candidates = SelectorCandidates::from_params(&json!({
+            "selector": "#login",
+            "fallbackSelectors": ["button[type=submit]", 3, ".login-btn"],
+            "fallbackText": "Sign in",
+        }))
+        .unwrap();
+        assert_eq!(candidates.selector, "#login");
+        assert_eq!(candidates.fallbacks, ["button[type=submit]", ".login-btn"]);
+        assert_eq!(candidates.text.as_deref(), Some("Sign in"));
+        assert!(candidates.fingerprint.is_none());
+
+        let candidates = SelectorCandidates::from_params(&json!({
+            "selector": "#login",
+            "fingerprint": { "tag": "button", "text": "Sign in" },
+            "fingerprintThreshold": 0.8,
+        }))
+        .unwrap();
+        assert_eq!(candidates.fingerprint.unwrap().text, "Sign in");
+        assert_eq!(candidates.fingerprint_threshold, Some(0.8));
+
+        assert!(SelectorCandidates::from_params(&json!({ "text": "x" })).is_none());
+
+        let source = serde_json::to_value(MatchSource::Fallback { index: 1 }).unwrap();
+        assert_eq!(source, json!({ "kind": "fallback", "index": 1 }));
+    }
+}
@@ -0,0 +1,336 @@
+//! SEO Metadata
+//!
+//! Reads what search engines and link previews see in a page's `<head>`: title,
+//! meta description, canonical URL, robots directives, Open Graph and Twitter card
+//! tags, and JSON-LD structured data (parsed). [`SeoRules`] checks the result for
+//! the usual problems: missing tags, titles and descriptions outside the lengths
+//! search results display, unparseable JSON-LD, and (optionally) `noindex`.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+const SEO_SCRIPT: &str = r#"(() => {
+    const meta = (selector) => {
+        const element = document.querySelector(selector);
+        return element ? element.getAttribute('content') : null;
+    };
+    const prefixed = (attribute, prefix) => {
+        const tags = {};
+        for (const element of document.querySelectorAll(`meta[${attribute}^="${prefix}"]`)) {
+            const key = element.getAttribute(attribute);
+            if (!(key in tags)) tags[key] = element.getAttribute('content') || '';
+        }
+        return tags;
+    };
+    const canonical = document.querySelector('link[rel="canonical"]');
+    return {
+        url: location.href,
+        title: document.querySelector('title') ? document.title : null,
+        description: meta('meta[name="description" i]'),
+        canonical: canonical ? canonical.href : null,
+        robots: meta('meta[name="robots" i]'),
+        lang: document.documentElement.getAttribute('lang'),
+        h1: Array.from(document.querySelectorAll('h1'), (h) => h.textContent.trim()),
+        open_graph: prefixed('property', 'og:'),
+        // Twitter tags are specified with name=, but property= is common in the wild
+        twitter: Object.assign(prefixed('property', 'twitter:'), prefixed('name', 'twitter:')),
+        json_ld: Array.from(
+            document.querySelectorAll('script[type="application/ld+json"]'),
+            (s) => s.textContent),
+    };
+})()"#;
+
+/// SEO-relevant metadata of a page
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SeoMetadata {
+    /// Page URL
+    pub url: String,
+
+    /// `<title>` text
+    pub title: Option<String>,
+
+    /// `<meta name="description">` content
+    pub description: Option<String>,
+
+    /// `<link rel="canonical">` URL (absolute)
+    pub canonical: Option<String>,
+
+    /// `<meta name="robots">` directives, lowercased (e.g. `noindex`, `nofollow`)
+    pub robots: Vec<String>,
+
+    /// `<html lang>`
+    pub lang: Option<String>,
+
+    /// Text of each `<h1>`
+    pub h1: Vec<String>,
+
+    /// `og:*` tags by property (first occurrence wins)
+    pub open_graph: BTreeMap<String, String>,
+
+    /// `twitter:*` tags by name
+    pub twitter: BTreeMap<String, String>,
+
+    /// Parsed `application/ld+json` blocks
+    pub structured_data: Vec<Value>,
+
+    /// Parse errors of JSON-LD blocks that are not valid JSON
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub structured_data_errors: Vec<String>,
+}
+
+impl SeoMetadata {
+    /// Whether robots directives keep the page out of search results
+    pub fn is_noindex(&self) -> bool {
+        self.robots.iter().any(|d| d == "noindex" || d == "none")
+    }
+
+    /// Problems found by `rules`, in field order
+    pub fn validate(&self, rules: &SeoRules) -> Vec<SeoIssue> {
+        let mut issues = Vec::new();
+        check_length(
+            &mut issues,
+            "title",
+            self.title.as_deref(),
+            rules.title_length,
+        );
+        check_length(
+            &mut issues,
+            "description",
+            self.description.as_deref(),
+            rules.description_length,
+        );
+
+        for tag in &rules.required {
+            let present = match tag.as_str() {
+                "title" | "description" => continue, // covered by the length checks
+                "canonical" => self.canonical.is_some(),
+                "lang" => self.lang.is_some(),
+                "h1" => !self.h1.is_empty(),
+                "structured_data" => !self.structured_data.is_empty(),
+                key if key.starts_with("og:") => self.open_graph.contains_key(key),
+                key if key.starts_with("twitter:") => self.twitter.contains_key(key),
+                _ => true,
+            };
+            if !present {
+                issues.push(SeoIssue::new(tag, "missing"));
+            }
+        }
+
+        if self.h1.len() > 1 && rules.single_h1 {
+            issues.push(SeoIssue::new(
+                "h1",
+                format!("{} <h1> elements, expected one", self.h1.len()),
+            ));
+        }
+        if self.is_noindex() && !rules.allow_noindex {
+            issues.push(SeoIssue::new("robots", "page is noindex"));
+        }
+        for error in &self.structured_data_errors {
+            issues.push(SeoIssue::new("structured_data", error.clone()));
+        }
+        issues
+    }
+}
+
+/// Inclusive length range, in characters
+pub type LengthRange = (usize, usize);
+
+/// What [`SeoMetadata::validate`] checks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SeoRules {
+    /// Title length search results show without truncating
+    pub title_length: LengthRange,
+
+    /// Description length search results show without truncating
+    pub description_length: LengthRange,
+
+    /// Tags that must be present: `canonical`, `lang`, `h1`, `structured_data`, or any
+    /// `og:*` / `twitter:*` name (title and description are always required)
+    pub required: Vec<String>,
+
+    /// Report pages with more than one `<h1>`
+    pub single_h1: bool,
+
+    /// Accept `noindex` pages (turn off to catch staging settings leaking to production)
+    pub allow_noindex: bool,
+}
+
+impl Default for SeoRules {
+    fn default() -> Self {
+        Self {
+            title_length: (10, 60),
+            description_length: (50, 160),
+            required: vec!["canonical".to_string(), "og:title".to_string()],
+            single_h1: false,
+            allow_noindex: true,
+        }
+    }
+}
+
+/// A validation problem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeoIssue {
+    /// Field or tag the problem is about (e.g. `title`, `og:image`)
+    pub field: String,
+
+    pub message: String,
+}
+
+impl SeoIssue {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+fn check_length(issues: &mut Vec<SeoIssue>, field: &str, text: Option<&str>, range: LengthRange) {
+    let Some(text) = text.map(str::trim).filter(|t| !t.is_empty()) else {
+        issues.push(SeoIssue::new(field, "missing"));
+        return;
+    };
+    let length = text.chars().count();
+    if length < range.0 {
+        issues.push(SeoIssue::new(
+            field,
+            format!("{} characters, shorter than {}", length, range.0),
+        ));
+    } else if length > range.1 {
+        issues.push(SeoIssue::new(
+            field,
+            format!("{} characters, longer than {}", length, range.1),
+        ));
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMetadata {
+    url: String,
+    title: Option<String>,
+    description: Option<String>,
+    canonical: Option<String>,
+    robots: Option<String>,
+    lang: Option<String>,
+    h1: Vec<String>,
+    open_graph: BTreeMap<String, String>,
+    twitter: BTreeMap<String, String>,
+    json_ld: Vec<String>,
+}
+
+impl From<RawMetadata> for SeoMetadata {
+    fn from(raw: RawMetadata) -> Self {
+        let mut structured_data = Vec::new();
+        let mut structured_data_errors = Vec::new();
+        for (index, block) in raw.json_ld.iter().enumerate() {
+            match serde_json::from_str::<Value>(block) {
+                Ok(value) => structured_data.push(value),
+                Err(e) => {
+                    structured_data_errors.push(format!("JSON-LD block {}: {}", index + 1, e))
+                }
+            }
+        }
+        Self {
+            url: raw.url,
+            title: raw.title,
+            description: raw.description,
+            canonical: raw.canonical,
+            robots: raw
+                .robots
+                .unwrap_or_default()
+                .split(',')
+                .map(|d| d.trim().to_lowercase())
+                .filter(|d| !d.is_empty())
+                .collect(),
+            lang: raw.lang,
+            h1: raw.h1,
+            open_graph: raw.open_graph,
+            twitter: raw.twitter,
+            structured_data,
+            structured_data_errors,
+        }
+    }
+}
+
+/// SEO metadata of the page currently shown in `page`
+pub async fn extract_seo(page: &Page) -> Result<SeoMetadata> {
+    let raw: RawMetadata = page
+        .evaluate(SEO_SCRIPT)
+        .await?
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Failed to read SEO metadata: {}", e)))?;
+    Ok(raw.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_metadata_parsing() {
+        let raw = RawMetadata {
+            url: "https://example.com/".to_string(),
+            title: Some("Example".to_string()),
+            description: None,
+            canonical: None,
+            robots: Some("NoIndex, follow".to_string()),
+            lang: None,
+            h1: Vec::new(),
+            open_graph: BTreeMap::new(),
+            twitter: BTreeMap::new(),
+            json_ld: vec![
+                r#"{"@type": "Organization"}"#.to_string(),
+                "{oops".to_string(),
+            ],
+        };
+        let metadata = SeoMetadata::from(raw);
+        assert_eq!(metadata.robots, vec!["noindex", "follow"]);
+        assert!(metadata.is_noindex());
+        assert_eq!(metadata.structured_data[0]["@type"], "Organization");
+        assert_eq!(metadata.structured_data_errors.len(), 1);
+        assert!(metadata.structured_data_errors[0].starts_with("JSON-LD block 2"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let metadata = SeoMetadata {
+            url: "https://example.com/".to_string(),
+            title: Some("Short".to_string()),
+            description: Some("A".repeat(200)),
+            canonical: Some("https://example.com/".to_string()),
+            robots: vec!["noindex".to_string()],
+            h1: vec!["One".to_string(), "Two".to_string()],
+            open_graph: [("og:title".to_string(), "Example".to_string())].into(),
+            ..Default::default()
+        };
+        let rules = SeoRules {
+            required: vec!["canonical".to_string(), "og:image".to_string()],
+            single_h1: true,
+            allow_noindex: false,
+            ..Default::default()
+        };
+
+        let fields: Vec<(String, String)> = metadata
+            .validate(&rules)
+            .into_iter()
+            .map(|issue| (issue.field, issue.message))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("title".into(), "5 characters, shorter than 10".into()),
+                (
+                    "description".into(),
+                    "200 characters, longer than 160".into()
+                ),
+                ("og:image".into(), "missing".into()),
+                ("h1".into(), "2 <h1> elements, expected one".into()),
+                ("robots".into(), "page is noindex".into()),
+            ]
+        );
+    }
+}
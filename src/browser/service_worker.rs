@@ -0,0 +1,125 @@
+//! Service Workers and Offline Mode
+//!
+//! Progressive web apps answer requests from a service worker and its caches, so
+//! a scraping run can keep seeing stale content long after the site has changed.
+//! These helpers list a page's service worker registrations, stop or unregister
+//! them, route requests around them, clear the caches they fill, and emulate
+//! being offline (e.g. to check what a PWA serves without a network).
+//!
+//! Bypass and offline emulation apply to the page they were set on and persist
+//! across its navigations.
+
+use crate::error::{BrowserError, Result};
+use chromiumoxide::cdp::browser_protocol::network::{
+    EmulateNetworkConditionsParams, SetBypassServiceWorkerParams,
+};
+use chromiumoxide::cdp::browser_protocol::{network, service_worker, storage};
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+
+/// A service worker registration of the current page's origin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceWorkerRegistration {
+    /// URL scope the worker controls
+    pub scope: String,
+
+    /// Script URL of the newest worker (active, waiting, or installing)
+    pub script_url: Option<String>,
+
+    /// State of that worker (`installing`, `installed`, `activating`, `activated`, `redundant`)
+    pub state: Option<String>,
+}
+
+/// Service worker registrations of the page's origin
+pub async fn registrations(page: &Page) -> Result<Vec<ServiceWorkerRegistration>> {
+    page.evaluate(REGISTRATIONS_SCRIPT)
+        .await
+        .map_err(|e| BrowserError::Other(format!("Failed to list service workers: {}", e)))?
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Invalid service worker list: {}", e)))
+}
+
+/// Stop all running service workers (they restart on the next request they handle)
+pub async fn stop_all(page: &Page) -> Result<()> {
+    page.execute(service_worker::EnableParams::default())
+        .await?;
+    page.execute(service_worker::StopAllWorkersParams::default())
+        .await?;
+    Ok(())
+}
+
+/// Unregister every service worker of the page's origin; returns how many there were
+pub async fn unregister_all(page: &Page) -> Result<usize> {
+    let registrations = registrations(page).await?;
+    page.execute(service_worker::EnableParams::default())
+        .await?;
+    for registration in &registrations {
+        page.execute(service_worker::UnregisterParams::new(
+            registration.scope.clone(),
+        ))
+        .await
+        .map_err(|e| {
+            BrowserError::Other(format!(
+                "Failed to unregister service worker for {}: {}",
+                registration.scope, e
+            ))
+        })?;
+    }
+    Ok(registrations.len())
+}
+
+/// Send the page's requests straight to the network instead of its service workers
+pub async fn set_bypass(page: &Page, bypass: bool) -> Result<()> {
+    page.execute(network::EnableParams::default()).await?;
+    page.execute(SetBypassServiceWorkerParams::new(bypass))
+        .await?;
+    tracing::debug!(bypass, "Service worker bypass set");
+    Ok(())
+}
+
+/// Delete the Cache Storage of the page's origin (where service workers keep responses)
+pub async fn clear_caches(page: &Page) -> Result<()> {
+    let origin: String = page
+        .evaluate("window.location.origin")
+        .await
+        .map_err(|e| BrowserError::Other(format!("Failed to read page origin: {}", e)))?
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Invalid page origin: {}", e)))?;
+    if origin == "null" {
+        return Err(BrowserError::Other(
+            "Page has an opaque origin with no caches to clear".to_string(),
+        ));
+    }
+    page.execute(storage::ClearDataForOriginParams::new(
+        origin.clone(),
+        "cache_storage",
+    ))
+    .await?;
+    tracing::debug!(origin = %origin, "Cleared cache storage");
+    Ok(())
+}
+
+/// Emulate having no network connection (or restore it)
+pub async fn set_offline(page: &Page, offline: bool) -> Result<()> {
+    page.execute(network::EnableParams::default()).await?;
+    // -1 throughput disables throttling
+    page.execute(EmulateNetworkConditionsParams::new(
+        offline, 0.0, -1.0, -1.0,
+    ))
+    .await?;
+    tracing::debug!(offline, "Offline emulation set");
+    Ok(())
+}
+
+const REGISTRATIONS_SCRIPT: &str = r#"(async () => {
+    if (!('serviceWorker' in navigator)) return [];
+    const registrations = await navigator.serviceWorker.getRegistrations();
+    return registrations.map((r) => {
+        const worker = r.installing || r.waiting || r.active;
+        return {
+            scope: r.scope,
+            script_url: worker ? worker.scriptURL : null,
+            state: worker ? worker.state : null,
+        };
+    });
+})()"#;
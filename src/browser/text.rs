@@ -0,0 +1,214 @@
+//! Finding Text on the Page
+//!
+//! Looks for text the way a reader sees it: every visible element with text of its
+//! own (and every form control's value, placeholder, or label) is a candidate, and
+//! [`TextMatcher`] compares them with the wanted text after normalization, so nbsp,
+//! diacritics, typographic punctuation, and case don't cause false failures. Hits
+//! come back best first, with the smallest element among equally good ones.
+
+use crate::error::{BrowserError, Result};
+use crate::model::text_match::TextMatcher;
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+
+/// Most candidates read from the page
+const MAX_CANDIDATES: usize = 2000;
+
+/// Visible elements with text of their own below `scope` (the body if null), with
+/// their rendered text; null if `scope` matches nothing
+const TEXT_SCRIPT: &str = r#"(scope, limit) => {
+    const root = scope ? document.querySelector(scope) : document.body;
+    if (!root) return null;
+    const selectorOf = (target) => {
+        const parts = [];
+        for (let el = target; el && el !== document.documentElement; el = el.parentElement) {
+            if (el.id && /^[A-Za-z][\w-]*$/.test(el.id) && document.querySelectorAll('#' + el.id).length === 1) {
+                parts.unshift('#' + el.id);
+                break;
+            }
+            const tag = el.tagName.toLowerCase();
+            const same = el.parentElement
+                ? Array.from(el.parentElement.children).filter((c) => c.tagName === el.tagName)
+                : [el];
+            parts.unshift(same.length > 1 ? `${tag}:nth-of-type(${same.indexOf(el) + 1})` : tag);
+        }
+        if (!parts.length || !parts[0].startsWith('#')) parts.unshift('html');
+        return parts.join(' > ');
+    };
+    const visible = (el) => {
+        const rect = el.getBoundingClientRect();
+        return rect.width > 0 && rect.height > 0
+            && window.getComputedStyle(el).visibility !== 'hidden';
+    };
+    const ownText = (el) => Array.from(el.childNodes)
+        .some((node) => node.nodeType === Node.TEXT_NODE && node.textContent.trim());
+    const candidates = [];
+    for (const el of [root, ...root.querySelectorAll('*')]) {
+        if (candidates.length >= limit) break;
+        if (['SCRIPT', 'STYLE', 'NOSCRIPT', 'TEMPLATE'].includes(el.tagName) || !visible(el)) continue;
+        let text = null;
+        if (['INPUT', 'TEXTAREA', 'SELECT'].includes(el.tagName)) {
+            text = el.value || el.getAttribute('placeholder') || el.getAttribute('aria-label');
+        } else if (ownText(el)) {
+            text = el.innerText;
+        }
+        if (text && text.trim()) {
+            candidates.push({ selector: selectorOf(el), text: text.slice(0, 1000) });
+        }
+    }
+    return candidates;
+}"#;
+
+/// Text to look for, and how
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextQuery {
+    /// The wanted text
+    pub text: String,
+
+    /// Only look inside the first element matching this selector
+    #[serde(default)]
+    pub selector: Option<String>,
+
+    /// Match an element's whole text rather than text it contains
+    #[serde(default)]
+    pub exact: bool,
+
+    #[serde(flatten)]
+    pub matcher: TextMatcher,
+}
+
+impl TextQuery {
+    /// Look for `text` anywhere on the page with the default [`TextMatcher`]
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// An element whose text matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextHit {
+    /// CSS selector of the element
+    pub selector: String,
+
+    /// Its rendered text
+    pub text: String,
+
+    /// How well it matches, from the fuzzy threshold to 1 (exact after normalization)
+    #[serde(default)]
+    pub score: f64,
+}
+
+/// Elements on the page whose text matches `query`, best first
+pub async fn find_text(page: &Page, query: &TextQuery) -> Result<Vec<TextHit>> {
+    let selector = query
+        .selector
+        .as_deref()
+        .map_or(serde_json::Value::Null, serde_json::Value::from);
+    let candidates: Option<Vec<TextHit>> = page
+        .evaluate(format!(
+            "({})({}, {})",
+            TEXT_SCRIPT, selector, MAX_CANDIDATES
+        ))
+        .await?
+        .into_value()
+        .map_err(|e| BrowserError::Other(format!("Failed to read the page's text: {}", e)))?;
+    match candidates {
+        Some(candidates) => Ok(rank(candidates, query)),
+        None => Err(BrowserError::ElementNotFound(
+            query.selector.clone().unwrap_or_default(),
+        )),
+    }
+}
+
+/// `candidates` (read with a score of 0) that match `query`, scored and sorted best
+/// first, then shortest text first
+fn rank(candidates: Vec<TextHit>, query: &TextQuery) -> Vec<TextHit> {
+    let matcher = &query.matcher;
+    let mut hits: Vec<TextHit> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let score = if query.exact {
+                let score = match matcher.fuzzy {
+                    Some(_) => matcher.similarity(&candidate.text, &query.text),
+                    None => 1.0,
+                };
+                matcher
+                    .matches(&candidate.text, &query.text)
+                    .then_some(score)
+            } else {
+                matcher.find(&candidate.text, &query.text)
+            }?;
+            Some(TextHit { score, ..candidate })
+        })
+        .collect();
+    hits.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.text.chars().count().cmp(&b.text.chars().count()))
+    });
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(selector: &str, text: &str) -> TextHit {
+        TextHit {
+            selector: selector.to_string(),
+            text: text.to_string(),
+            score: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_rank() {
+        let candidates = vec![
+            candidate("main", "Votre commande\u{a0}est confirmée. Merci !"),
+            candidate("h1", "Commande confirmée"),
+            candidate("button", "Continuer mes achats"),
+        ];
+
+        let hits = rank(candidates.clone(), &TextQuery::new("commande confirmee"));
+        let selectors: Vec<&str> = hits.iter().map(|hit| hit.selector.as_str()).collect();
+        assert_eq!(selectors, ["h1"]);
+        assert_eq!(hits[0].score, 1.0);
+
+        let query = TextQuery {
+            matcher: TextMatcher::fuzzy(0.7),
+            ..TextQuery::new("Commande est confirmee")
+        };
+        let hits = rank(candidates.clone(), &query);
+        assert_eq!(hits[0].selector, "main");
+        assert_eq!(hits[0].score, 1.0);
+        assert!(hits[1].score < 1.0 && hits[1].selector == "h1");
+
+        let query = TextQuery {
+            exact: true,
+            ..TextQuery::new("continuer  mes achats")
+        };
+        let hits = rank(candidates.clone(), &query);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].selector, "button");
+        assert!(rank(
+            candidates,
+            &TextQuery {
+                exact: true,
+                ..TextQuery::new("commande")
+            }
+        )
+        .is_empty());
+
+        let query: TextQuery = serde_json::from_value(serde_json::json!({
+            "text": "Merci", "selector": "main", "caseSensitive": true, "fuzzy": 0.9
+        }))
+        .unwrap();
+        assert_eq!(query.selector.as_deref(), Some("main"));
+        assert!(query.matcher.case_sensitive && query.matcher.fold_diacritics);
+        assert_eq!(query.matcher.fuzzy, Some(0.9));
+    }
+}
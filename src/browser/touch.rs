@@ -0,0 +1,150 @@
+//! Touch Gestures
+//!
+//! Taps, swipes, and pinches sent as `Input.dispatchTouchEvent` sequences, so
+//! mobile-emulated sessions can drive UI that only listens for touch events
+//! (swipe carousels, pull-to-refresh, pinch-to-zoom maps). Touch emulation is
+//! turned on before each gesture; pages see `touchstart`/`touchmove`/`touchend`
+//! (and the pointer events Chrome derives from them) as they would on a phone.
+//!
+//! Coordinates are CSS pixels relative to the viewport, like
+//! [`ElementRect::viewport`](super::geometry::ElementRect::viewport).
+
+use super::geometry;
+use crate::error::{BrowserError, Result};
+use chromiumoxide::cdp::browser_protocol::emulation::SetTouchEmulationEnabledParams;
+use chromiumoxide::cdp::browser_protocol::input::{
+    DispatchTouchEventParams, DispatchTouchEventType, TouchPoint,
+};
+use chromiumoxide::page::Page;
+use std::time::Duration;
+
+/// Interval between touch moves (one frame at 60 Hz)
+const FRAME: Duration = Duration::from_millis(16);
+
+/// Touch points the emulated screen supports (two for pinches)
+const MAX_TOUCH_POINTS: i64 = 2;
+
+/// Distance of each finger from the center when a pinch starts, in CSS pixels
+const PINCH_RADIUS: f64 = 50.0;
+
+/// How long a pinch takes
+const PINCH_DURATION: Duration = Duration::from_millis(300);
+
+/// Turn on touch event emulation for `page`
+pub async fn enable_touch(page: &Page) -> Result<()> {
+    page.execute(
+        SetTouchEmulationEnabledParams::builder()
+            .enabled(true)
+            .max_touch_points(MAX_TOUCH_POINTS)
+            .build()
+            .map_err(BrowserError::Other)?,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Tap the center of the first element matching `selector`, scrolling it into view
+pub async fn tap(page: &Page, selector: &str) -> Result<()> {
+    let (x, y) = geometry::visible_center(page, selector).await?;
+    enable_touch(page).await?;
+    touch(page, DispatchTouchEventType::TouchStart, &[(x, y)]).await?;
+    touch(page, DispatchTouchEventType::TouchEnd, &[]).await?;
+    tracing::debug!(selector, x, y, "Tapped");
+    Ok(())
+}
+
+/// Drag one finger from `from` to `to` over `duration`
+pub async fn swipe(
+    page: &Page,
+    from: (f64, f64),
+    to: (f64, f64),
+    duration: Duration,
+) -> Result<()> {
+    enable_touch(page).await?;
+    touch(page, DispatchTouchEventType::TouchStart, &[from]).await?;
+    for point in path(from, to, steps(duration)) {
+        tokio::time::sleep(FRAME).await;
+        touch(page, DispatchTouchEventType::TouchMove, &[point]).await?;
+    }
+    touch(page, DispatchTouchEventType::TouchEnd, &[]).await?;
+    tracing::debug!(?from, ?to, ?duration, "Swiped");
+    Ok(())
+}
+
+/// Pinch two fingers around the center of the viewport; `scale` > 1 spreads them
+/// apart (zoom in), < 1 brings them together (zoom out)
+pub async fn pinch_zoom(page: &Page, scale: f64) -> Result<()> {
+    if !(scale.is_finite() && scale > 0.0) {
+        return Err(BrowserError::Other(format!(
+            "Pinch scale must be positive, got {}",
+            scale
+        )));
+    }
+    let viewport = geometry::layout_viewport(page).await?;
+    let (cx, cy) = (viewport.width / 2.0, viewport.height / 2.0);
+    let start = PINCH_RADIUS;
+    let end = PINCH_RADIUS * scale;
+
+    enable_touch(page).await?;
+    touch(
+        page,
+        DispatchTouchEventType::TouchStart,
+        &[(cx - start, cy), (cx + start, cy)],
+    )
+    .await?;
+    let steps = steps(PINCH_DURATION);
+    let left = path((cx - start, cy), (cx - end, cy), steps);
+    let right = path((cx + start, cy), (cx + end, cy), steps);
+    for (l, r) in left.into_iter().zip(right) {
+        tokio::time::sleep(FRAME).await;
+        touch(page, DispatchTouchEventType::TouchMove, &[l, r]).await?;
+    }
+    touch(page, DispatchTouchEventType::TouchEnd, &[]).await?;
+    tracing::debug!(scale, "Pinched");
+    Ok(())
+}
+
+/// Dispatch one touch event with a point per finger
+async fn touch(page: &Page, kind: DispatchTouchEventType, points: &[(f64, f64)]) -> Result<()> {
+    let points = points
+        .iter()
+        .enumerate()
+        .map(|(id, &(x, y))| TouchPoint {
+            id: Some(id as f64),
+            ..TouchPoint::new(x, y)
+        })
+        .collect();
+    page.execute(DispatchTouchEventParams::new(kind, points))
+        .await?;
+    Ok(())
+}
+
+/// Number of moves a gesture lasting `duration` takes
+fn steps(duration: Duration) -> usize {
+    (duration.as_millis() / FRAME.as_millis()).max(1) as usize
+}
+
+/// `steps` points evenly spaced from `from` (exclusive) to `to` (inclusive)
+fn path(from: (f64, f64), to: (f64, f64), steps: usize) -> Vec<(f64, f64)> {
+    (1..=steps)
+        .map(|i| {
+            let t = i as f64 / steps as f64;
+            (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path() {
+        assert_eq!(
+            path((0.0, 100.0), (300.0, 100.0), 3),
+            vec![(100.0, 100.0), (200.0, 100.0), (300.0, 100.0)]
+        );
+        assert_eq!(steps(Duration::from_millis(320)), 20);
+        assert_eq!(steps(Duration::ZERO), 1);
+    }
+}
@@ -0,0 +1,102 @@
+//! SSH Tunnels to Remote Chrome
+//!
+//! Chrome's debug port only listens on localhost by default. [`SshTunnel`] forwards
+//! a free local port to it over `ssh -L`, so a Chrome on another machine can be
+//! driven as if it were local. [`ChromeDriver::connect_ssh`] opens the tunnel and
+//! keeps it open for as long as the driver lives.
+//!
+//! The tunnel runs the system `ssh` client in batch mode, so the destination must
+//! be reachable without a password prompt (keys or an agent); host aliases and
+//! options from `~/.ssh/config` apply.
+//!
+//! [`ChromeDriver::connect_ssh`]: super::ChromeDriver::connect_ssh
+
+use super::chrome::ConnectionMode;
+use crate::error::{BrowserError, Result};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long `ssh` gets to set up the forward
+const TUNNEL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// An `ssh -L` forward from a local port to a remote Chrome debug port, closed when dropped
+#[derive(Debug)]
+pub struct SshTunnel {
+    process: Child,
+    local_port: u16,
+}
+
+impl SshTunnel {
+    /// Forward a free local port to `remote_port` on `destination` (`user@host` or a
+    /// host alias), returning once the forward accepts connections
+    pub async fn open(destination: &str, remote_port: u16) -> Result<Self> {
+        let local_port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .and_then(|listener| listener.local_addr())
+            .map_err(|e| BrowserError::ConnectionFailed(format!("No free local port: {}", e)))?
+            .port();
+
+        let process = Command::new("ssh")
+            .args([
+                "-N",
+                "-L",
+                &format!("{}:localhost:{}", local_port, remote_port),
+                "-o",
+                "ExitOnForwardFailure=yes",
+                "-o",
+                "BatchMode=yes",
+                destination,
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| BrowserError::ConnectionFailed(format!("Failed to start ssh: {}", e)))?;
+        let mut tunnel = Self {
+            process,
+            local_port,
+        };
+
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, local_port));
+        let deadline = Instant::now() + TUNNEL_TIMEOUT;
+        while tokio::net::TcpStream::connect(addr).await.is_err() {
+            if let Ok(Some(status)) = tunnel.process.try_wait() {
+                return Err(BrowserError::ConnectionFailed(format!(
+                    "ssh to {} exited ({}) before forwarding port {}",
+                    destination, status, remote_port
+                )));
+            }
+            if Instant::now() >= deadline {
+                return Err(BrowserError::ConnectionFailed(format!(
+                    "ssh to {} didn't forward port {} within {}s",
+                    destination,
+                    remote_port,
+                    TUNNEL_TIMEOUT.as_secs()
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        tracing::info!(destination, remote_port, local_port, "Opened SSH tunnel");
+        Ok(tunnel)
+    }
+
+    /// Local end of the forward
+    pub fn local_port(&self) -> u16 {
+        self.local_port
+    }
+
+    /// Connection mode reaching the remote Chrome through this tunnel
+    ///
+    /// The tunnel must stay open while the driver is connected.
+    pub fn connection_mode(&self) -> ConnectionMode {
+        ConnectionMode::DebugPort(self.local_port)
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
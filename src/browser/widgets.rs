@@ -0,0 +1,498 @@
+//! Widget Interaction Strategies
+//!
+//! Typing into a plain `<input>` is enough for most fields, but date pickers,
+//! comboboxes, and rich text editors ignore (or undo) text that arrives that way:
+//! a flatpickr input keeps its old date, a React-Select combobox never commits the
+//! option, and a ProseMirror editor never sees the value at all. A
+//! [`WidgetStrategy`] knows the event choreography one kind of widget needs, and a
+//! [`WidgetRegistry`] picks the strategy for an element before typing into it.
+//!
+//! Built-in strategies, checked in this order:
+//!
+//! | Name | Widget | How text is entered |
+//! |------|--------|---------------------|
+//! | `flatpickr` | flatpickr date inputs (including `altInput`) | `setDate(text, true)` |
+//! | `jquery-ui-datepicker` | jQuery UI datepicker inputs | `datepicker('setDate', text)` + `change` |
+//! | `select2` | `<select>` enhanced by Select2 | selects the option by value or text + `change` |
+//! | `tinymce` | `<textarea>` replaced by TinyMCE | `setContent(text)` + `save()` |
+//! | `native-date` | date/time/month/week/color/range inputs | native value setter + `input`/`change` |
+//! | `combobox` | ARIA comboboxes (React-Select, Downshift, MUI Autocomplete) | types, then clicks the matching `role="option"` |
+//! | `contenteditable` | rich text editors (ProseMirror, Quill, CKEditor 5, Draft.js, Lexical) | selects the content, then `Input.insertText` |
+//!
+//! Elements no strategy claims get plain typing: click, select the existing text,
+//! `Input.insertText`, then a `change` event.
+//!
+//! Custom widgets plug in by implementing [`WidgetStrategy`], or, when a page
+//! script can do the job, with a [`ScriptStrategy`]:
+//!
+//! ```no_run
+//! use robert_webdriver::browser::widgets::ScriptStrategy;
+//! use std::sync::Arc;
+//! # async fn example(driver: &robert_webdriver::ChromeDriver) -> robert_webdriver::error::Result<()> {
+//! driver.widgets().register(Arc::new(ScriptStrategy::new(
+//!     "acme-datepicker",
+//!     "function() { return this.classList.contains('acme-date'); }",
+//!     "function(text) { this.acmePicker.set(text); return null; }",
+//! )));
+//! driver.type_text("#start-date", "2024-05-01").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::ime;
+use crate::error::{BrowserError, Result};
+use chromiumoxide::element::Element;
+use chromiumoxide::page::Page;
+use futures::future::BoxFuture;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// How long a combobox may take to show options for the typed text
+const COMBOBOX_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Interval between option checks while waiting
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Attribute marking the combobox option to click
+const OPTION_MARKER: &str = "data-robert-option";
+
+/// Knows how to enter text into one kind of widget
+pub trait WidgetStrategy: Send + Sync {
+    /// Name used in logs and [`WidgetRegistry::names`]
+    fn name(&self) -> &str;
+
+    /// JavaScript function (`function() { ... }`, with `this` bound to the target
+    /// element) returning whether this strategy handles the element
+    fn detect_script(&self) -> &str;
+
+    /// Replace the widget's value with `text`
+    fn type_text<'a>(
+        &'a self,
+        page: &'a Page,
+        element: &'a Element,
+        text: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Strategy whose work is done by a page script
+///
+/// The apply script is a function (`function(text) { ... }`, with `this` bound to
+/// the element) that returns `null` on success or an error message; it may be async.
+#[derive(Debug, Clone)]
+pub struct ScriptStrategy {
+    name: String,
+    detect: String,
+    apply: String,
+}
+
+impl ScriptStrategy {
+    pub fn new(
+        name: impl Into<String>,
+        detect: impl Into<String>,
+        apply: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            detect: detect.into(),
+            apply: apply.into(),
+        }
+    }
+}
+
+impl WidgetStrategy for ScriptStrategy {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn detect_script(&self) -> &str {
+        &self.detect
+    }
+
+    fn type_text<'a>(
+        &'a self,
+        _page: &'a Page,
+        element: &'a Element,
+        text: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let call = format!(
+                "async function() {{ return await ({}).call(this, {}); }}",
+                self.apply,
+                Value::from(text)
+            );
+            let returned = element.call_js_fn(call, true).await?;
+            if let Some(details) = returned.exception_details {
+                anyhow::bail!("{}", details.text);
+            }
+            match returned.result.value {
+                Some(Value::String(error)) => anyhow::bail!(error),
+                _ => Ok(()),
+            }
+        })
+    }
+}
+
+/// ARIA comboboxes: type to filter, then click the option whose text matches
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComboboxStrategy;
+
+impl WidgetStrategy for ComboboxStrategy {
+    fn name(&self) -> &str {
+        "combobox"
+    }
+
+    fn detect_script(&self) -> &str {
+        "function() { return this.tagName !== 'SELECT' && \
+         (this.getAttribute('role') === 'combobox' || this.getAttribute('aria-autocomplete') === 'list'); }"
+    }
+
+    fn type_text<'a>(
+        &'a self,
+        page: &'a Page,
+        element: &'a Element,
+        text: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            type_plain(page, element, text).await?;
+            if text.is_empty() {
+                return Ok(());
+            }
+
+            let find_option = format!(
+                r#"function() {{
+    const norm = (s) => (s || '').replace(/\s+/g, ' ').trim().toLowerCase();
+    const wanted = norm({text});
+    const ids = (this.getAttribute('aria-controls') || this.getAttribute('aria-owns') || '')
+        .split(/\s+/).filter(Boolean);
+    const lists = ids.map((id) => document.getElementById(id)).filter(Boolean);
+    const options = (lists.length ? lists : [document])
+        .flatMap((list) => Array.from(list.querySelectorAll('[role="option"]')))
+        .filter((option) => option.getClientRects().length > 0);
+    if (!options.length) return 'none';
+    const option = options.find((o) => norm(o.textContent) === wanted)
+        || options.find((o) => norm(o.textContent).startsWith(wanted));
+    if (!option) return 'nomatch';
+    option.setAttribute('{marker}', '');
+    return 'found';
+}}"#,
+                text = Value::from(text),
+                marker = OPTION_MARKER,
+            );
+
+            // Options may render (or filter) asynchronously after the input events
+            let deadline = Instant::now() + COMBOBOX_TIMEOUT;
+            let state = loop {
+                let state = element
+                    .call_js_fn(find_option.as_str(), false)
+                    .await?
+                    .result
+                    .value;
+                let state = state.as_ref().and_then(Value::as_str).unwrap_or("none");
+                if state == "found" || Instant::now() >= deadline {
+                    break state.to_string();
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            };
+
+            match state.as_str() {
+                "found" => {
+                    let selector = format!("[{}]", OPTION_MARKER);
+                    let option = page.find_element(selector.as_str()).await?;
+                    option.scroll_into_view().await?;
+                    let clicked = option.click().await.map(|_| ());
+                    let _ = page
+                        .evaluate(format!(
+                            "document.querySelectorAll('[{0}]').forEach((o) => o.removeAttribute('{0}'))",
+                            OPTION_MARKER
+                        ))
+                        .await;
+                    clicked?;
+                    Ok(())
+                }
+                "nomatch" => anyhow::bail!("no option matching '{}'", text),
+                // Free-text comboboxes (e.g. search suggestions) may show no options
+                _ => Ok(()),
+            }
+        })
+    }
+}
+
+/// Rich text editors built on `contenteditable`: select everything, then insert
+///
+/// `Input.insertText` produces the `beforeinput`/`input` events editors listen
+/// for, so their document model stays in sync with the DOM. Targeting an editor's
+/// wrapper works too; the first editable descendant gets the text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContentEditableStrategy;
+
+impl WidgetStrategy for ContentEditableStrategy {
+    fn name(&self) -> &str {
+        "contenteditable"
+    }
+
+    fn detect_script(&self) -> &str {
+        "function() { return this.isContentEditable || \
+         !!this.querySelector('[contenteditable=\"true\"], [contenteditable=\"\"]'); }"
+    }
+
+    fn type_text<'a>(
+        &'a self,
+        page: &'a Page,
+        element: &'a Element,
+        text: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            element.scroll_into_view().await?;
+            element
+                .call_js_fn(
+                    "function() { \
+                     const editor = this.isContentEditable ? this \
+                         : this.querySelector('[contenteditable=\"true\"], [contenteditable=\"\"]'); \
+                     editor.focus(); \
+                     const range = document.createRange(); \
+                     range.selectNodeContents(editor); \
+                     const selection = window.getSelection(); \
+                     selection.removeAllRanges(); \
+                     selection.addRange(range); }",
+                    false,
+                )
+                .await?;
+            if text.is_empty() {
+                page.evaluate("document.execCommand('delete')").await?;
+            } else {
+                ime::insert_text(page, text).await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Ordered set of strategies consulted before typing into an element
+///
+/// Strategies registered later are checked first, so custom strategies can
+/// override the built-in ones.
+pub struct WidgetRegistry {
+    strategies: RwLock<Vec<Arc<dyn WidgetStrategy>>>,
+}
+
+impl WidgetRegistry {
+    /// Registry without any strategies (everything gets plain typing)
+    pub fn empty() -> Self {
+        Self {
+            strategies: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Add `strategy`, checked before those already registered
+    pub fn register(&self, strategy: Arc<dyn WidgetStrategy>) {
+        tracing::debug!(strategy = strategy.name(), "Registered widget strategy");
+        self.strategies.write().unwrap().insert(0, strategy);
+    }
+
+    /// Names of the strategies, in the order they are checked
+    pub fn names(&self) -> Vec<String> {
+        self.strategies
+            .read()
+            .unwrap()
+            .iter()
+            .map(|s| s.name().to_string())
+            .collect()
+    }
+
+    /// Strategy handling `element`, if any
+    pub async fn detect(&self, element: &Element) -> Result<Option<Arc<dyn WidgetStrategy>>> {
+        let strategies = self.strategies.read().unwrap().clone();
+        if strategies.is_empty() {
+            return Ok(None);
+        }
+        let checks: Vec<&str> = strategies.iter().map(|s| s.detect_script()).collect();
+        let index = element
+            .call_js_fn(detect_script(&checks), false)
+            .await
+            .map_err(|e| BrowserError::Other(format!("Failed to detect widget: {}", e)))?
+            .result
+            .value
+            .and_then(|v| v.as_i64())
+            .unwrap_or(-1);
+        Ok(usize::try_from(index)
+            .ok()
+            .and_then(|index| strategies.get(index).cloned()))
+    }
+
+    /// Replace the value of `element` with `text` using the strategy that handles it
+    ///
+    /// Returns the name of the strategy used (None for plain typing).
+    pub async fn type_text(
+        &self,
+        page: &Page,
+        element: &Element,
+        text: &str,
+    ) -> Result<Option<String>> {
+        let Some(strategy) = self.detect(element).await? else {
+            type_plain(page, element, text).await?;
+            return Ok(None);
+        };
+        tracing::debug!(strategy = strategy.name(), "Typing into widget");
+        strategy
+            .type_text(page, element, text)
+            .await
+            .map_err(|e| BrowserError::Other(format!("{} widget: {}", strategy.name(), e)))?;
+        Ok(Some(strategy.name().to_string()))
+    }
+}
+
+impl Default for WidgetRegistry {
+    /// Registry with the built-in strategies
+    fn default() -> Self {
+        let registry = Self::empty();
+        // Registered in reverse: the last one registered is checked first
+        registry.register(Arc::new(ContentEditableStrategy));
+        registry.register(Arc::new(ComboboxStrategy));
+        for (name, detect, apply) in SCRIPT_STRATEGIES.iter().rev() {
+            registry.register(Arc::new(ScriptStrategy::new(*name, *detect, *apply)));
+        }
+        registry
+    }
+}
+
+impl std::fmt::Debug for WidgetRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WidgetRegistry")
+            .field("strategies", &self.names())
+            .finish()
+    }
+}
+
+/// Built-in script strategies as (name, detect, apply), in priority order
+const SCRIPT_STRATEGIES: &[(&str, &str, &str)] = &[
+    (
+        "flatpickr",
+        // With altInput, the visible input follows the original one
+        "function() { return !!(this._flatpickr || (this.previousElementSibling || {})._flatpickr); }",
+        "function(text) { \
+         const picker = this._flatpickr || this.previousElementSibling._flatpickr; \
+         picker.setDate(text, true); \
+         return picker.selectedDates.length || !text ? null : `'${text}' is not a date the picker accepts`; }",
+    ),
+    (
+        "jquery-ui-datepicker",
+        "function() { return !!window.jQuery && this.classList.contains('hasDatepicker'); }",
+        "function(text) { \
+         const input = window.jQuery(this); \
+         input.datepicker('setDate', text); \
+         input.trigger('change'); \
+         return null; }",
+    ),
+    (
+        "select2",
+        "function() { return !!window.jQuery && this.tagName === 'SELECT' \
+         && this.classList.contains('select2-hidden-accessible'); }",
+        "function(text) { \
+         const norm = (s) => (s || '').replace(/\\s+/g, ' ').trim().toLowerCase(); \
+         const option = Array.from(this.options).find((o) => o.value === text || norm(o.textContent) === norm(text)); \
+         if (!option) return `no option '${text}'`; \
+         window.jQuery(this).val(option.value).trigger('change'); \
+         return null; }",
+    ),
+    (
+        "tinymce",
+        "function() { return !!window.tinymce && !!this.id && !!window.tinymce.get(this.id); }",
+        "function(text) { \
+         const editor = window.tinymce.get(this.id); \
+         editor.setContent(text); \
+         editor.save(); \
+         editor.fire('change'); \
+         this.dispatchEvent(new Event('change', { bubbles: true })); \
+         return null; }",
+    ),
+    (
+        "native-date",
+        "function() { return this.tagName === 'INPUT' && \
+         ['date', 'time', 'datetime-local', 'month', 'week', 'color', 'range'].includes(this.type); }",
+        "function(text) { \
+         Object.getOwnPropertyDescriptor(HTMLInputElement.prototype, 'value').set.call(this, text); \
+         this.dispatchEvent(new Event('input', { bubbles: true })); \
+         this.dispatchEvent(new Event('change', { bubbles: true })); \
+         return this.value === text ? null : `value '${text}' was rejected`; }",
+    ),
+];
+
+/// Function returning the index of the first check that accepts `this`, or -1
+fn detect_script(checks: &[&str]) -> String {
+    format!(
+        "function() {{ const checks = [{}]; return checks.findIndex((check) => {{ \
+         try {{ return !!check.call(this); }} catch (e) {{ return false; }} }}); }}",
+        checks.join(", ")
+    )
+}
+
+/// Click the element, select its text, and type `text` over it
+pub async fn type_plain(page: &Page, element: &Element, text: &str) -> Result<()> {
+    element.click().await?;
+    // Select any existing text so the typed value replaces it
+    element
+        .call_js_fn(
+            "function() { this.focus(); if (this.select) this.select(); }",
+            false,
+        )
+        .await?;
+    if text.is_empty() {
+        element
+            .call_js_fn(
+                "function() { \
+                     Object.getOwnPropertyDescriptor(Object.getPrototypeOf(this), 'value').set.call(this, ''); \
+                     this.dispatchEvent(new Event('input', { bubbles: true })); }",
+                false,
+            )
+            .await?;
+    } else {
+        ime::insert_text(page, text).await?;
+    }
+    // Typing fires input events; change normally follows when the field loses focus
+    element
+        .call_js_fn(
+            "function() { this.dispatchEvent(new Event('change', { bubbles: true })); }",
+            false,
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_order() {
+        let registry = WidgetRegistry::default();
+        assert_eq!(
+            registry.names(),
+            vec![
+                "flatpickr",
+                "jquery-ui-datepicker",
+                "select2",
+                "tinymce",
+                "native-date",
+                "combobox",
+                "contenteditable"
+            ]
+        );
+
+        registry.register(Arc::new(ScriptStrategy::new(
+            "custom",
+            "function() { return true; }",
+            "function(text) { return null; }",
+        )));
+        assert_eq!(registry.names()[0], "custom");
+        assert!(WidgetRegistry::empty().names().is_empty());
+    }
+
+    #[test]
+    fn test_detect_script() {
+        let script = detect_script(&["function() { return 1; }", "function() { return 2; }"]);
+        assert!(
+            script.contains("const checks = [function() { return 1; }, function() { return 2; }];")
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Generated Script Cache
+//!
+//! Generating a script calls the LLM, which takes seconds and costs tokens each
+//! time. [`ScriptCache`] keeps the scripts generated for a prompt on a page so an
+//! identical request can reuse one until its TTL passes. Entries are keyed by the
+//! normalized prompt (case and whitespace folded) and a fingerprint of the page
+//! (its URL without query string or fragment).
+//!
+//! Cached scripts go stale when the site changes, so callers invalidate an entry
+//! when its script fails ([`ScriptCache::invalidate`]), or every entry for a page
+//! ([`ScriptCache::invalidate_page`]). A cache opened on a directory also writes
+//! each entry to `<dir>/<key>.json` and loads them back on startup.
+
+use super::CdpScript;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// A script in the cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedScript {
+    /// Cache key ([`cache_key`])
+    pub key: String,
+
+    /// Prompt the script was generated from, as given
+    pub prompt: String,
+
+    /// Fingerprint of the page it was generated for (None = no page context)
+    pub page: Option<String>,
+
+    pub script: CdpScript,
+
+    /// ISO 8601 timestamp when the script was generated
+    pub created: String,
+}
+
+/// Generated scripts by prompt and page, expiring after a TTL
+pub struct ScriptCache {
+    ttl: Duration,
+    dir: Option<PathBuf>,
+    entries: RwLock<HashMap<String, CachedScript>>,
+}
+
+impl ScriptCache {
+    /// Cache that only keeps scripts in memory
+    pub fn in_memory(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            dir: None,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Cache persisted in `dir`, loading the unexpired scripts already stored there
+    ///
+    /// Files that cannot be parsed are skipped with a warning; expired ones are deleted.
+    pub fn open(dir: &Path, ttl: Duration) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let cache = Self {
+            ttl,
+            dir: Some(dir.to_path_buf()),
+            entries: RwLock::new(HashMap::new()),
+        };
+
+        let mut entries = HashMap::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| Ok(serde_json::from_str::<CachedScript>(&json)?));
+            match parsed {
+                Ok(cached) if cache.is_expired(&cached) => {
+                    let _ = std::fs::remove_file(&path);
+                }
+                Ok(cached) => {
+                    entries.insert(cached.key.clone(), cached);
+                }
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "Skipping unreadable cached script")
+                }
+            }
+        }
+        tracing::debug!(dir = %dir.display(), scripts = entries.len(), "Loaded script cache");
+
+        *cache.entries.write().unwrap() = entries;
+        Ok(cache)
+    }
+
+    /// The unexpired script cached under `key`
+    pub fn get(&self, key: &str) -> Option<CdpScript> {
+        let cached = self.entries.read().unwrap().get(key).cloned()?;
+        if self.is_expired(&cached) {
+            self.invalidate(key);
+            return None;
+        }
+        Some(cached.script)
+    }
+
+    /// Cache `script`, generated from `prompt` on the page at `page_url`
+    ///
+    /// Returns the key it was stored under. Persisting is best effort: a failed
+    /// write is logged and the script is still cached in memory.
+    pub fn insert(&self, prompt: &str, page_url: Option<&str>, script: CdpScript) -> String {
+        let key = cache_key(prompt, page_url);
+        let cached = CachedScript {
+            key: key.clone(),
+            prompt: prompt.to_string(),
+            page: page_url.map(page_fingerprint),
+            script,
+            created: Utc::now().to_rfc3339(),
+        };
+
+        if let Some(path) = self.path(&key) {
+            let written = serde_json::to_string_pretty(&cached)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| Ok(std::fs::write(&path, json)?));
+            if let Err(e) = written {
+                tracing::warn!(path = %path.display(), error = %e, "Failed to persist cached script");
+            }
+        }
+
+        self.entries.write().unwrap().insert(key.clone(), cached);
+        key
+    }
+
+    /// Drop the script cached under `key`, returning whether there was one
+    pub fn invalidate(&self, key: &str) -> bool {
+        if let Some(path) = self.path(key) {
+            let _ = std::fs::remove_file(path);
+        }
+        self.entries.write().unwrap().remove(key).is_some()
+    }
+
+    /// Drop every script generated for the page at `url`, returning how many there were
+    pub fn invalidate_page(&self, url: &str) -> usize {
+        let page = page_fingerprint(url);
+        self.invalidate_where(|cached| cached.page.as_deref() == Some(page.as_str()))
+    }
+
+    /// Drop every cached script, returning how many there were
+    pub fn clear(&self) -> usize {
+        self.invalidate_where(|_| true)
+    }
+
+    /// Number of scripts cached, including expired ones not yet dropped
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    /// Whether no scripts are cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn invalidate_where<F>(&self, predicate: F) -> usize
+    where
+        F: Fn(&CachedScript) -> bool,
+    {
+        let keys: Vec<String> = self
+            .entries
+            .read()
+            .unwrap()
+            .values()
+            .filter(|cached| predicate(cached))
+            .map(|cached| cached.key.clone())
+            .collect();
+        keys.iter().filter(|key| self.invalidate(key)).count()
+    }
+
+    /// Whether `cached` is older than the TTL (or has an unreadable timestamp)
+    fn is_expired(&self, cached: &CachedScript) -> bool {
+        let Ok(created) = DateTime::parse_from_rfc3339(&cached.created) else {
+            return true;
+        };
+        let age = Utc::now().signed_duration_since(created);
+        age.to_std().is_ok_and(|age| age > self.ttl)
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", key)))
+    }
+}
+
+/// Cache key for `prompt` on the page at `page_url`: a hex SHA-256 of the
+/// normalized prompt and the page fingerprint
+pub fn cache_key(prompt: &str, page_url: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(normalize_prompt(prompt).as_bytes());
+    hasher.update(b"\n");
+    hasher.update(
+        page_url
+            .map(page_fingerprint)
+            .unwrap_or_default()
+            .as_bytes(),
+    );
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// `prompt` lowercased with runs of whitespace collapsed to one space
+fn normalize_prompt(prompt: &str) -> String {
+    prompt
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// `url` without query string, fragment, or trailing slash (`https://example.com/login`)
+fn page_fingerprint(url: &str) -> String {
+    match reqwest::Url::parse(url.trim()) {
+        Ok(mut parsed) => {
+            parsed.set_query(None);
+            parsed.set_fragment(None);
+            parsed.as_str().trim_end_matches('/').to_string()
+        }
+        Err(_) => url.trim().trim_end_matches('/').to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(name: &str) -> CdpScript {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "description": "test",
+            "cdp_commands": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cache_key() {
+        assert_eq!(
+            normalize_prompt("  Take a\n screenshot  "),
+            "take a screenshot"
+        );
+        assert_eq!(
+            page_fingerprint("https://Example.com/login/?next=%2F#form"),
+            "https://example.com/login"
+        );
+
+        let key = cache_key("Take a screenshot", Some("https://example.com/"));
+        assert_eq!(key.len(), 64);
+        assert_eq!(
+            key,
+            cache_key("take a  screenshot", Some("https://example.com?utm=x"))
+        );
+        assert_ne!(key, cache_key("Take a screenshot", None));
+        assert_ne!(
+            key,
+            cache_key("Take a screenshot", Some("https://example.com/about"))
+        );
+    }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let cache = ScriptCache::in_memory(Duration::from_secs(60));
+        let key = cache.insert("log in", Some("https://example.com/login"), script("login"));
+        cache.insert(
+            "log out",
+            Some("https://example.com/login"),
+            script("logout"),
+        );
+        cache.insert("log in", None, script("login-anywhere"));
+        assert_eq!(cache.get(&key).unwrap().name, "login");
+
+        assert!(cache.invalidate(&key));
+        assert!(cache.get(&key).is_none());
+        assert_eq!(
+            cache.invalidate_page("https://example.com/login?retry=1"),
+            1
+        );
+        assert_eq!(cache.clear(), 1);
+        assert!(cache.is_empty());
+
+        let expired = ScriptCache::in_memory(Duration::ZERO);
+        let key = expired.insert("log in", None, script("login"));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(expired.get(&key).is_none());
+        assert!(expired.is_empty());
+    }
+
+    #[test]
+    fn test_cache_persists() {
+        let dir = std::env::temp_dir().join(format!("robert-script-cache-{}", std::process::id()));
+        let cache = ScriptCache::open(&dir, Duration::from_secs(60)).unwrap();
+        let key = cache.insert("log in", Some("https://example.com"), script("login"));
+        assert!(dir.join(format!("{}.json", key)).exists());
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+
+        let reopened = ScriptCache::open(&dir, Duration::from_secs(60)).unwrap();
+        assert_eq!(reopened.get(&key).unwrap().name, "login");
+        assert!(reopened.invalidate(&key));
+        assert!(!dir.join(format!("{}.json", key)).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+//! Execution Checkpoints
+//!
+//! A snapshot of a script run taken after each command, so a run that crashed or
+//! was deliberately stopped can pick up where it left off with
+//! [`CdpExecutor::execute_script_resume`](super::CdpExecutor::execute_script_resume)
+//! instead of starting over.
+
+use super::script::{CdpScript, CommandResult, CommandStatus};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Executor state after the last completed command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionCheckpoint {
+    /// The script being run
+    pub script: CdpScript,
+
+    /// Index of the next command to run (0-based); equal to the number of
+    /// commands once the script has finished
+    pub next_step: usize,
+
+    /// URL of the current page after the last completed command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Outputs saved so far: each `save_as` name and the file it was written to
+    #[serde(default)]
+    pub bindings: BTreeMap<String, String>,
+
+    /// Results of the completed commands
+    pub results: Vec<CommandResult>,
+
+    /// When the checkpoint was written (RFC 3339)
+    pub updated: String,
+}
+
+impl ExecutionCheckpoint {
+    /// A checkpoint before the first command of `script`
+    pub fn new(script: CdpScript) -> Self {
+        Self {
+            script,
+            next_step: 0,
+            url: None,
+            bindings: BTreeMap::new(),
+            results: Vec::new(),
+            updated: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Record a completed command and move past it
+    ///
+    /// Only successful commands advance the checkpoint: resuming after a failure
+    /// runs the failed command again.
+    pub fn record(&mut self, result: &CommandResult, url: Option<String>) {
+        if result.status != CommandStatus::Success {
+            return;
+        }
+        if let (Some(name), Some(file)) = (
+            self.script
+                .cdp_commands
+                .get(self.next_step)
+                .and_then(|cmd| cmd.save_as.clone()),
+            result.saved_file.clone(),
+        ) {
+            self.bindings.insert(name, file);
+        }
+        self.next_step += 1;
+        self.url = url.or(self.url.take());
+        self.results.push(result.clone());
+        self.updated = chrono::Utc::now().to_rfc3339();
+    }
+
+    /// Whether every command of the script has completed
+    pub fn is_complete(&self) -> bool {
+        self.next_step >= self.script.cdp_commands.len()
+    }
+
+    /// Load a checkpoint from a JSON file
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read checkpoint {}", path.display()))?;
+        let checkpoint: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid checkpoint {}", path.display()))?;
+        if checkpoint.next_step > checkpoint.script.cdp_commands.len() {
+            anyhow::bail!(
+                "Checkpoint {} is past the end of the script ({} of {} commands)",
+                path.display(),
+                checkpoint.next_step,
+                checkpoint.script.cdp_commands.len()
+            );
+        }
+        Ok(checkpoint)
+    }
+
+    /// Write the checkpoint to a JSON file
+    ///
+    /// The file is replaced atomically, so a crash while writing leaves the previous
+    /// checkpoint intact.
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .context("Failed to create checkpoint directory")?;
+        }
+        let staging = path.with_extension("json.tmp");
+        tokio::fs::write(&staging, serde_json::to_string_pretty(self)?)
+            .await
+            .context("Failed to write checkpoint")?;
+        tokio::fs::rename(&staging, path)
+            .await
+            .context("Failed to write checkpoint")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn result(step: usize, status: CommandStatus, saved_file: Option<&str>) -> CommandResult {
+        CommandResult {
+            step,
+            method: "Page.captureScreenshot".to_string(),
+            status,
+            duration: Duration::from_millis(5),
+            response: None,
+            error: None,
+            saved_file: saved_file.map(str::to_string),
+            frames: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_roundtrip() {
+        let script: CdpScript = serde_json::from_value(json!({
+            "name": "checkout",
+            "description": "Checkout flow",
+            "cdp_commands": [
+                { "method": "Page.navigate", "params": { "url": "https://shop.test" } },
+                { "method": "Page.captureScreenshot", "params": {}, "save_as": "cart.png" },
+                { "method": "Page.reload", "params": {} },
+            ]
+        }))
+        .unwrap();
+
+        let mut checkpoint = ExecutionCheckpoint::new(script);
+        checkpoint.record(
+            &result(1, CommandStatus::Success, None),
+            Some("https://shop.test/".to_string()),
+        );
+        checkpoint.record(
+            &result(2, CommandStatus::Success, Some("out/cart.png")),
+            None,
+        );
+        // Failures don't advance the checkpoint
+        checkpoint.record(&result(3, CommandStatus::Failed, None), None);
+
+        assert_eq!(checkpoint.next_step, 2);
+        assert!(!checkpoint.is_complete());
+        assert_eq!(checkpoint.url.as_deref(), Some("https://shop.test/"));
+        assert_eq!(checkpoint.bindings["cart.png"], "out/cart.png");
+
+        let dir = std::env::temp_dir().join(format!("robert-checkpoint-{}", std::process::id()));
+        let path = dir.join("checkout.json");
+        checkpoint.save(&path).await.unwrap();
+        let loaded = ExecutionCheckpoint::load(&path).await.unwrap();
+        assert_eq!(loaded.next_step, 2);
+        assert_eq!(loaded.results.len(), 2);
+        assert_eq!(loaded.results[1].duration, Duration::from_millis(5));
+        assert_eq!(loaded.bindings, checkpoint.bindings);
+
+        checkpoint.next_step = 4;
+        checkpoint.save(&path).await.unwrap();
+        assert!(ExecutionCheckpoint::load(&path).await.is_err());
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
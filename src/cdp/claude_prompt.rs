@@ -1,7 +1,23 @@
 //! Claude prompt templates for CDP script generation
 
+use super::examples::ScriptExample;
+use crate::model::goal::PageEvidence;
+use std::path::Path;
+
+/// Most characters of page text included in a goal verification prompt
+const MAX_GOAL_PAGE_TEXT: usize = 4000;
+
 /// Generate a prompt for Claude to create a CDP automation script
 pub fn generate_cdp_script_prompt(user_request: &str) -> String {
+    generate_cdp_script_prompt_with_examples(user_request, &[])
+}
+
+/// [`generate_cdp_script_prompt`] with the user's own examples of similar tasks,
+/// which the prompt asks Claude to follow where they apply
+pub fn generate_cdp_script_prompt_with_examples(
+    user_request: &str,
+    examples: &[&ScriptExample],
+) -> String {
     format!(
         r#"You are a browser automation expert generating Chrome DevTools Protocol (CDP) scripts.
 
@@ -20,11 +36,11 @@ AVAILABLE CDP COMMANDS:
 3. Page.reload - Refresh page
    {{"method": "Page.reload", "params": {{"ignoreCache": true}}}}
 
-4. Page.goBack - Browser back
-   {{"method": "Page.goBack", "params": {{"entryId": -1}}}}
+4. Page.goBack - Browser back (to the previous history entry)
+   {{"method": "Page.goBack", "params": {{}}}}
 
-5. Page.goForward - Browser forward
-   {{"method": "Page.goForward", "params": {{"entryId": 1}}}}
+5. Page.goForward - Browser forward (to the next history entry)
+   {{"method": "Page.goForward", "params": {{}}}}
 
 6. Runtime.evaluate - Execute JavaScript (saves result if save_as provided)
    {{"method": "Runtime.evaluate", "params": {{"expression": "document.title", "returnByValue": true}}, "save_as": "result.json"}}
@@ -53,11 +69,71 @@ AVAILABLE CDP COMMANDS:
 14. Emulation.setDeviceMetricsOverride - Mobile emulation
     {{"method": "Emulation.setDeviceMetricsOverride", "params": {{"width": 375, "height": 667, "deviceScaleFactor": 2, "mobile": true}}}}
 
+15. Emulation.setEmulatedMedia - Dark mode, reduced motion, or print stylesheet
+    {{"method": "Emulation.setEmulatedMedia", "params": {{"media": "print", "features": [{{"name": "prefers-color-scheme", "value": "dark"}}]}}}}
+
+16. Target.waitForPopup - Wait for a window/tab opened by the previous step (e.g. a target="_blank" link) and run the following commands in it
+    {{"method": "Target.waitForPopup", "params": {{"timeout": 10000}}}}
+
+17. Target.switchToOpener - Go back to the page that opened the popup
+    {{"method": "Target.switchToOpener", "params": {{}}}}
+
+18. Audit.accessibility - Run an axe-core accessibility audit of the page (failOn: minor, moderate, serious, or critical fails the step)
+    {{"method": "Audit.accessibility", "params": {{"tags": ["wcag2a", "wcag2aa"], "failOn": "serious"}}, "save_as": "a11y.json"}}
+
+19. Audit.performance - Score how the page loaded (TTFB, LCP, CLS, blocking time, bytes by type); minScore (0-100) fails the step below it
+    {{"method": "Audit.performance", "params": {{"minScore": 80}}, "save_as": "performance.json"}}
+
+20. Extract.seo - Read title, meta description, canonical, robots, Open Graph/Twitter tags, and JSON-LD; validate checks lengths and missing tags
+    {{"method": "Extract.seo", "params": {{"validate": true, "rules": {{"required": ["canonical", "og:image"]}}}}, "save_as": "seo.json"}}
+
+21. Form.fill - Fill form fields by name, label, or placeholder; text for inputs and selects, true/false for checkboxes, the option for radios
+    {{"method": "Form.fill", "params": {{"fields": {{"Email": "user@example.com", "Country": "Canada", "Subscribe": true}}, "failOnUnmatched": true}}}}
+
+22. Form.typeText - Type into the element matching a selector; handles date pickers, comboboxes (picks the matching option), and rich text editors
+    {{"method": "Form.typeText", "params": {{"selector": "input[name=country]", "text": "Canada"}}}}
+
+23. Input.imeSetComposition - Show in-progress IME composition text; Input.insertText then commits it. Form.typeText does this for Chinese, Japanese, and Korean text automatically; never type such text with Input.dispatchKeyEvent
+    {{"method": "Input.imeSetComposition", "params": {{"text": "にほん", "selectionStart": 3, "selectionEnd": 3}}}}
+
+24. Touch.tap - Tap an element with a touch event (for mobile-emulated pages)
+    {{"method": "Touch.tap", "params": {{"selector": ".carousel .next"}}}}
+
+25. Touch.swipe - Drag one finger between two viewport points [x, y] over duration ms
+    {{"method": "Touch.swipe", "params": {{"from": [300, 400], "to": [50, 400], "duration": 300}}}}
+
+26. Touch.pinch - Pinch around the viewport center; scale > 1 zooms in, < 1 zooms out
+    {{"method": "Touch.pinch", "params": {{"scale": 2.0}}}}
+
+27. Assert.snapshot - Extract JSON with a JavaScript expression and compare it with the snapshot stored by the first run; ignorePaths (JSONPath) leaves out values that change between runs
+    {{"method": "Assert.snapshot", "params": {{"name": "prices", "expression": "Array.from(document.querySelectorAll('.price')).map(e => e.textContent)", "ignorePaths": ["$.updated"]}}}}
+
+28. Assert.text - Check that text is on the page (optionally inside selector); case, accents, and nbsp are ignored, "exact" matches an element's whole text, and "fuzzy" (0-1) accepts near matches
+    {{"method": "Assert.text", "params": {{"text": "Added to cart", "selector": ".notice", "fuzzy": 0.85}}}}
+
+29. Verify.goal - Check that the task succeeded, as the last step: states the success criterion in plain language (quote text that must appear) and fails the run if the final page doesn't meet it
+    {{"method": "Verify.goal", "params": {{"goal": "The page shows \"Order confirmed\" and an order number"}}}}
+
+30. Element.click - Scroll an element into view and click its center with the mouse
+    {{"method": "Element.click", "params": {{"selector": "button.add-to-cart"}}}}
+
+31. Element.type - Click an element and replace its contents with text
+    {{"method": "Element.type", "params": {{"selector": "input[name=q]", "text": "wireless headphones"}}}}
+
+32. Element.waitFor - Wait until an element is rendered (timeout in ms, default 10000; "visible": false accepts it once it is in the DOM)
+    {{"method": "Element.waitFor", "params": {{"selector": ".results", "timeout": 5000}}}}
+
+33. Element.scrollTo - Scroll an element into the middle of the viewport
+    {{"method": "Element.scrollTo", "params": {{"selector": ".reviews"}}}}
+
+Form.typeText, Touch.tap, Element.click, Element.type, and Element.scrollTo also take "fallbackSelectors" (tried in order when "selector" matches nothing) and "fallbackText" (the element's visible text, tried last). Keep any "fingerprint" already on a step when editing a script:
+    {{"method": "Touch.tap", "params": {{"selector": "button.sign-in", "fallbackSelectors": ["button[type=submit]"], "fallbackText": "Sign in"}}}}
+
 IMPORTANT RULES:
 
 1. ONLY use commands from the list above
 2. Always navigate to a page first before interacting with it
-3. For clicking elements, use Runtime.evaluate with JavaScript like: document.querySelector('button').click()
+3. For clicking and typing, use Element.click and Element.type; wait for elements that appear late with Element.waitFor
 4. For extracting data, use Runtime.evaluate with JavaScript
 5. For screenshots, always set "captureBeyondViewport": true for full page
 6. Use save_as field when you want to save screenshots or extracted data
@@ -167,10 +243,63 @@ Example 3 - Click and interact:
     }}
   ]
 }}
-
+{user_examples}
 Now generate the CDP script for the user's request. Output ONLY valid JSON, no markdown code blocks."#,
         user_request = user_request,
-        current_timestamp = chrono::Utc::now().to_rfc3339()
+        current_timestamp = chrono::Utc::now().to_rfc3339(),
+        user_examples = user_examples_section(examples),
+    )
+}
+
+/// Prompt section listing scripts that worked for the user's similar tasks (empty if none)
+fn user_examples_section(examples: &[&ScriptExample]) -> String {
+    if examples.is_empty() {
+        return String::new();
+    }
+    let mut section = String::from(
+        "\nSCRIPTS THAT WORKED FOR THIS USER'S SIMILAR TASKS (reuse their URLs, selectors, \
+         and steps where they fit the request):\n",
+    );
+    for (i, example) in examples.iter().enumerate() {
+        let script = serde_json::to_string_pretty(&example.script).unwrap_or_default();
+        section.push_str(&format!(
+            "\nUser example {} - {}:\n{}\n",
+            i + 1,
+            example.task,
+            script
+        ));
+    }
+    section
+}
+
+/// Prompt asking Claude whether the page in `screenshot` meets `goal`, answered
+/// as `{"passed": bool, "confidence": 0-1, "reason": "..."}`
+pub fn verify_goal_prompt(goal: &str, page: &PageEvidence, screenshot: &Path) -> String {
+    let text: String = page.text.chars().take(MAX_GOAL_PAGE_TEXT).collect();
+    format!(
+        r#"You are checking whether a browser automation run achieved its goal.
+
+GOAL: {goal}
+
+Read the screenshot of the page the run ended on: {screenshot}
+
+The page is {url} ("{title}"). Its visible text begins:
+---
+{text}
+---
+
+Judge from what a user would see on the screenshot, using the text only to read what's hard to
+make out. Error messages, login forms, or empty states where the goal expects content mean the
+goal was not met. If you can't tell, answer with a low confidence.
+
+OUTPUT FORMAT (JSON only, no markdown):
+{{"passed": true, "confidence": 0.9, "reason": "One sentence on what on the page decides it"}}
+"#,
+        goal = goal,
+        screenshot = screenshot.display(),
+        url = page.url,
+        title = page.title,
+        text = text,
     )
 }
 
@@ -194,6 +323,7 @@ pub fn validate_generated_script(json: &str) -> Result<crate::cdp::CdpScript, St
         "Page.goForward",
         "Runtime.evaluate",
         "Input.insertText",
+        "Input.imeSetComposition",
         "Input.dispatchMouseEvent",
         "Input.dispatchKeyEvent",
         "Network.getCookies",
@@ -201,6 +331,23 @@ pub fn validate_generated_script(json: &str) -> Result<crate::cdp::CdpScript, St
         "Network.deleteCookies",
         "Emulation.setGeolocationOverride",
         "Emulation.setDeviceMetricsOverride",
+        "Target.waitForPopup",
+        "Target.switchToOpener",
+        "Audit.accessibility",
+        "Audit.performance",
+        "Extract.seo",
+        "Assert.snapshot",
+        "Assert.text",
+        "Verify.goal",
+        "Form.fill",
+        "Form.typeText",
+        "Touch.tap",
+        "Touch.swipe",
+        "Touch.pinch",
+        "Element.click",
+        "Element.type",
+        "Element.waitFor",
+        "Element.scrollTo",
     ];
 
     for cmd in &script.cdp_commands {
@@ -225,6 +372,24 @@ mod tests {
         assert!(prompt.contains("USER REQUEST"));
         assert!(prompt.contains("Page.navigate"));
         assert!(prompt.contains("Page.captureScreenshot"));
+        assert!(!prompt.contains("SIMILAR TASKS"));
+    }
+
+    #[test]
+    fn test_prompt_with_examples() {
+        let script: crate::cdp::CdpScript = serde_json::from_str(
+            r#"{"name": "acme-invoices", "description": "Export invoices", "cdp_commands": [
+                {"method": "Page.navigate", "params": {"url": "https://billing.acme.io"}}
+            ]}"#,
+        )
+        .unwrap();
+        let example = ScriptExample::new("Export Acme invoices as CSV", script);
+        let prompt = generate_cdp_script_prompt_with_examples("Export invoices", &[&example]);
+
+        assert!(prompt.contains("User example 1 - Export Acme invoices as CSV:"));
+        assert!(prompt.contains("https://billing.acme.io"));
+        let examples = prompt.find("SIMILAR TASKS").unwrap();
+        assert!(examples < prompt.find("Now generate the CDP script").unwrap());
     }
 
     #[test]
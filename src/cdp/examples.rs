@@ -0,0 +1,269 @@
+//! Few-Shot Examples
+//!
+//! Users who keep automating the same sites (logging into their app, exporting a
+//! report) can show the generator scripts that worked there. An [`ExampleStore`]
+//! holds (task description → script) pairs, and the generator adds the ones most
+//! relevant to each request to its prompt, so it reuses their URLs, selectors, and
+//! structure instead of guessing.
+//!
+//! Relevance is keyword overlap weighted by rarity (BM25-style IDF): a word shared
+//! with few examples, like a product or site name, counts for more than one shared
+//! with many. An example's keywords come from its task, the script's name,
+//! description, and tags, and the hosts it navigates to.
+//!
+//! A store loads from a directory of JSON files, each either an example
+//! (`{"task": "...", "script": {...}}`) or a bare script, whose description is
+//! used as the task. That means the script library can double as an example store.
+
+use super::CdpScript;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Words too common to say anything about a task
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "at", "by", "for", "from", "in", "into", "is", "it", "me", "my", "of", "on",
+    "or", "the", "then", "this", "to", "with", "https", "http", "www",
+];
+
+/// A task and a script that accomplishes it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptExample {
+    /// What the user asked for, in natural language
+    pub task: String,
+
+    pub script: CdpScript,
+}
+
+impl ScriptExample {
+    /// Example showing `script` accomplishing `task`
+    pub fn new(task: impl Into<String>, script: CdpScript) -> Self {
+        Self {
+            task: task.into(),
+            script,
+        }
+    }
+
+    /// The example for a bare script, with its description as the task
+    pub fn from_script(script: CdpScript) -> Self {
+        Self::new(script.description.clone(), script)
+    }
+
+    /// Keywords the example is found by
+    fn keywords(&self) -> HashSet<String> {
+        let script = &self.script;
+        let hosts = script
+            .cdp_commands
+            .iter()
+            .filter(|cmd| cmd.method == "Page.navigate")
+            .filter_map(|cmd| cmd.params["url"].as_str())
+            .filter_map(|url| reqwest::Url::parse(url).ok())
+            .filter_map(|url| url.host_str().map(str::to_string))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = [
+            self.task.as_str(),
+            &script.name,
+            &script.description,
+            &script.tags.join(" "),
+            &hosts,
+        ]
+        .join(" ");
+        keywords(&text).collect()
+    }
+}
+
+/// Example file contents: an example, or a bare script
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExampleFile {
+    Example(ScriptExample),
+    Script(CdpScript),
+}
+
+/// Examples the generator picks from, by relevance to a task
+#[derive(Debug, Clone, Default)]
+pub struct ExampleStore {
+    examples: Vec<ScriptExample>,
+    /// Keywords of each example, by index
+    keywords: Vec<HashSet<String>>,
+}
+
+impl ExampleStore {
+    /// Store holding `examples`
+    pub fn new(examples: impl IntoIterator<Item = ScriptExample>) -> Self {
+        let mut store = Self::default();
+        for example in examples {
+            store.add(example);
+        }
+        store
+    }
+
+    /// Store of the examples (or bare scripts) in `dir`'s `.json` files
+    ///
+    /// Files that are neither are skipped with a warning.
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            anyhow::anyhow!("Failed to read examples directory {}: {}", dir.display(), e)
+        })?;
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut store = Self::default();
+        for path in paths {
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| Ok(serde_json::from_str::<ExampleFile>(&json)?));
+            match parsed {
+                Ok(ExampleFile::Example(example)) => store.add(example),
+                Ok(ExampleFile::Script(script)) => store.add(ScriptExample::from_script(script)),
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "Skipping unreadable example")
+                }
+            }
+        }
+        tracing::debug!(dir = %dir.display(), examples = store.len(), "Loaded generator examples");
+        Ok(store)
+    }
+
+    /// Add an example, e.g. a script that just worked for a task
+    pub fn add(&mut self, example: ScriptExample) {
+        self.keywords.push(example.keywords());
+        self.examples.push(example);
+    }
+
+    pub fn len(&self) -> usize {
+        self.examples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.examples.is_empty()
+    }
+
+    /// Up to `limit` examples sharing keywords with `task`, most relevant first
+    pub fn relevant(&self, task: &str, limit: usize) -> Vec<&ScriptExample> {
+        let query: HashSet<String> = keywords(task).collect();
+        let document_frequency = |word: &str| {
+            self.keywords
+                .iter()
+                .filter(|keywords| keywords.contains(word))
+                .count()
+        };
+        let total = self.examples.len() as f64;
+        let idf: HashMap<&str, f64> = query
+            .iter()
+            .map(|word| {
+                let df = document_frequency(word) as f64;
+                (word.as_str(), (1.0 + (total - df + 0.5) / (df + 0.5)).ln())
+            })
+            .collect();
+
+        let mut scored: Vec<(f64, usize)> = self
+            .keywords
+            .iter()
+            .enumerate()
+            .map(|(index, keywords)| {
+                let score = idf
+                    .iter()
+                    .filter(|(word, _)| keywords.contains(**word))
+                    .map(|(_, weight)| weight)
+                    .sum();
+                (score, index)
+            })
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, index)| &self.examples[index])
+            .collect()
+    }
+}
+
+/// Lowercase words of `text` (letters and digits), without stopwords or single characters
+fn keywords(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(name: &str, description: &str, url: &str) -> CdpScript {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "description": description,
+            "cdp_commands": [{"method": "Page.navigate", "params": {"url": url}}],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_relevant_examples() {
+        let store = ExampleStore::new([
+            ScriptExample::new(
+                "Export this month's invoices from Acme billing as CSV",
+                script(
+                    "acme-invoices",
+                    "Export invoices",
+                    "https://billing.acme.io/invoices",
+                ),
+            ),
+            ScriptExample::from_script(script(
+                "screenshot-home",
+                "Take a screenshot of the home page",
+                "https://example.com",
+            )),
+            ScriptExample::new(
+                "Log into the Acme dashboard",
+                script("acme-login", "Log in", "https://app.acme.io/login"),
+            ),
+        ]);
+
+        let found = store.relevant("download the invoices CSV from acme", 2);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].script.name, "acme-invoices");
+        assert_eq!(found[1].script.name, "acme-login");
+
+        let found = store.relevant("Screenshot example.com", 3);
+        assert_eq!(found[0].script.name, "screenshot-home");
+        assert!(store.relevant("the of and", 3).is_empty());
+        assert!(ExampleStore::default().relevant("anything", 3).is_empty());
+    }
+
+    #[test]
+    fn test_load_examples() {
+        let dir = std::env::temp_dir().join(format!("robert-examples-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let example = ScriptExample::new(
+            "Export invoices",
+            script("invoices", "Export", "https://billing.acme.io"),
+        );
+        std::fs::write(
+            dir.join("invoices.json"),
+            serde_json::to_string(&example).unwrap(),
+        )
+        .unwrap();
+        let bare = script("login", "Log into Acme", "https://app.acme.io/login");
+        std::fs::write(
+            dir.join("login.json"),
+            serde_json::to_string(&bare).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+
+        let store = ExampleStore::load(&dir).unwrap();
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.relevant("log in", 1)[0].task, "Log into Acme");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,10 +2,40 @@
 //!
 //! Runtime interpreter that executes CDP commands via spider_chrome's Page API.
 
+use super::checkpoint::ExecutionCheckpoint;
+use super::forensics::{bundle_key, ForensicsBundle, ForensicsOptions};
+use super::goal::GoalJudge;
+use super::hooks::{StepContext, StepHooks};
+use super::replay::{CdpTrace, TraceReplay};
 use super::script::{CdpCommand, CdpScript, CommandResult, CommandStatus, ExecutionReport};
+use crate::browser::accessibility::{self, AccessibilityOptions, Impact};
+use crate::browser::captcha::{self, CaptchaHandler};
+use crate::browser::consent::{self, CookieConsent};
+use crate::browser::form::{self, FormValue};
+use crate::browser::geometry;
+use crate::browser::navigation::{self, HistoryNavigation};
+use crate::browser::overlay::{self, OverlayHandling, OverlayOutcome};
+use crate::browser::performance;
+use crate::browser::selector::{self, SelectorCandidates};
+use crate::browser::seo::{self, SeoRules};
+use crate::browser::text::{self, TextQuery};
+use crate::browser::touch;
+use crate::browser::widgets::WidgetRegistry;
+use crate::browser::ChromeDriver;
+use crate::error::BrowserError;
+use crate::json_path::JsonPath;
+use crate::model::goal::{check_page, GoalVerdict, PageEvidence, VerifyMode};
+use crate::model::lint::describe;
+use crate::model::macros::MacroCommand;
+use crate::model::narration::narrate;
+use crate::model::snapshot;
+use crate::step_frame::{capture_step_frame, ActionInfo, CaptureOptions, StepFrame};
 use anyhow::{Context, Result};
 use serde_json::Value;
-use std::time::Instant;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 // Import spider_chrome types
 // Note: We use chromiumoxide module names because spider_chrome re-exports them
@@ -15,35 +45,436 @@ use chromiumoxide::cdp::browser_protocol::network;
 use chromiumoxide::cdp::browser_protocol::page;
 use chromiumoxide::cdp::js_protocol::runtime;
 use chromiumoxide::page::Page;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+/// Default time `Target.waitForPopup` waits for a popup to open
+const DEFAULT_POPUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long `Page.goBack` and `Page.goForward` wait for the history entry to commit
+const HISTORY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a failed command waits for the crash event that may explain it
+const CRASH_GRACE: Duration = Duration::from_millis(500);
+
+/// Confidence below which an `auto` mode `Verify.goal` asks the goal judge
+const DEFAULT_GOAL_CONFIDENCE: f64 = 0.7;
+
+/// Reads what `Verify.goal` checks a goal against
+const PAGE_EVIDENCE_JS: &str =
+    "({ url: location.href, title: document.title, text: document.body ? document.body.innerText : '' })";
+
+/// When the executor captures step frames
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameCapture {
+    /// Don't capture frames
+    #[default]
+    Never,
+    /// Capture a frame before and after every command
+    BeforeAndAfter,
+    /// Capture a frame after a command fails
+    OnFailure,
+}
+
+/// Execution options
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionOptions {
+    /// When to capture step frames (screenshot + DOM) around commands
+    ///
+    /// Frames are attached to the command's result, with the command as their
+    /// action. Capturing frames needs a driver ([`CdpExecutor::with_driver`]).
+    pub frame_capture: FrameCapture,
+
+    /// Where and how frames are captured
+    pub capture: CaptureOptions,
+
+    /// Collect a forensics bundle when a command fails (None = off)
+    ///
+    /// The bundle's location is appended to the command's error.
+    pub forensics: Option<ForensicsOptions>,
+
+    /// Directory `Assert.snapshot` snapshots are stored in (None = `snapshots` in
+    /// the output directory)
+    pub snapshot_dir: Option<PathBuf>,
+
+    /// Overwrite stored snapshots with the extracted data instead of comparing them
+    pub update_snapshots: bool,
+
+    /// Keep running the remaining commands after one fails (by default the run
+    /// stops at the first failure)
+    pub continue_on_error: bool,
+
+    /// Fail a command that takes longer than this (None = no limit beyond the
+    /// command's own timeouts)
+    pub command_timeout: Option<Duration>,
+
+    /// Give captured frames a generated transcript: what each step does, why, and
+    /// what it should lead to ([`narrate`]), rather than just the step's description
+    pub narrate: bool,
+}
 
 /// CDP Script Executor
 ///
 /// Executes CDP scripts by dispatching JSON commands to typed CDP command structs
 /// and executing them via spider_chrome's Page API.
-pub struct CdpExecutor {
-    page: Page,
+pub struct CdpExecutor<'a> {
+    /// Pages commands run against; the last one is current, earlier ones opened it
+    pages: Mutex<Vec<Page>>,
+    driver: Option<&'a ChromeDriver>,
+    cancel_token: CancellationToken,
+    output_dir: Option<PathBuf>,
+    captcha_handler: Option<Arc<dyn CaptchaHandler>>,
+    cookie_consent: Option<CookieConsent>,
+    overlays: Option<OverlayHandling>,
+    goal_judge: Option<Arc<dyn GoalJudge>>,
+    checkpoint_path: Option<PathBuf>,
+    step_hooks: Vec<Arc<dyn StepHooks>>,
+    options: ExecutionOptions,
+    /// Verdict of the `Verify.goal` step that just ran, for the report
+    verdict: Mutex<Option<GoalVerdict>>,
+    /// Response of the step that just failed after producing one (an audit over its
+    /// threshold), kept in its result
+    failed_response: Mutex<Option<Value>>,
+    /// Frames captured so far, which numbers the next one
+    frames_captured: AtomicUsize,
+    /// Recorded trace answering commands instead of a page
+    replay: Option<TraceReplay>,
 }
 
-impl CdpExecutor {
+impl<'a> CdpExecutor<'a> {
     /// Create a new executor with the given Page
     pub fn new(page: Page) -> Self {
-        Self { page }
+        Self {
+            pages: Mutex::new(vec![page]),
+            driver: None,
+            cancel_token: CancellationToken::new(),
+            output_dir: None,
+            captcha_handler: None,
+            cookie_consent: None,
+            overlays: None,
+            goal_judge: None,
+            checkpoint_path: None,
+            step_hooks: Vec::new(),
+            options: ExecutionOptions::default(),
+            verdict: Mutex::new(None),
+            failed_response: Mutex::new(None),
+            frames_captured: AtomicUsize::new(0),
+            replay: None,
+        }
+    }
+
+    /// Create an executor that replays `trace` instead of driving a browser
+    ///
+    /// Each command is answered with its recorded response or error, so a script
+    /// (or the agent logic generating it) can be tested without Chrome. A command
+    /// that doesn't match the next recorded one fails with a "Replay diverged"
+    /// error. Step hooks, captcha handling, forensics, and step frames need a page
+    /// and are skipped; files commands saved aren't written again.
+    pub fn from_trace(trace: CdpTrace) -> Self {
+        Self {
+            pages: Mutex::new(Vec::new()),
+            driver: None,
+            cancel_token: CancellationToken::new(),
+            output_dir: None,
+            captcha_handler: None,
+            cookie_consent: None,
+            overlays: None,
+            goal_judge: None,
+            checkpoint_path: None,
+            step_hooks: Vec::new(),
+            options: ExecutionOptions::default(),
+            verdict: Mutex::new(None),
+            failed_response: Mutex::new(None),
+            frames_captured: AtomicUsize::new(0),
+            replay: Some(TraceReplay::new(trace)),
+        }
+    }
+
+    /// Use `driver` for commands that need more than one page (`Target.waitForPopup`)
+    ///
+    /// The driver's active tab follows the executor, so once a script continues in
+    /// a popup, driver methods act on that popup too. Cookie banners and overlays
+    /// are handled the way the driver handles them, unless set on the executor.
+    pub fn with_driver(mut self, driver: &'a ChromeDriver) -> Self {
+        self.driver = Some(driver);
+        if self.cookie_consent.is_none() {
+            self.cookie_consent = driver.cookie_consent().cloned();
+        }
+        if self.overlays.is_none() {
+            self.overlays = driver.overlay_handling().cloned();
+        }
+        self
+    }
+
+    /// Page the next command runs against
+    fn page(&self) -> Page {
+        let pages = self.pages.lock().unwrap();
+        pages
+            .last()
+            .cloned()
+            .expect("executor has a page unless it's replaying a trace")
+    }
+
+    /// Use a cancellation token to abort script execution
+    ///
+    /// The token is checked at every command boundary: once cancelled, the
+    /// command in flight is allowed to finish and all remaining commands are
+    /// recorded as Skipped.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel_token = token;
+        self
+    }
+
+    /// Write `save_as` files under `dir` instead of the working directory
+    ///
+    /// Steps whose `save_as` is absolute or leaves `dir` through `..` fail.
+    pub fn with_output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = Some(dir.into());
+        self
+    }
+
+    /// Check for captchas after navigation and input commands, passing any to `handler`
+    ///
+    /// A command that leaves an unresolved challenge on the page fails with a
+    /// "Captcha detected" error instead of letting later commands time out.
+    /// Use [`FailOnCaptcha`](crate::browser::captcha::FailOnCaptcha) to stop on the
+    /// first challenge.
+    pub fn with_captcha_handler(mut self, handler: Arc<dyn CaptchaHandler>) -> Self {
+        self.captcha_handler = Some(handler);
+        self
+    }
+
+    /// Answer cookie consent banners after `Page.navigate` and `Page.reload`
+    pub fn with_cookie_consent(mut self, settings: CookieConsent) -> Self {
+        self.cookie_consent = Some(settings);
+        self
+    }
+
+    /// Handle overlays covering the page after `Page.navigate` and `Page.reload`, and
+    /// before mouse presses an overlay would catch, so clicks reach their target
+    pub fn with_overlay_handling(mut self, settings: OverlayHandling) -> Self {
+        self.overlays = Some(settings);
+        self
+    }
+
+    /// Ask `judge` about a screenshot when a `Verify.goal` step's text check isn't
+    /// conclusive (or always, in `vision` mode)
+    ///
+    /// [`CdpScriptGenerator`](super::CdpScriptGenerator) is a judge.
+    pub fn with_goal_judge(mut self, judge: Arc<dyn GoalJudge>) -> Self {
+        self.goal_judge = Some(judge);
+        self
+    }
+
+    /// Write an [`ExecutionCheckpoint`] to `path` after every successful command
+    ///
+    /// Load it with [`ExecutionCheckpoint::load`] and pass it to
+    /// [`execute_script_resume`](Self::execute_script_resume) to continue a run
+    /// that crashed, failed, or was cancelled.
+    pub fn with_checkpoint(mut self, path: impl Into<PathBuf>) -> Self {
+        self.checkpoint_path = Some(path.into());
+        self
+    }
+
+    /// Call `hooks` before and after every command
+    ///
+    /// Can be called more than once; hooks run in the order they were added.
+    pub fn with_step_hooks(mut self, hooks: Arc<dyn StepHooks>) -> Self {
+        self.step_hooks.push(hooks);
+        self
+    }
+
+    /// Set execution options
+    pub fn with_options(mut self, options: ExecutionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Where a command's `save_as` file should be written
+    ///
+    /// With an output directory, `filename` must stay inside it: absolute paths and
+    /// `..` are refused.
+    async fn output_path(&self, filename: &str) -> Result<PathBuf> {
+        match &self.output_dir {
+            Some(dir) => {
+                let path = path_within(dir, filename)?;
+                tokio::fs::create_dir_all(dir)
+                    .await
+                    .context("Failed to create output directory")?;
+                Ok(path)
+            }
+            None => Ok(PathBuf::from(filename)),
+        }
     }
 
     /// Execute a complete CDP script
     pub async fn execute_script(&self, script: &CdpScript) -> Result<ExecutionReport> {
+        self.execute_script_with_progress(script, |_| {}).await
+    }
+
+    /// Execute a complete CDP script, reporting each command result as it completes
+    ///
+    /// `on_result` is called after every command with the result that was just
+    /// recorded, which lets callers (e.g. the async job API) surface partial
+    /// results while a long script is still running.
+    #[tracing::instrument(
+        name = "cdp_script",
+        skip_all,
+        fields(script = %script.name, commands = script.cdp_commands.len())
+    )]
+    pub async fn execute_script_with_progress<F>(
+        &self,
+        script: &CdpScript,
+        mut on_result: F,
+    ) -> Result<ExecutionReport>
+    where
+        F: FnMut(&CommandResult),
+    {
         // Validate script before execution
         script.validate()?;
 
+        let checkpoint = ExecutionCheckpoint::new(script.clone());
+        self.run_from(checkpoint, &mut on_result).await
+    }
+
+    /// Continue a script run from a checkpoint written by
+    /// [`with_checkpoint`](Self::with_checkpoint)
+    ///
+    /// Navigates back to the checkpoint's URL if the page is elsewhere, then runs
+    /// the remaining commands. The report includes the results recorded before the
+    /// checkpoint.
+    pub async fn execute_script_resume(
+        &self,
+        checkpoint: ExecutionCheckpoint,
+    ) -> Result<ExecutionReport> {
+        self.execute_script_resume_with_progress(checkpoint, |_| {})
+            .await
+    }
+
+    /// [`execute_script_resume`](Self::execute_script_resume), reporting each
+    /// command result as it completes
+    #[tracing::instrument(
+        name = "cdp_script_resume",
+        skip_all,
+        fields(script = %checkpoint.script.name, from_step = checkpoint.next_step + 1)
+    )]
+    pub async fn execute_script_resume_with_progress<F>(
+        &self,
+        checkpoint: ExecutionCheckpoint,
+        mut on_result: F,
+    ) -> Result<ExecutionReport>
+    where
+        F: FnMut(&CommandResult),
+    {
+        checkpoint.script.validate()?;
+
+        if let (Some(url), None) = (&checkpoint.url, &self.replay) {
+            let page = self.page();
+            if page.url().await?.as_deref() != Some(url.as_str()) {
+                tracing::info!(url = %url, "Returning to the checkpoint page");
+                page.goto(url.as_str())
+                    .await
+                    .context("Failed to return to the checkpoint page")?;
+            }
+        }
+
+        self.run_from(checkpoint, &mut on_result).await
+    }
+
+    /// Run the commands after `checkpoint`, advancing (and saving) it as they succeed
+    async fn run_from<F>(
+        &self,
+        mut checkpoint: ExecutionCheckpoint,
+        on_result: &mut F,
+    ) -> Result<ExecutionReport>
+    where
+        F: FnMut(&CommandResult),
+    {
+        if self.options.frame_capture != FrameCapture::Never && self.driver.is_none() {
+            anyhow::bail!("Capturing step frames needs a driver (CdpExecutor::with_driver)");
+        }
+        // Hooks are given the page, which a replay doesn't have
+        let run_hooks = !self.step_hooks.is_empty() && self.replay.is_none();
+
+        let script = checkpoint.script.clone();
+        let started = Instant::now();
         let mut report = ExecutionReport::new(script.name.clone(), script.cdp_commands.len());
+        report.browser = self.driver.map(|driver| driver.browser_info().clone());
+        for result in &checkpoint.results {
+            report.add_result(result.clone());
+        }
 
-        for (i, cmd) in script.cdp_commands.iter().enumerate() {
+        let start_step = checkpoint.next_step;
+        for (i, cmd) in script.cdp_commands.iter().enumerate().skip(start_step) {
             let step = i + 1;
+
+            if self.cancel_token.is_cancelled() {
+                tracing::info!(step, "Execution cancelled, skipping remaining commands");
+                report.cancelled = true;
+                for (j, skipped) in script.cdp_commands.iter().enumerate().skip(i) {
+                    let result = CommandResult {
+                        step: j + 1,
+                        method: skipped.method.clone(),
+                        status: CommandStatus::Skipped,
+                        duration: Duration::ZERO,
+                        response: None,
+                        error: Some("Execution cancelled".to_string()),
+                        saved_file: None,
+                        frames: Vec::new(),
+                    };
+                    on_result(&result);
+                    report.add_result(result);
+                }
+                break;
+            }
+
+            if run_hooks {
+                let page = self.page();
+                let context = StepContext {
+                    step,
+                    command: cmd,
+                    script: &script,
+                    page: &page,
+                };
+                for hooks in &self.step_hooks {
+                    hooks.on_step_start(&context).await;
+                }
+            }
+
+            let mut frames = Vec::new();
+            if self.options.frame_capture == FrameCapture::BeforeAndAfter {
+                frames.extend(self.capture_frame(&script, step, started).await);
+            }
+
             let start = Instant::now();
 
-            match self.execute_command(cmd).await {
-                Ok((response, saved_file)) => {
-                    report.add_result(CommandResult {
+            let span = tracing::info_span!("cdp_command", step, method = %cmd.method);
+            let outcome = match self.options.command_timeout {
+                Some(limit) => tokio::time::timeout(limit, self.run_command(cmd))
+                    .instrument(span.clone())
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(anyhow::anyhow!(
+                            "Command timed out after {}ms",
+                            limit.as_millis()
+                        ))
+                    }),
+                None => self.run_command(cmd).instrument(span.clone()).await,
+            };
+            let (mut result, failed) = match outcome {
+                Ok((response, saved_file)) => (
+                    CommandResult {
                         step,
                         method: cmd.method.clone(),
                         status: CommandStatus::Success,
@@ -51,29 +482,350 @@ impl CdpExecutor {
                         response: Some(response),
                         error: None,
                         saved_file,
-                    });
-                }
+                        frames: Vec::new(),
+                    },
+                    false,
+                ),
                 Err(e) => {
-                    report.add_result(CommandResult {
-                        step,
-                        method: cmd.method.clone(),
-                        status: CommandStatus::Failed,
-                        duration: start.elapsed(),
-                        response: None,
-                        error: Some(e.to_string()),
-                        saved_file: None,
-                    });
+                    let (mut error, saved_file) = match self.crash_details(step).await {
+                        Some(crash) => crash,
+                        None => (e.to_string(), None),
+                    };
+                    if let Some(location) = self.save_forensics(&script, step, cmd, &error).await {
+                        error = format!("{}; forensics: {}", error, location);
+                    }
+                    (
+                        CommandResult {
+                            step,
+                            method: cmd.method.clone(),
+                            status: CommandStatus::Failed,
+                            duration: start.elapsed(),
+                            response: self.failed_response.lock().unwrap().take(),
+                            error: Some(error),
+                            saved_file,
+                            frames: Vec::new(),
+                        },
+                        true,
+                    )
+                }
+            };
+
+            let capture_after = match self.options.frame_capture {
+                FrameCapture::Never => false,
+                FrameCapture::BeforeAndAfter => true,
+                FrameCapture::OnFailure => failed,
+            };
+            if capture_after {
+                frames.extend(self.capture_frame(&script, step, started).await);
+            }
+            result.frames = frames;
+
+            if cmd.method == "Verify.goal" {
+                // A replayed step has no verdict of its own, only its recorded response
+                let verdict = self
+                    .verdict
+                    .lock()
+                    .unwrap()
+                    .take()
+                    .or_else(|| serde_json::from_value(result.response.clone()?).ok());
+                if verdict.is_some() {
+                    report.verdict = verdict;
+                }
+            }
 
-                    // Stop execution on first error
-                    // TODO: Make this configurable (continue_on_error flag)
-                    break;
+            span.in_scope(|| {
+                if failed {
+                    tracing::warn!(
+                        duration_ms = result.duration.as_millis() as u64,
+                        error = result.error.as_deref().unwrap_or_default(),
+                        "Command failed"
+                    );
+                } else {
+                    tracing::debug!(
+                        duration_ms = result.duration.as_millis() as u64,
+                        "Command succeeded"
+                    );
+                }
+            });
+
+            if run_hooks {
+                // The command may have moved the script to another page (a popup)
+                let page = self.page();
+                let context = StepContext {
+                    step,
+                    command: cmd,
+                    script: &script,
+                    page: &page,
+                };
+                for hooks in &self.step_hooks {
+                    if failed {
+                        hooks.on_step_failure(&context, &result).await;
+                    } else {
+                        hooks.on_step_success(&context, &result).await;
+                    }
                 }
             }
+
+            if !failed {
+                self.save_checkpoint(&mut checkpoint, &result).await;
+            }
+
+            on_result(&result);
+            report.add_result(result);
+
+            if failed && !self.options.continue_on_error {
+                break;
+            }
         }
 
         Ok(report)
     }
 
+    /// Advance the checkpoint past a successful command and write it, if enabled
+    ///
+    /// A checkpoint that can't be written is logged rather than failing the run.
+    async fn save_checkpoint(&self, checkpoint: &mut ExecutionCheckpoint, result: &CommandResult) {
+        let Some(path) = &self.checkpoint_path else {
+            return;
+        };
+        let url = match self.replay {
+            Some(_) => None,
+            None => self.page().url().await.ok().flatten(),
+        };
+        checkpoint.record(result, url);
+        if let Err(e) = checkpoint.save(path).await {
+            tracing::warn!(error = %e, path = %path.display(), "Failed to write checkpoint");
+        }
+    }
+
+    /// Capture a step frame of the current page with step `step` of `script` as its action
+    ///
+    /// A frame that can't be captured (e.g. because the page crashed) is logged
+    /// rather than failing the command.
+    async fn capture_frame(
+        &self,
+        script: &CdpScript,
+        step: usize,
+        started: Instant,
+    ) -> Option<StepFrame> {
+        let driver = self.driver?;
+        let cmd = &script.cdp_commands[step - 1];
+        let frame_id = self.frames_captured.fetch_add(1, Ordering::Relaxed);
+        let action = ActionInfo {
+            action_type: cmd.method.clone(),
+            intent: cmd.description.clone().unwrap_or_else(|| describe(cmd)),
+            target: cmd.params["selector"].as_str().map(str::to_string),
+            target_screenshot: None,
+        };
+
+        match capture_step_frame(
+            driver,
+            frame_id,
+            started.elapsed().as_millis() as u64,
+            &self.options.capture,
+            None,
+            Some(action),
+        )
+        .await
+        {
+            Ok(mut frame) => {
+                if self.options.narrate {
+                    frame.transcript = narrate(script, step);
+                }
+                Some(frame)
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, frame_id, "Failed to capture step frame");
+                None
+            }
+        }
+    }
+
+    /// Collect and store a forensics bundle for a failed command, if enabled
+    ///
+    /// Returns the bundle's location; a bundle that can't be stored is logged.
+    async fn save_forensics(
+        &self,
+        script: &CdpScript,
+        step: usize,
+        cmd: &CdpCommand,
+        error: &str,
+    ) -> Option<String> {
+        let options = self.options.forensics.as_ref()?;
+        if self.replay.is_some() {
+            return None;
+        }
+        let page = self.page();
+        let monitor = self.driver.and_then(|driver| driver.crash_monitor(&page));
+        let save_as = cmd.save_as.as_ref().map(|filename| match &self.output_dir {
+            Some(dir) => path_within(dir, filename).unwrap_or_else(|_| PathBuf::from(filename)),
+            None => PathBuf::from(filename),
+        });
+        let command = serde_json::json!({
+            "script": script.name,
+            "step": step,
+            "method": cmd.method,
+            "description": cmd.description,
+            "params": cmd.params,
+            "save_as": save_as,
+            "error": error,
+        });
+
+        let bundle =
+            ForensicsBundle::collect(&page, monitor.as_deref(), command, options.network_events)
+                .await;
+        match bundle
+            .save(options.store.as_ref(), &bundle_key(&script.name, step))
+            .await
+        {
+            Ok(location) => Some(location),
+            Err(e) => {
+                tracing::warn!(error = %e, step, "Failed to save forensics bundle");
+                None
+            }
+        }
+    }
+
+    /// If the page crashed, the crash error and the saved pre-crash screenshot
+    ///
+    /// Needs a driver, which watches its pages for crashes.
+    async fn crash_details(&self, step: usize) -> Option<(String, Option<String>)> {
+        if self.replay.is_some() {
+            return None;
+        }
+        let monitor = self.driver?.crash_monitor(&self.page())?;
+        let error = monitor.crash_error(CRASH_GRACE).await?;
+
+        let screenshot = match &error {
+            BrowserError::TargetCrashed(report) => report.screenshot.clone(),
+            _ => None,
+        };
+        let saved_file = match screenshot {
+            Some(bytes) => match self.save_crash_screenshot(step, &bytes).await {
+                Ok(path) => Some(path),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to save crash screenshot");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        Some((error.to_string(), saved_file))
+    }
+
+    async fn save_crash_screenshot(&self, step: usize, bytes: &[u8]) -> Result<String> {
+        let path = self
+            .output_path(&format!("crash-step-{}.jpg", step))
+            .await?;
+        tokio::fs::write(&path, bytes)
+            .await
+            .context("Failed to write crash screenshot")?;
+        Ok(path.display().to_string())
+    }
+
+    /// Run `operation` holding the [`PageLock`](crate::browser::PageLock) of the page
+    /// the next command runs against, so driver calls other tasks make on it wait
+    ///
+    /// Only the driver's calls are queued, so without [`with_driver`](Self::with_driver)
+    /// the lock isn't taken.
+    async fn with_page_lock<F: std::future::Future>(&self, operation: F) -> F::Output {
+        match (self.driver, &self.replay) {
+            (Some(driver), None) => driver.page_lock_for(&self.page()).run(operation).await,
+            _ => operation.await,
+        }
+    }
+
+    /// Execute a command, then deal with any captcha it surfaced
+    ///
+    /// The page lock is held while the command and its overlay and cookie banner
+    /// handling run, and released before a captcha handler, which may wait minutes
+    /// for a person, gets the page.
+    async fn run_command(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        if let Some(replay) = &self.replay {
+            return replay.answer(cmd);
+        }
+        let output = self.with_page_lock(self.run_locked(cmd)).await?;
+
+        let may_show_captcha = matches!(
+            cmd.method.as_str(),
+            "Page.navigate"
+                | "Page.reload"
+                | "Input.dispatchMouseEvent"
+                | "Input.dispatchKeyEvent"
+                | "Element.click"
+                | "Element.type"
+        );
+        if let (Some(handler), true) = (&self.captcha_handler, may_show_captcha) {
+            let page = self.page();
+            if let Some(event) = captcha::detect_captcha(&page).await? {
+                captcha::resolve_captcha(&page, &event, handler.as_ref()).await?;
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// The part of [`run_command`](Self::run_command) that runs under the page lock:
+    /// the command itself, with overlays and cookie banners handled around it
+    async fn run_locked(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let pressing = cmd.method == "Input.dispatchMouseEvent"
+            && cmd.params.get("type").and_then(Value::as_str) == Some("mousePressed");
+        if pressing {
+            let coordinate = |name: &str| cmd.params.get(name).and_then(Value::as_f64);
+            if let (Some(x), Some(y)) = (coordinate("x"), coordinate("y")) {
+                self.handle_overlays(Some((x, y))).await;
+            }
+        }
+        let output = self.execute_command(cmd).await?;
+
+        let navigated = matches!(
+            cmd.method.as_str(),
+            "Page.navigate" | "Page.reload" | "Page.goBack" | "Page.goForward"
+        );
+        if let (Some(settings), true) = (&self.cookie_consent, navigated) {
+            if let Err(e) = consent::dismiss_cookie_banner(&self.page(), settings).await {
+                tracing::warn!(error = %e, "Failed to dismiss cookie banner");
+            }
+        }
+        if navigated {
+            self.handle_overlays(None).await;
+        }
+
+        Ok(output)
+    }
+
+    /// Apply the overlay policy, if there is one, to the overlays on the page (or the
+    /// one a click at `point` would hit); overlays that stay up are logged
+    async fn handle_overlays(&self, point: Option<(f64, f64)>) {
+        let Some(settings) = &self.overlays else {
+            return;
+        };
+        let page = self.page();
+        let handled = match point {
+            Some((x, y)) => overlay::handle_overlays_at(&page, settings, x, y).await,
+            None => overlay::handle_overlays(&page, settings).await,
+        };
+        match handled {
+            Ok(handled) => {
+                for handled in handled {
+                    let open = matches!(
+                        handled.outcome,
+                        OverlayOutcome::Reported | OverlayOutcome::StillOpen
+                    );
+                    if open {
+                        tracing::warn!(
+                            overlay = %handled.overlay.selector,
+                            text = %handled.overlay.text,
+                            "Overlay covers the page"
+                        );
+                    }
+                }
+            }
+            Err(e) => tracing::warn!(error = %e, "Failed to handle overlays"),
+        }
+    }
+
     /// Execute a single CDP command
     ///
     /// Returns (response_json, optional_saved_file_path)
@@ -83,14 +835,21 @@ impl CdpExecutor {
             "Page.navigate" => self.execute_page_navigate(cmd).await,
             "Page.captureScreenshot" => self.execute_page_capture_screenshot(cmd).await,
             "Page.reload" => self.execute_page_reload(cmd).await,
-            "Page.goBack" => self.execute_page_go_back(cmd).await,
-            "Page.goForward" => self.execute_page_go_forward(cmd).await,
+            "Page.goBack" => {
+                self.execute_page_history(cmd, HistoryNavigation::Back)
+                    .await
+            }
+            "Page.goForward" => {
+                self.execute_page_history(cmd, HistoryNavigation::Forward)
+                    .await
+            }
 
             // ===== RUNTIME DOMAIN =====
             "Runtime.evaluate" => self.execute_runtime_evaluate(cmd).await,
 
             // ===== INPUT DOMAIN =====
             "Input.insertText" => self.execute_input_insert_text(cmd).await,
+            "Input.imeSetComposition" => self.execute_input_ime_set_composition(cmd).await,
             "Input.dispatchMouseEvent" => self.execute_input_dispatch_mouse_event(cmd).await,
             "Input.dispatchKeyEvent" => self.execute_input_dispatch_key_event(cmd).await,
 
@@ -104,6 +863,37 @@ impl CdpExecutor {
             "Emulation.setDeviceMetricsOverride" => {
                 self.execute_emulation_set_device_metrics(cmd).await
             }
+            "Emulation.setEmulatedMedia" => self.execute_emulation_set_emulated_media(cmd).await,
+
+            // ===== TARGET DOMAIN =====
+            "Target.waitForPopup" => self.execute_target_wait_for_popup(cmd).await,
+            "Target.switchToOpener" => self.execute_target_switch_to_opener().await,
+
+            // ===== AUDIT (not a CDP domain; runs axe-core in the page) =====
+            "Audit.accessibility" => self.execute_audit_accessibility(cmd).await,
+            "Audit.performance" => self.execute_audit_performance(cmd).await,
+
+            // ===== EXTRACT (not a CDP domain; reads page metadata) =====
+            "Extract.seo" => self.execute_extract_seo(cmd).await,
+
+            // ===== ASSERT (not a CDP domain; checks extracted data) =====
+            "Assert.snapshot" => self.execute_assert_snapshot(cmd).await,
+            "Assert.text" => self.execute_assert_text(cmd).await,
+
+            // ===== VERIFY (not a CDP domain; checks the run reached its goal) =====
+            "Verify.goal" => self.execute_verify_goal(cmd).await,
+
+            // ===== FORM (not a CDP domain; fills fields matched by name or label) =====
+            "Form.fill" => self.execute_form_fill(cmd).await,
+            "Form.typeText" => self.execute_form_type_text(cmd).await,
+
+            // ===== TOUCH (not a CDP domain; dispatches touch event sequences) =====
+            "Touch.tap" => self.execute_touch_tap(cmd).await,
+            "Touch.swipe" => self.execute_touch_swipe(cmd).await,
+            "Touch.pinch" => self.execute_touch_pinch(cmd).await,
+
+            // ===== ELEMENT (not a CDP domain; macros expanded into CDP commands) =====
+            method if MacroCommand::is_macro(method) => self.execute_macro(cmd).await,
 
             // Unsupported method
             _ => {
@@ -119,7 +909,7 @@ impl CdpExecutor {
             .context("Failed to parse Page.navigate parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Page.navigate failed")?;
@@ -135,7 +925,7 @@ impl CdpExecutor {
             .context("Failed to parse Page.captureScreenshot parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Page.captureScreenshot failed")?;
@@ -149,11 +939,12 @@ impl CdpExecutor {
                 .context("Failed to decode screenshot base64 data")?;
 
             // Save to file
-            tokio::fs::write(filename, image_data)
+            let path = self.output_path(filename).await?;
+            tokio::fs::write(&path, image_data)
                 .await
                 .context("Failed to write screenshot to file")?;
 
-            Some(filename.clone())
+            Some(path.display().to_string())
         } else {
             None
         };
@@ -166,7 +957,7 @@ impl CdpExecutor {
             .context("Failed to parse Page.reload parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Page.reload failed")?;
@@ -174,30 +965,31 @@ impl CdpExecutor {
         Ok((serde_json::to_value(&*response)?, None))
     }
 
-    async fn execute_page_go_back(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
-        let params: page::NavigateToHistoryEntryParams = serde_json::from_value(cmd.params.clone())
-            .context("Failed to parse Page.goBack parameters")?;
-
-        let response = self
-            .page
-            .execute(params)
-            .await
-            .context("Page.goBack failed")?;
-
-        Ok((serde_json::to_value(&*response)?, None))
-    }
-
-    async fn execute_page_go_forward(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
-        let params: page::NavigateToHistoryEntryParams = serde_json::from_value(cmd.params.clone())
-            .context("Failed to parse Page.goForward parameters")?;
-
-        let response = self
-            .page
-            .execute(params)
-            .await
-            .context("Page.goForward failed")?;
+    /// Go back or forward one entry in the tab's history and wait for it to commit
+    ///
+    /// Params: `entryId` (optional) goes to that `Page.getNavigationHistory` entry
+    /// instead. Returns the page's new `url`.
+    async fn execute_page_history(
+        &self,
+        cmd: &CdpCommand,
+        navigation: HistoryNavigation,
+    ) -> Result<(Value, Option<String>)> {
+        if cmd.params.get("entryId").is_some() {
+            let params: page::NavigateToHistoryEntryParams =
+                serde_json::from_value(cmd.params.clone())
+                    .with_context(|| format!("Failed to parse {} parameters", cmd.method))?;
+            self.page()
+                .execute(params)
+                .await
+                .with_context(|| format!("{} failed", cmd.method))?;
+        } else {
+            navigation::navigate_history(&self.page(), navigation, HISTORY_TIMEOUT)
+                .await
+                .with_context(|| format!("{} failed", cmd.method))?;
+        }
 
-        Ok((serde_json::to_value(&*response)?, None))
+        let url = self.page().url().await.ok().flatten().unwrap_or_default();
+        Ok((serde_json::json!({ "url": url }), None))
     }
 
     // ===== RUNTIME DOMAIN IMPLEMENTATIONS =====
@@ -207,7 +999,7 @@ impl CdpExecutor {
             .context("Failed to parse Runtime.evaluate parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Runtime.evaluate failed")?;
@@ -216,10 +1008,11 @@ impl CdpExecutor {
         let saved_file = if let Some(filename) = &cmd.save_as {
             // Serialize the result value to JSON string
             let content = serde_json::to_string_pretty(&response.result)?;
-            tokio::fs::write(filename, content)
+            let path = self.output_path(filename).await?;
+            tokio::fs::write(&path, content)
                 .await
                 .context("Failed to write evaluate result to file")?;
-            Some(filename.clone())
+            Some(path.display().to_string())
         } else {
             None
         };
@@ -234,7 +1027,7 @@ impl CdpExecutor {
             .context("Failed to parse Input.insertText parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Input.insertText failed")?;
@@ -242,6 +1035,22 @@ impl CdpExecutor {
         Ok((serde_json::to_value(&*response)?, None))
     }
 
+    async fn execute_input_ime_set_composition(
+        &self,
+        cmd: &CdpCommand,
+    ) -> Result<(Value, Option<String>)> {
+        let params: input::ImeSetCompositionParams = serde_json::from_value(cmd.params.clone())
+            .context("Failed to parse Input.imeSetComposition parameters")?;
+
+        let response = self
+            .page()
+            .execute(params)
+            .await
+            .context("Input.imeSetComposition failed")?;
+
+        Ok((serde_json::to_value(&*response)?, None))
+    }
+
     async fn execute_input_dispatch_mouse_event(
         &self,
         cmd: &CdpCommand,
@@ -250,7 +1059,7 @@ impl CdpExecutor {
             .context("Failed to parse Input.dispatchMouseEvent parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Input.dispatchMouseEvent failed")?;
@@ -266,7 +1075,7 @@ impl CdpExecutor {
             .context("Failed to parse Input.dispatchKeyEvent parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Input.dispatchKeyEvent failed")?;
@@ -284,7 +1093,7 @@ impl CdpExecutor {
             .context("Failed to parse Network.getCookies parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Network.getCookies failed")?;
@@ -292,10 +1101,11 @@ impl CdpExecutor {
         // Optionally save cookies to file
         let saved_file = if let Some(filename) = &cmd.save_as {
             let json = serde_json::to_string_pretty(&response.cookies)?;
-            tokio::fs::write(filename, json)
+            let path = self.output_path(filename).await?;
+            tokio::fs::write(&path, json)
                 .await
                 .context("Failed to write cookies to file")?;
-            Some(filename.clone())
+            Some(path.display().to_string())
         } else {
             None
         };
@@ -311,7 +1121,7 @@ impl CdpExecutor {
             .context("Failed to parse Network.setCookie parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Network.setCookie failed")?;
@@ -327,7 +1137,7 @@ impl CdpExecutor {
             .context("Failed to parse Network.deleteCookies parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Network.deleteCookies failed")?;
@@ -346,7 +1156,7 @@ impl CdpExecutor {
                 .context("Failed to parse Emulation.setGeolocationOverride parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Emulation.setGeolocationOverride failed")?;
@@ -363,11 +1173,727 @@ impl CdpExecutor {
                 .context("Failed to parse Emulation.setDeviceMetricsOverride parameters")?;
 
         let response = self
-            .page
+            .page()
             .execute(params)
             .await
             .context("Emulation.setDeviceMetricsOverride failed")?;
 
         Ok((serde_json::to_value(&*response)?, None))
     }
+
+    async fn execute_emulation_set_emulated_media(
+        &self,
+        cmd: &CdpCommand,
+    ) -> Result<(Value, Option<String>)> {
+        let params: emulation::SetEmulatedMediaParams = serde_json::from_value(cmd.params.clone())
+            .context("Failed to parse Emulation.setEmulatedMedia parameters")?;
+
+        let response = self
+            .page()
+            .execute(params)
+            .await
+            .context("Emulation.setEmulatedMedia failed")?;
+
+        Ok((serde_json::to_value(&*response)?, None))
+    }
+
+    // ===== TARGET DOMAIN IMPLEMENTATIONS =====
+
+    /// Wait for a popup opened by the current page; by default later commands run in it
+    ///
+    /// Params: `timeout` (ms, default 10000), `switch` (default true).
+    async fn execute_target_wait_for_popup(
+        &self,
+        cmd: &CdpCommand,
+    ) -> Result<(Value, Option<String>)> {
+        let driver = self
+            .driver
+            .context("Target.waitForPopup requires an executor created with a driver")?;
+        let timeout = cmd
+            .params
+            .get("timeout")
+            .and_then(Value::as_u64)
+            .map_or(DEFAULT_POPUP_TIMEOUT, Duration::from_millis);
+        let switch = cmd
+            .params
+            .get("switch")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+
+        let popup = driver.wait_for_popup_from(&self.page(), timeout).await?;
+        let url = popup.url().await.ok().flatten();
+        let response = serde_json::json!({
+            "targetId": popup.target_id().as_ref(),
+            "url": url,
+            "switched": switch,
+        });
+
+        if switch {
+            driver.switch_to_page(&popup).await?;
+            self.pages.lock().unwrap().push(popup);
+        }
+
+        Ok((response, None))
+    }
+
+    /// Continue in the page that opened the current popup
+    async fn execute_target_switch_to_opener(&self) -> Result<(Value, Option<String>)> {
+        let opener = {
+            let mut pages = self.pages.lock().unwrap();
+            if pages.len() < 2 {
+                anyhow::bail!("Target.switchToOpener: not running in a popup");
+            }
+            pages.pop();
+            pages.last().cloned().expect("opener is still on the stack")
+        };
+
+        if let Some(driver) = self.driver {
+            driver.switch_to_page(&opener).await?;
+        }
+
+        Ok((
+            serde_json::json!({ "targetId": opener.target_id().as_ref() }),
+            None,
+        ))
+    }
+
+    // ===== AUDIT IMPLEMENTATIONS =====
+
+    /// Run an axe-core audit; fails the step if a violation reaches `failOn`
+    async fn execute_audit_accessibility(
+        &self,
+        cmd: &CdpCommand,
+    ) -> Result<(Value, Option<String>)> {
+        let options: AccessibilityOptions = serde_json::from_value(cmd.params.clone())
+            .context("Failed to parse Audit.accessibility parameters")?;
+        let fail_on = cmd
+            .params
+            .get("failOn")
+            .and_then(Value::as_str)
+            .map(str::parse::<Impact>)
+            .transpose()
+            .map_err(anyhow::Error::msg)?;
+
+        let axe_core_path = self.driver.and_then(ChromeDriver::axe_core_path);
+        let report =
+            accessibility::audit_accessibility(&self.page(), axe_core_path, &options).await?;
+        let response = serde_json::to_value(&report)?;
+
+        let saved_file = if let Some(filename) = &cmd.save_as {
+            let path = self.output_path(filename).await?;
+            tokio::fs::write(&path, serde_json::to_string_pretty(&response)?)
+                .await
+                .context("Failed to write accessibility report to file")?;
+            Some(path.display().to_string())
+        } else {
+            None
+        };
+
+        if let Some(impact) = fail_on {
+            let failing: Vec<&str> = report
+                .violations_at_least(impact)
+                .map(|v| v.id.as_str())
+                .collect();
+            if !failing.is_empty() {
+                // The findings stay in the failed step's result
+                *self.failed_response.lock().unwrap() = Some(response);
+                anyhow::bail!(
+                    "Accessibility violations at or above {:?}: {}",
+                    impact,
+                    failing.join(", ")
+                );
+            }
+        }
+
+        Ok((response, saved_file))
+    }
+
+    /// Summarize the current page load; fails the step if the score is below `minScore`
+    async fn execute_audit_performance(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let min_score = cmd.params.get("minScore").and_then(Value::as_u64);
+
+        let report = performance::audit_performance(&self.page()).await?;
+        let response = serde_json::to_value(&report)?;
+
+        let saved_file = if let Some(filename) = &cmd.save_as {
+            let path = self.output_path(filename).await?;
+            tokio::fs::write(&path, serde_json::to_string_pretty(&response)?)
+                .await
+                .context("Failed to write performance report to file")?;
+            Some(path.display().to_string())
+        } else {
+            None
+        };
+
+        if let Some(min_score) = min_score {
+            if u64::from(report.score) < min_score {
+                anyhow::bail!(
+                    "Performance score {} is below the minimum of {}",
+                    report.score,
+                    min_score
+                );
+            }
+        }
+
+        Ok((response, saved_file))
+    }
+
+    // ===== EXTRACT IMPLEMENTATIONS =====
+
+    /// Read the page's SEO metadata, validated against `rules` when given (or when
+    /// `validate` is set); `failOnIssues` fails the step if validation finds problems
+    async fn execute_extract_seo(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let rules: Option<SeoRules> = match cmd.params.get("rules") {
+            Some(rules) => Some(
+                serde_json::from_value(rules.clone())
+                    .context("Failed to parse Extract.seo rules")?,
+            ),
+            None => cmd
+                .params
+                .get("validate")
+                .and_then(Value::as_bool)
+                .unwrap_or(false)
+                .then(SeoRules::default),
+        };
+        let fail_on_issues = cmd
+            .params
+            .get("failOnIssues")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let metadata = seo::extract_seo(&self.page()).await?;
+        let issues = rules.map(|rules| metadata.validate(&rules));
+        let mut response = serde_json::to_value(&metadata)?;
+        if let Some(issues) = &issues {
+            response["issues"] = serde_json::to_value(issues)?;
+        }
+
+        let saved_file = if let Some(filename) = &cmd.save_as {
+            let path = self.output_path(filename).await?;
+            tokio::fs::write(&path, serde_json::to_string_pretty(&response)?)
+                .await
+                .context("Failed to write SEO metadata to file")?;
+            Some(path.display().to_string())
+        } else {
+            None
+        };
+
+        if let Some(issues) = issues.filter(|issues| fail_on_issues && !issues.is_empty()) {
+            let summary: Vec<String> = issues
+                .iter()
+                .map(|issue| format!("{}: {}", issue.field, issue.message))
+                .collect();
+            anyhow::bail!("SEO validation failed: {}", summary.join("; "));
+        }
+
+        Ok((response, saved_file))
+    }
+
+    // ===== ASSERT IMPLEMENTATIONS =====
+
+    /// Evaluate `expression` and compare the JSON it returns with the snapshot
+    /// called `name`, leaving out `ignorePaths` (JSONPath, e.g. `$.items[*].id`)
+    ///
+    /// The first run (or any run with `update_snapshots`) stores the snapshot. On a
+    /// mismatch the step fails and a diff is written to `<name>.snapshot.diff` in the
+    /// output directory.
+    async fn execute_assert_snapshot(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let name = cmd.params["name"]
+            .as_str()
+            .context("Assert.snapshot needs a name")?;
+        let expression = cmd.params["expression"]
+            .as_str()
+            .context("Assert.snapshot needs an expression")?;
+        let ignore_paths: Vec<String> = match cmd.params.get("ignorePaths") {
+            Some(paths) => serde_json::from_value(paths.clone())
+                .context("Failed to parse Assert.snapshot ignorePaths")?,
+            None => Vec::new(),
+        };
+        let ignore_paths = ignore_paths
+            .iter()
+            .map(|path| path.parse::<JsonPath>().map_err(anyhow::Error::msg))
+            .collect::<Result<Vec<_>>>()?;
+        let ignored = Value::from(snapshot::IGNORED);
+        let redact = |value: &mut Value| {
+            for path in &ignore_paths {
+                path.replace(value, &ignored);
+            }
+        };
+
+        let response = self
+            .page()
+            .execute(
+                runtime::EvaluateParams::builder()
+                    .expression(expression)
+                    .return_by_value(true)
+                    .await_promise(true)
+                    .build()
+                    .map_err(anyhow::Error::msg)?,
+            )
+            .await
+            .context("Assert.snapshot failed to evaluate its expression")?
+            .result;
+        if let Some(exception) = &response.exception_details {
+            anyhow::bail!("Snapshot expression threw: {}", exception.text);
+        }
+        let mut actual = response.result.value.unwrap_or(Value::Null);
+        redact(&mut actual);
+
+        let saved_file = if let Some(filename) = &cmd.save_as {
+            let path = self.output_path(filename).await?;
+            tokio::fs::write(&path, serde_json::to_string_pretty(&actual)?)
+                .await
+                .context("Failed to write extracted data to file")?;
+            Some(path.display().to_string())
+        } else {
+            None
+        };
+
+        let dir = match &self.options.snapshot_dir {
+            Some(dir) => dir.clone(),
+            None => self.output_path("snapshots").await?,
+        };
+        let path = dir.join(format!("{}.json", name));
+        let stored = match tokio::fs::read_to_string(&path).await {
+            Ok(json) if !self.options.update_snapshots => Some(
+                serde_json::from_str::<Value>(&json)
+                    .with_context(|| format!("Failed to parse snapshot {}", path.display()))?,
+            ),
+            _ => None,
+        };
+
+        let Some(mut expected) = stored else {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .context("Failed to create snapshot directory")?;
+            }
+            tokio::fs::write(&path, serde_json::to_string_pretty(&actual)?)
+                .await
+                .context("Failed to write snapshot")?;
+            tracing::info!(snapshot = %path.display(), "Stored snapshot");
+            let response = serde_json::json!({
+                "snapshot": path.display().to_string(),
+                "status": "stored",
+            });
+            return Ok((response, saved_file));
+        };
+
+        // Paths ignored since the snapshot was stored
+        redact(&mut expected);
+        let changes = snapshot::compare(&expected, &actual);
+        if changes.is_empty() {
+            let response = serde_json::json!({
+                "snapshot": path.display().to_string(),
+                "status": "matched",
+            });
+            return Ok((response, saved_file));
+        }
+
+        let diff_path = self.output_path(&format!("{}.snapshot.diff", name)).await?;
+        if let Some(parent) = diff_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context("Failed to create snapshot diff directory")?;
+        }
+        tokio::fs::write(&diff_path, snapshot::render_diff(name, &changes))
+            .await
+            .context("Failed to write snapshot diff")?;
+        let summary: Vec<String> = changes
+            .iter()
+            .take(5)
+            .map(|change| format!("{}: {} -> {}", change.field, change.before, change.after))
+            .collect();
+        anyhow::bail!(
+            "Snapshot '{}' doesn't match ({} changes: {}); diff: {}",
+            name,
+            changes.len(),
+            summary.join("; "),
+            diff_path.display()
+        );
+    }
+
+    /// Check that `text` is on the page (inside `selector`, if given)
+    ///
+    /// Text is compared without case, diacritics, nbsp, or typographic punctuation
+    /// (`caseSensitive` and `foldDiacritics` turn those off), as an element's whole
+    /// text if `exact` is set, and with similarity down to `fuzzy` (0-1) accepted.
+    async fn execute_assert_text(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let query: TextQuery = serde_json::from_value(cmd.params.clone())
+            .context("Assert.text needs a text and valid matching options")?;
+        let hits = text::find_text(&self.page(), &query)
+            .await
+            .context("Assert.text failed to read the page")?;
+        let Some(best) = hits.first() else {
+            anyhow::bail!("Text \"{}\" not found on the page", query.text);
+        };
+        tracing::info!(selector = %best.selector, score = best.score, "Found text");
+        let response = serde_json::json!({
+            "matches": hits.len(),
+            "selector": best.selector,
+            "text": best.text,
+            "score": best.score,
+        });
+        Ok((response, None))
+    }
+
+    // ===== VERIFY IMPLEMENTATIONS =====
+
+    /// Check the natural-language `goal` against the current page
+    ///
+    /// `mode` is `dom` (the page's text, title, and URL), `vision` (a screenshot,
+    /// saved as `screenshot` in the output directory, judged by the goal judge), or
+    /// `auto` (default): the text, then the judge if the text check's confidence is
+    /// below `minConfidence` (default 0.7). The verdict goes in the report, and the
+    /// step fails if the goal wasn't met unless `failOnUnmet` is false.
+    async fn execute_verify_goal(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let goal = cmd.params["goal"]
+            .as_str()
+            .context("Verify.goal needs a goal")?;
+        let mode: VerifyMode = match cmd.params.get("mode") {
+            Some(mode) => serde_json::from_value(mode.clone())
+                .context("Verify.goal mode must be dom, vision, or auto")?,
+            None => VerifyMode::default(),
+        };
+        let min_confidence = cmd
+            .params
+            .get("minConfidence")
+            .and_then(Value::as_f64)
+            .unwrap_or(DEFAULT_GOAL_CONFIDENCE);
+        let fail_on_unmet = cmd
+            .params
+            .get("failOnUnmet")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+
+        let page = self.page();
+        let evidence = page
+            .execute(
+                runtime::EvaluateParams::builder()
+                    .expression(PAGE_EVIDENCE_JS)
+                    .return_by_value(true)
+                    .build()
+                    .map_err(anyhow::Error::msg)?,
+            )
+            .await
+            .context("Verify.goal failed to read the page")?
+            .result
+            .result
+            .value
+            .unwrap_or(Value::Null);
+        let evidence: PageEvidence =
+            serde_json::from_value(evidence).context("Verify.goal failed to read the page")?;
+
+        let dom = check_page(goal, &evidence);
+        let verdict = match (&self.goal_judge, mode) {
+            (_, VerifyMode::Dom) => dom,
+            (_, VerifyMode::Auto) if dom.confidence >= min_confidence => dom,
+            (None, VerifyMode::Auto) => dom,
+            (None, VerifyMode::Vision) => anyhow::bail!(
+                "Verify.goal in vision mode needs a goal judge (CdpExecutor::with_goal_judge)"
+            ),
+            (Some(judge), _) => {
+                let filename = cmd.params["screenshot"].as_str().unwrap_or("goal.png");
+                let judged = self.judge_goal(judge.as_ref(), goal, &evidence, filename);
+                match (judged.await, mode) {
+                    (Ok(verdict), _) => verdict,
+                    (Err(e), VerifyMode::Auto) => {
+                        tracing::warn!(error = %e, "Goal judge failed, keeping the text check's verdict");
+                        dom
+                    }
+                    (Err(e), _) => return Err(e.context("Verify.goal vision check failed")),
+                }
+            }
+        };
+        tracing::info!(
+            goal,
+            passed = verdict.passed,
+            confidence = verdict.confidence,
+            method = ?verdict.method,
+            "Verified goal"
+        );
+
+        let response = serde_json::to_value(&verdict)?;
+        *self.verdict.lock().unwrap() = Some(verdict.clone());
+
+        let saved_file = if let Some(filename) = &cmd.save_as {
+            let path = self.output_path(filename).await?;
+            tokio::fs::write(&path, serde_json::to_string_pretty(&response)?)
+                .await
+                .context("Failed to write goal verdict to file")?;
+            Some(path.display().to_string())
+        } else {
+            None
+        };
+
+        if !verdict.passed && fail_on_unmet {
+            anyhow::bail!(
+                "Goal not met (confidence {:.2}): {}",
+                verdict.confidence,
+                verdict.reason
+            );
+        }
+        Ok((response, saved_file))
+    }
+
+    /// Screenshot the page to `filename` and ask `judge` whether it meets `goal`
+    async fn judge_goal(
+        &self,
+        judge: &dyn GoalJudge,
+        goal: &str,
+        evidence: &PageEvidence,
+        filename: &str,
+    ) -> Result<GoalVerdict> {
+        let screenshot = self
+            .page()
+            .execute(
+                page::CaptureScreenshotParams::builder()
+                    .format(page::CaptureScreenshotFormat::Png)
+                    .build(),
+            )
+            .await
+            .context("Failed to screenshot the page")?;
+        use base64::{engine::general_purpose, Engine as _};
+        let image_data = general_purpose::STANDARD
+            .decode(&screenshot.data)
+            .context("Failed to decode screenshot base64 data")?;
+        let path = self.output_path(filename).await?;
+        tokio::fs::write(&path, image_data)
+            .await
+            .context("Failed to write goal screenshot")?;
+
+        let mut verdict = judge.judge(goal, evidence, &path).await?;
+        verdict.screenshot = Some(path.display().to_string());
+        Ok(verdict)
+    }
+
+    // ===== FORM IMPLEMENTATIONS =====
+
+    /// Fill form fields given as `{"field": value}`, matched by name, id, label, or
+    /// placeholder; `failOnUnmatched` fails the step if any field couldn't be filled
+    async fn execute_form_fill(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let fields = cmd
+            .params
+            .get("fields")
+            .and_then(Value::as_object)
+            .context("Form.fill requires a 'fields' object")?
+            .iter()
+            .map(|(key, value)| {
+                FormValue::from_json(value)
+                    .map(|value| (key.clone(), value))
+                    .with_context(|| {
+                        format!(
+                            "Form.fill field '{}' must be a string, number, boolean, or array",
+                            key
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let fail_on_unmatched = cmd
+            .params
+            .get("failOnUnmatched")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let builtin;
+        let widgets = match self.driver {
+            Some(driver) => driver.widgets(),
+            None => {
+                builtin = WidgetRegistry::default();
+                &builtin
+            }
+        };
+        let result = form::fill_form(&self.page(), &fields, widgets).await?;
+        if fail_on_unmatched && !result.is_complete() {
+            let mut problems: Vec<String> = result
+                .unmatched
+                .iter()
+                .map(|field| format!("{}: no matching field", field))
+                .collect();
+            problems.extend(
+                result
+                    .invalid
+                    .iter()
+                    .map(|invalid| format!("{}: {}", invalid.field, invalid.reason)),
+            );
+            anyhow::bail!("Form.fill failed: {}", problems.join("; "));
+        }
+
+        Ok((serde_json::to_value(&result)?, None))
+    }
+
+    /// Replace the value of the element matching `selector` (or a fallback) with
+    /// `text`, through the widget strategy (date picker, combobox, rich text editor)
+    /// that handles it
+    async fn execute_form_type_text(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let candidates = SelectorCandidates::from_params(&cmd.params)
+            .context("Form.typeText requires a 'selector'")?;
+        let text = cmd
+            .params
+            .get("text")
+            .and_then(Value::as_str)
+            .context("Form.typeText requires a 'text'")?;
+
+        let builtin;
+        let widgets = match self.driver {
+            Some(driver) => driver.widgets(),
+            None => {
+                builtin = WidgetRegistry::default();
+                &builtin
+            }
+        };
+        let page = self.page();
+        let matched = selector::resolve(&page, &candidates).await?;
+        let element = page
+            .find_element(matched.selector.as_str())
+            .await
+            .map_err(|_| BrowserError::ElementNotFound(matched.selector.clone()))?;
+        let strategy = widgets.type_text(&page, &element, text).await?;
+
+        Ok((
+            serde_json::json!({ "strategy": strategy, "matched": matched }),
+            None,
+        ))
+    }
+
+    // ===== TOUCH IMPLEMENTATIONS =====
+
+    /// Tap the element matching `selector` (or a fallback)
+    async fn execute_touch_tap(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let candidates = SelectorCandidates::from_params(&cmd.params)
+            .context("Touch.tap requires a 'selector'")?;
+        let page = self.page();
+        let matched = selector::resolve(&page, &candidates).await?;
+        touch::tap(&page, &matched.selector).await?;
+        Ok((serde_json::json!({ "matched": matched }), None))
+    }
+
+    /// Swipe between `from` and `to` (`[x, y]` viewport points) over `duration` ms
+    async fn execute_touch_swipe(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let point = |name: &str| -> Result<(f64, f64)> {
+            let (x, y): (f64, f64) = serde_json::from_value(
+                cmd.params
+                    .get(name)
+                    .cloned()
+                    .with_context(|| format!("Touch.swipe requires '{}'", name))?,
+            )
+            .with_context(|| format!("Touch.swipe '{}' must be [x, y]", name))?;
+            Ok((x, y))
+        };
+        let from = point("from")?;
+        let to = point("to")?;
+        let duration = cmd
+            .params
+            .get("duration")
+            .and_then(Value::as_u64)
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::from_millis(300));
+
+        touch::swipe(&self.page(), from, to, duration).await?;
+        Ok((serde_json::json!({}), None))
+    }
+
+    async fn execute_touch_pinch(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let scale = cmd
+            .params
+            .get("scale")
+            .and_then(Value::as_f64)
+            .context("Touch.pinch requires a 'scale'")?;
+        touch::pinch_zoom(&self.page(), scale).await?;
+        Ok((serde_json::json!({}), None))
+    }
+
+    // ===== ELEMENT MACRO IMPLEMENTATIONS =====
+
+    /// Expand a macro command (`Element.click`, `Element.type`, `Element.waitFor`,
+    /// `Element.scrollTo`) and run the CDP commands it expands to in order
+    ///
+    /// Every macro but `Element.waitFor` finds its element with the usual fallbacks
+    /// first; clicks and typing scroll it into view and click its center. The
+    /// commands run like script steps, so overlays are handled as for hand-written
+    /// input, and are returned as `expanded`. Captchas are checked once the macro has
+    /// finished, after the page lock is released.
+    async fn execute_macro(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let command = MacroCommand::from_command(cmd)
+            .context("Not a macro command")?
+            .map_err(anyhow::Error::msg)?;
+        let page = self.page();
+
+        let mut matched = None;
+        let mut center = None;
+        if command.needs_element() {
+            let candidates = SelectorCandidates::from_params(&cmd.params)
+                .with_context(|| format!("{} requires a 'selector'", cmd.method))?;
+            let found = selector::resolve(&page, &candidates).await?;
+            if command.needs_position() {
+                center = Some(geometry::visible_center(&page, &found.selector).await?);
+            }
+            matched = Some(found);
+        }
+        let selector = matched
+            .as_ref()
+            .map_or(command.selector(), |found| found.selector.as_str());
+        let expanded = command
+            .expand(selector, center)
+            .map_err(anyhow::Error::msg)?;
+
+        for step in &expanded {
+            // Boxed, since run_locked is what called this
+            let (response, _) = Box::pin(self.run_locked(step))
+                .await
+                .with_context(|| format!("{}: {} failed", cmd.method, describe(step)))?;
+            if let Some(exception) = response.get("exceptionDetails") {
+                let message = exception["exception"]["description"]
+                    .as_str()
+                    .or_else(|| exception["text"].as_str())
+                    .unwrap_or("the page threw an exception");
+                anyhow::bail!("{}: {}", cmd.method, message);
+            }
+        }
+
+        Ok((
+            serde_json::json!({ "matched": matched, "expanded": expanded }),
+            None,
+        ))
+    }
+}
+
+/// `filename` under `dir`, refusing absolute paths and `..` that would leave it
+fn path_within(dir: &Path, filename: &str) -> Result<PathBuf> {
+    let mut path = dir.to_path_buf();
+    let mut named = false;
+    for component in Path::new(filename).components() {
+        match component {
+            Component::Normal(part) => {
+                path.push(part);
+                named = true;
+            }
+            Component::CurDir => {}
+            _ => anyhow::bail!(
+                "save_as '{}' must be a relative path inside the output directory",
+                filename
+            ),
+        }
+    }
+    if !named {
+        anyhow::bail!("save_as '{}' names no file", filename);
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_within() {
+        let dir = Path::new("/artifacts/inference/run-1");
+        assert_eq!(
+            path_within(dir, "./out/data.json").unwrap(),
+            dir.join("out/data.json")
+        );
+        for escaping in ["../../x", "out/../../x", "/etc/passwd", "", "."] {
+            assert!(path_within(dir, escaping).is_err(), "{}", escaping);
+        }
+    }
 }
@@ -0,0 +1,239 @@
+//! Failure Forensics
+//!
+//! When a command fails, the executor can collect what is needed to debug it without
+//! re-running the script: a screenshot and the HTML of the page, its recent console
+//! messages and network events, and the failing command as it was run. The bundle is
+//! zipped into a [`FrameStore`] and its location added to the command's error.
+
+use crate::browser::crash::{CrashMonitor, PageEvent, PageEventKind};
+use crate::frame_store::FrameStore;
+use anyhow::{Context, Result};
+use chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat;
+use chromiumoxide::page::{Page, ScreenshotParams};
+use serde_json::Value;
+use std::io::{Cursor, Write};
+use std::sync::Arc;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Number of recent network events included in a bundle by default
+pub const DEFAULT_NETWORK_EVENTS: usize = 20;
+
+/// Where and how much to collect when a command fails
+#[derive(Debug, Clone)]
+pub struct ForensicsOptions {
+    /// Store the zipped bundles are written to (under `forensics/`)
+    pub store: Arc<dyn FrameStore>,
+
+    /// Number of recent network events to include
+    pub network_events: usize,
+}
+
+impl ForensicsOptions {
+    pub fn new(store: Arc<dyn FrameStore>) -> Self {
+        Self {
+            store,
+            network_events: DEFAULT_NETWORK_EVENTS,
+        }
+    }
+}
+
+/// What was collected about a failed command
+#[derive(Debug, Clone, Default)]
+pub struct ForensicsBundle {
+    /// The failing command, with its parameters as run, the error, and the page URL
+    /// (`command.json`)
+    pub command: Value,
+
+    /// Screenshot of the page (`screenshot.png`, or `screenshot.jpg` taken after the
+    /// last load if the page crashed)
+    pub screenshot: Option<Screenshot>,
+
+    /// HTML of the page (`page.html`)
+    pub html: Option<String>,
+
+    /// Recent console messages and uncaught exceptions, oldest first (`console.json`)
+    pub console: Vec<PageEvent>,
+
+    /// Recent network events, oldest first (`network.json`)
+    pub network: Vec<PageEvent>,
+}
+
+/// An encoded screenshot
+#[derive(Debug, Clone)]
+pub struct Screenshot {
+    /// File extension of the image format (`png`, `jpg`)
+    pub extension: &'static str,
+
+    pub data: Vec<u8>,
+}
+
+impl ForensicsBundle {
+    /// Collect a bundle from `page`, which a command just failed on
+    ///
+    /// Console and network events come from the page's crash monitor, if the driver
+    /// has one. Anything that can't be collected (from a crashed page, say) is left
+    /// out of the bundle.
+    pub async fn collect(
+        page: &Page,
+        monitor: Option<&CrashMonitor>,
+        mut command: Value,
+        network_events: usize,
+    ) -> Self {
+        let crash = monitor.and_then(|monitor| monitor.report());
+        let url = match &crash {
+            Some(report) => report.url.clone(),
+            None => page.url().await.ok().flatten(),
+        };
+        command["url"] = url.into();
+        command["timestamp"] = chrono::Utc::now().to_rfc3339().into();
+
+        let screenshot = match crash {
+            Some(report) => report.screenshot.map(|data| Screenshot {
+                extension: "jpg",
+                data,
+            }),
+            None => page
+                .screenshot(
+                    ScreenshotParams::builder()
+                        .format(CaptureScreenshotFormat::Png)
+                        .build(),
+                )
+                .await
+                .ok()
+                .map(|data| Screenshot {
+                    extension: "png",
+                    data,
+                }),
+        };
+        let html = if monitor.is_some_and(|monitor| monitor.is_crashed()) {
+            None
+        } else {
+            page.content().await.ok()
+        };
+
+        let (console, network): (Vec<PageEvent>, Vec<PageEvent>) = monitor
+            .map(|monitor| monitor.recent_events())
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|event| {
+                matches!(
+                    event.kind,
+                    PageEventKind::Console | PageEventKind::Exception
+                )
+            });
+        let network = network[network.len().saturating_sub(network_events)..].to_vec();
+
+        Self {
+            command,
+            screenshot,
+            html,
+            console,
+            network,
+        }
+    }
+
+    /// The bundle as a zip archive
+    pub fn to_zip(&self) -> Result<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let mut add = |name: &str, data: &[u8]| -> Result<()> {
+            zip.start_file(name, options)?;
+            zip.write_all(data)?;
+            Ok(())
+        };
+        add("command.json", &serde_json::to_vec_pretty(&self.command)?)?;
+        if let Some(screenshot) = &self.screenshot {
+            add(
+                &format!("screenshot.{}", screenshot.extension),
+                &screenshot.data,
+            )?;
+        }
+        if let Some(html) = &self.html {
+            add("page.html", html.as_bytes())?;
+        }
+        add("console.json", &serde_json::to_vec_pretty(&self.console)?)?;
+        add("network.json", &serde_json::to_vec_pretty(&self.network)?)?;
+
+        Ok(zip
+            .finish()
+            .context("Failed to write forensics bundle")?
+            .into_inner())
+    }
+
+    /// Zip the bundle into `store` under `key` and return its location
+    pub async fn save(&self, store: &dyn FrameStore, key: &str) -> Result<String> {
+        store.put(key, self.to_zip()?).await
+    }
+}
+
+/// Store key of the bundle for a failed step of `script`
+pub fn bundle_key(script: &str, step: usize) -> String {
+    let script: String = script
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!(
+        "forensics/{}-step-{}-{}.zip",
+        script,
+        step,
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Read;
+
+    #[test]
+    fn test_bundle_zip() {
+        let bundle = ForensicsBundle {
+            command: json!({ "step": 2, "method": "Page.navigate", "params": { "url": "x" } }),
+            screenshot: Some(Screenshot {
+                extension: "png",
+                data: vec![0x89, b'P', b'N', b'G'],
+            }),
+            html: Some("<html></html>".to_string()),
+            console: Vec::new(),
+            network: Vec::new(),
+        };
+
+        let zip = bundle.to_zip().unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(zip)).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "command.json",
+                "console.json",
+                "network.json",
+                "page.html",
+                "screenshot.png"
+            ]
+        );
+
+        let mut command = String::new();
+        archive
+            .by_name("command.json")
+            .unwrap()
+            .read_to_string(&mut command)
+            .unwrap();
+        let command: Value = serde_json::from_str(&command).unwrap();
+        assert_eq!(command["method"], "Page.navigate");
+    }
+
+    #[test]
+    fn test_bundle_key() {
+        let key = bundle_key("checkout flow/v2", 3);
+        assert!(
+            key.starts_with("forensics/checkout-flow-v2-step-3-"),
+            "{}",
+            key
+        );
+        assert!(key.ends_with(".zip"));
+    }
+}
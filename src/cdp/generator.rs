@@ -1,18 +1,117 @@
 //! CDP Script Generator using Claude CLI
 //!
 //! This module handles generating CDP scripts from natural language using Claude.
+//!
+//! Every call's tokens and cost (as the CLI reports them) are returned as a
+//! [`GenerationUsage`] and added to the generator's running total. A
+//! [`GenerationBudget`] caps that total: once it's reached, generation is refused.
+//! Calls already in flight still finish, so the total can overshoot by one call.
+//!
+//! With an [`ExampleStore`], the prompt also includes the user's examples most
+//! relevant to each request.
 
-use super::claude_prompt::{generate_cdp_script_prompt, validate_generated_script};
+use super::claude_prompt::{generate_cdp_script_prompt_with_examples, validate_generated_script};
+use super::examples::ExampleStore;
 use super::CdpScript;
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::process::Stdio;
+use std::sync::Mutex;
 use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
+use utoipa::ToSchema;
+
+/// Tokens and cost of one or more generation calls
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GenerationUsage {
+    /// Number of calls to the provider
+    pub calls: u32,
+
+    /// Prompt tokens
+    pub input_tokens: u64,
+
+    /// Completion tokens
+    pub output_tokens: u64,
+
+    /// Prompt tokens written to or read from the provider's prompt cache
+    #[serde(default)]
+    pub cache_tokens: u64,
+
+    /// Cost in US dollars, as reported by the provider (0 if it doesn't report one)
+    pub cost_usd: f64,
+}
+
+impl GenerationUsage {
+    /// Usage of one call, from the Claude CLI's JSON output
+    ///
+    /// Missing fields count as zero; older CLIs report `cost_usd` instead of
+    /// `total_cost_usd`.
+    pub fn from_claude_output(output: &serde_json::Value) -> Self {
+        let usage = &output["usage"];
+        let tokens = |key: &str| usage[key].as_u64().unwrap_or(0);
+        Self {
+            calls: 1,
+            input_tokens: tokens("input_tokens"),
+            output_tokens: tokens("output_tokens"),
+            cache_tokens: tokens("cache_creation_input_tokens") + tokens("cache_read_input_tokens"),
+            cost_usd: output["total_cost_usd"]
+                .as_f64()
+                .or_else(|| output["cost_usd"].as_f64())
+                .unwrap_or(0.0),
+        }
+    }
+
+    /// Every token counted: input, output, and cache
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_tokens
+    }
+}
+
+impl std::ops::AddAssign for GenerationUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.calls += other.calls;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_tokens += other.cache_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+/// Limits on a generator's total usage (None = unlimited)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GenerationBudget {
+    /// Most US dollars to spend
+    pub max_cost_usd: Option<f64>,
+
+    /// Most tokens to use ([`GenerationUsage::total_tokens`])
+    pub max_tokens: Option<u64>,
+}
+
+impl GenerationBudget {
+    /// Why `usage` has used up this budget, or None if there's some left
+    pub fn exhausted_by(&self, usage: &GenerationUsage) -> Option<String> {
+        if let Some(max) = self.max_cost_usd.filter(|max| usage.cost_usd >= *max) {
+            return Some(format!(
+                "spent ${:.4} of the ${:.4} budget",
+                usage.cost_usd, max
+            ));
+        }
+        self.max_tokens
+            .filter(|max| usage.total_tokens() >= *max)
+            .map(|max| format!("used {} of the {} token budget", usage.total_tokens(), max))
+    }
+}
 
 /// CDP Script Generator using Claude CLI
 pub struct CdpScriptGenerator {
     claude_path: String,
     model: Option<String>,
+    budget: GenerationBudget,
+    /// Usage of every call so far
+    usage: Mutex<GenerationUsage>,
+    examples: ExampleStore,
+    /// Most examples added to a prompt
+    max_examples: usize,
 }
 
 impl CdpScriptGenerator {
@@ -21,6 +120,10 @@ impl CdpScriptGenerator {
         Self {
             claude_path: "claude".to_string(),
             model: None,
+            budget: GenerationBudget::default(),
+            usage: Mutex::new(GenerationUsage::default()),
+            examples: ExampleStore::default(),
+            max_examples: 0,
         }
     }
 
@@ -36,6 +139,29 @@ impl CdpScriptGenerator {
         self
     }
 
+    /// Add up to `max_examples` of the `examples` most relevant to each request to its prompt
+    pub fn with_examples(mut self, examples: ExampleStore, max_examples: usize) -> Self {
+        self.examples = examples;
+        self.max_examples = max_examples;
+        self
+    }
+
+    /// Refuse to generate once total usage reaches `budget`
+    pub fn with_budget(mut self, budget: GenerationBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// The generator's budget
+    pub fn budget(&self) -> GenerationBudget {
+        self.budget
+    }
+
+    /// Total usage of every call so far, including calls whose script failed validation
+    pub fn usage(&self) -> GenerationUsage {
+        *self.usage.lock().unwrap()
+    }
+
     /// Generate a CDP script from a natural language description
     ///
     /// # Arguments
@@ -48,21 +174,32 @@ impl CdpScriptGenerator {
     /// * If Claude CLI is not available
     /// * If Claude generates invalid JSON
     /// * If generated script fails validation
+    /// * If the budget is used up
     pub async fn generate(&self, description: &str) -> Result<CdpScript> {
-        // Generate prompt
-        let prompt = generate_cdp_script_prompt(description);
+        Ok(self.generate_with_usage(description).await?.0)
+    }
 
-        // Call Claude CLI
-        let response = self.call_claude(&prompt).await?;
+    /// Like [`generate`](Self::generate), also returning the call's usage
+    pub async fn generate_with_usage(
+        &self,
+        description: &str,
+    ) -> Result<(CdpScript, GenerationUsage)> {
+        // Generate prompt, with the user's examples of similar tasks
+        let examples = self.examples.relevant(description, self.max_examples);
+        if !examples.is_empty() {
+            let names: Vec<&str> = examples.iter().map(|e| e.script.name.as_str()).collect();
+            tracing::debug!(examples = ?names, "Adding examples to the prompt");
+        }
+        let prompt = generate_cdp_script_prompt_with_examples(description, &examples);
 
-        // Clean response (remove markdown code blocks if present)
-        let json = self.clean_response(&response);
+        // Call Claude CLI
+        let (json, usage) = self.ask(&prompt).await?;
 
         // Validate and parse
         let script = validate_generated_script(&json)
             .map_err(|e| anyhow::anyhow!("Validation failed: {}", e))?;
 
-        Ok(script)
+        Ok((script, usage))
     }
 
     /// Generate with retry on failure
@@ -77,9 +214,11 @@ impl CdpScriptGenerator {
             match self.generate(description).await {
                 Ok(script) => return Ok(script),
                 Err(e) => {
-                    eprintln!(
+                    tracing::warn!(
                         "Generation attempt {}/{} failed: {}",
-                        attempt, max_retries, e
+                        attempt,
+                        max_retries,
+                        e
                     );
                     last_error = Some(e);
 
@@ -94,8 +233,26 @@ impl CdpScriptGenerator {
         Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Generation failed")))
     }
 
-    /// Call Claude CLI with a prompt
-    async fn call_claude(&self, prompt: &str) -> Result<String> {
+    /// Send `prompt` to Claude, returning its answer without markdown fences and the
+    /// call's usage, which counts toward the budget
+    pub(crate) async fn ask(&self, prompt: &str) -> Result<(String, GenerationUsage)> {
+        if let Some(reason) = self.budget.exhausted_by(&self.usage()) {
+            anyhow::bail!("Generation budget exhausted: {}", reason);
+        }
+
+        let (response, usage) = self.call_claude(prompt).await?;
+        *self.usage.lock().unwrap() += usage;
+        tracing::debug!(
+            tokens = usage.total_tokens(),
+            cost_usd = usage.cost_usd,
+            "Generation call finished"
+        );
+
+        Ok((self.clean_response(&response), usage))
+    }
+
+    /// Call Claude CLI with a prompt, returning its text and usage
+    async fn call_claude(&self, prompt: &str) -> Result<(String, GenerationUsage)> {
         // Build command
         let mut cmd = Command::new(&self.claude_path);
         cmd.arg("--print") // Non-interactive mode
@@ -148,7 +305,10 @@ impl CdpScriptGenerator {
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Claude response missing 'text' field"))?;
 
-        Ok(text.to_string())
+        Ok((
+            text.to_string(),
+            GenerationUsage::from_claude_output(&response),
+        ))
     }
 
     /// Clean Claude's response (remove markdown formatting if present)
@@ -201,6 +361,60 @@ mod tests {
         assert_eq!(cleaned, r#"{"name": "test"}"#);
     }
 
+    #[test]
+    fn test_usage_and_budget() {
+        let output = serde_json::json!({
+            "text": "{}",
+            "total_cost_usd": 0.0125,
+            "usage": {
+                "input_tokens": 1200,
+                "output_tokens": 300,
+                "cache_read_input_tokens": 500,
+            },
+        });
+        let usage = GenerationUsage::from_claude_output(&output);
+        assert_eq!(usage.calls, 1);
+        assert_eq!(usage.total_tokens(), 2000);
+        assert_eq!(usage.cost_usd, 0.0125);
+
+        let mut total = usage;
+        total += usage;
+        assert_eq!(total.calls, 2);
+        assert_eq!(total.output_tokens, 600);
+
+        let budget = GenerationBudget {
+            max_cost_usd: Some(0.02),
+            max_tokens: None,
+        };
+        assert_eq!(budget.exhausted_by(&usage), None);
+        assert!(budget.exhausted_by(&total).unwrap().contains("$0.0250"));
+        let tokens = GenerationBudget {
+            max_cost_usd: None,
+            max_tokens: Some(4000),
+        };
+        assert!(tokens
+            .exhausted_by(&total)
+            .unwrap()
+            .contains("4000 of the 4000"));
+        assert_eq!(GenerationBudget::default().exhausted_by(&total), None);
+    }
+
+    #[tokio::test]
+    async fn test_generation_refused_over_budget() {
+        let generator = CdpScriptGenerator::new()
+            .with_claude_path("/nonexistent/claude".to_string())
+            .with_budget(GenerationBudget {
+                max_cost_usd: Some(0.0),
+                max_tokens: None,
+            });
+        let err = generator.generate("take a screenshot").await.unwrap_err();
+        assert!(
+            err.to_string().starts_with("Generation budget exhausted"),
+            "{}",
+            err
+        );
+    }
+
     // Note: Integration tests for generation are in tests/cdp_generator_test.rs
     // They require external Claude CLI and are excluded from CI
 }
@@ -0,0 +1,41 @@
+//! Vision Judges for `Verify.goal`
+//!
+//! When checking a page's text can't settle whether a run reached its goal, the
+//! executor asks a [`GoalJudge`] to look at a screenshot. [`CdpScriptGenerator`]
+//! is one: it asks Claude (which reads the screenshot file) for a verdict, counting
+//! the call toward the generator's usage and budget.
+
+use super::claude_prompt::verify_goal_prompt;
+use super::generator::CdpScriptGenerator;
+use crate::model::goal::{GoalVerdict, PageEvidence};
+use futures::future::BoxFuture;
+use std::path::Path;
+
+/// Decides from a screenshot whether a page meets a goal
+pub trait GoalJudge: Send + Sync {
+    /// Verdict on `goal` for the page in `screenshot` (a PNG file), with the page's
+    /// URL, title, and text as context
+    fn judge<'a>(
+        &'a self,
+        goal: &'a str,
+        page: &'a PageEvidence,
+        screenshot: &'a Path,
+    ) -> BoxFuture<'a, anyhow::Result<GoalVerdict>>;
+}
+
+impl GoalJudge for CdpScriptGenerator {
+    fn judge<'a>(
+        &'a self,
+        goal: &'a str,
+        page: &'a PageEvidence,
+        screenshot: &'a Path,
+    ) -> BoxFuture<'a, anyhow::Result<GoalVerdict>> {
+        Box::pin(async move {
+            let screenshot = std::path::absolute(screenshot)?;
+            let prompt = verify_goal_prompt(goal, page, &screenshot);
+            let (answer, usage) = self.ask(&prompt).await?;
+            tracing::debug!(tokens = usage.total_tokens(), goal, "Goal judged");
+            GoalVerdict::from_judgement(goal, &answer)
+        })
+    }
+}
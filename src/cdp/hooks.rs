@@ -0,0 +1,54 @@
+//! Step Hooks
+//!
+//! Callbacks around each command the executor runs, so applications embedding the
+//! executor can update their UI, log, or capture extra artifacts per step without
+//! re-implementing the execution loop.
+
+use super::script::{CdpCommand, CdpScript, CommandResult};
+use chromiumoxide::page::Page;
+use futures::future::BoxFuture;
+
+/// The command a hook is called for
+pub struct StepContext<'a> {
+    /// Step number (1-indexed)
+    pub step: usize,
+
+    /// The command being run
+    pub command: &'a CdpCommand,
+
+    /// The script the command belongs to
+    pub script: &'a CdpScript,
+
+    /// The page the command runs against
+    pub page: &'a Page,
+}
+
+/// Called before and after every command the executor runs
+///
+/// All methods do nothing by default. The executor waits for each hook, so slow
+/// hooks slow the script down. Hooks can't fail a command, so they handle (or log)
+/// their own errors. Commands skipped after a cancellation don't call hooks.
+pub trait StepHooks: Send + Sync {
+    /// Before the command runs
+    fn on_step_start<'a>(&'a self, _step: &'a StepContext<'a>) -> BoxFuture<'a, ()> {
+        Box::pin(async {})
+    }
+
+    /// After the command succeeded
+    fn on_step_success<'a>(
+        &'a self,
+        _step: &'a StepContext<'a>,
+        _result: &'a CommandResult,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async {})
+    }
+
+    /// After the command failed (the script stops after this)
+    fn on_step_failure<'a>(
+        &'a self,
+        _step: &'a StepContext<'a>,
+        _result: &'a CommandResult,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async {})
+    }
+}
@@ -3,16 +3,41 @@
 //! This module provides runtime interpretation of CDP scripts generated by Claude.
 //! Scripts are stored as JSON files and executed dynamically without compilation.
 
+pub mod cache;
+pub mod checkpoint;
 pub mod claude_prompt;
+pub mod examples;
 pub mod executor;
+pub mod forensics;
 pub mod generator;
+pub mod goal;
+pub mod hooks;
+pub mod replay;
 pub mod script;
-pub mod validation;
 
-pub use claude_prompt::{generate_cdp_script_prompt, validate_generated_script};
-pub use executor::CdpExecutor;
-pub use generator::CdpScriptGenerator;
+pub use crate::model::goal::{GoalVerdict, VerificationMethod, VerifyMode};
+pub use crate::model::{builder, diff, lint, narration, summary, validation, version};
+
+pub use builder::{CdpScriptBuilder, ImageFormat, ScriptSteps};
+pub use cache::{CachedScript, ScriptCache};
+pub use checkpoint::ExecutionCheckpoint;
+pub use claude_prompt::{
+    generate_cdp_script_prompt, generate_cdp_script_prompt_with_examples, validate_generated_script,
+};
+pub use diff::ScriptDiff;
+pub use examples::{ExampleStore, ScriptExample};
+pub use executor::{CdpExecutor, ExecutionOptions, FrameCapture};
+pub use forensics::{ForensicsBundle, ForensicsOptions};
+pub use generator::{CdpScriptGenerator, GenerationBudget, GenerationUsage};
+pub use goal::GoalJudge;
+pub use hooks::{StepContext, StepHooks};
+pub use lint::{CdpLinter, LintFix, LintRule, LintWarning, PatchOperation};
+pub use narration::narrate;
+pub use replay::{CdpTrace, TraceEntry, TraceRecorder};
 pub use script::{CdpCommand, CdpScript, CommandResult, CommandStatus, ExecutionReport};
+pub use summary::{ExecutionSummary, KeyFrame};
 pub use validation::{
-    CdpValidator, ErrorLocation, ParamType, ValidationError, ValidationErrorType, ValidationResult,
+    CdpValidator, ErrorLocation, FixedScript, ParamType, ScriptFix, ValidationError,
+    ValidationErrorType, ValidationResult,
 };
+pub use version::{ScriptVersion, VersionBump};
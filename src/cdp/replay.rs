@@ -0,0 +1,247 @@
+//! Recorded Traces and Replay
+//!
+//! A [`TraceRecorder`] (a [`StepHooks`]) records every command a real run sends and
+//! what came back: the response, or the error. [`CdpExecutor::from_trace`] replays
+//! the trace without a browser, answering each command with its recorded response,
+//! so executor and agent logic can be tested in CI quickly and without flakes
+//! against realistic protocol traffic.
+//!
+//! Replay is strict: a command whose method or parameters differ from the next
+//! recorded one fails with a "Replay diverged" error, as does running past the end
+//! of the trace.
+//!
+//! [`CdpExecutor::from_trace`]: super::CdpExecutor::from_trace
+
+use super::hooks::{StepContext, StepHooks};
+use super::script::{CdpCommand, CommandResult};
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Commands of a run and what they returned, in the order they ran
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CdpTrace {
+    /// Name of the script that was recorded
+    pub script: String,
+
+    /// When the recording started (RFC 3339)
+    pub recorded: String,
+
+    pub entries: Vec<TraceEntry>,
+}
+
+/// A command and its outcome
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraceEntry {
+    pub method: String,
+
+    /// Parameters as sent
+    pub params: Value,
+
+    /// Response, if the command succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<Value>,
+
+    /// Error message, if it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// File the command saved its output to, if any (the file itself isn't recorded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved_file: Option<String>,
+}
+
+impl CdpTrace {
+    /// Load a trace from a JSON file
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read trace {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Invalid trace {}", path.display()))
+    }
+
+    /// Write the trace to a JSON file
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .context("Failed to create trace directory")?;
+        }
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?)
+            .await
+            .context("Failed to write trace")
+    }
+}
+
+/// Step hooks recording the commands of a run into a [`CdpTrace`]
+#[derive(Debug, Default)]
+pub struct TraceRecorder {
+    trace: Mutex<CdpTrace>,
+}
+
+impl TraceRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The commands recorded so far
+    pub fn trace(&self) -> CdpTrace {
+        self.trace.lock().unwrap().clone()
+    }
+
+    fn record(&self, step: &StepContext<'_>, result: &CommandResult) {
+        let mut trace = self.trace.lock().unwrap();
+        if trace.entries.is_empty() {
+            trace.script = step.script.name.clone();
+            trace.recorded = chrono::Utc::now().to_rfc3339();
+        }
+        trace.entries.push(TraceEntry {
+            method: step.command.method.clone(),
+            params: step.command.params.clone(),
+            response: result.response.clone(),
+            error: result.error.clone(),
+            saved_file: result.saved_file.clone(),
+        });
+    }
+}
+
+impl StepHooks for TraceRecorder {
+    fn on_step_success<'a>(
+        &'a self,
+        step: &'a StepContext<'a>,
+        result: &'a CommandResult,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async move { self.record(step, result) })
+    }
+
+    fn on_step_failure<'a>(
+        &'a self,
+        step: &'a StepContext<'a>,
+        result: &'a CommandResult,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async move { self.record(step, result) })
+    }
+}
+
+/// A trace being replayed, answering commands in order
+#[derive(Debug)]
+pub(crate) struct TraceReplay {
+    trace: CdpTrace,
+    next: Mutex<usize>,
+}
+
+impl TraceReplay {
+    pub(crate) fn new(trace: CdpTrace) -> Self {
+        Self {
+            trace,
+            next: Mutex::new(0),
+        }
+    }
+
+    /// The recorded outcome of `cmd`, which must be the next command in the trace
+    pub(crate) fn answer(&self, cmd: &CdpCommand) -> Result<(Value, Option<String>)> {
+        let mut next = self.next.lock().unwrap();
+        let Some(entry) = self.trace.entries.get(*next) else {
+            anyhow::bail!(
+                "Replay diverged: {} ran after the {} recorded commands",
+                cmd.method,
+                self.trace.entries.len()
+            );
+        };
+        if entry.method != cmd.method || entry.params != cmd.params {
+            anyhow::bail!(
+                "Replay diverged at recorded command {}: recorded {} {}, got {} {}",
+                *next + 1,
+                entry.method,
+                entry.params,
+                cmd.method,
+                cmd.params
+            );
+        }
+        *next += 1;
+
+        match &entry.error {
+            Some(error) => Err(anyhow::Error::msg(error.clone())),
+            None => Ok((
+                entry.response.clone().unwrap_or(Value::Null),
+                entry.saved_file.clone(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn command(method: &str, params: Value) -> CdpCommand {
+        CdpCommand {
+            method: method.to_string(),
+            params,
+            save_as: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_replay_answers_in_order() {
+        let replay = TraceReplay::new(CdpTrace {
+            script: "test".to_string(),
+            recorded: String::new(),
+            entries: vec![
+                TraceEntry {
+                    method: "Page.navigate".to_string(),
+                    params: json!({ "url": "https://example.com" }),
+                    response: Some(json!({ "frameId": "F1" })),
+                    error: None,
+                    saved_file: None,
+                },
+                TraceEntry {
+                    method: "Runtime.evaluate".to_string(),
+                    params: json!({ "expression": "boom()" }),
+                    response: None,
+                    error: Some("Runtime.evaluate failed".to_string()),
+                    saved_file: None,
+                },
+            ],
+        });
+
+        let navigate = command("Page.navigate", json!({ "url": "https://example.com" }));
+        let (response, _) = replay.answer(&navigate).unwrap();
+        assert_eq!(response["frameId"], "F1");
+
+        let evaluate = command("Runtime.evaluate", json!({ "expression": "boom()" }));
+        let error = replay.answer(&evaluate).unwrap_err();
+        assert_eq!(error.to_string(), "Runtime.evaluate failed");
+
+        let error = replay.answer(&navigate).unwrap_err();
+        assert!(
+            error.to_string().starts_with("Replay diverged"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_replay_diverged_params() {
+        let replay = TraceReplay::new(CdpTrace {
+            entries: vec![TraceEntry {
+                method: "Page.navigate".to_string(),
+                params: json!({ "url": "https://example.com" }),
+                response: Some(json!({})),
+                error: None,
+                saved_file: None,
+            }],
+            ..Default::default()
+        });
+        let other = command("Page.navigate", json!({ "url": "https://example.org" }));
+        let error = replay.answer(&other).unwrap_err().to_string();
+        assert!(error.contains("example.org"), "{}", error);
+    }
+}
@@ -0,0 +1,463 @@
+//! Change Detection Between Runs
+//!
+//! Compares the artifacts of two runs of the same script and summarizes what
+//! changed in a [`ChangeReport`]:
+//!
+//! - JSON files (extracted data, cookies) are diffed structurally. Arrays of objects
+//!   are matched by an identity field (`id`, `key`, `url`, `sku`, `name`, or `title`)
+//!   when one is present, so new rows and changed values (e.g. prices) are reported
+//!   per row rather than as a reshuffled list.
+//! - Screenshots (PNG/JPEG) are compared pixel by pixel; a change is flagged when
+//!   the fraction of differing pixels exceeds the configured threshold.
+//! - Any other file is compared byte for byte.
+//!
+//! Run reports (`report.json`) and earlier change reports are ignored.
+
+use crate::config::ChangeDetectionConfig;
+use anyhow::Context;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+use utoipa::ToSchema;
+
+/// Files written by the runner itself, excluded from comparison
+const IGNORED_FILES: &[&str] = &["report.json", CHANGES_FILE];
+
+/// File name the change report is written to inside the current run's directory
+pub const CHANGES_FILE: &str = "changes.json";
+
+/// Fields that identify a row in an array of objects, in order of preference
+const IDENTITY_KEYS: &[&str] = &["id", "key", "url", "sku", "name", "title"];
+
+/// Differences between a run and the one before it
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ChangeReport {
+    /// Run that was compared against
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_run: Option<String>,
+
+    /// Whether anything below was flagged
+    pub changed: bool,
+
+    /// Value-level changes in JSON artifacts
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub data: Vec<DataChange>,
+
+    /// Screenshots that differ by more than the threshold
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub visual: Vec<VisualChange>,
+
+    /// Artifacts that only exist in the current run
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files_added: Vec<String>,
+
+    /// Artifacts that only existed in the previous run
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files_removed: Vec<String>,
+
+    /// Other artifacts whose bytes differ
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files_changed: Vec<String>,
+}
+
+/// How a value changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    /// Present only in the current run (e.g. a new row)
+    Added,
+    /// Present only in the previous run
+    Removed,
+    /// Present in both with different values
+    Changed,
+}
+
+/// A single changed value in a JSON artifact
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct DataChange {
+    /// Artifact file, relative to the run directory
+    pub file: String,
+
+    /// Location of the value, e.g. `$.value[sku=A1].price`
+    pub path: String,
+
+    /// Kind of change
+    pub kind: ChangeKind,
+
+    /// Previous value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+
+    /// Current value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+}
+
+/// A screenshot that differs from the previous run
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct VisualChange {
+    /// Screenshot file, relative to the run directory
+    pub file: String,
+
+    /// Fraction of pixels that differ (0.0-1.0; 1.0 if the dimensions changed)
+    pub diff_ratio: f64,
+
+    /// Whether the image dimensions changed
+    pub dimensions_changed: bool,
+}
+
+impl ChangeReport {
+    fn finish(mut self) -> Self {
+        self.changed = !(self.data.is_empty()
+            && self.visual.is_empty()
+            && self.files_added.is_empty()
+            && self.files_removed.is_empty()
+            && self.files_changed.is_empty());
+        self
+    }
+}
+
+/// Compare the artifacts of two run directories
+///
+/// Blocking: reads and decodes every artifact, so call from `spawn_blocking` in
+/// async code.
+pub fn compare_runs(
+    previous: &Path,
+    current: &Path,
+    config: &ChangeDetectionConfig,
+) -> anyhow::Result<ChangeReport> {
+    let before = list_artifacts(previous)?;
+    let after = list_artifacts(current)?;
+    let mut report = ChangeReport::default();
+
+    for file in after.difference(&before) {
+        report.files_added.push(file.clone());
+    }
+    for file in before.difference(&after) {
+        report.files_removed.push(file.clone());
+    }
+
+    for file in before.intersection(&after) {
+        let old = std::fs::read(previous.join(file))
+            .with_context(|| format!("Failed to read {}", previous.join(file).display()))?;
+        let new = std::fs::read(current.join(file))
+            .with_context(|| format!("Failed to read {}", current.join(file).display()))?;
+        if old == new {
+            continue;
+        }
+
+        match extension(file).as_deref() {
+            Some("json") => match (
+                serde_json::from_slice::<Value>(&old),
+                serde_json::from_slice::<Value>(&new),
+            ) {
+                (Ok(old), Ok(new)) => report.data.extend(diff_json(file, &old, &new)),
+                _ => report.files_changed.push(file.clone()),
+            },
+            Some("png" | "jpg" | "jpeg") => match image_diff(&old, &new, config.pixel_tolerance) {
+                Ok((diff_ratio, dimensions_changed)) => {
+                    if diff_ratio > config.visual_threshold {
+                        report.visual.push(VisualChange {
+                            file: file.clone(),
+                            diff_ratio,
+                            dimensions_changed,
+                        });
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(file = %file, error = %e, "Could not decode screenshot for comparison");
+                    report.files_changed.push(file.clone());
+                }
+            },
+            _ => report.files_changed.push(file.clone()),
+        }
+    }
+
+    Ok(report.finish())
+}
+
+/// Artifact paths under `dir`, relative and `/`-separated
+fn list_artifacts(dir: &Path) -> anyhow::Result<BTreeSet<String>> {
+    fn walk(root: &Path, dir: &Path, files: &mut BTreeSet<String>) -> anyhow::Result<()> {
+        for entry in std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read run directory {}", dir.display()))?
+        {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(root, &path, files)?;
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                let relative: Vec<_> = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect();
+                let relative = relative.join("/");
+                if !IGNORED_FILES.contains(&relative.as_str()) {
+                    files.insert(relative);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = BTreeSet::new();
+    walk(dir, dir, &mut files)?;
+    Ok(files)
+}
+
+fn extension(file: &str) -> Option<String> {
+    Path::new(file)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+/// Structural diff of two JSON documents
+pub fn diff_json(file: &str, before: &Value, after: &Value) -> Vec<DataChange> {
+    let mut changes = Vec::new();
+    diff_value(file, "$", before, after, &mut changes);
+    changes
+}
+
+fn diff_value(file: &str, path: &str, before: &Value, after: &Value, out: &mut Vec<DataChange>) {
+    if before == after {
+        return;
+    }
+
+    let change = |path: String, kind, before: Option<&Value>, after: Option<&Value>| DataChange {
+        file: file.to_string(),
+        path,
+        kind,
+        before: before.cloned(),
+        after: after.cloned(),
+    };
+
+    match (before, after) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let child = format!("{}.{}", path, key);
+                match new.get(key) {
+                    Some(new_value) => diff_value(file, &child, old_value, new_value, out),
+                    None => out.push(change(child, ChangeKind::Removed, Some(old_value), None)),
+                }
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    let child = format!("{}.{}", path, key);
+                    out.push(change(child, ChangeKind::Added, None, Some(new_value)));
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => match identity_key(old, new) {
+            Some(key) => {
+                let id = |row: &Value| row[key].to_string();
+                for old_row in old {
+                    let child = format!("{}[{}={}]", path, key, display_id(&old_row[key]));
+                    match new.iter().find(|row| id(row) == id(old_row)) {
+                        Some(new_row) => diff_value(file, &child, old_row, new_row, out),
+                        None => out.push(change(child, ChangeKind::Removed, Some(old_row), None)),
+                    }
+                }
+                for new_row in new {
+                    if !old.iter().any(|row| id(row) == id(new_row)) {
+                        let child = format!("{}[{}={}]", path, key, display_id(&new_row[key]));
+                        out.push(change(child, ChangeKind::Added, None, Some(new_row)));
+                    }
+                }
+            }
+            None if old.len() == new.len() && !old.iter().chain(new).any(Value::is_object) => {
+                // Same-shaped scalar lists: report positional changes
+                for (i, (old_item, new_item)) in old.iter().zip(new).enumerate() {
+                    diff_value(file, &format!("{}[{}]", path, i), old_item, new_item, out);
+                }
+            }
+            None => {
+                // Rows without an identity: report membership changes
+                for (i, row) in new.iter().enumerate() {
+                    if !old.contains(row) {
+                        let child = format!("{}[{}]", path, i);
+                        out.push(change(child, ChangeKind::Added, None, Some(row)));
+                    }
+                }
+                for (i, row) in old.iter().enumerate() {
+                    if !new.contains(row) {
+                        let child = format!("{}[{}]", path, i);
+                        out.push(change(child, ChangeKind::Removed, Some(row), None));
+                    }
+                }
+            }
+        },
+        _ => out.push(change(
+            path.to_string(),
+            ChangeKind::Changed,
+            Some(before),
+            Some(after),
+        )),
+    }
+}
+
+/// First identity field present and unique in every row of both arrays
+fn identity_key(old: &[Value], new: &[Value]) -> Option<&'static str> {
+    let rows = || old.iter().chain(new);
+    if old.is_empty() || new.is_empty() || !rows().all(Value::is_object) {
+        return None;
+    }
+
+    IDENTITY_KEYS.iter().copied().find(|key| {
+        let unique = |rows: &[Value]| {
+            let ids: BTreeSet<String> = rows.iter().map(|row| row[*key].to_string()).collect();
+            ids.len() == rows.len()
+        };
+        rows().all(|row| row.get(*key).is_some_and(|v| !v.is_null())) && unique(old) && unique(new)
+    })
+}
+
+fn display_id(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Fraction of differing pixels between two encoded images, and whether their
+/// dimensions differ
+///
+/// A pixel differs when any RGBA channel differs by more than `tolerance`.
+fn image_diff(before: &[u8], after: &[u8], tolerance: u8) -> anyhow::Result<(f64, bool)> {
+    let before = image::load_from_memory(before)
+        .context("Failed to decode previous screenshot")?
+        .to_rgba8();
+    let after = image::load_from_memory(after)
+        .context("Failed to decode current screenshot")?
+        .to_rgba8();
+
+    if before.dimensions() != after.dimensions() {
+        return Ok((1.0, true));
+    }
+
+    let pixels = before.as_raw().len() / 4;
+    if pixels == 0 {
+        return Ok((0.0, false));
+    }
+    let differing = before
+        .as_raw()
+        .chunks_exact(4)
+        .zip(after.as_raw().chunks_exact(4))
+        .filter(|(a, b)| {
+            a.iter()
+                .zip(b.iter())
+                .any(|(x, y)| x.abs_diff(*y) > tolerance)
+        })
+        .count();
+
+    Ok((differing as f64 / pixels as f64, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_scalars_and_objects() {
+        let before = json!({ "title": "Store", "stock": 3, "old": true });
+        let after = json!({ "title": "Store", "stock": 5, "new": 1 });
+
+        let changes = diff_json("data.json", &before, &after);
+        assert_eq!(changes.len(), 3);
+        let stock = changes.iter().find(|c| c.path == "$.stock").unwrap();
+        assert_eq!(stock.kind, ChangeKind::Changed);
+        assert_eq!(stock.before, Some(json!(3)));
+        assert_eq!(stock.after, Some(json!(5)));
+        assert!(changes
+            .iter()
+            .any(|c| c.path == "$.old" && c.kind == ChangeKind::Removed));
+        assert!(changes
+            .iter()
+            .any(|c| c.path == "$.new" && c.kind == ChangeKind::Added));
+    }
+
+    #[test]
+    fn test_diff_keyed_rows() {
+        let before = json!({ "value": [
+            { "sku": "A1", "price": 10 },
+            { "sku": "B2", "price": 20 },
+        ]});
+        let after = json!({ "value": [
+            { "sku": "C3", "price": 30 },
+            { "sku": "A1", "price": 12 },
+            { "sku": "B2", "price": 20 },
+        ]});
+
+        let changes = diff_json("prices.json", &before, &after);
+        assert_eq!(
+            changes,
+            vec![
+                DataChange {
+                    file: "prices.json".to_string(),
+                    path: "$.value[sku=A1].price".to_string(),
+                    kind: ChangeKind::Changed,
+                    before: Some(json!(10)),
+                    after: Some(json!(12)),
+                },
+                DataChange {
+                    file: "prices.json".to_string(),
+                    path: "$.value[sku=C3]".to_string(),
+                    kind: ChangeKind::Added,
+                    before: None,
+                    after: Some(json!({ "sku": "C3", "price": 30 })),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_unkeyed_rows() {
+        let before = json!([["a", 1], ["b", 2]]);
+        let after = json!([["c", 3], ["a", 1], ["b", 2]]);
+        let changes = diff_json("rows.json", &before, &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "$[0]");
+        assert_eq!(changes[0].kind, ChangeKind::Added);
+
+        // Positional comparison for equal-length scalar lists
+        let changes = diff_json("list.json", &json!([1, 2, 3]), &json!([1, 4, 3]));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "$[1]");
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let root = std::env::temp_dir().join(format!(
+            "robert-changes-test-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let (previous, current) = (root.join("run-1"), root.join("run-2"));
+        for dir in [&previous, &current] {
+            std::fs::create_dir_all(dir).unwrap();
+            // Reports always differ and must not be flagged
+            std::fs::write(dir.join("report.json"), dir.display().to_string()).unwrap();
+        }
+        std::fs::write(previous.join("data.json"), r#"{"count": 1}"#).unwrap();
+        std::fs::write(current.join("data.json"), r#"{"count": 2}"#).unwrap();
+        std::fs::write(previous.join("same.txt"), "unchanged").unwrap();
+        std::fs::write(current.join("same.txt"), "unchanged").unwrap();
+        std::fs::write(previous.join("gone.txt"), "x").unwrap();
+        std::fs::write(current.join("new.txt"), "y").unwrap();
+
+        let report = compare_runs(&previous, &current, &ChangeDetectionConfig::default()).unwrap();
+        assert!(report.changed);
+        assert_eq!(report.data.len(), 1);
+        assert_eq!(report.data[0].path, "$.count");
+        assert_eq!(report.files_added, vec!["new.txt"]);
+        assert_eq!(report.files_removed, vec!["gone.txt"]);
+        assert!(report.files_changed.is_empty());
+
+        let report = compare_runs(&current, &current, &ChangeDetectionConfig::default()).unwrap();
+        assert!(!report.changed);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
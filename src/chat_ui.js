@@ -3,7 +3,7 @@
   'use strict';
 
   // Prevent multiple injections
-  if (window.__ROBERT_CHAT_UI_INJECTED__) {
+  if (window.__ROBERT_CHAT_UI_INJECTED__ || document.getElementById('robert-chat-container')) {
     return;
   }
   window.__ROBERT_CHAT_UI_INJECTED__ = true;
@@ -142,6 +142,100 @@
       margin-top: 4px;
     }
 
+    /* Rich content */
+    .robert-chat-content p,
+    .robert-chat-content ul {
+      margin: 0 0 6px;
+    }
+
+    .robert-chat-content ul {
+      padding-left: 18px;
+    }
+
+    .robert-chat-content pre {
+      margin: 0 0 6px;
+      padding: 6px 8px;
+      background: rgba(0,0,0,0.06);
+      border-radius: 4px;
+      overflow-x: auto;
+    }
+
+    .robert-chat-content code {
+      font-family: ui-monospace, Menlo, Consolas, monospace;
+      font-size: 12px;
+    }
+
+    .robert-chat-heading {
+      font-weight: 600;
+      margin-bottom: 4px;
+    }
+
+    .robert-chat-image {
+      display: block;
+      max-width: 100%;
+      margin-top: 6px;
+      border-radius: 4px;
+      border: 1px solid var(--border-color);
+    }
+
+    .robert-chat-actions {
+      display: flex;
+      flex-wrap: wrap;
+      gap: 6px;
+      margin-top: 8px;
+    }
+
+    .robert-chat-action {
+      background: white;
+      border: 1px solid var(--primary-color);
+      color: var(--primary-color);
+      border-radius: 6px;
+      padding: 4px 10px;
+      font-size: 13px;
+      cursor: pointer;
+    }
+
+    .robert-chat-action:disabled {
+      cursor: default;
+      opacity: 0.5;
+    }
+
+    .robert-chat-action.chosen {
+      background: var(--primary-color);
+      color: white;
+      opacity: 1;
+    }
+
+    .robert-chat-form {
+      display: flex;
+      flex-direction: column;
+      gap: 6px;
+      margin-top: 8px;
+    }
+
+    .robert-chat-form label {
+      display: flex;
+      flex-direction: column;
+      gap: 2px;
+      font-size: 12px;
+    }
+
+    .robert-chat-form label.checkbox {
+      flex-direction: row;
+      align-items: center;
+      gap: 6px;
+    }
+
+    .robert-chat-form input:not([type=checkbox]),
+    .robert-chat-form textarea,
+    .robert-chat-form select {
+      padding: 4px 6px;
+      border: 1px solid var(--border-color);
+      border-radius: 4px;
+      font-family: inherit;
+      font-size: 13px;
+    }
+
     #robert-chat-input-area {
       padding: 15px;
       border-top: 1px solid var(--border-color);
@@ -259,7 +353,9 @@
   // Chat state
   const chatState = {
     messages: [],
-    collapsed: false
+    collapsed: false,
+    // ID of the latest agent message waiting for a free-text reply
+    awaitingReply: null
   };
 
   // Get DOM elements
@@ -269,28 +365,229 @@
   const sendButton = document.getElementById('robert-chat-send');
   const toggleButton = document.getElementById('robert-chat-toggle');
 
-  // Add a message to the chat
-  function addMessage(text, sender = 'agent') {
+  // Show a message (text, plus optional markdown, images, and actions) in the sidebar
+  function renderMessage(message) {
     const messageDiv = document.createElement('div');
-    messageDiv.className = `robert-chat-message ${sender}`;
+    messageDiv.classList.add('robert-chat-message', message.sender === 'user' ? 'user' : 'agent');
 
-    const timestamp = new Date().toLocaleTimeString();
-    messageDiv.innerHTML = `
-      <div class="robert-chat-content">${escapeHtml(text)}</div>
-      <div class="robert-chat-timestamp">${timestamp}</div>
-    `;
+    const content = document.createElement('div');
+    content.className = 'robert-chat-content';
+    content.innerHTML = message.markdown ? renderMarkdown(message.text) : escapeHtml(message.text);
+    messageDiv.appendChild(content);
+
+    for (const image of message.images || []) {
+      if (!isSafeImageSource(image.src)) continue;
+      const img = document.createElement('img');
+      img.className = 'robert-chat-image';
+      img.src = image.src;
+      img.alt = image.alt || '';
+      messageDiv.appendChild(img);
+    }
+
+    if (message.actions && message.actions.length) {
+      messageDiv.appendChild(renderActions(message.actions, message.id));
+    }
+
+    if (message.form && message.form.length) {
+      messageDiv.appendChild(renderForm(message.form, message.id));
+    }
+
+    // The next user message answers the latest prompt
+    if (message.sender === 'user') {
+      chatState.awaitingReply = null;
+    } else if (message.awaits_reply && message.id) {
+      chatState.awaitingReply = message.id;
+    }
+
+    const timestamp = document.createElement('div');
+    timestamp.className = 'robert-chat-timestamp';
+    timestamp.textContent = new Date(message.timestamp).toLocaleTimeString();
+    messageDiv.appendChild(timestamp);
 
     messagesContainer.appendChild(messageDiv);
     messagesContainer.scrollTop = messagesContainer.scrollHeight;
 
-    chatState.messages.push({
-      text,
-      sender,
-      timestamp: Date.now()
+    chatState.messages.push(message);
+  }
+
+  // Store feedback for the agent, and in persistent mode hand it to the driver so it
+  // survives navigations
+  function pushFeedback(feedback) {
+    if (!window.__ROBERT_FEEDBACK__) {
+      window.__ROBERT_FEEDBACK__ = [];
+    }
+    window.__ROBERT_FEEDBACK__.push(feedback);
+
+    if (typeof window.__robertFeedbackRecord === 'function') {
+      window.__robertFeedbackRecord(JSON.stringify(feedback));
+    }
+  }
+
+  // Action buttons; a click is stored as feedback for the agent and disables the others
+  function renderActions(actions, messageId) {
+    const actionsDiv = document.createElement('div');
+    actionsDiv.className = 'robert-chat-actions';
+
+    for (const action of actions) {
+      const button = document.createElement('button');
+      button.className = 'robert-chat-action';
+      button.textContent = action.label;
+      button.addEventListener('click', () => {
+        for (const other of actionsDiv.querySelectorAll('button')) {
+          other.disabled = true;
+        }
+        button.classList.add('chosen');
+
+        pushFeedback({
+          type: 'choice',
+          correlationId: messageId || null,
+          actionId: action.id,
+          label: action.label,
+          timestamp: Date.now()
+        });
+      });
+      actionsDiv.appendChild(button);
+    }
+
+    return actionsDiv;
+  }
+
+  // Form fields with a submit button; submitting stores the values as feedback
+  function renderForm(fields, messageId) {
+    const form = document.createElement('form');
+    form.className = 'robert-chat-form';
+
+    for (const field of fields) {
+      const label = document.createElement('label');
+      let input;
+      if (field.kind === 'textarea') {
+        input = document.createElement('textarea');
+        input.rows = 3;
+      } else if (field.kind === 'select') {
+        input = document.createElement('select');
+        for (const option of field.options || []) {
+          const element = document.createElement('option');
+          element.value = option;
+          element.textContent = option;
+          input.appendChild(element);
+        }
+      } else {
+        input = document.createElement('input');
+        input.type = field.kind === 'checkbox' ? 'checkbox' : 'text';
+      }
+      input.name = field.name;
+      if (field.kind === 'checkbox') {
+        input.checked = field.value === 'true';
+        label.classList.add('checkbox');
+        label.append(input, field.label);
+      } else {
+        if (field.value != null) input.value = field.value;
+        label.append(field.label, input);
+      }
+      form.appendChild(label);
+    }
+
+    const submit = document.createElement('button');
+    submit.type = 'submit';
+    submit.className = 'robert-chat-action';
+    submit.textContent = 'Submit';
+    form.appendChild(submit);
+
+    form.addEventListener('submit', (e) => {
+      e.preventDefault();
+      const values = {};
+      for (const element of form.elements) {
+        if (!element.name) continue;
+        values[element.name] = element.type === 'checkbox' ? String(element.checked) : element.value;
+        element.disabled = true;
+      }
+      submit.disabled = true;
+      submit.classList.add('chosen');
+
+      pushFeedback({
+        type: 'form',
+        correlationId: messageId || null,
+        values,
+        timestamp: Date.now()
+      });
     });
 
-    // Store message for retrieval by the agent
-    storeMessage(text, sender);
+    return form;
+  }
+
+  // Images may only come from data URLs or the web, not javascript: and the like
+  function isSafeImageSource(src) {
+    return typeof src === 'string' &&
+      /^(data:image\/(png|jpeg|gif|webp);base64,|https?:\/\/)/i.test(src);
+  }
+
+  // Render a small, safe subset of markdown: code, emphasis, links, headings, and lists
+  function renderMarkdown(text) {
+    return escapeHtml(text)
+      .split('```')
+      .map((block, i) => i % 2 === 1
+        ? `<pre><code>${block.replace(/^[\w-]*\n/, '')}</code></pre>`
+        : renderMarkdownLines(block))
+      .join('');
+  }
+
+  function renderMarkdownLines(block) {
+    let html = '';
+    let inList = false;
+
+    for (const line of block.split('\n')) {
+      const item = line.match(/^\s*[-*] (.*)$/);
+      if (item && !inList) {
+        html += '<ul>';
+        inList = true;
+      } else if (!item && inList) {
+        html += '</ul>';
+        inList = false;
+      }
+
+      const heading = line.match(/^#{1,6} (.*)$/);
+      if (item) {
+        html += `<li>${renderInline(item[1])}</li>`;
+      } else if (heading) {
+        html += `<div class="robert-chat-heading">${renderInline(heading[1])}</div>`;
+      } else if (line.trim()) {
+        html += `<p>${renderInline(line)}</p>`;
+      }
+    }
+
+    if (inList) html += '</ul>';
+    return html;
+  }
+
+  // Inline markup on already-escaped text; link targets can't contain quotes, so they
+  // can't break out of the href attribute
+  function renderInline(line) {
+    return line
+      .replace(/`([^`]+)`/g, '<code>$1</code>')
+      .replace(/\*\*([^*]+)\*\*/g, '<strong>$1</strong>')
+      .replace(/\*([^*]+)\*/g, '<em>$1</em>')
+      .replace(/\[([^\]]+)\]\((https?:\/\/[^\s)"'<>]+)\)/g,
+        '<a href="$2" target="_blank" rel="noopener noreferrer">$1</a>');
+  }
+
+  // Show a message and store it for the agent
+  function postMessage(message) {
+    renderMessage(message);
+    storeMessage(message);
+  }
+
+  // Add a plain-text message to the chat
+  function addMessage(text, sender = 'agent') {
+    postMessage({ text, sender, timestamp: Date.now() });
+  }
+
+  // Show messages from before the last navigation without recording them again
+  function restoreMessages(messages) {
+    window.__ROBERT_CHAT_MESSAGES__ = window.__ROBERT_CHAT_MESSAGES__ || [];
+    for (const message of messages) {
+      renderMessage(message);
+      window.__ROBERT_CHAT_MESSAGES__.push(message);
+    }
   }
 
   // Escape HTML to prevent XSS
@@ -301,15 +598,16 @@
   }
 
   // Store message in a way the agent can retrieve it
-  function storeMessage(text, sender) {
+  function storeMessage(message) {
     if (!window.__ROBERT_CHAT_MESSAGES__) {
       window.__ROBERT_CHAT_MESSAGES__ = [];
     }
-    window.__ROBERT_CHAT_MESSAGES__.push({
-      text,
-      sender,
-      timestamp: Date.now()
-    });
+    window.__ROBERT_CHAT_MESSAGES__.push(message);
+
+    // In persistent mode, the driver keeps the history across navigations
+    if (typeof window.__robertChatRecord === 'function') {
+      window.__robertChatRecord(JSON.stringify(message));
+    }
   }
 
   // Send a message
@@ -317,9 +615,17 @@
     const text = inputArea.value.trim();
     if (!text) return;
 
+    const replyTo = chatState.awaitingReply;
     addMessage(text, 'user');
     inputArea.value = '';
 
+    pushFeedback({
+      type: 'message',
+      correlationId: replyTo,
+      text,
+      timestamp: Date.now()
+    });
+
     // Trigger custom event that the agent can listen to
     window.dispatchEvent(new CustomEvent('robert-chat-message', {
       detail: { text, sender: 'user', timestamp: Date.now() }
@@ -365,11 +671,8 @@
 
   // Submit feedback - store for agent to retrieve
   function submitFeedback(actionId, positive, originalRequest, agentName, comment, errorDescription) {
-    if (!window.__ROBERT_FEEDBACK__) {
-      window.__ROBERT_FEEDBACK__ = [];
-    }
-
-    const feedback = {
+    pushFeedback({
+      type: 'rating',
       actionId: actionId,
       positive: positive,
       comment: comment || null,
@@ -377,9 +680,7 @@
       originalRequest: originalRequest,
       errorDescription: errorDescription || null,
       timestamp: Date.now()
-    };
-
-    window.__ROBERT_FEEDBACK__.push(feedback);
+    });
     addMessage(positive ? 'Thank you for your feedback! 👍' : 'Feedback noted. The agent will learn from this. 👎', 'agent');
   }
 
@@ -408,6 +709,11 @@
   // Expose API for the agent to send messages to the chat
   window.__ROBERT_CHAT_API__ = {
     sendMessage: (text) => addMessage(text, 'agent'),
+    sendRichMessage: (message) => postMessage({
+      ...message,
+      sender: 'agent',
+      timestamp: message.timestamp || Date.now()
+    }),
     getMessages: () => window.__ROBERT_CHAT_MESSAGES__ || [],
     clearMessages: () => {
       messagesContainer.innerHTML = '';
@@ -422,8 +728,14 @@
     }
   };
 
-  // Send welcome message
-  addMessage('Chat UI loaded. You can provide feedback to the agent here.', 'agent');
+  // Restore the history after a navigation, or send the welcome message
+  const history = window.__ROBERT_CHAT_HISTORY__;
+  delete window.__ROBERT_CHAT_HISTORY__;
+  if (history && history.length) {
+    restoreMessages(history);
+  } else {
+    addMessage('Chat UI loaded. You can provide feedback to the agent here.', 'agent');
+  }
 
   console.log('[Robert Chat UI] Injected successfully');
 })();
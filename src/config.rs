@@ -0,0 +1,803 @@
+//! Layered Configuration
+//!
+//! Settings for the daemon and driver defaults are resolved in order of increasing
+//! precedence:
+//!
+//! 1. Built-in defaults
+//! 2. TOML config file (`--config`, `$ROBERT_CONFIG`, or `~/.config/robert/webdriver.toml`)
+//! 3. `ROBERT_*` environment variables (CI environments also imply headless + no-sandbox)
+//! 4. Command-line flags (applied by the binary after loading)
+//!
+//! ```toml
+//! [chrome]
+//! version = "128.0.x"
+//! headless = true
+//!
+//! [timeouts]
+//! navigation_secs = 45
+//!
+//! [server]
+//! port = 9669
+//! auth_token = "secret"
+//!
+//! [generator]
+//! provider = "claude"
+//! model = "sonnet"
+//!
+//! [library]
+//! dir = "./scripts"
+//!
+//! [[schedules]]
+//! name = "nightly-report"
+//! script = "report"
+//! cron = "30 2 * * *"
+//!
+//! [[webhooks]]
+//! url = "https://hooks.example.com/robert"
+//! secret = "signing-key"
+//!
+//! [changes]
+//! visual_threshold = 0.02
+//!
+//! [artifacts]
+//! root = "./artifacts"
+//! max_mb = 10240
+//! min_free_mb = 512
+//! ```
+
+use crate::browser::chrome::{ConnectionMode, DriverOptions};
+use crate::browser::consent::CookieConsent;
+use crate::browser::determinism::DeterministicRendering;
+use crate::browser::overlay::{OverlayHandling, OverlayPolicy};
+use crate::cdp::{CdpScriptGenerator, ExampleStore, GenerationBudget, ScriptCache};
+use crate::model::browser::VersionCheck;
+use crate::webhook::WebhookEvent;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable naming an explicit config file
+pub const CONFIG_ENV: &str = "ROBERT_CONFIG";
+
+/// Complete configuration for the daemon and driver
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Chrome launch settings
+    pub chrome: ChromeConfig,
+
+    /// Where screenshots, DOM snapshots, and reports are written
+    pub artifacts: ArtifactsConfig,
+
+    /// Operation timeouts
+    pub timeouts: TimeoutConfig,
+
+    /// HTTP server settings
+    pub server: ServerConfig,
+
+    /// Script generator settings
+    pub generator: GeneratorConfig,
+
+    /// Saved script library
+    pub library: LibraryConfig,
+
+    /// Recurring runs of library scripts
+    pub schedules: Vec<ScheduleConfig>,
+
+    /// Endpoints notified when runs finish
+    pub webhooks: Vec<WebhookConfig>,
+
+    /// Comparison of scheduled runs with their previous run
+    pub changes: ChangeDetectionConfig,
+}
+
+/// Chrome launch settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChromeConfig {
+    /// Chrome executable (None = auto-download Chrome for Testing)
+    pub path: Option<String>,
+
+    /// Run without a visible window
+    pub headless: bool,
+
+    /// Launch with `--no-sandbox` (Linux AppArmor workaround)
+    pub no_sandbox: bool,
+
+    /// Launch headless with the flags Chrome needs inside a container (implies
+    /// `headless` and `no_sandbox`)
+    pub container: bool,
+
+    /// Connect to an existing Chrome on this debug port instead of launching one
+    pub debug_port: Option<u16>,
+
+    /// Connect to a remote Chrome by DevTools WebSocket (or HTTP) URL instead of
+    /// launching one; takes precedence over `debug_port`
+    pub ws_url: Option<String>,
+
+    /// Cache directory for downloaded Chrome (None = `~/.cache/robert/chrome`)
+    pub download_dir: Option<PathBuf>,
+
+    /// Chrome for Testing version pin, e.g. `128.0.x` (None = any cached or current)
+    pub version: Option<String>,
+
+    /// Render pages deterministically (frozen time, no animations, fixed font and
+    /// scale settings) so screenshot hashes are stable across runs
+    pub deterministic: bool,
+
+    /// Treat 4xx/5xx responses to a navigation as errors
+    pub fail_on_http_error: bool,
+
+    /// Load pages despite certificate errors (self-signed test environments)
+    pub ignore_cert_errors: bool,
+
+    /// axe-core script (`axe.min.js`) injected by accessibility audits
+    pub axe_core_path: Option<PathBuf>,
+
+    /// Run a headed Chrome on an Xvfb virtual display when there's no display,
+    /// instead of falling back to headless
+    pub xvfb: bool,
+
+    /// What to do when Chrome is older than the executor supports: off, warn, or fail
+    pub version_check: VersionCheck,
+
+    /// Reject (or, failing that, accept) cookie consent banners after each navigation
+    pub dismiss_cookie_banners: bool,
+
+    /// What to do with pop-ups and interstitials covering the page after each
+    /// navigation: dismiss, hide, or report (None = leave them)
+    pub overlays: Option<OverlayPolicy>,
+}
+
+/// Artifact output settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArtifactsConfig {
+    /// Root directory for all artifacts
+    pub root: PathBuf,
+
+    /// Refuse to capture step frames once the artifacts under `root` reach this size
+    /// (None = no quota)
+    pub max_mb: Option<u64>,
+
+    /// Refuse to capture step frames when the disk has less than this free (0 = don't check)
+    pub min_free_mb: u64,
+}
+
+impl Default for ArtifactsConfig {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("./artifacts"),
+            max_mb: None,
+            min_free_mb: 256,
+        }
+    }
+}
+
+/// Operation timeouts, in seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeoutConfig {
+    /// Maximum time to launch or connect to Chrome
+    pub launch_secs: u64,
+
+    /// Maximum time to wait for a page load event
+    pub navigation_secs: u64,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            launch_secs: 60,
+            navigation_secs: 30,
+        }
+    }
+}
+
+/// HTTP server settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Port to listen on (localhost only)
+    pub port: u16,
+
+    /// Bearer token required on every endpoint except `/health` (None = no auth)
+    pub auth_token: Option<String>,
+
+    /// Number of Chrome sessions in the pool used by `/batch`
+    pub pool_size: usize,
+
+    /// Port of the gRPC server (requires the `grpc` feature; None = HTTP only)
+    pub grpc_port: Option<u16>,
+
+    /// Finished `/jobs` kept for polling before the oldest are dropped
+    pub max_finished_jobs: usize,
+
+    /// Runs kept in `/runs` (and `<artifacts.root>/runs`) before the oldest are deleted
+    pub max_runs: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            port: 9669,
+            auth_token: None,
+            pool_size: 4,
+            grpc_port: None,
+            max_finished_jobs: crate::jobs::DEFAULT_MAX_FINISHED,
+            max_runs: crate::runs::DEFAULT_MAX_RUNS,
+        }
+    }
+}
+
+/// Script generator settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratorConfig {
+    /// Generator backend (currently only "claude")
+    pub provider: String,
+
+    /// Model name passed to the provider (e.g. "sonnet")
+    pub model: Option<String>,
+
+    /// Path to the provider's CLI executable
+    pub cli_path: Option<String>,
+
+    /// Reuse the script generated for a prompt when the same prompt is sent for the same page
+    pub cache: bool,
+
+    /// How long a cached script is reused, in seconds
+    pub cache_ttl_secs: u64,
+
+    /// Keep cached scripts in `<artifacts.root>/script-cache` across restarts
+    pub cache_persist: bool,
+
+    /// Refuse to generate once this many US dollars have been spent since startup
+    pub budget_usd: Option<f64>,
+
+    /// Refuse to generate once this many tokens have been used since startup
+    pub budget_tokens: Option<u64>,
+
+    /// Directory of example scripts for similar tasks to show the generator (e.g. the
+    /// script library); see [`ExampleStore::load`]
+    pub examples_dir: Option<PathBuf>,
+
+    /// Most examples added to a prompt
+    pub max_examples: usize,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            provider: "claude".to_string(),
+            model: None,
+            cli_path: None,
+            cache: false,
+            cache_ttl_secs: 24 * 60 * 60,
+            cache_persist: false,
+            budget_usd: None,
+            budget_tokens: None,
+            examples_dir: None,
+            max_examples: 3,
+        }
+    }
+}
+
+/// Script library settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LibraryConfig {
+    /// Directory of named `<name>.json` scripts
+    pub dir: PathBuf,
+}
+
+impl Default for LibraryConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("./scripts"),
+        }
+    }
+}
+
+/// A recurring run of a library script
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    /// Unique schedule name (used in the API and artifact paths)
+    pub name: String,
+
+    /// Name of the library script to run
+    pub script: String,
+
+    /// Script version the schedule was written against (e.g. `"1.2"`); the script
+    /// only runs if its `version` is compatible (same major, at least this version)
+    #[serde(default)]
+    pub script_version: Option<String>,
+
+    /// Cron expression, evaluated in UTC (e.g. `"*/15 * * * *"` or `"@daily"`)
+    pub cron: String,
+
+    /// Set to false to keep the schedule in the file without running it
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A URL notified when a run finishes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Endpoint that receives a JSON POST per finished run
+    pub url: String,
+
+    /// Key for the HMAC-SHA256 `X-Robert-Signature` header (None = unsigned)
+    #[serde(default)]
+    pub secret: Option<String>,
+
+    /// Events to send (empty = all of completed, failed, cancelled)
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+
+    /// Include the paths of files written by the run
+    #[serde(default = "default_enabled")]
+    pub include_artifacts: bool,
+
+    /// Rewrite artifact paths under `artifacts.root` into links below this URL
+    #[serde(default)]
+    pub artifact_base_url: Option<String>,
+
+    /// Retries after the first failed attempt
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubled for each one after
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+}
+
+/// Change detection settings for scheduled runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChangeDetectionConfig {
+    /// Compare each scheduled run with the previous one
+    pub enabled: bool,
+
+    /// Fraction of differing pixels (0.0-1.0) above which a screenshot is flagged
+    pub visual_threshold: f64,
+
+    /// Per-channel difference (0-255) below which pixels count as unchanged
+    pub pixel_tolerance: u8,
+}
+
+impl Default for ChangeDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            visual_threshold: 0.01,
+            pixel_tolerance: 16,
+        }
+    }
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    1000
+}
+
+impl Config {
+    /// Load configuration from defaults, config file, and environment
+    ///
+    /// `path` takes precedence over `$ROBERT_CONFIG`; an explicitly named file must
+    /// exist. The default location is used only if present.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let explicit = path
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::var_os(CONFIG_ENV).map(PathBuf::from));
+
+        let mut config = match explicit {
+            Some(path) => Self::from_file(&path)?,
+            None => match Self::default_path().filter(|p| p.exists()) {
+                Some(path) => Self::from_file(&path)?,
+                None => Self::default(),
+            },
+        };
+
+        config.apply_env(|key| std::env::var(key).ok())?;
+        Ok(config)
+    }
+
+    /// Parse a TOML config file
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        Self::from_toml(&content).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// Parse configuration from a TOML string
+    pub fn from_toml(content: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Default config file location (`~/.config/robert/webdriver.toml`)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("robert").join("webdriver.toml"))
+    }
+
+    /// Override settings from `ROBERT_*` variables returned by `lookup`
+    ///
+    /// Takes a lookup function rather than reading the process environment
+    /// directly so overrides can be tested without mutating global state.
+    pub fn apply_env<F>(&mut self, lookup: F) -> anyhow::Result<()>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        fn parse<T: std::str::FromStr>(key: &str, value: String) -> anyhow::Result<T>
+        where
+            T::Err: std::fmt::Display,
+        {
+            value
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid value for {}: {}", key, e))
+        }
+
+        // Same CI detection as ChromeDriver::launch_auto
+        let is_ci = [
+            "CI",
+            "GITHUB_ACTIONS",
+            "GITLAB_CI",
+            "JENKINS_HOME",
+            "CIRCLECI",
+        ]
+        .iter()
+        .any(|key| lookup(key).is_some());
+        if is_ci {
+            self.chrome.headless = true;
+            self.chrome.no_sandbox = true;
+        }
+
+        if let Some(v) = lookup("ROBERT_CHROME_PATH") {
+            self.chrome.path = Some(v);
+        }
+        if let Some(v) = lookup("ROBERT_HEADLESS") {
+            self.chrome.headless = parse("ROBERT_HEADLESS", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_NO_SANDBOX") {
+            self.chrome.no_sandbox = parse("ROBERT_NO_SANDBOX", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_CONTAINER") {
+            self.chrome.container = parse("ROBERT_CONTAINER", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_DEBUG_PORT") {
+            self.chrome.debug_port = Some(parse("ROBERT_DEBUG_PORT", v)?);
+        }
+        if let Some(v) = lookup("ROBERT_WS_URL") {
+            self.chrome.ws_url = Some(v);
+        }
+        if let Some(v) = lookup("ROBERT_DOWNLOAD_DIR") {
+            self.chrome.download_dir = Some(PathBuf::from(v));
+        }
+        if let Some(v) = lookup("ROBERT_CHROME_VERSION") {
+            self.chrome.version = Some(v);
+        }
+        if let Some(v) = lookup("ROBERT_DETERMINISTIC") {
+            self.chrome.deterministic = parse("ROBERT_DETERMINISTIC", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_FAIL_ON_HTTP_ERROR") {
+            self.chrome.fail_on_http_error = parse("ROBERT_FAIL_ON_HTTP_ERROR", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_IGNORE_CERT_ERRORS") {
+            self.chrome.ignore_cert_errors = parse("ROBERT_IGNORE_CERT_ERRORS", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_AXE_CORE_PATH") {
+            self.chrome.axe_core_path = Some(PathBuf::from(v));
+        }
+        if let Some(v) = lookup("ROBERT_XVFB") {
+            self.chrome.xvfb = parse("ROBERT_XVFB", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_DISMISS_COOKIE_BANNERS") {
+            self.chrome.dismiss_cookie_banners = parse("ROBERT_DISMISS_COOKIE_BANNERS", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_OVERLAYS") {
+            self.chrome.overlays = Some(parse("ROBERT_OVERLAYS", v)?);
+        }
+        if let Some(v) = lookup("ROBERT_VERSION_CHECK") {
+            self.chrome.version_check = parse("ROBERT_VERSION_CHECK", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_ARTIFACT_ROOT") {
+            self.artifacts.root = PathBuf::from(v);
+        }
+        if let Some(v) = lookup("ROBERT_ARTIFACTS_MAX_MB") {
+            self.artifacts.max_mb = Some(parse("ROBERT_ARTIFACTS_MAX_MB", v)?);
+        }
+        if let Some(v) = lookup("ROBERT_ARTIFACTS_MIN_FREE_MB") {
+            self.artifacts.min_free_mb = parse("ROBERT_ARTIFACTS_MIN_FREE_MB", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_LAUNCH_TIMEOUT_SECS") {
+            self.timeouts.launch_secs = parse("ROBERT_LAUNCH_TIMEOUT_SECS", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_NAVIGATION_TIMEOUT_SECS") {
+            self.timeouts.navigation_secs = parse("ROBERT_NAVIGATION_TIMEOUT_SECS", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_PORT") {
+            self.server.port = parse("ROBERT_PORT", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_AUTH_TOKEN") {
+            self.server.auth_token = Some(v);
+        }
+        if let Some(v) = lookup("ROBERT_POOL_SIZE") {
+            self.server.pool_size = parse("ROBERT_POOL_SIZE", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_GENERATOR_PROVIDER") {
+            self.generator.provider = v;
+        }
+        if let Some(v) = lookup("ROBERT_GENERATOR_MODEL") {
+            self.generator.model = Some(v);
+        }
+        if let Some(v) = lookup("ROBERT_GENERATOR_BUDGET_USD") {
+            self.generator.budget_usd = Some(parse("ROBERT_GENERATOR_BUDGET_USD", v)?);
+        }
+        if let Some(v) = lookup("ROBERT_GENERATOR_BUDGET_TOKENS") {
+            self.generator.budget_tokens = Some(parse("ROBERT_GENERATOR_BUDGET_TOKENS", v)?);
+        }
+        if let Some(v) = lookup("ROBERT_GENERATOR_EXAMPLES_DIR") {
+            self.generator.examples_dir = Some(PathBuf::from(v));
+        }
+        if let Some(v) = lookup("ROBERT_SCRIPT_CACHE") {
+            self.generator.cache = parse("ROBERT_SCRIPT_CACHE", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_SCRIPT_CACHE_TTL_SECS") {
+            self.generator.cache_ttl_secs = parse("ROBERT_SCRIPT_CACHE_TTL_SECS", v)?;
+        }
+        if let Some(v) = lookup("ROBERT_LIBRARY_DIR") {
+            self.library.dir = PathBuf::from(v);
+        }
+
+        Ok(())
+    }
+
+    /// Connection mode described by the Chrome settings
+    pub fn connection_mode(&self) -> ConnectionMode {
+        if let Some(url) = &self.chrome.ws_url {
+            return ConnectionMode::WebSocketUrl(url.clone());
+        }
+        match self.chrome.debug_port {
+            Some(port) => ConnectionMode::DebugPort(port),
+            None if self.chrome.container => ConnectionMode::Container {
+                chrome_path: self.chrome.path.clone(),
+            },
+            None => ConnectionMode::Sandboxed {
+                chrome_path: self.chrome.path.clone(),
+                no_sandbox: self.chrome.no_sandbox,
+                headless: self.chrome.headless,
+            },
+        }
+    }
+
+    /// Driver options (download dir, version pin, and timeouts) for [`ChromeDriver::new_with_options`]
+    ///
+    /// [`ChromeDriver::new_with_options`]: crate::ChromeDriver::new_with_options
+    pub fn driver_options(&self) -> DriverOptions {
+        DriverOptions {
+            download_dir: self.chrome.download_dir.clone(),
+            chrome_version: self.chrome.version.clone(),
+            launch_timeout: Duration::from_secs(self.timeouts.launch_secs),
+            navigation_timeout: Duration::from_secs(self.timeouts.navigation_secs),
+            deterministic: self
+                .chrome
+                .deterministic
+                .then(DeterministicRendering::default),
+            fail_on_http_error: self.chrome.fail_on_http_error,
+            ignore_cert_errors: self.chrome.ignore_cert_errors,
+            axe_core_path: self.chrome.axe_core_path.clone(),
+            xvfb: self.chrome.xvfb,
+            version_check: self.chrome.version_check,
+            cookie_consent: self
+                .chrome
+                .dismiss_cookie_banners
+                .then(CookieConsent::default),
+            overlays: self.chrome.overlays.map(|policy| OverlayHandling {
+                policy,
+                ..Default::default()
+            }),
+            ..DriverOptions::default()
+        }
+    }
+
+    /// Build the configured script generator
+    pub fn generator(&self) -> anyhow::Result<CdpScriptGenerator> {
+        match self.generator.provider.as_str() {
+            "claude" => {
+                let mut generator = CdpScriptGenerator::new().with_budget(GenerationBudget {
+                    max_cost_usd: self.generator.budget_usd,
+                    max_tokens: self.generator.budget_tokens,
+                });
+                if let Some(path) = &self.generator.cli_path {
+                    generator = generator.with_claude_path(path.clone());
+                }
+                if let Some(model) = &self.generator.model {
+                    generator = generator.with_model(model.clone());
+                }
+                if let Some(dir) = &self.generator.examples_dir {
+                    let examples = ExampleStore::load(dir)?;
+                    generator = generator.with_examples(examples, self.generator.max_examples);
+                }
+                Ok(generator)
+            }
+            other => anyhow::bail!("Unknown generator provider '{}' (expected: claude)", other),
+        }
+    }
+
+    /// Build the generated-script cache, or None if `generator.cache` is off
+    pub fn script_cache(&self) -> anyhow::Result<Option<ScriptCache>> {
+        if !self.generator.cache {
+            return Ok(None);
+        }
+        let ttl = Duration::from_secs(self.generator.cache_ttl_secs);
+        let cache = if self.generator.cache_persist {
+            ScriptCache::open(&self.artifacts.root.join("script-cache"), ttl)?
+        } else {
+            ScriptCache::in_memory(ttl)
+        };
+        Ok(Some(cache))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_defaults() {
+        let config = Config::default();
+        assert_eq!(config.server.port, 9669);
+        assert_eq!(config.timeouts.navigation_secs, 30);
+        assert_eq!(config.generator.provider, "claude");
+        assert!(matches!(
+            config.connection_mode(),
+            ConnectionMode::Sandboxed {
+                headless: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_partial_toml() {
+        let config = Config::from_toml(
+            r#"
+            [chrome]
+            headless = true
+
+            [server]
+            auth_token = "secret"
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.chrome.headless);
+        assert_eq!(config.server.auth_token.as_deref(), Some("secret"));
+        // Unspecified fields keep their defaults
+        assert_eq!(config.server.port, 9669);
+        assert_eq!(config.timeouts.launch_secs, 60);
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let mut config = Config::from_toml("[server]\nport = 8000\n").unwrap();
+        let env: HashMap<&str, &str> = [
+            ("ROBERT_PORT", "8100"),
+            ("ROBERT_DEBUG_PORT", "9222"),
+            ("ROBERT_NAVIGATION_TIMEOUT_SECS", "5"),
+            ("ROBERT_FAIL_ON_HTTP_ERROR", "true"),
+            ("ROBERT_DISMISS_COOKIE_BANNERS", "true"),
+            ("ROBERT_OVERLAYS", "hide"),
+            ("ROBERT_SCRIPT_CACHE", "true"),
+            ("ROBERT_SCRIPT_CACHE_TTL_SECS", "600"),
+            ("ROBERT_ARTIFACTS_MAX_MB", "2048"),
+            ("ROBERT_ARTIFACTS_MIN_FREE_MB", "0"),
+        ]
+        .into_iter()
+        .collect();
+
+        config
+            .apply_env(|key| env.get(key).map(|v| v.to_string()))
+            .unwrap();
+
+        assert_eq!(config.server.port, 8100);
+        assert!(matches!(
+            config.connection_mode(),
+            ConnectionMode::DebugPort(9222)
+        ));
+        assert_eq!(
+            config.driver_options().navigation_timeout,
+            Duration::from_secs(5)
+        );
+        assert!(config.driver_options().fail_on_http_error);
+        assert_eq!(
+            config.driver_options().cookie_consent,
+            Some(CookieConsent::default())
+        );
+        assert_eq!(
+            config.driver_options().overlays.map(|o| o.policy),
+            Some(OverlayPolicy::Hide)
+        );
+        assert!(!config.chrome.headless);
+        assert_eq!(config.generator.cache_ttl_secs, 600);
+        assert!(config.script_cache().unwrap().is_some());
+        assert_eq!(config.artifacts.max_mb, Some(2048));
+        assert_eq!(config.artifacts.min_free_mb, 0);
+
+        let ws_url = "ws://browserless:3000/devtools/browser/abc";
+        config
+            .apply_env(|key| (key == "ROBERT_WS_URL").then(|| ws_url.to_string()))
+            .unwrap();
+        assert!(matches!(
+            config.connection_mode(),
+            ConnectionMode::WebSocketUrl(url) if url == ws_url
+        ));
+
+        let err = config
+            .apply_env(|key| (key == "ROBERT_HEADLESS").then(|| "maybe".to_string()))
+            .unwrap_err();
+        assert!(err.to_string().contains("ROBERT_HEADLESS"));
+    }
+
+    #[test]
+    fn test_ci_implies_headless() {
+        let mut config = Config::default();
+        config
+            .apply_env(|key| (key == "GITHUB_ACTIONS").then(|| "true".to_string()))
+            .unwrap();
+        assert!(config.chrome.headless);
+        assert!(config.chrome.no_sandbox);
+
+        // Explicit variables still win
+        config
+            .apply_env(|key| match key {
+                "CI" => Some("1".to_string()),
+                "ROBERT_HEADLESS" => Some("false".to_string()),
+                _ => None,
+            })
+            .unwrap();
+        assert!(!config.chrome.headless);
+    }
+
+    #[test]
+    fn test_webhook_defaults() {
+        let config = Config::from_toml(
+            r#"
+            [[webhooks]]
+            url = "https://hooks.example.com/a"
+
+            [[webhooks]]
+            url = "https://hooks.example.com/b"
+            events = ["failed"]
+            max_retries = 0
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.webhooks.len(), 2);
+        let first = &config.webhooks[0];
+        assert!(first.events.is_empty());
+        assert!(first.include_artifacts);
+        assert_eq!(first.max_retries, 3);
+        assert_eq!(first.retry_backoff_ms, 1000);
+        assert_eq!(config.webhooks[1].events, vec![WebhookEvent::Failed]);
+        assert_eq!(config.webhooks[1].max_retries, 0);
+    }
+
+    #[test]
+    fn test_unknown_provider() {
+        let mut config = Config::default();
+        assert!(config.generator().is_ok());
+        config.generator.provider = "gpt".to_string();
+        assert!(config.generator().is_err());
+    }
+}
@@ -0,0 +1,560 @@
+//! Crawling
+//!
+//! [`Crawler`] visits a site breadth-first from seed URLs using the sessions of a
+//! [`BrowserPool`], so pages are rendered (and their script-generated links seen)
+//! the way a user's browser would render them. For each page it can capture a
+//! [`StepFrame`], run an extraction script, and audit broken subresources and
+//! links (see [`LinkAuditor`]), and it follows `<a href>` links
+//! that:
+//!
+//! - stay on the origin of one of the seeds
+//! - are within `max_depth` clicks of a seed
+//! - match an `include` glob (if any) and no `exclude` glob
+//! - are allowed by the origin's `robots.txt` (when `respect_robots` is set)
+//!
+//! Links are compared without their `#fragment`, so each page is visited once.
+//! Pages of one depth run concurrently, up to the pool size.
+
+use crate::browser::link_check::{LinkAuditor, LinkCheckOptions, LinkReport};
+use crate::browser::pool::BrowserPool;
+use crate::browser::response_tap::glob_match;
+use crate::error::{BrowserError, Result};
+use crate::step_frame::{capture_step_frame, ActionInfo, CaptureOptions, StepFrame};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// User agent token matched against `robots.txt` groups
+pub const ROBOTS_USER_AGENT: &str = "robert-webdriver";
+
+/// How long to wait for a `robots.txt` response
+const ROBOTS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Absolute URLs of the page's links
+const LINKS_SCRIPT: &str = "Array.from(document.querySelectorAll('a[href]'), (a) => a.href)";
+
+/// Limits and per-page work for a crawl
+#[derive(Debug, Clone)]
+pub struct CrawlOptions {
+    /// Link depth to follow from the seeds (0 = only the seeds)
+    pub max_depth: usize,
+
+    /// Stop after visiting this many pages
+    pub max_pages: usize,
+
+    /// URL globs (`*`, `?`) a page must match to be visited (empty = any); seeds are exempt
+    pub include: Vec<String>,
+
+    /// URL globs of pages never visited
+    pub exclude: Vec<String>,
+
+    /// Skip pages disallowed for [`ROBOTS_USER_AGENT`] by the origin's `robots.txt`
+    pub respect_robots: bool,
+
+    /// Capture a step frame of each page (frame ids follow visit order)
+    ///
+    /// VisualDom deltas are not used: consecutive pages are unrelated.
+    pub capture: Option<CaptureOptions>,
+
+    /// JavaScript expression evaluated on each page; its value is the page's extraction result
+    pub extract_script: Option<String>,
+
+    /// Report each page's broken subresources and anchor targets
+    pub check_links: Option<LinkCheckOptions>,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            max_pages: 100,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            respect_robots: true,
+            capture: None,
+            extract_script: None,
+            check_links: None,
+        }
+    }
+}
+
+/// One visited page
+#[derive(Debug, Clone, Serialize)]
+pub struct CrawledPage {
+    /// URL that was queued
+    pub url: String,
+
+    /// URL after redirects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+
+    /// Link depth from the nearest seed
+    pub depth: usize,
+
+    /// HTTP status of the page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i64>,
+
+    /// Links found on the page that were newly queued
+    pub queued_links: usize,
+
+    /// Step frame, when capturing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame: Option<StepFrame>,
+
+    /// Value of the extraction script
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extracted: Option<Value>,
+
+    /// Broken subresources and links, when checking links
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<LinkReport>,
+
+    /// Why the page could not be visited or processed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of a crawl
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CrawlReport {
+    /// Visited pages, in visit order
+    pub pages: Vec<CrawledPage>,
+
+    /// Links not visited because `robots.txt` disallows them
+    pub disallowed: Vec<String>,
+
+    /// Whether `max_pages` stopped the crawl with links still queued
+    pub truncated: bool,
+
+    /// Whether the crawl was cancelled
+    pub cancelled: bool,
+}
+
+impl CrawlReport {
+    /// Pages that failed to load or process
+    pub fn failures(&self) -> impl Iterator<Item = &CrawledPage> {
+        self.pages.iter().filter(|page| page.error.is_some())
+    }
+
+    /// Pages whose link check found broken subresources or links
+    pub fn broken_links(&self) -> impl Iterator<Item = &LinkReport> {
+        self.pages
+            .iter()
+            .filter_map(|page| page.links.as_ref())
+            .filter(|report| !report.is_clean())
+    }
+}
+
+/// Breadth-first crawler over a browser pool
+pub struct Crawler<'a> {
+    pool: &'a BrowserPool,
+    options: CrawlOptions,
+    client: reqwest::Client,
+}
+
+impl<'a> Crawler<'a> {
+    pub fn new(pool: &'a BrowserPool, mut options: CrawlOptions) -> Self {
+        if let Some(capture) = &mut options.capture {
+            capture.visual_dom_deltas = None;
+        }
+        Self {
+            pool,
+            options,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Crawl from `seeds`
+    pub async fn crawl(&self, seeds: &[String]) -> Result<CrawlReport> {
+        self.crawl_cancellable(seeds, &CancellationToken::new())
+            .await
+    }
+
+    /// Crawl from `seeds`, stopping after the current pages when `cancel_token` fires
+    pub async fn crawl_cancellable(
+        &self,
+        seeds: &[String],
+        cancel_token: &CancellationToken,
+    ) -> Result<CrawlReport> {
+        let origins: HashSet<String> = seeds
+            .iter()
+            .filter_map(|seed| origin(seed).map(str::to_string))
+            .collect();
+        if origins.is_empty() {
+            return Err(BrowserError::Other(
+                "Crawl needs at least one http(s) seed URL".to_string(),
+            ));
+        }
+
+        let start = Instant::now();
+        let mut report = CrawlReport::default();
+        let mut robots: HashMap<String, RobotsRules> = HashMap::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut level: Vec<String> = Vec::new();
+        for seed in seeds {
+            let url = strip_fragment(seed).to_string();
+            if origin(&url).is_some() && seen.insert(url.clone()) {
+                level.push(url);
+            }
+        }
+
+        let mut depth = 0;
+        while !level.is_empty() {
+            if cancel_token.is_cancelled() {
+                report.cancelled = true;
+                break;
+            }
+
+            let mut batch = Vec::new();
+            for url in level {
+                if report.pages.len() + batch.len() >= self.options.max_pages {
+                    report.truncated = true;
+                    break;
+                }
+                if self.options.respect_robots && !self.robots_allow(&mut robots, &url).await {
+                    report.disallowed.push(url);
+                    continue;
+                }
+                batch.push(url);
+            }
+
+            let first_frame = report.pages.len();
+            let visits = batch
+                .iter()
+                .enumerate()
+                .map(|(i, url)| self.visit(url, depth, first_frame + i, start, cancel_token));
+            let visited = futures::future::join_all(visits).await;
+
+            let mut next = Vec::new();
+            for (mut page, links) in visited {
+                if depth < self.options.max_depth {
+                    for link in links {
+                        let link = strip_fragment(&link).to_string();
+                        if self.should_follow(&link, &origins) && seen.insert(link.clone()) {
+                            next.push(link);
+                            page.queued_links += 1;
+                        }
+                    }
+                }
+                report.pages.push(page);
+            }
+            if report.truncated {
+                break;
+            }
+            level = next;
+            depth += 1;
+        }
+
+        tracing::info!(
+            pages = report.pages.len(),
+            failures = report.failures().count(),
+            disallowed = report.disallowed.len(),
+            "Crawl finished"
+        );
+        Ok(report)
+    }
+
+    /// Load one page on a pooled session; returns the page and its links
+    async fn visit(
+        &self,
+        url: &str,
+        depth: usize,
+        frame_id: usize,
+        start: Instant,
+        cancel_token: &CancellationToken,
+    ) -> (CrawledPage, Vec<String>) {
+        let mut page = CrawledPage {
+            url: url.to_string(),
+            final_url: None,
+            depth,
+            status: None,
+            queued_links: 0,
+            frame: None,
+            extracted: None,
+            links: None,
+            error: None,
+        };
+        let mut links = Vec::new();
+
+        let result: Result<()> = async {
+            let driver = tokio::select! {
+                driver = self.pool.acquire() => driver?,
+                _ = cancel_token.cancelled() => return Err(BrowserError::Cancelled),
+            };
+            let auditor = match &self.options.check_links {
+                Some(_) => Some(LinkAuditor::attach(&driver.current_page().await?).await?),
+                None => None,
+            };
+            let navigation = driver.navigate_cancellable(url, cancel_token).await?;
+            page.final_url = Some(navigation.url.clone()).filter(|final_url| final_url != url);
+            page.status = navigation.status;
+
+            links = driver
+                .execute_script_typed::<Vec<String>>(LINKS_SCRIPT)
+                .await
+                .unwrap_or_default();
+            if let (Some(auditor), Some(options)) = (auditor, &self.options.check_links) {
+                let report = auditor
+                    .finish(&driver.current_page().await?, &navigation, options)
+                    .await?;
+                page.links = Some(report);
+            }
+            if let Some(script) = &self.options.extract_script {
+                page.extracted = Some(driver.execute_script(script).await?);
+            }
+            if let Some(capture) = &self.options.capture {
+                let action = ActionInfo {
+                    action_type: "navigate".to_string(),
+                    intent: format!("Crawl {}", url),
+                    target: None,
+                    target_screenshot: None,
+                };
+                page.frame = Some(
+                    capture_step_frame(
+                        &driver,
+                        frame_id,
+                        start.elapsed().as_millis() as u64,
+                        capture,
+                        None,
+                        Some(action),
+                    )
+                    .await?,
+                );
+            }
+            Ok(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => tracing::debug!(url, depth, links = links.len(), "Crawled page"),
+            Err(e) => {
+                tracing::warn!(url, depth, error = %e, "Failed to crawl page");
+                page.error = Some(e.to_string());
+            }
+        }
+        (page, links)
+    }
+
+    fn should_follow(&self, url: &str, origins: &HashSet<String>) -> bool {
+        let Some(link_origin) = origin(url) else {
+            return false;
+        };
+        origins.contains(link_origin)
+            && (self.options.include.is_empty()
+                || self.options.include.iter().any(|p| glob_match(p, url)))
+            && !self.options.exclude.iter().any(|p| glob_match(p, url))
+    }
+
+    /// Whether `robots.txt` of the URL's origin allows it (fetched once per origin)
+    async fn robots_allow(&self, cache: &mut HashMap<String, RobotsRules>, url: &str) -> bool {
+        let Some(url_origin) = origin(url) else {
+            return true;
+        };
+        if !cache.contains_key(url_origin) {
+            let rules = self.fetch_robots(url_origin).await;
+            cache.insert(url_origin.to_string(), rules);
+        }
+        let path = &url[url_origin.len()..];
+        cache[url_origin].allows(if path.is_empty() { "/" } else { path })
+    }
+
+    /// Rules for `origin`; a missing or unreadable `robots.txt` allows everything
+    async fn fetch_robots(&self, origin: &str) -> RobotsRules {
+        let robots_url = format!("{}/robots.txt", origin);
+        let response = self
+            .client
+            .get(&robots_url)
+            .timeout(ROBOTS_TIMEOUT)
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => match response.text().await {
+                Ok(text) => RobotsRules::parse(&text, ROBOTS_USER_AGENT),
+                Err(e) => {
+                    tracing::warn!(url = %robots_url, error = %e, "Failed to read robots.txt");
+                    RobotsRules::default()
+                }
+            },
+            Ok(response) => {
+                tracing::debug!(url = %robots_url, status = %response.status(), "No robots.txt");
+                RobotsRules::default()
+            }
+            Err(e) => {
+                tracing::warn!(url = %robots_url, error = %e, "Failed to fetch robots.txt");
+                RobotsRules::default()
+            }
+        }
+    }
+}
+
+/// `Allow`/`Disallow` rules of the `robots.txt` group that applies to one user agent
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RobotsRules {
+    /// (path pattern, allowed) pairs
+    rules: Vec<(String, bool)>,
+}
+
+impl RobotsRules {
+    /// Rules for `user_agent`: its own group if there is one, otherwise the `*` group
+    pub fn parse(text: &str, user_agent: &str) -> Self {
+        let user_agent = user_agent.to_ascii_lowercase();
+        let mut specific: Option<Vec<(String, bool)>> = None;
+        let mut wildcard: Option<Vec<(String, bool)>> = None;
+
+        // Agents of the group being read, and whether its rules have started
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        let mut rules: Vec<(String, bool)> = Vec::new();
+        let mut finish = |agents: &[String], rules: &[(String, bool)]| {
+            for agent in agents {
+                if agent == "*" {
+                    wildcard
+                        .get_or_insert_with(Vec::new)
+                        .extend_from_slice(rules);
+                } else if user_agent.starts_with(agent.as_str()) {
+                    specific
+                        .get_or_insert_with(Vec::new)
+                        .extend_from_slice(rules);
+                }
+            }
+        };
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match field.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        finish(&agents, &rules);
+                        agents.clear();
+                        rules.clear();
+                        in_rules = false;
+                    }
+                    agents.push(value.to_ascii_lowercase());
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty Disallow allows everything
+                    if !value.is_empty() {
+                        rules.push((
+                            value.to_string(),
+                            field.trim().eq_ignore_ascii_case("allow"),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        finish(&agents, &rules);
+
+        Self {
+            rules: specific.or(wildcard).unwrap_or_default(),
+        }
+    }
+
+    /// Whether `path` (with query) may be fetched; the longest matching rule wins, `Allow` on ties
+    pub fn allows(&self, path: &str) -> bool {
+        let mut best: Option<(usize, bool)> = None;
+        for (pattern, allow) in &self.rules {
+            if robots_match(pattern, path) {
+                let better = match best {
+                    None => true,
+                    Some((len, best_allow)) => {
+                        pattern.len() > len || (pattern.len() == len && *allow && !best_allow)
+                    }
+                };
+                if better {
+                    best = Some((pattern.len(), *allow));
+                }
+            }
+        }
+        best.is_none_or(|(_, allow)| allow)
+    }
+}
+
+/// `robots.txt` path matching: a prefix match where `*` is any run and a trailing `$` anchors the end
+fn robots_match(pattern: &str, path: &str) -> bool {
+    match pattern.strip_suffix('$') {
+        Some(anchored) => glob_match(anchored, path),
+        None => glob_match(&format!("{}*", pattern), path),
+    }
+}
+
+/// `scheme://host[:port]` of an http(s) URL
+pub(crate) fn origin(url: &str) -> Option<&str> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let host_len = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    if host_len == 0 {
+        return None;
+    }
+    Some(&url[..url.len() - rest.len() + host_len])
+}
+
+fn strip_fragment(url: &str) -> &str {
+    url.split('#').next().unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin() {
+        assert_eq!(
+            origin("https://example.com/a/b?c"),
+            Some("https://example.com")
+        );
+        assert_eq!(
+            origin("http://127.0.0.1:8080"),
+            Some("http://127.0.0.1:8080")
+        );
+        assert_eq!(origin("http://host#top"), Some("http://host"));
+        assert_eq!(origin("mailto:someone@example.com"), None);
+        assert_eq!(origin("https:///path"), None);
+        assert_eq!(
+            strip_fragment("https://example.com/a#b"),
+            "https://example.com/a"
+        );
+    }
+
+    #[test]
+    fn test_robots_groups() {
+        let text = "\
+# comment
+User-agent: *
+Disallow: /private
+Disallow: /*.pdf$
+
+User-agent: other-bot
+Disallow: /
+
+User-agent: Robert-Webdriver
+User-agent: another
+Disallow: /admin
+Allow: /admin/public
+";
+        let rules = RobotsRules::parse(text, ROBOTS_USER_AGENT);
+        assert!(!rules.allows("/admin"));
+        assert!(!rules.allows("/admin/settings"));
+        assert!(rules.allows("/admin/public/page"));
+        // The specific group replaces the * group
+        assert!(rules.allows("/private"));
+
+        let rules = RobotsRules::parse(text, "some-crawler");
+        assert!(!rules.allows("/private/data"));
+        assert!(!rules.allows("/files/report.pdf"));
+        assert!(rules.allows("/files/report.pdf?download=1"));
+        assert!(rules.allows("/"));
+
+        assert!(RobotsRules::parse("User-agent: *\nDisallow:\n", "x").allows("/anything"));
+        assert!(RobotsRules::default().allows("/"));
+    }
+}
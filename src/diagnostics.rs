@@ -0,0 +1,605 @@
+//! Environment Diagnostics
+//!
+//! Most "Chrome won't start" reports come down to the environment: no browser and no
+//! network to download one, a sandbox the kernel doesn't allow, a headed session
+//! without a display, a tiny `/dev/shm` in a container, or a read-only cache
+//! directory. [`diagnose`] checks all of these at once and returns a
+//! [`DiagnosticReport`] with what it found, a list of issues with their fixes, and
+//! the result of actually launching Chrome with the configured settings.
+//!
+//! The report is printed by `robert-webdriver diagnose` and served at `/diagnose`,
+//! so it can be attached to a bug report as it is.
+
+use crate::browser::chrome::ChromeDriver;
+use crate::browser::container;
+use crate::browser::detect::{detect_browsers, DetectedBrowser};
+use crate::browser::display::has_display;
+use crate::browser::install::ChromeInstaller;
+use crate::config::{ChromeConfig, Config};
+use crate::model::browser::BrowserInfo;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// URL fetched to check that Chrome for Testing can be downloaded
+pub const DEFAULT_EGRESS_URL: &str =
+    "https://googlechromelabs.github.io/chrome-for-testing/last-known-good-versions.json";
+
+/// How long the egress check waits for a response
+const EGRESS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Which of the slower checks [`diagnose`] runs
+#[derive(Debug, Clone)]
+pub struct DiagnoseOptions {
+    /// Launch (or connect to) Chrome with the configured settings and load a page
+    pub launch: bool,
+
+    /// URL fetched to check network egress (None = skip the check)
+    pub egress_url: Option<String>,
+}
+
+impl Default for DiagnoseOptions {
+    fn default() -> Self {
+        Self {
+            launch: true,
+            egress_url: Some(DEFAULT_EGRESS_URL.to_string()),
+        }
+    }
+}
+
+/// What [`diagnose`] found
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DiagnosticReport {
+    /// Whether no issues were found
+    pub ok: bool,
+
+    /// Problems that keep Chrome from running (or running as configured), with fixes
+    pub issues: Vec<String>,
+
+    pub os: OsInfo,
+
+    /// Chromium-based browsers installed on the system
+    #[schema(value_type = Vec<Object>)]
+    pub browsers: Vec<DetectedBrowser>,
+
+    pub sandbox: SandboxCheck,
+    pub display: DisplayCheck,
+    pub cache: CacheCheck,
+
+    /// None if the check was skipped
+    pub network: Option<NetworkCheck>,
+
+    /// None if the check was skipped
+    pub launch: Option<LaunchCheck>,
+}
+
+/// The system the driver runs on
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OsInfo {
+    /// `linux`, `macos`, or `windows`
+    pub family: String,
+    pub arch: String,
+
+    /// Distribution or release name (e.g. `Ubuntu 24.04 LTS`), if known
+    pub version: Option<String>,
+
+    /// Whether this looks like a Docker, Podman, or Kubernetes container
+    pub container: bool,
+
+    /// Size limit of `/dev/shm` in bytes, if it has one
+    pub shm_bytes: Option<u64>,
+}
+
+/// Whether Chrome can use its sandbox
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SandboxCheck {
+    pub available: bool,
+
+    /// Whether the config turns the sandbox off (`no_sandbox` or `container`)
+    pub disabled: bool,
+
+    /// Why the sandbox isn't available
+    pub reason: Option<String>,
+}
+
+/// Whether a headed Chrome has a display
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DisplayCheck {
+    /// Whether there's an X11 or Wayland display (always true on macOS and Windows)
+    pub available: bool,
+
+    /// Whether Xvfb is installed
+    pub xvfb: bool,
+
+    /// Whether the config launches Chrome headless
+    pub headless: bool,
+}
+
+/// State of the Chrome for Testing cache directory
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CacheCheck {
+    /// The directory (None if the home directory is unknown)
+    pub dir: Option<String>,
+    pub exists: bool,
+
+    /// Whether Chrome can be downloaded into it (or into the directory it'd be created in)
+    pub writable: bool,
+
+    /// Versions installed in it
+    pub installed: Vec<String>,
+}
+
+/// Result of fetching the egress URL
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NetworkCheck {
+    pub url: String,
+
+    /// Whether any HTTP response came back
+    pub reachable: bool,
+
+    /// HTTP status of the response
+    pub status: Option<u16>,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Result of launching Chrome and loading a page
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LaunchCheck {
+    pub ok: bool,
+
+    /// The browser that started
+    pub browser: Option<BrowserInfo>,
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Check the environment the driver runs in, with the settings in `config`
+///
+/// Never fails: checks that can't run record why in the report.
+pub async fn diagnose(config: &Config, options: &DiagnoseOptions) -> DiagnosticReport {
+    // Runs each browser with --version
+    let browsers = tokio::task::spawn_blocking(detect_browsers)
+        .await
+        .unwrap_or_default();
+
+    let network = async {
+        match &options.egress_url {
+            Some(url) => Some(check_network(url).await),
+            None => None,
+        }
+    };
+    let launch = async {
+        match options.launch {
+            true => Some(check_launch(config).await),
+            false => None,
+        }
+    };
+    let (cache, network, launch) = tokio::join!(
+        check_cache(config.chrome.download_dir.as_deref()),
+        network,
+        launch
+    );
+
+    let mut report = DiagnosticReport {
+        ok: true,
+        issues: Vec::new(),
+        os: os_info(),
+        browsers,
+        sandbox: check_sandbox(&config.chrome),
+        display: display_check(&config.chrome),
+        cache,
+        network,
+        launch,
+    };
+    report.issues = issues(&report, &config.chrome);
+    report.ok = report.issues.is_empty();
+    report
+}
+
+fn os_info() -> OsInfo {
+    let read = |path: &str| std::fs::read_to_string(path).ok();
+    let version = if cfg!(target_os = "linux") {
+        read("/etc/os-release").and_then(|release| parse_os_release(&release))
+    } else if cfg!(target_os = "macos") {
+        command_output("sw_vers", &["-productVersion"]).map(|version| format!("macOS {}", version))
+    } else if cfg!(target_os = "windows") {
+        command_output("cmd", &["/C", "ver"])
+    } else {
+        None
+    };
+    let in_container = Path::new("/.dockerenv").exists()
+        || Path::new("/run/.containerenv").exists()
+        || read("/proc/1/cgroup").is_some_and(|cgroup| {
+            ["docker", "kubepods", "containerd", "libpod"]
+                .iter()
+                .any(|runtime| cgroup.contains(runtime))
+        });
+    OsInfo {
+        family: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        version,
+        container: in_container,
+        shm_bytes: container::shm_size(),
+    }
+}
+
+/// `PRETTY_NAME` (or `NAME` and `VERSION`) from `/etc/os-release` content
+fn parse_os_release(release: &str) -> Option<String> {
+    let field = |key: &str| {
+        release.lines().find_map(|line| {
+            let value = line.strip_prefix(key)?.strip_prefix('=')?;
+            Some(value.trim().trim_matches('"').to_string())
+        })
+    };
+    field("PRETTY_NAME").or_else(|| {
+        let name = field("NAME")?;
+        Some(match field("VERSION") {
+            Some(version) => format!("{} {}", name, version),
+            None => name,
+        })
+    })
+}
+
+/// First line of a command's standard output
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())?;
+    Some(line.to_string())
+}
+
+fn check_sandbox(chrome: &ChromeConfig) -> SandboxCheck {
+    let reason = if cfg!(target_os = "linux") {
+        sandbox_problem(|path| std::fs::read_to_string(path).ok())
+    } else {
+        None
+    };
+    SandboxCheck {
+        available: reason.is_none(),
+        disabled: chrome.no_sandbox || chrome.container,
+        reason,
+    }
+}
+
+/// Why Chrome's sandbox won't work on this Linux system, with files read by `read`
+fn sandbox_problem<F>(read: F) -> Option<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let root = read("/proc/self/status").is_some_and(|status| {
+        status.lines().any(|line| {
+            line.strip_prefix("Uid:")
+                .and_then(|ids| ids.split_whitespace().next())
+                == Some("0")
+        })
+    });
+    if root {
+        return Some("running as root, where Chrome refuses to start with its sandbox".to_string());
+    }
+    let setting = |path: &str| read(path).and_then(|value| value.trim().parse::<u64>().ok());
+    if setting("/proc/sys/kernel/unprivileged_userns_clone") == Some(0) {
+        return Some(
+            "unprivileged user namespaces are disabled (kernel.unprivileged_userns_clone = 0)"
+                .to_string(),
+        );
+    }
+    if setting("/proc/sys/user/max_user_namespaces") == Some(0) {
+        return Some("user namespaces are disabled (user.max_user_namespaces = 0)".to_string());
+    }
+    if setting("/proc/sys/kernel/apparmor_restrict_unprivileged_userns") == Some(1) {
+        return Some(
+            "AppArmor restricts unprivileged user namespaces \
+             (kernel.apparmor_restrict_unprivileged_userns = 1)"
+                .to_string(),
+        );
+    }
+    None
+}
+
+fn display_check(chrome: &ChromeConfig) -> DisplayCheck {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    DisplayCheck {
+        available: has_display(),
+        xvfb: std::env::split_paths(&path).any(|dir| dir.join("Xvfb").is_file()),
+        headless: chrome.headless || chrome.container,
+    }
+}
+
+async fn check_cache(download_dir: Option<&Path>) -> CacheCheck {
+    let Some(installer) = ChromeInstaller::for_download_dir(download_dir) else {
+        return CacheCheck {
+            dir: None,
+            exists: false,
+            writable: false,
+            installed: Vec::new(),
+        };
+    };
+    let dir = installer.root();
+    let exists = tokio::fs::metadata(dir)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir());
+
+    // The cache is created on first download, so probe the nearest directory that exists
+    let writable = match dir.ancestors().find(|ancestor| ancestor.is_dir()) {
+        Some(parent) => {
+            let probe = parent.join(format!(".robert-diagnose-{}", std::process::id()));
+            let written = tokio::fs::write(&probe, b"").await.is_ok();
+            let _ = tokio::fs::remove_file(&probe).await;
+            written
+        }
+        None => false,
+    };
+    let installed = installer
+        .list()
+        .await
+        .map(|installed| installed.into_iter().map(|chrome| chrome.version).collect())
+        .unwrap_or_default();
+
+    CacheCheck {
+        dir: Some(dir.display().to_string()),
+        exists,
+        writable,
+        installed,
+    }
+}
+
+async fn check_network(url: &str) -> NetworkCheck {
+    let started = Instant::now();
+    let response = match reqwest::Client::builder().timeout(EGRESS_TIMEOUT).build() {
+        Ok(client) => client.head(url).send().await,
+        Err(e) => Err(e),
+    };
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    match response {
+        Ok(response) => NetworkCheck {
+            url: url.to_string(),
+            reachable: true,
+            status: Some(response.status().as_u16()),
+            elapsed_ms,
+            error: None,
+        },
+        Err(e) => NetworkCheck {
+            url: url.to_string(),
+            reachable: false,
+            status: None,
+            elapsed_ms,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+async fn check_launch(config: &Config) -> LaunchCheck {
+    let started = Instant::now();
+    let result = async {
+        let driver = ChromeDriver::from_config(config).await?;
+        let browser = driver.browser_info().clone();
+        let loaded = driver.navigate("about:blank").await;
+        // Leave a browser we connected to running
+        if config.chrome.ws_url.is_none() && config.chrome.debug_port.is_none() {
+            driver.close().await?;
+        }
+        loaded.map(|_| browser)
+    }
+    .await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(browser) => LaunchCheck {
+            ok: true,
+            browser: Some(browser),
+            elapsed_ms,
+            error: None,
+        },
+        Err(e) => LaunchCheck {
+            ok: false,
+            browser: None,
+            elapsed_ms,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+/// The problems in `report`, each with what to do about it
+fn issues(report: &DiagnosticReport, chrome: &ChromeConfig) -> Vec<String> {
+    let mut issues = Vec::new();
+    let connects = chrome.ws_url.is_some() || chrome.debug_port.is_some();
+
+    if let Some(launch) = report.launch.as_ref().filter(|launch| !launch.ok) {
+        issues.push(format!(
+            "Chrome failed to start: {}",
+            launch.error.as_deref().unwrap_or("unknown error")
+        ));
+    }
+    if connects {
+        return issues;
+    }
+
+    let offline = report
+        .network
+        .as_ref()
+        .is_some_and(|network| !network.reachable || network.status.is_some_and(|s| s >= 400));
+    if chrome.path.is_none() && report.cache.installed.is_empty() {
+        if offline {
+            issues.push(
+                "Chrome for Testing isn't cached and can't be downloaded; allow access to \
+                 googlechromelabs.github.io and storage.googleapis.com, or set chrome.path \
+                 (ROBERT_CHROME_PATH) to an installed Chrome"
+                    .to_string(),
+            );
+        }
+        if !report.cache.writable {
+            issues.push(format!(
+                "The Chrome cache directory {} isn't writable; set chrome.download_dir \
+                 (ROBERT_DOWNLOAD_DIR) to a writable directory",
+                report.cache.dir.as_deref().unwrap_or("(unknown)")
+            ));
+        }
+    }
+    if !report.sandbox.available && !report.sandbox.disabled {
+        issues.push(format!(
+            "Chrome's sandbox is unavailable ({}); set chrome.no_sandbox (ROBERT_NO_SANDBOX=true), \
+             or chrome.container in a container",
+            report.sandbox.reason.as_deref().unwrap_or("unknown reason")
+        ));
+    }
+    if !report.display.available && !report.display.headless && !chrome.xvfb {
+        issues.push(if report.display.xvfb {
+            "There's no display for a headed Chrome, so it runs headless; set chrome.xvfb \
+             (ROBERT_XVFB=true) to run it on a virtual display"
+                .to_string()
+        } else {
+            "There's no display for a headed Chrome, so it runs headless; install Xvfb and set \
+             chrome.xvfb (ROBERT_XVFB=true) to run it on a virtual display"
+                .to_string()
+        });
+    }
+    if chrome.xvfb && !report.display.available && !report.display.xvfb {
+        issues.push("chrome.xvfb is set but Xvfb isn't installed".to_string());
+    }
+    if let Some(hint) = report.os.shm_bytes.and_then(container::hint_for) {
+        issues.push(hint);
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> DiagnosticReport {
+        DiagnosticReport {
+            ok: true,
+            issues: Vec::new(),
+            os: OsInfo {
+                family: "linux".to_string(),
+                arch: "x86_64".to_string(),
+                version: Some("Ubuntu 24.04 LTS".to_string()),
+                container: false,
+                shm_bytes: None,
+            },
+            browsers: Vec::new(),
+            sandbox: SandboxCheck {
+                available: true,
+                disabled: false,
+                reason: None,
+            },
+            display: DisplayCheck {
+                available: true,
+                xvfb: false,
+                headless: true,
+            },
+            cache: CacheCheck {
+                dir: Some("/home/ci/.cache/robert/chrome".to_string()),
+                exists: true,
+                writable: true,
+                installed: vec!["128.0.6613.119".to_string()],
+            },
+            network: None,
+            launch: None,
+        }
+    }
+
+    #[test]
+    fn test_issues() {
+        let chrome = ChromeConfig::default();
+        assert!(issues(&report(), &chrome).is_empty());
+
+        // A container: no sandbox, no display, small /dev/shm, no network, empty cache
+        let mut broken = report();
+        broken.sandbox = SandboxCheck {
+            available: false,
+            disabled: false,
+            reason: Some("running as root".to_string()),
+        };
+        broken.display.available = false;
+        broken.display.headless = false;
+        broken.os.shm_bytes = Some(64 * 1024 * 1024);
+        broken.cache.installed.clear();
+        broken.network = Some(NetworkCheck {
+            url: DEFAULT_EGRESS_URL.to_string(),
+            reachable: false,
+            status: None,
+            elapsed_ms: 10_000,
+            error: Some("timed out".to_string()),
+        });
+        broken.launch = Some(LaunchCheck {
+            ok: false,
+            browser: None,
+            elapsed_ms: 10_000,
+            error: Some("Chrome not found".to_string()),
+        });
+        let found = issues(&broken, &chrome);
+        assert_eq!(found.len(), 5, "{:?}", found);
+        assert_eq!(found[0], "Chrome failed to start: Chrome not found");
+        assert!(found[1].contains("can't be downloaded"));
+        assert!(found[2].contains("(running as root)") && found[2].contains("no_sandbox"));
+        assert!(found[3].contains("install Xvfb"));
+        assert!(found[4].contains("/dev/shm is only 64 MiB"));
+
+        // Settings that work around them
+        let chrome = ChromeConfig {
+            path: Some("/usr/bin/chromium".to_string()),
+            no_sandbox: true,
+            headless: true,
+            ..Default::default()
+        };
+        broken.sandbox.disabled = true;
+        broken.display.headless = true;
+        broken.os.shm_bytes = None;
+        broken.launch = None;
+        assert!(issues(&broken, &chrome).is_empty());
+    }
+
+    #[test]
+    fn test_sandbox_problem() {
+        let files = |entries: &'static [(&'static str, &'static str)]| {
+            move |path: &str| {
+                entries
+                    .iter()
+                    .find(|(p, _)| *p == path)
+                    .map(|(_, content)| content.to_string())
+            }
+        };
+        assert_eq!(sandbox_problem(files(&[])), None);
+        assert_eq!(
+            sandbox_problem(files(&[(
+                "/proc/self/status",
+                "Name:\tx\nUid:\t1000\t1000\t1000\t1000\n"
+            )])),
+            None
+        );
+        assert!(
+            sandbox_problem(files(&[("/proc/self/status", "Uid:\t0\t0\t0\t0\n")]))
+                .unwrap()
+                .contains("root")
+        );
+        assert!(sandbox_problem(files(&[(
+            "/proc/sys/kernel/apparmor_restrict_unprivileged_userns",
+            "1\n"
+        )]))
+        .unwrap()
+        .contains("AppArmor"));
+        assert!(sandbox_problem(files(&[("/proc/sys/user/max_user_namespaces", "0\n")])).is_some());
+    }
+
+    #[test]
+    fn test_parse_os_release() {
+        let release = "NAME=\"Ubuntu\"\nVERSION=\"24.04 LTS (Noble Numbat)\"\nPRETTY_NAME=\"Ubuntu 24.04 LTS\"\n";
+        assert_eq!(
+            parse_os_release(release).as_deref(),
+            Some("Ubuntu 24.04 LTS")
+        );
+        assert_eq!(
+            parse_os_release("NAME=Alpine Linux\nVERSION=3.20\n").as_deref(),
+            Some("Alpine Linux 3.20")
+        );
+        assert_eq!(parse_os_release(""), None);
+    }
+}
@@ -11,12 +11,36 @@ pub enum BrowserError {
     #[error("Navigation failed: {0}")]
     NavigationFailed(String),
 
+    #[error("HTTP {status} from {url}")]
+    HttpStatus { url: String, status: i64 },
+
     #[error("Element not found: {0}")]
     ElementNotFound(String),
 
     #[error("No page available")]
     NoPage,
 
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    #[error("Login failed: {0}")]
+    LoginFailed(String),
+
+    #[error("Captcha detected: {0}")]
+    CaptchaDetected(String),
+
+    #[error("No popup opened within {0:?}")]
+    NoPopup(std::time::Duration),
+
+    #[error("Page crashed: {0}")]
+    TargetCrashed(Box<crate::browser::crash::CrashReport>),
+
+    #[error("Unsupported browser: {0}")]
+    UnsupportedBrowser(String),
+
+    #[error("Not enough disk space for artifacts: {0}")]
+    DiskSpace(String),
+
     #[error("CDP error: {0}")]
     CdpError(#[from] chromiumoxide::error::CdpError),
 
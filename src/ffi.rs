@@ -0,0 +1,330 @@
+//! C-Compatible FFI
+//!
+//! A stable C ABI over the driver, the CDP executor, and step-frame capture, so
+//! Python (ctypes/cffi), Node (ffi-napi/koffi), and other languages can bind to the
+//! library without a Rust toolchain. The header is `include/robert_webdriver.h`; build
+//! the shared and static libraries with:
+//!
+//! ```bash
+//! cargo build --release --lib --features ffi
+//! ```
+//!
+//! Conventions:
+//! - Every call blocks until the browser work is done. A session owns its own Tokio
+//!   runtime and must only be used from one thread at a time.
+//! - Functions returning `int` return `0` on success and `-1` on error; functions
+//!   returning a string return NULL on error. Call [`robert_last_error`] for the
+//!   message of the last error on the calling thread.
+//! - Strings are UTF-8 and NUL-terminated. Strings returned by the library are owned
+//!   by the caller and must be released with [`robert_string_free`].
+
+use crate::browser::chrome::ChromeDriver;
+use crate::cdp::{CdpScript, ExecutionReport};
+use crate::config::Config;
+use crate::step_frame::{capture_step_frame, CaptureOptions};
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+use std::time::Instant;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A Chrome session driven through the C ABI
+pub struct RobertSession {
+    runtime: tokio::runtime::Runtime,
+    driver: Option<ChromeDriver>,
+    config: Config,
+    started: Instant,
+    frames: usize,
+    report: Option<ExecutionReport>,
+}
+
+impl RobertSession {
+    fn driver(&self) -> anyhow::Result<&ChromeDriver> {
+        self.driver
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Session is closed"))
+    }
+}
+
+/// Run `f`, recording its error (or panic) for [`robert_last_error`] and returning
+/// `on_error` instead
+fn ffi_call<T>(on_error: T, f: impl FnOnce() -> anyhow::Result<T>) -> T {
+    let message = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => return value,
+        Ok(Err(e)) => format!("{:#}", e),
+        Err(panic) => {
+            let reason = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            format!("Panicked: {}", reason)
+        }
+    };
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = Some(to_c_string(message));
+    });
+    on_error
+}
+
+/// Borrow a required C string argument
+///
+/// # Safety
+/// `value` must be NULL or point to a NUL-terminated string.
+unsafe fn str_arg<'a>(name: &str, value: *const c_char) -> anyhow::Result<&'a str> {
+    if value.is_null() {
+        anyhow::bail!("{} must not be NULL", name);
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|_| anyhow::anyhow!("{} is not valid UTF-8", name))
+}
+
+/// Borrow an optional C string argument (NULL = None)
+///
+/// # Safety
+/// `value` must be NULL or point to a NUL-terminated string.
+unsafe fn opt_str_arg<'a>(name: &str, value: *const c_char) -> anyhow::Result<Option<&'a str>> {
+    if value.is_null() {
+        Ok(None)
+    } else {
+        str_arg(name, value).map(Some)
+    }
+}
+
+/// Borrow the session behind a handle
+///
+/// # Safety
+/// `session` must be NULL or a handle from [`robert_session_create`] that has not
+/// been freed.
+unsafe fn session_arg<'a>(session: *mut RobertSession) -> anyhow::Result<&'a mut RobertSession> {
+    session
+        .as_mut()
+        .ok_or_else(|| anyhow::anyhow!("session must not be NULL"))
+}
+
+/// Hand a string to the caller (interior NULs, which C can't represent, are dropped)
+fn to_c_string(value: String) -> CString {
+    CString::new(value).unwrap_or_else(|e| {
+        let bytes: Vec<u8> = e.into_vec().into_iter().filter(|&b| b != 0).collect();
+        CString::new(bytes).unwrap()
+    })
+}
+
+/// Launch a Chrome session configured from `config_path` (NULL = `$ROBERT_CONFIG`
+/// or the default config location, plus `ROBERT_*` environment overrides)
+///
+/// Returns NULL on error. Free the session with [`robert_session_free`].
+///
+/// # Safety
+/// `config_path` must be NULL or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn robert_session_create(config_path: *const c_char) -> *mut RobertSession {
+    ffi_call(ptr::null_mut(), || {
+        let config_path = opt_str_arg("config_path", config_path)?.map(PathBuf::from);
+        let config = Config::load(config_path.as_deref())?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let driver = runtime.block_on(ChromeDriver::from_config(&config))?;
+        Ok(Box::into_raw(Box::new(RobertSession {
+            runtime,
+            driver: Some(driver),
+            config,
+            started: Instant::now(),
+            frames: 0,
+            report: None,
+        })))
+    })
+}
+
+/// Close the session's Chrome and free the session (NULL is ignored)
+///
+/// # Safety
+/// `session` must be NULL or a handle from [`robert_session_create`] that has not
+/// been freed; it must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn robert_session_free(session: *mut RobertSession) {
+    if session.is_null() {
+        return;
+    }
+    let mut session = Box::from_raw(session);
+    ffi_call((), || {
+        if let Some(driver) = session.driver.take() {
+            if let Err(e) = session.runtime.block_on(driver.close()) {
+                tracing::warn!(error = %e, "Failed to close Chrome session");
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Navigate the session's current page to `url` and wait for it to load
+///
+/// # Safety
+/// `session` must be a live handle and `url` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn robert_navigate(session: *mut RobertSession, url: *const c_char) -> c_int {
+    ffi_call(-1, || {
+        let session = session_arg(session)?;
+        let url = str_arg("url", url)?;
+        session.runtime.block_on(session.driver()?.navigate(url))?;
+        Ok(0)
+    })
+}
+
+/// Execute a CDP script (JSON, as accepted by `robert-webdriver run`) in the session
+///
+/// Returns `0` if every command succeeded, `1` if the script ran but a command failed,
+/// and `-1` if it could not be run. Fetch the report with [`robert_report_json`].
+///
+/// # Safety
+/// `session` must be a live handle and `script_json` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn robert_run_script(
+    session: *mut RobertSession,
+    script_json: *const c_char,
+) -> c_int {
+    ffi_call(-1, || {
+        let session = session_arg(session)?;
+        let script: CdpScript = serde_json::from_str(str_arg("script_json", script_json)?)
+            .map_err(|e| anyhow::anyhow!("Invalid script: {}", e))?;
+        let report = session
+            .runtime
+            .block_on(session.driver()?.execute_cdp_script_direct(&script))?;
+        let status = if report.is_success() { 0 } else { 1 };
+        session.report = Some(report);
+        Ok(status)
+    })
+}
+
+/// Execution report of the session's last [`robert_run_script`], as JSON
+///
+/// Returns NULL on error or if no script has run yet.
+///
+/// # Safety
+/// `session` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn robert_report_json(session: *mut RobertSession) -> *mut c_char {
+    ffi_call(ptr::null_mut(), || {
+        let session = session_arg(session)?;
+        let report = session
+            .report
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No script has run in this session"))?;
+        Ok(to_c_string(serde_json::to_string(report)?).into_raw())
+    })
+}
+
+/// Capture a step frame of the session's current page into `output_dir` (NULL =
+/// the config's `artifacts.root`) and return it as JSON
+///
+/// `instruction` (may be NULL) is recorded in the frame's transcript. Returns NULL
+/// on error.
+///
+/// # Safety
+/// `session` must be a live handle; `output_dir` and `instruction` must be NULL or
+/// NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn robert_capture_frame(
+    session: *mut RobertSession,
+    output_dir: *const c_char,
+    instruction: *const c_char,
+) -> *mut c_char {
+    ffi_call(ptr::null_mut(), || {
+        let session = session_arg(session)?;
+        let output_dir = opt_str_arg("output_dir", output_dir)?
+            .map(PathBuf::from)
+            .unwrap_or_else(|| session.config.artifacts.root.clone());
+        let instruction = opt_str_arg("instruction", instruction)?.map(str::to_string);
+        let options = CaptureOptions {
+            screenshot_dir: output_dir.join("screenshots"),
+            dom_dir: Some(output_dir.join("dom")),
+            ..Default::default()
+        };
+
+        let frame = session.runtime.block_on(capture_step_frame(
+            session.driver()?,
+            session.frames,
+            session.started.elapsed().as_millis() as u64,
+            &options,
+            instruction,
+            None,
+        ))?;
+        session.frames += 1;
+        Ok(to_c_string(serde_json::to_string(&frame)?).into_raw())
+    })
+}
+
+/// Message of the last error on the calling thread (NULL if none)
+///
+/// The returned string must be freed with [`robert_string_free`].
+#[no_mangle]
+pub extern "C" fn robert_last_error() -> *mut c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null_mut(), |message| message.clone().into_raw())
+    })
+}
+
+/// Free a string returned by the library (NULL is ignored)
+///
+/// # Safety
+/// `value` must be NULL or a string returned by this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn robert_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        let message = robert_last_error();
+        assert!(!message.is_null());
+        let text = unsafe { CStr::from_ptr(message) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        unsafe { robert_string_free(message) };
+        text
+    }
+
+    #[test]
+    fn test_errors_are_reported() {
+        let script = CString::new("{}").unwrap();
+        let status = unsafe { robert_run_script(ptr::null_mut(), script.as_ptr()) };
+        assert_eq!(status, -1);
+        assert_eq!(last_error(), "session must not be NULL");
+
+        assert!(unsafe { robert_report_json(ptr::null_mut()) }.is_null());
+        assert_eq!(last_error(), "session must not be NULL");
+
+        let missing = CString::new("/nonexistent/robert.toml").unwrap();
+        assert!(unsafe { robert_session_create(missing.as_ptr()) }.is_null());
+        assert!(last_error().contains("/nonexistent/robert.toml"));
+
+        // Freeing NULL is a no-op
+        unsafe {
+            robert_session_free(ptr::null_mut());
+            robert_string_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_ffi_call_catches_panics() {
+        let value = ffi_call(-1, || -> anyhow::Result<c_int> { panic!("boom") });
+        assert_eq!(value, -1);
+        assert_eq!(last_error(), "Panicked: boom");
+        assert_eq!(to_c_string("a\0b".to_string()).to_str().unwrap(), "ab");
+    }
+}
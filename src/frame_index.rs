@@ -0,0 +1,418 @@
+//! Step Frame Session Index
+//!
+//! A SQLite database recording sessions, their frames, and where each frame's
+//! artifacts are, for analytics over large corpora of recorded runs: find every frame
+//! of a URL, spot duplicate screenshots across sessions, list failed sessions. Each
+//! frame's full JSON is kept too, so a [`StepFrame`] can be loaded back from the index.
+//!
+//! Requires the `sqlite` feature. Set [`CaptureOptions::index`] to record every
+//! captured frame under the driver's session id; sessions start as
+//! [`SessionStatus::Running`] and are closed with [`FrameIndex::finish_session`].
+//! Artifact locations are recorded after the frame is moved to its
+//! [`FrameStore`](crate::frame_store::FrameStore), if it has one.
+//!
+//! [`CaptureOptions::index`]: crate::step_frame::CaptureOptions::index
+
+use crate::step_frame::StepFrame;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    id TEXT PRIMARY KEY,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    status TEXT NOT NULL,
+    error TEXT
+);
+CREATE TABLE IF NOT EXISTS frames (
+    session_id TEXT NOT NULL REFERENCES sessions(id),
+    frame_id INTEGER NOT NULL,
+    timestamp TEXT NOT NULL,
+    url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    screenshot_path TEXT NOT NULL,
+    screenshot_hash TEXT,
+    html_path TEXT,
+    html_hash TEXT,
+    visual_dom_path TEXT,
+    visual_dom_hash TEXT,
+    action_type TEXT,
+    action_target TEXT,
+    frame_json TEXT NOT NULL,
+    PRIMARY KEY (session_id, frame_id)
+);
+CREATE INDEX IF NOT EXISTS frames_url ON frames(url);
+CREATE INDEX IF NOT EXISTS frames_screenshot_hash ON frames(screenshot_hash);
+CREATE INDEX IF NOT EXISTS sessions_status ON sessions(status);
+";
+
+/// How a session ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStatus {
+    /// Still recording (or stopped without being finished)
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl SessionStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SessionStatus::Running => "running",
+            SessionStatus::Completed => "completed",
+            SessionStatus::Failed => "failed",
+            SessionStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(status: &str) -> Self {
+        match status {
+            "completed" => SessionStatus::Completed,
+            "failed" => SessionStatus::Failed,
+            "cancelled" => SessionStatus::Cancelled,
+            _ => SessionStatus::Running,
+        }
+    }
+}
+
+/// A recorded session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionRecord {
+    pub id: String,
+
+    /// RFC 3339 timestamp of the first frame (or [`FrameIndex::begin_session`])
+    pub started_at: String,
+
+    /// RFC 3339 timestamp of [`FrameIndex::finish_session`]
+    pub finished_at: Option<String>,
+
+    pub status: SessionStatus,
+
+    /// Why the session failed
+    pub error: Option<String>,
+
+    /// Number of frames recorded
+    pub frame_count: usize,
+}
+
+/// A recorded frame
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FrameRecord {
+    pub session_id: String,
+    pub frame_id: usize,
+    pub timestamp: String,
+    pub url: String,
+    pub title: String,
+    pub screenshot_path: String,
+    pub screenshot_hash: Option<String>,
+    pub html_path: Option<String>,
+    pub visual_dom_path: Option<String>,
+    pub action_type: Option<String>,
+    pub action_target: Option<String>,
+}
+
+/// Frames whose screenshots are identical
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicateScreenshots {
+    /// SHA-256 hash of the screenshots
+    pub hash: String,
+
+    /// `(session id, frame id)` of each frame, in order
+    pub frames: Vec<(String, usize)>,
+}
+
+/// SQLite index of step frame sessions
+///
+/// Calls block while the database is written or queried; they are short, but run
+/// large queries with `spawn_blocking`.
+pub struct FrameIndex {
+    connection: Mutex<Connection>,
+}
+
+impl std::fmt::Debug for FrameIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameIndex").finish_non_exhaustive()
+    }
+}
+
+const FRAME_COLUMNS: &str = "session_id, frame_id, timestamp, url, title, screenshot_path, \
+    screenshot_hash, html_path, visual_dom_path, action_type, action_target";
+
+impl FrameIndex {
+    /// Open (or create) the index at `path`
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// An index that lives only as long as this value
+    pub fn open_in_memory() -> anyhow::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> anyhow::Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record that session `id` started (done implicitly by its first frame)
+    pub fn begin_session(&self, id: &str) -> anyhow::Result<()> {
+        self.connection().execute(
+            "INSERT OR IGNORE INTO sessions (id, started_at, status) VALUES (?1, ?2, ?3)",
+            params![
+                id,
+                chrono::Utc::now().to_rfc3339(),
+                SessionStatus::Running.as_str()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record how session `id` ended
+    pub fn finish_session(
+        &self,
+        id: &str,
+        status: SessionStatus,
+        error: Option<&str>,
+    ) -> anyhow::Result<()> {
+        self.begin_session(id)?;
+        self.connection().execute(
+            "UPDATE sessions SET finished_at = ?2, status = ?3, error = ?4 WHERE id = ?1",
+            params![id, chrono::Utc::now().to_rfc3339(), status.as_str(), error],
+        )?;
+        Ok(())
+    }
+
+    /// Record `frame` as part of session `session_id`, replacing a frame with the same id
+    pub fn record_frame(&self, session_id: &str, frame: &StepFrame) -> anyhow::Result<()> {
+        let frame_json = serde_json::to_string(frame)?;
+        let action = frame.action.as_ref();
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        transaction.execute(
+            "INSERT OR IGNORE INTO sessions (id, started_at, status) VALUES (?1, ?2, ?3)",
+            params![session_id, frame.timestamp, SessionStatus::Running.as_str()],
+        )?;
+        transaction.execute(
+            &format!(
+                "INSERT OR REPLACE INTO frames ({}, html_hash, visual_dom_hash, frame_json)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                FRAME_COLUMNS
+            ),
+            params![
+                session_id,
+                frame.frame_id as i64,
+                frame.timestamp,
+                frame.dom.url,
+                frame.dom.title,
+                frame.screenshot.path,
+                frame.screenshot.hash,
+                frame.dom.html_path,
+                frame.visual_dom.as_ref().map(|v| v.path.as_str()),
+                action.map(|a| a.action_type.as_str()),
+                action.and_then(|a| a.target.as_deref()),
+                frame.dom.html_hash,
+                frame.visual_dom.as_ref().and_then(|v| v.hash.as_deref()),
+                frame_json,
+            ],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Sessions with `status` (all sessions if None), oldest first
+    pub fn sessions(&self, status: Option<SessionStatus>) -> anyhow::Result<Vec<SessionRecord>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT s.id, s.started_at, s.finished_at, s.status, s.error,
+                    (SELECT COUNT(*) FROM frames f WHERE f.session_id = s.id)
+             FROM sessions s
+             WHERE ?1 IS NULL OR s.status = ?1
+             ORDER BY s.started_at, s.id",
+        )?;
+        let sessions = statement
+            .query_map(params![status.map(|s| s.as_str())], |row| {
+                Ok(SessionRecord {
+                    id: row.get(0)?,
+                    started_at: row.get(1)?,
+                    finished_at: row.get(2)?,
+                    status: SessionStatus::parse(&row.get::<_, String>(3)?),
+                    error: row.get(4)?,
+                    frame_count: row.get::<_, i64>(5)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(sessions)
+    }
+
+    /// Frames of session `session_id`, in order
+    pub fn session_frames(&self, session_id: &str) -> anyhow::Result<Vec<FrameRecord>> {
+        self.query_frames(
+            "WHERE session_id = ?1 ORDER BY frame_id",
+            params![session_id],
+        )
+    }
+
+    /// Frames whose URL matches `pattern` (an SQL `LIKE` pattern: `%` matches any
+    /// run of characters, `_` any one character), by session and frame
+    pub fn frames_by_url(&self, pattern: &str) -> anyhow::Result<Vec<FrameRecord>> {
+        self.query_frames(
+            "WHERE url LIKE ?1 ORDER BY session_id, frame_id",
+            params![pattern],
+        )
+    }
+
+    /// Groups of frames (across all sessions) with identical screenshots
+    pub fn duplicate_screenshots(&self) -> anyhow::Result<Vec<DuplicateScreenshots>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT screenshot_hash, session_id, frame_id FROM frames
+             WHERE screenshot_hash IN (
+                 SELECT screenshot_hash FROM frames
+                 WHERE screenshot_hash IS NOT NULL
+                 GROUP BY screenshot_hash HAVING COUNT(*) > 1
+             )
+             ORDER BY screenshot_hash, session_id, frame_id",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)? as usize,
+            ))
+        })?;
+
+        let mut groups: Vec<DuplicateScreenshots> = Vec::new();
+        for row in rows {
+            let (hash, session_id, frame_id) = row?;
+            match groups.last_mut() {
+                Some(group) if group.hash == hash => group.frames.push((session_id, frame_id)),
+                _ => groups.push(DuplicateScreenshots {
+                    hash,
+                    frames: vec![(session_id, frame_id)],
+                }),
+            }
+        }
+        Ok(groups)
+    }
+
+    /// The full step frame recorded as frame `frame_id` of session `session_id`
+    pub fn step_frame(
+        &self,
+        session_id: &str,
+        frame_id: usize,
+    ) -> anyhow::Result<Option<StepFrame>> {
+        let json: Option<String> = self
+            .connection()
+            .query_row(
+                "SELECT frame_json FROM frames WHERE session_id = ?1 AND frame_id = ?2",
+                params![session_id, frame_id as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(json.map(|json| serde_json::from_str(&json)).transpose()?)
+    }
+
+    fn query_frames(
+        &self,
+        filter: &str,
+        params: impl rusqlite::Params,
+    ) -> anyhow::Result<Vec<FrameRecord>> {
+        let connection = self.connection();
+        let mut statement =
+            connection.prepare(&format!("SELECT {} FROM frames {}", FRAME_COLUMNS, filter))?;
+        let frames = statement
+            .query_map(params, |row| {
+                Ok(FrameRecord {
+                    session_id: row.get(0)?,
+                    frame_id: row.get::<_, i64>(1)? as usize,
+                    timestamp: row.get(2)?,
+                    url: row.get(3)?,
+                    title: row.get(4)?,
+                    screenshot_path: row.get(5)?,
+                    screenshot_hash: row.get(6)?,
+                    html_path: row.get(7)?,
+                    visual_dom_path: row.get(8)?,
+                    action_type: row.get(9)?,
+                    action_target: row.get(10)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(frame_id: usize, url: &str, hash: &str) -> StepFrame {
+        serde_json::from_value(serde_json::json!({
+            "frame_id": frame_id,
+            "timestamp": format!("2024-06-01T00:00:0{}Z", frame_id),
+            "elapsed_ms": 0,
+            "screenshot": {
+                "path": format!("screenshots/frame_{:04}.png", frame_id),
+                "format": "png",
+                "size_bytes": 100,
+                "hash": hash,
+            },
+            "dom": { "url": url, "title": "Example" },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_index_queries() {
+        let index = FrameIndex::open_in_memory().unwrap();
+        index
+            .record_frame("a", &frame(0, "https://example.com/", "h1"))
+            .unwrap();
+        index
+            .record_frame("a", &frame(1, "https://example.com/cart", "h2"))
+            .unwrap();
+        index
+            .record_frame("b", &frame(0, "https://example.com/", "h1"))
+            .unwrap();
+        index
+            .finish_session("a", SessionStatus::Completed, None)
+            .unwrap();
+        index
+            .finish_session("b", SessionStatus::Failed, Some("Element not found"))
+            .unwrap();
+
+        let failed = index.sessions(Some(SessionStatus::Failed)).unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].id, "b");
+        assert_eq!(failed[0].error.as_deref(), Some("Element not found"));
+        assert_eq!(failed[0].frame_count, 1);
+        assert_eq!(index.sessions(None).unwrap().len(), 2);
+
+        let cart = index.frames_by_url("%/cart").unwrap();
+        assert_eq!(cart.len(), 1);
+        assert_eq!((cart[0].session_id.as_str(), cart[0].frame_id), ("a", 1));
+        assert_eq!(index.session_frames("a").unwrap().len(), 2);
+
+        assert_eq!(
+            index.duplicate_screenshots().unwrap(),
+            vec![DuplicateScreenshots {
+                hash: "h1".to_string(),
+                frames: vec![("a".to_string(), 0), ("b".to_string(), 0)],
+            }]
+        );
+
+        let loaded = index.step_frame("a", 1).unwrap().unwrap();
+        assert_eq!(loaded.dom.url, "https://example.com/cart");
+        assert!(index.step_frame("a", 7).unwrap().is_none());
+    }
+}
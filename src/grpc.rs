@@ -0,0 +1,432 @@
+//! gRPC Server
+//!
+//! A strongly-typed alternative to the HTTP API for integrators that prefer
+//! streaming RPC (`proto/robert_webdriver.proto`). Unlike `/inference`, which shares
+//! one Chrome session, clients open their own sessions, run scripts in them with
+//! per-command progress streamed back, and capture or fetch step frames.
+//!
+//! Served on `127.0.0.1:<server.grpc_port>` next to the HTTP server when built with
+//! `--features grpc`. The same `server.auth_token` is required, as
+//! `authorization: Bearer <token>` metadata.
+
+// tonic's interceptors and handlers return `Status`, which is large
+#![allow(clippy::result_large_err)]
+
+use crate::browser::chrome::ChromeDriver;
+use crate::cdp::{CdpExecutor, CdpScript, CdpScriptGenerator, CommandStatus};
+use crate::config::Config;
+use crate::step_frame::{capture_step_frame, CaptureOptions, StepFrame};
+use futures::Stream;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{mpsc, Mutex};
+use tokio_util::sync::CancellationToken;
+use tonic::service::interceptor::InterceptedService;
+use tonic::{Request, Response, Status};
+
+/// Types and service stubs generated from `proto/robert_webdriver.proto`
+pub mod proto {
+    tonic::include_proto!("robert.webdriver.v1");
+}
+
+use proto::execute_script_request::Script;
+use proto::execution_event::Event;
+use proto::web_driver_server::{WebDriver, WebDriverServer};
+
+/// Run the gRPC server on `127.0.0.1:<port>` until `shutdown` is cancelled
+///
+/// Sessions still open at shutdown are closed.
+pub async fn serve(config: Config, port: u16, shutdown: CancellationToken) -> anyhow::Result<()> {
+    let auth = config
+        .server
+        .auth_token
+        .clone()
+        .map(|t| format!("Bearer {}", t));
+    let service = GrpcService {
+        generator: config.generator()?,
+        config,
+        sessions: Mutex::new(HashMap::new()),
+        counter: AtomicU64::new(0),
+        shutdown: shutdown.clone(),
+    };
+    let service = Arc::new(service);
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    tracing::info!("gRPC listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(InterceptedService::new(
+            WebDriverServer::from_arc(service.clone()),
+            move |request| check_auth(auth.as_deref(), request),
+        ))
+        .serve_with_shutdown(addr, shutdown.cancelled())
+        .await
+        .map_err(|e| anyhow::anyhow!("gRPC server on port {} failed: {}", port, e))?;
+
+    let open: Vec<Arc<GrpcSession>> = service
+        .sessions
+        .lock()
+        .await
+        .drain()
+        .map(|(_, s)| s)
+        .collect();
+    for session in open {
+        session.close().await;
+    }
+    Ok(())
+}
+
+/// Reject calls without the expected `authorization` metadata (no-op if None)
+fn check_auth(expected: Option<&str>, request: Request<()>) -> Result<Request<()>, Status> {
+    let Some(expected) = expected else {
+        return Ok(request);
+    };
+    let header = request
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok());
+    if header == Some(expected) {
+        Ok(request)
+    } else {
+        Err(Status::unauthenticated("Missing or invalid bearer token"))
+    }
+}
+
+struct GrpcService {
+    config: Config,
+    generator: CdpScriptGenerator,
+    sessions: Mutex<HashMap<String, Arc<GrpcSession>>>,
+    counter: AtomicU64,
+    /// Server-wide token; cancelled on Ctrl-C to stop running scripts
+    shutdown: CancellationToken,
+}
+
+/// A Chrome session opened by a client
+struct GrpcSession {
+    id: String,
+    created: String,
+    /// Held for the duration of a script run or capture
+    state: Mutex<SessionState>,
+}
+
+struct SessionState {
+    driver: Option<ChromeDriver>,
+    started: Instant,
+    frames: Vec<StepFrame>,
+}
+
+impl GrpcSession {
+    fn info(&self) -> proto::Session {
+        proto::Session {
+            id: self.id.clone(),
+            created: self.created.clone(),
+            busy: self.state.try_lock().is_err(),
+        }
+    }
+
+    async fn close(&self) {
+        let driver = self.state.lock().await.driver.take();
+        if let Some(driver) = driver {
+            if let Err(e) = driver.close().await {
+                tracing::warn!(session = %self.id, error = %e, "Failed to close Chrome session");
+            }
+        }
+    }
+}
+
+impl GrpcService {
+    async fn session(&self, id: &str) -> Result<Arc<GrpcSession>, Status> {
+        self.sessions
+            .lock()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Status::not_found(format!("Session not found: {}", id)))
+    }
+
+    /// Directory step frames of `session` are written to
+    fn frame_options(&self, session: &GrpcSession) -> CaptureOptions {
+        let dir = self.config.artifacts.root.join("grpc").join(&session.id);
+        CaptureOptions {
+            screenshot_dir: dir.join("screenshots"),
+            dom_dir: Some(dir.join("dom")),
+            ..Default::default()
+        }
+    }
+}
+
+type ExecutionStream = Pin<Box<dyn Stream<Item = Result<proto::ExecutionEvent, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl WebDriver for GrpcService {
+    type ExecuteScriptStream = ExecutionStream;
+
+    async fn create_session(
+        &self,
+        request: Request<proto::CreateSessionRequest>,
+    ) -> Result<Response<proto::Session>, Status> {
+        let driver = ChromeDriver::from_config(&self.config)
+            .await
+            .map_err(|e| Status::unavailable(format!("Failed to launch Chrome: {}", e)))?;
+        if let Some(url) = request.into_inner().url {
+            if let Err(e) = driver.navigate(&url).await {
+                let _ = driver.close().await;
+                return Err(Status::invalid_argument(format!(
+                    "Failed to open {}: {}",
+                    url, e
+                )));
+            }
+        }
+
+        let seq = self.counter.fetch_add(1, Ordering::SeqCst);
+        let unique_id = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let session = Arc::new(GrpcSession {
+            id: format!("session-{}-{}", unique_id, seq),
+            created: chrono::Utc::now().to_rfc3339(),
+            state: Mutex::new(SessionState {
+                driver: Some(driver),
+                started: Instant::now(),
+                frames: Vec::new(),
+            }),
+        });
+        tracing::info!(session = %session.id, "Opened gRPC session");
+
+        let info = session.info();
+        self.sessions
+            .lock()
+            .await
+            .insert(session.id.clone(), session);
+        Ok(Response::new(info))
+    }
+
+    async fn list_sessions(
+        &self,
+        _request: Request<proto::ListSessionsRequest>,
+    ) -> Result<Response<proto::ListSessionsResponse>, Status> {
+        let mut sessions: Vec<proto::Session> = self
+            .sessions
+            .lock()
+            .await
+            .values()
+            .map(|s| s.info())
+            .collect();
+        sessions.sort_by(|a, b| a.created.cmp(&b.created));
+        Ok(Response::new(proto::ListSessionsResponse { sessions }))
+    }
+
+    async fn close_session(
+        &self,
+        request: Request<proto::CloseSessionRequest>,
+    ) -> Result<Response<proto::CloseSessionResponse>, Status> {
+        let id = request.into_inner().session_id;
+        let session = self
+            .sessions
+            .lock()
+            .await
+            .remove(&id)
+            .ok_or_else(|| Status::not_found(format!("Session not found: {}", id)))?;
+        // Waits for a running script to finish
+        session.close().await;
+        tracing::info!(session = %id, "Closed gRPC session");
+        Ok(Response::new(proto::CloseSessionResponse {}))
+    }
+
+    async fn execute_script(
+        &self,
+        request: Request<proto::ExecuteScriptRequest>,
+    ) -> Result<Response<Self::ExecuteScriptStream>, Status> {
+        let request = request.into_inner();
+        let session = self.session(&request.session_id).await?;
+        let script = match request.script {
+            Some(Script::ScriptJson(json)) => serde_json::from_str::<CdpScript>(&json)
+                .map_err(|e| Status::invalid_argument(format!("Invalid script: {}", e)))?,
+            Some(Script::Prompt(prompt)) => self
+                .generator
+                .generate(&prompt)
+                .await
+                .map_err(|e| Status::internal(format!("Generation failed: {}", e)))?,
+            None => {
+                return Err(Status::invalid_argument(
+                    "Either script_json or prompt is required",
+                ))
+            }
+        };
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let cancel = self.shutdown.child_token();
+        tokio::spawn(async move {
+            let state = session.state.lock().await;
+            let Some(driver) = state.driver.as_ref() else {
+                let _ = tx.send(Err(Status::not_found("Session was closed")));
+                return;
+            };
+            let page = match driver.current_page().await {
+                Ok(page) => page,
+                Err(e) => {
+                    let _ = tx.send(Err(Status::internal(format!(
+                        "Failed to get current page: {}",
+                        e
+                    ))));
+                    return;
+                }
+            };
+
+            let executor = CdpExecutor::new(page)
+                .with_driver(driver)
+                .with_cancellation(cancel.clone());
+            let outcome = executor
+                .execute_script_with_progress(&script, |result| {
+                    let event = Event::Command(command_result(result));
+                    // The client went away: stop at the next command boundary
+                    if tx
+                        .send(Ok(proto::ExecutionEvent { event: Some(event) }))
+                        .is_err()
+                    {
+                        cancel.cancel();
+                    }
+                })
+                .await;
+
+            let event = match outcome {
+                Ok(report) => Ok(proto::ExecutionEvent {
+                    event: Some(Event::Report(execution_report(&report))),
+                }),
+                Err(e) => Err(Status::internal(format!("Execution failed: {}", e))),
+            };
+            let _ = tx.send(event);
+        });
+
+        Ok(Response::new(Box::pin(
+            tokio_stream::wrappers::UnboundedReceiverStream::new(rx),
+        )))
+    }
+
+    async fn capture_step_frame(
+        &self,
+        request: Request<proto::CaptureStepFrameRequest>,
+    ) -> Result<Response<proto::StepFrame>, Status> {
+        let request = request.into_inner();
+        let session = self.session(&request.session_id).await?;
+        let options = self.frame_options(&session);
+
+        let mut state = session.state.lock().await;
+        let Some(driver) = state.driver.as_ref() else {
+            return Err(Status::not_found("Session was closed"));
+        };
+        let frame = capture_step_frame(
+            driver,
+            state.frames.len(),
+            state.started.elapsed().as_millis() as u64,
+            &options,
+            request.instruction,
+            None,
+        )
+        .await
+        .map_err(|e| Status::internal(format!("Failed to capture step frame: {}", e)))?;
+        state.frames.push(frame.clone());
+        drop(state);
+
+        Ok(Response::new(step_frame(&frame).await?))
+    }
+
+    async fn get_step_frame(
+        &self,
+        request: Request<proto::GetStepFrameRequest>,
+    ) -> Result<Response<proto::StepFrame>, Status> {
+        let request = request.into_inner();
+        let session = self.session(&request.session_id).await?;
+        let frame = session
+            .state
+            .lock()
+            .await
+            .frames
+            .get(request.frame_id as usize)
+            .cloned()
+            .ok_or_else(|| {
+                Status::not_found(format!("Step frame not found: {}", request.frame_id))
+            })?;
+        Ok(Response::new(step_frame(&frame).await?))
+    }
+}
+
+fn command_result(result: &crate::cdp::CommandResult) -> proto::CommandResult {
+    let status = match result.status {
+        CommandStatus::Success => proto::CommandStatus::Success,
+        CommandStatus::Failed => proto::CommandStatus::Failed,
+        CommandStatus::Skipped => proto::CommandStatus::Skipped,
+    };
+    proto::CommandResult {
+        step: result.step as u32,
+        method: result.method.clone(),
+        status: status as i32,
+        duration_ms: result.duration.as_millis() as u64,
+        response_json: result.response.as_ref().map(|r| r.to_string()),
+        error: result.error.clone(),
+        saved_file: result.saved_file.clone(),
+    }
+}
+
+fn execution_report(report: &crate::cdp::ExecutionReport) -> proto::ExecutionReport {
+    proto::ExecutionReport {
+        script_name: report.script_name.clone(),
+        total_commands: report.total_commands as u32,
+        successful: report.successful as u32,
+        failed: report.failed as u32,
+        skipped: report.skipped as u32,
+        duration_ms: report.total_duration.as_millis() as u64,
+        cancelled: report.cancelled,
+        results: report.results.iter().map(command_result).collect(),
+    }
+}
+
+/// `frame` with its screenshot loaded
+async fn step_frame(frame: &StepFrame) -> Result<proto::StepFrame, Status> {
+    let screenshot = tokio::fs::read(&frame.screenshot.path).await.map_err(|e| {
+        Status::internal(format!(
+            "Failed to read screenshot {}: {}",
+            frame.screenshot.path, e
+        ))
+    })?;
+    let frame_json = serde_json::to_string(frame)
+        .map_err(|e| Status::internal(format!("Failed to serialize step frame: {}", e)))?;
+    Ok(proto::StepFrame {
+        frame_id: frame.frame_id as u32,
+        timestamp: frame.timestamp.clone(),
+        elapsed_ms: frame.elapsed_ms,
+        url: frame.dom.url.clone(),
+        title: frame.dom.title.clone(),
+        screenshot,
+        screenshot_format: frame.screenshot.format.clone(),
+        frame_json,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_auth() {
+        let request = |token: Option<&str>| {
+            let mut request = Request::new(());
+            if let Some(token) = token {
+                request
+                    .metadata_mut()
+                    .insert("authorization", token.parse().unwrap());
+            }
+            request
+        };
+
+        assert!(check_auth(None, request(None)).is_ok());
+        assert!(check_auth(Some("Bearer secret"), request(Some("Bearer secret"))).is_ok());
+        let denied = check_auth(Some("Bearer secret"), request(Some("Bearer wrong"))).unwrap_err();
+        assert_eq!(denied.code(), tonic::Code::Unauthenticated);
+        assert!(check_auth(Some("Bearer secret"), request(None)).is_err());
+    }
+}
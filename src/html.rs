@@ -0,0 +1,320 @@
+//! Sanitization of Saved Frame HTML
+//!
+//! Raw page source is often several megabytes of inline scripts, styles, and
+//! tracking markup that says little about what was on screen. [`HtmlOptions`]
+//! controls what is kept in the HTML saved with each step frame:
+//!
+//! - `<script>`/`<noscript>` tags and inline `on*` handlers can be stripped
+//! - `<style>` tags and stylesheet links can be stripped, or readable external
+//!   stylesheets inlined so the snapshot renders without the network
+//! - 1x1 tracking pixels can be dropped
+//! - the result can be capped at a byte size, ending with a truncation marker
+//! - a text-only rendition (the page's visible text) can be saved alongside
+//!
+//! All processing happens on a detached copy of the document; the live page is
+//! not modified.
+//!
+//! For prompts, [`PAGE_TREE_SCRIPT`] reads the rendered page as a
+//! [`DomNode`](crate::model::llm_dom::DomNode) tree, which
+//! `ChromeDriver::serialize_dom_for_llm` outlines within a token budget.
+
+use crate::error::Result;
+use crate::redaction::{ResolvedRedaction, MASK_CLONE_SCRIPT};
+use crate::ChromeDriver;
+use serde::{Deserialize, Serialize};
+
+/// What to strip, inline, and keep in saved frame HTML
+///
+/// Everything is off by default, which saves the page source unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HtmlOptions {
+    /// Remove `<script>` and `<noscript>` tags and inline `on*` event handlers
+    pub strip_scripts: bool,
+
+    /// Remove `<style>` tags and stylesheet `<link>`s
+    pub strip_styles: bool,
+
+    /// Remove images loaded at 1x1 or declared with a width and height of at most 1
+    pub strip_tracking_pixels: bool,
+
+    /// Replace stylesheet `<link>`s with `<style>` tags holding their rules
+    ///
+    /// Cross-origin stylesheets whose rules can't be read are left as links.
+    pub inline_stylesheets: bool,
+
+    /// Cap the saved HTML (and text) at this many bytes, ending with a truncation marker
+    pub max_bytes: Option<usize>,
+
+    /// Also save the page's visible text (`frame_NNNN.txt`)
+    pub save_text: bool,
+}
+
+impl HtmlOptions {
+    /// Strip scripts, styles, and tracking pixels, and save a text rendition
+    ///
+    /// Suited to frames that are read by a language model rather than re-rendered.
+    pub fn for_llm() -> Self {
+        Self {
+            strip_scripts: true,
+            strip_styles: true,
+            strip_tracking_pixels: true,
+            save_text: true,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the page needs to be serialized by script rather than read as-is
+    fn needs_processing(&self) -> bool {
+        self.strip_scripts
+            || self.strip_styles
+            || self.strip_tracking_pixels
+            || self.inline_stylesheets
+            || self.save_text
+    }
+}
+
+/// HTML (and optional text) ready to be saved with a frame
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CapturedHtml {
+    pub html: String,
+    pub text: Option<String>,
+    /// Size of the HTML before it was truncated, if it was
+    #[serde(skip)]
+    pub original_size: Option<usize>,
+}
+
+/// Serialize the current page according to `options`, masking `redaction`'s elements
+pub(crate) async fn capture_html(
+    driver: &ChromeDriver,
+    options: &HtmlOptions,
+    redaction: Option<&ResolvedRedaction>,
+) -> Result<CapturedHtml> {
+    let mut captured = if !options.needs_processing() && redaction.is_none() {
+        CapturedHtml {
+            html: driver.get_page_source().await?,
+            text: None,
+            original_size: None,
+        }
+    } else {
+        let script = format!(
+            "({})({}, {}, {})",
+            CAPTURE_SCRIPT,
+            serde_json::to_string(options).unwrap_or_default(),
+            serde_json::to_string(redaction.map_or(&[][..], ResolvedRedaction::selectors))
+                .unwrap_or_default(),
+            MASK_CLONE_SCRIPT,
+        );
+        driver.execute_script_typed(&script).await?
+    };
+
+    if let Some(max_bytes) = options.max_bytes {
+        let size = captured.html.len();
+        if truncate_html(&mut captured.html, max_bytes) {
+            captured.original_size = Some(size);
+        }
+        if let Some(text) = &mut captured.text {
+            truncate_text(text, max_bytes);
+        }
+    }
+    Ok(captured)
+}
+
+/// Cut `html` to at most `max_bytes`, ending with a comment noting the original size
+///
+/// The cut is moved back before any tag left open. Returns whether anything was cut.
+pub(crate) fn truncate_html(html: &mut String, max_bytes: usize) -> bool {
+    if html.len() <= max_bytes {
+        return false;
+    }
+    let marker = format!("<!-- truncated: {} of {} bytes -->", max_bytes, html.len());
+    let mut end = char_boundary(html, max_bytes.saturating_sub(marker.len()));
+    if let Some(open) = html[..end].rfind('<') {
+        if !html[open..end].contains('>') {
+            end = open;
+        }
+    }
+    html.truncate(end);
+    html.push_str(&marker);
+    true
+}
+
+/// Cut `text` to at most `max_bytes`, ending with a line noting the original size
+pub(crate) fn truncate_text(text: &mut String, max_bytes: usize) -> bool {
+    if text.len() <= max_bytes {
+        return false;
+    }
+    let marker = format!("\n[truncated: {} of {} bytes]", max_bytes, text.len());
+    let end = char_boundary(text, max_bytes.saturating_sub(marker.len()));
+    text.truncate(end);
+    text.push_str(&marker);
+    true
+}
+
+/// Largest char boundary in `s` at or before `index`
+fn char_boundary(s: &str, mut index: usize) -> usize {
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Serializes a processed copy of the document and, if requested, its visible text
+///
+/// Live elements are paired with their copies by position, so stylesheets and
+/// image sizes are read before the copy is changed.
+const CAPTURE_SCRIPT: &str = r#"(options, redacted, maskClone) => {
+    const live = document.documentElement;
+    const root = live.cloneNode(true);
+    const pairs = (selector) => {
+        const copies = root.querySelectorAll(selector);
+        return Array.from(live.querySelectorAll(selector), (el, i) => [el, copies[i]]);
+    };
+
+    if (options.inline_stylesheets) {
+        for (const [link, copy] of pairs('link[rel~="stylesheet"]')) {
+            let rules = null;
+            try {
+                rules = link.sheet && link.sheet.cssRules;
+            } catch (e) {
+                // Cross-origin stylesheet
+            }
+            if (!rules) continue;
+            const style = document.createElement('style');
+            style.setAttribute('data-href', link.href);
+            if (link.media) style.setAttribute('media', link.media);
+            style.textContent = Array.from(rules, (rule) => rule.cssText).join('\n');
+            copy.replaceWith(style);
+        }
+    }
+
+    if (options.strip_tracking_pixels) {
+        const tiny = (value) => value !== null && Number(value) <= 1;
+        for (const [img, copy] of pairs('img')) {
+            const loaded = img.complete && img.naturalWidth === 1 && img.naturalHeight === 1;
+            const declared = tiny(img.getAttribute('width')) && tiny(img.getAttribute('height'));
+            if (loaded || declared) copy.remove();
+        }
+    }
+
+    maskClone(root, redacted);
+
+    if (options.strip_scripts) {
+        root.querySelectorAll('script, noscript').forEach((el) => el.remove());
+        for (const el of root.querySelectorAll('*')) {
+            for (const attr of Array.from(el.attributes)) {
+                if (/^on/i.test(attr.name)) el.removeAttribute(attr.name);
+            }
+        }
+    }
+    if (options.strip_styles) {
+        root.querySelectorAll('style, link[rel~="stylesheet"]').forEach((el) => el.remove());
+    }
+
+    let text = null;
+    if (options.save_text) {
+        text = document.body ? document.body.innerText : '';
+        for (const selector of redacted) {
+            for (const el of document.querySelectorAll(selector)) {
+                const inner = el.innerText;
+                if (inner && inner.trim()) text = text.split(inner).join(inner.replace(/\S/g, '█'));
+            }
+        }
+    }
+
+    const doctype = document.doctype ? new XMLSerializer().serializeToString(document.doctype) : '';
+    return { html: doctype + root.outerHTML, text };
+}"#;
+
+/// Reads the rendered page below `<body>` as a JSON `DomNode` tree
+///
+/// Skips what isn't rendered (`display: none`, `visibility: hidden`, `opacity: 0`,
+/// `aria-hidden`), reads shadow roots and slots as they are composed, and keeps only
+/// attributes that say what an element is or does. Form fields report their live
+/// values, except passwords. Returned as a string, since deep trees exceed what the
+/// protocol returns by value.
+pub(crate) const PAGE_TREE_SCRIPT: &str = r#"(() => {
+    const SKIP = new Set(['script', 'style', 'noscript', 'template', 'head', 'meta', 'link']);
+    const LEAVES = new Set(['svg', 'iframe', 'video', 'audio', 'canvas', 'math']);
+    const KEEP = ['id', 'role', 'aria-label', 'aria-expanded', 'href', 'name', 'type', 'placeholder',
+        'alt', 'title', 'for', 'value'];
+    const FIELDS = new Set(['input', 'textarea', 'select']);
+
+    const visit = (el) => {
+        const tag = el.localName;
+        if (SKIP.has(tag) || el.getAttribute('aria-hidden') === 'true') return null;
+        const contents = window.getComputedStyle(el).display === 'contents';
+        if (!contents && tag !== 'slot'
+            && !el.checkVisibility({ checkOpacity: true, checkVisibilityCSS: true })) return null;
+
+        const attrs = [];
+        for (const name of KEEP) {
+            let value = el.getAttribute(name);
+            if (name === 'value' && FIELDS.has(tag)) value = el.value || null;
+            if (value === null || (name === 'value' && el.type === 'password')) continue;
+            attrs.push([name, value.trim()]);
+        }
+        if (el.checked) attrs.push(['checked', '']);
+        if (el.disabled) attrs.push(['disabled', '']);
+        if (tag === 'option' && el.selected) attrs.push(['selected', '']);
+
+        const children = [];
+        if (!LEAVES.has(tag)) {
+            const nodes = tag === 'slot'
+                ? el.assignedNodes({ flatten: true })
+                : (el.shadowRoot || el).childNodes;
+            for (const node of nodes) {
+                if (node.nodeType === Node.TEXT_NODE) {
+                    if (node.textContent.trim()) children.push(node.textContent);
+                } else if (node.nodeType === Node.ELEMENT_NODE) {
+                    const child = visit(node);
+                    if (child) children.push(child);
+                }
+            }
+        }
+        return { tag, attrs, children };
+    };
+
+    return JSON.stringify(visit(document.body || document.documentElement));
+})()"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_html() {
+        let mut html = "<html><body><p>hello</p><p>world</p></body></html>".to_string();
+        assert!(!truncate_html(&mut html, 1000));
+
+        let original = html.len();
+        let mut long = html.repeat(10);
+        assert!(truncate_html(&mut long, 100));
+        assert!(long.len() <= 100);
+        assert!(long.ends_with(&format!(
+            "<!-- truncated: 100 of {} bytes -->",
+            original * 10
+        )));
+        // The cut doesn't leave a tag half-written
+        let body = long.split("<!--").next().unwrap();
+        assert!(body.rfind('<') < body.rfind('>'));
+    }
+
+    #[test]
+    fn test_truncate_text_char_boundary() {
+        let mut text = "█".repeat(100);
+        assert!(truncate_text(&mut text, 64));
+        assert!(text.len() <= 64);
+        assert!(text.ends_with("[truncated: 64 of 300 bytes]"));
+        assert!(text.starts_with('█'));
+    }
+
+    #[test]
+    fn test_options_default_saves_source_unchanged() {
+        assert!(!HtmlOptions::default().needs_processing());
+        assert!(HtmlOptions::for_llm().needs_processing());
+        let options: HtmlOptions = serde_json::from_str(r#"{"max_bytes": 1024}"#).unwrap();
+        assert_eq!(options.max_bytes, Some(1024));
+        assert!(!options.needs_processing());
+    }
+}
@@ -0,0 +1,389 @@
+//! Async Job Tracking
+//!
+//! Long-running inferences (script generation + execution) can take minutes.
+//! Instead of holding an HTTP connection open, the server registers each request
+//! as a job that clients can poll for status, progress, and partial results.
+//!
+//! Finished jobs are kept for polling until the store holds more than its limit of
+//! them, then the oldest are dropped. Their reports outlive them in the run history.
+
+use crate::cdp::{CommandResult, ExecutionReport, GenerationUsage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+/// Lifecycle state of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Accepted but not started yet
+    Queued,
+    /// Generating the CDP script from the prompt
+    Generating,
+    /// Executing the generated script
+    Running,
+    /// Finished successfully
+    Completed,
+    /// Finished with an error
+    Failed,
+    /// Cancelled by the client
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job has reached a final state
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+/// Step progress of a running job
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct JobProgress {
+    /// Number of commands that have finished executing
+    pub completed_steps: usize,
+
+    /// Total number of commands in the script (known once generation finishes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_steps: Option<usize>,
+}
+
+/// Snapshot of a job as reported to clients
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Job {
+    /// Unique job identifier
+    pub id: String,
+
+    /// Prompt that started this job
+    pub prompt: String,
+
+    /// Current status
+    pub status: JobStatus,
+
+    /// ISO 8601 timestamp when the job was created
+    pub created: String,
+
+    /// ISO 8601 timestamp of the last status change
+    pub updated: String,
+
+    /// Step progress
+    pub progress: JobProgress,
+
+    /// Browser session the script runs in (once Chrome is up); pass it back to keep
+    /// working on the same pages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+
+    /// Human-readable status or error message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Command results collected so far (partial while running)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<CommandResult>,
+
+    /// Final execution report (once the job has completed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_report: Option<ExecutionReport>,
+
+    /// Tokens and cost of generating the script (None until generated, or if it
+    /// came from the script cache)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<GenerationUsage>,
+}
+
+/// Finished jobs a store keeps unless told otherwise
+pub const DEFAULT_MAX_FINISHED: usize = 100;
+
+struct JobEntry {
+    job: Job,
+    cancel_token: CancellationToken,
+    /// When the job reached a terminal state
+    finished: Option<Instant>,
+}
+
+/// Thread-safe in-memory registry of jobs
+pub struct JobStore {
+    jobs: RwLock<HashMap<String, JobEntry>>,
+    counter: AtomicU64,
+    max_finished: usize,
+}
+
+impl JobStore {
+    /// Create an empty job store keeping up to [`DEFAULT_MAX_FINISHED`] finished jobs
+    pub fn new() -> Self {
+        Self::with_max_finished(DEFAULT_MAX_FINISHED)
+    }
+
+    /// Create an empty job store keeping up to `max_finished` finished jobs
+    ///
+    /// Running jobs are never dropped, and neither is the job that finished last.
+    pub fn with_max_finished(max_finished: usize) -> Self {
+        Self {
+            jobs: RwLock::new(HashMap::new()),
+            counter: AtomicU64::new(0),
+            max_finished: max_finished.max(1),
+        }
+    }
+
+    /// Register a new queued job and return its snapshot
+    pub fn create(&self, prompt: &str) -> Job {
+        self.create_with_token(prompt, CancellationToken::new())
+    }
+
+    /// Register a new queued job whose cancellation is driven by `cancel_token`
+    ///
+    /// Pass a child of a server-wide token so shutdown (e.g. Ctrl-C) cancels
+    /// every running job.
+    pub fn create_with_token(&self, prompt: &str, cancel_token: CancellationToken) -> Job {
+        let now = chrono::Utc::now().to_rfc3339();
+        let seq = self.counter.fetch_add(1, Ordering::SeqCst);
+        let unique_id = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+
+        let job = Job {
+            id: format!("job-{}-{}", unique_id, seq),
+            prompt: prompt.to_string(),
+            status: JobStatus::Queued,
+            created: now.clone(),
+            updated: now,
+            progress: JobProgress::default(),
+            session_id: None,
+            message: None,
+            results: Vec::new(),
+            execution_report: None,
+            usage: None,
+        };
+
+        self.jobs.write().unwrap().insert(
+            job.id.clone(),
+            JobEntry {
+                job: job.clone(),
+                cancel_token,
+                finished: None,
+            },
+        );
+
+        job
+    }
+
+    /// Get a snapshot of a job
+    pub fn get(&self, id: &str) -> Option<Job> {
+        self.jobs.read().unwrap().get(id).map(|e| e.job.clone())
+    }
+
+    /// List snapshots of all jobs
+    pub fn list(&self) -> Vec<Job> {
+        self.jobs
+            .read()
+            .unwrap()
+            .values()
+            .map(|e| e.job.clone())
+            .collect()
+    }
+
+    /// Get the cancellation token the job's worker should observe
+    pub fn cancellation_token(&self, id: &str) -> Option<CancellationToken> {
+        self.jobs
+            .read()
+            .unwrap()
+            .get(id)
+            .map(|e| e.cancel_token.clone())
+    }
+
+    /// Apply an update to a job
+    ///
+    /// Updates to jobs that already reached a terminal state are ignored, so a
+    /// task finishing after cancellation cannot overwrite the Cancelled status.
+    pub fn update<F>(&self, id: &str, f: F)
+    where
+        F: FnOnce(&mut Job),
+    {
+        let mut jobs = self.jobs.write().unwrap();
+        let Some(entry) = jobs.get_mut(id) else {
+            return;
+        };
+        if entry.job.status.is_terminal() {
+            return;
+        }
+        f(&mut entry.job);
+        entry.job.updated = chrono::Utc::now().to_rfc3339();
+        if entry.job.status.is_terminal() {
+            entry.finished = Some(Instant::now());
+            self.evict_finished(&mut jobs);
+        }
+    }
+
+    /// Set job status and message
+    pub fn set_status(&self, id: &str, status: JobStatus, message: Option<String>) {
+        self.update(id, |job| {
+            job.status = status;
+            if message.is_some() {
+                job.message = message;
+            }
+        });
+    }
+
+    /// Record a finished command for a running job
+    pub fn push_result(&self, id: &str, result: &CommandResult) {
+        self.update(id, |job| {
+            job.progress.completed_steps += 1;
+            job.results.push(result.clone());
+        });
+    }
+
+    /// Attach the final execution report, even if the job was already cancelled
+    ///
+    /// A cancelled script still produces a report (remaining steps marked Skipped),
+    /// which is worth keeping for the client.
+    pub fn attach_report(&self, id: &str, report: ExecutionReport) {
+        if let Some(entry) = self.jobs.write().unwrap().get_mut(id) {
+            entry.job.results = report.results.clone();
+            entry.job.execution_report = Some(report);
+            entry.job.updated = chrono::Utc::now().to_rfc3339();
+        }
+    }
+
+    /// Cancel a job, signalling its worker to stop at the next command boundary
+    ///
+    /// Returns the job snapshot after cancellation, or None if the job is unknown.
+    /// Jobs that already finished are returned unchanged.
+    pub fn cancel(&self, id: &str) -> Option<Job> {
+        let mut jobs = self.jobs.write().unwrap();
+        let entry = jobs.get_mut(id)?;
+        if entry.job.status.is_terminal() {
+            return Some(entry.job.clone());
+        }
+
+        entry.cancel_token.cancel();
+        entry.job.status = JobStatus::Cancelled;
+        entry.job.message = Some("Job cancelled by client".to_string());
+        entry.job.updated = chrono::Utc::now().to_rfc3339();
+        entry.finished = Some(Instant::now());
+        let job = entry.job.clone();
+        self.evict_finished(&mut jobs);
+        Some(job)
+    }
+
+    /// Drop the oldest finished jobs beyond `max_finished`
+    fn evict_finished(&self, jobs: &mut HashMap<String, JobEntry>) {
+        let mut finished: Vec<(Instant, String)> = jobs
+            .iter()
+            .filter_map(|(id, entry)| Some((entry.finished?, id.clone())))
+            .collect();
+        if finished.len() <= self.max_finished {
+            return;
+        }
+        finished.sort();
+        let excess = finished.len() - self.max_finished;
+        for (_, id) in finished.into_iter().take(excess) {
+            tracing::debug!(job = %id, "Dropping finished job");
+            jobs.remove(&id);
+        }
+    }
+}
+
+impl Default for JobStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cdp::CommandStatus;
+    use std::time::Duration;
+
+    fn result(step: usize) -> CommandResult {
+        CommandResult {
+            step,
+            method: "Page.navigate".to_string(),
+            status: CommandStatus::Success,
+            duration: Duration::from_millis(10),
+            response: None,
+            error: None,
+            saved_file: None,
+            frames: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_create_and_get() {
+        let store = JobStore::new();
+        let job = store.create("take a screenshot");
+
+        assert_eq!(job.status, JobStatus::Queued);
+        let fetched = store.get(&job.id).unwrap();
+        assert_eq!(fetched.prompt, "take a screenshot");
+        assert!(store.get("missing").is_none());
+
+        // IDs must be unique
+        let other = store.create("another");
+        assert_ne!(job.id, other.id);
+        assert_eq!(store.list().len(), 2);
+    }
+
+    #[test]
+    fn test_progress_updates() {
+        let store = JobStore::new();
+        let job = store.create("extract data");
+
+        store.set_status(&job.id, JobStatus::Running, None);
+        store.update(&job.id, |j| j.progress.total_steps = Some(2));
+        store.push_result(&job.id, &result(1));
+
+        let job = store.get(&job.id).unwrap();
+        assert_eq!(job.status, JobStatus::Running);
+        assert_eq!(job.progress.completed_steps, 1);
+        assert_eq!(job.progress.total_steps, Some(2));
+        assert_eq!(job.results.len(), 1);
+    }
+
+    #[test]
+    fn test_cancel_is_final() {
+        let store = JobStore::new();
+        let job = store.create("long workflow");
+
+        let token = store.cancellation_token(&job.id).unwrap();
+        let cancelled = store.cancel(&job.id).unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        assert!(token.is_cancelled());
+
+        // Late updates from the worker must not resurrect the job
+        store.set_status(&job.id, JobStatus::Completed, Some("done".to_string()));
+        assert_eq!(store.get(&job.id).unwrap().status, JobStatus::Cancelled);
+
+        assert!(store.cancel("missing").is_none());
+    }
+
+    #[test]
+    fn test_evicts_oldest_finished() {
+        let store = JobStore::with_max_finished(2);
+        let running = store.create("still running");
+        store.set_status(&running.id, JobStatus::Running, None);
+
+        let first = store.create("first");
+        let second = store.create("second");
+        let third = store.create("third");
+        store.set_status(&first.id, JobStatus::Completed, None);
+        store.set_status(&second.id, JobStatus::Failed, None);
+        assert_eq!(store.list().len(), 4);
+
+        store.cancel(&third.id);
+        assert!(store.get(&first.id).is_none());
+        assert_eq!(store.get(&second.id).unwrap().status, JobStatus::Failed);
+        assert_eq!(store.get(&third.id).unwrap().status, JobStatus::Cancelled);
+        assert_eq!(store.get(&running.id).unwrap().status, JobStatus::Running);
+        assert_eq!(store.list().len(), 3);
+    }
+}
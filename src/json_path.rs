@@ -0,0 +1,315 @@
+//! JSONPath Selection
+//!
+//! A small JSONPath subset for picking values out of JSON responses, accepting
+//! jq-style shorthand as well:
+//!
+//! | Syntax | Selects |
+//! |--------|---------|
+//! | `$` | the whole document |
+//! | `.name`, `['name']` | an object member |
+//! | `[2]`, `[-1]` | an array element (negative counts from the end) |
+//! | `.*`, `[*]`, `[]` | every member or element |
+//! | `..name`, `..*` | matching members at any depth |
+//!
+//! The leading `$` is optional, so `.data.items[].id` (jq) and
+//! `$.data.items[*].id` (JSONPath) are the same path.
+
+use serde_json::Value;
+use std::str::FromStr;
+
+/// One step of a path
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Member(String),
+    Index(i64),
+    Wildcard,
+    /// Apply the inner step at every depth
+    Descendant(Box<Segment>),
+}
+
+/// A parsed path, applied with [`JsonPath::select`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    /// Every value the path selects in `value` (shallower matches first for `..`)
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![value];
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|value| step(segment, value))
+                .collect();
+        }
+        current
+    }
+
+    /// Set every value the path selects in `value` to `replacement`
+    pub fn replace(&self, value: &mut Value, replacement: &Value) {
+        replace_at(&self.segments, value, replacement);
+    }
+
+    /// The path as written
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl std::fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let trimmed = source.trim();
+        let mut rest = trimmed.strip_prefix('$').unwrap_or(trimmed);
+        let mut segments = Vec::new();
+
+        while !rest.is_empty() {
+            let (segment, remaining) = if let Some(after) = rest.strip_prefix("..") {
+                let (inner, remaining) = parse_step(after, source)?;
+                (Segment::Descendant(Box::new(inner)), remaining)
+            } else if let Some(after) = rest.strip_prefix('.') {
+                parse_step(after, source)?
+            } else if rest.starts_with('[') {
+                parse_step(rest, source)?
+            } else {
+                return Err(format!("Unexpected '{}' in path '{}'", rest, source));
+            };
+            segments.push(segment);
+            rest = remaining;
+        }
+
+        Ok(Self {
+            source: trimmed.to_string(),
+            segments,
+        })
+    }
+}
+
+/// Parse a member name, `*`, or bracket expression at the start of `input`
+fn parse_step<'a>(input: &'a str, source: &str) -> Result<(Segment, &'a str), String> {
+    if let Some(after) = input.strip_prefix('*') {
+        return Ok((Segment::Wildcard, after));
+    }
+    if let Some(after) = input.strip_prefix('[') {
+        let end = after
+            .find(']')
+            .ok_or_else(|| format!("Unclosed '[' in path '{}'", source))?;
+        let inner = after[..end].trim();
+        let segment = match inner {
+            "" | "*" => Segment::Wildcard,
+            quoted if quoted.starts_with(['\'', '"']) => {
+                let (quote, rest) = quoted.split_at(1);
+                let name = rest.strip_suffix(quote).ok_or_else(|| {
+                    format!("Unclosed quote in '[{}]' in path '{}'", quoted, source)
+                })?;
+                Segment::Member(name.to_string())
+            }
+            index => Segment::Index(
+                index
+                    .parse()
+                    .map_err(|_| format!("Invalid index '{}' in path '{}'", index, source))?,
+            ),
+        };
+        return Ok((segment, &after[end + 1..]));
+    }
+
+    let end = input.find(['.', '[']).unwrap_or(input.len());
+    if end == 0 {
+        return Err(format!("Missing member name in path '{}'", source));
+    }
+    Ok((Segment::Member(input[..end].to_string()), &input[end..]))
+}
+
+fn step<'a>(segment: &Segment, value: &'a Value) -> Vec<&'a Value> {
+    match segment {
+        Segment::Member(name) => value.get(name).into_iter().collect(),
+        Segment::Index(index) => {
+            let Some(items) = value.as_array() else {
+                return Vec::new();
+            };
+            let index = if *index < 0 {
+                items.len() as i64 + index
+            } else {
+                *index
+            };
+            usize::try_from(index)
+                .ok()
+                .and_then(|i| items.get(i))
+                .into_iter()
+                .collect()
+        }
+        Segment::Wildcard => children(value),
+        Segment::Descendant(inner) => {
+            let mut nodes = vec![value];
+            let mut i = 0;
+            while i < nodes.len() {
+                nodes.extend(children(nodes[i]));
+                i += 1;
+            }
+            nodes
+                .into_iter()
+                .flat_map(|node| step(inner, node))
+                .collect()
+        }
+    }
+}
+
+fn replace_at(segments: &[Segment], value: &mut Value, replacement: &Value) {
+    let Some((segment, rest)) = segments.split_first() else {
+        *value = replacement.clone();
+        return;
+    };
+    match segment {
+        Segment::Member(name) => {
+            if let Some(child) = value.get_mut(name) {
+                replace_at(rest, child, replacement);
+            }
+        }
+        Segment::Index(index) => {
+            let Some(items) = value.as_array_mut() else {
+                return;
+            };
+            let index = if *index < 0 {
+                items.len() as i64 + index
+            } else {
+                *index
+            };
+            if let Some(child) = usize::try_from(index).ok().and_then(|i| items.get_mut(i)) {
+                replace_at(rest, child, replacement);
+            }
+        }
+        Segment::Wildcard => {
+            for child in children_mut(value) {
+                replace_at(rest, child, replacement);
+            }
+        }
+        Segment::Descendant(inner) => {
+            // Deepest first, so replacing a value doesn't hide matches inside it
+            for child in children_mut(value) {
+                replace_at(segments, child, replacement);
+            }
+            let here: Vec<Segment> = std::iter::once((**inner).clone())
+                .chain(rest.iter().cloned())
+                .collect();
+            replace_at(&here, value, replacement);
+        }
+    }
+}
+
+fn children_mut(value: &mut Value) -> Vec<&mut Value> {
+    match value {
+        Value::Array(items) => items.iter_mut().collect(),
+        Value::Object(members) => members.values_mut().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(members) => members.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn select(path: &str, value: &Value) -> Vec<Value> {
+        let path: JsonPath = path.parse().unwrap();
+        path.select(value).into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_select_members_and_indexes() {
+        let doc = json!({
+            "data": {
+                "products": [
+                    {"id": 1, "name": "Widget", "tags": ["a", "b"]},
+                    {"id": 2, "name": "Gadget", "tags": []},
+                ],
+                "my key": true,
+            }
+        });
+
+        assert_eq!(select("$", &doc), vec![doc.clone()]);
+        assert_eq!(
+            select("$.data.products[0].name", &doc),
+            vec![json!("Widget")]
+        );
+        assert_eq!(select("$.data.products[-1].id", &doc), vec![json!(2)]);
+        assert_eq!(
+            select("$.data.products[*].id", &doc),
+            vec![json!(1), json!(2)]
+        );
+        assert_eq!(
+            select(".data.products[].name", &doc),
+            vec![json!("Widget"), json!("Gadget")]
+        );
+        assert_eq!(select("$.data['my key']", &doc), vec![json!(true)]);
+        assert!(select("$.data.missing", &doc).is_empty());
+        assert!(select("$.data.products[5]", &doc).is_empty());
+    }
+
+    #[test]
+    fn test_select_descendants() {
+        let doc = json!({
+            "id": 0,
+            "items": [{"id": 1, "child": {"id": 2}}, {"name": "x"}],
+        });
+        assert_eq!(select("$..id", &doc), vec![json!(0), json!(1), json!(2)]);
+        assert_eq!(select("$..child.id", &doc), vec![json!(2)]);
+        assert_eq!(select("$.items..*", &doc).len(), 6);
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut doc = json!({
+            "updated": "2026-10-16",
+            "items": [{"id": 1, "child": {"id": 2}}, {"name": "x"}],
+        });
+        for path in ["$.updated", "$.items[-1].name", "$..id"] {
+            let path: JsonPath = path.parse().unwrap();
+            path.replace(&mut doc, &json!("-"));
+        }
+        assert_eq!(
+            doc,
+            json!({
+                "updated": "-",
+                "items": [{"id": "-", "child": {"id": "-"}}, {"name": "-"}],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("$.data[".parse::<JsonPath>().is_err());
+        assert!("$.data[x]".parse::<JsonPath>().is_err());
+        assert!("$.data.".parse::<JsonPath>().is_err());
+        assert!("data".parse::<JsonPath>().is_err());
+        // Quotes must be closed with the same character, even after multibyte text
+        for unclosed in ["$['é]", "$['a\"]", "$[']", "$[\"a]"] {
+            let err = unclosed.parse::<JsonPath>().unwrap_err();
+            assert!(err.starts_with("Unclosed quote"), "{}: {}", unclosed, err);
+        }
+        assert_eq!(
+            "$['é']"
+                .parse::<JsonPath>()
+                .unwrap()
+                .select(&json!({"é": 1})),
+            [&json!(1)]
+        );
+        assert_eq!("$.a.b".parse::<JsonPath>().unwrap().to_string(), "$.a.b");
+    }
+}
@@ -1,18 +1,104 @@
+#[cfg(feature = "runtime")]
+pub mod artifacts;
+#[cfg(feature = "runtime")]
 pub mod browser;
+#[cfg(feature = "runtime")]
 pub mod cdp;
+#[cfg(feature = "runtime")]
+pub mod changes;
+#[cfg(feature = "runtime")]
+pub mod config;
+#[cfg(feature = "runtime")]
+pub mod crawl;
+#[cfg(feature = "runtime")]
+pub(crate) mod crypto;
+#[cfg(feature = "runtime")]
+pub mod diagnostics;
+#[cfg(feature = "runtime")]
 pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "sqlite")]
+pub mod frame_index;
+#[cfg(feature = "runtime")]
+pub mod frame_store;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "runtime")]
+pub mod html;
+#[cfg(feature = "runtime")]
+pub mod jobs;
+#[cfg(feature = "runtime")]
+pub mod json_path;
+#[cfg(feature = "runtime")]
+pub mod library;
+pub mod model;
+#[cfg(feature = "runtime")]
+pub mod redaction;
+#[cfg(feature = "runtime")]
+pub mod runs;
+#[cfg(feature = "runtime")]
+pub mod scheduler;
+#[cfg(feature = "runtime")]
+pub mod server;
+#[cfg(feature = "runtime")]
+pub mod sitemap;
+#[cfg(feature = "runtime")]
 pub mod step_frame;
+#[cfg(feature = "runtime")]
+pub mod telemetry;
+#[cfg(feature = "runtime")]
+pub mod testing;
+#[cfg(feature = "runtime")]
+pub mod visual_dom_delta;
+#[cfg(feature = "runtime")]
+pub mod webhook;
 
 //  Re-export commonly used items
-pub use browser::chat::{ChatMessage, ChatUI, UserFeedback};
-pub use browser::chrome::{ChromeDriver, ConnectionMode};
-pub use cdp::{
-    CdpCommand, CdpExecutor, CdpScript, CdpScriptGenerator, CdpValidator, CommandResult,
-    CommandStatus, ErrorLocation, ExecutionReport, ValidationError, ValidationErrorType,
-    ValidationResult,
+pub use model::browser::{BrowserInfo, BrowserKind, VersionCheck};
+pub use model::builder::{CdpScriptBuilder, ScriptSteps};
+pub use model::diff::ScriptDiff;
+pub use model::fingerprint::ElementFingerprint;
+pub use model::frame::{
+    ActionInfo, DomInfo, FrameElement, InteractiveElement, ScreenshotInfo, StepFrame,
+    TargetScreenshot, TranscriptInfo, ViewportInfo,
 };
-pub use error::BrowserError;
-pub use step_frame::{
-    capture_step_frame, ActionInfo, CaptureOptions, DomInfo, InteractiveElement, ScreenshotFormat,
-    ScreenshotInfo, StepFrame, TranscriptInfo,
+pub use model::lint::{CdpLinter, LintWarning};
+pub use model::macros::MacroCommand;
+pub use model::script::{CdpCommand, CdpScript, CommandResult, CommandStatus, ExecutionReport};
+pub use model::typed::TypedCommand;
+pub use model::validation::{
+    CdpValidator, ErrorLocation, ValidationError, ValidationErrorType, ValidationResult,
+};
+pub use model::version::ScriptVersion;
+
+#[cfg(feature = "runtime")]
+pub use artifacts::{ArtifactQuota, ArtifactUsage, PurgeReport};
+#[cfg(feature = "runtime")]
+pub use browser::chat::{
+    ChatAction, ChatFieldKind, ChatFormField, ChatImage, ChatMessage, ChatUI, UserFeedback,
 };
+#[cfg(feature = "runtime")]
+pub use browser::chrome::{ChromeDriver, ConnectionMode, DriverConfig, DriverOptions};
+#[cfg(feature = "runtime")]
+pub use browser::pool::{BrowserPool, ScriptOutcome};
+#[cfg(feature = "runtime")]
+pub use cdp::{CdpExecutor, CdpScriptGenerator, ExecutionCheckpoint};
+#[cfg(feature = "runtime")]
+pub use changes::ChangeReport;
+#[cfg(feature = "runtime")]
+pub use config::Config;
+#[cfg(feature = "runtime")]
+pub use diagnostics::{diagnose, DiagnosticReport};
+#[cfg(feature = "runtime")]
+pub use error::BrowserError;
+#[cfg(feature = "runtime")]
+pub use frame_store::{FileStore, FrameStore, S3Store};
+#[cfg(feature = "runtime")]
+pub use library::ScriptLibrary;
+#[cfg(feature = "runtime")]
+pub use scheduler::{CronSchedule, Scheduler};
+#[cfg(feature = "runtime")]
+pub use step_frame::{capture_step_frame, CaptureOptions, ScreenshotFormat, TargetScreenshotMode};
+#[cfg(feature = "runtime")]
+pub use webhook::{Notifier, RunNotification};
@@ -0,0 +1,128 @@
+//! Script Library
+//!
+//! A directory of named CDP scripts (`<dir>/<name>.json`) that the daemon can run
+//! by name, e.g. from a schedule, instead of receiving the script in a request.
+
+use crate::cdp::CdpScript;
+use crate::model::version::ScriptVersion;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+/// Directory of saved CDP scripts, addressed by name
+#[derive(Debug, Clone)]
+pub struct ScriptLibrary {
+    dir: PathBuf,
+}
+
+impl ScriptLibrary {
+    /// Library backed by `dir` (created on first save)
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Directory holding the scripts
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Names of all scripts in the library, sorted
+    ///
+    /// A missing library directory is treated as empty.
+    pub async fn list(&self) -> anyhow::Result<Vec<String>> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to read script library {}", self.dir.display())
+                })
+            }
+        };
+
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    names.push(stem.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Load the script named `name`
+    pub async fn load(&self, name: &str) -> anyhow::Result<CdpScript> {
+        let path = self.path_for(name)?;
+        CdpScript::from_file(&path)
+            .await
+            .with_context(|| format!("Failed to load script '{}' from {}", name, path.display()))
+    }
+
+    /// Load the script named `name`, failing unless its version is compatible with
+    /// `required` (see [`ScriptVersion::is_compatible_with`])
+    pub async fn load_compatible(
+        &self,
+        name: &str,
+        required: &ScriptVersion,
+    ) -> anyhow::Result<CdpScript> {
+        let script = self.load(name).await?;
+        match script.script_version()? {
+            Some(version) if version.is_compatible_with(required) => Ok(script),
+            Some(version) => anyhow::bail!(
+                "Script '{}' is version {}, which is not compatible with {}",
+                name,
+                version,
+                required
+            ),
+            None => anyhow::bail!(
+                "Script '{}' has no version (need one compatible with {})",
+                name,
+                required
+            ),
+        }
+    }
+
+    /// Save `script` under its own name, replacing any existing script
+    pub async fn save(&self, script: &CdpScript) -> anyhow::Result<PathBuf> {
+        let path = self.path_for(&script.name)?;
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create script library {}", self.dir.display()))?;
+        script.to_file(&path).await?;
+        Ok(path)
+    }
+
+    /// File path for a script name, rejecting names that would escape the library
+    fn path_for(&self, name: &str) -> anyhow::Result<PathBuf> {
+        if !is_valid_name(name) {
+            anyhow::bail!("Invalid script name '{}'", name);
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+}
+
+/// Script names are plain file stems: no separators, no leading dot
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\'])
+        && !name.chars().any(char::is_control)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_names() {
+        assert!(is_valid_name("daily-report"));
+        assert!(is_valid_name("login_check.v2"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("../secrets"));
+        assert!(!is_valid_name("nested/script"));
+        assert!(!is_valid_name("..\\script"));
+        assert!(!is_valid_name(".hidden"));
+    }
+}
@@ -1,177 +1,1181 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
+use robert_webdriver::artifacts::{self, ArtifactQuota};
 use robert_webdriver::browser::chrome::ChromeDriver;
-use robert_webdriver::cdp::{CdpExecutor, CdpScriptGenerator};
-use std::net::SocketAddr;
+use robert_webdriver::browser::detect::detect_browsers;
+use robert_webdriver::browser::install::{self, ChromeInstaller, VersionPin};
+use robert_webdriver::browser::link_check::LinkCheckOptions;
+use robert_webdriver::browser::overlay::OverlayPolicy;
+use robert_webdriver::browser::pool::BrowserPool;
+use robert_webdriver::cdp::{
+    CdpExecutor, CdpLinter, CdpScript, CdpTrace, CdpValidator, ExecutionCheckpoint,
+    ExecutionOptions, TraceRecorder,
+};
+use robert_webdriver::config::{ArtifactsConfig, Config};
+use robert_webdriver::crawl::{CrawlOptions, Crawler};
+use robert_webdriver::diagnostics::{DiagnoseOptions, DEFAULT_EGRESS_URL};
+#[cfg(feature = "sqlite")]
+use robert_webdriver::frame_index::{FrameIndex, SessionStatus};
+use robert_webdriver::frame_store;
+use robert_webdriver::sitemap::{self, BatchCapture};
+use robert_webdriver::step_frame::{capture_step_frame, ActionInfo, CaptureOptions};
+use robert_webdriver::telemetry::{init_tracing_with, LogFormat, LoggingConfig};
+use robert_webdriver::visual_dom_delta::VisualDomDeltas;
+use robert_webdriver::webhook::{Notifier, RunNotification, RunSource};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use warp::Filter;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
-struct Args {
-    /// Port to listen on
-    #[arg(short, long, default_value_t = 9669)]
-    port: u16,
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Server options used when no subcommand is given
+    #[command(flatten)]
+    serve: ServeArgs,
+
+    /// Config file (defaults to $ROBERT_CONFIG or ~/.config/robert/webdriver.toml)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Log output format: quiet, pretty, or json
+    #[arg(long, global = true, default_value = "pretty")]
+    log_format: LogFormat,
+
+    /// Log filter (e.g. "debug" or "robert_webdriver=trace"); overrides RUST_LOG
+    #[arg(long, global = true)]
+    log_level: Option<String>,
 }
 
-#[derive(Debug, serde::Deserialize)]
-struct InferenceRequest {
-    prompt: String,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Start the HTTP inference server (default)
+    Serve(ServeArgs),
+
+    /// Execute a CDP script and print the execution report as JSON
+    Run {
+        /// Path to the CDP script JSON file
+        script: PathBuf,
+
+        /// Write a checkpoint to this file after every successful command
+        #[arg(long)]
+        checkpoint: Option<PathBuf>,
+
+        /// Continue from the `--checkpoint` file if it exists instead of starting over
+        #[arg(long, requires = "checkpoint")]
+        resume: bool,
+
+        /// Overwrite `Assert.snapshot` snapshots (in `snapshots/` next to the script)
+        /// with the current data instead of comparing against them
+        #[arg(long)]
+        update_snapshots: bool,
+
+        /// Record every command and its response to this trace file, for `replay`
+        #[arg(long)]
+        record: Option<PathBuf>,
+
+        /// Also write the report as an HTML page (steps and accessibility findings)
+        #[arg(long)]
+        html_report: Option<PathBuf>,
+
+        #[command(flatten)]
+        browser: BrowserArgs,
+    },
+
+    /// Run a CDP script against a trace recorded with `run --record`, without a
+    /// browser, and print the execution report as JSON
+    Replay {
+        /// Path to the CDP script JSON file
+        script: PathBuf,
+
+        /// Trace recorded with `run --record`
+        trace: PathBuf,
+    },
+
+    /// Validate a CDP script without running it
+    Validate {
+        /// Path to the CDP script JSON file
+        script: PathBuf,
+
+        /// Repair recoverable problems (misspelled methods, numbers given as strings,
+        /// URLs without a scheme), rewrite the file, and print the changes
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Compare two versions of a CDP script, printing the changes and the version
+    /// bump they need as JSON
+    Diff {
+        /// The old script
+        old: PathBuf,
+
+        /// The new script
+        new: PathBuf,
+    },
+
+    /// Check a CDP script for anti-patterns, printing warnings (with suggested fixes)
+    /// as JSON
+    Lint {
+        /// Path to the CDP script JSON file
+        script: PathBuf,
+
+        /// Flag Runtime.evaluate expressions longer than this many bytes
+        #[arg(long, default_value_t = robert_webdriver::cdp::lint::DEFAULT_MAX_EVALUATE_BYTES)]
+        max_evaluate_bytes: usize,
+    },
+
+    /// Navigate to a URL and save a screenshot
+    Screenshot {
+        /// URL to capture
+        url: String,
+
+        /// Output file
+        #[arg(short, long, default_value = "screenshot.png")]
+        output: PathBuf,
+
+        #[command(flatten)]
+        browser: BrowserArgs,
+    },
+
+    /// Navigate to a URL and capture step frames, printing them as JSON
+    Capture {
+        /// URL to capture
+        url: String,
+
+        /// Number of frames to capture
+        #[arg(long, default_value_t = 1)]
+        frames: usize,
+
+        /// Delay between frames in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        interval_ms: u64,
+
+        /// Directory for screenshots and DOM snapshots (defaults to the artifact root)
+        #[arg(short, long)]
+        output_dir: Option<PathBuf>,
+
+        /// Also capture VisualDom snapshots
+        #[arg(long)]
+        visual_dom: bool,
+
+        /// Save VisualDom frames as deltas against the previous frame, with a full
+        /// snapshot every 10 frames
+        #[arg(long, requires = "visual_dom")]
+        visual_dom_delta: bool,
+
+        /// Also save an MHTML archive of the page with each frame
+        #[arg(long)]
+        mhtml: bool,
+
+        /// Also save each screenshot with numbered labels on the interactive elements,
+        /// and a JSON file mapping the labels to selectors and boxes
+        #[arg(long)]
+        marks: bool,
+
+        /// Move frame files to this store once captured: `s3://bucket/prefix` (AWS
+        /// credentials from the environment) or a directory
+        #[arg(long)]
+        store: Option<String>,
+
+        /// Record the session and its frames in this SQLite index (requires the
+        /// `sqlite` feature)
+        #[arg(long)]
+        index: Option<PathBuf>,
+
+        #[command(flatten)]
+        browser: BrowserArgs,
+    },
+
+    /// Load a page and report broken subresources and links as JSON
+    Audit {
+        /// URL to audit
+        url: String,
+
+        /// Only check subresources, not anchor targets
+        #[arg(long)]
+        no_anchors: bool,
+
+        /// Only check anchor targets on the page's own origin
+        #[arg(long)]
+        same_origin: bool,
+
+        /// Anchor targets checked at once
+        #[arg(long, default_value_t = 8)]
+        concurrency: usize,
+
+        #[command(flatten)]
+        browser: BrowserArgs,
+    },
+
+    /// Crawl a site from seed URLs and print the crawl report as JSON
+    Crawl {
+        /// Seed URLs (links are followed on their origins only)
+        #[arg(required = true)]
+        seeds: Vec<String>,
+
+        /// Link depth to follow from the seeds
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+
+        /// Stop after visiting this many pages
+        #[arg(long, default_value_t = 100)]
+        max_pages: usize,
+
+        /// Only visit URLs matching this glob (repeatable)
+        #[arg(long)]
+        include: Vec<String>,
+
+        /// Never visit URLs matching this glob (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+
+        /// Visit pages that robots.txt disallows
+        #[arg(long)]
+        ignore_robots: bool,
+
+        /// Browser sessions crawling at once (defaults to server.pool_size)
+        #[arg(long)]
+        concurrency: Option<usize>,
+
+        /// Save a screenshot and DOM snapshot of each page in this directory
+        #[arg(short, long)]
+        output_dir: Option<PathBuf>,
+
+        /// JavaScript expression evaluated on each page, reported as `extracted`
+        #[arg(long)]
+        extract: Option<String>,
+
+        /// Report broken subresources and links of each page
+        #[arg(long)]
+        check_links: bool,
+
+        #[command(flatten)]
+        browser: BrowserArgs,
+    },
+
+    /// Capture every page listed in a sitemap or URL list, writing index.json and failures.json
+    Sitemap {
+        /// sitemap.xml (or sitemap index) or a file with one URL per line; a path or an http(s) URL
+        source: String,
+
+        /// Directory for screenshots, DOM snapshots, and the index files
+        #[arg(short, long)]
+        output_dir: PathBuf,
+
+        /// Also capture a VisualDom snapshot of each page
+        #[arg(long)]
+        visual_dom: bool,
+
+        /// JavaScript expression evaluated on each page, recorded in the index as `extracted`
+        #[arg(long)]
+        extract: Option<String>,
+
+        /// Skip pages that robots.txt disallows
+        #[arg(long)]
+        respect_robots: bool,
+
+        /// Browser sessions capturing at once (defaults to server.pool_size)
+        #[arg(long)]
+        concurrency: Option<usize>,
+
+        #[command(flatten)]
+        browser: BrowserArgs,
+    },
+
+    /// Check the environment (browsers, sandbox, display, cache, network) and try
+    /// launching Chrome, printing the report as JSON; exits non-zero if there are issues
+    Diagnose {
+        /// Don't launch Chrome
+        #[arg(long)]
+        no_launch: bool,
+
+        /// Don't check that Chrome for Testing can be downloaded
+        #[arg(long)]
+        offline: bool,
+
+        #[command(flatten)]
+        browser: BrowserArgs,
+    },
+
+    /// Delete step frame files (screenshots, DOM, VisualDom) older than a number of
+    /// days, printing what was removed as JSON
+    Purge {
+        /// Delete files last modified more than this many days ago
+        #[arg(long)]
+        older_than_days: u64,
+
+        /// Artifact directory to purge (defaults to artifacts.root)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+
+    /// Print the HTTP server's OpenAPI document
+    Openapi {
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Manage downloaded Chrome for Testing versions
+    Chrome {
+        #[command(subcommand)]
+        action: ChromeCommand,
+    },
 }
 
-#[derive(Debug, serde::Serialize)]
-struct InferenceResponse {
-    status: String,
-    message: String,
-    script_steps: Option<usize>,
-    execution_report: Option<serde_json::Value>,
+#[derive(Subcommand, Debug)]
+enum ChromeCommand {
+    /// Download Chrome for Testing (reuses a verified cached build if one matches)
+    Install {
+        /// Version pin such as "128.0.x" (defaults to chrome.version from the config)
+        version: Option<VersionPin>,
+    },
+
+    /// List installed versions as JSON
+    List,
+
+    /// List the Chrome, Chromium, Edge, and Brave installs found on this system as JSON
+    Detect,
+
+    /// Check installed versions against their recorded SHA-256 hashes
+    Verify,
+
+    /// Remove an installed version
+    Remove {
+        /// Exact installed version, as shown by `chrome list`
+        version: String,
+    },
 }
 
-// Shared state
-struct AppState {
-    driver: Mutex<Option<ChromeDriver>>,
-    generator: CdpScriptGenerator,
+#[derive(Args, Debug)]
+struct ServeArgs {
+    /// Port to listen on (overrides the config file; default 9669)
+    #[arg(short, long)]
+    port: Option<u16>,
 }
 
-#[tokio::main]
-async fn main() {
-    env_logger::init();
-    let args = Args::parse();
+#[derive(Args, Debug)]
+struct BrowserArgs {
+    /// Run Chrome without a visible window
+    #[arg(long)]
+    headless: bool,
 
-    log::info!("Starting Robert Webdriver on port {}", args.port);
+    /// Launch Chrome with --no-sandbox
+    #[arg(long)]
+    no_sandbox: bool,
 
-    // Initialize state
-    let state = Arc::new(AppState {
-        driver: Mutex::new(None),
-        generator: CdpScriptGenerator::new(),
-    });
+    /// Launch headless Chrome with the flags containers (Docker) need
+    #[arg(long)]
+    container: bool,
 
-    // Health check endpoint
-    let health =
-        warp::path("health").map(|| warp::reply::json(&serde_json::json!({ "status": "ok" })));
+    /// Path to the Chrome executable (defaults to auto-download)
+    #[arg(long)]
+    chrome_path: Option<String>,
 
-    // Inference endpoint
-    let state_filter = warp::any().map(move || state.clone());
+    /// Connect to an existing Chrome on this debug port instead of launching one
+    #[arg(long, conflicts_with_all = ["headless", "no_sandbox", "container", "chrome_path", "xvfb"])]
+    debug_port: Option<u16>,
 
-    let inference = warp::path("inference")
-        .and(warp::post())
-        .and(warp::body::json())
-        .and(state_filter)
-        .and_then(handle_inference);
+    /// Connect to a remote Chrome by DevTools WebSocket (or HTTP) URL, e.g. a
+    /// browserless or Docker container
+    #[arg(long, conflicts_with_all = ["headless", "no_sandbox", "container", "chrome_path", "xvfb", "debug_port"])]
+    ws_url: Option<String>,
 
-    let routes = health.or(inference);
+    /// Render pages deterministically (frozen time, no animations) for stable screenshots
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Fail when a navigation gets a 4xx/5xx response
+    #[arg(long)]
+    fail_on_http_error: bool,
+
+    /// Load pages despite certificate errors (self-signed test environments)
+    #[arg(long)]
+    ignore_cert_errors: bool,
+
+    /// Without a display, run Chrome headed on an Xvfb virtual display instead of
+    /// falling back to headless
+    #[arg(long)]
+    xvfb: bool,
+
+    /// Dismiss cookie consent banners after each navigation (rejecting optional
+    /// cookies where possible)
+    #[arg(long)]
+    dismiss_cookie_banners: bool,
+
+    /// What to do with pop-ups covering the page after each navigation: dismiss,
+    /// hide, or report
+    #[arg(long, value_name = "POLICY")]
+    overlays: Option<OverlayPolicy>,
+}
 
-    // Bind manually to handle "port in use" error gracefully
-    let addr = SocketAddr::from(([127, 0, 0, 1], args.port));
+impl BrowserArgs {
+    /// Launch or connect to Chrome, with flags taking precedence over the config
+    async fn connect(&self, config: &Config) -> anyhow::Result<ChromeDriver> {
+        Ok(ChromeDriver::from_config(&self.apply(config)).await?)
+    }
 
-    match tokio::net::TcpListener::bind(addr).await {
-        Ok(listener) => {
-            log::info!("Listening on http://{}", addr);
-            warp::serve(routes)
-                .run_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
-                .await;
+    /// `config` with these flags applied
+    fn apply(&self, config: &Config) -> Config {
+        let mut config = config.clone();
+        if self.headless {
+            config.chrome.headless = true;
         }
-        Err(e) => {
-            log::error!("Failed to bind to port {}: {}", args.port, e);
-            eprintln!(
-                "Error: Port {} is already in use or unavailable.",
-                args.port
-            );
-            std::process::exit(1);
+        if self.no_sandbox {
+            config.chrome.no_sandbox = true;
+        }
+        if self.container {
+            config.chrome.container = true;
+        }
+        if let Some(path) = &self.chrome_path {
+            config.chrome.path = Some(path.clone());
+        }
+        if let Some(url) = &self.ws_url {
+            config.chrome.ws_url = Some(url.clone());
+        }
+        if let Some(port) = self.debug_port {
+            config.chrome.debug_port = Some(port);
+        }
+        if self.deterministic {
+            config.chrome.deterministic = true;
         }
+        if self.fail_on_http_error {
+            config.chrome.fail_on_http_error = true;
+        }
+        if self.ignore_cert_errors {
+            config.chrome.ignore_cert_errors = true;
+        }
+        if self.xvfb {
+            config.chrome.xvfb = true;
+        }
+        if self.dismiss_cookie_banners {
+            config.chrome.dismiss_cookie_banners = true;
+        }
+        if let Some(policy) = self.overlays {
+            config.chrome.overlays = Some(policy);
+        }
+        config
     }
 }
 
-async fn handle_inference(
-    req: InferenceRequest,
-    state: Arc<AppState>,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    log::info!("Received inference request: {}", req.prompt);
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let logging = LoggingConfig {
+        format: cli.log_format,
+        level: cli.log_level.clone(),
+    };
+    let _tracing_guard = match init_tracing_with("robert-webdriver", &logging) {
+        Ok(guard) => Some(guard),
+        Err(e) => {
+            eprintln!("Failed to initialize tracing: {}", e);
+            None
+        }
+    };
 
-    // 1. Ensure Chrome is running
-    let mut driver_guard = state.driver.lock().await;
+    // Cancel running scripts/jobs on Ctrl-C
+    let shutdown = CancellationToken::new();
+    let ctrl_c = shutdown.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            tracing::info!("Ctrl-C received, cancelling running work...");
+            ctrl_c.cancel();
+        }
+    });
 
-    // Check if alive, otherwise close and clear
-    if let Some(driver) = driver_guard.as_ref() {
-        if !driver.is_alive().await {
-            log::warn!("Chrome session DEAD, restarting...");
-            *driver_guard = None; // Drop dead driver
+    let config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            return ExitCode::FAILURE;
         }
-    }
+    };
 
-    // Launch if needed
-    if driver_guard.is_none() {
-        log::info!("Launching new Chrome session...");
-        match ChromeDriver::launch_auto().await {
-            Ok(d) => {
-                log::info!("Chrome launched successfully.");
-                *driver_guard = Some(d);
+    let result = match cli.command {
+        None => serve(cli.serve, config, shutdown).await,
+        Some(Command::Serve(args)) => serve(args, config, shutdown).await,
+        Some(Command::Run {
+            script,
+            checkpoint,
+            resume,
+            update_snapshots,
+            record,
+            html_report,
+            browser,
+        }) => {
+            let checkpoint = checkpoint.map(|path| (path, resume));
+            let outputs = RunOutputs {
+                record,
+                html_report,
+            };
+            run(
+                script,
+                checkpoint,
+                update_snapshots,
+                outputs,
+                browser,
+                &config,
+                shutdown,
+            )
+            .await
+        }
+        Some(Command::Replay { script, trace }) => replay(script, trace).await,
+        Some(Command::Validate { script, fix }) => validate(script, fix).await,
+        Some(Command::Diff { old, new }) => diff(old, new).await,
+        Some(Command::Lint {
+            script,
+            max_evaluate_bytes,
+        }) => lint(script, max_evaluate_bytes).await,
+        Some(Command::Screenshot {
+            url,
+            output,
+            browser,
+        }) => screenshot(url, output, browser, &config, shutdown).await,
+        Some(Command::Capture {
+            url,
+            frames,
+            interval_ms,
+            output_dir,
+            visual_dom,
+            visual_dom_delta,
+            mhtml,
+            marks,
+            store,
+            index,
+            browser,
+        }) => {
+            let output_dir = output_dir.unwrap_or_else(|| config.artifacts.root.clone());
+            let quota = ArtifactQuota::from_config(&ArtifactsConfig {
+                root: output_dir.clone(),
+                ..config.artifacts.clone()
+            })
+            .await;
+            let options = CaptureOptions {
+                screenshot_dir: output_dir.join("screenshots"),
+                dom_dir: Some(output_dir.join("dom")),
+                visual_dom_dir: Some(output_dir.join("visualdom")),
+                capture_visual_dom: visual_dom,
+                visual_dom_deltas: visual_dom_delta.then(VisualDomDeltas::default),
+                save_mhtml: mhtml,
+                set_of_marks: marks,
+                quota: Some(Arc::new(quota)),
+                cancel_token: Some(shutdown.clone()),
+                ..Default::default()
+            };
+            match with_storage(options, store.as_deref(), index.as_deref()) {
+                Ok(options) => {
+                    capture(
+                        url,
+                        frames,
+                        interval_ms,
+                        options,
+                        browser,
+                        &config,
+                        shutdown,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
             }
-            Err(e) => {
-                log::error!("Failed to launch Chrome: {}", e);
-                return Ok(warp::reply::json(&InferenceResponse {
-                    status: "error".to_string(),
-                    message: format!("Failed to launch Chrome: {}", e),
-                    script_steps: None,
-                    execution_report: None,
-                }));
+        }
+        Some(Command::Audit {
+            url,
+            no_anchors,
+            same_origin,
+            concurrency,
+            browser,
+        }) => {
+            let options = LinkCheckOptions {
+                check_anchors: !no_anchors,
+                same_origin_only: same_origin,
+                concurrency,
+                ..Default::default()
+            };
+            audit(&url, &options, browser, &config).await
+        }
+        Some(Command::Crawl {
+            seeds,
+            depth,
+            max_pages,
+            include,
+            exclude,
+            ignore_robots,
+            concurrency,
+            output_dir,
+            extract,
+            check_links,
+            browser,
+        }) => {
+            let options = CrawlOptions {
+                max_depth: depth,
+                max_pages,
+                include,
+                exclude,
+                respect_robots: !ignore_robots,
+                capture: output_dir.map(|dir| CaptureOptions {
+                    screenshot_dir: dir.join("screenshots"),
+                    dom_dir: Some(dir.join("dom")),
+                    cancel_token: Some(shutdown.clone()),
+                    ..Default::default()
+                }),
+                extract_script: extract,
+                check_links: check_links.then(LinkCheckOptions::default),
+            };
+            let concurrency = concurrency.unwrap_or(config.server.pool_size);
+            crawl(seeds, options, concurrency, browser, &config, shutdown).await
+        }
+        Some(Command::Sitemap {
+            source,
+            output_dir,
+            visual_dom,
+            extract,
+            respect_robots,
+            concurrency,
+            browser,
+        }) => {
+            let batch = BatchCapture {
+                output_dir,
+                capture_visual_dom: visual_dom,
+                extract_script: extract,
+                respect_robots,
+            };
+            let concurrency = concurrency.unwrap_or(config.server.pool_size);
+            sitemap_capture(&source, batch, concurrency, browser, &config, shutdown).await
+        }
+        Some(Command::Diagnose {
+            no_launch,
+            offline,
+            browser,
+        }) => {
+            let options = DiagnoseOptions {
+                launch: !no_launch,
+                egress_url: (!offline).then(|| DEFAULT_EGRESS_URL.to_string()),
+            };
+            diagnose(&browser.apply(&config), &options).await
+        }
+        Some(Command::Purge {
+            older_than_days,
+            dir,
+        }) => {
+            let dir = dir.unwrap_or_else(|| config.artifacts.root.clone());
+            purge(&dir, Duration::from_secs(older_than_days * 24 * 60 * 60)).await
+        }
+        Some(Command::Openapi { output }) => openapi(output).await,
+        Some(Command::Chrome { action }) => chrome(action, &config).await,
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            tracing::error!("{:#}", e);
+            eprintln!("Error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn serve(
+    args: ServeArgs,
+    mut config: Config,
+    shutdown: CancellationToken,
+) -> anyhow::Result<ExitCode> {
+    if let Some(port) = args.port {
+        config.server.port = port;
+    }
+    robert_webdriver::server::serve(config, shutdown).await?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Execute a script file; exits non-zero if any command failed
+///
+/// `checkpoint` is a checkpoint file to write, and whether to resume from it if it
+/// exists. `record` is a trace file to record the run to.
+/// Files `run` writes besides printing the report
+struct RunOutputs {
+    /// Trace of every command and response
+    record: Option<PathBuf>,
+    /// The report as HTML
+    html_report: Option<PathBuf>,
+}
+
+async fn run(
+    path: PathBuf,
+    checkpoint: Option<(PathBuf, bool)>,
+    update_snapshots: bool,
+    outputs: RunOutputs,
+    browser: BrowserArgs,
+    config: &Config,
+    shutdown: CancellationToken,
+) -> anyhow::Result<ExitCode> {
+    let script = CdpScript::from_file(&path).await?;
+    script.validate()?;
+
+    let resume_from = match &checkpoint {
+        Some((checkpoint_path, true)) if checkpoint_path.exists() => {
+            let saved = ExecutionCheckpoint::load(checkpoint_path).await?;
+            if !saved.script.diff(&script).is_empty() {
+                anyhow::bail!(
+                    "Checkpoint {} is for a different version of {}",
+                    checkpoint_path.display(),
+                    path.display()
+                );
             }
+            Some(saved)
         }
+        _ => None,
+    };
+
+    let driver = browser.connect(config).await?;
+    let page = driver.current_page().await?;
+    let mut executor = CdpExecutor::new(page)
+        .with_driver(&driver)
+        .with_cancellation(shutdown)
+        .with_options(ExecutionOptions {
+            snapshot_dir: Some(path.with_file_name("snapshots")),
+            update_snapshots,
+            ..Default::default()
+        });
+    if let Some((checkpoint_path, _)) = checkpoint {
+        executor = executor.with_checkpoint(checkpoint_path);
+    }
+    let recorder = outputs
+        .record
+        .as_ref()
+        .map(|_| Arc::new(TraceRecorder::new()));
+    if let Some(recorder) = &recorder {
+        executor = executor.with_step_hooks(recorder.clone());
     }
+    let report = match resume_from {
+        Some(saved) => executor.execute_script_resume(saved).await,
+        None => executor.execute_script(&script).await,
+    };
+    driver.close().await?;
 
-    let driver = driver_guard.as_ref().unwrap();
+    if let (Some(path), Some(recorder)) = (&outputs.record, &recorder) {
+        recorder.trace().save(path).await?;
+    }
 
-    // Get page for execution
-    let page = match driver.current_page().await {
-        Ok(p) => p,
+    let notifier = Notifier::from_config(config);
+    let report = match report {
+        Ok(report) => report,
         Err(e) => {
-            return Ok(warp::reply::json(&InferenceResponse {
-                status: "error".to_string(),
-                message: format!("Failed to get current page: {}", e),
-                script_steps: None,
-                execution_report: None,
-            }));
+            if !notifier.is_empty() {
+                let notification =
+                    RunNotification::failed(RunSource::Cli, format!("Execution failed: {:#}", e))
+                        .with_script(&script.name);
+                notifier.notify(&notification).await;
+            }
+            return Err(e);
         }
     };
 
-    // 2. Generate Script
-    let script_result = state.generator.generate(&req.prompt).await;
-
-    match script_result {
-        Ok(script) => {
-            log::info!("Generated script with {} steps", script.cdp_commands.len());
-
-            // 3. Execute Script
-            let executor = CdpExecutor::new(page);
-            match executor.execute_script(&script).await {
-                Ok(report) => {
-                    log::info!("Execution completed: {:?}", report);
-                    Ok(warp::reply::json(&InferenceResponse {
-                        status: "success".to_string(),
-                        message: "Script generated and executed".to_string(),
-                        script_steps: Some(script.cdp_commands.len()),
-                        execution_report: serde_json::to_value(report).ok(),
-                    }))
-                }
-                Err(e) => {
-                    log::error!("Execution failed: {}", e);
-                    Ok(warp::reply::json(&InferenceResponse {
-                        status: "error".to_string(),
-                        message: format!("Execution failed: {}", e),
-                        script_steps: Some(script.cdp_commands.len()),
-                        execution_report: None,
-                    }))
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    if let Some(html_path) = &outputs.html_report {
+        tokio::fs::write(html_path, report.to_html())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", html_path.display(), e))?;
+    }
+
+    if !notifier.is_empty() {
+        let notification = RunNotification::from_report(RunSource::Cli, report.clone());
+        notifier.notify(&notification).await;
+    }
+
+    Ok(if report.is_success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Execute a script file against a recorded trace; exits non-zero if any command
+/// failed or the script diverged from the trace
+async fn replay(path: PathBuf, trace: PathBuf) -> anyhow::Result<ExitCode> {
+    let script = CdpScript::from_file(&path).await?;
+    let trace = CdpTrace::load(&trace).await?;
+    let report = CdpExecutor::from_trace(trace)
+        .execute_script(&script)
+        .await?;
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(if report.is_success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Validate a script file, printing the result as JSON; exits non-zero if invalid
+///
+/// With `fix`, repairable problems are fixed in the file first, and the changes are
+/// printed along with the validation of the repaired script.
+async fn validate(path: PathBuf, fix: bool) -> anyhow::Result<ExitCode> {
+    let json = tokio::fs::read_to_string(&path).await?;
+    let validator = CdpValidator::new();
+    // Scripts that don't parse have nothing to repair; report them as usual
+    if let Some(script) = fix
+        .then(|| serde_json::from_str::<CdpScript>(&json).ok())
+        .flatten()
+    {
+        let fixed = validator.validate_and_fix(&script);
+        if !fixed.changes.is_empty() {
+            fixed.script.to_file(&path).await?;
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "changes": fixed.changes,
+                "validation": fixed.validation,
+            }))?
+        );
+        return Ok(if fixed.validation.is_valid {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
+    let result = validator.validate_json(&json);
+
+    println!("{}", serde_json::to_string_pretty(&result)?);
+
+    Ok(if result.is_valid {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Diff two script files; exits non-zero if the new script's version doesn't cover
+/// the changes (both scripts must be versioned for the check)
+async fn diff(old: PathBuf, new: PathBuf) -> anyhow::Result<ExitCode> {
+    let old = CdpScript::from_file(&old).await?;
+    let new = CdpScript::from_file(&new).await?;
+    let diff = old.diff(&new);
+    let required_bump = diff.required_bump();
+    let versions_ok = match (old.script_version()?, new.script_version()?) {
+        (Some(old), Some(new)) => new.covers(&old, required_bump),
+        _ => true,
+    };
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&serde_json::json!({
+            "diff": diff,
+            "required_bump": required_bump,
+            "version_ok": versions_ok,
+        }))?
+    );
+
+    Ok(if versions_ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Lint a script file, printing the warnings as JSON; exits non-zero if there are any
+async fn lint(path: PathBuf, max_evaluate_bytes: usize) -> anyhow::Result<ExitCode> {
+    let script = CdpScript::from_file(&path).await?;
+    let warnings = CdpLinter::new()
+        .with_max_evaluate_bytes(max_evaluate_bytes)
+        .lint(&script);
+
+    println!("{}", serde_json::to_string_pretty(&warnings)?);
+
+    Ok(if warnings.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Environment report; fails if it found issues
+async fn diagnose(config: &Config, options: &DiagnoseOptions) -> anyhow::Result<ExitCode> {
+    let report = robert_webdriver::diagnose(config, options).await;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(if report.ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+async fn purge(dir: &Path, older_than: Duration) -> anyhow::Result<ExitCode> {
+    let report = artifacts::purge(dir, older_than)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to purge {}: {}", dir.display(), e))?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn openapi(output: Option<PathBuf>) -> anyhow::Result<ExitCode> {
+    let json = robert_webdriver::server::openapi().to_pretty_json()?;
+    match output {
+        Some(path) => tokio::fs::write(&path, json).await?,
+        None => println!("{}", json),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn screenshot(
+    url: String,
+    output: PathBuf,
+    browser: BrowserArgs,
+    config: &Config,
+    shutdown: CancellationToken,
+) -> anyhow::Result<ExitCode> {
+    let driver = browser.connect(config).await?;
+    let result = async {
+        driver.navigate_cancellable(&url, &shutdown).await?;
+        driver.screenshot_to_file(&output).await
+    }
+    .await;
+    driver.close().await?;
+    result?;
+
+    println!("{}", output.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn capture(
+    url: String,
+    frames: usize,
+    interval_ms: u64,
+    options: CaptureOptions,
+    browser: BrowserArgs,
+    config: &Config,
+    shutdown: CancellationToken,
+) -> anyhow::Result<ExitCode> {
+    let driver = browser.connect(config).await?;
+    #[cfg(feature = "sqlite")]
+    let index = options.index.clone();
+    let start = Instant::now();
+    let result = async {
+        driver.navigate_cancellable(&url, &shutdown).await?;
+
+        let mut captured = Vec::with_capacity(frames);
+        for frame_id in 0..frames {
+            if frame_id > 0 {
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_millis(interval_ms)) => {}
+                    _ = shutdown.cancelled() => break,
                 }
             }
+
+            let action = (frame_id == 0).then(|| ActionInfo {
+                action_type: "navigate".to_string(),
+                intent: format!("Navigate to {}", url),
+                target: None,
+                target_screenshot: None,
+            });
+            let frame = capture_step_frame(
+                &driver,
+                frame_id,
+                start.elapsed().as_millis() as u64,
+                &options,
+                None,
+                action,
+            )
+            .await?;
+            captured.push(frame);
         }
-        Err(e) => {
-            log::error!("Failed to generate script: {}", e);
-            Ok(warp::reply::json(&InferenceResponse {
-                status: "error".to_string(),
-                message: format!("Generation failed: {}", e),
-                script_steps: None,
-                execution_report: None,
-            }))
+        Ok::<_, anyhow::Error>(captured)
+    }
+    .await;
+    #[cfg(feature = "sqlite")]
+    if let Some(index) = index {
+        let (status, error) = match &result {
+            Ok(_) if shutdown.is_cancelled() => (SessionStatus::Cancelled, None),
+            Ok(_) => (SessionStatus::Completed, None),
+            Err(e) => (SessionStatus::Failed, Some(format!("{:#}", e))),
+        };
+        index.finish_session(driver.session_id(), status, error.as_deref())?;
+    }
+    driver.close().await?;
+    let captured = result?;
+
+    println!("{}", serde_json::to_string_pretty(&captured)?);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Add the `--store` and `--index` destinations to capture options
+fn with_storage(
+    mut options: CaptureOptions,
+    store: Option<&str>,
+    index: Option<&Path>,
+) -> anyhow::Result<CaptureOptions> {
+    options.store = store.map(frame_store::from_location).transpose()?;
+    #[cfg(feature = "sqlite")]
+    {
+        options.index = index.map(FrameIndex::open).transpose()?.map(Arc::new);
+    }
+    #[cfg(not(feature = "sqlite"))]
+    if index.is_some() {
+        anyhow::bail!("--index requires building with the `sqlite` feature");
+    }
+    Ok(options)
+}
+
+/// Audit a page's links; exits non-zero if anything is broken
+async fn audit(
+    url: &str,
+    options: &LinkCheckOptions,
+    browser: BrowserArgs,
+    config: &Config,
+) -> anyhow::Result<ExitCode> {
+    let driver = browser.connect(config).await?;
+    let report = driver.audit_links(url, options).await;
+    driver.close().await?;
+    let report = report?;
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(if report.is_clean() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Crawl from seed URLs; exits non-zero if any page failed or has broken links
+async fn crawl(
+    seeds: Vec<String>,
+    options: CrawlOptions,
+    concurrency: usize,
+    browser: BrowserArgs,
+    config: &Config,
+    shutdown: CancellationToken,
+) -> anyhow::Result<ExitCode> {
+    let pool = BrowserPool::launch(concurrency, &browser.apply(config)).await?;
+    let report = Crawler::new(&pool, options)
+        .crawl_cancellable(&seeds, &shutdown)
+        .await;
+    pool.close().await?;
+    let report = report?;
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(
+        if report.failures().next().is_none() && report.broken_links().next().is_none() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        },
+    )
+}
+
+/// Capture the pages of a sitemap; prints the summary as JSON and exits non-zero
+/// if any page failed
+async fn sitemap_capture(
+    source: &str,
+    batch: BatchCapture,
+    concurrency: usize,
+    browser: BrowserArgs,
+    config: &Config,
+    shutdown: CancellationToken,
+) -> anyhow::Result<ExitCode> {
+    let urls = sitemap::load_urls(source).await?;
+    if urls.is_empty() {
+        anyhow::bail!("No URLs found in {}", source);
+    }
+    tracing::info!(count = urls.len(), source, "Capturing sitemap pages");
+
+    let pool = BrowserPool::launch(concurrency, &browser.apply(config)).await?;
+    let summary = batch.run(&pool, &urls, &shutdown).await;
+    pool.close().await?;
+    let summary = summary?;
+
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    Ok(if summary.is_success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Chrome install management; prints results as JSON
+async fn chrome(action: ChromeCommand, config: &Config) -> anyhow::Result<ExitCode> {
+    let installer = ChromeInstaller::for_download_dir(config.chrome.download_dir.as_deref())
+        .ok_or_else(|| anyhow::anyhow!("Cannot determine cache directory"))?;
+
+    match action {
+        ChromeCommand::Install { version } => {
+            let pin = match version {
+                Some(pin) => Some(pin),
+                None => config
+                    .chrome
+                    .version
+                    .as_deref()
+                    .map(str::parse)
+                    .transpose()
+                    .map_err(|e: String| anyhow::anyhow!(e))?,
+            };
+            let chrome = installer.ensure(pin.as_ref()).await?;
+            println!("{}", serde_json::to_string_pretty(&chrome)?);
         }
+        ChromeCommand::List => {
+            let installed = installer.list().await?;
+            println!("{}", serde_json::to_string_pretty(&installed)?);
+        }
+        ChromeCommand::Detect => {
+            let browsers = tokio::task::spawn_blocking(detect_browsers).await?;
+            println!("{}", serde_json::to_string_pretty(&browsers)?);
+        }
+        ChromeCommand::Verify => {
+            let mut all_ok = true;
+            for chrome in installer.list().await? {
+                let result = install::verify(&chrome).await;
+                all_ok &= result.is_ok();
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "version": chrome.version,
+                        "ok": result.is_ok(),
+                        "error": result.err().map(|e| e.to_string()),
+                    })
+                );
+            }
+            if !all_ok {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        ChromeCommand::Remove { version } => {
+            if !installer.remove(&version).await? {
+                anyhow::bail!("Chrome {} is not installed", version);
+            }
+            println!("Removed Chrome {}", version);
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_defaults_to_serve() {
+        let cli = Cli::parse_from(["robert-webdriver", "--port", "8080"]);
+        assert!(cli.command.is_none());
+        assert_eq!(cli.serve.port, Some(8080));
+
+        let cli = Cli::parse_from([
+            "robert-webdriver",
+            "run",
+            "script.json",
+            "--log-format",
+            "json",
+        ]);
+        assert!(matches!(cli.command, Some(Command::Run { .. })));
+        assert_eq!(cli.log_format, LogFormat::Json);
     }
 }
@@ -0,0 +1,207 @@
+//! Browser Flavors
+//!
+//! The Chromium-based browsers the driver can run (Chrome, Chromium, Edge, Brave)
+//! and the flavor and version recorded in execution reports and step frames, so
+//! results from different browsers can be told apart, plus the check that a
+//! browser is new enough for the commands the executor sends.
+
+use serde::{Deserialize, Serialize};
+
+/// A Chromium-based browser
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "runtime", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum BrowserKind {
+    Chrome,
+    Chromium,
+    Edge,
+    Brave,
+}
+
+impl BrowserKind {
+    /// The browser an executable belongs to, judging by its path (Chrome if unknown)
+    ///
+    /// Matches the launcher and bundle names vendors use on Linux, macOS, and
+    /// Windows, e.g. `microsoft-edge`, `Brave Browser.app`, or `msedge.exe`.
+    pub fn from_executable(path: &str) -> Self {
+        let path = path.to_lowercase();
+        if path.contains("msedge")
+            || path.contains("microsoft edge")
+            || path.contains("microsoft-edge")
+        {
+            Self::Edge
+        } else if path.contains("brave") {
+            Self::Brave
+        } else if path.contains("chromium") {
+            Self::Chromium
+        } else {
+            Self::Chrome
+        }
+    }
+
+    /// The browser behind a `Browser.getVersion` user agent (Brave reports itself as Chrome)
+    pub fn from_user_agent(user_agent: &str) -> Self {
+        if user_agent.contains(" Edg/") {
+            Self::Edge
+        } else {
+            Self::Chrome
+        }
+    }
+
+    /// Human-readable name, e.g. `Microsoft Edge`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Chrome => "Google Chrome",
+            Self::Chromium => "Chromium",
+            Self::Edge => "Microsoft Edge",
+            Self::Brave => "Brave",
+        }
+    }
+}
+
+/// Oldest Chromium major version the executor supports; older releases lack commands
+/// and parameters it sends (e.g. `Input.imeSetComposition`, `captureBeyondViewport`)
+pub const MIN_CHROME_MAJOR: u32 = 100;
+
+/// Oldest DevTools protocol version the executor supports
+pub const MIN_PROTOCOL_VERSION: &str = "1.3";
+
+/// The browser a run or frame was captured with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "runtime", derive(utoipa::ToSchema))]
+pub struct BrowserInfo {
+    pub kind: BrowserKind,
+
+    /// Chromium version (e.g. `128.0.6613.119`)
+    pub version: String,
+
+    /// DevTools protocol version (e.g. `1.3`)
+    #[serde(default)]
+    pub protocol_version: String,
+}
+
+impl BrowserInfo {
+    /// Browser info from `Browser.getVersion`'s product (`HeadlessChrome/128.0.6613.119`)
+    /// and protocol version
+    pub fn from_version(kind: BrowserKind, product: &str, protocol_version: &str) -> Self {
+        let version = product.rsplit('/').next().unwrap_or(product);
+        Self {
+            kind,
+            version: version.to_string(),
+            protocol_version: protocol_version.to_string(),
+        }
+    }
+
+    /// Why the executor may not work with this browser, or None if it's new enough
+    ///
+    /// Versions that can't be parsed are given the benefit of the doubt.
+    pub fn compatibility_issue(&self) -> Option<String> {
+        let numbers = |version: &str| -> Vec<u32> {
+            version
+                .split('.')
+                .map_while(|part| part.parse().ok())
+                .collect()
+        };
+
+        let protocol = numbers(&self.protocol_version);
+        if !protocol.is_empty() && protocol < numbers(MIN_PROTOCOL_VERSION) {
+            return Some(format!(
+                "{} speaks DevTools protocol {}, older than the {} the executor needs",
+                self.kind.name(),
+                self.protocol_version,
+                MIN_PROTOCOL_VERSION
+            ));
+        }
+        match numbers(&self.version).first() {
+            Some(&major) if major < MIN_CHROME_MAJOR => Some(format!(
+                "{} {} is older than {}, the oldest version whose protocol has every command \
+                 the executor sends; update it",
+                self.kind.name(),
+                self.version,
+                MIN_CHROME_MAJOR
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// What to do when the browser is older than the executor supports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionCheck {
+    /// Don't check
+    Off,
+    /// Log a warning and continue
+    #[default]
+    Warn,
+    /// Fail to launch or connect
+    Fail,
+}
+
+impl std::str::FromStr for VersionCheck {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "warn" => Ok(Self::Warn),
+            "fail" => Ok(Self::Fail),
+            other => Err(format!(
+                "Unknown version check '{}' (expected: off, warn, fail)",
+                other
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_browser_kind() {
+        let kind = BrowserKind::from_executable;
+        assert_eq!(kind("/usr/bin/google-chrome-stable"), BrowserKind::Chrome);
+        assert_eq!(kind("/usr/bin/microsoft-edge"), BrowserKind::Edge);
+        assert_eq!(
+            kind(r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe"),
+            BrowserKind::Edge
+        );
+        assert_eq!(
+            kind("/Applications/Brave Browser.app/Contents/MacOS/Brave Browser"),
+            BrowserKind::Brave
+        );
+        assert_eq!(kind("/snap/bin/chromium"), BrowserKind::Chromium);
+
+        let edge = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) \
+                    Chrome/128.0.0.0 Safari/537.36 Edg/128.0.2739.42";
+        assert_eq!(BrowserKind::from_user_agent(edge), BrowserKind::Edge);
+
+        let info =
+            BrowserInfo::from_version(BrowserKind::Chrome, "HeadlessChrome/128.0.6613.119", "1.3");
+        assert_eq!(info.version, "128.0.6613.119");
+        assert_eq!(info.compatibility_issue(), None);
+    }
+
+    #[test]
+    fn test_compatibility_issue() {
+        let old = BrowserInfo::from_version(BrowserKind::Chromium, "Chrome/83.0.4103.0", "1.3");
+        let issue = old.compatibility_issue().unwrap();
+        assert!(
+            issue.starts_with("Chromium 83.0.4103.0 is older than 100"),
+            "{}",
+            issue
+        );
+
+        let old_protocol =
+            BrowserInfo::from_version(BrowserKind::Chrome, "Chrome/120.0.0.0", "1.2");
+        assert!(old_protocol
+            .compatibility_issue()
+            .unwrap()
+            .contains("protocol 1.2"));
+
+        let unknown = BrowserInfo::from_version(BrowserKind::Chrome, "Chrome/dev", "");
+        assert_eq!(unknown.compatibility_issue(), None);
+        assert_eq!("fail".parse::<VersionCheck>(), Ok(VersionCheck::Fail));
+    }
+}
@@ -0,0 +1,685 @@
+//! CDP Script Builder
+//!
+//! A fluent, typed way to write [`CdpScript`]s in Rust instead of `json!` maps:
+//!
+//! ```
+//! use robert_webdriver::model::builder::{ImageFormat, ScriptSteps};
+//! use robert_webdriver::model::script::CdpScript;
+//!
+//! let script = CdpScript::builder("homepage-check", "Screenshot the homepage")
+//!     .tag("smoke")
+//!     .navigate("https://example.com")
+//!     .wait_for_load()
+//!     .evaluate("document.title")
+//!     .save_as("title.json")
+//!     .screenshot()
+//!     .full_page()
+//!     .format(ImageFormat::Png)
+//!     .save_as("home.png")
+//!     .build();
+//! assert_eq!(script.cdp_commands.len(), 4);
+//! ```
+//!
+//! Each step only offers the options its command accepts (`save_as` exists for
+//! commands that save output, `full_page` only for screenshots), and `build` is only
+//! available once there is at least one step, so built scripts pass
+//! [`CdpValidator`](crate::model::validation::CdpValidator) without errors. Steps
+//! without a description get a generated one. Commands the builder has no helper
+//! for can be added with [`ScriptSteps::typed`], or with [`ScriptSteps::command`],
+//! which isn't checked.
+
+use crate::model::fingerprint::ElementFingerprint;
+use crate::model::lint::{describe, WAIT_FOR_LOAD};
+use crate::model::script::{CdpCommand, CdpScript};
+use crate::model::typed::TypedCommand;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+impl CdpScript {
+    /// Start building a script
+    ///
+    /// `name` should be lowercase-hyphenated (e.g. `checkout-smoke`).
+    pub fn builder(name: impl Into<String>, description: impl Into<String>) -> CdpScriptBuilder {
+        CdpScriptBuilder {
+            script: CdpScript {
+                name: name.into(),
+                description: description.into(),
+                created: None,
+                author: None,
+                version: None,
+                tags: Vec::new(),
+                cdp_commands: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Builder for a [`CdpScript`], before its first step
+///
+/// Created by [`CdpScript::builder`]; add steps with the [`ScriptSteps`] methods.
+#[derive(Debug, Clone)]
+pub struct CdpScriptBuilder {
+    script: CdpScript,
+}
+
+impl CdpScriptBuilder {
+    /// Set the author
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.script.author = Some(author.into());
+        self
+    }
+
+    /// Set the creation timestamp (ISO 8601)
+    pub fn created(mut self, created: impl Into<String>) -> Self {
+        self.script.created = Some(created.into());
+        self
+    }
+
+    /// Add a tag
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.script.tags.push(tag.into());
+        self
+    }
+}
+
+/// A step just added to a script, which can still be configured
+///
+/// `K` is the kind of command, which decides the options available.
+#[derive(Debug, Clone)]
+pub struct Step<K> {
+    builder: CdpScriptBuilder,
+    command: CdpCommand,
+    kind: PhantomData<K>,
+}
+
+/// Step kind of commands without options
+#[derive(Debug, Clone, Copy)]
+pub struct Plain;
+
+/// Step kind of `Runtime.evaluate`
+#[derive(Debug, Clone, Copy)]
+pub struct Evaluate;
+
+/// Step kind of `Page.captureScreenshot`
+#[derive(Debug, Clone, Copy)]
+pub struct Screenshot;
+
+/// Step kind of commands whose output can be saved to a file
+#[derive(Debug, Clone, Copy)]
+pub struct Output;
+
+/// Step kind of commands that act on an element found by selector
+#[derive(Debug, Clone, Copy)]
+pub struct Element;
+
+/// Step kind of `Element.waitFor`
+#[derive(Debug, Clone, Copy)]
+pub struct Wait;
+
+/// Step kind of `Assert.snapshot`
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot;
+
+/// Step kind of `Assert.text`
+#[derive(Debug, Clone, Copy)]
+pub struct Text;
+
+/// Screenshot image format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl ImageFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpeg",
+            ImageFormat::Webp => "webp",
+        }
+    }
+}
+
+impl<K> Step<K> {
+    /// Describe the step (instead of the generated description)
+    pub fn describe(mut self, description: impl Into<String>) -> Self {
+        self.command.description = Some(description.into());
+        self
+    }
+
+    /// Finish the script
+    pub fn build(self) -> CdpScript {
+        self.into_builder().script
+    }
+
+    fn param(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.command.params[name] = value.into();
+        self
+    }
+
+    fn save_to(mut self, path: impl Into<String>) -> Self {
+        self.command.save_as = Some(path.into());
+        self
+    }
+}
+
+impl Step<Evaluate> {
+    /// Wait for the promise the expression returns to settle
+    pub fn await_promise(self) -> Self {
+        self.param("awaitPromise", true)
+    }
+
+    /// Return the result by value rather than as a remote object reference
+    pub fn return_by_value(self) -> Self {
+        self.param("returnByValue", true)
+    }
+
+    /// Evaluate as if triggered by a user gesture (allows popups, fullscreen, ...)
+    pub fn user_gesture(self) -> Self {
+        self.param("userGesture", true)
+    }
+
+    /// Save the result as JSON to `path` (relative to the output directory)
+    pub fn save_as(self, path: impl Into<String>) -> Self {
+        self.save_to(path)
+    }
+}
+
+impl Step<Screenshot> {
+    /// Capture the whole page rather than the viewport
+    pub fn full_page(self) -> Self {
+        self.param("captureBeyondViewport", true)
+    }
+
+    /// Image format (PNG by default)
+    pub fn format(self, format: ImageFormat) -> Self {
+        self.param("format", format.as_str())
+    }
+
+    /// Compression quality from 0 to 100 (JPEG and WebP only)
+    pub fn quality(self, quality: u8) -> Self {
+        self.param("quality", quality.min(100))
+    }
+
+    /// Save the image to `path` (relative to the output directory)
+    pub fn save_as(self, path: impl Into<String>) -> Self {
+        self.save_to(path)
+    }
+}
+
+impl Step<Element> {
+    /// Selector to try if the earlier ones match nothing
+    pub fn fallback(mut self, selector: impl Into<String>) -> Self {
+        let selector = Value::from(selector.into());
+        let params = &mut self.command.params;
+        match params["fallbackSelectors"].as_array_mut() {
+            Some(selectors) => selectors.push(selector),
+            None => params["fallbackSelectors"] = json!([selector]),
+        }
+        self
+    }
+
+    /// Find the element by its visible text if no selector matches
+    pub fn fallback_text(self, text: impl Into<String>) -> Self {
+        self.param("fallbackText", text.into())
+    }
+
+    /// Find the element most like `fingerprint` (recorded with
+    /// `ChromeDriver::element_fingerprint`) if no selector matches
+    pub fn fingerprint(self, fingerprint: &ElementFingerprint) -> Self {
+        self.param("fingerprint", json!(fingerprint))
+    }
+
+    /// Lowest similarity (0 to 1) accepted for a fingerprint match
+    pub fn fingerprint_threshold(self, threshold: f64) -> Self {
+        self.param("fingerprintThreshold", threshold)
+    }
+}
+
+impl Step<Wait> {
+    /// Give up after `timeout` (10 seconds by default)
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.param("timeout", timeout.as_millis() as u64)
+    }
+
+    /// Stop waiting once the element is in the DOM, even if it isn't rendered
+    pub fn attached(self) -> Self {
+        self.param("visible", false)
+    }
+}
+
+impl Step<Snapshot> {
+    /// Leave the values at `path` (JSONPath, e.g. `$.items[*].id`) out of the
+    /// comparison
+    pub fn ignore(mut self, path: impl Into<String>) -> Self {
+        let path = Value::from(path.into());
+        let params = &mut self.command.params;
+        match params["ignorePaths"].as_array_mut() {
+            Some(paths) => paths.push(path),
+            None => params["ignorePaths"] = json!([path]),
+        }
+        self
+    }
+
+    /// Save the extracted JSON to `path` (relative to the output directory)
+    pub fn save_as(self, path: impl Into<String>) -> Self {
+        self.save_to(path)
+    }
+}
+
+impl Step<Text> {
+    /// Only look inside the first element matching `selector`
+    pub fn within(self, selector: impl Into<String>) -> Self {
+        self.param("selector", selector.into())
+    }
+
+    /// Match an element's whole text rather than text it contains
+    pub fn exact(self) -> Self {
+        self.param("exact", true)
+    }
+
+    /// Compare case
+    pub fn case_sensitive(self) -> Self {
+        self.param("caseSensitive", true)
+    }
+
+    /// Compare diacritics (`é` doesn't match `e`)
+    pub fn keep_diacritics(self) -> Self {
+        self.param("foldDiacritics", false)
+    }
+
+    /// Also accept text at least `threshold` (0 to 1) similar
+    pub fn fuzzy(self, threshold: f64) -> Self {
+        self.param("fuzzy", threshold)
+    }
+}
+
+impl Step<Output> {
+    /// Save the output as JSON to `path` (relative to the output directory)
+    pub fn save_as(self, path: impl Into<String>) -> Self {
+        self.save_to(path)
+    }
+}
+
+/// Steps that can be added to a script, after the header or after another step
+pub trait ScriptSteps: Sized {
+    /// The builder with every step so far added
+    fn into_builder(self) -> CdpScriptBuilder;
+
+    /// Add a command the builder has no helper for (not checked)
+    fn command(self, command: CdpCommand) -> Step<Plain> {
+        Step {
+            builder: self.into_builder(),
+            command,
+            kind: PhantomData,
+        }
+    }
+
+    /// Add a [`TypedCommand`], whose params the compiler checks
+    fn typed(self, command: TypedCommand) -> Step<Plain> {
+        self.command(command.into())
+    }
+
+    /// Navigate to `url` (`Page.navigate`)
+    ///
+    /// Follow with [`ScriptSteps::wait_for_load`] before reading the page.
+    fn navigate(self, url: impl Into<String>) -> Step<Plain> {
+        step(self, "Page.navigate", json!({ "url": url.into() }))
+    }
+
+    /// Reload the page (`Page.reload`)
+    fn reload(self) -> Step<Plain> {
+        step(self, "Page.reload", json!({}))
+    }
+
+    /// Go back in history (`Page.goBack`)
+    fn go_back(self) -> Step<Plain> {
+        step(self, "Page.goBack", json!({}))
+    }
+
+    /// Go forward in history (`Page.goForward`)
+    fn go_forward(self) -> Step<Plain> {
+        step(self, "Page.goForward", json!({}))
+    }
+
+    /// Wait until the page has finished loading
+    fn wait_for_load(self) -> Step<Evaluate> {
+        step(
+            self,
+            "Runtime.evaluate",
+            json!({ "expression": WAIT_FOR_LOAD, "awaitPromise": true }),
+        )
+        .describe("Wait for the page to load")
+    }
+
+    /// Evaluate a JavaScript expression in the page (`Runtime.evaluate`)
+    fn evaluate(self, expression: impl Into<String>) -> Step<Evaluate> {
+        step(
+            self,
+            "Runtime.evaluate",
+            json!({ "expression": expression.into() }),
+        )
+    }
+
+    /// Take a screenshot of the viewport (`Page.captureScreenshot`)
+    fn screenshot(self) -> Step<Screenshot> {
+        step(self, "Page.captureScreenshot", json!({}))
+    }
+
+    /// Insert text at the focused element (`Input.insertText`)
+    fn insert_text(self, text: impl Into<String>) -> Step<Plain> {
+        step(self, "Input.insertText", json!({ "text": text.into() }))
+    }
+
+    /// Focus the element matching `selector` and type `text` key by key
+    /// (`Form.typeText`)
+    fn type_text(self, selector: impl Into<String>, text: impl Into<String>) -> Step<Element> {
+        step(
+            self,
+            "Form.typeText",
+            json!({ "selector": selector.into(), "text": text.into() }),
+        )
+    }
+
+    /// Tap the element matching `selector` (`Touch.tap`)
+    fn tap(self, selector: impl Into<String>) -> Step<Element> {
+        step(self, "Touch.tap", json!({ "selector": selector.into() }))
+    }
+
+    /// Click the element matching `selector` with the mouse (`Element.click`)
+    fn click(self, selector: impl Into<String>) -> Step<Element> {
+        step(
+            self,
+            "Element.click",
+            json!({ "selector": selector.into() }),
+        )
+    }
+
+    /// Click the element matching `selector` and type `text` over its contents
+    /// (`Element.type`)
+    fn type_into(self, selector: impl Into<String>, text: impl Into<String>) -> Step<Element> {
+        step(
+            self,
+            "Element.type",
+            json!({ "selector": selector.into(), "text": text.into() }),
+        )
+    }
+
+    /// Wait until an element matching `selector` is rendered (`Element.waitFor`)
+    fn wait_for(self, selector: impl Into<String>) -> Step<Wait> {
+        step(
+            self,
+            "Element.waitFor",
+            json!({ "selector": selector.into() }),
+        )
+    }
+
+    /// Scroll the element matching `selector` into view (`Element.scrollTo`)
+    fn scroll_to(self, selector: impl Into<String>) -> Step<Element> {
+        step(
+            self,
+            "Element.scrollTo",
+            json!({ "selector": selector.into() }),
+        )
+    }
+
+    /// Read the page's cookies (`Network.getCookies`)
+    fn get_cookies(self) -> Step<Output> {
+        step(self, "Network.getCookies", json!({}))
+    }
+
+    /// Extract SEO metadata (`Extract.seo`)
+    fn extract_seo(self) -> Step<Output> {
+        step(self, "Extract.seo", json!({}))
+    }
+
+    /// Compare the JSON `expression` returns with the snapshot called `name`,
+    /// storing it on the first run (`Assert.snapshot`)
+    fn assert_snapshot(
+        self,
+        name: impl Into<String>,
+        expression: impl Into<String>,
+    ) -> Step<Snapshot> {
+        step(
+            self,
+            "Assert.snapshot",
+            json!({ "name": name.into(), "expression": expression.into() }),
+        )
+    }
+
+    /// Check that `text` is on the page, ignoring case, diacritics, and nbsp
+    /// (`Assert.text`)
+    fn assert_text(self, text: impl Into<String>) -> Step<Text> {
+        step(self, "Assert.text", json!({ "text": text.into() }))
+    }
+
+    /// Check that the page meets `goal`, a natural-language success criterion, and
+    /// record the verdict in the report (`Verify.goal`)
+    fn verify_goal(self, goal: impl Into<String>) -> Step<Output> {
+        step(self, "Verify.goal", json!({ "goal": goal.into() }))
+    }
+}
+
+impl ScriptSteps for CdpScriptBuilder {
+    fn into_builder(self) -> CdpScriptBuilder {
+        self
+    }
+}
+
+impl<K> ScriptSteps for Step<K> {
+    fn into_builder(self) -> CdpScriptBuilder {
+        let mut builder = self.builder;
+        let mut command = self.command;
+        if command.description.is_none() {
+            command.description = Some(describe(&command));
+        }
+        builder.script.cdp_commands.push(command);
+        builder
+    }
+}
+
+fn step<K>(steps: impl ScriptSteps, method: &str, params: Value) -> Step<K> {
+    Step {
+        builder: steps.into_builder(),
+        command: CdpCommand {
+            method: method.to_string(),
+            params,
+            save_as: None,
+            description: None,
+        },
+        kind: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::lint::CdpLinter;
+    use crate::model::validation::{CdpValidator, ValidationResult};
+
+    #[test]
+    fn test_builder_output() {
+        let script = CdpScript::builder("login-check", "Log in and check the dashboard")
+            .author("QA")
+            .tag("smoke")
+            .navigate("https://example.com/login")
+            .wait_for_load()
+            .type_text("#email", "qa@example.com")
+            .fallback("input[type=email]")
+            .fallback("input[name=email]")
+            .fallback_text("Email")
+            .fingerprint(&ElementFingerprint {
+                tag: "input".to_string(),
+                ..Default::default()
+            })
+            .describe("Enter the email")
+            .evaluate("document.querySelector('form').submit()")
+            .user_gesture()
+            .evaluate("document.title")
+            .return_by_value()
+            .save_as("title.json")
+            .screenshot()
+            .full_page()
+            .format(ImageFormat::Jpeg)
+            .quality(80)
+            .save_as("dashboard.jpg")
+            .get_cookies()
+            .save_as("cookies.json")
+            .build();
+
+        assert_eq!(script.tags, ["smoke"]);
+        let commands = &script.cdp_commands;
+        assert_eq!(commands.len(), 7);
+        assert_eq!(commands[0].method, "Page.navigate");
+        assert_eq!(
+            commands[0].description.as_deref(),
+            Some("Navigate to https://example.com/login")
+        );
+        assert_eq!(commands[2].description.as_deref(), Some("Enter the email"));
+        assert_eq!(
+            commands[2].params["fallbackSelectors"],
+            json!(["input[type=email]", "input[name=email]"])
+        );
+        assert_eq!(commands[2].params["fallbackText"], "Email");
+        assert_eq!(commands[2].params["fingerprint"], json!({ "tag": "input" }));
+        assert_eq!(
+            commands[5].params,
+            json!({ "captureBeyondViewport": true, "format": "jpeg", "quality": 80 })
+        );
+        assert_eq!(commands[5].save_as.as_deref(), Some("dashboard.jpg"));
+        assert_eq!(commands[6].save_as.as_deref(), Some("cookies.json"));
+
+        // Built scripts validate and lint clean
+        let mut result = ValidationResult::success();
+        CdpValidator::new().validate_script(&script, &mut result);
+        assert!(result.is_valid, "{:?}", result.errors);
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+        assert!(CdpLinter::new().lint(&script).is_empty());
+
+        // And round-trip through JSON
+        let json = serde_json::to_string(&script).unwrap();
+        let parsed: CdpScript = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.cdp_commands.len(), 7);
+    }
+
+    #[test]
+    fn test_assert_snapshot() {
+        let script = CdpScript::builder("prices", "Check the price list")
+            .navigate("https://example.com/prices")
+            .wait_for_load()
+            .assert_snapshot("prices", "window.prices")
+            .ignore("$.updated")
+            .ignore("$.items[*].id")
+            .save_as("prices.json")
+            .build();
+        let command = &script.cdp_commands[2];
+        assert_eq!(command.method, "Assert.snapshot");
+        assert_eq!(
+            command.params,
+            json!({
+                "name": "prices",
+                "expression": "window.prices",
+                "ignorePaths": ["$.updated", "$.items[*].id"],
+            })
+        );
+        let mut result = ValidationResult::success();
+        CdpValidator::new().validate_script(&script, &mut result);
+        assert!(result.is_valid, "{:?}", result.errors);
+    }
+
+    #[test]
+    fn test_assert_text() {
+        let script = CdpScript::builder("cart", "Add to the cart")
+            .navigate("https://example.com/product")
+            .wait_for_load()
+            .assert_text("Ajouté au panier")
+            .within(".notice")
+            .fuzzy(0.85)
+            .build();
+        let command = &script.cdp_commands[2];
+        assert_eq!(command.method, "Assert.text");
+        assert_eq!(
+            command.params,
+            json!({ "text": "Ajouté au panier", "selector": ".notice", "fuzzy": 0.85 })
+        );
+        let mut result = ValidationResult::success();
+        CdpValidator::new().validate_script(&script, &mut result);
+        assert!(result.is_valid, "{:?}", result.errors);
+    }
+
+    #[test]
+    fn test_verify_goal() {
+        let script = CdpScript::builder("order", "Place an order")
+            .navigate("https://example.com/checkout")
+            .wait_for_load()
+            .verify_goal("The page shows \"Order confirmed\"")
+            .save_as("verdict.json")
+            .build();
+        let command = &script.cdp_commands[2];
+        assert_eq!(command.method, "Verify.goal");
+        assert_eq!(command.params["goal"], "The page shows \"Order confirmed\"");
+        assert_eq!(command.save_as.as_deref(), Some("verdict.json"));
+        let mut result = ValidationResult::success();
+        CdpValidator::new().validate_script(&script, &mut result);
+        assert!(result.is_valid, "{:?}", result.errors);
+    }
+
+    #[test]
+    fn test_macro_steps() {
+        let script = CdpScript::builder("search", "Search the docs")
+            .navigate("https://example.com")
+            .wait_for("input[name=q]")
+            .timeout(Duration::from_secs(5))
+            .type_into("input[name=q]", "builder")
+            .fallback_text("Search")
+            .click("button[type=submit]")
+            .fallback("form button")
+            .scroll_to("#results")
+            .wait_for(".spinner")
+            .attached()
+            .build();
+        let commands = &script.cdp_commands;
+        assert_eq!(
+            commands[1].params,
+            json!({ "selector": "input[name=q]", "timeout": 5000 })
+        );
+        assert_eq!(commands[2].params["fallbackText"], "Search");
+        assert_eq!(
+            commands[3].description.as_deref(),
+            Some("Click button[type=submit]")
+        );
+        assert_eq!(
+            commands[3].params["fallbackSelectors"],
+            json!(["form button"])
+        );
+        assert_eq!(commands[4].method, "Element.scrollTo");
+        assert_eq!(commands[5].params["visible"], false);
+
+        let mut result = ValidationResult::success();
+        CdpValidator::new().validate_script(&script, &mut result);
+        assert!(result.is_valid, "{:?}", result.errors);
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+    }
+
+    #[test]
+    fn test_raw_command() {
+        let script = CdpScript::builder("raw", "Raw command")
+            .command(CdpCommand {
+                method: "Emulation.setEmulatedMedia".to_string(),
+                params: json!({ "media": "print" }),
+                save_as: None,
+                description: None,
+            })
+            .build();
+        assert_eq!(script.cdp_commands[0].params["media"], "print");
+        assert_eq!(
+            script.cdp_commands[0].description.as_deref(),
+            Some("Run Emulation.setEmulatedMedia")
+        );
+    }
+}
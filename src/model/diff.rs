@@ -0,0 +1,381 @@
+//! Script Diffs
+//!
+//! Structured comparison of two versions of a [`CdpScript`]: metadata changes and
+//! commands added, removed, or modified (with their field changes), plus the
+//! [`VersionBump`] the change calls for.
+//!
+//! Commands are aligned on their longest common subsequence (ignoring
+//! descriptions); between aligned commands, commands with the same method are
+//! paired up as modifications and the rest are additions and removals.
+
+use crate::model::script::{CdpCommand, CdpScript};
+use crate::model::version::VersionBump;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Changes from one script to another
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ScriptDiff {
+    /// Changed script fields (`name`, `description`, `version`, `author`, `tags`)
+    pub metadata: Vec<FieldChange>,
+
+    /// Commands only in the new script
+    pub added: Vec<CommandRef>,
+
+    /// Commands only in the old script
+    pub removed: Vec<CommandRef>,
+
+    /// Commands in both scripts that changed
+    pub modified: Vec<CommandChange>,
+}
+
+/// A changed field; missing values are `null`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldChange {
+    /// Field path (`description`, `params.url`, `save_as`)
+    pub field: String,
+
+    pub before: Value,
+
+    pub after: Value,
+}
+
+/// A command added or removed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommandRef {
+    /// Index in the script it is in (0-based)
+    pub index: usize,
+
+    pub method: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// A command present in both scripts, with its changes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommandChange {
+    /// Index in the old script (0-based)
+    pub old_index: usize,
+
+    /// Index in the new script (0-based)
+    pub new_index: usize,
+
+    pub method: String,
+
+    pub changes: Vec<FieldChange>,
+}
+
+impl ScriptDiff {
+    /// Whether the scripts are the same (apart from the version)
+    pub fn is_empty(&self) -> bool {
+        self.required_bump() == VersionBump::None
+    }
+
+    /// The smallest version bump this change needs
+    ///
+    /// Removing a command or a saved output (`save_as`) breaks whoever relies on it
+    /// (major); adding commands or changing parameters is compatible (minor); other
+    /// changes are cosmetic (patch).
+    pub fn required_bump(&self) -> VersionBump {
+        let removes_output = self.modified.iter().any(|command| {
+            command
+                .changes
+                .iter()
+                .any(|change| change.field == "save_as" && !change.before.is_null())
+        });
+        let changes_behavior = self.modified.iter().any(|command| {
+            command
+                .changes
+                .iter()
+                .any(|change| change.field != "description")
+        });
+
+        if !self.removed.is_empty() || removes_output {
+            VersionBump::Major
+        } else if !self.added.is_empty() || changes_behavior {
+            VersionBump::Minor
+        } else if self.modified.is_empty()
+            && self.metadata.iter().all(|change| change.field == "version")
+        {
+            VersionBump::None
+        } else {
+            VersionBump::Patch
+        }
+    }
+}
+
+impl CdpScript {
+    /// Changes from this script to `other`
+    pub fn diff(&self, other: &CdpScript) -> ScriptDiff {
+        let mut diff = ScriptDiff::default();
+
+        let fields = [
+            ("name", json_of(&self.name), json_of(&other.name)),
+            (
+                "description",
+                json_of(&self.description),
+                json_of(&other.description),
+            ),
+            ("version", json_of(&self.version), json_of(&other.version)),
+            ("author", json_of(&self.author), json_of(&other.author)),
+            ("tags", json_of(&self.tags), json_of(&other.tags)),
+        ];
+        for (field, before, after) in fields {
+            if before != after {
+                diff.metadata.push(FieldChange {
+                    field: field.to_string(),
+                    before,
+                    after,
+                });
+            }
+        }
+
+        let old = &self.cdp_commands;
+        let new = &other.cdp_commands;
+        let mut aligned = common_subsequence(old, new);
+        aligned.push((old.len(), new.len()));
+
+        let (mut old_start, mut new_start) = (0, 0);
+        for (old_end, new_end) in aligned {
+            diff_gap(&mut diff, old, old_start..old_end, new, new_start..new_end);
+            if old_end < old.len() {
+                // Aligned commands can only differ in their descriptions
+                let changes = command_changes(&old[old_end], &new[new_end]);
+                if !changes.is_empty() {
+                    diff.modified.push(CommandChange {
+                        old_index: old_end,
+                        new_index: new_end,
+                        method: new[new_end].method.clone(),
+                        changes,
+                    });
+                }
+            }
+            (old_start, new_start) = (old_end + 1, new_end + 1);
+        }
+
+        diff.modified.sort_by_key(|change| change.new_index);
+        diff
+    }
+}
+
+fn json_of<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// Whether two commands do the same thing (descriptions aside)
+fn same_behavior(a: &CdpCommand, b: &CdpCommand) -> bool {
+    a.method == b.method && a.params == b.params && a.save_as == b.save_as
+}
+
+/// Index pairs of a longest common subsequence of `old` and `new`, in order
+fn common_subsequence(old: &[CdpCommand], new: &[CdpCommand]) -> Vec<(usize, usize)> {
+    // lengths[i][j] = LCS length of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if same_behavior(&old[i], &new[j]) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if same_behavior(&old[i], &new[j]) {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Record the commands between two aligned pairs: same-method commands are paired
+/// up as modifications, the rest are removals and additions
+fn diff_gap(
+    diff: &mut ScriptDiff,
+    old: &[CdpCommand],
+    old_range: std::ops::Range<usize>,
+    new: &[CdpCommand],
+    new_range: std::ops::Range<usize>,
+) {
+    let mut unmatched_new: Vec<usize> = new_range.collect();
+    for old_index in old_range {
+        let method = &old[old_index].method;
+        match unmatched_new
+            .iter()
+            .position(|&new_index| new[new_index].method == *method)
+        {
+            Some(position) => {
+                let new_index = unmatched_new.remove(position);
+                diff.modified.push(CommandChange {
+                    old_index,
+                    new_index,
+                    method: method.clone(),
+                    changes: command_changes(&old[old_index], &new[new_index]),
+                });
+            }
+            None => diff.removed.push(command_ref(old, old_index)),
+        }
+    }
+    diff.added.extend(
+        unmatched_new
+            .into_iter()
+            .map(|new_index| command_ref(new, new_index)),
+    );
+}
+
+fn command_ref(commands: &[CdpCommand], index: usize) -> CommandRef {
+    CommandRef {
+        index,
+        method: commands[index].method.clone(),
+        description: commands[index].description.clone(),
+    }
+}
+
+/// Field changes between two commands with the same method
+fn command_changes(old: &CdpCommand, new: &CdpCommand) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    match (old.params.as_object(), new.params.as_object()) {
+        (Some(before), Some(after)) => {
+            let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+            names.sort();
+            names.dedup();
+            for name in names {
+                let (before, after) = (before.get(name), after.get(name));
+                if before != after {
+                    changes.push(FieldChange {
+                        field: format!("params.{}", name),
+                        before: before.cloned().unwrap_or(Value::Null),
+                        after: after.cloned().unwrap_or(Value::Null),
+                    });
+                }
+            }
+        }
+        _ if old.params != new.params => changes.push(FieldChange {
+            field: "params".to_string(),
+            before: old.params.clone(),
+            after: new.params.clone(),
+        }),
+        _ => {}
+    }
+    if old.save_as != new.save_as {
+        changes.push(FieldChange {
+            field: "save_as".to_string(),
+            before: json_of(&old.save_as),
+            after: json_of(&new.save_as),
+        });
+    }
+    if old.description != new.description {
+        changes.push(FieldChange {
+            field: "description".to_string(),
+            before: json_of(&old.description),
+            after: json_of(&new.description),
+        });
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn script(version: &str, commands: Value) -> CdpScript {
+        serde_json::from_value(json!({
+            "name": "checkout",
+            "description": "Checkout flow",
+            "version": version,
+            "cdp_commands": commands,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff_commands() {
+        let old = script(
+            "1.0.0",
+            json!([
+                { "method": "Page.navigate", "params": { "url": "https://shop.test/cart" } },
+                { "method": "Runtime.evaluate", "params": { "expression": "document.title" },
+                  "save_as": "title.json" },
+                { "method": "Page.captureScreenshot", "params": {}, "save_as": "cart.png" },
+                { "method": "Network.getCookies", "params": {} },
+            ]),
+        );
+        let new = script(
+            "1.1.0",
+            json!([
+                { "method": "Page.navigate", "params": { "url": "https://shop.test/checkout" } },
+                { "method": "Runtime.evaluate", "params": { "expression": "document.title" },
+                  "save_as": "title.json", "description": "Read the title" },
+                { "method": "Input.insertText", "params": { "text": "4242" } },
+                { "method": "Page.captureScreenshot", "params": { "format": "jpeg" },
+                  "save_as": "cart.png" },
+            ]),
+        );
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.metadata.len(), 1);
+        assert_eq!(diff.metadata[0].field, "version");
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].method, "Input.insertText");
+        assert_eq!(diff.added[0].index, 2);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].method, "Network.getCookies");
+
+        let modified: Vec<(usize, usize, &str)> = diff
+            .modified
+            .iter()
+            .map(|change| {
+                (
+                    change.old_index,
+                    change.new_index,
+                    change.changes[0].field.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            modified,
+            [
+                (0, 0, "params.url"),
+                (1, 1, "description"),
+                (2, 3, "params.format")
+            ]
+        );
+        assert_eq!(diff.modified[2].changes[0].before, Value::Null);
+        assert_eq!(diff.modified[2].changes[0].after, "jpeg");
+
+        assert_eq!(diff.required_bump(), VersionBump::Major);
+    }
+
+    #[test]
+    fn test_required_bump() {
+        let commands = json!([
+            { "method": "Page.navigate", "params": { "url": "https://example.com" } },
+            { "method": "Page.captureScreenshot", "params": {}, "save_as": "home.png" },
+        ]);
+        let base = script("1.0.0", commands.clone());
+        assert!(base.diff(&script("1.0.1", commands.clone())).is_empty());
+
+        let mut described = base.clone();
+        described.description = "Homepage".to_string();
+        assert_eq!(base.diff(&described).required_bump(), VersionBump::Patch);
+
+        let mut added = base.clone();
+        added.cdp_commands.push(base.cdp_commands[0].clone());
+        assert_eq!(base.diff(&added).required_bump(), VersionBump::Minor);
+
+        let mut renamed = base.clone();
+        renamed.cdp_commands[1].save_as = Some("homepage.png".to_string());
+        assert_eq!(base.diff(&renamed).required_bump(), VersionBump::Major);
+    }
+}
@@ -0,0 +1,204 @@
+//! Element Fingerprints
+//!
+//! What an element looked like when a script was written (tag, text, identifying
+//! attributes, and position), stored next to a step's selector so the executor can
+//! find the element again after its selector stops matching. [`similarity`] scores a
+//! candidate element against the recorded fingerprint.
+//!
+//! [`similarity`]: ElementFingerprint::similarity
+
+use crate::model::geometry::Rect;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Lowest similarity accepted as the same element by default
+pub const DEFAULT_MATCH_THRESHOLD: f64 = 0.7;
+
+/// Attributes recorded in fingerprints (the ones that tend to identify an element)
+pub const FINGERPRINT_ATTRIBUTES: &[&str] = &[
+    "id",
+    "name",
+    "class",
+    "type",
+    "role",
+    "aria-label",
+    "placeholder",
+    "href",
+    "title",
+    "alt",
+    "data-testid",
+];
+
+/// Distance in CSS pixels at which position stops counting towards a match
+const MAX_DISTANCE: f64 = 500.0;
+
+// Weights of each feature in the similarity score
+const TAG_WEIGHT: f64 = 0.2;
+const TEXT_WEIGHT: f64 = 0.35;
+const ATTRIBUTES_WEIGHT: f64 = 0.3;
+const POSITION_WEIGHT: f64 = 0.15;
+
+/// How an element looked when it was recorded
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ElementFingerprint {
+    /// Tag name, lowercase (`button`)
+    pub tag: String,
+
+    /// Visible text (or value), whitespace collapsed
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+
+    /// Identifying attributes ([`FINGERPRINT_ATTRIBUTES`]) the element had
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+
+    /// Bounding box in page coordinates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rect: Option<Rect>,
+}
+
+impl ElementFingerprint {
+    /// How much `candidate` looks like this element, from 0 (nothing in common) to 1
+    ///
+    /// Tag, text, attributes, and position are compared and weighted (text most);
+    /// features this fingerprint doesn't have are left out rather than counted as
+    /// mismatches.
+    pub fn similarity(&self, candidate: &ElementFingerprint) -> f64 {
+        let mut score = 0.0;
+        let mut weight = 0.0;
+        let mut add = |feature: f64, feature_weight: f64| {
+            score += feature * feature_weight;
+            weight += feature_weight;
+        };
+
+        add(
+            if self.tag.eq_ignore_ascii_case(&candidate.tag) {
+                1.0
+            } else {
+                0.0
+            },
+            TAG_WEIGHT,
+        );
+        if !self.text.is_empty() {
+            add(text_similarity(&self.text, &candidate.text), TEXT_WEIGHT);
+        }
+        if !self.attributes.is_empty() {
+            let total: f64 = self
+                .attributes
+                .iter()
+                .map(|(name, value)| match candidate.attributes.get(name) {
+                    Some(other) if name == "class" => jaccard(&tokens(value), &tokens(other)),
+                    Some(other) if other == value => 1.0,
+                    _ => 0.0,
+                })
+                .sum();
+            add(total / self.attributes.len() as f64, ATTRIBUTES_WEIGHT);
+        }
+        if let (Some(rect), Some(other)) = (&self.rect, &candidate.rect) {
+            let (x, y) = rect.center();
+            let (other_x, other_y) = other.center();
+            let distance = (x - other_x).hypot(y - other_y);
+            add(1.0 - (distance / MAX_DISTANCE).min(1.0), POSITION_WEIGHT);
+        }
+
+        score / weight
+    }
+}
+
+/// Lowercase words of `s`
+fn tokens(s: &str) -> BTreeSet<String> {
+    s.split_whitespace().map(str::to_lowercase).collect()
+}
+
+/// Shared fraction of two sets (1 if both are empty)
+fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+fn text_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (tokens(a), tokens(b));
+    if a == b {
+        1.0
+    } else {
+        jaccard(&a, &b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button(text: &str, attributes: &[(&str, &str)], x: f64) -> ElementFingerprint {
+        ElementFingerprint {
+            tag: "button".to_string(),
+            text: text.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            rect: Some(Rect {
+                x,
+                y: 100.0,
+                width: 80.0,
+                height: 30.0,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_similarity() {
+        let recorded = button(
+            "Sign in",
+            &[("id", "login"), ("class", "btn btn-primary")],
+            200.0,
+        );
+        assert!((recorded.similarity(&recorded) - 1.0).abs() < 1e-9);
+
+        // The id changed, the element moved a little
+        let renamed = button(
+            "Sign in",
+            &[("id", "signin"), ("class", "btn btn-primary")],
+            220.0,
+        );
+        let score = recorded.similarity(&renamed);
+        assert!(score > DEFAULT_MATCH_THRESHOLD, "{}", score);
+
+        // A different button elsewhere
+        let other = button("Cancel", &[("class", "btn")], 700.0);
+        let score = recorded.similarity(&other);
+        assert!(score < DEFAULT_MATCH_THRESHOLD, "{}", score);
+        assert!(score < recorded.similarity(&renamed));
+
+        let link = ElementFingerprint {
+            tag: "a".to_string(),
+            ..renamed.clone()
+        };
+        assert!(recorded.similarity(&link) < recorded.similarity(&renamed));
+    }
+
+    #[test]
+    fn test_missing_features_are_skipped() {
+        let recorded = ElementFingerprint {
+            tag: "input".to_string(),
+            attributes: [("name".to_string(), "email".to_string())].into(),
+            ..Default::default()
+        };
+        let candidate = ElementFingerprint {
+            tag: "input".to_string(),
+            text: "anything".to_string(),
+            attributes: [("name".to_string(), "email".to_string())].into(),
+            rect: Some(Rect::default()),
+        };
+        assert!((recorded.similarity(&candidate) - 1.0).abs() < 1e-9);
+
+        let json = serde_json::to_value(&recorded).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "tag": "input", "attributes": { "name": "email" } })
+        );
+    }
+}
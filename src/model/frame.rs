@@ -0,0 +1,1016 @@
+//! Step Frame Schema
+//!
+//! The serialized form of step frames and their VisualDom snapshots, with the pure
+//! lookups on them (screenshot pixel to document point, hit testing, selectors).
+//! Capturing and loading frames is in `step_frame`.
+
+use crate::model::browser::BrowserInfo;
+use crate::model::geometry::Rect;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A complete step frame capturing a moment in a browser workflow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepFrame {
+    /// Unique frame identifier (sequential starting from 0)
+    pub frame_id: usize,
+
+    /// ISO 8601 timestamp when frame was captured
+    pub timestamp: String,
+
+    /// Milliseconds elapsed since workflow start
+    pub elapsed_ms: u64,
+
+    /// Visual state (screenshot)
+    pub screenshot: ScreenshotInfo,
+
+    /// Viewport and scroll position when the screenshot was taken
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub viewport: Option<ViewportInfo>,
+
+    /// DOM state
+    pub dom: DomInfo,
+
+    /// VisualDom state (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visual_dom: Option<VisualDomInfo>,
+
+    /// User/Agent action being performed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<ActionInfo>,
+
+    /// Natural language transcript
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transcript: Option<TranscriptInfo>,
+
+    /// Browser the frame was captured in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub browser: Option<BrowserInfo>,
+}
+
+/// Screenshot information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenshotInfo {
+    /// Relative or absolute path to screenshot file
+    pub path: String,
+
+    /// Image format (png, jpeg, webp)
+    pub format: String,
+
+    /// File size in bytes
+    pub size_bytes: usize,
+
+    /// Image dimensions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<Dimensions>,
+
+    /// SHA-256 hash for deduplication
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+
+    /// Size as captured, if the screenshot was downscaled to `max_screenshot_size`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_dimensions: Option<Dimensions>,
+
+    /// Small copy saved alongside the screenshot, if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<ThumbnailInfo>,
+
+    /// Copy with the interactive elements labelled (set-of-marks), if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marked: Option<MarkedScreenshot>,
+}
+
+/// Thumbnail saved next to a screenshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThumbnailInfo {
+    /// Path to thumbnail file (same format as the screenshot)
+    pub path: String,
+
+    /// File size in bytes
+    pub size_bytes: usize,
+
+    /// Image dimensions
+    pub dimensions: Dimensions,
+}
+
+/// Copy of a screenshot with a numbered label on each interactive element in view
+///
+/// Set-of-marks prompting: a vision model shown the labelled image can answer with
+/// a label ("click 7"), which the marks file maps back to a selector and a box.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkedScreenshot {
+    /// Path to the labelled image (the screenshot's format, at full resolution)
+    pub path: String,
+
+    /// Path to the JSON file holding the [`Mark`]s, in label order
+    pub marks_path: String,
+
+    /// Image file size in bytes
+    pub size_bytes: usize,
+
+    /// Number of labelled elements
+    pub count: usize,
+}
+
+/// An element labelled in a [`MarkedScreenshot`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mark {
+    /// Number drawn on the element, counting from 1 in document order
+    pub label: u32,
+
+    /// CSS selector of the element: the nearest unique `#id` ancestor, then
+    /// `tag:nth-of-type(n)` steps
+    pub selector: String,
+
+    /// Lowercase tag name
+    pub tag: String,
+
+    /// Accessible name: `aria-label`, the text, or a field's placeholder (first 100
+    /// characters; masked for redacted elements)
+    pub text: String,
+
+    /// Bounding box relative to the viewport (CSS pixels; screenshot pixels are
+    /// these times the device pixel ratio)
+    pub bounds: Rect,
+}
+
+/// One line per mark (`[3] button "Sign in"`), for telling a model what the labels are
+pub fn marks_legend(marks: &[Mark]) -> String {
+    marks
+        .iter()
+        .map(|mark| match mark.text.as_str() {
+            "" => format!("[{}] {}", mark.label, mark.tag),
+            text => format!("[{}] {} \"{}\"", mark.label, mark.tag, text),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Viewport geometry at capture time, for mapping screenshot pixels to the document
+///
+/// All lengths are CSS pixels. The screenshot shows the viewport, so screenshot pixel
+/// `(px, py)` is at document position `(scroll_x + px / ratio, scroll_y + py / ratio)`,
+/// where `ratio` is the screenshot width divided by `width` (the device pixel ratio,
+/// unless the screenshot was downscaled); see [`ViewportInfo::to_document`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ViewportInfo {
+    /// Viewport width, excluding scrollbars
+    pub width: f64,
+
+    /// Viewport height, excluding scrollbars
+    pub height: f64,
+
+    /// Device pixels per CSS pixel (`window.devicePixelRatio`)
+    pub device_pixel_ratio: f64,
+
+    /// Horizontal scroll offset of the viewport within the document
+    pub scroll_x: f64,
+
+    /// Vertical scroll offset of the viewport within the document
+    pub scroll_y: f64,
+
+    /// Pinch-zoom scale of the visual viewport (1 when not zoomed)
+    pub scale: f64,
+
+    /// Full document width
+    pub document_width: f64,
+
+    /// Full document height
+    pub document_height: f64,
+}
+
+impl ViewportInfo {
+    /// Document position (CSS pixels) of pixel `(x, y)` in a screenshot of `screenshot` size
+    pub fn to_document(&self, x: f64, y: f64, screenshot: &Dimensions) -> (f64, f64) {
+        let ratio_x = screenshot.width as f64 / self.width;
+        let ratio_y = screenshot.height as f64 / self.height;
+        (self.scroll_x + x / ratio_x, self.scroll_y + y / ratio_y)
+    }
+}
+
+/// The element under a screenshot pixel of a captured frame
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrameElement {
+    /// CSS selector (same form as `ChromeDriver::node_at_point` produces)
+    pub selector: String,
+
+    /// Lowercase tag name
+    pub tag: String,
+
+    /// Index of the node in the VisualDom main document's node arrays
+    pub node_index: usize,
+
+    /// Backend DOM node id, valid while the captured document is still loaded
+    pub backend_node_id: Option<i64>,
+
+    /// Layout box in document coordinates (CSS pixels)
+    pub bounds: Option<Rect>,
+
+    /// The screenshot pixel in document coordinates (CSS pixels)
+    pub document_point: (f64, f64),
+}
+
+impl StepFrame {
+    /// Element under screenshot pixel `(x, y)`, looked up in `snapshot` (this frame's
+    /// VisualDom)
+    ///
+    /// Returns None for pixels outside the screenshot or over no element, and for
+    /// frames without viewport metadata or screenshot dimensions.
+    pub fn element_in(
+        &self,
+        snapshot: &CaptureSnapshotResponse,
+        x: f64,
+        y: f64,
+    ) -> Option<FrameElement> {
+        let viewport = self.viewport.as_ref()?;
+        let screenshot = self.screenshot.dimensions.as_ref()?;
+        if x < 0.0 || y < 0.0 || x >= screenshot.width as f64 || y >= screenshot.height as f64 {
+            return None;
+        }
+
+        let document_point = viewport.to_document(x, y, screenshot);
+        let node = snapshot.node_at(document_point.0, document_point.1)?;
+        let selector = snapshot.selector(node)?;
+        let document = &snapshot.documents[0];
+        let tag = document
+            .nodes
+            .node_name
+            .as_ref()
+            .and_then(|names| names.get(node))
+            .and_then(|&index| snapshot.strings.get(index as usize))
+            .map(|name| name.to_lowercase())
+            .unwrap_or_default();
+        let bounds = document
+            .layout
+            .node_index
+            .iter()
+            .position(|&n| n as usize == node)
+            .and_then(|layout| match document.layout.bounds[layout][..] {
+                [x, y, width, height] => Some(Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                }),
+                _ => None,
+            });
+        Some(FrameElement {
+            selector,
+            tag,
+            node_index: node,
+            backend_node_id: document
+                .nodes
+                .backend_node_id
+                .as_ref()
+                .and_then(|ids| ids.get(node).copied()),
+            bounds,
+            document_point,
+        })
+    }
+}
+
+/// Image or viewport dimensions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dimensions {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// DOM state information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomInfo {
+    /// Current page URL
+    pub url: String,
+
+    /// Page title
+    pub title: String,
+
+    /// Path to saved HTML file (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html_path: Option<String>,
+
+    /// SHA-256 hash of HTML content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html_hash: Option<String>,
+
+    /// Size of the HTML in bytes before it was truncated to `HtmlOptions::max_bytes`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html_original_size: Option<usize>,
+
+    /// Path to the saved text rendition of the page (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_path: Option<String>,
+
+    /// Path to the saved MHTML archive of the page (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mhtml_path: Option<String>,
+
+    /// Interactive elements on the page (optional, can be expensive to collect)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interactive_elements: Option<Vec<InteractiveElement>>,
+}
+
+/// An interactive element on the page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractiveElement {
+    pub selector: String,
+    pub tag: String,
+    pub text: String,
+    pub is_visible: bool,
+    pub is_enabled: bool,
+}
+
+// ===== VISUALDOM STRUCTS =====
+
+/// VisualDom snapshot information
+///
+/// VisualDom is a custom format we created that provides a structured representation
+/// of the DOM with layout and visual information. It combines data from Chrome DevTools
+/// Protocol's DOMSnapshot.captureSnapshot with embedded base64 images.
+///
+/// This format allows AI agents to understand page structure, layout, and content
+/// without requiring expensive OCR on screenshots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VisualDomInfo {
+    /// Path to saved VisualDom JSON file
+    pub path: String,
+
+    /// File size in bytes
+    pub size_bytes: usize,
+
+    /// Number of DOM nodes in the snapshot
+    pub node_count: usize,
+
+    /// SHA-256 hash for deduplication
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+
+    /// How many images were embedded, downscaled, or left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub images: Option<ImageStats>,
+
+    /// Why the snapshot is incomplete (e.g. image budget exhausted), if it is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<String>,
+
+    /// File name of the frame this snapshot is a delta against, if it is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta_base: Option<String>,
+}
+
+/// Limits on the images embedded in a VisualDom snapshot
+///
+/// Keeps snapshots of image-heavy pages from growing to hundreds of megabytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageBudget {
+    /// Images with more source pixels than this are skipped (metadata only)
+    pub max_pixels: u64,
+
+    /// Images whose longest side exceeds this are downscaled to fit
+    pub max_dimension: u32,
+
+    /// Stop embedding once this much base64 image data has been collected
+    pub max_total_bytes: usize,
+
+    /// Stop embedding once this much time has been spent encoding images
+    pub time_budget: Duration,
+}
+
+impl Default for ImageBudget {
+    fn default() -> Self {
+        Self {
+            max_pixels: 16_000_000,
+            max_dimension: 1920,
+            max_total_bytes: 20 * 1024 * 1024,
+            time_budget: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Image counts for a VisualDom snapshot
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageStats {
+    /// Visible images on the page
+    pub total: usize,
+
+    /// Images embedded as base64
+    pub embedded: usize,
+
+    /// Embedded images that were downscaled
+    pub downscaled: usize,
+
+    /// Images skipped for exceeding `max_pixels`
+    pub skipped_too_large: usize,
+
+    /// Images skipped because the byte or time budget ran out
+    pub skipped_over_budget: usize,
+}
+
+impl ImageStats {
+    /// Count the `images` entries of a VisualDom snapshot
+    pub fn from_snapshot(snapshot: &serde_json::Value) -> Option<Self> {
+        let images = snapshot.get("images")?.as_array()?;
+        let mut stats = Self {
+            total: images.len(),
+            ..Self::default()
+        };
+        for image in images {
+            if image.get("data").is_some_and(|d| d.is_string()) {
+                stats.embedded += 1;
+            }
+            if image.get("downscaled").and_then(|d| d.as_bool()) == Some(true) {
+                stats.downscaled += 1;
+            }
+            match image.get("skipped").and_then(|s| s.as_str()) {
+                Some("too_large") => stats.skipped_too_large += 1,
+                Some(_) => stats.skipped_over_budget += 1,
+                None => {}
+            }
+        }
+        Some(stats)
+    }
+
+    /// Description of what was left out, or None if nothing was
+    pub fn truncation(&self, budget: &ImageBudget) -> Option<String> {
+        let mut reasons = Vec::new();
+        if self.skipped_too_large > 0 {
+            reasons.push(format!(
+                "{} image(s) over {} pixels skipped",
+                self.skipped_too_large, budget.max_pixels
+            ));
+        }
+        if self.skipped_over_budget > 0 {
+            reasons.push(format!(
+                "{} image(s) skipped after the image budget ran out",
+                self.skipped_over_budget
+            ));
+        }
+        (!reasons.is_empty()).then(|| reasons.join("; "))
+    }
+}
+
+/// CDP DOMSnapshot captureSnapshot response
+///
+/// This is the raw response from CDP's DOMSnapshot.captureSnapshot command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureSnapshotResponse {
+    /// Array of document snapshots (usually one, but can include iframes)
+    pub documents: Vec<DocumentSnapshot>,
+
+    /// String table - all strings are stored as indexes into this array for efficiency
+    pub strings: Vec<String>,
+}
+
+impl CaptureSnapshotResponse {
+    /// Topmost element of the main document whose layout box contains document
+    /// point `(x, y)`, as a node index
+    ///
+    /// "Topmost" is the highest paint order when the snapshot has paint orders, then
+    /// the smallest box. Text boxes count for their parent element.
+    pub fn node_at(&self, x: f64, y: f64) -> Option<usize> {
+        let document = self.documents.first()?;
+        let nodes = &document.nodes;
+        let parents = nodes.parent_index.as_ref()?;
+        let types = nodes.node_type.as_ref()?;
+        let paint_orders = document.layout.paint_orders.as_deref().unwrap_or_default();
+
+        // (paint order, area, node)
+        let mut best: Option<(i64, f64, usize)> = None;
+        for (layout, bounds) in document.layout.bounds.iter().enumerate() {
+            let [left, top, width, height] = bounds[..] else {
+                continue;
+            };
+            if x < left || y < top || x >= left + width || y >= top + height {
+                continue;
+            }
+            let mut node = *document.layout.node_index.get(layout)? as usize;
+            if types.get(node) != Some(&ELEMENT_NODE) {
+                node = usize::try_from(*parents.get(node)?).ok()?;
+            }
+            let paint = paint_orders.get(layout).copied().unwrap_or(0);
+            let area = width * height;
+            let on_top = best.is_none_or(|(best_paint, best_area, _)| {
+                paint > best_paint || (paint == best_paint && area <= best_area)
+            });
+            if on_top {
+                best = Some((paint, area, node));
+            }
+        }
+        best.map(|(_, _, node)| node)
+    }
+
+    /// CSS selector of element `node` of the main document: the nearest unique `#id`
+    /// ancestor, then `tag:nth-of-type(n)` steps (from `html` if no ancestor has an id)
+    pub fn selector(&self, node: usize) -> Option<String> {
+        let document = self.documents.first()?;
+        let nodes = &document.nodes;
+        let parents = nodes.parent_index.as_ref()?;
+        let types = nodes.node_type.as_ref()?;
+        let names = nodes.node_name.as_ref()?;
+        let name = |n: usize| -> Option<String> {
+            let index = usize::try_from(*names.get(n)?).ok()?;
+            Some(self.strings.get(index)?.to_lowercase())
+        };
+        let id = |n: usize| -> Option<&str> {
+            let attributes = nodes.attributes.as_ref()?.get(n)?;
+            attributes.chunks_exact(2).find_map(|pair| {
+                let key = self.strings.get(usize::try_from(pair[0]).ok()?)?;
+                let value = self.strings.get(usize::try_from(pair[1]).ok()?)?;
+                (key == "id").then_some(value.as_str())
+            })
+        };
+        let unique_id = |n: usize| -> Option<&str> {
+            let value = id(n)?;
+            let valid = value.chars().next()?.is_ascii_alphabetic()
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            let count = (0..types.len()).filter(|&m| id(m) == Some(value)).count();
+            (valid && count == 1).then_some(value)
+        };
+
+        let mut parts = Vec::new();
+        let mut rooted = false;
+        let mut current = node;
+        while types.get(current) == Some(&ELEMENT_NODE) {
+            let tag = name(current)?;
+            if tag == "html" {
+                break;
+            }
+            if let Some(id) = unique_id(current) {
+                parts.push(format!("#{}", id));
+                rooted = true;
+                break;
+            }
+            let parent = *parents.get(current)?;
+            let same: Vec<usize> = (0..types.len())
+                .filter(|&m| {
+                    parents[m] == parent
+                        && types[m] == ELEMENT_NODE
+                        && name(m).as_deref() == Some(&tag)
+                })
+                .collect();
+            parts.push(if same.len() > 1 {
+                let position = same.iter().position(|&m| m == current)? + 1;
+                format!("{}:nth-of-type({})", tag, position)
+            } else {
+                tag
+            });
+            current = usize::try_from(parent).ok()?;
+        }
+        if !rooted {
+            parts.push("html".to_string());
+        }
+        parts.reverse();
+        Some(parts.join(" > "))
+    }
+}
+
+/// DOM node type of elements
+pub(crate) const ELEMENT_NODE: i64 = 1;
+
+/// A snapshot of a single document (page or iframe)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSnapshot {
+    /// Index of document URL in string table
+    #[serde(rename = "documentURL")]
+    pub document_url: i64,
+
+    /// Index of title in string table
+    pub title: i64,
+
+    /// Index of base URL in string table
+    #[serde(rename = "baseURL")]
+    pub base_url: i64,
+
+    /// Index of content language in string table
+    #[serde(rename = "contentLanguage")]
+    pub content_language: i64,
+
+    /// Index of encoding name in string table
+    #[serde(rename = "encodingName")]
+    pub encoding_name: i64,
+
+    /// Index of public ID in string table
+    #[serde(rename = "publicId")]
+    pub public_id: i64,
+
+    /// Index of system ID in string table
+    #[serde(rename = "systemId")]
+    pub system_id: i64,
+
+    /// Index of frame ID in string table
+    #[serde(rename = "frameId")]
+    pub frame_id: i64,
+
+    /// DOM node tree
+    pub nodes: NodeTreeSnapshot,
+
+    /// Layout tree (positions, styles, text)
+    pub layout: LayoutTreeSnapshot,
+
+    /// Text boxes
+    #[serde(rename = "textBoxes")]
+    pub text_boxes: TextBoxSnapshot,
+
+    /// Scroll offset X
+    #[serde(rename = "scrollOffsetX")]
+    pub scroll_offset_x: Option<f64>,
+
+    /// Scroll offset Y
+    #[serde(rename = "scrollOffsetY")]
+    pub scroll_offset_y: Option<f64>,
+
+    /// Content width
+    #[serde(rename = "contentWidth")]
+    pub content_width: Option<f64>,
+
+    /// Content height
+    #[serde(rename = "contentHeight")]
+    pub content_height: Option<f64>,
+}
+
+/// Snapshot of the DOM node tree structure
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeTreeSnapshot {
+    /// Parent node index (parallel array)
+    #[serde(rename = "parentIndex", default)]
+    pub parent_index: Option<Vec<i64>>,
+
+    /// Node type (parallel array)
+    #[serde(rename = "nodeType", default)]
+    pub node_type: Option<Vec<i64>>,
+
+    /// Node name (string index, parallel array)
+    #[serde(rename = "nodeName", default)]
+    pub node_name: Option<Vec<i64>>,
+
+    /// Node value (string index, parallel array)
+    #[serde(rename = "nodeValue", default)]
+    pub node_value: Option<Vec<i64>>,
+
+    /// Backend node ID (parallel array)
+    #[serde(rename = "backendNodeId", default)]
+    pub backend_node_id: Option<Vec<i64>>,
+
+    /// Attributes (array of string index arrays)
+    #[serde(default)]
+    pub attributes: Option<Vec<Vec<i64>>>,
+
+    /// Text value for text nodes
+    #[serde(rename = "textValue", default)]
+    pub text_value: Option<RareStringData>,
+
+    /// Input value for input elements
+    #[serde(rename = "inputValue", default)]
+    pub input_value: Option<RareStringData>,
+
+    /// Current source URL for images/media
+    #[serde(rename = "currentSourceURL", default)]
+    pub current_source_url: Option<RareStringData>,
+
+    /// Origin URL
+    #[serde(rename = "originURL", default)]
+    pub origin_url: Option<RareStringData>,
+
+    /// Is clickable
+    #[serde(rename = "isClickable", default)]
+    pub is_clickable: Option<RareBooleanData>,
+}
+
+/// Layout tree snapshot with visual information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutTreeSnapshot {
+    /// Node index (maps to NodeTreeSnapshot, parallel array)
+    #[serde(rename = "nodeIndex")]
+    pub node_index: Vec<i64>,
+
+    /// Computed styles (array of string index arrays)
+    pub styles: Vec<Vec<i64>>,
+
+    /// Bounding rectangles (parallel array)
+    pub bounds: Vec<Rectangle>,
+
+    /// Text content (string index, parallel array)
+    pub text: Vec<i64>,
+
+    /// Stacking contexts
+    #[serde(rename = "stackingContexts", default)]
+    pub stacking_contexts: Option<RareBooleanData>,
+
+    /// Paint orders (parallel array)
+    #[serde(rename = "paintOrders", default)]
+    pub paint_orders: Option<Vec<i64>>,
+
+    /// Offset rectangles
+    #[serde(rename = "offsetRects", default)]
+    pub offset_rects: Option<Vec<Rectangle>>,
+
+    /// Scroll rectangles
+    #[serde(rename = "scrollRects", default)]
+    pub scroll_rects: Option<Vec<Rectangle>>,
+
+    /// Client rectangles
+    #[serde(rename = "clientRects", default)]
+    pub client_rects: Option<Vec<Rectangle>>,
+
+    /// Blended background colors (string index array)
+    #[serde(rename = "blendedBackgroundColors", default)]
+    pub blended_background_colors: Option<Vec<i64>>,
+
+    /// Text color opacities
+    #[serde(rename = "textColorOpacities", default)]
+    pub text_color_opacities: Option<Vec<f64>>,
+}
+
+/// Text box snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextBoxSnapshot {
+    /// Layout index (maps to LayoutTreeSnapshot)
+    #[serde(rename = "layoutIndex")]
+    pub layout_index: Vec<i64>,
+
+    /// Start position in text
+    pub start: Vec<i64>,
+
+    /// Length of text
+    pub length: Vec<i64>,
+
+    /// Bounding rectangles
+    pub bounds: Vec<Rectangle>,
+}
+
+/// Rectangle [x, y, width, height]
+pub type Rectangle = Vec<f64>;
+
+/// Sparse boolean data (only stores true values with their indexes)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RareBooleanData {
+    /// Indexes where the value is true
+    pub index: Vec<i64>,
+}
+
+/// Sparse integer data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RareIntegerData {
+    /// Indexes
+    pub index: Vec<i64>,
+
+    /// Values at those indexes
+    pub value: Vec<i64>,
+}
+
+/// Sparse string data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RareStringData {
+    /// Indexes
+    pub index: Vec<i64>,
+
+    /// String table indexes at those indexes
+    pub value: Vec<i64>,
+}
+
+/// Action being performed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionInfo {
+    /// Action type
+    pub action_type: String,
+
+    /// High-level description of intent
+    pub intent: String,
+
+    /// CSS selector or description of target
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+
+    /// Cropped screenshot of the target element, filled in by step frame capture
+    /// when `target` is a selector of a visible element
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_screenshot: Option<TargetScreenshot>,
+}
+
+/// Screenshot of an action's target element, cropped from the frame screenshot
+///
+/// Cropped after redaction, so masked regions stay masked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetScreenshot {
+    /// Path to the image file (same format as the screenshot), if saved to a file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Base64-encoded image, if embedded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+
+    /// Encoded image size in bytes
+    pub size_bytes: usize,
+
+    /// Image dimensions
+    pub dimensions: Dimensions,
+
+    /// Element bounding box relative to the viewport (CSS pixels)
+    pub rect: Rect,
+}
+
+/// Natural language transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptInfo {
+    /// Description of what is happening
+    pub action_description: String,
+
+    /// Why this action was chosen
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+
+    /// What should happen next
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_outcome: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_frame_serialization() {
+        let frame = StepFrame {
+            frame_id: 0,
+            timestamp: "2025-10-11T12:00:00Z".to_string(),
+            elapsed_ms: 0,
+            screenshot: ScreenshotInfo {
+                path: "./screenshots/frame_0000.png".to_string(),
+                format: "png".to_string(),
+                size_bytes: 12345,
+                dimensions: Some(Dimensions {
+                    width: 1920,
+                    height: 1080,
+                }),
+                hash: Some("abc123".to_string()),
+                original_dimensions: None,
+                thumbnail: None,
+                marked: None,
+            },
+            viewport: Some(ViewportInfo {
+                width: 960.0,
+                height: 540.0,
+                device_pixel_ratio: 2.0,
+                scroll_x: 0.0,
+                scroll_y: 1200.0,
+                scale: 1.0,
+                document_width: 960.0,
+                document_height: 4000.0,
+            }),
+            dom: DomInfo {
+                url: "https://example.com".to_string(),
+                title: "Example".to_string(),
+                html_path: Some("./dom/frame_0000.html".to_string()),
+                html_hash: Some("def456".to_string()),
+                html_original_size: None,
+                text_path: None,
+                mhtml_path: None,
+                interactive_elements: None,
+            },
+            visual_dom: Some(VisualDomInfo {
+                path: "./visualdom/frame_0000.visualdom.json".to_string(),
+                size_bytes: 54321,
+                node_count: 150,
+                hash: Some("ghi789".to_string()),
+                images: None,
+                truncated: None,
+                delta_base: None,
+            }),
+            action: Some(ActionInfo {
+                action_type: "navigate".to_string(),
+                intent: "Navigate to example.com".to_string(),
+                target: None,
+                target_screenshot: None,
+            }),
+            transcript: Some(TranscriptInfo {
+                action_description: "Navigating to example.com".to_string(),
+                reasoning: Some("User requested navigation".to_string()),
+                expected_outcome: Some("Page should load".to_string()),
+            }),
+            browser: None,
+        };
+
+        // Test serialization
+        let json = serde_json::to_string_pretty(&frame).unwrap();
+        assert!(json.contains("frame_id"));
+        assert!(json.contains("screenshot"));
+        assert!(json.contains("dom"));
+        assert!(json.contains("visual_dom"));
+
+        // Test deserialization
+        let deserialized: StepFrame = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.frame_id, 0);
+        assert_eq!(deserialized.screenshot.size_bytes, 12345);
+        assert!(deserialized.visual_dom.is_some());
+        assert_eq!(deserialized.visual_dom.unwrap().node_count, 150);
+
+        // Screenshot pixels map back to the document through the viewport
+        let viewport = deserialized.viewport.unwrap();
+        let screenshot = deserialized.screenshot.dimensions.unwrap();
+        assert_eq!(
+            viewport.to_document(100.0, 50.0, &screenshot),
+            (50.0, 1225.0)
+        );
+    }
+
+    #[test]
+    fn test_image_stats() {
+        let snapshot = serde_json::json!({
+            "images": [
+                { "src": "a.png", "data": "data:image/png;base64,AAAA" },
+                { "src": "b.png", "data": "data:image/png;base64,BBBB", "downscaled": true },
+                { "src": "c.png", "data": null, "skipped": "too_large" },
+                { "src": "d.png", "data": null, "skipped": "byte_budget" },
+                { "src": "e.png", "data": null, "error": "CORS or load error" },
+            ]
+        });
+        let stats = ImageStats::from_snapshot(&snapshot).unwrap();
+        assert_eq!(
+            stats,
+            ImageStats {
+                total: 5,
+                embedded: 2,
+                downscaled: 1,
+                skipped_too_large: 1,
+                skipped_over_budget: 1,
+            }
+        );
+
+        let budget = ImageBudget::default();
+        assert_eq!(
+            stats.truncation(&budget).unwrap(),
+            "1 image(s) over 16000000 pixels skipped; \
+            1 image(s) skipped after the image budget ran out"
+        );
+        assert!(ImageStats::default().truncation(&budget).is_none());
+        assert!(ImageStats::from_snapshot(&serde_json::json!({})).is_none());
+    }
+
+    #[test]
+    fn test_snapshot_hit_testing() {
+        let snapshot: CaptureSnapshotResponse = serde_json::from_value(serde_json::json!({
+            "documents": [{
+                "documentURL": 0, "title": 0, "baseURL": 0, "contentLanguage": 0,
+                "encodingName": 0, "publicId": 0, "systemId": 0, "frameId": 0,
+                "nodes": {
+                    "parentIndex": [-1, 0, 1, 2, 3, 2, 1],
+                    "nodeType": [1, 1, 1, 1, 3, 1, 1],
+                    "nodeName": [1, 2, 3, 6, 7, 6, 3],
+                    "attributes": [[], [], [4, 5], [], [], [], []]
+                },
+                "layout": {
+                    "nodeIndex": [0, 1, 2, 3, 4, 5, 6],
+                    "styles": [[], [], [], [], [], [], []],
+                    "bounds": [
+                        [0, 0, 800, 600], [0, 0, 800, 600], [0, 0, 800, 100],
+                        [0, 0, 800, 50], [0, 0, 20, 20], [0, 50, 800, 50], [0, 100, 800, 100]
+                    ],
+                    "text": [0, 0, 0, 0, 0, 0, 0]
+                },
+                "textBoxes": { "layoutIndex": [], "start": [], "length": [], "bounds": [] }
+            }],
+            "strings": ["", "HTML", "BODY", "DIV", "id", "main", "P", "#text"]
+        }))
+        .unwrap();
+
+        // Text boxes count for their element; the smallest box wins
+        assert_eq!(snapshot.node_at(10.0, 10.0), Some(3));
+        assert_eq!(
+            snapshot.selector(3).as_deref(),
+            Some("#main > p:nth-of-type(1)")
+        );
+        assert_eq!(snapshot.node_at(10.0, 150.0), Some(6));
+        assert_eq!(
+            snapshot.selector(6).as_deref(),
+            Some("html > body > div:nth-of-type(2)")
+        );
+        assert_eq!(snapshot.node_at(10.0, 550.0), Some(1));
+        assert_eq!(snapshot.selector(1).as_deref(), Some("html > body"));
+        assert_eq!(snapshot.node_at(900.0, 10.0), None);
+    }
+
+    #[test]
+    fn test_marks() {
+        let marks: Vec<Mark> = serde_json::from_value(serde_json::json!([
+            {"label": 1, "selector": "#login > button", "tag": "button", "text": "Sign in",
+             "bounds": {"x": 10.0, "y": 20.0, "width": 80.0, "height": 24.0}},
+            {"label": 2, "selector": "html > body > input", "tag": "input", "text": "",
+             "bounds": {"x": 10.0, "y": 60.0, "width": 200.0, "height": 24.0}}
+        ]))
+        .unwrap();
+        assert_eq!(marks[0].bounds.center(), (50.0, 32.0));
+        assert_eq!(marks_legend(&marks), "[1] button \"Sign in\"\n[2] input");
+        assert_eq!(marks_legend(&[]), "");
+
+        // Frames captured before set-of-marks have no marked screenshot
+        let info: ScreenshotInfo = serde_json::from_value(serde_json::json!({
+            "path": "frame_0000.png", "format": "png", "size_bytes": 10
+        }))
+        .unwrap();
+        assert!(info.marked.is_none());
+    }
+}
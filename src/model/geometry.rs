@@ -0,0 +1,60 @@
+//! Geometry
+//!
+//! Rectangles in CSS pixels, shared by element geometry, frame hit testing, and
+//! target screenshots.
+
+use serde::{Deserialize, Serialize};
+
+/// A rectangle in CSS pixels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    /// Center point `(x, y)`
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    /// Whether the rectangle has no area
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0.0 || self.height <= 0.0
+    }
+
+    /// Whether the two rectangles overlap
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rect_intersects() {
+        let viewport = Rect {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+        };
+        let inside = Rect {
+            x: 790.0,
+            y: 590.0,
+            width: 20.0,
+            height: 20.0,
+        };
+        let below = Rect { y: 600.0, ..inside };
+        assert!(inside.intersects(&viewport));
+        assert!(!below.intersects(&viewport));
+        assert!(Rect::default().is_empty());
+    }
+}
@@ -0,0 +1,387 @@
+//! Goal Verification
+//!
+//! A `Verify.goal` step checks a natural-language success criterion ("the order
+//! confirmation shows an order number") against the page a run ends on, so an
+//! agent run finishes with an explicit verdict instead of "no command failed".
+//!
+//! [`check_page`] is the cheap check: it looks for the criterion's quoted phrases
+//! and keywords in the page's text, title, and URL, and for error messages the
+//! criterion doesn't mention. Keyword overlap can't understand a criterion, so its
+//! confidence stays below what a vision model's judgement of the screenshot can
+//! reach; `auto` mode asks one (a [`GoalJudge`]) when the text check is unsure.
+//!
+//! [`GoalJudge`]: crate::cdp::goal::GoalJudge
+
+use crate::model::text_match::TextMatcher;
+use serde::{Deserialize, Serialize};
+
+/// Words that say nothing about what should be on the page
+const FILLER: &[&str] = &[
+    "a",
+    "an",
+    "and",
+    "any",
+    "are",
+    "as",
+    "at",
+    "be",
+    "been",
+    "by",
+    "can",
+    "displayed",
+    "displays",
+    "for",
+    "from",
+    "has",
+    "have",
+    "in",
+    "into",
+    "is",
+    "it",
+    "its",
+    "least",
+    "now",
+    "of",
+    "on",
+    "or",
+    "page",
+    "screen",
+    "see",
+    "shown",
+    "shows",
+    "should",
+    "that",
+    "the",
+    "their",
+    "there",
+    "this",
+    "to",
+    "user",
+    "visible",
+    "was",
+    "were",
+    "with",
+    "appears",
+    "contains",
+    "successfully",
+];
+
+/// Words that turn a criterion into one about absence, which keyword matching can't judge
+const NEGATIONS: &[&str] = &["no", "not", "without", "never", "none", "isn't", "doesn't"];
+
+/// Text on a page that suggests something went wrong
+const ERROR_CUES: &[&str] = &[
+    "error",
+    "failed",
+    "invalid",
+    "not found",
+    "access denied",
+    "try again",
+    "something went wrong",
+];
+
+/// How a `Verify.goal` step checks its goal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "runtime", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyMode {
+    /// Check the page's text only
+    Dom,
+    /// Ask a vision model about a screenshot of the page
+    Vision,
+    /// Check the text, and ask a vision model if that isn't conclusive
+    #[default]
+    Auto,
+}
+
+/// What decided a verdict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "runtime", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationMethod {
+    Dom,
+    Vision,
+}
+
+/// Whether a run achieved its goal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "runtime", derive(utoipa::ToSchema))]
+pub struct GoalVerdict {
+    /// The success criterion, as given
+    pub goal: String,
+
+    pub passed: bool,
+
+    /// How sure the check is of `passed`, from 0 to 1
+    pub confidence: f64,
+
+    pub method: VerificationMethod,
+
+    /// What the verdict is based on
+    pub reason: String,
+
+    /// Screenshot the vision model judged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<String>,
+}
+
+impl GoalVerdict {
+    /// Verdict from a vision model's JSON answer (`{"passed", "confidence", "reason"}`)
+    pub fn from_judgement(goal: &str, answer: &str) -> anyhow::Result<Self> {
+        #[derive(Deserialize)]
+        struct Judgement {
+            passed: bool,
+            confidence: f64,
+            #[serde(default)]
+            reason: String,
+        }
+
+        let judgement: Judgement = serde_json::from_str(answer.trim())
+            .map_err(|e| anyhow::anyhow!("Unreadable goal judgement: {}", e))?;
+        Ok(Self {
+            goal: goal.to_string(),
+            passed: judgement.passed,
+            confidence: round(judgement.confidence.clamp(0.0, 1.0)),
+            method: VerificationMethod::Vision,
+            reason: judgement.reason,
+            screenshot: None,
+        })
+    }
+}
+
+/// The page a goal is checked against
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageEvidence {
+    pub url: String,
+    pub title: String,
+
+    /// Visible text (`document.body.innerText`)
+    pub text: String,
+}
+
+/// Check `goal` against the page's text, title, and URL
+///
+/// Every quoted phrase in the goal ("shows \"Order confirmed\"") must be on the page,
+/// and at least half its keywords. Confidence tops out at 0.9 with quoted phrases
+/// and 0.8 without; criteria about absence ("no error") cap it at 0.5, and error
+/// messages the goal doesn't mention lower it.
+pub fn check_page(goal: &str, page: &PageEvidence) -> GoalVerdict {
+    let haystack = normalize(&format!("{}\n{}\n{}", page.title, page.url, page.text));
+    let goal_lower = goal.to_lowercase();
+
+    let phrases = quoted_phrases(goal);
+    let (found_phrases, missing_phrases): (Vec<&String>, Vec<&String>) = phrases
+        .iter()
+        .partition(|phrase| haystack.contains(&normalize(phrase)));
+
+    let unquoted = phrases.iter().fold(goal.to_string(), |rest, phrase| {
+        rest.replace(phrase.as_str(), " ")
+    });
+    let keywords = keywords(&unquoted);
+    let (found, missing): (Vec<&String>, Vec<&String>) = keywords
+        .iter()
+        .partition(|keyword| haystack.contains(&normalize(keyword)));
+    let coverage = match keywords.len() {
+        0 => 1.0,
+        total => found.len() as f64 / total as f64,
+    };
+
+    let mut notes = Vec::new();
+    if !found_phrases.is_empty() {
+        notes.push(format!("found {}", quote_all(&found_phrases)));
+    }
+    if !missing_phrases.is_empty() {
+        notes.push(format!("missing {}", quote_all(&missing_phrases)));
+    }
+    if !keywords.is_empty() {
+        notes.push(format!(
+            "matched {} of {} keywords",
+            found.len(),
+            keywords.len()
+        ));
+        if !missing.is_empty() {
+            let missing: Vec<&str> = missing.iter().map(|k| k.as_str()).collect();
+            notes.push(format!("missing {}", missing.join(", ")));
+        }
+    }
+
+    let (passed, mut confidence) = if phrases.is_empty() && keywords.is_empty() {
+        notes.push("nothing in the goal to look for".to_string());
+        (false, 0.0)
+    } else if !missing_phrases.is_empty() {
+        let missing = missing_phrases.len() as f64 / phrases.len() as f64;
+        (false, 0.6 + 0.3 * missing)
+    } else if !phrases.is_empty() {
+        (coverage >= 0.5, 0.6 + 0.3 * coverage.max(1.0 - coverage))
+    } else if coverage >= 0.5 {
+        (true, 0.3 + 0.5 * coverage)
+    } else {
+        (false, 0.3 + 0.5 * (1.0 - coverage))
+    };
+
+    let errors: Vec<&str> = ERROR_CUES
+        .iter()
+        .copied()
+        .filter(|cue| haystack.contains(cue) && !goal_lower.contains(cue))
+        .collect();
+    if passed && !errors.is_empty() {
+        notes.push(format!("but the page mentions {}", errors.join(", ")));
+        confidence *= 0.7;
+    }
+    if goal_lower
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .any(|word| NEGATIONS.contains(&word))
+    {
+        notes.push("the goal is about something being absent".to_string());
+        confidence = confidence.min(0.5);
+    }
+
+    let mut reason = notes.join("; ");
+    if let Some(first) = reason.get(..1) {
+        reason = first.to_uppercase() + &reason[1..];
+    }
+    GoalVerdict {
+        goal: goal.to_string(),
+        passed,
+        confidence: round(confidence),
+        method: VerificationMethod::Dom,
+        reason,
+        screenshot: None,
+    }
+}
+
+/// Phrases in double, single, or curly quotes
+fn quoted_phrases(goal: &str) -> Vec<String> {
+    let mut phrases = Vec::new();
+    for (open, close) in [('"', '"'), ('“', '”'), ('\'', '\'')] {
+        let mut rest = goal;
+        while let Some(start) = rest.find(open) {
+            // An apostrophe inside a word isn't a quote
+            if open == '\'' && rest[..start].ends_with(|c: char| c.is_alphanumeric()) {
+                rest = &rest[start + 1..];
+                continue;
+            }
+            let after = &rest[start + open.len_utf8()..];
+            let Some(end) = after.find(close) else {
+                break;
+            };
+            let phrase = after[..end].trim();
+            if !phrase.is_empty() {
+                phrases.push(phrase.to_string());
+            }
+            rest = &after[end + close.len_utf8()..];
+        }
+    }
+    phrases
+}
+
+/// Distinct lowercase words of `text` worth looking for
+fn keywords(text: &str) -> Vec<String> {
+    let mut keywords: Vec<String> = Vec::new();
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+    {
+        let filler = FILLER.contains(&word.as_str()) || NEGATIONS.contains(&word.as_str());
+        if word.chars().count() > 1 && !filler && !keywords.contains(&word) {
+            keywords.push(word);
+        }
+    }
+    keywords
+}
+
+/// Lowercase without diacritics, with nbsp and typographic quotes and dashes made
+/// plain, and runs of whitespace collapsed
+fn normalize(text: &str) -> String {
+    TextMatcher::default().normalize(text)
+}
+
+fn quote_all(phrases: &[&String]) -> String {
+    phrases
+        .iter()
+        .map(|phrase| format!("\"{}\"", phrase))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn round(confidence: f64) -> f64 {
+    (confidence * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(text: &str) -> PageEvidence {
+        PageEvidence {
+            url: "https://shop.example.com/checkout/complete".to_string(),
+            title: "Thank you".to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_quoted_phrases() {
+        let confirmed = page("Order confirmed\nYour order number is 1042.");
+        let verdict = check_page(
+            "The page shows \"Order  confirmed\" and an order number",
+            &confirmed,
+        );
+        assert!(verdict.passed);
+        assert_eq!(verdict.method, VerificationMethod::Dom);
+        assert_eq!(verdict.confidence, 0.9);
+        assert_eq!(
+            verdict.reason,
+            "Found \"Order  confirmed\"; matched 2 of 2 keywords"
+        );
+
+        let verdict = check_page("Shows “Payment declined”", &confirmed);
+        assert!(!verdict.passed);
+        assert_eq!(verdict.confidence, 0.9);
+        assert!(verdict.reason.starts_with("Missing \"Payment declined\""));
+        assert_eq!(quoted_phrases("the user's cart shows 'Empty'"), ["Empty"]);
+
+        // Rendered text with nbsp and accents still matches
+        let localized = page("Commande\u{a0}confirmée");
+        assert!(check_page("Shows \"Commande confirmee\"", &localized).passed);
+    }
+
+    #[test]
+    fn test_keywords() {
+        let verdict = check_page("Checkout is complete", &page("Thanks for shopping with us"));
+        assert!(verdict.passed);
+        assert_eq!(verdict.confidence, 0.8);
+
+        let verdict = check_page(
+            "Invoice list is visible",
+            &page("Error: session expired, try again"),
+        );
+        assert!(!verdict.passed);
+        assert_eq!(
+            verdict.reason,
+            "Matched 0 of 2 keywords; missing invoice, list"
+        );
+
+        let verdict = check_page("Checkout complete", &page("Payment failed"));
+        assert!(verdict.passed);
+        assert_eq!(verdict.confidence, 0.56);
+        assert!(verdict.reason.ends_with("but the page mentions failed"));
+
+        let verdict = check_page("No error is shown on checkout", &page("All good"));
+        assert_eq!(verdict.confidence, 0.5);
+        assert!(!check_page("the page", &page("anything")).passed);
+    }
+
+    #[test]
+    fn test_judgement() {
+        let verdict = GoalVerdict::from_judgement(
+            "Logged in",
+            r#"{"passed": true, "confidence": 1.3, "reason": "Avatar in the header"}"#,
+        )
+        .unwrap();
+        assert!(verdict.passed);
+        assert_eq!(verdict.confidence, 1.0);
+        assert_eq!(verdict.method, VerificationMethod::Vision);
+        assert!(GoalVerdict::from_judgement("Logged in", "yes").is_err());
+    }
+}
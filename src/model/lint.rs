@@ -0,0 +1,462 @@
+//! CDP Script Linting
+//!
+//! Flags scripts that are valid but likely to misbehave: reading or capturing a page
+//! before it has loaded, oversized inline JavaScript, repeated commands, and output
+//! paths that escape the output directory. Where there is an obvious repair, the
+//! warning carries it as a JSON Patch (RFC 6902) against the script.
+
+use crate::model::script::{CdpCommand, CdpScript};
+use crate::model::validation::ErrorLocation;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Default limit for inline `Runtime.evaluate` expressions, in bytes
+pub const DEFAULT_MAX_EVALUATE_BYTES: usize = 8 * 1024;
+
+/// Expression of a `Runtime.evaluate` command that waits for the page load
+pub(crate) const WAIT_FOR_LOAD: &str = "new Promise(resolve => document.readyState === 'complete' \
+    ? resolve() : addEventListener('load', () => resolve(), { once: true }))";
+
+/// Lint rules
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LintRule {
+    /// A navigation is followed by another command without waiting for the load
+    NavigationWithoutWait,
+
+    /// A screenshot is taken while a navigation may still be loading
+    ScreenshotBeforeLoad,
+
+    /// A `Runtime.evaluate` expression is longer than the linter's limit
+    LargeEvaluate,
+
+    /// A command has no description
+    MissingDescription,
+
+    /// A command repeats the previous command exactly
+    DuplicateCommand,
+
+    /// A `save_as` path is absolute, so it ignores the output directory
+    AbsoluteSaveAs,
+
+    /// A `save_as` path climbs out of the output directory with `..`
+    ParentSaveAs,
+}
+
+impl LintRule {
+    /// Whether the rule flags a `save_as` that would write outside the output
+    /// directory (which executors with an output directory refuse to run)
+    pub fn escapes_output_dir(&self) -> bool {
+        matches!(self, LintRule::AbsoluteSaveAs | LintRule::ParentSaveAs)
+    }
+}
+
+/// A lint finding
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LintWarning {
+    /// Rule that fired
+    pub rule: LintRule,
+
+    /// Human-readable message
+    pub message: String,
+
+    /// Location of the offending command or field
+    pub location: ErrorLocation,
+
+    /// Suggested repair, if there is a mechanical one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<LintFix>,
+}
+
+/// Suggested repair for a lint warning
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LintFix {
+    /// What the fix does
+    pub description: String,
+
+    /// JSON Patch operations against the original script
+    ///
+    /// Each fix is relative to the script as linted; apply fixes one at a time and
+    /// lint again rather than combining patches of several warnings.
+    pub patch: Vec<PatchOperation>,
+}
+
+/// A JSON Patch (RFC 6902) operation, with a JSON Pointer `path`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+}
+
+/// CDP script linter
+#[derive(Debug, Clone)]
+pub struct CdpLinter {
+    max_evaluate_bytes: usize,
+}
+
+impl CdpLinter {
+    /// Create a linter with the default limits
+    pub fn new() -> Self {
+        Self {
+            max_evaluate_bytes: DEFAULT_MAX_EVALUATE_BYTES,
+        }
+    }
+
+    /// Flag `Runtime.evaluate` expressions longer than `bytes`
+    pub fn with_max_evaluate_bytes(mut self, bytes: usize) -> Self {
+        self.max_evaluate_bytes = bytes;
+        self
+    }
+
+    /// Lint a parsed script, returning warnings in command order
+    pub fn lint(&self, script: &CdpScript) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        // Index of a navigation not yet followed by a wait
+        let mut pending_navigation: Option<usize> = None;
+
+        for (index, cmd) in script.cdp_commands.iter().enumerate() {
+            if let Some(navigation) = pending_navigation.take() {
+                if !is_wait(cmd) {
+                    warnings.push(self.unwaited_navigation(script, navigation, index));
+                }
+            }
+            if is_navigation(cmd) {
+                pending_navigation = Some(index);
+            }
+
+            if index > 0 && same_command(&script.cdp_commands[index - 1], cmd) {
+                warnings.push(LintWarning {
+                    rule: LintRule::DuplicateCommand,
+                    message: format!(
+                        "Command {} ({}) repeats the previous command",
+                        index + 1,
+                        cmd.method
+                    ),
+                    location: location(index, format!("cdp_commands[{}]", index)),
+                    fix: Some(LintFix {
+                        description: "Remove the repeated command".to_string(),
+                        patch: vec![PatchOperation::Remove {
+                            path: format!("/cdp_commands/{}", index),
+                        }],
+                    }),
+                });
+            }
+
+            if cmd.method == "Runtime.evaluate" {
+                let length = cmd.params["expression"].as_str().map_or(0, str::len);
+                if length > self.max_evaluate_bytes {
+                    warnings.push(LintWarning {
+                        rule: LintRule::LargeEvaluate,
+                        message: format!(
+                            "Command {} evaluates {} bytes of JavaScript (limit {}); \
+                            large inline scripts are hard to review and to debug",
+                            index + 1,
+                            length,
+                            self.max_evaluate_bytes
+                        ),
+                        location: location(
+                            index,
+                            format!("cdp_commands[{}].params.expression", index),
+                        ),
+                        fix: None,
+                    });
+                }
+            }
+
+            if cmd
+                .description
+                .as_deref()
+                .is_none_or(|d| d.trim().is_empty())
+            {
+                let description = describe(cmd);
+                warnings.push(LintWarning {
+                    rule: LintRule::MissingDescription,
+                    message: format!("Command {} ({}) has no description", index + 1, cmd.method),
+                    location: location(index, format!("cdp_commands[{}].description", index)),
+                    fix: Some(LintFix {
+                        description: format!("Describe it as \"{}\"", description),
+                        patch: vec![PatchOperation::Add {
+                            path: format!("/cdp_commands/{}/description", index),
+                            value: Value::String(description),
+                        }],
+                    }),
+                });
+            }
+
+            let escape = cmd.save_as.as_deref().and_then(|path| {
+                if is_absolute(path) {
+                    Some((LintRule::AbsoluteSaveAs, "absolute path"))
+                } else if path.split(['/', '\\']).any(|part| part == "..") {
+                    Some((LintRule::ParentSaveAs, "path"))
+                } else {
+                    None
+                }
+            });
+            if let (Some(save_as), Some((rule, kind))) = (cmd.save_as.as_deref(), escape) {
+                let file_name = save_as.rsplit(['/', '\\']).next().unwrap_or_default();
+                let file_name = if file_name == ".." { "" } else { file_name };
+                warnings.push(LintWarning {
+                    rule,
+                    message: format!(
+                        "Command {} saves to {} '{}', outside the output directory",
+                        index + 1,
+                        kind,
+                        save_as
+                    ),
+                    location: location(index, format!("cdp_commands[{}].save_as", index)),
+                    fix: (!file_name.is_empty()).then(|| LintFix {
+                        description: format!("Save to '{}' in the output directory", file_name),
+                        patch: vec![PatchOperation::Replace {
+                            path: format!("/cdp_commands/{}/save_as", index),
+                            value: Value::String(file_name.to_string()),
+                        }],
+                    }),
+                });
+            }
+        }
+
+        warnings
+    }
+
+    /// Warning for navigation `navigation` followed by command `next` without a wait
+    fn unwaited_navigation(
+        &self,
+        script: &CdpScript,
+        navigation: usize,
+        next: usize,
+    ) -> LintWarning {
+        let fix = Some(LintFix {
+            description: "Wait for the page to load before the next command".to_string(),
+            patch: vec![PatchOperation::Add {
+                path: format!("/cdp_commands/{}", next),
+                value: json!({
+                    "method": "Runtime.evaluate",
+                    "params": { "expression": WAIT_FOR_LOAD, "awaitPromise": true },
+                    "description": "Wait for the page to load",
+                }),
+            }],
+        });
+        let navigation_method = &script.cdp_commands[navigation].method;
+        if script.cdp_commands[next].method == "Page.captureScreenshot" {
+            LintWarning {
+                rule: LintRule::ScreenshotBeforeLoad,
+                message: format!(
+                    "Command {} takes a screenshot right after {} (command {}), before the \
+                    page has loaded",
+                    next + 1,
+                    navigation_method,
+                    navigation + 1
+                ),
+                location: location(next, format!("cdp_commands[{}]", next)),
+                fix,
+            }
+        } else {
+            LintWarning {
+                rule: LintRule::NavigationWithoutWait,
+                message: format!(
+                    "Command {} ({}) is not followed by a wait, so command {} may run \
+                    against the previous page",
+                    navigation + 1,
+                    navigation_method,
+                    next + 1
+                ),
+                location: location(navigation, format!("cdp_commands[{}]", navigation)),
+                fix,
+            }
+        }
+    }
+}
+
+impl Default for CdpLinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn location(command_index: usize, field_path: String) -> ErrorLocation {
+    ErrorLocation {
+        command_index: Some(command_index),
+        field_path,
+        line: None,
+        column: None,
+    }
+}
+
+/// Whether the command starts loading a new document
+fn is_navigation(cmd: &CdpCommand) -> bool {
+    matches!(
+        cmd.method.as_str(),
+        "Page.navigate" | "Page.reload" | "Page.goBack" | "Page.goForward"
+    )
+}
+
+/// Whether the command waits for something (a promise, an element, or a popup)
+fn is_wait(cmd: &CdpCommand) -> bool {
+    match cmd.method.as_str() {
+        "Runtime.evaluate" => cmd.params["awaitPromise"] == Value::Bool(true),
+        "Element.waitFor" | "Target.waitForPopup" => true,
+        _ => false,
+    }
+}
+
+fn same_command(a: &CdpCommand, b: &CdpCommand) -> bool {
+    a.method == b.method && a.params == b.params && a.save_as == b.save_as
+}
+
+/// Unix, Windows drive, and UNC absolute paths
+fn is_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with(['/', '\\'])
+        || (bytes.len() > 2
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && matches!(bytes[2], b'/' | b'\\'))
+}
+
+/// Default description for a command that has none
+pub(crate) fn describe(cmd: &CdpCommand) -> String {
+    let param = |name: &str| cmd.params[name].as_str().unwrap_or_default().to_string();
+    match cmd.method.as_str() {
+        "Page.navigate" => format!("Navigate to {}", param("url")),
+        "Page.captureScreenshot" => match &cmd.save_as {
+            Some(path) => format!("Capture a screenshot to {}", path),
+            None => "Capture a screenshot".to_string(),
+        },
+        "Page.reload" => "Reload the page".to_string(),
+        "Page.goBack" => "Go back".to_string(),
+        "Page.goForward" => "Go forward".to_string(),
+        "Input.insertText" => format!("Type \"{}\"", param("text")),
+        "Element.click" => format!("Click {}", param("selector")),
+        "Element.type" => format!("Type \"{}\" into {}", param("text"), param("selector")),
+        "Element.waitFor" => format!("Wait for {}", param("selector")),
+        "Element.scrollTo" => format!("Scroll to {}", param("selector")),
+        _ => format!("Run {}", cmd.method),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(commands: Value) -> CdpScript {
+        serde_json::from_value(json!({
+            "name": "lint-test",
+            "description": "Lint test",
+            "cdp_commands": commands,
+        }))
+        .unwrap()
+    }
+
+    fn rules(warnings: &[LintWarning]) -> Vec<LintRule> {
+        warnings.iter().map(|w| w.rule).collect()
+    }
+
+    #[test]
+    fn test_clean_script() {
+        let script = script(json!([
+            { "method": "Page.navigate", "params": { "url": "https://example.com" },
+              "description": "Open the page" },
+            { "method": "Runtime.evaluate",
+              "params": { "expression": "new Promise(r => setTimeout(r, 500))", "awaitPromise": true },
+              "description": "Wait for the page" },
+            { "method": "Page.captureScreenshot", "params": {}, "save_as": "shots/home.png",
+              "description": "Screenshot" },
+        ]));
+        assert!(CdpLinter::new().lint(&script).is_empty());
+    }
+
+    #[test]
+    fn test_load_rules() {
+        let script = script(json!([
+            { "method": "Page.navigate", "params": { "url": "https://example.com" },
+              "description": "Open the page" },
+            { "method": "Page.captureScreenshot", "params": {}, "description": "Screenshot" },
+            { "method": "Page.reload", "params": {}, "description": "Reload" },
+            { "method": "Runtime.evaluate", "params": { "expression": "document.title" },
+              "description": "Read the title" },
+        ]));
+        let warnings = CdpLinter::new().lint(&script);
+        assert_eq!(
+            rules(&warnings),
+            [
+                LintRule::ScreenshotBeforeLoad,
+                LintRule::NavigationWithoutWait
+            ]
+        );
+        assert_eq!(warnings[0].location.command_index, Some(1));
+        assert_eq!(warnings[1].location.command_index, Some(2));
+
+        // The fix inserts a wait in front of the command that ran too early
+        let fix = warnings[1].fix.as_ref().unwrap();
+        let PatchOperation::Add { path, value } = &fix.patch[0] else {
+            panic!("expected an add operation");
+        };
+        assert_eq!(path, "/cdp_commands/3");
+        assert_eq!(value["params"]["awaitPromise"], true);
+    }
+
+    #[test]
+    fn test_command_rules() {
+        let big = "x".repeat(200);
+        let script = script(json!([
+            { "method": "Page.navigate", "params": { "url": "https://example.com" } },
+            { "method": "Target.waitForPopup", "params": {}, "description": "Wait" },
+            { "method": "Runtime.evaluate", "params": { "expression": big }, "description": "Big" },
+            { "method": "Runtime.evaluate", "params": { "expression": big }, "description": "Big" },
+            { "method": "Page.captureScreenshot", "params": {}, "save_as": "C:\\shots\\a.png",
+              "description": "Screenshot" },
+            { "method": "Page.captureScreenshot", "params": {}, "save_as": "../../b.png",
+              "description": "Screenshot" },
+        ]));
+        let warnings = CdpLinter::new().with_max_evaluate_bytes(100).lint(&script);
+        assert_eq!(
+            rules(&warnings),
+            [
+                LintRule::MissingDescription,
+                LintRule::LargeEvaluate,
+                LintRule::DuplicateCommand,
+                LintRule::LargeEvaluate,
+                LintRule::AbsoluteSaveAs,
+                LintRule::ParentSaveAs,
+            ]
+        );
+        assert!(warnings[5].message.contains("saves to path '../../b.png'"));
+
+        let patch = |i: usize| warnings[i].fix.as_ref().unwrap().patch[0].clone();
+        assert_eq!(
+            patch(0),
+            PatchOperation::Add {
+                path: "/cdp_commands/0/description".to_string(),
+                value: json!("Navigate to https://example.com"),
+            }
+        );
+        assert_eq!(
+            patch(2),
+            PatchOperation::Remove {
+                path: "/cdp_commands/3".to_string()
+            }
+        );
+        assert_eq!(
+            patch(4),
+            PatchOperation::Replace {
+                path: "/cdp_commands/4/save_as".to_string(),
+                value: json!("a.png"),
+            }
+        );
+        assert_eq!(
+            patch(5),
+            PatchOperation::Replace {
+                path: "/cdp_commands/5/save_as".to_string(),
+                value: json!("b.png"),
+            }
+        );
+
+        // Patches serialize as JSON Patch operations
+        assert_eq!(
+            serde_json::to_value(patch(2)).unwrap(),
+            json!({ "op": "remove", "path": "/cdp_commands/3" })
+        );
+    }
+}
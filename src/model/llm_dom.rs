@@ -0,0 +1,276 @@
+//! DOM Serialization for Language Models
+//!
+//! Page HTML doesn't fit a model's context window: a typical page is hundreds of
+//! kilobytes of wrappers, class names, and scripts around a few hundred words and a
+//! few dozen controls. The driver extracts the rendered page as a [`DomNode`] tree
+//! (visible elements only, with their salient attributes), and [`serialize`] turns
+//! it into an indented outline that fits a token budget:
+//!
+//! ```text
+//! form name="login"
+//!  input type="email" name="email" placeholder="Email"
+//!  button "Sign in"
+//!  a href="/forgot" "Forgot password?"
+//! ```
+//!
+//! Wrappers that carry nothing (a `div` around a single child, a `span` around
+//! text) are collapsed into their content. If the outline is over budget, long
+//! texts are shortened at word boundaries, more aggressively each round, and as a
+//! last resort the outline is cut after the last line that fits.
+
+use serde::{Deserialize, Serialize};
+
+/// Tags that only group or style their content
+const GENERIC: &[&str] = &[
+    "div", "span", "slot", "font", "center", "b", "strong", "i", "em", "u", "small", "mark",
+];
+
+/// Tags worth keeping even without content or attributes
+const CONTROLS: &[&str] = &[
+    "input", "select", "textarea", "button", "img", "iframe", "video", "audio", "canvas", "hr",
+];
+
+/// Longest text kept in each round of shortening, until the outline fits
+const TEXT_CAPS: &[usize] = &[500, 200, 80, 30];
+
+/// Longest attribute value kept (URLs can be data URLs)
+const MAX_ATTRIBUTE_CHARS: usize = 100;
+
+/// A node of the rendered page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DomNode {
+    Element(DomElement),
+    Text(String),
+}
+
+/// An element with its salient attributes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DomElement {
+    /// Lowercase tag name
+    pub tag: String,
+
+    /// Attributes in page order; an empty value is a flag (`checked`, `disabled`)
+    #[serde(default)]
+    pub attrs: Vec<(String, String)>,
+
+    #[serde(default)]
+    pub children: Vec<DomNode>,
+}
+
+/// Rough token count of `text` (about four characters per token for English and markup)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Outline of the page below `root` (its children at the top level) in at most
+/// `max_tokens` tokens, as estimated by [`estimate_tokens`]
+pub fn serialize(root: &DomNode, max_tokens: usize) -> String {
+    let children = match simplify(root.clone()) {
+        Some(DomNode::Element(root)) => root.children,
+        Some(text) => vec![text],
+        None => return String::new(),
+    };
+
+    let mut lines = Vec::new();
+    for &cap in TEXT_CAPS {
+        lines.clear();
+        for child in &children {
+            render(child, 0, cap, &mut lines);
+        }
+        let outline = lines.join("\n");
+        if estimate_tokens(&outline) <= max_tokens {
+            return outline;
+        }
+    }
+
+    // Still over budget with the shortest texts: keep the lines that fit, and a note
+    // of how many didn't
+    let marker = |kept: usize| format!("... {} more lines", lines.len() - kept);
+    let mut chars = 0;
+    let mut kept = 0;
+    for line in &lines[..lines.len() - 1] {
+        // Each kept line is followed by a newline, then the marker
+        let next = chars + line.chars().count() + 1;
+        if (next + marker(kept + 1).len()).div_ceil(4) > max_tokens {
+            break;
+        }
+        chars = next;
+        kept += 1;
+    }
+    if (chars + marker(kept).len()).div_ceil(4) > max_tokens {
+        return String::new();
+    }
+    let mut outline: String = lines[..kept]
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect();
+    outline.push_str(&marker(kept));
+    outline
+}
+
+/// `node` with whitespace collapsed, empty nodes dropped, and bare wrappers replaced
+/// by their content (None if nothing is left)
+fn simplify(node: DomNode) -> Option<DomNode> {
+    let element = match node {
+        DomNode::Text(text) => {
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            return (!text.is_empty()).then_some(DomNode::Text(text));
+        }
+        DomNode::Element(element) => element,
+    };
+
+    // Adjacent texts (including those of collapsed inline wrappers) become one
+    let mut children: Vec<DomNode> = Vec::with_capacity(element.children.len());
+    for child in element.children.into_iter().filter_map(simplify) {
+        match (children.last_mut(), child) {
+            (Some(DomNode::Text(last)), DomNode::Text(text)) => {
+                last.push(' ');
+                last.push_str(&text);
+            }
+            (_, child) => children.push(child),
+        }
+    }
+
+    let generic = GENERIC.contains(&element.tag.as_str()) || element.tag.contains('-');
+    if element.attrs.is_empty() {
+        if children.is_empty() && !CONTROLS.contains(&element.tag.as_str()) {
+            return None;
+        }
+        if generic && children.len() == 1 {
+            return children.pop();
+        }
+    }
+    Some(DomNode::Element(DomElement {
+        children,
+        ..element
+    }))
+}
+
+/// Append the lines of `node` at `depth`, with texts shortened to `cap` characters
+fn render(node: &DomNode, depth: usize, cap: usize, lines: &mut Vec<String>) {
+    let indent = " ".repeat(depth);
+    let element = match node {
+        DomNode::Text(text) => {
+            lines.push(format!("{}{}", indent, quote(&shorten(text, cap))));
+            return;
+        }
+        DomNode::Element(element) => element,
+    };
+
+    let mut line = format!("{}{}", indent, element.tag);
+    for (name, value) in &element.attrs {
+        line.push(' ');
+        line.push_str(name);
+        if !value.is_empty() {
+            line.push('=');
+            line.push_str(&quote(&shorten(value, cap.min(MAX_ATTRIBUTE_CHARS))));
+        }
+    }
+    // A lone text goes on the element's line
+    if let [DomNode::Text(text)] = element.children.as_slice() {
+        line.push(' ');
+        line.push_str(&quote(&shorten(text, cap)));
+        lines.push(line);
+        return;
+    }
+    lines.push(line);
+    for child in &element.children {
+        render(child, depth + 1, cap, lines);
+    }
+}
+
+/// `text` cut to at most `max_chars` characters (plus an ellipsis), at a word
+/// boundary unless that loses more than half of it
+fn shorten(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(space) if space >= cut.len() / 2 => &cut[..space],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end())
+}
+
+fn quote(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page() -> DomNode {
+        serde_json::from_value(serde_json::json!({
+            "tag": "body",
+            "children": [
+                {"tag": "div", "children": [{"tag": "div", "children": [
+                    {"tag": "h1", "children": ["  Welcome\n back "]}
+                ]}]},
+                {"tag": "form", "attrs": [["name", "login"]], "children": [
+                    {"tag": "input", "attrs": [["type", "email"], ["placeholder", "Email"]]},
+                    {"tag": "input", "attrs": [["type", "checkbox"], ["checked", ""]]},
+                    {"tag": "button", "children": [{"tag": "span", "children": ["Sign"]}, " in"]},
+                    {"tag": "p", "children": []}
+                ]},
+                {"tag": "p", "children": [
+                    "Read our ", {"tag": "a", "attrs": [["href", "/terms"]], "children": ["terms"]},
+                    " before signing in."
+                ]}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_serialize() {
+        assert_eq!(
+            serialize(&page(), 1000),
+            [
+                "h1 \"Welcome back\"",
+                "form name=\"login\"",
+                " input type=\"email\" placeholder=\"Email\"",
+                " input type=\"checkbox\" checked",
+                " button \"Sign in\"",
+                "p",
+                " \"Read our\"",
+                " a href=\"/terms\" \"terms\"",
+                " \"before signing in.\"",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_budget() {
+        let long = "word ".repeat(200);
+        let root = DomNode::Element(DomElement {
+            tag: "body".to_string(),
+            attrs: Vec::new(),
+            children: vec![DomNode::Text(long.clone()), page()],
+        });
+
+        // Long texts are shortened first
+        let outline = serialize(&root, 60);
+        assert!(estimate_tokens(&outline) <= 60);
+        assert!(outline.starts_with("\"word word"));
+        assert!(outline.lines().next().unwrap().ends_with("word…\""));
+        assert!(outline.ends_with("\"before signing in.\""));
+
+        // Then the outline is cut
+        let outline = serialize(&root, 20);
+        assert!(estimate_tokens(&outline) <= 20);
+        assert!(outline.ends_with("more lines"));
+        assert_eq!(serialize(&root, 0), "");
+    }
+
+    #[test]
+    fn test_shorten() {
+        assert_eq!(shorten("short", 10), "short");
+        assert_eq!(shorten("one two three four", 12), "one two…");
+        assert_eq!(shorten("abcdefghijklmnop", 5), "abcde…");
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+}
@@ -0,0 +1,398 @@
+//! Macro Commands
+//!
+//! Clicking an element takes three `Input.dispatchMouseEvent` commands at its
+//! coordinates; typing into a field, a click, a `Runtime.evaluate` to select what it
+//! holds, and `Input.insertText`. Scripts that spell these out carry coordinates that
+//! break when the layout moves and JavaScript that generated scripts get subtly
+//! wrong. Macro commands name the element by selector instead, and the executor
+//! expands them into those CDP commands when it runs them:
+//!
+//! - `Element.click` (`selector`): mouse move, press, and release at the element's center
+//! - `Element.type` (`selector`, `text`): the click, then select the field's value
+//!   and insert `text` over it (an empty `text` deletes the value)
+//! - `Element.waitFor` (`selector`, `timeout` in ms, default 10000, `visible`,
+//!   default true): one `Runtime.evaluate` awaiting a promise that polls for the element
+//! - `Element.scrollTo` (`selector`): scroll the element to the middle of the viewport
+//!
+//! Click, type, and scroll steps accept the same fallbacks as `Touch.tap`
+//! (`fallbackSelectors`, `fingerprint`, `fallbackText`). Before expanding a click or
+//! type, the executor scrolls the element into view and measures it, since the
+//! coordinates depend on where it is at that moment.
+
+use crate::model::script::CdpCommand;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Methods of the macro commands
+pub const MACRO_METHODS: &[&str] = &[
+    "Element.click",
+    "Element.type",
+    "Element.waitFor",
+    "Element.scrollTo",
+];
+
+/// Default time `Element.waitFor` waits, in milliseconds
+pub const DEFAULT_WAIT_TIMEOUT_MS: u64 = 10_000;
+
+/// Interval between checks while waiting for an element, in milliseconds
+const POLL_INTERVAL_MS: u64 = 100;
+
+/// Focuses the element matching `selector` and selects its contents
+const SELECT_FUNCTION: &str = r#"(selector) => {
+    const el = document.querySelector(selector);
+    if (!el) throw new Error(`No element matches ${selector}`);
+    el.focus();
+    if (typeof el.select === 'function') {
+        el.select();
+    } else if (el.isContentEditable) {
+        window.getSelection().selectAllChildren(el);
+    }
+    return true;
+}"#;
+
+/// Resolves once an element matches `selector` (and is rendered, if `visible`);
+/// rejects after `timeout` ms
+const WAIT_FUNCTION: &str = r#"(selector, timeout, visible, interval) => new Promise((resolve, reject) => {
+    const deadline = Date.now() + timeout;
+    const ready = () => {
+        const el = document.querySelector(selector);
+        if (!el || !visible) return !!el;
+        const rect = el.getBoundingClientRect();
+        return rect.width > 0 && rect.height > 0
+            && window.getComputedStyle(el).visibility !== 'hidden';
+    };
+    const check = () => {
+        if (ready()) return resolve(true);
+        if (Date.now() >= deadline) {
+            return reject(new Error(`Timed out after ${timeout}ms waiting for ${selector}`));
+        }
+        setTimeout(check, interval);
+    };
+    check();
+})"#;
+
+/// Scrolls the element matching `selector` to the middle of the viewport
+const SCROLL_FUNCTION: &str = r#"(selector) => {
+    const el = document.querySelector(selector);
+    if (!el) throw new Error(`No element matches ${selector}`);
+    el.scrollIntoView({ block: 'center', inline: 'center' });
+    return true;
+}"#;
+
+/// A script step that names an element and runs as a sequence of CDP commands
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params")]
+pub enum MacroCommand {
+    /// Click the element's center with the left mouse button
+    #[serde(rename = "Element.click")]
+    Click { selector: String },
+
+    /// Click the element and type `text` over what it holds
+    #[serde(rename = "Element.type")]
+    Type { selector: String, text: String },
+
+    /// Wait up to `timeout` ms until an element matches (and, if `visible`, is rendered)
+    #[serde(rename = "Element.waitFor")]
+    WaitFor {
+        selector: String,
+        #[serde(default = "default_wait_timeout")]
+        timeout: u64,
+        #[serde(default = "default_visible")]
+        visible: bool,
+    },
+
+    /// Scroll the element to the middle of the viewport
+    #[serde(rename = "Element.scrollTo")]
+    ScrollTo { selector: String },
+}
+
+fn default_wait_timeout() -> u64 {
+    DEFAULT_WAIT_TIMEOUT_MS
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+impl MacroCommand {
+    /// Whether `method` is one of the [`MACRO_METHODS`]
+    pub fn is_macro(method: &str) -> bool {
+        MACRO_METHODS.contains(&method)
+    }
+
+    /// The macro `cmd` is (None for other commands), or why its params are invalid
+    pub fn from_command(cmd: &CdpCommand) -> Option<Result<Self, String>> {
+        if !Self::is_macro(&cmd.method) {
+            return None;
+        }
+        let tagged = json!({ "method": cmd.method, "params": cmd.params });
+        Some(
+            serde_json::from_value(tagged)
+                .map_err(|e| format!("Invalid {} parameters: {}", cmd.method, e)),
+        )
+    }
+
+    pub fn method(&self) -> &'static str {
+        match self {
+            Self::Click { .. } => "Element.click",
+            Self::Type { .. } => "Element.type",
+            Self::WaitFor { .. } => "Element.waitFor",
+            Self::ScrollTo { .. } => "Element.scrollTo",
+        }
+    }
+
+    /// Selector of the element the macro acts on
+    pub fn selector(&self) -> &str {
+        match self {
+            Self::Click { selector }
+            | Self::Type { selector, .. }
+            | Self::WaitFor { selector, .. }
+            | Self::ScrollTo { selector } => selector,
+        }
+    }
+
+    /// Whether the element must be on the page when the step starts (every macro
+    /// but `Element.waitFor`), so its fallbacks apply
+    pub fn needs_element(&self) -> bool {
+        !matches!(self, Self::WaitFor { .. })
+    }
+
+    /// Whether expanding the macro takes the element's position (it clicks the element)
+    pub fn needs_position(&self) -> bool {
+        matches!(self, Self::Click { .. } | Self::Type { .. })
+    }
+
+    /// The CDP commands the macro runs, acting on the element matching `selector`
+    /// (the macro's own selector, or the fallback that found the element)
+    ///
+    /// `center` is the element's center in viewport coordinates, which
+    /// [`needs_position`](Self::needs_position) macros can't be expanded without.
+    pub fn expand(
+        &self,
+        selector: &str,
+        center: Option<(f64, f64)>,
+    ) -> Result<Vec<CdpCommand>, String> {
+        let center =
+            || center.ok_or_else(|| format!("{} needs the element's position", self.method()));
+        let commands = match self {
+            Self::Click { .. } => click(selector, center()?),
+            Self::Type { text, .. } => {
+                let mut commands = click(selector, center()?);
+                commands.push(evaluate(
+                    call(SELECT_FUNCTION, &[json!(selector)]),
+                    false,
+                    format!("Select the contents of {}", selector),
+                ));
+                if text.is_empty() {
+                    commands.extend(["keyDown", "keyUp"].map(|kind| {
+                        command(
+                            "Input.dispatchKeyEvent",
+                            json!({
+                                "type": kind,
+                                "key": "Delete",
+                                "code": "Delete",
+                                "windowsVirtualKeyCode": 46
+                            }),
+                            format!("Delete the contents of {}", selector),
+                        )
+                    }));
+                } else {
+                    commands.push(command(
+                        "Input.insertText",
+                        json!({ "text": text }),
+                        format!("Type \"{}\"", text),
+                    ));
+                }
+                commands
+            }
+            Self::WaitFor {
+                timeout, visible, ..
+            } => vec![evaluate(
+                call(
+                    WAIT_FUNCTION,
+                    &[
+                        json!(selector),
+                        json!(timeout),
+                        json!(visible),
+                        json!(POLL_INTERVAL_MS),
+                    ],
+                ),
+                true,
+                format!("Wait for {}", selector),
+            )],
+            Self::ScrollTo { .. } => vec![evaluate(
+                call(SCROLL_FUNCTION, &[json!(selector)]),
+                false,
+                format!("Scroll to {}", selector),
+            )],
+        };
+        Ok(commands)
+    }
+}
+
+/// Mouse move, press, and release at `(x, y)`
+fn click(selector: &str, (x, y): (f64, f64)) -> Vec<CdpCommand> {
+    let event = |kind: &str, action: &str| {
+        let mut params = json!({ "type": kind, "x": x, "y": y });
+        if kind != "mouseMoved" {
+            params["button"] = json!("left");
+            params["clickCount"] = json!(1);
+        }
+        command(
+            "Input.dispatchMouseEvent",
+            params,
+            format!("{} {}", action, selector),
+        )
+    };
+    vec![
+        event("mouseMoved", "Move the mouse to"),
+        event("mousePressed", "Press the mouse on"),
+        event("mouseReleased", "Release the mouse on"),
+    ]
+}
+
+/// `Runtime.evaluate` of `expression`, returning its value
+fn evaluate(expression: String, await_promise: bool, description: String) -> CdpCommand {
+    let mut params = json!({ "expression": expression, "returnByValue": true });
+    if await_promise {
+        params["awaitPromise"] = json!(true);
+    }
+    command("Runtime.evaluate", params, description)
+}
+
+/// Expression calling `function` with `args`
+fn call(function: &str, args: &[Value]) -> String {
+    let args: Vec<String> = args.iter().map(Value::to_string).collect();
+    format!("({})({})", function, args.join(", "))
+}
+
+fn command(method: &str, params: Value, description: String) -> CdpCommand {
+    CdpCommand {
+        method: method.to_string(),
+        params,
+        save_as: None,
+        description: Some(description),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(method: &str, params: Value) -> CdpCommand {
+        CdpCommand {
+            method: method.to_string(),
+            params,
+            save_as: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_from_command() {
+        let cmd = step(
+            "Element.type",
+            json!({ "selector": "#email", "text": "a@b.c", "fallbackText": "Email" }),
+        );
+        assert_eq!(
+            MacroCommand::from_command(&cmd),
+            Some(Ok(MacroCommand::Type {
+                selector: "#email".to_string(),
+                text: "a@b.c".to_string()
+            }))
+        );
+
+        let wait = step("Element.waitFor", json!({ "selector": ".results" }));
+        assert_eq!(
+            MacroCommand::from_command(&wait),
+            Some(Ok(MacroCommand::WaitFor {
+                selector: ".results".to_string(),
+                timeout: DEFAULT_WAIT_TIMEOUT_MS,
+                visible: true
+            }))
+        );
+
+        let missing = step("Element.click", json!({}));
+        let err = MacroCommand::from_command(&missing).unwrap().unwrap_err();
+        assert!(
+            err.contains("Element.click") && err.contains("selector"),
+            "{}",
+            err
+        );
+        assert!(MacroCommand::from_command(&step("Page.reload", json!({}))).is_none());
+    }
+
+    #[test]
+    fn test_expand_click_and_type() {
+        let click = MacroCommand::Click {
+            selector: "#buy".to_string(),
+        };
+        assert!(click.expand("#buy", None).is_err());
+        let commands = click.expand("#buy", Some((120.0, 48.5))).unwrap();
+        let kinds: Vec<&str> = commands
+            .iter()
+            .map(|cmd| cmd.params["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(kinds, ["mouseMoved", "mousePressed", "mouseReleased"]);
+        assert!(commands
+            .iter()
+            .all(|cmd| cmd.method == "Input.dispatchMouseEvent"
+                && cmd.params["x"] == 120.0
+                && cmd.params["y"] == 48.5));
+        assert_eq!(commands[1].params["button"], "left");
+        assert_eq!(commands[2].params["clickCount"], 1);
+
+        // The fallback selector that matched is the one used
+        let type_text = MacroCommand::Type {
+            selector: "#q".to_string(),
+            text: "rust".to_string(),
+        };
+        let commands = type_text
+            .expand("input[name=\"q\"]", Some((10.0, 10.0)))
+            .unwrap();
+        let methods: Vec<&str> = commands.iter().map(|cmd| cmd.method.as_str()).collect();
+        assert_eq!(
+            methods,
+            [
+                "Input.dispatchMouseEvent",
+                "Input.dispatchMouseEvent",
+                "Input.dispatchMouseEvent",
+                "Runtime.evaluate",
+                "Input.insertText"
+            ]
+        );
+        let select = commands[3].params["expression"].as_str().unwrap();
+        assert!(select.ends_with(r#"})("input[name=\"q\"]")"#), "{}", select);
+        assert_eq!(commands[4].params["text"], "rust");
+
+        let clear = MacroCommand::Type {
+            selector: "#q".to_string(),
+            text: String::new(),
+        };
+        let commands = clear.expand("#q", Some((10.0, 10.0))).unwrap();
+        assert_eq!(commands.last().unwrap().params["key"], "Delete");
+    }
+
+    #[test]
+    fn test_expand_wait_and_scroll() {
+        let wait = MacroCommand::WaitFor {
+            selector: ".toast".to_string(),
+            timeout: 2500,
+            visible: false,
+        };
+        assert!(!wait.needs_element() && !wait.needs_position());
+        let commands = wait.expand(".toast", None).unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].params["awaitPromise"], true);
+        let expression = commands[0].params["expression"].as_str().unwrap();
+        assert!(expression.ends_with(r#"(".toast", 2500, false, 100)"#));
+
+        let scroll = MacroCommand::ScrollTo {
+            selector: "footer".to_string(),
+        };
+        let commands = scroll.expand("footer", None).unwrap();
+        assert_eq!(commands[0].method, "Runtime.evaluate");
+        assert!(commands[0].params.get("awaitPromise").is_none());
+        assert_eq!(commands[0].description.as_deref(), Some("Scroll to footer"));
+    }
+}
@@ -0,0 +1,31 @@
+//! Core Data Model
+//!
+//! The pure half of the crate: CDP scripts and execution reports, script validation,
+//! linting, diffs, versions, step narration, run summaries, HTML reports, goal checks, element
+//! fingerprints, element salience, data snapshot comparison, localization-aware text
+//! matching, macro command expansion, typed commands, browser flavors, the step frame
+//! schema, and page outlines for language models.
+//! Nothing here does IO, so it builds without the `runtime` feature (including for
+//! `wasm32-unknown-unknown`) and web frontends can validate scripts and render
+//! reports with the same code the driver uses.
+
+pub mod browser;
+pub mod builder;
+pub mod diff;
+pub mod fingerprint;
+pub mod frame;
+pub mod geometry;
+pub mod goal;
+pub mod lint;
+pub mod llm_dom;
+pub mod macros;
+pub mod narration;
+pub mod report_html;
+pub mod salience;
+pub mod script;
+pub mod snapshot;
+pub mod summary;
+pub mod text_match;
+pub mod typed;
+pub mod validation;
+pub mod version;
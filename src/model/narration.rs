@@ -0,0 +1,317 @@
+//! Step Narration
+//!
+//! Step frames carry a [`TranscriptInfo`] so a replayed session reads as a story
+//! rather than a list of CDP methods. Scripts rarely come with one written per
+//! step, so [`narrate`] derives it from the command and its place in the script:
+//! what the step does (its description, or one made from its method and
+//! parameters), why it's there (the script's goal, the page it acts on, and what
+//! kind of step it is), and what should happen if it works.
+//!
+//! Narration is rule-based: it never calls a model, so it is cheap enough to run
+//! for every frame and gives the same text for the same script.
+
+use super::frame::TranscriptInfo;
+use super::lint::describe;
+use super::script::{CdpCommand, CdpScript};
+
+/// Narration of step `step` (1-indexed) of `script`, or None if there is no such step
+pub fn narrate(script: &CdpScript, step: usize) -> Option<TranscriptInfo> {
+    let index = step.checked_sub(1)?;
+    let cmd = script.cdp_commands.get(index)?;
+
+    let mut reasoning = format!("Step {} of {}", step, script.cdp_commands.len());
+    if !script.description.trim().is_empty() {
+        reasoning.push_str(&format!(" toward \"{}\"", script.description.trim()));
+    }
+    if let Some(host) = current_host(&script.cdp_commands[..index]) {
+        reasoning.push_str(&format!(" on {}", host));
+    }
+    reasoning.push_str(&format!(": {}", purpose(cmd)));
+
+    Some(TranscriptInfo {
+        action_description: cmd.description.clone().unwrap_or_else(|| action(cmd)),
+        reasoning: Some(reasoning),
+        expected_outcome: expected_outcome(cmd),
+    })
+}
+
+/// What the command does, for one without a description
+fn action(cmd: &CdpCommand) -> String {
+    let str_param = |name: &str| cmd.params[name].as_str().unwrap_or_default();
+    match cmd.method.as_str() {
+        "Runtime.evaluate" if is_wait(cmd) => "Wait for the page".to_string(),
+        "Runtime.evaluate" => "Run JavaScript in the page".to_string(),
+        "Input.dispatchMouseEvent" => format!(
+            "Send a mouse {} at ({}, {})",
+            str_param("type").trim_start_matches("mouse").to_lowercase(),
+            cmd.params["x"],
+            cmd.params["y"]
+        ),
+        "Input.dispatchKeyEvent" => format!("Press {}", key_name(cmd)),
+        "Form.fill" => format!("Fill in {}", field_names(cmd)),
+        "Form.typeText" => format!(
+            "Type \"{}\" into {}",
+            str_param("text"),
+            str_param("selector")
+        ),
+        "Touch.tap" => format!("Tap {}", str_param("selector")),
+        "Touch.swipe" => "Swipe across the page".to_string(),
+        "Touch.pinch" => "Pinch to zoom".to_string(),
+        "Network.setCookie" => format!("Set the {} cookie", str_param("name")),
+        "Network.deleteCookies" => format!("Delete the {} cookie", str_param("name")),
+        "Network.getCookies" => "Read the cookies".to_string(),
+        "Emulation.setDeviceMetricsOverride" => format!(
+            "Resize the viewport to {}x{}",
+            cmd.params["width"], cmd.params["height"]
+        ),
+        "Emulation.setGeolocationOverride" => "Set the reported location".to_string(),
+        "Emulation.setEmulatedMedia" => "Change the emulated media".to_string(),
+        "Target.waitForPopup" => "Wait for a popup to open".to_string(),
+        "Target.switchToOpener" => "Return to the page that opened the popup".to_string(),
+        "Audit.accessibility" => "Audit the page's accessibility".to_string(),
+        "Audit.performance" => "Measure the page's performance".to_string(),
+        "Extract.seo" => "Read the page's SEO metadata".to_string(),
+        "Assert.snapshot" => format!("Check the {} snapshot", str_param("name")),
+        "Assert.text" => format!("Check the page shows \"{}\"", str_param("text")),
+        "Verify.goal" => format!("Check the goal: {}", str_param("goal")),
+        _ => describe(cmd),
+    }
+}
+
+/// Why a step like this is in a script
+fn purpose(cmd: &CdpCommand) -> &'static str {
+    match cmd.method.as_str() {
+        "Page.navigate" => "load the page the following steps work on",
+        "Page.reload" => "start again from a freshly loaded page",
+        "Page.goBack" | "Page.goForward" => "move through the tab's history",
+        "Page.captureScreenshot" => "record what the page looks like at this point",
+        "Runtime.evaluate" if is_wait(cmd) => {
+            "give the page time to finish loading or animating before continuing"
+        }
+        "Runtime.evaluate" if cmd.save_as.is_some() => "extract data from the page",
+        "Runtime.evaluate" => "read or change the page with JavaScript",
+        "Input.insertText" | "Input.imeSetComposition" | "Form.typeText" | "Element.type" => {
+            "enter text the way a user would type it"
+        }
+        "Input.dispatchMouseEvent" | "Touch.tap" | "Element.click" => {
+            "interact with an element the way a user would"
+        }
+        "Element.waitFor" => "wait until the element the next steps need is on the page",
+        "Element.scrollTo" => "bring the element the next steps need into view",
+        "Input.dispatchKeyEvent" => "press a key the way a user would",
+        "Form.fill" => "fill in the form's fields",
+        "Touch.swipe" | "Touch.pinch" => "perform a touch gesture",
+        "Network.getCookies" | "Network.setCookie" | "Network.deleteCookies" => {
+            "control the session's cookies"
+        }
+        "Emulation.setDeviceMetricsOverride"
+        | "Emulation.setGeolocationOverride"
+        | "Emulation.setEmulatedMedia" => "make the page behave as it would on another device",
+        "Target.waitForPopup" | "Target.switchToOpener" => "follow the flow across windows",
+        "Audit.accessibility" | "Audit.performance" => "check the page's quality",
+        "Extract.seo" => "extract data from the page",
+        "Assert.snapshot" => "verify the page's data hasn't changed",
+        "Assert.text" => "verify the page shows what it should",
+        "Verify.goal" => "confirm the run achieved what it set out to do",
+        _ => "carry out the next part of the task",
+    }
+}
+
+/// What should happen if the command works
+fn expected_outcome(cmd: &CdpCommand) -> Option<String> {
+    let str_param = |name: &str| cmd.params[name].as_str().unwrap_or_default();
+    let saved = |what: &str| match &cmd.save_as {
+        Some(path) => format!("{} is saved to {}", what, path),
+        None => format!("{} is returned", what),
+    };
+    let outcome = match cmd.method.as_str() {
+        "Page.navigate" => match host(str_param("url")) {
+            Some(host) => format!("The page at {} loads", host),
+            None => "The page loads".to_string(),
+        },
+        "Page.reload" => "The page reloads".to_string(),
+        "Page.goBack" => "The previous page is shown".to_string(),
+        "Page.goForward" => "The next page is shown".to_string(),
+        "Page.captureScreenshot" => saved("A screenshot"),
+        "Runtime.evaluate" if is_wait(cmd) => "The page is ready for the next step".to_string(),
+        "Runtime.evaluate" if cmd.save_as.is_some() => saved("The result"),
+        "Runtime.evaluate" => "The script runs without throwing".to_string(),
+        "Input.insertText" | "Input.imeSetComposition" => {
+            format!("The focused field contains \"{}\"", str_param("text"))
+        }
+        "Form.typeText" => format!(
+            "{} contains \"{}\"",
+            str_param("selector"),
+            str_param("text")
+        ),
+        "Form.fill" => format!("The form shows the given values for {}", field_names(cmd)),
+        "Input.dispatchMouseEvent" if str_param("type") == "mouseReleased" => {
+            "The element under the pointer receives the click".to_string()
+        }
+        "Touch.tap" => format!("{} receives the tap", str_param("selector")),
+        "Element.click" => format!("{} receives the click", str_param("selector")),
+        "Element.type" => format!(
+            "{} contains \"{}\"",
+            str_param("selector"),
+            str_param("text")
+        ),
+        "Element.waitFor" => format!("{} is on the page", str_param("selector")),
+        "Element.scrollTo" => format!("{} is in view", str_param("selector")),
+        "Input.dispatchKeyEvent" if str_param("type") != "keyUp" => {
+            format!("The page handles {}", key_name(cmd))
+        }
+        "Network.getCookies" => saved("The cookies"),
+        "Network.setCookie" => format!("The {} cookie is set", str_param("name")),
+        "Network.deleteCookies" => format!("The {} cookie is gone", str_param("name")),
+        "Emulation.setDeviceMetricsOverride" => format!(
+            "The page lays out at {}x{}",
+            cmd.params["width"], cmd.params["height"]
+        ),
+        "Target.waitForPopup" => "A popup opens and becomes the current page".to_string(),
+        "Target.switchToOpener" => "The opener is the current page again".to_string(),
+        "Audit.accessibility" | "Audit.performance" | "Extract.seo" => saved("A report"),
+        "Assert.snapshot" => format!(
+            "The extracted data matches the {} snapshot",
+            str_param("name")
+        ),
+        "Assert.text" => match cmd.params["selector"].as_str() {
+            Some(selector) => format!("{} shows \"{}\"", selector, str_param("text")),
+            None => format!("The page shows \"{}\"", str_param("text")),
+        },
+        "Verify.goal" => "The page meets the goal".to_string(),
+        _ => return None,
+    };
+    Some(outcome)
+}
+
+/// Whether the command only waits (a promise around `setTimeout`)
+fn is_wait(cmd: &CdpCommand) -> bool {
+    cmd.method == "Runtime.evaluate"
+        && cmd.params["expression"]
+            .as_str()
+            .is_some_and(|expression| expression.contains("setTimeout"))
+        && cmd.save_as.is_none()
+}
+
+/// The key an `Input.dispatchKeyEvent` sends, e.g. `Enter`
+fn key_name(cmd: &CdpCommand) -> String {
+    ["key", "code", "text"]
+        .iter()
+        .find_map(|name| cmd.params[*name].as_str().filter(|key| !key.is_empty()))
+        .map(|key| format!("\"{}\"", key))
+        .unwrap_or_else(|| "a key".to_string())
+}
+
+/// The fields a `Form.fill` sets, e.g. `email and password`
+fn field_names(cmd: &CdpCommand) -> String {
+    let names: Vec<&str> = cmd.params["fields"]
+        .as_object()
+        .map(|fields| fields.keys().map(String::as_str).collect())
+        .unwrap_or_default();
+    match names.as_slice() {
+        [] => "the form".to_string(),
+        [name] => name.to_string(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+/// Host the page is on after `commands`, going by the last navigation
+fn current_host(commands: &[CdpCommand]) -> Option<String> {
+    commands
+        .iter()
+        .rev()
+        .find(|cmd| cmd.method == "Page.navigate")
+        .and_then(|cmd| host(cmd.params["url"].as_str()?))
+}
+
+/// Host of an absolute URL (`https://www.example.com:8080/a` → `www.example.com`)
+fn host(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn script() -> CdpScript {
+        serde_json::from_value(json!({
+            "name": "login",
+            "description": "Log into the dashboard",
+            "cdp_commands": [
+                { "method": "Page.navigate", "params": { "url": "https://App.example.com:8443/login?next=/" } },
+                { "method": "Runtime.evaluate",
+                  "params": { "expression": "new Promise(r => setTimeout(r, 500))", "awaitPromise": true } },
+                { "method": "Form.fill", "params": { "fields": { "email": "a@b.c", "password": "x" } },
+                  "description": "Enter the credentials" },
+                { "method": "Input.dispatchKeyEvent", "params": { "type": "keyDown", "key": "Enter" } },
+                { "method": "Page.captureScreenshot", "params": {}, "save_as": "dashboard.png" },
+                { "method": "Browser.crash", "params": {} },
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_narrate() {
+        let script = script();
+
+        let open = narrate(&script, 1).unwrap();
+        assert_eq!(
+            open.action_description,
+            "Navigate to https://App.example.com:8443/login?next=/"
+        );
+        assert_eq!(
+            open.reasoning.as_deref(),
+            Some(
+                "Step 1 of 6 toward \"Log into the dashboard\": load the page the following \
+                 steps work on"
+            )
+        );
+        assert_eq!(
+            open.expected_outcome.as_deref(),
+            Some("The page at app.example.com loads")
+        );
+
+        let wait = narrate(&script, 2).unwrap();
+        assert_eq!(wait.action_description, "Wait for the page");
+        assert!(wait
+            .reasoning
+            .unwrap()
+            .starts_with("Step 2 of 6 toward \"Log into the dashboard\" on app.example.com: give"));
+
+        let fill = narrate(&script, 3).unwrap();
+        assert_eq!(fill.action_description, "Enter the credentials");
+        assert_eq!(
+            fill.expected_outcome.as_deref(),
+            Some("The form shows the given values for email and password")
+        );
+
+        let key = narrate(&script, 4).unwrap();
+        assert_eq!(key.action_description, "Press \"Enter\"");
+        assert_eq!(
+            narrate(&script, 5).unwrap().expected_outcome.as_deref(),
+            Some("A screenshot is saved to dashboard.png")
+        );
+
+        let unknown = narrate(&script, 6).unwrap();
+        assert_eq!(unknown.action_description, "Run Browser.crash");
+        assert_eq!(unknown.expected_outcome, None);
+
+        assert!(narrate(&script, 0).is_none());
+        assert!(narrate(&script, 7).is_none());
+    }
+
+    #[test]
+    fn test_host() {
+        assert_eq!(
+            host("https://user:pw@Example.com:8080/a?b#c").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(host("about:blank"), None);
+        assert_eq!(host("file:///tmp/page.html"), None);
+    }
+}
@@ -0,0 +1,271 @@
+//! HTML Execution Reports
+//!
+//! [`ExecutionReport::to_html`] renders a report as a standalone HTML page for CI
+//! artifacts and the server's `/runs/{id}/report.html`: the run's outcome, a table of
+//! its steps, and the findings of its `Audit.accessibility` steps (each violation with
+//! its impact, the fix, a link to the rule, and the failing elements). Audits that
+//! failed the step on `failOn` keep their findings, so the report shows what tripped
+//! the gate.
+//!
+//! The page has inline styles and no scripts, so it opens from disk.
+
+use super::script::{CommandStatus, ExecutionReport};
+use serde::Deserialize;
+use std::fmt::Write;
+
+/// Failing elements listed per violation before the rest are counted
+const MAX_LISTED_NODES: usize = 10;
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2rem;color:#222}\
+table{border-collapse:collapse;width:100%;margin-bottom:1.5rem}\
+th,td{border:1px solid #ddd;padding:.4rem .6rem;text-align:left;vertical-align:top}\
+th{background:#f4f4f4}.success{color:#1a7f37}.failed{color:#cf222e}.skipped{color:#777}\
+.impact-critical,.impact-serious{color:#cf222e;font-weight:600}\
+.impact-moderate{color:#9a6700}code{font-size:.9em}";
+
+/// The part of an `Audit.accessibility` response the report shows
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct AuditFindings {
+    url: String,
+    axe_version: String,
+    violations: Vec<Violation>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Violation {
+    id: String,
+    impact: Option<String>,
+    help: String,
+    help_url: String,
+    nodes: Vec<ViolationNode>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ViolationNode {
+    target: Vec<String>,
+    failure_summary: Option<String>,
+}
+
+impl ExecutionReport {
+    /// The report as a standalone HTML page
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let title = format!("{}: {}", self.script_name, self.outcome());
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            escape(&title),
+            STYLE,
+            escape(&title)
+        );
+        let _ = writeln!(
+            html,
+            "<p>{} commands: {} succeeded, {} failed, {} skipped in {:.1}s</p>",
+            self.total_commands,
+            self.successful,
+            self.failed,
+            self.skipped,
+            self.total_duration.as_secs_f64()
+        );
+        if let Some(verdict) = &self.verdict {
+            let _ = writeln!(
+                html,
+                "<p>Goal {}: {}</p>",
+                if verdict.passed { "met" } else { "not met" },
+                escape(&verdict.reason)
+            );
+        }
+        self.write_steps(&mut html);
+        self.write_accessibility(&mut html);
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// How the run ended, in a few words
+    fn outcome(&self) -> &'static str {
+        if self.cancelled {
+            "cancelled"
+        } else if self.is_success() {
+            "passed"
+        } else {
+            "failed"
+        }
+    }
+
+    fn write_steps(&self, html: &mut String) {
+        html.push_str(
+            "<h2>Steps</h2>\n<table>\n<tr><th>#</th><th>Command</th><th>Status</th>\
+             <th>Duration</th><th>Error</th></tr>\n",
+        );
+        for result in &self.results {
+            let status = match result.status {
+                CommandStatus::Success => "success",
+                CommandStatus::Failed => "failed",
+                CommandStatus::Skipped => "skipped",
+            };
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td><code>{}</code></td><td class=\"{}\">{}</td>\
+                 <td>{} ms</td><td>{}</td></tr>",
+                result.step,
+                escape(&result.method),
+                status,
+                status,
+                result.duration.as_millis(),
+                escape(result.error.as_deref().unwrap_or_default())
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn write_accessibility(&self, html: &mut String) {
+        let audits: Vec<(usize, AuditFindings)> = self
+            .results
+            .iter()
+            .filter(|result| result.method == "Audit.accessibility")
+            .filter_map(|result| {
+                let findings = serde_json::from_value(result.response.clone()?).ok()?;
+                Some((result.step, findings))
+            })
+            .collect();
+        if audits.is_empty() {
+            return;
+        }
+        html.push_str("<h2>Accessibility</h2>\n");
+        for (step, audit) in audits {
+            let _ = writeln!(
+                html,
+                "<h3>Step {}: {}</h3>\n<p>{} violation{} (axe-core {})</p>",
+                step,
+                escape(&audit.url),
+                audit.violations.len(),
+                if audit.violations.len() == 1 { "" } else { "s" },
+                escape(&audit.axe_version)
+            );
+            if audit.violations.is_empty() {
+                continue;
+            }
+            html.push_str(
+                "<table>\n<tr><th>Rule</th><th>Impact</th><th>Fix</th><th>Elements</th></tr>\n",
+            );
+            for violation in &audit.violations {
+                let impact = violation.impact.as_deref().unwrap_or("unknown");
+                let _ = write!(
+                    html,
+                    "<tr><td><a href=\"{}\">{}</a></td><td class=\"impact-{}\">{}</td>\
+                     <td>{}</td><td><ul>",
+                    escape(&violation.help_url),
+                    escape(&violation.id),
+                    escape(impact),
+                    escape(impact),
+                    escape(&violation.help)
+                );
+                for node in violation.nodes.iter().take(MAX_LISTED_NODES) {
+                    let _ = write!(html, "<li><code>{}</code>", escape(&node.target.join(", ")));
+                    if let Some(summary) = &node.failure_summary {
+                        let _ = write!(html, "<br>{}", escape(summary));
+                    }
+                    html.push_str("</li>");
+                }
+                if violation.nodes.len() > MAX_LISTED_NODES {
+                    let _ = write!(
+                        html,
+                        "<li>and {} more</li>",
+                        violation.nodes.len() - MAX_LISTED_NODES
+                    );
+                }
+                html.push_str("</ul></td></tr>\n");
+            }
+            html.push_str("</table>\n");
+        }
+    }
+}
+
+/// `text` with the characters HTML gives meaning to escaped
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::script::CommandResult;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn result(step: usize, method: &str, status: CommandStatus) -> CommandResult {
+        CommandResult {
+            step,
+            method: method.to_string(),
+            status,
+            duration: Duration::from_millis(120),
+            response: None,
+            error: None,
+            saved_file: None,
+            frames: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_to_html() {
+        let mut report = ExecutionReport::new("a11y-check".to_string(), 2);
+        report.add_result(result(1, "Page.navigate", CommandStatus::Success));
+        let mut audit = result(2, "Audit.accessibility", CommandStatus::Failed);
+        audit.error = Some("Accessibility violations at or above Serious: image-alt".to_string());
+        audit.response = Some(json!({
+            "url": "https://example.com/?a=1&b=2",
+            "axeVersion": "4.10.2",
+            "violations": [{
+                "id": "image-alt",
+                "impact": "critical",
+                "description": "Images must have alternate text",
+                "help": "Images must have alternate text",
+                "helpUrl": "https://dequeuniversity.com/rules/axe/4.10/image-alt",
+                "tags": ["wcag2a"],
+                "nodes": [{
+                    "target": ["img.hero"],
+                    "html": "<img class=\"hero\">",
+                    "failureSummary": "Element does not have an alt attribute",
+                    "impact": "critical"
+                }]
+            }]
+        }));
+        report.add_result(audit);
+
+        let html = report.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>a11y-check: failed</title>"));
+        assert!(html.contains("<td class=\"failed\">failed</td>"));
+        assert!(html.contains("<h3>Step 2: https://example.com/?a=1&amp;b=2</h3>"));
+        assert!(html.contains("1 violation (axe-core 4.10.2)"));
+        assert!(html.contains(
+            "<a href=\"https://dequeuniversity.com/rules/axe/4.10/image-alt\">image-alt</a>"
+        ));
+        assert!(html.contains("<td class=\"impact-critical\">critical</td>"));
+        assert!(html.contains("<code>img.hero</code><br>Element does not have an alt attribute"));
+        assert!(!html.contains("<img"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">Tom & Jerry's</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+}
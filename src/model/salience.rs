@@ -0,0 +1,638 @@
+//! Element Salience
+//!
+//! When an instruction is ambiguous ("continue", "sign up") an agent should pick the
+//! control a person would: the big, high-contrast button near the top of the page,
+//! not the footer link with the same words. [`CaptureSnapshotResponse::rank_elements`]
+//! scores the interactive elements of a VisualDom snapshot on five signals, each
+//! from 0 to 1:
+//!
+//! - **size**: box area relative to the viewport (boxes covering over a quarter of
+//!   it are usually containers, and score lower)
+//! - **position**: above the fold and near the horizontal center
+//! - **contrast**: how much the element's background stands out from its parent's
+//!   (needs blended background colors, which step frames capture)
+//! - **z-order**: whether the element is painted on top at its center, or covered
+//!   by something like a modal (needs paint orders)
+//! - **semantics**: buttons over links over fields, submit buttons, primary-action
+//!   wording ("Sign up", "Checkout") and class names, minus dismissive wording
+//!   ("Cancel", "Learn more")
+//!
+//! The score is their weighted sum, so callers can re-weigh from the signals.
+
+use crate::model::frame::{CaptureSnapshotResponse, ViewportInfo, ELEMENT_NODE};
+use crate::model::geometry::Rect;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// DOM node type of text nodes
+const TEXT_NODE: i64 = 3;
+
+/// Weights of size, position, contrast, z-order, and semantics in the score
+const WEIGHTS: [f64; 5] = [0.25, 0.2, 0.2, 0.1, 0.25];
+
+/// Share of the viewport at which an element gets the full size signal
+const FULL_SIZE: f64 = 0.02;
+
+/// Share of the viewport above which an element is probably a container
+const CONTAINER_SIZE: f64 = 0.25;
+
+/// Roles that make an element interactive
+const ROLES: &[&str] = &[
+    "button", "link", "checkbox", "radio", "switch", "tab", "menuitem", "option", "combobox",
+];
+
+/// Words of primary actions
+const PRIMARY_WORDS: &[&str] = &[
+    "sign up",
+    "sign in",
+    "log in",
+    "login",
+    "register",
+    "get started",
+    "start",
+    "try",
+    "buy",
+    "checkout",
+    "check out",
+    "add to cart",
+    "order",
+    "pay",
+    "subscribe",
+    "continue",
+    "next",
+    "submit",
+    "confirm",
+    "save",
+    "create",
+    "download",
+    "book",
+    "join",
+];
+
+/// Words of secondary or dismissive actions
+const SECONDARY_WORDS: &[&str] = &[
+    "cancel",
+    "back",
+    "skip",
+    "close",
+    "dismiss",
+    "no thanks",
+    "not now",
+    "later",
+    "learn more",
+    "read more",
+    "privacy",
+    "terms",
+];
+
+/// An interactive element with its salience
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SalientElement {
+    /// Index of the node in the snapshot's main document
+    pub node_index: usize,
+
+    /// CSS selector (see [`CaptureSnapshotResponse::selector`])
+    pub selector: String,
+
+    /// Lowercase tag name
+    pub tag: String,
+
+    /// Accessible name: `aria-label`, the text, or a field's value or placeholder
+    /// (first 100 characters)
+    pub text: String,
+
+    /// Layout box in document coordinates (CSS pixels)
+    pub bounds: Rect,
+
+    /// Weighted sum of the signals, from 0 to 1
+    pub score: f64,
+
+    pub signals: SalienceSignals,
+}
+
+/// What an element's salience score is made of, each from 0 to 1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SalienceSignals {
+    pub size: f64,
+    pub position: f64,
+    pub contrast: f64,
+    pub z_order: f64,
+    pub semantics: f64,
+}
+
+impl SalienceSignals {
+    /// Weighted sum of the signals
+    pub fn score(&self) -> f64 {
+        let signals = [
+            self.size,
+            self.position,
+            self.contrast,
+            self.z_order,
+            self.semantics,
+        ];
+        signals.iter().zip(WEIGHTS).map(|(s, w)| s * w).sum()
+    }
+}
+
+/// An element of the snapshot, with the lookups ranking needs
+struct Node<'a> {
+    snapshot: &'a CaptureSnapshotResponse,
+    index: usize,
+}
+
+impl Node<'_> {
+    fn string(&self, index: i64) -> Option<&str> {
+        self.snapshot
+            .strings
+            .get(usize::try_from(index).ok()?)
+            .map(String::as_str)
+    }
+
+    fn tag(&self) -> String {
+        let names = self.snapshot.documents[0].nodes.node_name.as_deref();
+        names
+            .and_then(|names| names.get(self.index))
+            .and_then(|&name| self.string(name))
+            .unwrap_or_default()
+            .to_lowercase()
+    }
+
+    fn attribute(&self, key: &str) -> Option<&str> {
+        let attributes = self.snapshot.documents[0].nodes.attributes.as_ref()?;
+        attributes
+            .get(self.index)?
+            .chunks_exact(2)
+            .find_map(|pair| {
+                (self.string(pair[0])? == key)
+                    .then(|| self.string(pair[1]))
+                    .flatten()
+            })
+    }
+}
+
+impl CaptureSnapshotResponse {
+    /// Interactive elements of the main document with a layout box, most salient first
+    ///
+    /// Interactive means a link, button, form field, or element with an interactive
+    /// role, plus clickable elements (click listeners) that neither contain nor sit
+    /// inside one. Elements inside another ranked element (an icon in a button) are
+    /// left out. `viewport` places the fold and the center.
+    pub fn rank_elements(&self, viewport: &ViewportInfo) -> Vec<SalientElement> {
+        let Some(document) = self.documents.first() else {
+            return Vec::new();
+        };
+        let nodes = &document.nodes;
+        let (Some(parents), Some(types)) = (&nodes.parent_index, &nodes.node_type) else {
+            return Vec::new();
+        };
+        let count = types.len();
+        let parent = |n: usize| parents.get(n).and_then(|&p| usize::try_from(p).ok());
+        let node = |index: usize| Node {
+            snapshot: self,
+            index,
+        };
+
+        // Layout box of each node, if it has one
+        let mut layouts: Vec<Option<usize>> = vec![None; count];
+        for (layout, &n) in document.layout.node_index.iter().enumerate() {
+            if let Some(slot) = usize::try_from(n).ok().and_then(|n| layouts.get_mut(n)) {
+                *slot = Some(layout);
+            }
+        }
+
+        let semantic: Vec<bool> = (0..count)
+            .map(|n| types[n] == ELEMENT_NODE && is_semantic(&node(n)))
+            .collect();
+        let clickable: HashSet<usize> = nodes
+            .is_clickable
+            .as_ref()
+            .map(|c| {
+                c.index
+                    .iter()
+                    .filter_map(|&n| usize::try_from(n).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut contains_semantic = vec![false; count];
+        for n in (0..count).filter(|&n| semantic[n]) {
+            let mut current = parent(n);
+            while let Some(p) = current.filter(|&p| !contains_semantic[p]) {
+                contains_semantic[p] = true;
+                current = parent(p);
+            }
+        }
+
+        // Snapshot nodes are in document order, so ancestors are seen first
+        let mut ranked_nodes = vec![false; count];
+        let mut ranked = Vec::new();
+        for n in 0..count {
+            let candidate = semantic[n]
+                || (types[n] == ELEMENT_NODE && clickable.contains(&n) && !contains_semantic[n]);
+            if !candidate || ancestors(n, &parent).any(|p| ranked_nodes[p]) {
+                continue;
+            }
+            let Some(bounds) = layouts[n].and_then(|l| rect(&document.layout.bounds[l])) else {
+                continue;
+            };
+            if bounds.width < 1.0 || bounds.height < 1.0 {
+                continue;
+            }
+            let element = node(n);
+            let tag = element.tag();
+            if tag == "html" || tag == "body" {
+                continue;
+            }
+            ranked_nodes[n] = true;
+
+            let text = self.accessible_name(&element, &tag);
+            let background_contrast = layouts[n].and_then(|l| {
+                let own = self.background(l)?;
+                let surrounding = ancestors(n, &parent).find_map(|p| layouts[p])?;
+                Some(contrast(own, self.background(surrounding)?))
+            });
+            let (cx, cy) = bounds.center();
+            let on_top = document.layout.paint_orders.is_some().then(|| {
+                self.node_at(cx, cy)
+                    .is_some_and(|top| top == n || ancestors(top, &parent).any(|p| p == n))
+            });
+            let signals = SalienceSignals {
+                size: size_signal(&bounds, viewport),
+                position: position_signal(&bounds, viewport),
+                contrast: background_contrast.unwrap_or(0.0),
+                // Without paint orders, assume nothing is covered
+                z_order: if on_top.unwrap_or(true) { 1.0 } else { 0.0 },
+                semantics: semantics_signal(&element, &tag, &text),
+            };
+            ranked.push(SalientElement {
+                node_index: n,
+                selector: self.selector(n).unwrap_or_default(),
+                tag,
+                text,
+                bounds,
+                score: round(signals.score()),
+                signals: SalienceSignals {
+                    size: round(signals.size),
+                    position: round(signals.position),
+                    contrast: round(signals.contrast),
+                    z_order: signals.z_order,
+                    semantics: round(signals.semantics),
+                },
+            });
+        }
+        ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+        ranked
+    }
+
+    /// Blended background color of layout node `layout`, if captured and parseable
+    fn background(&self, layout: usize) -> Option<[f64; 3]> {
+        let colors = self.documents[0]
+            .layout
+            .blended_background_colors
+            .as_ref()?;
+        let color = self
+            .strings
+            .get(usize::try_from(*colors.get(layout)?).ok()?)?;
+        parse_color(color)
+    }
+
+    fn accessible_name(&self, element: &Node, tag: &str) -> String {
+        let label = element.attribute("aria-label").map(str::to_string);
+        let field = matches!(tag, "input" | "textarea" | "select");
+        let name = label
+            .filter(|label| !label.trim().is_empty())
+            .or_else(|| {
+                if field {
+                    element
+                        .attribute("value")
+                        .filter(|_| element.attribute("type") != Some("password"))
+                        .or_else(|| element.attribute("placeholder"))
+                        .map(str::to_string)
+                } else {
+                    Some(self.text_of(element.index))
+                }
+            })
+            .filter(|name| !name.trim().is_empty())
+            .or_else(|| element.attribute("title").map(str::to_string))
+            .unwrap_or_default();
+        let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        name.chars().take(100).collect()
+    }
+
+    /// Text of the text nodes below `node`, in document order
+    fn text_of(&self, node: usize) -> String {
+        let nodes = &self.documents[0].nodes;
+        let (Some(parents), Some(types), Some(values)) =
+            (&nodes.parent_index, &nodes.node_type, &nodes.node_value)
+        else {
+            return String::new();
+        };
+        let mut inside: HashSet<usize> = HashSet::from([node]);
+        let mut text = Vec::new();
+        // Descendants directly follow their ancestor in document order
+        for n in node + 1..types.len() {
+            let in_subtree = usize::try_from(parents[n]).is_ok_and(|p| inside.contains(&p));
+            if !in_subtree {
+                break;
+            }
+            inside.insert(n);
+            if types[n] == TEXT_NODE {
+                if let Some(value) = values
+                    .get(n)
+                    .and_then(|&v| self.strings.get(usize::try_from(v).ok()?))
+                {
+                    text.push(value.trim().to_string());
+                }
+            }
+        }
+        text.retain(|t| !t.is_empty());
+        text.join(" ")
+    }
+}
+
+fn is_semantic(node: &Node) -> bool {
+    let tag = node.tag();
+    match tag.as_str() {
+        "a" => node.attribute("href").is_some(),
+        "button" | "select" | "textarea" | "summary" => true,
+        "input" => node.attribute("type") != Some("hidden"),
+        _ => node
+            .attribute("role")
+            .is_some_and(|role| ROLES.contains(&role)),
+    }
+}
+
+/// Ancestors of `node`, nearest first
+fn ancestors<'a>(
+    node: usize,
+    parent: &'a impl Fn(usize) -> Option<usize>,
+) -> impl Iterator<Item = usize> + 'a {
+    std::iter::successors(parent(node), move |&p| parent(p))
+}
+
+fn rect(bounds: &[f64]) -> Option<Rect> {
+    match bounds {
+        [x, y, width, height] => Some(Rect {
+            x: *x,
+            y: *y,
+            width: *width,
+            height: *height,
+        }),
+        _ => None,
+    }
+}
+
+fn size_signal(bounds: &Rect, viewport: &ViewportInfo) -> f64 {
+    let share = bounds.width * bounds.height / (viewport.width * viewport.height).max(1.0);
+    if share > CONTAINER_SIZE {
+        0.5
+    } else {
+        (share / FULL_SIZE).sqrt().min(1.0)
+    }
+}
+
+/// 1 in the first screen, halving a screen below it; less toward the sides
+fn position_signal(bounds: &Rect, viewport: &ViewportInfo) -> f64 {
+    let fold = viewport.scroll_y + viewport.height;
+    let below = (bounds.y - fold).max(0.0);
+    let above = (viewport.scroll_y - (bounds.y + bounds.height)).max(0.0);
+    let vertical = 1.0 / (1.0 + (below + above) / viewport.height.max(1.0));
+    let center = viewport.scroll_x + viewport.width / 2.0;
+    let offset =
+        ((bounds.x + bounds.width / 2.0 - center).abs() / (viewport.width / 2.0).max(1.0)).min(1.0);
+    vertical * (1.0 - 0.3 * offset)
+}
+
+fn semantics_signal(element: &Node, tag: &str, text: &str) -> f64 {
+    if element.attribute("disabled").is_some() || element.attribute("aria-disabled") == Some("true")
+    {
+        return 0.0;
+    }
+    let kind = element.attribute("type").unwrap_or_default();
+    let role = element.attribute("role").unwrap_or_default();
+    let mut score: f64 = match tag {
+        "button" => 0.5,
+        "input" if matches!(kind, "submit" | "button" | "image") => 0.5,
+        _ if role == "button" => 0.5,
+        "a" => 0.3,
+        _ => 0.2,
+    };
+    if kind == "submit" || (tag == "button" && kind.is_empty()) {
+        score += 0.1;
+    }
+    let text = text.to_lowercase();
+    let has = |words: &[&str]| {
+        words.iter().any(|word| {
+            text.match_indices(word).any(|(start, _)| {
+                let before = text[..start].chars().next_back();
+                let after = text[start + word.len()..].chars().next();
+                !before.is_some_and(char::is_alphanumeric)
+                    && !after.is_some_and(char::is_alphanumeric)
+            })
+        })
+    };
+    if has(PRIMARY_WORDS) {
+        score += 0.3;
+    }
+    if has(SECONDARY_WORDS) {
+        score -= 0.3;
+    }
+    let class = element
+        .attribute("class")
+        .unwrap_or_default()
+        .to_lowercase();
+    if ["primary", "cta", "hero"]
+        .iter()
+        .any(|hint| class.contains(hint))
+    {
+        score += 0.2;
+    }
+    score.clamp(0.0, 1.0)
+}
+
+/// `rgb(r, g, b)` or `rgba(r, g, b, a)` as linear-light RGB, None if transparent
+fn parse_color(color: &str) -> Option<[f64; 3]> {
+    let inner = color
+        .trim()
+        .strip_prefix("rgba(")
+        .or_else(|| color.trim().strip_prefix("rgb("))?
+        .strip_suffix(')')?;
+    let parts: Vec<f64> = inner
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    match parts[..] {
+        [.., alpha] if parts.len() == 4 && alpha == 0.0 => None,
+        [r, g, b, ..] => Some([r, g, b].map(|c| {
+            let c = c / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        })),
+        _ => None,
+    }
+}
+
+/// WCAG contrast ratio of two colors, scaled from 1..21 to 0..1
+fn contrast(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let luminance = |[r, g, b]: [f64; 3]| 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let (la, lb) = (luminance(a), luminance(b));
+    let ratio = (la.max(lb) + 0.05) / (la.min(lb) + 0.05);
+    // A ratio of 3 already reads as a filled button
+    ((ratio - 1.0) / 4.0).min(1.0)
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport() -> ViewportInfo {
+        ViewportInfo {
+            width: 1280.0,
+            height: 720.0,
+            device_pixel_ratio: 1.0,
+            scroll_x: 0.0,
+            scroll_y: 0.0,
+            scale: 1.0,
+            document_width: 1280.0,
+            document_height: 3000.0,
+        }
+    }
+
+    /// A hero with a primary button, an email field, a menu toggle, and a footer link
+    fn snapshot() -> serde_json::Value {
+        serde_json::json!({
+            "documents": [{
+                "documentURL": 0, "title": 0, "baseURL": 0, "contentLanguage": 0,
+                "encodingName": 0, "publicId": 0, "systemId": 0, "frameId": 0,
+                "nodes": {
+                    "parentIndex": [-1, 0, 1, 2, 3, 1, 5, 1, 1, 8],
+                    "nodeType": [1, 1, 1, 1, 3, 1, 3, 1, 1, 3],
+                    "nodeName": [1, 2, 3, 4, 5, 7, 5, 11, 18, 5],
+                    "nodeValue": [-1, -1, -1, -1, 6, -1, 10, -1, -1, 19],
+                    "attributes": [[], [], [], [16, 17], [], [8, 9], [], [12, 13, 14, 15], [], []],
+                    "isClickable": {"index": [3, 5, 8]}
+                },
+                "layout": {
+                    "nodeIndex": [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+                    "styles": [[], [], [], [], [], [], [], [], [], []],
+                    "bounds": [
+                        [0, 0, 1280, 3000], [0, 0, 1280, 3000], [0, 0, 1280, 600],
+                        [560, 300, 160, 48], [580, 312, 120, 24], [40, 2800, 100, 20],
+                        [40, 2800, 100, 20], [540, 400, 200, 32], [1200, 10, 40, 20],
+                        [1200, 10, 40, 20]
+                    ],
+                    "text": [0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                    "paintOrders": [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+                    "blendedBackgroundColors": [20, 20, 20, 21, 21, 20, 20, 20, 20, 20]
+                },
+                "textBoxes": {"layoutIndex": [], "start": [], "length": [], "bounds": []}
+            }],
+            "strings": [
+                "", "HTML", "BODY", "DIV", "BUTTON", "#text", "Get started", "A", "href",
+                "/about", "Learn more", "INPUT", "type", "email", "placeholder", "Email",
+                "class", "btn btn-primary", "SPAN", "Menu", "rgb(255, 255, 255)",
+                "rgb(37, 99, 235)"
+            ]
+        })
+    }
+
+    #[test]
+    fn test_rank_elements() {
+        let snapshot: CaptureSnapshotResponse = serde_json::from_value(snapshot()).unwrap();
+        let ranked = snapshot.rank_elements(&viewport());
+        let names: Vec<(&str, &str)> = ranked
+            .iter()
+            .map(|e| (e.tag.as_str(), e.text.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("button", "Get started"),
+                ("input", "Email"),
+                ("span", "Menu"),
+                ("a", "Learn more")
+            ]
+        );
+
+        let button = &ranked[0];
+        assert_eq!(button.selector, "html > body > div > button");
+        assert_eq!(button.signals.semantics, 1.0);
+        assert_eq!(button.signals.z_order, 1.0);
+        assert!(button.signals.contrast > 0.9);
+        assert_eq!(ranked[1].signals.contrast, 0.0);
+        // The footer link is three screens down and says "Learn more"
+        assert!(ranked[3].signals.position < 0.3);
+        assert_eq!(ranked[3].signals.semantics, 0.0);
+    }
+
+    #[test]
+    fn test_covered_elements() {
+        // A full-viewport overlay painted over everything
+        let mut value = snapshot();
+        let document = &mut value["documents"][0];
+        document["nodes"]["parentIndex"]
+            .as_array_mut()
+            .unwrap()
+            .push(1.into());
+        document["nodes"]["nodeType"]
+            .as_array_mut()
+            .unwrap()
+            .push(1.into());
+        document["nodes"]["nodeName"]
+            .as_array_mut()
+            .unwrap()
+            .push(3.into());
+        document["nodes"]["nodeValue"]
+            .as_array_mut()
+            .unwrap()
+            .push((-1).into());
+        document["nodes"]["attributes"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!([]));
+        let layout = &mut document["layout"];
+        layout["nodeIndex"].as_array_mut().unwrap().push(10.into());
+        layout["styles"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!([]));
+        layout["bounds"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!([0, 0, 1280, 720]));
+        layout["text"].as_array_mut().unwrap().push(0.into());
+        layout["paintOrders"]
+            .as_array_mut()
+            .unwrap()
+            .push(10.into());
+        layout["blendedBackgroundColors"]
+            .as_array_mut()
+            .unwrap()
+            .push(20.into());
+        let snapshot: CaptureSnapshotResponse = serde_json::from_value(value).unwrap();
+
+        let ranked = snapshot.rank_elements(&viewport());
+        let button = ranked.iter().find(|e| e.tag == "button").unwrap();
+        assert_eq!(button.signals.z_order, 0.0);
+        // The link below the overlay isn't covered
+        let link = ranked.iter().find(|e| e.tag == "a").unwrap();
+        assert_eq!(link.signals.z_order, 1.0);
+    }
+
+    #[test]
+    fn test_colors() {
+        assert_eq!(parse_color("rgba(0, 0, 0, 0)"), None);
+        assert_eq!(parse_color("rgb(255, 255, 255)"), Some([1.0, 1.0, 1.0]));
+        let black = parse_color("rgba(0, 0, 0, 0.5)").unwrap();
+        assert_eq!(contrast(black, [1.0, 1.0, 1.0]), 1.0);
+        assert_eq!(contrast(black, black), 0.0);
+        assert_eq!(parse_color("transparent"), None);
+    }
+}
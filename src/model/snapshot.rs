@@ -0,0 +1,102 @@
+//! Data Snapshots
+//!
+//! Snapshot assertions (`Assert.snapshot`) compare JSON extracted from a page with a
+//! snapshot stored by an earlier run. Values that change between runs (timestamps,
+//! ids) are left out with ignore paths, which replace them with [`IGNORED`] in both
+//! the stored snapshot and the value it is compared with.
+
+use crate::model::diff::FieldChange;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Placeholder stored instead of an ignored value
+pub const IGNORED: &str = "[ignored]";
+
+/// Changes from the `expected` snapshot to the `actual` value, one per differing
+/// leaf (missing values are `null`), with paths like `items[0].price`
+pub fn compare(expected: &Value, actual: &Value) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    compare_at(String::new(), expected, actual, &mut changes);
+    changes
+}
+
+/// Human-readable diff of `changes`, for a failed assertion's artifact
+pub fn render_diff(name: &str, changes: &[FieldChange]) -> String {
+    let mut diff = format!("--- snapshot {}\n+++ actual\n", name);
+    for change in changes {
+        let field = if change.field.is_empty() {
+            "(root)"
+        } else {
+            &change.field
+        };
+        diff.push_str(&format!(
+            "{}\n-  {}\n+  {}\n",
+            field, change.before, change.after
+        ));
+    }
+    diff
+}
+
+fn compare_at(path: String, expected: &Value, actual: &Value, changes: &mut Vec<FieldChange>) {
+    match (expected, actual) {
+        (Value::Object(before), Value::Object(after)) => {
+            let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                compare_at(
+                    child,
+                    before.get(key).unwrap_or(&Value::Null),
+                    after.get(key).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        (Value::Array(before), Value::Array(after)) => {
+            for index in 0..before.len().max(after.len()) {
+                compare_at(
+                    format!("{}[{}]", path, index),
+                    before.get(index).unwrap_or(&Value::Null),
+                    after.get(index).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        _ if expected != actual => changes.push(FieldChange {
+            field: path,
+            before: expected.clone(),
+            after: actual.clone(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compare() {
+        let expected = json!({ "title": "Menu", "items": [{ "price": 4 }, { "price": 6 }] });
+        assert!(compare(&expected, &expected).is_empty());
+
+        let actual = json!({ "title": "Menu", "items": [{ "price": 5 }], "count": 1 });
+        let changes = compare(&expected, &actual);
+        let fields: Vec<&str> = changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, ["count", "items[0].price", "items[1]"]);
+        assert_eq!(changes[1].before, 4);
+        assert_eq!(changes[1].after, 5);
+        assert_eq!(changes[2].after, Value::Null);
+
+        let diff = render_diff("menu", &changes);
+        assert!(diff.starts_with("--- snapshot menu\n+++ actual\n"));
+        assert!(diff.contains("items[0].price\n-  4\n+  5\n"));
+
+        let changes = compare(&json!([1]), &json!("one"));
+        assert_eq!(changes[0].field, "");
+    }
+}
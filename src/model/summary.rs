@@ -0,0 +1,397 @@
+//! Execution Summaries
+//!
+//! An [`ExecutionReport`] lists every command's status, timing, and response, which
+//! is more than a chat message or notification has room for. [`ExecutionSummary`]
+//! condenses a report into a headline, a short "what happened" paragraph, and the
+//! few screenshots worth looking at: the failure, the screenshots the script took,
+//! and the page it ended on.
+//!
+//! Summaries are written from the report and its frames alone (step descriptions
+//! come from the frames' transcripts and actions), so they need no LLM call.
+
+use super::frame::StepFrame;
+use super::script::{CommandResult, CommandStatus, ExecutionReport};
+use serde::{Deserialize, Serialize};
+
+/// Key screenshots a summary picks when not told otherwise
+pub const DEFAULT_KEY_FRAMES: usize = 3;
+
+/// Longest error message quoted in a summary, in characters
+const MAX_ERROR_CHARS: usize = 200;
+
+/// Most completed steps listed by description before the rest are counted
+const MAX_LISTED_STEPS: usize = 5;
+
+/// A concise digest of a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "runtime", derive(utoipa::ToSchema))]
+pub struct ExecutionSummary {
+    /// One line: the script and how it ended (e.g. `login: failed at step 3 of 5`)
+    pub headline: String,
+
+    /// A few sentences on what the run did and what went wrong
+    pub summary: String,
+
+    /// Screenshots worth looking at, in the order they were taken
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_frames: Vec<KeyFrame>,
+}
+
+/// A screenshot picked for a summary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "runtime", derive(utoipa::ToSchema))]
+pub struct KeyFrame {
+    /// Step the frame was captured around (None for frames not attached to a step)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<usize>,
+
+    pub frame_id: usize,
+
+    /// Screenshot path (or link, once resolved by the server)
+    pub screenshot: String,
+
+    /// What the screenshot shows
+    pub caption: String,
+}
+
+impl ExecutionSummary {
+    /// Summary of `report`, with up to `max_key_frames` screenshots picked from the
+    /// frames attached to its results and `frames` (e.g. captured separately)
+    pub fn from_report(
+        report: &ExecutionReport,
+        frames: &[StepFrame],
+        max_key_frames: usize,
+    ) -> Self {
+        let failure = report
+            .results
+            .iter()
+            .find(|result| result.status == CommandStatus::Failed);
+        let ran = report.successful + report.failed;
+
+        let headline = if let Some(failure) = failure {
+            format!(
+                "{}: failed at step {} of {}",
+                report.script_name, failure.step, report.total_commands
+            )
+        } else if report.cancelled {
+            format!(
+                "{}: cancelled after {} of {}",
+                report.script_name,
+                ran,
+                steps(report.total_commands)
+            )
+        } else if report.is_success() {
+            format!(
+                "{}: completed {} in {}",
+                report.script_name,
+                steps(report.total_commands),
+                format_duration(report.total_duration.as_millis())
+            )
+        } else {
+            format!(
+                "{}: ran {} of {}",
+                report.script_name,
+                ran,
+                steps(report.total_commands)
+            )
+        };
+
+        let mut sentences = Vec::new();
+        let mut opening = format!(
+            "Ran {} of {} in {}",
+            ran,
+            steps(report.total_commands),
+            format_duration(report.total_duration.as_millis())
+        );
+        if let Some(browser) = &report.browser {
+            opening.push_str(&format!(" in {} {}", browser.kind.name(), browser.version));
+        }
+        sentences.push(opening);
+
+        let completed: Vec<String> = report
+            .results
+            .iter()
+            .filter(|result| result.status == CommandStatus::Success)
+            .map(step_description)
+            .collect();
+        if !completed.is_empty() {
+            let mut listed = completed[..completed.len().min(MAX_LISTED_STEPS)].join("; ");
+            if completed.len() > MAX_LISTED_STEPS {
+                listed.push_str(&format!(
+                    "; and {} more",
+                    completed.len() - MAX_LISTED_STEPS
+                ));
+            }
+            sentences.push(format!("Completed: {}", listed));
+        }
+
+        if let Some(failure) = failure {
+            let error = failure.error.as_deref().unwrap_or("unknown error");
+            sentences.push(format!(
+                "Step {} ({}) failed: {}",
+                failure.step,
+                step_description(failure),
+                truncate(error.lines().next().unwrap_or_default(), MAX_ERROR_CHARS)
+            ));
+            if report.failed > 1 {
+                sentences.push(format!("{} more failed after it", steps(report.failed - 1)));
+            }
+        }
+        if let Some(verdict) = &report.verdict {
+            sentences.push(format!(
+                "Goal {} ({:.0}% confidence): {}",
+                if verdict.passed { "met" } else { "not met" },
+                verdict.confidence * 100.0,
+                verdict.reason
+            ));
+        }
+        if report.skipped > 0 {
+            let reason = if report.cancelled {
+                "the run was cancelled"
+            } else {
+                "the run stopped early"
+            };
+            sentences.push(format!(
+                "{} skipped because {}",
+                match report.skipped {
+                    1 => "1 step was".to_string(),
+                    n => format!("{} steps were", n),
+                },
+                reason
+            ));
+        }
+
+        let saved: Vec<&str> = report
+            .results
+            .iter()
+            .filter_map(|result| result.saved_file.as_deref())
+            .collect();
+        if !saved.is_empty() {
+            sentences.push(format!("Saved {}", saved.join(", ")));
+        }
+
+        Self {
+            headline,
+            summary: sentences
+                .iter()
+                .map(|sentence| format!("{}.", sentence.trim_end_matches('.')))
+                .collect::<Vec<_>>()
+                .join(" "),
+            key_frames: key_frames(report, frames, max_key_frames),
+        }
+    }
+}
+
+/// Up to `max` screenshots, most telling first: the failure, screenshots the script
+/// saved, then the last and first frames; returned in capture order
+fn key_frames(report: &ExecutionReport, frames: &[StepFrame], max: usize) -> Vec<KeyFrame> {
+    let candidates: Vec<(Option<&CommandResult>, &StepFrame)> = report
+        .results
+        .iter()
+        .flat_map(|result| result.frames.iter().map(move |frame| (Some(result), frame)))
+        .chain(frames.iter().map(|frame| (None, frame)))
+        .collect();
+
+    let failed = candidates
+        .iter()
+        .rposition(|(result, _)| result.is_some_and(|r| r.status == CommandStatus::Failed));
+    let screenshots = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (result, _))| {
+            result
+                .is_some_and(|r| r.method == "Page.captureScreenshot" && r.saved_file.is_some())
+                .then_some(i)
+        });
+    let last = candidates.len().checked_sub(1);
+    let first = (!candidates.is_empty()).then_some(0);
+
+    let mut picked: Vec<usize> = Vec::new();
+    for index in failed
+        .into_iter()
+        .chain(screenshots)
+        .chain(last)
+        .chain(first)
+    {
+        if picked.len() == max {
+            break;
+        }
+        if !picked.contains(&index) {
+            picked.push(index);
+        }
+    }
+    picked.sort_unstable();
+
+    picked
+        .into_iter()
+        .map(|index| {
+            let (result, frame) = candidates[index];
+            let description = frame
+                .transcript
+                .as_ref()
+                .map(|transcript| transcript.action_description.clone())
+                .or_else(|| frame.action.as_ref().map(|action| action.intent.clone()));
+            let caption = match (result, description) {
+                (Some(r), Some(description)) if r.status == CommandStatus::Failed => {
+                    format!("Step {} failed: {}", r.step, description)
+                }
+                (Some(r), Some(description)) => format!("Step {}: {}", r.step, description),
+                (Some(r), None) => format!("Step {}: {}", r.step, r.method),
+                (None, Some(description)) => description,
+                (None, None) => format!("Frame {}", frame.frame_id),
+            };
+            KeyFrame {
+                step: result.map(|r| r.step),
+                frame_id: frame.frame_id,
+                screenshot: frame.screenshot.path.clone(),
+                caption,
+            }
+        })
+        .collect()
+}
+
+/// What a step did, from its frames' transcript or action (its method if it has none)
+fn step_description(result: &CommandResult) -> String {
+    result
+        .frames
+        .iter()
+        .rev()
+        .find_map(|frame| {
+            frame
+                .transcript
+                .as_ref()
+                .map(|transcript| transcript.action_description.clone())
+                .or_else(|| frame.action.as_ref().map(|action| action.intent.clone()))
+        })
+        .unwrap_or_else(|| result.method.clone())
+}
+
+/// `1 step` or `3 steps`
+fn steps(count: usize) -> String {
+    match count {
+        1 => "1 step".to_string(),
+        n => format!("{} steps", n),
+    }
+}
+
+/// `1.2s`, `350ms`, or `2m 5s`
+fn format_duration(ms: u128) -> String {
+    match ms {
+        0..=999 => format!("{}ms", ms),
+        1_000..=59_999 => format!("{:.1}s", ms as f64 / 1000.0),
+        _ => format!("{}m {}s", ms / 60_000, (ms % 60_000) / 1000),
+    }
+}
+
+/// `text` cut to `max` characters, with an ellipsis if it was longer
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn frame(frame_id: usize, intent: &str) -> StepFrame {
+        serde_json::from_value(json!({
+            "frame_id": frame_id,
+            "timestamp": "2025-10-11T12:00:00Z",
+            "elapsed_ms": 0,
+            "screenshot": {
+                "path": format!("shots/frame_{:04}.png", frame_id),
+                "format": "png",
+                "size_bytes": 100,
+            },
+            "dom": { "url": "https://example.com", "title": "Example" },
+            "action": { "action_type": "Page.navigate", "intent": intent },
+        }))
+        .unwrap()
+    }
+
+    fn result(step: usize, method: &str, status: CommandStatus) -> CommandResult {
+        CommandResult {
+            step,
+            method: method.to_string(),
+            status,
+            duration: std::time::Duration::from_millis(400),
+            response: None,
+            error: None,
+            saved_file: None,
+            frames: Vec::new(),
+        }
+    }
+
+    fn report() -> ExecutionReport {
+        let mut report = ExecutionReport::new("login".to_string(), 4);
+        let mut open = result(1, "Page.navigate", CommandStatus::Success);
+        open.frames = vec![frame(0, "Open the login page")];
+        report.add_result(open);
+        let mut shot = result(2, "Page.captureScreenshot", CommandStatus::Success);
+        shot.saved_file = Some("out/login.png".to_string());
+        shot.frames = vec![frame(1, "Screenshot the form")];
+        report.add_result(shot);
+        let mut fill = result(3, "Form.fill", CommandStatus::Failed);
+        fill.error = Some("Form.fill failed: email: no matching field\nat step 3".to_string());
+        fill.frames = vec![frame(2, "Enter the credentials")];
+        report.add_result(fill);
+        let mut skipped = result(4, "Input.dispatchKeyEvent", CommandStatus::Skipped);
+        skipped.duration = std::time::Duration::ZERO;
+        report.add_result(skipped);
+        report
+    }
+
+    #[test]
+    fn test_failed_run_summary() {
+        let summary = ExecutionSummary::from_report(&report(), &[], DEFAULT_KEY_FRAMES);
+        assert_eq!(summary.headline, "login: failed at step 3 of 4");
+        assert_eq!(
+            summary.summary,
+            "Ran 3 of 4 steps in 1.2s. Completed: Open the login page; Screenshot the form. \
+             Step 3 (Enter the credentials) failed: Form.fill failed: email: no matching field. \
+             1 step was skipped because the run stopped early. Saved out/login.png."
+        );
+
+        let captions: Vec<&str> = summary
+            .key_frames
+            .iter()
+            .map(|frame| frame.caption.as_str())
+            .collect();
+        assert_eq!(
+            captions,
+            [
+                "Step 1: Open the login page",
+                "Step 2: Screenshot the form",
+                "Step 3 failed: Enter the credentials"
+            ]
+        );
+        assert_eq!(summary.key_frames[2].screenshot, "shots/frame_0002.png");
+
+        let one = ExecutionSummary::from_report(&report(), &[], 1);
+        assert_eq!(one.key_frames.len(), 1);
+        assert_eq!(one.key_frames[0].step, Some(3));
+    }
+
+    #[test]
+    fn test_successful_run_summary() {
+        let mut report = ExecutionReport::new("search".to_string(), 1);
+        report.add_result(result(1, "Page.navigate", CommandStatus::Success));
+        let extra = [frame(7, "Final page")];
+
+        let summary = ExecutionSummary::from_report(&report, &extra, DEFAULT_KEY_FRAMES);
+        assert_eq!(summary.headline, "search: completed 1 step in 400ms");
+        assert_eq!(
+            summary.summary,
+            "Ran 1 of 1 step in 400ms. Completed: Page.navigate."
+        );
+        assert_eq!(summary.key_frames.len(), 1);
+        assert_eq!(summary.key_frames[0].step, None);
+        assert_eq!(summary.key_frames[0].caption, "Final page");
+
+        assert_eq!(format_duration(125_000), "2m 5s");
+        assert_eq!(truncate("abcdef", 3), "abc…");
+    }
+}
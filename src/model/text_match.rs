@@ -0,0 +1,294 @@
+//! Localization-Aware Text Matching
+//!
+//! Rendered text rarely equals the string a script was written with: pages put
+//! non-breaking spaces between a price and its currency, typographic quotes and
+//! dashes in place of ASCII ones, soft hyphens and zero-width spaces inside long
+//! words, and translations that differ from the expected text only in accents or
+//! case. A [`TextMatcher`] compares both sides after the same normalization:
+//!
+//! - every kind of space becomes a plain space, and runs of whitespace collapse to one
+//! - zero-width characters and soft hyphens are removed
+//! - curly quotes, primes, and dashes become their ASCII forms, `…` becomes `...`
+//! - full-width ASCII and `ﬁ`-style ligatures become plain letters
+//! - diacritics are dropped (`é` → `e`, `ø` → `o`, `æ` → `ae`, combining marks)
+//! - case is folded (`ß` → `ss`)
+//!
+//! Diacritic folding covers Latin-1, Latin Extended-A, and combining marks, which is
+//! what Western and Central European text uses; other scripts are compared as they are.
+//!
+//! With a `fuzzy` threshold, texts also match when their similarity (one minus the
+//! edit distance over the expected text's length) reaches it, so a typo or a changed
+//! word in a long label doesn't fail the match.
+
+use serde::{Deserialize, Serialize};
+
+/// Latin letters with diacritics, by the letter they fold to
+const DIACRITICS: &[(&str, char)] = &[
+    ("àáâãäåāăąǎ", 'a'),
+    ("ÀÁÂÃÄÅĀĂĄǍ", 'A'),
+    ("çćĉċč", 'c'),
+    ("ÇĆĈĊČ", 'C'),
+    ("ďđð", 'd'),
+    ("ĎĐÐ", 'D'),
+    ("èéêëēĕėęě", 'e'),
+    ("ÈÉÊËĒĔĖĘĚ", 'E'),
+    ("ĝğġģ", 'g'),
+    ("ĜĞĠĢ", 'G'),
+    ("ĥħ", 'h'),
+    ("ĤĦ", 'H'),
+    ("ìíîïĩīĭįıǐ", 'i'),
+    ("ÌÍÎÏĨĪĬĮİǏ", 'I'),
+    ("ĵ", 'j'),
+    ("Ĵ", 'J'),
+    ("ķ", 'k'),
+    ("Ķ", 'K'),
+    ("ĺļľŀł", 'l'),
+    ("ĹĻĽĿŁ", 'L'),
+    ("ñńņňŉ", 'n'),
+    ("ÑŃŅŇ", 'N'),
+    ("òóôõöøōŏőǒ", 'o'),
+    ("ÒÓÔÕÖØŌŎŐǑ", 'O'),
+    ("ŕŗř", 'r'),
+    ("ŔŖŘ", 'R'),
+    ("śŝşšș", 's'),
+    ("ŚŜŞŠȘ", 'S'),
+    ("ţťŧț", 't'),
+    ("ŢŤŦȚ", 'T'),
+    ("ùúûüũūŭůűųǔ", 'u'),
+    ("ÙÚÛÜŨŪŬŮŰŲǓ", 'U'),
+    ("ŵ", 'w'),
+    ("Ŵ", 'W'),
+    ("ýÿŷ", 'y'),
+    ("ÝŸŶ", 'Y'),
+    ("źżž", 'z'),
+    ("ŹŻŽ", 'Z'),
+];
+
+/// How to compare expected text with rendered text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TextMatcher {
+    /// Compare case (default: fold it)
+    pub case_sensitive: bool,
+
+    /// Drop diacritics before comparing (default: true)
+    pub fold_diacritics: bool,
+
+    /// Lowest similarity (0-1) that still matches; None matches normalized text exactly
+    pub fuzzy: Option<f64>,
+}
+
+impl Default for TextMatcher {
+    fn default() -> Self {
+        Self {
+            case_sensitive: false,
+            fold_diacritics: true,
+            fuzzy: None,
+        }
+    }
+}
+
+impl TextMatcher {
+    /// The default matcher, also accepting texts at least `threshold` similar
+    pub fn fuzzy(threshold: f64) -> Self {
+        Self {
+            fuzzy: Some(threshold),
+            ..Self::default()
+        }
+    }
+
+    /// `text` as this matcher compares it
+    pub fn normalize(&self, text: &str) -> String {
+        let mut folded = String::with_capacity(text.len());
+        for c in text.chars() {
+            self.fold(c, &mut folded);
+        }
+        folded.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Similarity of two whole texts after normalization, from 0 (nothing in common)
+    /// to 1 (equal)
+    pub fn similarity(&self, a: &str, b: &str) -> f64 {
+        let (a, b) = (self.normalize(a), self.normalize(b));
+        let longest = a.chars().count().max(b.chars().count());
+        if longest == 0 {
+            return 1.0;
+        }
+        1.0 - edit_distance(&a, &b) as f64 / longest as f64
+    }
+
+    /// Whether `actual` is the `expected` text as a whole
+    pub fn matches(&self, actual: &str, expected: &str) -> bool {
+        match self.fuzzy {
+            None => self.normalize(actual) == self.normalize(expected),
+            Some(threshold) => self.similarity(actual, expected) >= threshold,
+        }
+    }
+
+    /// How well `needle` occurs somewhere in `haystack` (1 for an exact occurrence
+    /// after normalization), or None if it doesn't reach the fuzzy threshold
+    pub fn find(&self, haystack: &str, needle: &str) -> Option<f64> {
+        let (haystack, needle) = (self.normalize(haystack), self.normalize(needle));
+        if haystack.contains(&needle) {
+            return Some(1.0);
+        }
+        let threshold = self.fuzzy?;
+        let length = needle.chars().count();
+        let score = 1.0 - substring_distance(&haystack, &needle) as f64 / length as f64;
+        (score >= threshold).then_some(score)
+    }
+
+    /// Append the normalized form of `c` (before whitespace collapsing)
+    fn fold(&self, c: char, out: &mut String) {
+        let fold_case = !self.case_sensitive;
+        let replacement = match c {
+            '\u{200b}'..='\u{200d}' | '\u{2060}' | '\u{feff}' | '\u{ad}' => "",
+            '‘' | '’' | '‚' | '‛' | '′' | '`' | '´' => "'",
+            '“' | '”' | '„' | '‟' | '″' | '«' | '»' => "\"",
+            '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' => "-",
+            '…' => "...",
+            'ﬀ' => "ff",
+            'ﬁ' => "fi",
+            'ﬂ' => "fl",
+            'ﬃ' => "ffi",
+            'ﬄ' => "ffl",
+            'ﬅ' | 'ﬆ' => "st",
+            'ß' | 'ẞ' if fold_case => "ss",
+            'æ' if self.fold_diacritics => "ae",
+            'Æ' if self.fold_diacritics => "AE",
+            'œ' if self.fold_diacritics => "oe",
+            'Œ' if self.fold_diacritics => "OE",
+            'þ' if self.fold_diacritics => "th",
+            'Þ' if self.fold_diacritics => "TH",
+            '\u{0300}'..='\u{036f}' if self.fold_diacritics => "",
+            c if c.is_whitespace() => " ",
+            c => {
+                let c = match c {
+                    // Full-width ASCII (`Ａ`, `１`, `！`)
+                    '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+                    c if self.fold_diacritics => DIACRITICS
+                        .iter()
+                        .find(|(letters, _)| letters.contains(c))
+                        .map_or(c, |&(_, base)| base),
+                    c => c,
+                };
+                if fold_case {
+                    out.extend(c.to_lowercase());
+                } else {
+                    out.push(c);
+                }
+                return;
+            }
+        };
+        if fold_case {
+            out.push_str(&replacement.to_lowercase());
+        } else {
+            out.push_str(replacement);
+        }
+    }
+}
+
+/// Levenshtein distance between two strings, in characters
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Fewest edits that turn `needle` into some substring of `haystack`, in characters
+fn substring_distance(haystack: &str, needle: &str) -> usize {
+    let haystack: Vec<char> = haystack.chars().collect();
+    // Row i holds the distance of the first i needle characters to the best
+    // substring ending at each haystack position; a match may start anywhere
+    let mut previous = vec![0; haystack.len() + 1];
+    for (i, cn) in needle.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &ch) in haystack.iter().enumerate() {
+            let substitution = previous[j] + usize::from(cn != ch);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous.into_iter().min().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let matcher = TextMatcher::default();
+        assert_eq!(
+            matcher.normalize("  Total:\u{a0}42\u{202f}€ "),
+            "total: 42 €"
+        );
+        assert_eq!(matcher.normalize("Crème Brûlée"), "creme brulee");
+        assert_eq!(matcher.normalize("Cre\u{301}me"), "creme");
+        assert_eq!(matcher.normalize("Straße"), "strasse");
+        assert_eq!(matcher.normalize("“Don’t” — ok…"), "\"don't\" - ok...");
+        assert_eq!(
+            matcher.normalize("in\u{ad}ter\u{200b}na\u{ad}tional"),
+            "international"
+        );
+        assert_eq!(matcher.normalize("ＡＢＣ１２３"), "abc123");
+        assert_eq!(matcher.normalize("ﬁnal Œuvre"), "final oeuvre");
+
+        let strict = TextMatcher {
+            case_sensitive: true,
+            fold_diacritics: false,
+            fuzzy: None,
+        };
+        assert_eq!(strict.normalize("Crème\u{a0}Brûlée"), "Crème Brûlée");
+        assert_eq!(strict.normalize("Straße"), "Straße");
+    }
+
+    #[test]
+    fn test_matches() {
+        let matcher = TextMatcher::default();
+        assert!(matcher.matches("Add\u{a0}to\u{a0}Cart", "add to cart"));
+        assert!(matcher.matches("Télécharger", "telecharger"));
+        assert!(!matcher.matches("Add to cart now", "add to cart"));
+
+        let fuzzy = TextMatcher::fuzzy(0.8);
+        assert!(fuzzy.matches("Add to basket", "Add to baskt"));
+        assert!(!fuzzy.matches("Remove", "Add to cart"));
+        assert!((fuzzy.similarity("abcd", "abce") - 0.75).abs() < 1e-9);
+        assert_eq!(fuzzy.similarity("", ""), 1.0);
+    }
+
+    #[test]
+    fn test_find() {
+        let matcher = TextMatcher::default();
+        let page = "Your order\u{a0}#1234 is confirmed. Thank you!";
+        assert_eq!(matcher.find(page, "ORDER #1234"), Some(1.0));
+        assert_eq!(matcher.find(page, "order confrimed"), None);
+
+        let fuzzy = TextMatcher::fuzzy(0.8);
+        let score = fuzzy.find(page, "is confrimed").unwrap();
+        assert!((0.8..1.0).contains(&score), "{}", score);
+        assert_eq!(fuzzy.find(page, "shipped"), None);
+
+        let json = serde_json::json!({"text": "x", "caseSensitive": true, "fuzzy": 0.9});
+        let parsed: TextMatcher = serde_json::from_value(json).unwrap();
+        assert!(parsed.case_sensitive && parsed.fold_diacritics);
+        assert_eq!(parsed.fuzzy, Some(0.9));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("page.navigate", "page.navigate"), 0);
+        assert_eq!(substring_distance("the kitten sat", "sitten"), 1);
+        assert_eq!(substring_distance("abc", ""), 0);
+    }
+}
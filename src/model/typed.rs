@@ -0,0 +1,869 @@
+//! Typed Commands
+//!
+//! [`CdpCommand`] keeps a step's method and params as a string and a JSON value, which
+//! is what generated and stored scripts need, but it leaves Rust code that writes
+//! steps to find a misspelled method or a missing parameter at validation time.
+//! [`TypedCommand`] mirrors every command the
+//! [`CdpValidator`](crate::model::validation::CdpValidator) accepts with a struct of
+//! its parameters, so the compiler checks them:
+//!
+//! ```
+//! use robert_webdriver::model::typed::{NavigateParams, TypedCommand};
+//! use robert_webdriver::CdpCommand;
+//!
+//! let navigate = TypedCommand::Navigate(NavigateParams::new("https://example.com"));
+//! let command = CdpCommand::from(navigate);
+//! assert_eq!(command.method, "Page.navigate");
+//! assert_eq!(command.params["url"], "https://example.com");
+//! ```
+//!
+//! Typed commands serialize to the same `{"method": ..., "params": {...}}` JSON as
+//! script steps, and [`TypedCommand::from_command`] converts back. Conversion fails
+//! for methods without a variant and for params the structs don't have (at any
+//! depth, e.g. `clip.zoom`), rather than dropping them; such steps stay
+//! [`CdpCommand`]s. Parameters left `None` are left out of the JSON, so the
+//! executor's defaults apply.
+
+use crate::model::builder::ImageFormat;
+use crate::model::fingerprint::ElementFingerprint;
+use crate::model::goal::VerifyMode;
+use crate::model::script::CdpCommand;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// A script step with typed parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params")]
+pub enum TypedCommand {
+    #[serde(rename = "Page.navigate")]
+    Navigate(NavigateParams),
+    #[serde(rename = "Page.captureScreenshot")]
+    CaptureScreenshot(CaptureScreenshotParams),
+    #[serde(rename = "Page.reload")]
+    Reload(ReloadParams),
+    #[serde(rename = "Page.goBack")]
+    GoBack(HistoryParams),
+    #[serde(rename = "Page.goForward")]
+    GoForward(HistoryParams),
+
+    #[serde(rename = "Runtime.evaluate")]
+    Evaluate(EvaluateParams),
+
+    #[serde(rename = "Input.insertText")]
+    InsertText(InsertTextParams),
+    #[serde(rename = "Input.imeSetComposition")]
+    ImeSetComposition(ImeSetCompositionParams),
+    #[serde(rename = "Input.dispatchMouseEvent")]
+    DispatchMouseEvent(MouseEventParams),
+    #[serde(rename = "Input.dispatchKeyEvent")]
+    DispatchKeyEvent(KeyEventParams),
+
+    #[serde(rename = "Network.getCookies")]
+    GetCookies(GetCookiesParams),
+    #[serde(rename = "Network.setCookie")]
+    SetCookie(SetCookieParams),
+    #[serde(rename = "Network.deleteCookies")]
+    DeleteCookies(DeleteCookiesParams),
+
+    #[serde(rename = "Emulation.setGeolocationOverride")]
+    SetGeolocationOverride(GeolocationParams),
+    #[serde(rename = "Emulation.clearGeolocationOverride")]
+    ClearGeolocationOverride(NoParams),
+    #[serde(rename = "Emulation.setDeviceMetricsOverride")]
+    SetDeviceMetricsOverride(DeviceMetricsParams),
+    #[serde(rename = "Emulation.setEmulatedMedia")]
+    SetEmulatedMedia(EmulatedMediaParams),
+
+    #[serde(rename = "Target.waitForPopup")]
+    WaitForPopup(WaitForPopupParams),
+    #[serde(rename = "Target.switchToOpener")]
+    SwitchToOpener(NoParams),
+
+    #[serde(rename = "Audit.accessibility")]
+    AuditAccessibility(AccessibilityParams),
+    #[serde(rename = "Audit.performance")]
+    AuditPerformance(PerformanceParams),
+    #[serde(rename = "Extract.seo")]
+    ExtractSeo(SeoParams),
+
+    #[serde(rename = "Assert.snapshot")]
+    AssertSnapshot(SnapshotParams),
+    #[serde(rename = "Assert.text")]
+    AssertText(AssertTextParams),
+    #[serde(rename = "Verify.goal")]
+    VerifyGoal(GoalParams),
+
+    #[serde(rename = "Form.fill")]
+    FormFill(FormFillParams),
+    #[serde(rename = "Form.typeText")]
+    FormTypeText(TypeTextParams),
+
+    #[serde(rename = "Touch.tap")]
+    TouchTap(ElementTarget),
+    #[serde(rename = "Touch.swipe")]
+    TouchSwipe(SwipeParams),
+    #[serde(rename = "Touch.pinch")]
+    TouchPinch(PinchParams),
+
+    #[serde(rename = "Element.click")]
+    ElementClick(ElementTarget),
+    #[serde(rename = "Element.type")]
+    ElementType(TypeTextParams),
+    #[serde(rename = "Element.waitFor")]
+    ElementWaitFor(WaitForParams),
+    #[serde(rename = "Element.scrollTo")]
+    ElementScrollTo(ElementTarget),
+}
+
+/// Methods [`TypedCommand`] has a variant for
+pub const TYPED_METHODS: &[&str] = &[
+    "Page.navigate",
+    "Page.captureScreenshot",
+    "Page.reload",
+    "Page.goBack",
+    "Page.goForward",
+    "Runtime.evaluate",
+    "Input.insertText",
+    "Input.imeSetComposition",
+    "Input.dispatchMouseEvent",
+    "Input.dispatchKeyEvent",
+    "Network.getCookies",
+    "Network.setCookie",
+    "Network.deleteCookies",
+    "Emulation.setGeolocationOverride",
+    "Emulation.clearGeolocationOverride",
+    "Emulation.setDeviceMetricsOverride",
+    "Emulation.setEmulatedMedia",
+    "Target.waitForPopup",
+    "Target.switchToOpener",
+    "Audit.accessibility",
+    "Audit.performance",
+    "Extract.seo",
+    "Assert.snapshot",
+    "Assert.text",
+    "Verify.goal",
+    "Form.fill",
+    "Form.typeText",
+    "Touch.tap",
+    "Touch.swipe",
+    "Touch.pinch",
+    "Element.click",
+    "Element.type",
+    "Element.waitFor",
+    "Element.scrollTo",
+];
+
+impl TypedCommand {
+    /// The typed form of `cmd`, or why it has none (an unknown method, or params
+    /// that are missing, mistyped, or not in the command's struct, at any depth)
+    pub fn from_command(cmd: &CdpCommand) -> Result<Self, String> {
+        if !TYPED_METHODS.contains(&cmd.method.as_str()) {
+            return Err(format!("{} has no typed form", cmd.method));
+        }
+        let tagged = json!({ "method": cmd.method, "params": cmd.params });
+        let typed: Self = serde_json::from_value(tagged)
+            .map_err(|e| format!("Invalid {} parameters: {}", cmd.method, e))?;
+
+        // Params the structs don't have were skipped while parsing
+        if let Some(name) = dropped_param(&cmd.params, &typed.params()) {
+            return Err(format!("{} has no typed parameter '{}'", cmd.method, name));
+        }
+        Ok(typed)
+    }
+
+    pub fn method(&self) -> &'static str {
+        match self {
+            Self::Navigate(_) => "Page.navigate",
+            Self::CaptureScreenshot(_) => "Page.captureScreenshot",
+            Self::Reload(_) => "Page.reload",
+            Self::GoBack(_) => "Page.goBack",
+            Self::GoForward(_) => "Page.goForward",
+            Self::Evaluate(_) => "Runtime.evaluate",
+            Self::InsertText(_) => "Input.insertText",
+            Self::ImeSetComposition(_) => "Input.imeSetComposition",
+            Self::DispatchMouseEvent(_) => "Input.dispatchMouseEvent",
+            Self::DispatchKeyEvent(_) => "Input.dispatchKeyEvent",
+            Self::GetCookies(_) => "Network.getCookies",
+            Self::SetCookie(_) => "Network.setCookie",
+            Self::DeleteCookies(_) => "Network.deleteCookies",
+            Self::SetGeolocationOverride(_) => "Emulation.setGeolocationOverride",
+            Self::ClearGeolocationOverride(_) => "Emulation.clearGeolocationOverride",
+            Self::SetDeviceMetricsOverride(_) => "Emulation.setDeviceMetricsOverride",
+            Self::SetEmulatedMedia(_) => "Emulation.setEmulatedMedia",
+            Self::WaitForPopup(_) => "Target.waitForPopup",
+            Self::SwitchToOpener(_) => "Target.switchToOpener",
+            Self::AuditAccessibility(_) => "Audit.accessibility",
+            Self::AuditPerformance(_) => "Audit.performance",
+            Self::ExtractSeo(_) => "Extract.seo",
+            Self::AssertSnapshot(_) => "Assert.snapshot",
+            Self::AssertText(_) => "Assert.text",
+            Self::VerifyGoal(_) => "Verify.goal",
+            Self::FormFill(_) => "Form.fill",
+            Self::FormTypeText(_) => "Form.typeText",
+            Self::TouchTap(_) => "Touch.tap",
+            Self::TouchSwipe(_) => "Touch.swipe",
+            Self::TouchPinch(_) => "Touch.pinch",
+            Self::ElementClick(_) => "Element.click",
+            Self::ElementType(_) => "Element.type",
+            Self::ElementWaitFor(_) => "Element.waitFor",
+            Self::ElementScrollTo(_) => "Element.scrollTo",
+        }
+    }
+
+    /// The command's params as script JSON
+    pub fn params(&self) -> Value {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|mut tagged| tagged.get_mut("params").map(Value::take))
+            .unwrap_or_else(|| json!({}))
+    }
+}
+
+impl From<TypedCommand> for CdpCommand {
+    fn from(command: TypedCommand) -> Self {
+        CdpCommand {
+            method: command.method().to_string(),
+            params: command.params(),
+            save_as: None,
+            description: None,
+        }
+    }
+}
+
+impl TryFrom<&CdpCommand> for TypedCommand {
+    type Error = String;
+
+    fn try_from(cmd: &CdpCommand) -> Result<Self, String> {
+        Self::from_command(cmd)
+    }
+}
+
+/// Params of commands that take none
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoParams {}
+
+/// `Page.navigate`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NavigateParams {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub referrer: Option<String>,
+    /// CDP transition type (`link`, `typed`, `reload`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_id: Option<String>,
+}
+
+impl NavigateParams {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// `Page.captureScreenshot`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureScreenshotParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<ImageFormat>,
+    /// JPEG/WebP quality, 0-100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clip: Option<ScreenshotClip>,
+    /// Capture the whole page rather than the viewport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_beyond_viewport: Option<bool>,
+}
+
+/// Region of the page to capture, in CSS pixels
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScreenshotClip {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+/// `Page.reload`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReloadParams {
+    /// Bypass the cache, like shift-reload
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignore_cache: Option<bool>,
+}
+
+/// `Page.goBack` and `Page.goForward`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryParams {
+    /// Go to this `Page.getNavigationHistory` entry instead of the adjacent one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_id: Option<i64>,
+}
+
+/// `Runtime.evaluate`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateParams {
+    pub expression: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_by_value: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub await_promise: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_gesture: Option<bool>,
+}
+
+impl EvaluateParams {
+    pub fn new(expression: impl Into<String>) -> Self {
+        Self {
+            expression: expression.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// `Input.insertText`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InsertTextParams {
+    pub text: String,
+}
+
+/// `Input.imeSetComposition`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImeSetCompositionParams {
+    pub text: String,
+    pub selection_start: i64,
+    pub selection_end: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement_start: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement_end: Option<i64>,
+}
+
+/// `Input.dispatchMouseEvent`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MouseEventParams {
+    #[serde(rename = "type")]
+    pub kind: MouseEventKind,
+    /// Viewport coordinates, in CSS pixels
+    pub x: f64,
+    pub y: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub button: Option<MouseButton>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub click_count: Option<u32>,
+    /// Bit field: Alt=1, Ctrl=2, Meta=4, Shift=8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modifiers: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MouseEventKind {
+    MousePressed,
+    MouseReleased,
+    MouseMoved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MouseButton {
+    None,
+    Left,
+    Middle,
+    Right,
+    Back,
+    Forward,
+}
+
+/// `Input.dispatchKeyEvent`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyEventParams {
+    #[serde(rename = "type")]
+    pub kind: KeyEventKind,
+    /// DOM `key` value (`Enter`, `a`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// DOM `code` value (`Enter`, `KeyA`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Text the key produces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Bit field: Alt=1, Ctrl=2, Meta=4, Shift=8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modifiers: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyEventKind {
+    KeyDown,
+    KeyUp,
+    RawKeyDown,
+    Char,
+}
+
+/// `Network.getCookies`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GetCookiesParams {
+    /// Only cookies sent to these URLs (None = the current page's)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub urls: Option<Vec<String>>,
+}
+
+/// `Network.setCookie`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCookieParams {
+    pub name: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secure: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_only: Option<bool>,
+    /// Expiry as seconds since the Unix epoch (None = session cookie)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<f64>,
+}
+
+/// `Network.deleteCookies`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeleteCookiesParams {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// `Emulation.setGeolocationOverride` (no position emulates it being unavailable)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GeolocationParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    /// In meters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accuracy: Option<f64>,
+}
+
+/// `Emulation.setDeviceMetricsOverride`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceMetricsParams {
+    pub width: u32,
+    pub height: u32,
+    /// 0 keeps the device's own
+    pub device_scale_factor: f64,
+    pub mobile: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen_width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen_height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen_orientation: Option<ScreenOrientation>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScreenOrientation {
+    /// `portraitPrimary`, `portraitSecondary`, `landscapePrimary`, or `landscapeSecondary`
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub angle: u32,
+}
+
+/// `Emulation.setEmulatedMedia`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmulatedMediaParams {
+    /// Media type (`print`, `screen`; empty to stop emulating)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub features: Option<Vec<MediaFeature>>,
+}
+
+/// A media feature value, such as `prefers-color-scheme: dark`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MediaFeature {
+    pub name: String,
+    pub value: String,
+}
+
+/// `Target.waitForPopup`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WaitForPopupParams {
+    /// In milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Make the popup the page later steps act on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub switch: Option<bool>,
+}
+
+/// `Audit.accessibility`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessibilityParams {
+    /// Only rules with one of these tags (`wcag2a`, `wcag2aa`, `best-practice`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disable_rules: Option<Vec<String>>,
+    /// Selectors of the parts of the page to audit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<Vec<String>>,
+    /// Lowest impact that fails the step (`minor`, `moderate`, `serious`, `critical`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fail_on: Option<String>,
+}
+
+/// `Audit.performance`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceParams {
+    /// Lowest score (0-100) that passes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f64>,
+}
+
+/// `Extract.seo`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeoParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validate: Option<bool>,
+    /// Validation rules, as the driver's `SeoRules` JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fail_on_issues: Option<bool>,
+}
+
+/// Path (e.g. `clip.zoom`) of the first key in `given` that `kept`, its typed
+/// round trip, lost
+///
+/// Keys without a value (null, or an empty string, object, or array) are absent
+/// either way, so they don't count.
+fn dropped_param(given: &Value, kept: &Value) -> Option<String> {
+    match (given, kept) {
+        (Value::Object(given), Value::Object(kept)) => {
+            given.iter().find_map(|(name, value)| match kept.get(name) {
+                Some(kept) => dropped_param(value, kept).map(|inner| join_path(name, &inner)),
+                None if is_empty(value) => None,
+                None => Some(name.clone()),
+            })
+        }
+        (Value::Array(given), Value::Array(kept)) => {
+            given
+                .iter()
+                .zip(kept)
+                .enumerate()
+                .find_map(|(index, (value, kept))| {
+                    dropped_param(value, kept)
+                        .map(|inner| join_path(&format!("[{}]", index), &inner))
+                })
+        }
+        _ => None,
+    }
+}
+
+/// `inner` appended to `outer` as a nested key or array index
+fn join_path(outer: &str, inner: &str) -> String {
+    if inner.starts_with('[') {
+        format!("{}{}", outer, inner)
+    } else {
+        format!("{}.{}", outer, inner)
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        Value::Object(fields) => fields.is_empty(),
+        _ => false,
+    }
+}
+
+/// `Assert.snapshot`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotParams {
+    pub name: String,
+    /// JavaScript producing the JSON to compare
+    pub expression: String,
+    /// JSONPaths of values left out of the comparison
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignore_paths: Option<Vec<String>>,
+}
+
+/// `Assert.text`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertTextParams {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exact: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_sensitive: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fold_diacritics: Option<bool>,
+    /// Lowest similarity (0-1) that still matches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuzzy: Option<f64>,
+}
+
+/// `Verify.goal`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoalParams {
+    pub goal: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<VerifyMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_confidence: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fail_on_unmet: Option<bool>,
+    /// File the screenshot a vision check judges is saved as
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<String>,
+}
+
+/// `Form.fill`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormFillParams {
+    /// Values by field name, id, label, or placeholder (strings, numbers, booleans,
+    /// or arrays for multi-selects)
+    pub fields: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fail_on_unmatched: Option<bool>,
+}
+
+/// The element a step acts on, with the fallbacks tried when `selector` matches nothing
+/// (`Touch.tap`, `Element.click`, `Element.scrollTo`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElementTarget {
+    pub selector: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_selectors: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<ElementFingerprint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint_threshold: Option<f64>,
+    /// The element's visible text, tried last
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_text: Option<String>,
+}
+
+impl ElementTarget {
+    pub fn new(selector: impl Into<String>) -> Self {
+        Self {
+            selector: selector.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// `Form.typeText` and `Element.type`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TypeTextParams {
+    #[serde(flatten)]
+    pub target: ElementTarget,
+    pub text: String,
+}
+
+/// `Touch.swipe`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SwipeParams {
+    /// Viewport point `[x, y]`
+    pub from: [f64; 2],
+    pub to: [f64; 2],
+    /// In milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u64>,
+}
+
+/// `Touch.pinch`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PinchParams {
+    /// Over 1 zooms in, under 1 zooms out
+    pub scale: f64,
+}
+
+/// `Element.waitFor`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WaitForParams {
+    pub selector: String,
+    /// In milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Wait for the element to be rendered, not just in the DOM (default true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::builder::ScriptSteps;
+    use crate::model::script::CdpScript;
+    use crate::model::validation::{CdpValidator, ValidationResult};
+
+    fn command(value: Value) -> CdpCommand {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let steps = [
+            json!({"method": "Page.navigate", "params": {"url": "https://example.com"}}),
+            json!({"method": "Page.captureScreenshot", "params": {"format": "png", "captureBeyondViewport": true}}),
+            json!({"method": "Page.goBack", "params": {}}),
+            json!({"method": "Input.dispatchMouseEvent", "params": {"type": "mousePressed", "x": 10.5, "y": 20.5, "button": "left", "clickCount": 1}}),
+            json!({"method": "Emulation.setEmulatedMedia", "params": {"features": [{"name": "prefers-color-scheme", "value": "dark"}]}}),
+            json!({"method": "Target.switchToOpener", "params": {}}),
+            json!({"method": "Verify.goal", "params": {"goal": "Shows \"Order confirmed\"", "mode": "dom"}}),
+            json!({"method": "Form.fill", "params": {"fields": {"email": "a@example.com", "terms": true}}}),
+            json!({"method": "Form.typeText", "params": {"selector": "#q", "fallbackSelectors": ["input[name=q]"], "text": "rust"}}),
+            json!({"method": "Touch.swipe", "params": {"from": [300.0, 400.0], "to": [50.0, 400.0], "duration": 300}}),
+            json!({"method": "Element.waitFor", "params": {"selector": ".results", "timeout": 5000}}),
+        ];
+        for step in steps {
+            let cmd = command(step.clone());
+            let typed = TypedCommand::from_command(&cmd).unwrap();
+            assert_eq!(typed.method(), cmd.method);
+            assert_eq!(serde_json::to_value(&typed).unwrap(), step);
+            assert_eq!(CdpCommand::from(typed).params, step["params"]);
+        }
+    }
+
+    #[test]
+    fn test_rejected_commands() {
+        let unknown = command(json!({"method": "Page.printToPDF", "params": {}}));
+        assert!(TypedCommand::from_command(&unknown)
+            .unwrap_err()
+            .contains("no typed form"));
+
+        let missing = command(json!({"method": "Page.navigate", "params": {}}));
+        assert!(TypedCommand::try_from(&missing)
+            .unwrap_err()
+            .contains("missing field `url`"));
+
+        let mistyped = command(json!({"method": "Touch.pinch", "params": {"scale": "2"}}));
+        assert!(TypedCommand::from_command(&mistyped).is_err());
+
+        // Extra params would be lost, so they fail the conversion; nulls are absent
+        let extra =
+            command(json!({"method": "Touch.tap", "params": {"selector": "a", "force": true}}));
+        assert_eq!(
+            TypedCommand::from_command(&extra).unwrap_err(),
+            "Touch.tap has no typed parameter 'force'"
+        );
+        let null = command(
+            json!({"method": "Touch.tap", "params": {"selector": "a", "fallbackText": null}}),
+        );
+        assert_eq!(
+            TypedCommand::from_command(&null).unwrap(),
+            TypedCommand::TouchTap(ElementTarget::new("a"))
+        );
+
+        // ... in nested objects too
+        let clip = command(json!({"method": "Page.captureScreenshot", "params": {
+            "clip": {"x": 0, "y": 0, "width": 100, "height": 50, "zoom": 2}
+        }}));
+        assert_eq!(
+            TypedCommand::from_command(&clip).unwrap_err(),
+            "Page.captureScreenshot has no typed parameter 'clip.zoom'"
+        );
+        let feature = command(json!({"method": "Emulation.setEmulatedMedia", "params": {
+            "features": [
+                {"name": "prefers-color-scheme", "value": "dark"},
+                {"name": "prefers-reduced-motion", "value": "reduce", "priority": 1}
+            ]
+        }}));
+        assert_eq!(
+            TypedCommand::from_command(&feature).unwrap_err(),
+            "Emulation.setEmulatedMedia has no typed parameter 'features[1].priority'"
+        );
+        let fingerprint = command(json!({"method": "Element.click", "params": {
+            "selector": "a",
+            "fingerprint": {"tag": "a", "text": "", "attributes": {}}
+        }}));
+        assert!(TypedCommand::from_command(&fingerprint).is_ok());
+    }
+
+    #[test]
+    fn test_covers_validator_commands() {
+        let validator = CdpValidator::new();
+        let missing: Vec<_> = validator
+            .commands()
+            .iter()
+            .filter(|method| !TYPED_METHODS.contains(*method))
+            .collect();
+        assert!(missing.is_empty(), "No TypedCommand for {:?}", missing);
+
+        let script = CdpScript::builder("typed", "Typed steps")
+            .typed(TypedCommand::SetDeviceMetricsOverride(
+                DeviceMetricsParams {
+                    width: 390,
+                    height: 844,
+                    device_scale_factor: 3.0,
+                    mobile: true,
+                    ..DeviceMetricsParams::default()
+                },
+            ))
+            .typed(TypedCommand::Navigate(NavigateParams::new(
+                "https://example.com",
+            )))
+            .typed(TypedCommand::ElementType(TypeTextParams {
+                target: ElementTarget::new("#q"),
+                text: "rust".to_string(),
+            }))
+            .typed(TypedCommand::Evaluate(EvaluateParams {
+                return_by_value: Some(true),
+                ..EvaluateParams::new("document.title")
+            }))
+            .build();
+        assert_eq!(
+            script.cdp_commands[2].description.as_deref(),
+            Some("Type \"rust\" into #q")
+        );
+        let mut result = ValidationResult::success();
+        validator.validate_script(&script, &mut result);
+        assert!(result.is_valid, "{:?}", result.errors);
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+    }
+}
@@ -2,12 +2,19 @@
 //!
 //! This module provides comprehensive validation of CDP scripts before execution,
 //! catching errors early and providing detailed error messages.
+//! [`CdpValidator::validate_and_fix`] also repairs the mistakes that have one
+//! obvious fix (typos in method names, numbers written as strings, URLs without a
+//! scheme).
 
+use crate::model::script::{CdpCommand, CdpScript};
+use crate::model::text_match::edit_distance;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 
 /// Detailed validation error with location information
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "runtime", derive(utoipa::ToSchema))]
 pub struct ValidationError {
     /// Error type/category
     pub error_type: ValidationErrorType,
@@ -24,6 +31,7 @@ pub struct ValidationError {
 
 /// Types of validation errors
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "runtime", derive(utoipa::ToSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ValidationErrorType {
     /// JSON syntax error
@@ -53,6 +61,7 @@ pub enum ValidationErrorType {
 
 /// Location information for errors
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "runtime", derive(utoipa::ToSchema))]
 pub struct ErrorLocation {
     /// Command index (0-based) if error is in a specific command
     pub command_index: Option<usize>,
@@ -69,6 +78,7 @@ pub struct ErrorLocation {
 
 /// Result of validation with all errors found
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "runtime", derive(utoipa::ToSchema))]
 pub struct ValidationResult {
     /// Whether the script is valid
     pub is_valid: bool,
@@ -111,6 +121,35 @@ impl ValidationResult {
     }
 }
 
+/// A script repaired by [`CdpValidator::validate_and_fix`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixedScript {
+    /// The script with every repair applied
+    pub script: CdpScript,
+
+    /// What was changed, in command order
+    pub changes: Vec<ScriptFix>,
+
+    /// Validation of the repaired script (errors that couldn't be repaired remain)
+    pub validation: ValidationResult,
+}
+
+/// One repair made by [`CdpValidator::validate_and_fix`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScriptFix {
+    /// Location of the repaired field
+    pub location: ErrorLocation,
+
+    /// Human-readable description of the repair
+    pub description: String,
+
+    /// Value before the repair
+    pub before: Value,
+
+    /// Value after the repair
+    pub after: Value,
+}
+
 /// Comprehensive CDP script validator
 pub struct CdpValidator {
     /// Valid CDP commands (domain.method)
@@ -154,6 +193,7 @@ impl CdpValidator {
             "Page.goForward",
             "Runtime.evaluate",
             "Input.insertText",
+            "Input.imeSetComposition",
             "Input.dispatchMouseEvent",
             "Input.dispatchKeyEvent",
             "Network.getCookies",
@@ -162,6 +202,24 @@ impl CdpValidator {
             "Emulation.setGeolocationOverride",
             "Emulation.setDeviceMetricsOverride",
             "Emulation.clearGeolocationOverride",
+            "Emulation.setEmulatedMedia",
+            "Target.waitForPopup",
+            "Target.switchToOpener",
+            "Audit.accessibility",
+            "Audit.performance",
+            "Extract.seo",
+            "Assert.snapshot",
+            "Assert.text",
+            "Verify.goal",
+            "Form.fill",
+            "Form.typeText",
+            "Touch.tap",
+            "Touch.swipe",
+            "Touch.pinch",
+            "Element.click",
+            "Element.type",
+            "Element.waitFor",
+            "Element.scrollTo",
         ];
 
         let mut parameter_schemas = HashMap::new();
@@ -227,6 +285,24 @@ impl CdpValidator {
             },
         );
 
+        // Input.imeSetComposition schema
+        parameter_schemas.insert(
+            "Input.imeSetComposition",
+            CommandSchema {
+                required_params: vec!["text", "selectionStart", "selectionEnd"],
+                optional_params: vec!["replacementStart", "replacementEnd"],
+                param_types: [
+                    ("text", ParamType::String),
+                    ("selectionStart", ParamType::Number),
+                    ("selectionEnd", ParamType::Number),
+                    ("replacementStart", ParamType::Number),
+                    ("replacementEnd", ParamType::Number),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
         // Input.dispatchMouseEvent schema
         parameter_schemas.insert(
             "Input.dispatchMouseEvent",
@@ -338,18 +414,327 @@ impl CdpValidator {
             },
         );
 
+        // Emulation.setEmulatedMedia schema
+        parameter_schemas.insert(
+            "Emulation.setEmulatedMedia",
+            CommandSchema {
+                required_params: vec![],
+                optional_params: vec!["media", "features"],
+                param_types: [("media", ParamType::String), ("features", ParamType::Array)]
+                    .into_iter()
+                    .collect(),
+            },
+        );
+
+        // Target.waitForPopup schema
+        parameter_schemas.insert(
+            "Target.waitForPopup",
+            CommandSchema {
+                required_params: vec![],
+                optional_params: vec!["timeout", "switch"],
+                param_types: [
+                    ("timeout", ParamType::Number),
+                    ("switch", ParamType::Boolean),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Audit.accessibility schema
+        parameter_schemas.insert(
+            "Audit.accessibility",
+            CommandSchema {
+                required_params: vec![],
+                optional_params: vec!["tags", "disableRules", "include", "exclude", "failOn"],
+                param_types: [
+                    ("tags", ParamType::Array),
+                    ("disableRules", ParamType::Array),
+                    ("include", ParamType::Array),
+                    ("exclude", ParamType::Array),
+                    ("failOn", ParamType::String),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Audit.performance schema
+        parameter_schemas.insert(
+            "Audit.performance",
+            CommandSchema {
+                required_params: vec![],
+                optional_params: vec!["minScore"],
+                param_types: [("minScore", ParamType::Number)].into_iter().collect(),
+            },
+        );
+
+        // Extract.seo schema
+        parameter_schemas.insert(
+            "Extract.seo",
+            CommandSchema {
+                required_params: vec![],
+                optional_params: vec!["validate", "rules", "failOnIssues"],
+                param_types: [
+                    ("validate", ParamType::Boolean),
+                    ("rules", ParamType::Object),
+                    ("failOnIssues", ParamType::Boolean),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Form.fill schema
+        parameter_schemas.insert(
+            "Form.fill",
+            CommandSchema {
+                required_params: vec!["fields"],
+                optional_params: vec!["failOnUnmatched"],
+                param_types: [
+                    ("fields", ParamType::Object),
+                    ("failOnUnmatched", ParamType::Boolean),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Form.typeText schema
+        parameter_schemas.insert(
+            "Form.typeText",
+            CommandSchema {
+                required_params: vec!["selector", "text"],
+                optional_params: vec![
+                    "fallbackSelectors",
+                    "fingerprint",
+                    "fingerprintThreshold",
+                    "fallbackText",
+                ],
+                param_types: [
+                    ("selector", ParamType::String),
+                    ("text", ParamType::String),
+                    ("fallbackSelectors", ParamType::Array),
+                    ("fingerprint", ParamType::Object),
+                    ("fingerprintThreshold", ParamType::Number),
+                    ("fallbackText", ParamType::String),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Touch.tap schema
+        parameter_schemas.insert(
+            "Touch.tap",
+            CommandSchema {
+                required_params: vec!["selector"],
+                optional_params: vec![
+                    "fallbackSelectors",
+                    "fingerprint",
+                    "fingerprintThreshold",
+                    "fallbackText",
+                ],
+                param_types: [
+                    ("selector", ParamType::String),
+                    ("fallbackSelectors", ParamType::Array),
+                    ("fingerprint", ParamType::Object),
+                    ("fingerprintThreshold", ParamType::Number),
+                    ("fallbackText", ParamType::String),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Touch.swipe schema
+        parameter_schemas.insert(
+            "Touch.swipe",
+            CommandSchema {
+                required_params: vec!["from", "to"],
+                optional_params: vec!["duration"],
+                param_types: [
+                    ("from", ParamType::Array),
+                    ("to", ParamType::Array),
+                    ("duration", ParamType::Number),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Element.click schema
+        parameter_schemas.insert(
+            "Element.click",
+            CommandSchema {
+                required_params: vec!["selector"],
+                optional_params: vec![
+                    "fallbackSelectors",
+                    "fingerprint",
+                    "fingerprintThreshold",
+                    "fallbackText",
+                ],
+                param_types: [
+                    ("selector", ParamType::String),
+                    ("fallbackSelectors", ParamType::Array),
+                    ("fingerprint", ParamType::Object),
+                    ("fingerprintThreshold", ParamType::Number),
+                    ("fallbackText", ParamType::String),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Element.type schema
+        parameter_schemas.insert(
+            "Element.type",
+            CommandSchema {
+                required_params: vec!["selector", "text"],
+                optional_params: vec![
+                    "fallbackSelectors",
+                    "fingerprint",
+                    "fingerprintThreshold",
+                    "fallbackText",
+                ],
+                param_types: [
+                    ("selector", ParamType::String),
+                    ("text", ParamType::String),
+                    ("fallbackSelectors", ParamType::Array),
+                    ("fingerprint", ParamType::Object),
+                    ("fingerprintThreshold", ParamType::Number),
+                    ("fallbackText", ParamType::String),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Element.waitFor schema
+        parameter_schemas.insert(
+            "Element.waitFor",
+            CommandSchema {
+                required_params: vec!["selector"],
+                optional_params: vec!["timeout", "visible"],
+                param_types: [
+                    ("selector", ParamType::String),
+                    ("timeout", ParamType::Number),
+                    ("visible", ParamType::Boolean),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Element.scrollTo schema
+        parameter_schemas.insert(
+            "Element.scrollTo",
+            CommandSchema {
+                required_params: vec!["selector"],
+                optional_params: vec![
+                    "fallbackSelectors",
+                    "fingerprint",
+                    "fingerprintThreshold",
+                    "fallbackText",
+                ],
+                param_types: [
+                    ("selector", ParamType::String),
+                    ("fallbackSelectors", ParamType::Array),
+                    ("fingerprint", ParamType::Object),
+                    ("fingerprintThreshold", ParamType::Number),
+                    ("fallbackText", ParamType::String),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Assert.snapshot schema
+        parameter_schemas.insert(
+            "Assert.snapshot",
+            CommandSchema {
+                required_params: vec!["name", "expression"],
+                optional_params: vec!["ignorePaths"],
+                param_types: [
+                    ("name", ParamType::String),
+                    ("expression", ParamType::String),
+                    ("ignorePaths", ParamType::Array),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Assert.text schema
+        parameter_schemas.insert(
+            "Assert.text",
+            CommandSchema {
+                required_params: vec!["text"],
+                optional_params: vec![
+                    "selector",
+                    "exact",
+                    "caseSensitive",
+                    "foldDiacritics",
+                    "fuzzy",
+                ],
+                param_types: [
+                    ("text", ParamType::String),
+                    ("selector", ParamType::String),
+                    ("exact", ParamType::Boolean),
+                    ("caseSensitive", ParamType::Boolean),
+                    ("foldDiacritics", ParamType::Boolean),
+                    ("fuzzy", ParamType::Number),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Verify.goal schema
+        parameter_schemas.insert(
+            "Verify.goal",
+            CommandSchema {
+                required_params: vec!["goal"],
+                optional_params: vec!["mode", "minConfidence", "failOnUnmet", "screenshot"],
+                param_types: [
+                    ("goal", ParamType::String),
+                    ("mode", ParamType::String),
+                    ("minConfidence", ParamType::Number),
+                    ("failOnUnmet", ParamType::Boolean),
+                    ("screenshot", ParamType::String),
+                ]
+                .into_iter()
+                .collect(),
+            },
+        );
+
+        // Touch.pinch schema
+        parameter_schemas.insert(
+            "Touch.pinch",
+            CommandSchema {
+                required_params: vec!["scale"],
+                optional_params: vec![],
+                param_types: [("scale", ParamType::Number)].into_iter().collect(),
+            },
+        );
+
         Self {
             valid_commands,
             parameter_schemas,
         }
     }
 
+    /// Methods the validator accepts
+    pub fn commands(&self) -> &[&'static str] {
+        &self.valid_commands
+    }
+
     /// Validate a CDP script from JSON string
     pub fn validate_json(&self, json: &str) -> ValidationResult {
         let mut result = ValidationResult::success();
 
         // Try to parse JSON
-        let script: crate::cdp::CdpScript = match serde_json::from_str(json) {
+        let script: CdpScript = match serde_json::from_str(json) {
             Ok(s) => s,
             Err(e) => {
                 result.add_error(ValidationError {
@@ -374,7 +759,7 @@ impl CdpValidator {
     }
 
     /// Validate a parsed CDP script
-    pub fn validate_script(&self, script: &crate::cdp::CdpScript, result: &mut ValidationResult) {
+    pub fn validate_script(&self, script: &CdpScript, result: &mut ValidationResult) {
         // Validate script name
         if script.name.is_empty() {
             result.add_error(ValidationError {
@@ -399,6 +784,20 @@ impl CdpValidator {
             );
         }
 
+        if let Err(e) = script.script_version() {
+            result.add_error(ValidationError {
+                error_type: ValidationErrorType::InvalidValue,
+                message: e.to_string(),
+                location: ErrorLocation {
+                    command_index: None,
+                    field_path: "version".to_string(),
+                    line: None,
+                    column: None,
+                },
+                suggestion: Some("Use a MAJOR.MINOR.PATCH version like \"1.0.0\"".to_string()),
+            });
+        }
+
         // Validate script description
         if script.description.is_empty() {
             result.add_warning("Script description is empty".to_string());
@@ -427,12 +826,7 @@ impl CdpValidator {
     }
 
     /// Validate a single CDP command
-    fn validate_command(
-        &self,
-        cmd: &crate::cdp::CdpCommand,
-        index: usize,
-        result: &mut ValidationResult,
-    ) {
+    fn validate_command(&self, cmd: &CdpCommand, index: usize, result: &mut ValidationResult) {
         let field_prefix = format!("cdp_commands[{}]", index);
 
         // Validate method name format
@@ -500,7 +894,7 @@ impl CdpValidator {
     /// Validate command parameters against schema
     fn validate_parameters(
         &self,
-        cmd: &crate::cdp::CdpCommand,
+        cmd: &CdpCommand,
         schema: &CommandSchema,
         index: usize,
         field_prefix: &str,
@@ -597,6 +991,147 @@ impl CdpValidator {
     }
 }
 
+impl CdpValidator {
+    /// Repair recoverable problems in `script`, then validate the result
+    ///
+    /// Repairs are:
+    /// - unknown method names within a small edit distance of exactly one supported
+    ///   command (`Page.navgate`, `page.navigate`)
+    /// - numeric parameters given as strings (`"x": "100"`)
+    /// - `url` parameters without a scheme (`example.com/login`), which get
+    ///   `https://` (`http://` for localhost)
+    pub fn validate_and_fix(&self, script: &CdpScript) -> FixedScript {
+        let mut script = script.clone();
+        let mut changes = Vec::new();
+
+        for (index, cmd) in script.cdp_commands.iter_mut().enumerate() {
+            let field_prefix = format!("cdp_commands[{}]", index);
+            let location = |field: &str| ErrorLocation {
+                command_index: Some(index),
+                field_path: format!("{}.{}", field_prefix, field),
+                line: None,
+                column: None,
+            };
+
+            if let Some(method) = self.closest_command(&cmd.method) {
+                changes.push(ScriptFix {
+                    location: location("method"),
+                    description: format!("Renamed unknown method '{}' to '{}'", cmd.method, method),
+                    before: Value::String(cmd.method.clone()),
+                    after: Value::String(method.to_string()),
+                });
+                cmd.method = method.to_string();
+            }
+
+            let schema = self.parameter_schemas.get(cmd.method.as_str());
+            let Some(params) = cmd.params.as_object_mut() else {
+                continue;
+            };
+            for (name, value) in params.iter_mut() {
+                let Value::String(text) = &*value else {
+                    continue;
+                };
+                let expected = schema.and_then(|schema| schema.param_types.get(name.as_str()));
+                let (fixed, description) = if expected == Some(&ParamType::Number) {
+                    match parse_number(text) {
+                        Some(number) => (number, format!("Converted '{}' to a number", name)),
+                        None => continue,
+                    }
+                } else if name == "url" {
+                    match with_scheme(text) {
+                        Some(url) => (Value::String(url), format!("Added a scheme to '{}'", name)),
+                        None => continue,
+                    }
+                } else {
+                    continue;
+                };
+                changes.push(ScriptFix {
+                    location: location(&format!("params.{}", name)),
+                    description,
+                    before: value.clone(),
+                    after: fixed.clone(),
+                });
+                *value = fixed;
+            }
+        }
+
+        let mut validation = ValidationResult::success();
+        self.validate_script(&script, &mut validation);
+        FixedScript {
+            script,
+            changes,
+            validation,
+        }
+    }
+
+    /// Supported command an unknown `method` was most likely meant to be
+    ///
+    /// None if `method` is supported, or if no command is close enough or several
+    /// are equally close.
+    fn closest_command(&self, method: &str) -> Option<&'static str> {
+        if method.is_empty() || self.valid_commands.contains(&method) {
+            return None;
+        }
+        let max_distance = (method.chars().count() / 5).clamp(1, 3);
+        let method = method.to_lowercase();
+        let mut best: Option<(usize, &'static str)> = None;
+        let mut tied = false;
+        for &candidate in &self.valid_commands {
+            let distance = edit_distance(&method, &candidate.to_lowercase());
+            match best {
+                Some((best_distance, _)) if distance > best_distance => {}
+                Some((best_distance, _)) if distance == best_distance => tied = true,
+                _ => {
+                    best = Some((distance, candidate));
+                    tied = false;
+                }
+            }
+        }
+        best.filter(|&(distance, _)| distance <= max_distance && !tied)
+            .map(|(_, candidate)| candidate)
+    }
+}
+
+/// JSON number written as a string (`"42"`, `" 1.5 "`)
+fn parse_number(text: &str) -> Option<Value> {
+    let text = text.trim();
+    if let Ok(integer) = text.parse::<i64>() {
+        return Some(integer.into());
+    }
+    text.parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map(Value::Number)
+}
+
+/// `url` with a scheme added, if it is a bare host or scheme-relative URL
+fn with_scheme(url: &str) -> Option<String> {
+    if url.contains("://") || url.is_empty() {
+        return None;
+    }
+    if let Some(rest) = url.strip_prefix("//") {
+        return Some(format!("https://{}", rest));
+    }
+    // `about:blank`, `data:...`, `mailto:...` have a scheme; `localhost:8080` has a port
+    if let Some((scheme, rest)) = url.split_once(':') {
+        let is_scheme = scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if is_scheme && !rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+    }
+
+    let host = url.split(['/', '?', '#', ':']).next().unwrap_or_default();
+    if host.eq_ignore_ascii_case("localhost") || host == "127.0.0.1" {
+        Some(format!("http://{}", url))
+    } else if host.contains('.') && !host.starts_with('.') {
+        Some(format!("https://{}", url))
+    } else {
+        None
+    }
+}
+
 impl Default for CdpValidator {
     fn default() -> Self {
         Self::new()
@@ -769,4 +1304,51 @@ mod tests {
         assert!(!result.is_valid);
         assert!(result.errors.len() >= 3, "Should catch multiple errors");
     }
+
+    #[test]
+    fn test_validate_and_fix() {
+        let validator = CdpValidator::new();
+        let script: CdpScript = serde_json::from_str(
+            r#"{
+            "name": "fix-me",
+            "description": "Script with recoverable mistakes",
+            "cdp_commands": [
+                {"method": "Page.navgate", "params": {"url": "example.com/login"}},
+                {"method": "input.dispatchmouseevent",
+                 "params": {"type": "mousePressed", "x": "100", "y": " 20.5 "}},
+                {"method": "Page.navigate", "params": {"url": "localhost:3000"}},
+                {"method": "Page.navigate", "params": {"url": "about:blank"}},
+                {"method": "Frobnicate.everything", "params": {}}
+            ]
+        }"#,
+        )
+        .unwrap();
+
+        let fixed = validator.validate_and_fix(&script);
+        let commands = &fixed.script.cdp_commands;
+        assert_eq!(commands[0].method, "Page.navigate");
+        assert_eq!(commands[0].params["url"], "https://example.com/login");
+        assert_eq!(commands[1].method, "Input.dispatchMouseEvent");
+        assert_eq!(commands[1].params["x"], 100);
+        assert_eq!(commands[1].params["y"], 20.5);
+        assert_eq!(commands[2].params["url"], "http://localhost:3000");
+        assert_eq!(commands[3].params["url"], "about:blank");
+        assert_eq!(commands[4].method, "Frobnicate.everything");
+
+        assert_eq!(fixed.changes.len(), 6);
+        assert_eq!(
+            fixed.changes[0].location.field_path,
+            "cdp_commands[0].method"
+        );
+        assert_eq!(fixed.changes[0].before, "Page.navgate");
+        assert_eq!(fixed.changes[0].after, "Page.navigate");
+
+        // Only the unrecoverable error is left
+        assert!(!fixed.validation.is_valid);
+        assert_eq!(fixed.validation.errors.len(), 1);
+        assert_eq!(
+            fixed.validation.errors[0].error_type,
+            ValidationErrorType::UnknownCommand
+        );
+    }
 }
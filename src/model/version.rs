@@ -0,0 +1,143 @@
+//! Script Versions
+//!
+//! Scripts carry a semantic version (`MAJOR.MINOR.PATCH`) so that changes to them
+//! can be reviewed like code changes: [`ScriptDiff::required_bump`] says which part
+//! a change should bump, and callers that depend on a script (a schedule, say) can
+//! require a compatible version when loading it.
+//!
+//! [`ScriptDiff::required_bump`]: crate::model::diff::ScriptDiff::required_bump
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A parsed script version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ScriptVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+/// Which part of a version a change needs to bump
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionBump {
+    /// Nothing changed
+    None,
+    /// Cosmetic changes (descriptions, tags)
+    Patch,
+    /// Compatible changes (commands added, parameters changed)
+    Minor,
+    /// Breaking changes (commands or saved outputs removed)
+    Major,
+}
+
+impl ScriptVersion {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// The next version after a change of the given size
+    pub fn bump(self, bump: VersionBump) -> Self {
+        match bump {
+            VersionBump::None => self,
+            VersionBump::Patch => Self::new(self.major, self.minor, self.patch + 1),
+            VersionBump::Minor => Self::new(self.major, self.minor + 1, 0),
+            VersionBump::Major => Self::new(self.major + 1, 0, 0),
+        }
+    }
+
+    /// Whether a script at this version can be used by a caller written against
+    /// `required` (caret semantics, like Cargo's `^`)
+    ///
+    /// The major versions must match (for `0.x`, the minor versions too) and this
+    /// version must be at least `required`.
+    pub fn is_compatible_with(&self, required: &ScriptVersion) -> bool {
+        let same_series = if required.major == 0 {
+            self.major == 0 && self.minor == required.minor
+        } else {
+            self.major == required.major
+        };
+        same_series && self >= required
+    }
+
+    /// Whether going from `previous` to this version is at least `bump`
+    pub fn covers(&self, previous: &ScriptVersion, bump: VersionBump) -> bool {
+        // In 0.x, the minor version is the breaking one
+        let bump = match bump {
+            VersionBump::Major if previous.major == 0 => VersionBump::Minor,
+            bump => bump,
+        };
+        *self >= previous.bump(bump)
+    }
+}
+
+impl FromStr for ScriptVersion {
+    type Err = String;
+
+    /// Parse `MAJOR.MINOR.PATCH`; `MAJOR.MINOR` and `MAJOR` are accepted as
+    /// shorthand with the missing parts 0
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix('v').unwrap_or(s);
+        let parts: Vec<&str> = s.split('.').collect();
+        if parts.len() > 3 {
+            return Err(format!(
+                "Invalid version '{}' (expected MAJOR.MINOR.PATCH)",
+                s
+            ));
+        }
+        let mut numbers = [0u64; 3];
+        for (number, part) in numbers.iter_mut().zip(&parts) {
+            *number = part
+                .parse()
+                .map_err(|_| format!("Invalid version '{}' (expected MAJOR.MINOR.PATCH)", s))?;
+        }
+        Ok(Self::new(numbers[0], numbers[1], numbers[2]))
+    }
+}
+
+impl fmt::Display for ScriptVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> ScriptVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(v("1.2.3"), ScriptVersion::new(1, 2, 3));
+        assert_eq!(v("v2.1"), ScriptVersion::new(2, 1, 0));
+        assert_eq!(v("3").to_string(), "3.0.0");
+        assert!("1.2.3.4".parse::<ScriptVersion>().is_err());
+        assert!("1.x".parse::<ScriptVersion>().is_err());
+        assert!("".parse::<ScriptVersion>().is_err());
+    }
+
+    #[test]
+    fn test_version_compatibility() {
+        assert!(v("1.4.2").is_compatible_with(&v("1.2")));
+        assert!(!v("1.1.0").is_compatible_with(&v("1.2")));
+        assert!(!v("2.0.0").is_compatible_with(&v("1.2")));
+        assert!(v("0.3.1").is_compatible_with(&v("0.3")));
+        assert!(!v("0.4.0").is_compatible_with(&v("0.3")));
+
+        assert_eq!(v("1.2.3").bump(VersionBump::Minor), v("1.3.0"));
+        assert!(v("2.0.0").covers(&v("1.2.3"), VersionBump::Major));
+        assert!(!v("1.3.0").covers(&v("1.2.3"), VersionBump::Major));
+        assert!(v("0.3.0").covers(&v("0.2.5"), VersionBump::Major));
+        assert!(v("1.2.4").covers(&v("1.2.3"), VersionBump::Patch));
+    }
+}
@@ -0,0 +1,505 @@
+//! Redaction of Sensitive Regions in Step Frames
+//!
+//! A [`Redaction`] names elements (CSS selectors) and viewport rectangles that must
+//! not appear in saved step frames. Before anything is written to disk:
+//!
+//! - the screenshot is blacked out or blurred over every matched element and region
+//! - text, input values, and descriptive attributes inside matched elements are masked
+//!   in the saved HTML, the VisualDom snapshot, and the interactive element list
+//! - images embedded in the VisualDom that overlap a redacted area are dropped
+//!
+//! Rectangles only affect pixels (screenshots and embedded images); use selectors to
+//! also mask the DOM. Only the top-level document is searched, not iframes.
+
+use crate::error::{BrowserError, Result};
+use crate::step_frame::{encode_image, ScreenshotFormat};
+use crate::visual_dom_delta::SnapshotRecords;
+use crate::ChromeDriver;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Replaces each non-whitespace character of masked text
+const MASK_CHAR: char = '█';
+
+/// Attributes of redacted elements left unmasked (needed to make sense of the layout)
+const KEPT_ATTRIBUTES: &[&str] = &["id", "class", "style", "type", "name", "role"];
+
+/// How redacted screenshot regions are obscured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionStyle {
+    /// Solid black
+    #[default]
+    Blackout,
+    /// Heavy Gaussian blur (keeps the rough look of the page)
+    Blur,
+}
+
+/// A rectangle in CSS pixels, relative to the viewport
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Region {
+    fn intersects(&self, other: &Region) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+/// What to hide in captured step frames
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Redaction {
+    /// Elements to hide (all matches of each selector, and their contents)
+    #[serde(default)]
+    pub selectors: Vec<String>,
+
+    /// Viewport rectangles to hide in screenshots
+    #[serde(default)]
+    pub regions: Vec<Region>,
+
+    /// How screenshot regions are obscured
+    #[serde(default)]
+    pub style: RedactionStyle,
+}
+
+impl Redaction {
+    /// Hide elements matching `selectors`
+    pub fn selectors<I, S>(selectors: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            selectors: selectors.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Also hide `region`
+    pub fn with_region(mut self, region: Region) -> Self {
+        self.regions.push(region);
+        self
+    }
+
+    /// Obscure screenshot regions with `style`
+    pub fn with_style(mut self, style: RedactionStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Locate the redacted elements on the current page
+    pub(crate) async fn resolve(&self, driver: &ChromeDriver) -> Result<ResolvedRedaction> {
+        let mut resolved = ResolvedRedaction {
+            selectors: self.selectors.clone(),
+            regions: self.regions.clone(),
+            scale: 1.0,
+            style: self.style,
+            backend_node_ids: HashSet::new(),
+        };
+        let selectors = serde_json::to_string(&self.selectors).unwrap_or_default();
+
+        let layout: ElementLayout = driver
+            .execute_script_typed(&format!("({})({})", LOCATE_SCRIPT, selectors))
+            .await
+            .map_err(|e| {
+                BrowserError::Other(format!("Failed to locate redacted elements: {}", e))
+            })?;
+        resolved.regions.extend(layout.rects);
+        resolved.scale = layout.scale;
+
+        let page = driver.current_page().await?;
+        for selector in &self.selectors {
+            let elements = page.find_elements(selector.as_str()).await.map_err(|e| {
+                BrowserError::Other(format!("Failed to find redacted elements: {}", e))
+            })?;
+            resolved
+                .backend_node_ids
+                .extend(elements.iter().map(|el| *el.backend_node_id.inner()));
+        }
+
+        Ok(resolved)
+    }
+}
+
+#[derive(Deserialize)]
+struct ElementLayout {
+    rects: Vec<Region>,
+    scale: f64,
+}
+
+/// A [`Redaction`] located on the page at capture time
+#[derive(Debug, Clone)]
+pub(crate) struct ResolvedRedaction {
+    selectors: Vec<String>,
+    /// Matched element boxes plus the configured regions, in CSS pixels
+    regions: Vec<Region>,
+    /// Device pixels per CSS pixel
+    scale: f64,
+    style: RedactionStyle,
+    /// Matched elements (descendants are redacted too)
+    backend_node_ids: HashSet<i64>,
+}
+
+impl ResolvedRedaction {
+    /// Selectors whose matches have their text masked
+    pub(crate) fn selectors(&self) -> &[String] {
+        &self.selectors
+    }
+
+    /// Obscure the redacted regions of an encoded screenshot, re-encoding as `format`
+    pub(crate) fn redact_screenshot(
+        &self,
+        data: &[u8],
+        format: ScreenshotFormat,
+    ) -> image::ImageResult<Vec<u8>> {
+        let mut image = image::load_from_memory(data)?.to_rgba8();
+        redact_pixels(&mut image, &self.regions, self.scale, self.style);
+        encode_image(&image::DynamicImage::ImageRgba8(image), format)
+    }
+
+    /// Mask redacted nodes' strings and drop overlapping images in a VisualDom snapshot
+    pub(crate) fn redact_visual_dom(&self, snapshot: &mut Value) {
+        let mut strings = StringMasker {
+            strings: std::mem::take(&mut snapshot["strings"]),
+            masked: HashMap::new(),
+        };
+
+        if let Some(documents) = snapshot.get_mut("documents").and_then(Value::as_array_mut) {
+            for doc in documents {
+                let redacted = self.redacted_nodes(&doc["nodes"]);
+                if !redacted.iter().any(|r| *r) {
+                    continue;
+                }
+                redact_document(doc, &redacted, &mut strings);
+            }
+        }
+        let masked_any = !strings.masked.is_empty();
+        snapshot["strings"] = strings.strings;
+
+        // Masked copies were appended; rebuild the table so the originals are dropped
+        // (they may still be referenced elsewhere, e.g. a tag name equal to masked text)
+        if masked_any {
+            *snapshot = SnapshotRecords::from_snapshot(snapshot).to_snapshot();
+        }
+
+        if let Some(images) = snapshot.get_mut("images").and_then(Value::as_array_mut) {
+            for image in images {
+                let bounds = Region {
+                    x: image["x"].as_f64().unwrap_or_default(),
+                    y: image["y"].as_f64().unwrap_or_default(),
+                    width: image["displayWidth"].as_f64().unwrap_or_default(),
+                    height: image["displayHeight"].as_f64().unwrap_or_default(),
+                };
+                if self.regions.iter().any(|r| r.intersects(&bounds)) {
+                    image["data"] = Value::Null;
+                    image["redacted"] = Value::Bool(true);
+                }
+            }
+        }
+    }
+
+    /// Per node: whether it or an ancestor was matched
+    fn redacted_nodes(&self, nodes: &Value) -> Vec<bool> {
+        let ids = nodes["backendNodeId"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let parents = nodes["parentIndex"].as_array();
+        let mut redacted = Vec::with_capacity(ids.len());
+        for (i, id) in ids.iter().enumerate() {
+            // Parents always precede their children in the snapshot
+            let parent = parents
+                .and_then(|p| p.get(i))
+                .and_then(|p| p.as_i64())
+                .and_then(|p| usize::try_from(p).ok())
+                .and_then(|p| redacted.get(p).copied())
+                .unwrap_or(false);
+            let matched = id
+                .as_i64()
+                .is_some_and(|id| self.backend_node_ids.contains(&id));
+            redacted.push(parent || matched);
+        }
+        redacted
+    }
+}
+
+/// Appends masked copies of strings to a snapshot's string table
+struct StringMasker {
+    strings: Value,
+    /// Original index -> masked index
+    masked: HashMap<i64, i64>,
+}
+
+impl StringMasker {
+    fn get(&self, index: &Value) -> Option<&str> {
+        let index = usize::try_from(index.as_i64()?).ok()?;
+        self.strings.get(index)?.as_str()
+    }
+
+    /// Index of the masked version of the string at `index`
+    fn mask(&mut self, index: &Value) -> Value {
+        let Some(original) = index.as_i64().filter(|i| *i >= 0) else {
+            return index.clone();
+        };
+        if let Some(masked) = self.masked.get(&original) {
+            return Value::from(*masked);
+        }
+        let Some(text) = self.get(index).map(mask_text) else {
+            return index.clone();
+        };
+        let Some(strings) = self.strings.as_array_mut() else {
+            return index.clone();
+        };
+        let masked = strings.len() as i64;
+        strings.push(Value::String(text));
+        self.masked.insert(original, masked);
+        Value::from(masked)
+    }
+}
+
+fn redact_document(doc: &mut Value, redacted: &[bool], strings: &mut StringMasker) {
+    let is_redacted = |index: &Value| {
+        index
+            .as_i64()
+            .and_then(|i| usize::try_from(i).ok())
+            .and_then(|i| redacted.get(i).copied())
+            .unwrap_or(false)
+    };
+
+    let Some(nodes) = doc.get_mut("nodes") else {
+        return;
+    };
+    if let Some(values) = nodes.get_mut("nodeValue").and_then(Value::as_array_mut) {
+        for (value, _) in values.iter_mut().zip(redacted).filter(|(_, r)| **r) {
+            *value = strings.mask(value);
+        }
+    }
+    for rare in ["textValue", "inputValue"] {
+        let indexes = nodes[rare]["index"].as_array().cloned().unwrap_or_default();
+        if let Some(values) = nodes
+            .get_mut(rare)
+            .and_then(|r| r.get_mut("value"))
+            .and_then(Value::as_array_mut)
+        {
+            for (value, node) in values.iter_mut().zip(&indexes) {
+                if is_redacted(node) {
+                    *value = strings.mask(value);
+                }
+            }
+        }
+    }
+    if let Some(attributes) = nodes.get_mut("attributes").and_then(Value::as_array_mut) {
+        for (attrs, _) in attributes.iter_mut().zip(redacted).filter(|(_, r)| **r) {
+            let Some(pairs) = attrs.as_array_mut() else {
+                continue;
+            };
+            for pair in pairs.chunks_mut(2) {
+                if let [name, value] = pair {
+                    let kept = strings
+                        .get(name)
+                        .is_some_and(|name| KEPT_ATTRIBUTES.contains(&name));
+                    if !kept {
+                        *value = strings.mask(value);
+                    }
+                }
+            }
+        }
+    }
+
+    let node_index = doc["layout"]["nodeIndex"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    if let Some(texts) = doc
+        .get_mut("layout")
+        .and_then(|l| l.get_mut("text"))
+        .and_then(Value::as_array_mut)
+    {
+        for (text, node) in texts.iter_mut().zip(&node_index) {
+            if is_redacted(node) {
+                *text = strings.mask(text);
+            }
+        }
+    }
+}
+
+/// Replace every non-whitespace character with [`MASK_CHAR`]
+pub(crate) fn mask_text(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_whitespace() { c } else { MASK_CHAR })
+        .collect()
+}
+
+/// Obscure `regions` (CSS pixels, `scale` device pixels each) of `image`
+fn redact_pixels(
+    image: &mut image::RgbaImage,
+    regions: &[Region],
+    scale: f64,
+    style: RedactionStyle,
+) {
+    let (width, height) = image.dimensions();
+    for region in regions {
+        let clamp = |v: f64, max: u32| (v.max(0.0) as u32).min(max);
+        let x0 = clamp((region.x * scale).floor(), width);
+        let y0 = clamp((region.y * scale).floor(), height);
+        let x1 = clamp(((region.x + region.width) * scale).ceil(), width);
+        let y1 = clamp(((region.y + region.height) * scale).ceil(), height);
+        if x1 <= x0 || y1 <= y0 {
+            continue;
+        }
+
+        match style {
+            RedactionStyle::Blackout => {
+                for y in y0..y1 {
+                    for x in x0..x1 {
+                        image.put_pixel(x, y, image::Rgba([0, 0, 0, 255]));
+                    }
+                }
+            }
+            RedactionStyle::Blur => {
+                let area = image::imageops::crop_imm(image, x0, y0, x1 - x0, y1 - y0).to_image();
+                // Strong enough that text of any size in the region is unreadable
+                let sigma = ((x1 - x0).min(y1 - y0) as f32 / 4.0).max(8.0);
+                let blurred = image::imageops::blur(&area, sigma);
+                image::imageops::replace(image, &blurred, x0 as i64, y0 as i64);
+            }
+        }
+    }
+}
+
+/// Returns `{rects, scale}`: viewport boxes of all visible matches and the device pixel ratio
+const LOCATE_SCRIPT: &str = r#"(selectors) => {
+    const rects = [];
+    for (const selector of selectors) {
+        for (const el of document.querySelectorAll(selector)) {
+            const r = el.getBoundingClientRect();
+            if (r.width > 0 && r.height > 0) {
+                rects.push({ x: r.x, y: r.y, width: r.width, height: r.height });
+            }
+        }
+    }
+    return { rects, scale: window.devicePixelRatio || 1 };
+}"#;
+
+/// Masks matched elements' text and values in a detached copy of the document
+pub(crate) const MASK_CLONE_SCRIPT: &str = r#"(root, selectors) => {
+    const kept = new Set(['id', 'class', 'style', 'type', 'name', 'role']);
+    const mask = (s) => s.replace(/\S/g, '█');
+    for (const selector of selectors) {
+        for (const el of root.querySelectorAll(selector)) {
+            const walker = document.createTreeWalker(el, NodeFilter.SHOW_TEXT);
+            for (let node = walker.nextNode(); node; node = walker.nextNode()) {
+                node.nodeValue = mask(node.nodeValue);
+            }
+            for (const child of [el, ...el.querySelectorAll('*')]) {
+                for (const attr of Array.from(child.attributes)) {
+                    if (!kept.has(attr.name)) child.setAttribute(attr.name, mask(attr.value));
+                }
+            }
+        }
+    }
+}"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn resolved(ids: &[i64], regions: Vec<Region>) -> ResolvedRedaction {
+        ResolvedRedaction {
+            selectors: vec![".secret".to_string()],
+            regions,
+            scale: 2.0,
+            style: RedactionStyle::Blackout,
+            backend_node_ids: ids.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn test_mask_text() {
+        assert_eq!(mask_text("Jane Doe"), "████ ███");
+        assert_eq!(mask_text(" \n"), " \n");
+    }
+
+    #[test]
+    fn test_redact_pixels() {
+        let mut image = image::RgbaImage::new(10, 10);
+        for pixel in [(0, 0), (2, 2), (3, 3), (9, 9)] {
+            image.put_pixel(pixel.0, pixel.1, image::Rgba([255, 255, 255, 255]));
+        }
+        let region = Region {
+            x: 1.0,
+            y: 1.0,
+            width: 1.0,
+            height: 1.0,
+        };
+
+        // One CSS pixel at 2x covers device pixels 2..4
+        redact_pixels(&mut image, &[region], 2.0, RedactionStyle::Blackout);
+        assert_eq!(image.get_pixel(2, 2), &image::Rgba([0, 0, 0, 255]));
+        assert_eq!(image.get_pixel(3, 3), &image::Rgba([0, 0, 0, 255]));
+        assert_eq!(image.get_pixel(0, 0), &image::Rgba([255, 255, 255, 255]));
+        assert_eq!(image.get_pixel(9, 9), &image::Rgba([255, 255, 255, 255]));
+
+        // Regions outside the image are ignored
+        let outside = Region { x: 50.0, ..region };
+        redact_pixels(&mut image, &[outside], 2.0, RedactionStyle::Blur);
+    }
+
+    #[test]
+    fn test_redact_visual_dom() {
+        // div.secret (id 11) > text (id 12); p (id 13) > text (id 14)
+        let mut snapshot = json!({
+            "documents": [{
+                "nodes": {
+                    "parentIndex": [-1, 0, 1, 0, 3],
+                    "backendNodeId": [10, 11, 12, 13, 14],
+                    "nodeValue": [-1, -1, 0, -1, 1],
+                    "attributes": [[], [2, 3, 4, 5], [], [], []],
+                    "inputValue": { "index": [1], "value": [0] },
+                },
+                "layout": { "nodeIndex": [2, 4], "text": [0, 1] },
+            }],
+            "strings": ["Jane Doe", "Public", "class", "secret", "title", "Jane"],
+            "images": [
+                { "x": 0.0, "y": 0.0, "displayWidth": 10.0, "displayHeight": 10.0, "data": "AAAA" },
+                { "x": 500.0, "y": 0.0, "displayWidth": 10.0, "displayHeight": 10.0, "data": "BBBB" },
+            ],
+        });
+        let region = Region {
+            x: 0.0,
+            y: 0.0,
+            width: 100.0,
+            height: 20.0,
+        };
+        resolved(&[11], vec![region]).redact_visual_dom(&mut snapshot);
+
+        let strings = snapshot["strings"].as_array().unwrap();
+        assert!(!strings.contains(&json!("Jane Doe")));
+        assert!(!strings.contains(&json!("Jane")));
+        let text = |index: &Value| strings[index.as_u64().unwrap() as usize].clone();
+        let doc = &snapshot["documents"][0];
+        assert_eq!(text(&doc["nodes"]["nodeValue"][2]), "████ ███");
+        assert_eq!(text(&doc["nodes"]["nodeValue"][4]), "Public");
+        assert_eq!(text(&doc["nodes"]["inputValue"]["value"][0]), "████ ███");
+        assert_eq!(text(&doc["layout"]["text"][0]), "████ ███");
+        assert_eq!(text(&doc["layout"]["text"][1]), "Public");
+        // class kept, title masked
+        assert_eq!(text(&doc["nodes"]["attributes"][1][1]), "secret");
+        assert_eq!(text(&doc["nodes"]["attributes"][1][3]), "████");
+
+        assert!(doc["nodes"].get("textValue").is_none());
+
+        assert!(snapshot["images"][0]["data"].is_null());
+        assert_eq!(snapshot["images"][0]["redacted"], json!(true));
+        assert_eq!(snapshot["images"][1]["data"], json!("BBBB"));
+    }
+}
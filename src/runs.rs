@@ -0,0 +1,515 @@
+//! Run History
+//!
+//! The server keeps a record of every finished inference, job, batch item, and
+//! scheduled run so clients can look back at what happened after the HTTP response
+//! (or webhook) is gone. Each [`RunRecord`] holds the outcome, the full
+//! [`ExecutionReport`](crate::cdp::ExecutionReport) as JSON, and the paths of the
+//! artifacts the run wrote.
+//!
+//! Records are written to `<artifacts.root>/runs/<id>.json` and loaded back when the
+//! server starts, so history survives restarts. Only the latest `max_runs` are kept;
+//! older records are dropped from memory and deleted from disk. [`RunQuery`] filters
+//! them by script name, status, source, and finish time.
+
+use crate::cdp::GenerationUsage;
+use crate::webhook::{RunNotification, RunSource, WebhookEvent};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use utoipa::{IntoParams, ToSchema};
+
+/// Runs returned by a query when no `limit` is given
+const DEFAULT_LIMIT: usize = 100;
+
+/// Finished runs a store keeps unless told otherwise
+pub const DEFAULT_MAX_RUNS: usize = 1000;
+
+/// Stored record of a finished run
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RunRecord {
+    /// Run identifier (the job or scheduled run id when there is one)
+    pub id: String,
+
+    /// What kind of run this was
+    pub source: RunSource,
+
+    /// Outcome of the run
+    pub status: WebhookEvent,
+
+    /// Script name (None if generation failed before a script existed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+
+    /// ISO 8601 timestamp when the run finished
+    pub finished: String,
+
+    /// Number of commands that succeeded
+    #[serde(default)]
+    pub successful: usize,
+
+    /// Number of commands that failed
+    #[serde(default)]
+    pub failed: usize,
+
+    /// Error that prevented the script from running or completing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Files written by the run (screenshots, extracted data, reports)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<String>,
+
+    /// Execution report, as serialized by the executor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<serde_json::Value>,
+
+    /// Differences from the previous run (scheduled runs only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<serde_json::Value>,
+
+    /// Tokens and cost of generating the script (None if it wasn't generated)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<GenerationUsage>,
+}
+
+impl RunRecord {
+    /// Record for a finished run described by `notification`, stored under `id`
+    pub fn from_notification(id: impl Into<String>, notification: &RunNotification) -> Self {
+        let (successful, failed) = notification
+            .report
+            .as_ref()
+            .map(|r| (r.successful, r.failed))
+            .unwrap_or_default();
+        Self {
+            id: id.into(),
+            source: notification.source,
+            status: notification.event,
+            script: notification.script.clone(),
+            finished: notification.timestamp.clone(),
+            successful,
+            failed,
+            error: notification.error.clone(),
+            artifacts: notification.artifacts.clone(),
+            report: notification
+                .report
+                .as_ref()
+                .and_then(|r| serde_json::to_value(r).ok()),
+            changes: notification
+                .changes
+                .as_ref()
+                .and_then(|c| serde_json::to_value(c).ok()),
+            usage: notification.usage,
+        }
+    }
+
+    /// The record without its report and change details, for listings
+    pub fn summary(&self) -> RunRecord {
+        RunRecord {
+            report: None,
+            changes: None,
+            ..self.clone()
+        }
+    }
+
+    fn finished_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.finished)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    }
+}
+
+/// Query string of `GET /runs`
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RunQuery {
+    /// Only runs of this script
+    pub script: Option<String>,
+
+    /// Only runs with this outcome
+    pub status: Option<WebhookEvent>,
+
+    /// Only runs of this kind
+    pub source: Option<RunSource>,
+
+    /// Only runs that finished at or after this time (RFC 3339 or `YYYY-MM-DD`)
+    pub since: Option<String>,
+
+    /// Only runs that finished before this time (RFC 3339 or `YYYY-MM-DD`; a date
+    /// includes the whole day)
+    pub until: Option<String>,
+
+    /// Maximum number of runs to return (default 100)
+    pub limit: Option<usize>,
+}
+
+/// Parsed [`RunQuery`]
+#[derive(Debug, Clone)]
+pub struct RunFilter {
+    script: Option<String>,
+    status: Option<WebhookEvent>,
+    source: Option<RunSource>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    limit: usize,
+}
+
+impl Default for RunFilter {
+    fn default() -> Self {
+        Self {
+            script: None,
+            status: None,
+            source: None,
+            since: None,
+            until: None,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl RunFilter {
+    /// Parse the query's dates
+    pub fn from_query(query: RunQuery) -> anyhow::Result<Self> {
+        Ok(Self {
+            since: query
+                .since
+                .as_deref()
+                .map(|s| parse_time(s, false))
+                .transpose()?,
+            until: query
+                .until
+                .as_deref()
+                .map(|s| parse_time(s, true))
+                .transpose()?,
+            script: query.script,
+            status: query.status,
+            source: query.source,
+            limit: query.limit.unwrap_or(DEFAULT_LIMIT),
+        })
+    }
+
+    /// Whether `record` passes the filter
+    pub fn matches(&self, record: &RunRecord) -> bool {
+        if self.script.is_some() && record.script != self.script {
+            return false;
+        }
+        if self.status.is_some_and(|s| s != record.status) {
+            return false;
+        }
+        if self.source.is_some_and(|s| s != record.source) {
+            return false;
+        }
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        let Some(finished) = record.finished_at() else {
+            return false;
+        };
+        self.since.is_none_or(|since| finished >= since)
+            && self.until.is_none_or(|until| finished < until)
+    }
+}
+
+/// Parse an RFC 3339 timestamp or a `YYYY-MM-DD` date (midnight UTC; the following
+/// midnight if `end_of_day`)
+fn parse_time(value: &str, end_of_day: bool) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        anyhow::anyhow!("Invalid time '{}': expected RFC 3339 or YYYY-MM-DD", value)
+    })?;
+    let date = if end_of_day {
+        date.succ_opt().unwrap_or(date)
+    } else {
+        date
+    };
+    Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+/// Finished runs, kept in memory and persisted as one JSON file per run
+pub struct RunStore {
+    dir: Option<PathBuf>,
+    runs: RwLock<Vec<RunRecord>>,
+    counter: AtomicU64,
+    max_runs: usize,
+}
+
+impl RunStore {
+    /// Store that only keeps runs in memory
+    pub fn in_memory() -> Self {
+        Self {
+            dir: None,
+            runs: RwLock::new(Vec::new()),
+            counter: AtomicU64::new(0),
+            max_runs: DEFAULT_MAX_RUNS,
+        }
+    }
+
+    /// Store persisted in `dir`, loading the runs already recorded there and keeping
+    /// up to [`DEFAULT_MAX_RUNS`]
+    ///
+    /// Files that cannot be parsed are skipped with a warning.
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let mut runs = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| Ok(serde_json::from_str::<RunRecord>(&json)?));
+            match parsed {
+                Ok(record) => runs.push(record),
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "Skipping unreadable run record")
+                }
+            }
+        }
+        runs.sort_by(|a, b| a.finished.cmp(&b.finished));
+        tracing::debug!(dir = %dir.display(), runs = runs.len(), "Loaded run history");
+
+        Ok(Self {
+            dir: Some(dir.to_path_buf()),
+            runs: RwLock::new(runs),
+            counter: AtomicU64::new(0),
+            max_runs: DEFAULT_MAX_RUNS,
+        })
+    }
+
+    /// Keep only the `max_runs` most recently finished runs, dropping any beyond that
+    /// now and as new ones are recorded
+    pub fn with_max_runs(mut self, max_runs: usize) -> Self {
+        self.max_runs = max_runs.max(1);
+        let runs = self.runs.get_mut().unwrap();
+        let dropped = Self::evict(runs, self.max_runs);
+        self.delete_records(&dropped);
+        self
+    }
+
+    /// Record a finished run and return the notification with its run id set
+    ///
+    /// Runs without an id (inferences, batch items) get a new one. Persisting is
+    /// best effort: a failed write is logged and the run is still kept in memory.
+    pub fn record(&self, notification: RunNotification) -> RunNotification {
+        let id = notification.id.clone().unwrap_or_else(|| self.next_id());
+        let record = RunRecord::from_notification(&id, &notification);
+
+        if let Some(dir) = &self.dir {
+            let path = dir.join(format!("{}.json", id));
+            let written = serde_json::to_string_pretty(&record)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| Ok(std::fs::write(&path, json)?));
+            if let Err(e) = written {
+                tracing::warn!(path = %path.display(), error = %e, "Failed to persist run record");
+            }
+        }
+
+        let dropped = {
+            let mut runs = self.runs.write().unwrap();
+            runs.retain(|r| r.id != id);
+            runs.push(record);
+            Self::evict(&mut runs, self.max_runs)
+        };
+        self.delete_records(&dropped);
+        notification.with_id(id)
+    }
+
+    /// Remove the oldest runs beyond `max_runs`, returning their ids
+    fn evict(runs: &mut Vec<RunRecord>, max_runs: usize) -> Vec<String> {
+        let excess = runs.len().saturating_sub(max_runs);
+        runs.drain(..excess).map(|r| r.id).collect()
+    }
+
+    /// Delete the persisted records of dropped runs
+    fn delete_records(&self, ids: &[String]) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        for id in ids {
+            tracing::debug!(run = %id, "Dropping run record");
+            let path = dir.join(format!("{}.json", id));
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!(path = %path.display(), error = %e, "Failed to delete run record");
+            }
+        }
+    }
+
+    /// Get a run by id
+    pub fn get(&self, id: &str) -> Option<RunRecord> {
+        self.runs
+            .read()
+            .unwrap()
+            .iter()
+            .find(|r| r.id == id)
+            .cloned()
+    }
+
+    /// Summaries of the runs passing `filter`, most recently finished first
+    pub fn list(&self, filter: &RunFilter) -> Vec<RunRecord> {
+        self.runs
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|r| filter.matches(r))
+            .take(filter.limit)
+            .map(RunRecord::summary)
+            .collect()
+    }
+
+    /// A fresh run id, for runs that need one (e.g. to name their artifact
+    /// directory) before they finish
+    pub fn next_id(&self) -> String {
+        let seq = self.counter.fetch_add(1, Ordering::SeqCst);
+        let unique_id = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        format!("run-{}-{}", unique_id, seq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cdp::ExecutionReport;
+
+    fn notification(script: &str, event: WebhookEvent, timestamp: &str) -> RunNotification {
+        let mut notification = RunNotification::from_report(
+            RunSource::Inference,
+            ExecutionReport::new(script.to_string(), 0),
+        );
+        notification.event = event;
+        notification.timestamp = timestamp.to_string();
+        notification
+    }
+
+    fn query(f: impl FnOnce(&mut RunQuery)) -> RunFilter {
+        let mut query = RunQuery::default();
+        f(&mut query);
+        RunFilter::from_query(query).unwrap()
+    }
+
+    #[test]
+    fn test_filter_runs() {
+        let store = RunStore::in_memory();
+        let first = store.record(notification(
+            "login",
+            WebhookEvent::Completed,
+            "2026-03-01T10:00:00+00:00",
+        ));
+        store.record(notification(
+            "login",
+            WebhookEvent::Failed,
+            "2026-03-02T10:00:00+00:00",
+        ));
+        store.record(notification(
+            "search",
+            WebhookEvent::Completed,
+            "2026-03-03T10:00:00+00:00",
+        ));
+
+        let scripts = |runs: Vec<RunRecord>| -> Vec<(String, String)> {
+            runs.into_iter()
+                .map(|r| (r.script.unwrap(), r.finished[..10].to_string()))
+                .collect()
+        };
+
+        // Newest first
+        let all = store.list(&RunFilter::default());
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].script.as_deref(), Some("search"));
+        assert!(all.iter().all(|r| r.report.is_none()));
+
+        assert_eq!(
+            scripts(store.list(&query(|q| q.script = Some("login".into())))),
+            vec![
+                ("login".to_string(), "2026-03-02".to_string()),
+                ("login".to_string(), "2026-03-01".to_string())
+            ]
+        );
+        assert_eq!(
+            scripts(store.list(&query(|q| q.status = Some(WebhookEvent::Completed)))),
+            vec![
+                ("search".to_string(), "2026-03-03".to_string()),
+                ("login".to_string(), "2026-03-01".to_string())
+            ]
+        );
+        // A date `until` includes the whole day
+        assert_eq!(
+            scripts(store.list(&query(|q| {
+                q.since = Some("2026-03-02".into());
+                q.until = Some("2026-03-02".into());
+            }))),
+            vec![("login".to_string(), "2026-03-02".to_string())]
+        );
+        assert_eq!(store.list(&query(|q| q.limit = Some(1))).len(), 1);
+        assert!(store
+            .list(&query(|q| q.source = Some(RunSource::Job)))
+            .is_empty());
+
+        let id = first.id.unwrap();
+        let record = store.get(&id).unwrap();
+        assert_eq!(record.status, WebhookEvent::Completed);
+        assert!(record.report.is_some());
+
+        assert!(RunFilter::from_query(RunQuery {
+            since: Some("yesterday".into()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_runs_persist() {
+        let dir = std::env::temp_dir().join(format!("robert-runs-{}", std::process::id()));
+        let store = RunStore::open(&dir).unwrap();
+        let recorded = store.record(
+            RunNotification::failed(RunSource::Job, "Generation failed: timeout").with_id("job-1"),
+        );
+        assert_eq!(recorded.id.as_deref(), Some("job-1"));
+        assert!(dir.join("job-1.json").exists());
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+
+        let reopened = RunStore::open(&dir).unwrap();
+        let record = reopened.get("job-1").unwrap();
+        assert_eq!(record.source, RunSource::Job);
+        assert_eq!(record.status, WebhookEvent::Failed);
+        assert_eq!(record.error.as_deref(), Some("Generation failed: timeout"));
+        assert_eq!(reopened.list(&RunFilter::default()).len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_max_runs() {
+        let dir = std::env::temp_dir().join(format!("robert-runs-max-{}", std::process::id()));
+        let store = RunStore::open(&dir).unwrap();
+        for (id, day) in [("run-a", 1), ("run-b", 2), ("run-c", 3)] {
+            let mut run = RunNotification::failed(RunSource::Batch, "failed").with_id(id);
+            run.timestamp = format!("2026-03-0{}T10:00:00+00:00", day);
+            store.record(run);
+        }
+
+        // Reopening with a lower limit drops the oldest, on disk too
+        let store = RunStore::open(&dir).unwrap().with_max_runs(2);
+        assert!(store.get("run-a").is_none());
+        assert!(!dir.join("run-a.json").exists());
+        assert!(dir.join("run-b.json").exists());
+
+        store.record(RunNotification::failed(RunSource::Batch, "failed").with_id("run-d"));
+        let ids: Vec<String> = store
+            .list(&RunFilter::default())
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, vec!["run-d", "run-c"]);
+        assert!(!dir.join("run-b.json").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
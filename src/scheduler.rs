@@ -0,0 +1,820 @@
+//! Scheduled Script Runs
+//!
+//! Runs named scripts from the [`ScriptLibrary`] on cron-style schedules declared in
+//! the config (`[[schedules]]`). Each run launches its own Chrome session, writes its
+//! `save_as` artifacts and `report.json` under
+//! `<artifacts.root>/schedules/<schedule>/<run id>/`, and is recorded in a bounded
+//! in-memory history served by the daemon's `/schedules` endpoints. Finished runs are
+//! also reported to the configured webhooks.
+//!
+//! Unless `[changes]` detection is disabled, each run's artifacts are compared with the
+//! previous run of the same schedule and the resulting [`ChangeReport`] is written to
+//! `changes.json`, attached to the run, and included in its webhook notification.
+//!
+//! Schedules are evaluated in UTC.
+
+use crate::browser::chrome::ChromeDriver;
+use crate::cdp::{CdpExecutor, ExecutionReport};
+use crate::changes::{self, ChangeReport};
+use crate::config::Config;
+use crate::library::ScriptLibrary;
+use crate::model::version::ScriptVersion;
+use crate::runs::RunStore;
+use crate::webhook::{Notifier, RunNotification, RunSource};
+use anyhow::Context;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeDelta, Timelike, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+/// Number of runs kept in the history of each schedule
+const MAX_HISTORY: usize = 50;
+
+/// How far ahead to search for the next matching time before giving up
+const MAX_LOOKAHEAD_DAYS: i64 = 5 * 366;
+
+/// Parsed cron expression
+///
+/// Standard five fields (`minute hour day-of-month month day-of-week`) supporting
+/// `*`, `*/n`, `a-b`, `a-b/n`, and comma-separated lists, plus the `@hourly`,
+/// `@daily`, `@weekly`, and `@monthly` shorthands. Day-of-week accepts 0-7 with both
+/// 0 and 7 meaning Sunday. As in classic cron, when both day fields are restricted a
+/// time matches if either one does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let source = s.trim();
+        let expanded = match source {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(format!(
+                "expected 5 fields (minute hour day-of-month month day-of-week), got {}",
+                fields.len()
+            ));
+        };
+
+        let mut days_of_week = parse_field(day_of_week, 0, 7, "day-of-week")?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days_of_month: parse_field(day_of_month, 1, 31, "day-of-month")?,
+            months: parse_field(month, 1, 12, "month")?,
+            days_of_week,
+            day_of_month_restricted: !day_of_month.starts_with('*'),
+            day_of_week_restricted: !day_of_week.starts_with('*'),
+        })
+    }
+}
+
+impl std::fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Parse one cron field into a bitmask of allowed values
+fn parse_field(spec: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let parse_value = |v: &str| -> Result<u32, String> {
+        let value: u32 = v
+            .parse()
+            .map_err(|_| format!("invalid {} value '{}'", name, v))?;
+        if value < min || value > max {
+            return Err(format!(
+                "{} value {} out of range {}-{}",
+                name, value, min, max
+            ));
+        }
+        Ok(value)
+    };
+
+    let mut mask = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid {} step '{}'", name, step))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (parse_value(a)?, parse_value(b)?),
+                // "a/n" means every n starting at a
+                None if step > 1 => (parse_value(range)?, max),
+                None => {
+                    let value = parse_value(range)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("invalid {} range '{}'", name, range));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn has(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+impl CronSchedule {
+    /// First matching minute strictly after `after`, or None if nothing matches
+    /// within the next five years (e.g. `0 0 31 2 *`)
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.naive_utc().with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        let limit = start + TimeDelta::days(MAX_LOOKAHEAD_DAYS);
+
+        let mut t = start;
+        while t <= limit {
+            if !has(self.months, t.month()) {
+                let (year, month) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = midnight(NaiveDate::from_ymd_opt(year, month, 1)?)?;
+            } else if !self.day_matches(t.date()) {
+                t = midnight(t.date().succ_opt()?)?;
+            } else if !has(self.hours, t.hour()) {
+                t = t.date().and_hms_opt(t.hour(), 0, 0)? + TimeDelta::hours(1);
+            } else if !has(self.minutes, t.minute()) {
+                t += TimeDelta::minutes(1);
+            } else {
+                return Some(t.and_utc());
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let dom = has(self.days_of_month, date.day());
+        let dow = has(self.days_of_week, date.weekday().num_days_from_sunday());
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => dom || dow,
+            (true, false) => dom,
+            (false, true) => dow,
+            (false, false) => true,
+        }
+    }
+}
+
+fn midnight(date: NaiveDate) -> Option<NaiveDateTime> {
+    date.and_hms_opt(0, 0, 0)
+}
+
+/// State of a scheduled run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    /// Still executing
+    Running,
+    /// Every command succeeded
+    Completed,
+    /// The script could not be run or a command failed
+    Failed,
+    /// Stopped by daemon shutdown
+    Cancelled,
+}
+
+/// Record of one execution of a schedule
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScheduledRun {
+    /// Unique run identifier (also the artifact directory name)
+    pub id: String,
+
+    /// Schedule that triggered the run
+    pub schedule: String,
+
+    /// Library script that was executed
+    pub script: String,
+
+    /// Current status
+    pub status: RunStatus,
+
+    /// ISO 8601 timestamp when the run started
+    pub started: String,
+
+    /// ISO 8601 timestamp when the run finished
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished: Option<String>,
+
+    /// Number of commands that succeeded
+    pub successful: usize,
+
+    /// Number of commands that failed
+    pub failed: usize,
+
+    /// Directory holding the run's artifacts and `report.json`
+    #[schema(value_type = String)]
+    pub output_dir: PathBuf,
+
+    /// Error that prevented the script from running or completing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Differences from the previous run's artifacts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<ChangeReport>,
+}
+
+/// Summary of a schedule as reported to clients
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScheduleInfo {
+    /// Schedule name
+    pub name: String,
+
+    /// Library script the schedule runs
+    pub script: String,
+
+    /// Cron expression
+    pub cron: String,
+
+    /// ISO 8601 timestamp of the next run (None if the expression never matches again)
+    pub next_run: Option<String>,
+
+    /// Whether a run is in progress
+    pub running: bool,
+
+    /// Most recent run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<ScheduledRun>,
+}
+
+struct Schedule {
+    name: String,
+    script: String,
+    script_version: Option<ScriptVersion>,
+    cron: CronSchedule,
+}
+
+#[derive(Default)]
+struct ScheduleState {
+    running: bool,
+    history: VecDeque<ScheduledRun>,
+}
+
+/// Runs library scripts on their configured schedules
+pub struct Scheduler {
+    config: Config,
+    library: ScriptLibrary,
+    schedules: Vec<Schedule>,
+    state: Mutex<HashMap<String, ScheduleState>>,
+    counter: AtomicU64,
+    notifier: Option<Arc<Notifier>>,
+    runs: Option<Arc<RunStore>>,
+}
+
+impl Scheduler {
+    /// Build the scheduler from the config's enabled `[[schedules]]`
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let mut schedules: Vec<Schedule> = Vec::new();
+        for schedule in config.schedules.iter().filter(|s| s.enabled) {
+            if schedules.iter().any(|s| s.name == schedule.name) {
+                anyhow::bail!("Duplicate schedule name '{}'", schedule.name);
+            }
+            let cron = schedule.cron.parse().map_err(|e| {
+                anyhow::anyhow!("Invalid cron for schedule '{}': {}", schedule.name, e)
+            })?;
+            let script_version = schedule
+                .script_version
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "Invalid script_version for schedule '{}': {}",
+                        schedule.name,
+                        e
+                    )
+                })?;
+            schedules.push(Schedule {
+                name: schedule.name.clone(),
+                script: schedule.script.clone(),
+                script_version,
+                cron,
+            });
+        }
+
+        let state = schedules
+            .iter()
+            .map(|s| (s.name.clone(), ScheduleState::default()))
+            .collect();
+
+        Ok(Self {
+            config: config.clone(),
+            library: ScriptLibrary::new(&config.library.dir),
+            schedules,
+            state: Mutex::new(state),
+            counter: AtomicU64::new(0),
+            notifier: None,
+            runs: None,
+        })
+    }
+
+    /// Report finished runs through `notifier`
+    pub fn with_notifier(mut self, notifier: Arc<Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Record finished runs in `runs`
+    pub fn with_runs(mut self, runs: Arc<RunStore>) -> Self {
+        self.runs = Some(runs);
+        self
+    }
+
+    /// Whether any schedules are configured
+    pub fn is_empty(&self) -> bool {
+        self.schedules.is_empty()
+    }
+
+    /// Summaries of all schedules, in config order
+    pub fn schedules(&self) -> Vec<ScheduleInfo> {
+        let now = Utc::now();
+        let state = self.state.lock().unwrap();
+        self.schedules
+            .iter()
+            .map(|schedule| {
+                let entry = state.get(&schedule.name);
+                ScheduleInfo {
+                    name: schedule.name.clone(),
+                    script: schedule.script.clone(),
+                    cron: schedule.cron.to_string(),
+                    next_run: schedule.cron.next_after(now).map(|t| t.to_rfc3339()),
+                    running: entry.is_some_and(|e| e.running),
+                    last_run: entry.and_then(|e| e.history.back().cloned()),
+                }
+            })
+            .collect()
+    }
+
+    /// Run history of a schedule, newest first (None if the schedule doesn't exist)
+    pub fn history(&self, name: &str) -> Option<Vec<ScheduledRun>> {
+        let state = self.state.lock().unwrap();
+        state
+            .get(name)
+            .map(|entry| entry.history.iter().rev().cloned().collect())
+    }
+
+    /// Trigger schedules as they come due until `shutdown` is cancelled
+    ///
+    /// A schedule whose previous run is still in progress skips that tick.
+    pub async fn run(self: Arc<Self>, shutdown: CancellationToken) {
+        let mut next: Vec<Option<DateTime<Utc>>> = self
+            .schedules
+            .iter()
+            .map(|s| s.cron.next_after(Utc::now()))
+            .collect();
+
+        loop {
+            let Some(due) = next.iter().flatten().min().copied() else {
+                tracing::info!("No upcoming scheduled runs, scheduler stopping");
+                return;
+            };
+
+            let wait = (due - Utc::now()).to_std().unwrap_or_default();
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = shutdown.cancelled() => return,
+            }
+
+            let now = Utc::now();
+            for (schedule, next_run) in self.schedules.iter().zip(next.iter_mut()) {
+                if !next_run.is_some_and(|t| t <= now) {
+                    continue;
+                }
+                *next_run = schedule.cron.next_after(now);
+
+                let scheduler = self.clone();
+                let name = schedule.name.clone();
+                let cancel = shutdown.child_token();
+                tokio::spawn(async move {
+                    if let Err(e) = scheduler.run_now(&name, cancel).await {
+                        tracing::warn!(schedule = %name, error = %e, "Skipped scheduled run");
+                    }
+                });
+            }
+        }
+    }
+
+    /// Run a schedule immediately and record the result in its history
+    ///
+    /// Fails without running if the schedule doesn't exist or is already running;
+    /// script failures are reported in the returned [`ScheduledRun`].
+    pub async fn run_now(
+        &self,
+        name: &str,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<ScheduledRun> {
+        let schedule = self
+            .schedules
+            .iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Schedule not found: {}", name))?;
+
+        let run = self.begin_run(schedule)?;
+        tracing::info!(schedule = %name, run_id = %run.id, script = %schedule.script, "Starting scheduled run");
+
+        let result = self.execute(schedule, &run.output_dir, cancel).await;
+
+        let changes = match &result {
+            Ok(report) if !report.cancelled && self.config.changes.enabled => {
+                self.detect_changes(&run).await
+            }
+            _ => None,
+        };
+
+        if self.notifier.is_some() || self.runs.is_some() {
+            let notification = match &result {
+                Ok(report) => RunNotification::from_report(RunSource::Schedule, report.clone())
+                    .with_artifact(run.output_dir.join("report.json").display().to_string()),
+                Err(e) => RunNotification::failed(RunSource::Schedule, format!("{:#}", e))
+                    .with_script(&schedule.script),
+            };
+            let notification = match &changes {
+                Some(changes) => notification.with_changes(changes.clone()),
+                None => notification,
+            };
+            let notification = notification.with_id(&run.id);
+            let notification = match &self.runs {
+                Some(runs) => runs.record(notification),
+                None => notification,
+            };
+            if let Some(notifier) = &self.notifier {
+                notifier.spawn_notify(notification);
+            }
+        }
+
+        let run = self.finish_run(name, run, result, changes);
+        tracing::info!(
+            schedule = %name,
+            run_id = %run.id,
+            status = ?run.status,
+            successful = run.successful,
+            failed = run.failed,
+            "Scheduled run finished"
+        );
+        Ok(run)
+    }
+
+    /// Mark a schedule as running and record a new run in its history
+    fn begin_run(&self, schedule: &Schedule) -> anyhow::Result<ScheduledRun> {
+        let mut state = self.state.lock().unwrap();
+        let entry = state.entry(schedule.name.clone()).or_default();
+        if entry.running {
+            anyhow::bail!("Schedule '{}' is already running", schedule.name);
+        }
+
+        let now = Utc::now();
+        let seq = self.counter.fetch_add(1, Ordering::SeqCst);
+        let id = format!("run-{}-{}", now.format("%Y%m%dT%H%M%SZ"), seq);
+        let run = ScheduledRun {
+            output_dir: self
+                .config
+                .artifacts
+                .root
+                .join("schedules")
+                .join(&schedule.name)
+                .join(&id),
+            id,
+            schedule: schedule.name.clone(),
+            script: schedule.script.clone(),
+            status: RunStatus::Running,
+            started: now.to_rfc3339(),
+            finished: None,
+            successful: 0,
+            failed: 0,
+            error: None,
+            changes: None,
+        };
+
+        entry.running = true;
+        entry.history.push_back(run.clone());
+        if entry.history.len() > MAX_HISTORY {
+            entry.history.pop_front();
+        }
+        Ok(run)
+    }
+
+    /// Record the outcome of a run and clear the schedule's running flag
+    fn finish_run(
+        &self,
+        name: &str,
+        mut run: ScheduledRun,
+        result: anyhow::Result<ExecutionReport>,
+        changes: Option<ChangeReport>,
+    ) -> ScheduledRun {
+        run.changes = changes;
+        match result {
+            Ok(report) => {
+                run.successful = report.successful;
+                run.failed = report.failed;
+                run.status = if report.cancelled {
+                    RunStatus::Cancelled
+                } else if report.is_success() {
+                    RunStatus::Completed
+                } else {
+                    RunStatus::Failed
+                };
+            }
+            Err(e) => {
+                run.status = RunStatus::Failed;
+                run.error = Some(format!("{:#}", e));
+            }
+        }
+        run.finished = Some(Utc::now().to_rfc3339());
+
+        let mut state = self.state.lock().unwrap();
+        if let Some(entry) = state.get_mut(name) {
+            entry.running = false;
+            if let Some(recorded) = entry.history.iter_mut().find(|r| r.id == run.id) {
+                *recorded = run.clone();
+            }
+        }
+        run
+    }
+
+    /// Compare a finished run's artifacts with the schedule's previous run and write
+    /// `changes.json` (None for the first run or if the comparison fails)
+    async fn detect_changes(&self, run: &ScheduledRun) -> Option<ChangeReport> {
+        let schedule_dir = run.output_dir.parent()?.to_path_buf();
+        let current = run.output_dir.clone();
+        let config = self.config.changes.clone();
+
+        let compared = tokio::task::spawn_blocking(move || {
+            let Some(previous) = previous_run_dir(&schedule_dir, &current)? else {
+                return Ok(None);
+            };
+            let mut report = changes::compare_runs(&previous, &current, &config)?;
+            report.previous_run = previous
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+            std::fs::write(
+                current.join(changes::CHANGES_FILE),
+                serde_json::to_string_pretty(&report)?,
+            )?;
+            anyhow::Ok(Some(report))
+        })
+        .await;
+
+        match compared {
+            Ok(Ok(report)) => {
+                if let Some(report) = &report {
+                    tracing::info!(
+                        run_id = %run.id,
+                        changed = report.changed,
+                        data_changes = report.data.len(),
+                        visual_changes = report.visual.len(),
+                        "Compared with previous run"
+                    );
+                }
+                report
+            }
+            Ok(Err(e)) => {
+                tracing::warn!(run_id = %run.id, error = %format!("{:#}", e), "Failed to compare with previous run");
+                None
+            }
+            Err(e) => {
+                tracing::warn!(run_id = %run.id, error = %e, "Change detection task failed");
+                None
+            }
+        }
+    }
+
+    /// Load the schedule's script, run it in a fresh Chrome session, and write
+    /// `report.json`
+    async fn execute(
+        &self,
+        schedule: &Schedule,
+        output_dir: &Path,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<ExecutionReport> {
+        let script = match &schedule.script_version {
+            Some(required) => {
+                self.library
+                    .load_compatible(&schedule.script, required)
+                    .await?
+            }
+            None => self.library.load(&schedule.script).await?,
+        };
+        tokio::fs::create_dir_all(output_dir)
+            .await
+            .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+
+        let driver = ChromeDriver::from_config(&self.config).await?;
+        let result = async {
+            let page = driver.current_page().await?;
+            CdpExecutor::new(page)
+                .with_driver(&driver)
+                .with_cancellation(cancel)
+                .with_output_dir(output_dir)
+                .execute_script(&script)
+                .await
+        }
+        .await;
+        if let Err(e) = driver.close().await {
+            tracing::warn!(error = %e, "Failed to close scheduled run's Chrome session");
+        }
+
+        let report = result?;
+        let json = serde_json::to_string_pretty(&report)?;
+        tokio::fs::write(output_dir.join("report.json"), json)
+            .await
+            .context("Failed to write run report")?;
+        Ok(report)
+    }
+}
+
+/// Most recent run directory next to `current` that has a `report.json`
+///
+/// Reads the schedule's artifact directory rather than the in-memory history so
+/// comparisons continue across daemon restarts. Run IDs start with a UTC timestamp,
+/// so name order is chronological.
+fn previous_run_dir(schedule_dir: &Path, current: &Path) -> anyhow::Result<Option<PathBuf>> {
+    let mut runs: Vec<PathBuf> = std::fs::read_dir(schedule_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path != current && path.join("report.json").is_file())
+        .collect();
+    runs.sort();
+    Ok(runs.pop())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    fn cron(s: &str) -> CronSchedule {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("* * * *".parse::<CronSchedule>().is_err());
+        assert!("60 * * * *".parse::<CronSchedule>().is_err());
+        assert!("*/0 * * * *".parse::<CronSchedule>().is_err());
+        assert!("0 5-2 * * *".parse::<CronSchedule>().is_err());
+        assert!("0 0 0 * *".parse::<CronSchedule>().is_err());
+        assert!("@yearly".parse::<CronSchedule>().is_err());
+    }
+
+    #[test]
+    fn test_next_after() {
+        // Every 15 minutes
+        let every_15 = cron("*/15 * * * *");
+        assert_eq!(
+            every_15.next_after(at(2024, 3, 1, 10, 7)),
+            Some(at(2024, 3, 1, 10, 15))
+        );
+        // Strictly after: a matching minute moves to the next one
+        assert_eq!(
+            every_15.next_after(at(2024, 3, 1, 10, 15)),
+            Some(at(2024, 3, 1, 10, 30))
+        );
+
+        // Daily at 02:30, rolling over month and year ends
+        let nightly = cron("30 2 * * *");
+        assert_eq!(
+            nightly.next_after(at(2024, 12, 31, 3, 0)),
+            Some(at(2025, 1, 1, 2, 30))
+        );
+
+        // Weekdays at 09:00 (2024-03-01 is a Friday)
+        let weekdays = cron("0 9 * * 1-5");
+        assert_eq!(
+            weekdays.next_after(at(2024, 3, 1, 9, 0)),
+            Some(at(2024, 3, 4, 9, 0))
+        );
+
+        // 7 is Sunday
+        assert_eq!(
+            cron("0 0 * * 7").next_after(at(2024, 3, 1, 0, 0)),
+            Some(at(2024, 3, 3, 0, 0))
+        );
+
+        // Leap day
+        assert_eq!(
+            cron("0 0 29 2 *").next_after(at(2024, 3, 1, 0, 0)),
+            Some(at(2028, 2, 29, 0, 0))
+        );
+
+        // Impossible dates never match
+        assert_eq!(cron("0 0 31 2 *").next_after(at(2024, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_day_fields_match_either() {
+        // The 15th or any Monday
+        let schedule = cron("0 12 15 * 1");
+        assert_eq!(
+            schedule.next_after(at(2024, 3, 1, 0, 0)),
+            Some(at(2024, 3, 4, 12, 0))
+        );
+        assert_eq!(
+            schedule.next_after(at(2024, 3, 12, 0, 0)),
+            Some(at(2024, 3, 15, 12, 0))
+        );
+    }
+
+    #[test]
+    fn test_shorthands_and_lists() {
+        assert_eq!(
+            cron("@daily").next_after(at(2024, 3, 1, 10, 0)),
+            Some(at(2024, 3, 2, 0, 0))
+        );
+        assert_eq!(
+            cron("@monthly").next_after(at(2024, 3, 1, 10, 0)),
+            Some(at(2024, 4, 1, 0, 0))
+        );
+        assert_eq!(
+            cron("5,50 8-10/2 * * *").next_after(at(2024, 3, 1, 8, 6)),
+            Some(at(2024, 3, 1, 8, 50))
+        );
+        assert_eq!(
+            cron("5,50 8-10/2 * * *").next_after(at(2024, 3, 1, 8, 51)),
+            Some(at(2024, 3, 1, 10, 5))
+        );
+    }
+
+    #[test]
+    fn test_scheduler_from_config() {
+        let mut config = Config::from_toml(
+            r#"
+            [[schedules]]
+            name = "nightly"
+            script = "report"
+            cron = "@daily"
+
+            [[schedules]]
+            name = "paused"
+            script = "report"
+            cron = "0 * * * *"
+            enabled = false
+            "#,
+        )
+        .unwrap();
+
+        let scheduler = Scheduler::from_config(&config).unwrap();
+        let schedules = scheduler.schedules();
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].name, "nightly");
+        assert!(schedules[0].next_run.is_some());
+        assert!(!schedules[0].running);
+        assert_eq!(scheduler.history("nightly").unwrap().len(), 0);
+        assert!(scheduler.history("paused").is_none());
+
+        config.schedules[1].enabled = true;
+        config.schedules[1].name = "nightly".to_string();
+        assert!(Scheduler::from_config(&config).is_err());
+
+        config.schedules.truncate(1);
+        config.schedules[0].cron = "every day".to_string();
+        let err = Scheduler::from_config(&config).err().unwrap();
+        assert!(err.to_string().contains("nightly"));
+    }
+}
@@ -0,0 +1,1899 @@
+//! HTTP Inference Server
+//!
+//! Serves the `/inference`, `/jobs`, and `/batch` endpoints: prompts are turned into
+//! CDP scripts by [`CdpScriptGenerator`] and executed against a shared Chrome session,
+//! which is (re)launched on demand. Runs on the session don't wait for each other:
+//! their commands take turns on the page through its
+//! [`PageLock`](crate::browser::page_lock::PageLock). Batches run across a separate
+//! [`BrowserPool`] launched on first use.
+//!
+//! Configured schedules run in the background via the [`Scheduler`]; their status and
+//! run history are served under `/schedules`. Finished inferences, jobs, and scheduled
+//! runs are reported to the configured webhooks.
+//!
+//! Every finished run (including batch items) is recorded in the [`RunStore`] under
+//! `<artifacts.root>/runs` and served from `/runs`, filterable by script, status,
+//! source, and date.
+//!
+//! A `dry_run` inference only generates and validates the script, returning it for
+//! review without starting Chrome.
+//!
+//! With `generator.cache` on, scripts generated for a prompt on a page are reused
+//! by later requests with the same prompt and page ([`ScriptCache`]). A cached
+//! script that fails is dropped, and `DELETE /script-cache` drops them on demand.
+//!
+//! Each generation's tokens and cost are returned with the inference, kept in the
+//! job and run history, and totalled at `/usage`. With `generator.budget_*` set,
+//! generation is refused once the total reaches the budget.
+//!
+//! `POST /summarize` condenses an execution report and its frames into a short
+//! digest with key screenshots ([`ExecutionSummary`]) for chat UIs and
+//! notifications; `/runs/{id}/summary` does the same for a recorded run.
+//! `/runs/{id}/report.html` renders a recorded run's report as a page, with the
+//! findings of its accessibility audits.
+//!
+//! Inferences and jobs can start from a URL, pin the browser session they expect,
+//! and set execution options ([`RunOptions`]). An inference runs as a job and waits
+//! for it. Files they write (step frames and `save_as` outputs) go to
+//! `<artifacts.root>/jobs/<job id>` and are served under `/artifacts`. Frames are
+//! refused once the artifacts reach `artifacts.max_mb` or the disk runs low
+//! ([`ArtifactQuota`]), and `DELETE /artifacts?older_than_days=N` purges old ones.
+//!
+//! `/diagnose` checks the server's environment (browsers, sandbox, display, cache,
+//! network egress) and tries launching Chrome ([`DiagnosticReport`]), for support
+//! requests.
+//!
+//! When `server.auth_token` is configured, every endpoint except `/health`,
+//! `/openapi.json`, and `/docs` (with its assets) requires an `Authorization: Bearer
+//! <token>` header.
+//!
+//! With the `grpc` feature and `server.grpc_port` set, the [`grpc`](crate::grpc)
+//! server runs alongside on that port.
+//!
+//! The API is described by an OpenAPI 3.1 document ([`openapi`]), served at
+//! `/openapi.json` with a Swagger UI at `/docs`, for generating typed clients. The
+//! UI's assets are vendored and served from `/docs/`, not a CDN.
+
+use crate::artifacts::{ArtifactQuota, PurgeReport};
+use crate::browser::chrome::ChromeDriver;
+use crate::browser::pool::{BrowserPool, ScriptOutcome};
+use crate::cdp::cache::cache_key;
+use crate::cdp::summary::DEFAULT_KEY_FRAMES;
+use crate::cdp::{
+    CdpExecutor, CdpScript, CdpScriptGenerator, CdpValidator, ExecutionOptions, ExecutionReport,
+    ExecutionSummary, FrameCapture, GenerationBudget, GenerationUsage, ScriptCache,
+    ValidationResult,
+};
+use crate::config::Config;
+use crate::crypto::constant_time_eq;
+use crate::diagnostics::{self, DiagnoseOptions, DiagnosticReport, DEFAULT_EGRESS_URL};
+use crate::jobs::{Job, JobStatus, JobStore};
+use crate::model::lint::CdpLinter;
+use crate::runs::{RunFilter, RunQuery, RunRecord, RunStore};
+use crate::scheduler::{ScheduleInfo, ScheduledRun, Scheduler};
+use crate::step_frame::{CaptureOptions, StepFrame};
+use crate::webhook::{artifact_link, Notifier, RunNotification, RunSource, WebhookEvent};
+use arc_swap::ArcSwap;
+use futures::StreamExt;
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tokio_util::sync::CancellationToken;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use warp::{Filter, Reply};
+
+/// Swagger UI page for `/docs`, loading the vendored assets served next to it
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Robert Webdriver API</title>
+  <link rel="stylesheet" href="/docs/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="/docs/swagger-ui-bundle.js"></script>
+  <script>
+    SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
+
+/// Swagger UI's script and stylesheet (see vendor/swagger-ui/README.md)
+const SWAGGER_UI_JS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/vendor/swagger-ui/swagger-ui-bundle.js"
+));
+const SWAGGER_UI_CSS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/vendor/swagger-ui/swagger-ui.css"
+));
+
+#[derive(Debug, serde::Deserialize, ToSchema)]
+struct InferenceRequest {
+    /// What to do, in natural language
+    prompt: String,
+
+    /// Page to open before running the generated script (None = the current page)
+    #[serde(default)]
+    url: Option<String>,
+
+    /// Browser session to run in, from an earlier response; the request fails if
+    /// that session has ended (e.g. because Chrome was restarted)
+    #[serde(default)]
+    session_id: Option<String>,
+
+    #[serde(default)]
+    options: RunOptions,
+
+    /// Generate and validate the script, returning it without running it
+    /// (`/inference` only; `url`, `session_id`, and `options` are ignored)
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// How an inference or job runs its generated script
+#[derive(Debug, Clone, Default, serde::Deserialize, ToSchema)]
+#[serde(default)]
+struct RunOptions {
+    /// Seconds the script may run; commands left after that are skipped
+    timeout_secs: Option<u64>,
+
+    /// Seconds each command may take before it fails
+    command_timeout_secs: Option<u64>,
+
+    /// Keep running the remaining commands after one fails
+    continue_on_error: bool,
+
+    /// When to capture step frames (screenshot and DOM), which are stored as artifacts
+    frame_capture: FrameCapture,
+
+    /// Give captured frames generated narration (what each step does, why, and
+    /// what should happen)
+    narrate: bool,
+
+    /// Also save each frame's screenshot with the interactive elements labelled, with
+    /// a JSON file mapping the labels to elements (set-of-marks)
+    set_of_marks: bool,
+}
+
+impl RunOptions {
+    /// Executor options writing frames to `artifact_dir`, within `quota`
+    fn execution_options(
+        &self,
+        artifact_dir: &Path,
+        quota: Option<Arc<ArtifactQuota>>,
+    ) -> ExecutionOptions {
+        ExecutionOptions {
+            frame_capture: self.frame_capture,
+            capture: CaptureOptions {
+                screenshot_dir: artifact_dir.join("screenshots"),
+                dom_dir: Some(artifact_dir.join("dom")),
+                set_of_marks: self.set_of_marks,
+                quota,
+                ..Default::default()
+            },
+            continue_on_error: self.continue_on_error,
+            command_timeout: self.command_timeout_secs.map(Duration::from_secs),
+            narrate: self.narrate,
+            ..Default::default()
+        }
+    }
+
+    /// Token for one run: cancelled with `parent`, or once `timeout_secs` pass
+    ///
+    /// Cancel it when the run finishes to stop the timer.
+    fn run_token(&self, parent: &CancellationToken) -> CancellationToken {
+        let token = parent.child_token();
+        if let Some(secs) = self.timeout_secs {
+            let timer = token.clone();
+            tokio::spawn(async move {
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_secs(secs)) => timer.cancel(),
+                    _ = timer.cancelled() => {}
+                }
+            });
+        }
+        token
+    }
+}
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+struct InferenceResponse {
+    /// `success` or `error`
+    status: String,
+    message: String,
+    /// Number of commands in the generated script
+    script_steps: Option<usize>,
+    /// [`ExecutionReport`](crate::cdp::ExecutionReport) of the run
+    execution_report: Option<serde_json::Value>,
+    /// Id of the run in `/runs`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_id: Option<String>,
+    /// Id of the job the run went through, also listed under `/jobs` until it's dropped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    job_id: Option<String>,
+    /// Browser session the script ran in; pass it back to keep working on the same pages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_id: Option<String>,
+    /// Links to files the run wrote (step frames, `save_as` outputs), under `/artifacts`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    artifacts: Vec<String>,
+    /// The generated script (dry runs only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    script: Option<CdpScript>,
+    /// Validation of the generated script: errors and warnings (dry runs only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    validation: Option<ValidationResult>,
+    /// Whether the script came from the script cache instead of being generated
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    cached: bool,
+    /// Tokens and cost of generating the script (None if it came from the cache)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<GenerationUsage>,
+}
+
+impl InferenceResponse {
+    fn error(message: String) -> Self {
+        Self {
+            status: "error".to_string(),
+            message,
+            script_steps: None,
+            execution_report: None,
+            run_id: None,
+            job_id: None,
+            session_id: None,
+            artifacts: Vec::new(),
+            script: None,
+            validation: None,
+            cached: false,
+            usage: None,
+        }
+    }
+
+    /// Response for an inference that ran as `job`, once the job has finished
+    fn from_job(config: &Config, job: Job) -> Self {
+        let artifacts = job
+            .execution_report
+            .as_ref()
+            .map(|report| {
+                let notification =
+                    RunNotification::from_report(RunSource::Inference, report.clone());
+                artifact_links(config, &notification, report)
+            })
+            .unwrap_or_default();
+        let status = if job.status == JobStatus::Completed {
+            "success"
+        } else {
+            "error"
+        };
+        Self {
+            status: status.to_string(),
+            message: job.message.unwrap_or_default(),
+            script_steps: job.progress.total_steps,
+            execution_report: job
+                .execution_report
+                .and_then(|report| serde_json::to_value(report).ok()),
+            run_id: Some(job.id.clone()),
+            job_id: Some(job.id),
+            session_id: job.session_id,
+            artifacts,
+            script: None,
+            validation: None,
+            cached: job.progress.total_steps.is_some() && job.usage.is_none(),
+            usage: job.usage,
+        }
+    }
+
+    /// Preview of a generated script for a dry run
+    fn dry_run(script: CdpScript) -> Self {
+        let mut validation = ValidationResult::success();
+        CdpValidator::new().validate_script(&script, &mut validation);
+        let (status, message) = if validation.is_valid {
+            ("success", "Script generated and validated; not executed")
+        } else {
+            (
+                "error",
+                "Script generated but failed validation; not executed",
+            )
+        };
+        Self {
+            status: status.to_string(),
+            message: message.to_string(),
+            script_steps: Some(script.cdp_commands.len()),
+            script: Some(script),
+            validation: Some(validation),
+            ..Self::error(String::new())
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize, ToSchema)]
+struct BatchRequest {
+    /// Scripts to execute as-is
+    #[serde(default)]
+    scripts: Vec<CdpScript>,
+
+    /// Prompts to generate scripts from, then execute
+    #[serde(default)]
+    prompts: Vec<String>,
+
+    /// Maximum number of items to run at once (clamped to the pool size)
+    parallelism: Option<usize>,
+}
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+struct BatchItemResult {
+    /// Prompt the script was generated from (prompt items only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prompt: Option<String>,
+
+    /// Id of the run in `/runs`
+    run_id: String,
+
+    #[serde(flatten)]
+    outcome: ScriptOutcome,
+}
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+struct BatchReport {
+    /// `success` if every item succeeded, otherwise `partial`
+    status: String,
+    total: usize,
+    succeeded: usize,
+    failed: usize,
+    parallelism: usize,
+    duration_ms: u64,
+    /// One entry per item: scripts first, then prompts, in request order
+    results: Vec<BatchItemResult>,
+}
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+struct JobAccepted {
+    job_id: String,
+    status: JobStatus,
+}
+
+/// Generation usage since the server started
+#[derive(Debug, serde::Serialize, ToSchema)]
+struct UsageReport {
+    /// Usage of every generation call, including ones whose script failed validation
+    usage: GenerationUsage,
+    /// Configured limits (`generator.budget_usd` / `generator.budget_tokens`)
+    budget: GenerationBudget,
+    /// Why generation is being refused, if the budget is used up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exhausted: Option<String>,
+}
+
+/// Which cached scripts `DELETE /script-cache` drops
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ScriptCacheQuery {
+    /// Only drop scripts generated for this page (query string and fragment are ignored)
+    url: Option<String>,
+}
+
+/// Which artifacts `DELETE /artifacts` purges
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PurgeQuery {
+    /// Delete step frame files last modified more than this many days ago
+    older_than_days: u64,
+}
+
+/// Which of the slower checks `/diagnose` runs
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DiagnoseQuery {
+    /// Launch Chrome with the server's settings (default true)
+    launch: Option<bool>,
+
+    /// Check that Chrome for Testing can be downloaded (default true)
+    network: Option<bool>,
+}
+
+#[derive(Debug, serde::Serialize, ToSchema)]
+struct ScriptCacheCleared {
+    /// Number of cached scripts dropped
+    removed: usize,
+}
+
+/// Report `POST /summarize` condenses
+#[derive(Debug, serde::Deserialize, ToSchema)]
+struct SummarizeRequest {
+    report: ExecutionReport,
+
+    /// Frames captured besides those attached to the report's results
+    #[serde(default)]
+    #[schema(value_type = Vec<Object>)]
+    frames: Vec<StepFrame>,
+
+    /// Most key screenshots to pick (default 3)
+    #[serde(default)]
+    max_key_frames: Option<usize>,
+}
+
+/// How many key screenshots `/runs/{id}/summary` picks
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SummaryQuery {
+    /// Most key screenshots to pick (default 3)
+    max_key_frames: Option<usize>,
+}
+
+/// Body of every error response
+#[derive(Debug, serde::Serialize, ToSchema)]
+struct ErrorBody {
+    /// Always `error`
+    status: String,
+    message: String,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Robert Webdriver",
+        description = "Generate and run Chrome automation scripts, and inspect their runs"
+    ),
+    paths(
+        health,
+        handle_inference,
+        handle_create_job,
+        handle_get_job,
+        handle_cancel_job,
+        handle_batch,
+        handle_list_schedules,
+        handle_schedule_runs,
+        handle_list_runs,
+        handle_get_run,
+        handle_run_summary,
+        handle_run_report_html,
+        handle_summarize,
+        handle_get_artifact,
+        handle_purge_artifacts,
+        handle_clear_script_cache,
+        handle_usage,
+        handle_diagnose,
+    ),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "inference", description = "Generate a script from a prompt and run it"),
+        (name = "jobs", description = "Inferences run in the background"),
+        (name = "batch", description = "Many scripts and prompts across a browser pool"),
+        (name = "schedules", description = "Configured recurring runs"),
+        (name = "runs", description = "History of finished runs"),
+        (name = "artifacts", description = "Files written by runs"),
+        (name = "script-cache", description = "Scripts reused for repeated prompts"),
+        (name = "usage", description = "Tokens and cost of script generation"),
+        (name = "summaries", description = "Short digests of runs for chat UIs and notifications"),
+        (name = "diagnostics", description = "Environment checks for support requests"),
+    )
+)]
+struct ApiDoc;
+
+/// Adds the `bearer` security scheme referenced by the authenticated endpoints
+struct BearerAuth;
+
+impl utoipa::Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+}
+
+/// OpenAPI document describing the server's endpoints (served at `/openapi.json`)
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+// Shared state
+struct AppState {
+    config: Config,
+    /// The shared Chrome session, launched on first use and swapped out if it dies
+    driver: OnceCell<ArcSwap<ChromeDriver>>,
+    generator: Arc<CdpScriptGenerator>,
+    jobs: JobStore,
+    /// Sessions for `/batch`, launched on the first batch request
+    pool: OnceCell<BrowserPool>,
+    /// Recurring library script runs
+    scheduler: Arc<Scheduler>,
+    /// Run completion webhooks
+    notifier: Arc<Notifier>,
+    /// History of finished runs
+    runs: Arc<RunStore>,
+    /// Scripts generated for earlier prompts (None = caching off)
+    script_cache: Option<ScriptCache>,
+    /// Disk limits for step frames under `artifacts.root`
+    quota: Arc<ArtifactQuota>,
+    /// Server-wide token; cancelled on Ctrl-C to stop all running work
+    shutdown: CancellationToken,
+}
+
+/// Rejection for requests missing a valid bearer token
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+/// Run the server on `127.0.0.1:<server.port>` until `shutdown` is cancelled
+///
+/// Chrome sessions are launched with the config's driver settings. Cancelling
+/// `shutdown` also cancels every running inference, job, and scheduled run.
+pub async fn serve(config: Config, shutdown: CancellationToken) -> anyhow::Result<()> {
+    let port = config.server.port;
+    tracing::info!("Starting Robert Webdriver on port {}", port);
+
+    let auth = require_auth(config.server.auth_token.clone());
+
+    let notifier = Arc::new(Notifier::from_config(&config));
+    let runs = Arc::new(
+        RunStore::open(&config.artifacts.root.join("runs"))?.with_max_runs(config.server.max_runs),
+    );
+    let scheduler = Arc::new(
+        Scheduler::from_config(&config)?
+            .with_notifier(notifier.clone())
+            .with_runs(runs.clone()),
+    );
+    if !scheduler.is_empty() {
+        tracing::info!(
+            schedules = scheduler.schedules().len(),
+            library = %config.library.dir.display(),
+            "Starting scheduler"
+        );
+        tokio::spawn(scheduler.clone().run(shutdown.clone()));
+    }
+
+    if let Some(grpc_port) = config.server.grpc_port {
+        #[cfg(feature = "grpc")]
+        {
+            let grpc = crate::grpc::serve(config.clone(), grpc_port, shutdown.clone());
+            tokio::spawn(async move {
+                if let Err(e) = grpc.await {
+                    tracing::error!("{:#}", e);
+                }
+            });
+        }
+        #[cfg(not(feature = "grpc"))]
+        tracing::warn!(
+            grpc_port,
+            "server.grpc_port is set but this build lacks the grpc feature; serving HTTP only"
+        );
+    }
+
+    // Initialize state
+    let jobs = JobStore::with_max_finished(config.server.max_finished_jobs);
+    let state = Arc::new(AppState {
+        generator: Arc::new(config.generator()?),
+        script_cache: config.script_cache()?,
+        quota: Arc::new(ArtifactQuota::from_config(&config.artifacts).await),
+        config,
+        driver: OnceCell::new(),
+        jobs,
+        pool: OnceCell::new(),
+        scheduler,
+        notifier,
+        runs,
+        shutdown: shutdown.clone(),
+    });
+
+    // Health check endpoint
+    let health = warp::path("health").map(health);
+
+    // API description
+    let spec = openapi();
+    let openapi_json = warp::path!("openapi.json")
+        .and(warp::get())
+        .map(move || warp::reply::json(&spec));
+    let docs = warp::path!("docs")
+        .and(warp::get())
+        .map(|| warp::reply::html(SWAGGER_UI));
+    let docs_js = warp::path!("docs" / "swagger-ui-bundle.js")
+        .and(warp::get())
+        .map(|| warp::reply::with_header(SWAGGER_UI_JS, "content-type", "text/javascript"));
+    let docs_css = warp::path!("docs" / "swagger-ui.css")
+        .and(warp::get())
+        .map(|| warp::reply::with_header(SWAGGER_UI_CSS, "content-type", "text/css"));
+
+    // Inference endpoint
+    let state_filter = warp::any().map(move || state.clone());
+
+    let inference = warp::path("inference")
+        .and(auth.clone())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .and_then(handle_inference);
+
+    // Async job endpoints
+    let create_job = warp::path!("jobs")
+        .and(auth.clone())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .and_then(handle_create_job);
+
+    let get_job = warp::path!("jobs" / String)
+        .and(auth.clone())
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(handle_get_job);
+
+    let cancel_job = warp::path!("jobs" / String)
+        .and(auth.clone())
+        .and(warp::delete())
+        .and(state_filter.clone())
+        .and_then(handle_cancel_job);
+
+    // Batch endpoint
+    let batch = warp::path!("batch")
+        .and(auth.clone())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .and_then(handle_batch);
+
+    // Schedule endpoints
+    let list_schedules = warp::path!("schedules")
+        .and(auth.clone())
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(handle_list_schedules);
+
+    let schedule_runs = warp::path!("schedules" / String / "runs")
+        .and(auth.clone())
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(handle_schedule_runs);
+
+    // Run history endpoints
+    let list_runs = warp::path!("runs")
+        .and(auth.clone())
+        .and(warp::get())
+        .and(warp::query::<RunQuery>())
+        .and(state_filter.clone())
+        .and_then(handle_list_runs);
+
+    let get_run = warp::path!("runs" / String)
+        .and(auth.clone())
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(handle_get_run);
+
+    let run_summary = warp::path!("runs" / String / "summary")
+        .and(auth.clone())
+        .and(warp::get())
+        .and(warp::query::<SummaryQuery>())
+        .and(state_filter.clone())
+        .and_then(handle_run_summary);
+
+    let run_report_html = warp::path!("runs" / String / "report.html")
+        .and(auth.clone())
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(handle_run_report_html);
+
+    // Summaries of reports
+    let summarize = warp::path!("summarize")
+        .and(auth.clone())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .and_then(handle_summarize);
+
+    // Artifacts written by runs
+    let get_artifact = warp::path("artifacts")
+        .and(warp::path::tail())
+        .and(auth.clone())
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(handle_get_artifact);
+    let purge_artifacts = warp::path!("artifacts")
+        .and(auth.clone())
+        .and(warp::delete())
+        .and(warp::query::<PurgeQuery>())
+        .and(state_filter.clone())
+        .and_then(handle_purge_artifacts);
+
+    // Generated script cache
+    let clear_script_cache = warp::path!("script-cache")
+        .and(auth.clone())
+        .and(warp::delete())
+        .and(warp::query::<ScriptCacheQuery>())
+        .and(state_filter.clone())
+        .and_then(handle_clear_script_cache);
+
+    // Generation usage
+    let usage = warp::path!("usage")
+        .and(auth.clone())
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(handle_usage);
+
+    // Environment report
+    let diagnose = warp::path!("diagnose")
+        .and(auth)
+        .and(warp::get())
+        .and(warp::query::<DiagnoseQuery>())
+        .and(state_filter)
+        .and_then(handle_diagnose);
+
+    let routes = health
+        .or(openapi_json)
+        .or(docs)
+        .or(docs_js)
+        .or(docs_css)
+        .or(inference)
+        .or(create_job)
+        .or(get_job)
+        .or(cancel_job)
+        .or(batch)
+        .or(list_schedules)
+        .or(schedule_runs)
+        .or(list_runs)
+        .or(get_run)
+        .or(run_summary)
+        .or(run_report_html)
+        .or(summarize)
+        .or(get_artifact)
+        .or(purge_artifacts)
+        .or(clear_script_cache)
+        .or(usage)
+        .or(diagnose)
+        .recover(handle_rejection);
+
+    // Bind manually to handle "port in use" error gracefully
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
+        tracing::error!("Failed to bind to port {}: {}", port, e);
+        anyhow::anyhow!("Port {} is already in use or unavailable: {}", port, e)
+    })?;
+
+    tracing::info!("Listening on http://{}", addr);
+    warp::serve(routes)
+        .serve_incoming_with_graceful_shutdown(
+            tokio_stream::wrappers::TcpListenerStream::new(listener),
+            async move { shutdown.cancelled().await },
+        )
+        .await;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses((status = 200, description = "Server is up"))
+)]
+fn health() -> warp::reply::Json {
+    warp::reply::json(&serde_json::json!({ "status": "ok" }))
+}
+
+/// Filter that rejects requests without the expected bearer token (no-op if None)
+fn require_auth(
+    token: Option<String>,
+) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    let expected = token.map(|t| format!("Bearer {}", t));
+    warp::header::optional::<String>("authorization")
+        .and_then(move |header: Option<String>| {
+            let authorized = match &expected {
+                None => true,
+                Some(expected) => header
+                    .is_some_and(|header| constant_time_eq(header.as_bytes(), expected.as_bytes())),
+            };
+            async move {
+                if authorized {
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(Unauthorized))
+                }
+            }
+        })
+        .untuple_one()
+}
+
+async fn handle_rejection(
+    rejection: warp::Rejection,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    if rejection.find::<Unauthorized>().is_some() {
+        return Ok(error_reply(
+            warp::http::StatusCode::UNAUTHORIZED,
+            "Missing or invalid bearer token".to_string(),
+        ));
+    }
+    Err(rejection)
+}
+
+/// Handle to the live Chrome session, (re)launching it if needed
+///
+/// Requests that find the session dead each launch a replacement, and the first to
+/// swap theirs in wins; the others close theirs and use the winner's.
+async fn ensure_driver(state: &AppState) -> Result<ChromeDriver, String> {
+    let session = state
+        .driver
+        .get_or_try_init(|| async {
+            launch_driver(&state.config)
+                .await
+                .map(ArcSwap::from_pointee)
+        })
+        .await?;
+
+    let current = session.load_full();
+    if current.is_alive().await {
+        return Ok((*current).clone());
+    }
+
+    tracing::warn!("Chrome session DEAD, restarting...");
+    let relaunched = Arc::new(launch_driver(&state.config).await?);
+    let previous = session.compare_and_swap(&current, relaunched.clone());
+    if !Arc::ptr_eq(&previous, &current) {
+        let winner = (**previous).clone();
+        drop(previous);
+        if let Err(e) = (*relaunched).clone().close().await {
+            tracing::warn!("Failed to close spare Chrome session: {}", e);
+        }
+        return Ok(winner);
+    }
+    Ok((*relaunched).clone())
+}
+
+/// Launch a Chrome session with the server's driver settings
+async fn launch_driver(config: &Config) -> Result<ChromeDriver, String> {
+    tracing::info!("Launching new Chrome session...");
+    match ChromeDriver::from_config(config).await {
+        Ok(driver) => {
+            tracing::info!("Chrome launched successfully.");
+            Ok(driver)
+        }
+        Err(e) => {
+            tracing::error!("Failed to launch Chrome: {}", e);
+            Err(format!("Failed to launch Chrome: {}", e))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/inference",
+    tag = "inference",
+    request_body = InferenceRequest,
+    responses(
+        (status = 200, description = "Run finished, or the error that stopped it", body = InferenceResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_inference(
+    req: InferenceRequest,
+    state: Arc<AppState>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    tracing::info!(url = ?req.url, dry_run = req.dry_run, "Received inference request: {}", req.prompt);
+
+    if req.dry_run {
+        let response = match generate_script(&state, &req.prompt, req.url.as_deref()).await {
+            Ok((script, usage)) => InferenceResponse {
+                cached: usage.is_none(),
+                usage,
+                ..InferenceResponse::dry_run(script)
+            },
+            Err(e) => InferenceResponse::error(format!("Generation failed: {}", e)),
+        };
+        return Ok(warp::reply::json(&response));
+    }
+
+    // Run it as a job the request waits on, so it can be followed and cancelled under
+    // /jobs; the job goes on if the client hangs up
+    let job = state
+        .jobs
+        .create_with_token(&req.prompt, state.shutdown.child_token());
+    let job_id = job.id.clone();
+    let job_state = state.clone();
+    let spawned_id = job_id.clone();
+    let finished = tokio::spawn(async move {
+        run_job(spawned_id.clone(), req, job_state.clone()).await;
+        notify_job_finished(&spawned_id, RunSource::Inference, &job_state);
+    })
+    .await;
+    if let Err(e) = finished {
+        tracing::error!("Inference job {} panicked: {}", job_id, e);
+    }
+
+    let response = match state.jobs.get(&job_id) {
+        Some(job) => InferenceResponse::from_job(&state.config, job),
+        None => InferenceResponse::error(format!("Job {} finished but is no longer kept", job_id)),
+    };
+    Ok(warp::reply::json(&response))
+}
+
+/// A script for `prompt` on the page at `page_url`, with the usage of generating
+/// it (None if it came from the cache)
+///
+/// Newly generated scripts are cached when caching is on.
+async fn generate_script(
+    state: &AppState,
+    prompt: &str,
+    page_url: Option<&str>,
+) -> anyhow::Result<(CdpScript, Option<GenerationUsage>)> {
+    let cache = state.script_cache.as_ref();
+    if let Some(script) = cache.and_then(|cache| cache.get(&cache_key(prompt, page_url))) {
+        tracing::debug!(script = %script.name, page_url, "Reusing cached script");
+        return Ok((script, None));
+    }
+    let (script, usage) = state.generator.generate_with_usage(prompt).await?;
+    if let Some(cache) = cache {
+        cache.insert(prompt, page_url, script.clone());
+    }
+    Ok((script, Some(usage)))
+}
+
+/// Drop the script cached for `prompt` on the page at `page_url`, so the next
+/// request generates a fresh one
+fn forget_script(state: &AppState, prompt: &str, page_url: Option<&str>) {
+    if let Some(cache) = &state.script_cache {
+        if cache.invalidate(&cache_key(prompt, page_url)) {
+            tracing::info!(page_url, "Dropped cached script after it failed");
+        }
+    }
+}
+
+/// Whether a run failed (cancelled runs didn't fail; they were stopped)
+fn is_failure<E>(result: &Result<ExecutionReport, E>) -> bool {
+    match result {
+        Ok(report) => !report.cancelled && !report.is_success(),
+        Err(_) => true,
+    }
+}
+
+/// Check the request's session and open its URL, returning the page to run on
+async fn prepare_page(
+    driver: &ChromeDriver,
+    req: &InferenceRequest,
+) -> Result<chromiumoxide::page::Page, String> {
+    if let Some(session_id) = &req.session_id {
+        if session_id != driver.session_id() {
+            return Err(format!(
+                "Session {} has ended; the browser is now in session {}",
+                session_id,
+                driver.session_id()
+            ));
+        }
+    }
+    if let Some(url) = &req.url {
+        driver
+            .navigate(url)
+            .await
+            .map_err(|e| format!("Failed to open {}: {}", url, e))?;
+    }
+    driver
+        .current_page()
+        .await
+        .map_err(|e| format!("Failed to get current page: {}", e))
+}
+
+/// Files a run wrote: its notification's artifacts plus the step frames in `report`
+fn run_artifacts(notification: &RunNotification, report: &ExecutionReport) -> Vec<String> {
+    let mut artifacts = notification.artifacts.clone();
+    for frame in report.results.iter().flat_map(|result| &result.frames) {
+        artifacts.push(frame.screenshot.path.clone());
+        artifacts.extend(frame.screenshot.thumbnail.as_ref().map(|t| t.path.clone()));
+        if let Some(marked) = &frame.screenshot.marked {
+            artifacts.push(marked.path.clone());
+            artifacts.push(marked.marks_path.clone());
+        }
+        artifacts.extend(frame.dom.html_path.clone());
+    }
+    artifacts
+}
+
+/// `/artifacts` links to the files a run wrote
+fn artifact_links(
+    config: &Config,
+    notification: &RunNotification,
+    report: &ExecutionReport,
+) -> Vec<String> {
+    run_artifacts(notification, report)
+        .iter()
+        .filter_map(|path| artifact_link("/artifacts", &config.artifacts.root, path))
+        .collect()
+}
+
+#[utoipa::path(
+    post,
+    path = "/jobs",
+    tag = "jobs",
+    request_body = InferenceRequest,
+    responses(
+        (status = 202, description = "Job queued", body = JobAccepted),
+        (status = 400, description = "`dry_run` was set; preview scripts with `/inference`", body = ErrorBody),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_create_job(
+    req: InferenceRequest,
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    tracing::info!("Received job request: {}", req.prompt);
+
+    if req.dry_run {
+        return Ok(error_reply(
+            warp::http::StatusCode::BAD_REQUEST,
+            "Jobs can't be dry runs; use /inference with dry_run".to_string(),
+        ));
+    }
+
+    let job = state
+        .jobs
+        .create_with_token(&req.prompt, state.shutdown.child_token());
+    let job_id = job.id.clone();
+
+    let job_state = state.clone();
+    let spawned_id = job_id.clone();
+    tokio::spawn(async move {
+        run_job(spawned_id.clone(), req, job_state.clone()).await;
+        notify_job_finished(&spawned_id, RunSource::Job, &job_state);
+    });
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&JobAccepted {
+            job_id,
+            status: job.status,
+        }),
+        warp::http::StatusCode::ACCEPTED,
+    )
+    .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}",
+    tag = "jobs",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Job status, progress, and partial results", body = Job),
+        (status = 404, description = "Unknown job", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_get_job(
+    id: String,
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    match state.jobs.get(&id) {
+        Some(job) => Ok(warp::reply::json(&job).into_response()),
+        None => Ok(job_not_found(&id)),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/jobs/{id}",
+    tag = "jobs",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Job after cancellation", body = Job),
+        (status = 404, description = "Unknown job", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_cancel_job(
+    id: String,
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    match state.jobs.cancel(&id) {
+        Some(job) => {
+            tracing::info!("Job {} cancelled", id);
+            Ok(warp::reply::json(&job).into_response())
+        }
+        None => Ok(job_not_found(&id)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/batch",
+    tag = "batch",
+    request_body = BatchRequest,
+    responses(
+        (status = 200, description = "Every item finished", body = BatchReport),
+        (status = 400, description = "Empty batch", body = ErrorBody),
+        (status = 503, description = "Browser pool could not be launched", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_batch(
+    req: BatchRequest,
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let total = req.scripts.len() + req.prompts.len();
+    tracing::info!(
+        scripts = req.scripts.len(),
+        prompts = req.prompts.len(),
+        "Received batch request"
+    );
+
+    if total == 0 {
+        return Ok(error_reply(
+            warp::http::StatusCode::BAD_REQUEST,
+            "Batch must contain at least one script or prompt".to_string(),
+        ));
+    }
+
+    let pool_size = state.config.server.pool_size.max(1);
+    let pool = match state
+        .pool
+        .get_or_try_init(|| BrowserPool::launch(pool_size, &state.config))
+        .await
+    {
+        Ok(pool) => pool,
+        Err(e) => {
+            tracing::error!("Failed to launch browser pool: {}", e);
+            return Ok(error_reply(
+                warp::http::StatusCode::SERVICE_UNAVAILABLE,
+                format!("Failed to launch browser pool: {}", e),
+            ));
+        }
+    };
+
+    let parallelism = req.parallelism.unwrap_or(pool_size).clamp(1, pool_size);
+    let cancel_token = state.shutdown.child_token();
+    let start = Instant::now();
+
+    let items = req
+        .scripts
+        .into_iter()
+        .map(BatchItem::Script)
+        .chain(req.prompts.into_iter().map(BatchItem::Prompt));
+
+    let results: Vec<BatchItemResult> = futures::stream::iter(items.enumerate())
+        .map(|(index, item)| run_batch_item(index, item, &state, pool, &cancel_token))
+        .buffered(parallelism)
+        .collect()
+        .await;
+
+    let succeeded = results.iter().filter(|r| r.outcome.is_success()).count();
+    let failed = total - succeeded;
+    tracing::info!(total, succeeded, failed, "Batch finished");
+
+    Ok(warp::reply::json(&BatchReport {
+        status: if failed == 0 { "success" } else { "partial" }.to_string(),
+        total,
+        succeeded,
+        failed,
+        parallelism,
+        duration_ms: start.elapsed().as_millis() as u64,
+        results,
+    })
+    .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/schedules",
+    tag = "schedules",
+    responses((status = 200, description = "Configured schedules", body = [ScheduleInfo])),
+    security(("bearer" = []))
+)]
+async fn handle_list_schedules(
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    Ok(warp::reply::json(&state.scheduler.schedules()).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/schedules/{name}/runs",
+    tag = "schedules",
+    params(("name" = String, Path, description = "Schedule name")),
+    responses(
+        (status = 200, description = "Recent runs, newest first", body = [ScheduledRun]),
+        (status = 404, description = "Unknown schedule", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_schedule_runs(
+    name: String,
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    match state.scheduler.history(&name) {
+        Some(runs) => Ok(warp::reply::json(&runs).into_response()),
+        None => Ok(error_reply(
+            warp::http::StatusCode::NOT_FOUND,
+            format!("Schedule not found: {}", name),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/runs",
+    tag = "runs",
+    params(RunQuery),
+    responses(
+        (status = 200, description = "Matching runs without their reports, newest first", body = [RunRecord]),
+        (status = 400, description = "Invalid date", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_list_runs(
+    query: RunQuery,
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    match RunFilter::from_query(query) {
+        Ok(filter) => Ok(warp::reply::json(&state.runs.list(&filter)).into_response()),
+        Err(e) => Ok(error_reply(
+            warp::http::StatusCode::BAD_REQUEST,
+            e.to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/runs/{id}",
+    tag = "runs",
+    params(("id" = String, Path, description = "Run id")),
+    responses(
+        (status = 200, description = "Run with its execution report", body = RunRecord),
+        (status = 404, description = "Unknown run", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_get_run(
+    id: String,
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    match state.runs.get(&id) {
+        Some(run) => Ok(warp::reply::json(&run).into_response()),
+        None => Ok(error_reply(
+            warp::http::StatusCode::NOT_FOUND,
+            format!("Run not found: {}", id),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/artifacts/{path}",
+    tag = "artifacts",
+    params(("path" = String, Path, description = "File path under `artifacts.root`, as linked from a response")),
+    responses(
+        (status = 200, description = "File contents"),
+        (status = 404, description = "Unknown artifact", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_get_artifact(
+    tail: warp::path::Tail,
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let not_found = || {
+        error_reply(
+            warp::http::StatusCode::NOT_FOUND,
+            format!("Artifact not found: {}", tail.as_str()),
+        )
+    };
+    let Some(path) = artifact_path(&state.config.artifacts.root, tail.as_str()) else {
+        return Ok(not_found());
+    };
+    match tokio::fs::read(&path).await {
+        Ok(data) => {
+            Ok(warp::reply::with_header(data, "content-type", content_type(&path)).into_response())
+        }
+        Err(_) => Ok(not_found()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/artifacts",
+    tag = "artifacts",
+    params(PurgeQuery),
+    responses(
+        (status = 200, description = "Old step frame files deleted", body = PurgeReport),
+        (status = 500, description = "The artifact directory could not be read", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_purge_artifacts(
+    query: PurgeQuery,
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let older_than = Duration::from_secs(query.older_than_days * 24 * 60 * 60);
+    match state.quota.purge(older_than).await {
+        Ok(report) => Ok(warp::reply::json(&report).into_response()),
+        Err(e) => Ok(error_reply(
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to purge artifacts: {}", e),
+        )),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/script-cache",
+    tag = "script-cache",
+    params(ScriptCacheQuery),
+    responses(
+        (status = 200, description = "Cached scripts dropped (none when caching is off)", body = ScriptCacheCleared),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_clear_script_cache(
+    query: ScriptCacheQuery,
+    state: Arc<AppState>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let removed = match (&state.script_cache, &query.url) {
+        (None, _) => 0,
+        (Some(cache), Some(url)) => cache.invalidate_page(url),
+        (Some(cache), None) => cache.clear(),
+    };
+    tracing::info!(removed, url = ?query.url, "Cleared script cache");
+    Ok(warp::reply::json(&ScriptCacheCleared { removed }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/usage",
+    tag = "usage",
+    responses(
+        (status = 200, description = "Generation usage since the server started, and the budget", body = UsageReport),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_usage(state: Arc<AppState>) -> Result<impl warp::Reply, warp::Rejection> {
+    let usage = state.generator.usage();
+    let budget = state.generator.budget();
+    Ok(warp::reply::json(&UsageReport {
+        usage,
+        budget,
+        exhausted: budget.exhausted_by(&usage),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/diagnose",
+    tag = "diagnostics",
+    params(DiagnoseQuery),
+    responses(
+        (status = 200, description = "What the checks found, with the issues and their fixes", body = DiagnosticReport),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_diagnose(
+    query: DiagnoseQuery,
+    state: Arc<AppState>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let options = DiagnoseOptions {
+        launch: query.launch.unwrap_or(true),
+        egress_url: query
+            .network
+            .unwrap_or(true)
+            .then(|| DEFAULT_EGRESS_URL.to_string()),
+    };
+    Ok(warp::reply::json(
+        &diagnostics::diagnose(&state.config, &options).await,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/summarize",
+    tag = "summaries",
+    request_body = SummarizeRequest,
+    responses(
+        (status = 200, description = "Digest of the report, with key screenshots linked under `/artifacts`", body = ExecutionSummary),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_summarize(
+    req: SummarizeRequest,
+    state: Arc<AppState>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let summary = ExecutionSummary::from_report(
+        &req.report,
+        &req.frames,
+        req.max_key_frames.unwrap_or(DEFAULT_KEY_FRAMES),
+    );
+    Ok(warp::reply::json(&link_key_frames(&state.config, summary)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/runs/{id}/summary",
+    tag = "summaries",
+    params(("id" = String, Path, description = "Run id"), SummaryQuery),
+    responses(
+        (status = 200, description = "Digest of the run's report, with key screenshots linked under `/artifacts`", body = ExecutionSummary),
+        (status = 404, description = "Unknown run, or one that never produced a report", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_run_summary(
+    id: String,
+    query: SummaryQuery,
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let report = state
+        .runs
+        .get(&id)
+        .and_then(|run| run.report)
+        .and_then(|report| serde_json::from_value::<ExecutionReport>(report).ok());
+    let Some(report) = report else {
+        return Ok(error_reply(
+            warp::http::StatusCode::NOT_FOUND,
+            format!("No report for run: {}", id),
+        ));
+    };
+    let summary = ExecutionSummary::from_report(
+        &report,
+        &[],
+        query.max_key_frames.unwrap_or(DEFAULT_KEY_FRAMES),
+    );
+    Ok(warp::reply::json(&link_key_frames(&state.config, summary)).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/runs/{id}/report.html",
+    tag = "runs",
+    params(("id" = String, Path, description = "Run id")),
+    responses(
+        (status = 200, description = "The run's report as an HTML page: steps and accessibility findings", content_type = "text/html"),
+        (status = 404, description = "Unknown run, or one that never produced a report", body = ErrorBody),
+    ),
+    security(("bearer" = []))
+)]
+async fn handle_run_report_html(
+    id: String,
+    state: Arc<AppState>,
+) -> Result<warp::reply::Response, warp::Rejection> {
+    let report = state
+        .runs
+        .get(&id)
+        .and_then(|run| run.report)
+        .and_then(|report| serde_json::from_value::<ExecutionReport>(report).ok());
+    match report {
+        Some(report) => Ok(warp::reply::html(report.to_html()).into_response()),
+        None => Ok(error_reply(
+            warp::http::StatusCode::NOT_FOUND,
+            format!("No report for run: {}", id),
+        )),
+    }
+}
+
+/// `summary` with its key screenshots as `/artifacts` links
+fn link_key_frames(config: &Config, mut summary: ExecutionSummary) -> ExecutionSummary {
+    summary.key_frames.retain_mut(|frame| {
+        match artifact_link("/artifacts", &config.artifacts.root, &frame.screenshot) {
+            Some(link) => {
+                frame.screenshot = link;
+                true
+            }
+            None => false,
+        }
+    });
+    summary
+}
+
+/// File under `root` for an `/artifacts` path, or None if it would leave the root
+fn artifact_path(root: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+    let inside = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    (inside && !relative.as_os_str().is_empty()).then(|| root.join(relative))
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("html") => "text/html; charset=utf-8",
+        Some("json") => "application/json",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("pdf") => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+enum BatchItem {
+    Script(CdpScript),
+    Prompt(String),
+}
+
+/// Generate (for prompts) and execute one batch item on the pool
+async fn run_batch_item(
+    index: usize,
+    item: BatchItem,
+    state: &AppState,
+    pool: &BrowserPool,
+    cancel_token: &CancellationToken,
+) -> BatchItemResult {
+    let (prompt, script, usage) = match item {
+        BatchItem::Script(script) => (None, Ok(script), None),
+        BatchItem::Prompt(prompt) => match generate_script(state, &prompt, None).await {
+            Ok((script, usage)) => (Some(prompt), Ok(script), usage),
+            Err(e) => (Some(prompt), Err(format!("Generation failed: {}", e)), None),
+        },
+    };
+
+    let run_id = state.runs.next_id();
+    let script = script.and_then(|script| match escaping_save_as(&script) {
+        Some(message) => Err(message),
+        None => Ok(script),
+    });
+    let outcome = match script {
+        Ok(script) => {
+            let artifact_dir = state.config.artifacts.root.join("batch").join(&run_id);
+            let result = pool
+                .run_script_in(&script, &artifact_dir, cancel_token)
+                .await;
+            if let Some(prompt) = prompt.as_deref().filter(|_| is_failure(&result)) {
+                forget_script(state, prompt, None);
+            }
+            let (report, error) = match result {
+                Ok(report) => (Some(report), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            ScriptOutcome {
+                index,
+                script: script.name,
+                report,
+                error,
+            }
+        }
+        Err(error) => ScriptOutcome {
+            index,
+            script: String::new(),
+            report: None,
+            error: Some(error),
+        },
+    };
+
+    // Batch items are recorded but not sent to webhooks; the response reports them
+    let notification = match &outcome.report {
+        Some(report) => RunNotification::from_report(RunSource::Batch, report.clone()),
+        None => {
+            RunNotification::failed(RunSource::Batch, outcome.error.clone().unwrap_or_default())
+        }
+    };
+    let notification = if outcome.script.is_empty() {
+        notification
+    } else {
+        notification.with_script(&outcome.script)
+    }
+    .with_id(run_id)
+    .with_usage(usage);
+    let run_id = state.runs.record(notification).id.unwrap_or_default();
+
+    BatchItemResult {
+        prompt,
+        run_id,
+        outcome,
+    }
+}
+
+/// Why `script` can't run on the server: a `save_as` that would write outside the
+/// run's artifact directory
+fn escaping_save_as(script: &CdpScript) -> Option<String> {
+    CdpLinter::new()
+        .lint(script)
+        .into_iter()
+        .find(|warning| warning.rule.escapes_output_dir())
+        .map(|warning| format!("Script '{}' rejected: {}", script.name, warning.message))
+}
+
+fn error_reply(status: warp::http::StatusCode, message: String) -> warp::reply::Response {
+    warp::reply::with_status(
+        warp::reply::json(&ErrorBody {
+            status: "error".to_string(),
+            message,
+        }),
+        status,
+    )
+    .into_response()
+}
+
+fn job_not_found(id: &str) -> warp::reply::Response {
+    error_reply(
+        warp::http::StatusCode::NOT_FOUND,
+        format!("Job not found: {}", id),
+    )
+}
+
+/// Record a finished job as a run of `source` and report it to the webhooks
+fn notify_job_finished(job_id: &str, source: RunSource, state: &AppState) {
+    let Some(job) = state.jobs.get(job_id) else {
+        return;
+    };
+    let event = match job.status {
+        JobStatus::Completed => WebhookEvent::Completed,
+        JobStatus::Cancelled => WebhookEvent::Cancelled,
+        _ => WebhookEvent::Failed,
+    };
+
+    let mut notification = match job.execution_report {
+        Some(report) => {
+            let mut notification = RunNotification::from_report(source, report.clone());
+            notification.artifacts = run_artifacts(&notification, &report);
+            notification
+        }
+        None => RunNotification::failed(source, job.message.unwrap_or_default()),
+    };
+    notification.event = event;
+    finish_run(state, notification.with_id(job_id).with_usage(job.usage));
+}
+
+/// Record a finished run and report it to the webhooks, returning its run id
+fn finish_run(state: &AppState, notification: RunNotification) -> String {
+    let notification = state.runs.record(notification);
+    let id = notification.id.clone().unwrap_or_default();
+    state.notifier.spawn_notify(notification);
+    id
+}
+
+/// Background task: generate and execute a script, recording progress in the job store
+async fn run_job(job_id: String, req: InferenceRequest, state: Arc<AppState>) {
+    let jobs = &state.jobs;
+    let Some(cancel_token) = jobs.cancellation_token(&job_id) else {
+        return;
+    };
+
+    // 1. Generate script (abandoned immediately if the job is cancelled)
+    jobs.set_status(&job_id, JobStatus::Generating, None);
+    let generated = tokio::select! {
+        result = generate_script(&state, &req.prompt, req.url.as_deref()) => result,
+        _ = cancel_token.cancelled() => {
+            tracing::info!("Job {} cancelled during generation", job_id);
+            jobs.set_status(&job_id, JobStatus::Cancelled, Some("Job cancelled".to_string()));
+            return;
+        }
+    };
+    let script = match generated {
+        Ok((script, usage)) => {
+            jobs.update(&job_id, |job| job.usage = usage);
+            script
+        }
+        Err(e) => {
+            tracing::error!("Job {}: generation failed: {}", job_id, e);
+            jobs.set_status(
+                &job_id,
+                JobStatus::Failed,
+                Some(format!("Generation failed: {}", e)),
+            );
+            return;
+        }
+    };
+
+    let total_steps = script.cdp_commands.len();
+    jobs.update(&job_id, |job| job.progress.total_steps = Some(total_steps));
+
+    // 2. Ensure Chrome is running (jobs share the single browser session)
+    let driver = match ensure_driver(&state).await {
+        Ok(driver) => driver,
+        Err(message) => {
+            jobs.set_status(&job_id, JobStatus::Failed, Some(message));
+            return;
+        }
+    };
+    jobs.update(&job_id, |job| {
+        job.session_id = Some(driver.session_id().to_string())
+    });
+
+    let page = match prepare_page(&driver, &req).await {
+        Ok(p) => p,
+        Err(message) => {
+            jobs.set_status(&job_id, JobStatus::Failed, Some(message));
+            return;
+        }
+    };
+
+    // 3. Execute script, publishing each command result as it completes
+    jobs.set_status(&job_id, JobStatus::Running, None);
+    let artifact_dir = state.config.artifacts.root.join("jobs").join(&job_id);
+    let run_token = req.options.run_token(&cancel_token);
+    let executor = CdpExecutor::new(page)
+        .with_driver(&driver)
+        .with_cancellation(run_token.clone())
+        .with_output_dir(&artifact_dir)
+        .with_goal_judge(state.generator.clone())
+        .with_options(
+            req.options
+                .execution_options(&artifact_dir, Some(state.quota.clone())),
+        );
+    let outcome = executor
+        .execute_script_with_progress(&script, |result| jobs.push_result(&job_id, result))
+        .await;
+    let timed_out = run_token.is_cancelled() && !cancel_token.is_cancelled();
+    run_token.cancel();
+    if is_failure(&outcome) {
+        forget_script(&state, &req.prompt, req.url.as_deref());
+    }
+
+    match outcome {
+        Ok(report) if report.cancelled && timed_out => {
+            tracing::info!("Job {} timed out after {} steps", job_id, report.successful);
+            jobs.set_status(
+                &job_id,
+                JobStatus::Failed,
+                Some(format!(
+                    "Job timed out after {}s",
+                    req.options.timeout_secs.unwrap_or_default()
+                )),
+            );
+            jobs.attach_report(&job_id, report);
+        }
+        Ok(report) if report.cancelled => {
+            tracing::info!("Job {} cancelled after {} steps", job_id, report.successful);
+            jobs.set_status(
+                &job_id,
+                JobStatus::Cancelled,
+                Some("Job cancelled".to_string()),
+            );
+            jobs.attach_report(&job_id, report);
+        }
+        Ok(report) => {
+            tracing::info!("Job {} finished: {:?}", job_id, report);
+            let (status, message) = if report.is_success() {
+                (JobStatus::Completed, "Script generated and executed")
+            } else {
+                (
+                    JobStatus::Failed,
+                    "Script execution stopped on a failed command",
+                )
+            };
+            jobs.update(&job_id, |job| {
+                job.status = status;
+                job.message = Some(message.to_string());
+                job.execution_report = Some(report);
+            });
+        }
+        Err(e) => {
+            tracing::error!("Job {}: execution failed: {}", job_id, e);
+            jobs.set_status(
+                &job_id,
+                JobStatus::Failed,
+                Some(format!("Execution failed: {}", e)),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_document() {
+        let spec = serde_json::to_value(openapi()).unwrap();
+
+        let paths = spec["paths"].as_object().unwrap();
+        for path in [
+            "/health",
+            "/inference",
+            "/jobs",
+            "/jobs/{id}",
+            "/batch",
+            "/schedules",
+            "/schedules/{name}/runs",
+            "/runs",
+            "/runs/{id}",
+            "/runs/{id}/summary",
+            "/runs/{id}/report.html",
+            "/summarize",
+            "/artifacts/{path}",
+            "/artifacts",
+            "/script-cache",
+            "/usage",
+            "/diagnose",
+        ] {
+            assert!(paths.contains_key(path), "missing {}", path);
+        }
+        assert!(paths["/jobs/{id}"]["delete"].is_object());
+        assert!(paths["/health"]["get"]["security"].is_null());
+        assert_eq!(
+            paths["/runs"]["get"]["security"][0]["bearer"],
+            serde_json::json!([])
+        );
+
+        let schemas = &spec["components"]["schemas"];
+        assert_eq!(
+            spec["components"]["securitySchemes"]["bearer"]["scheme"],
+            "bearer"
+        );
+        assert!(schemas["ExecutionReport"].is_object());
+        assert!(schemas["Duration"]["properties"]["secs"].is_object());
+        assert!(schemas["RunRecord"].is_object());
+        assert!(schemas["ExecutionSummary"]["properties"]["key_frames"].is_object());
+        assert!(schemas["RunOptions"]["properties"]["continue_on_error"].is_object());
+        assert!(schemas["DiagnosticReport"]["properties"]["sandbox"].is_object());
+    }
+
+    #[test]
+    fn test_inference_request() {
+        let minimal: InferenceRequest =
+            serde_json::from_str(r#"{"prompt": "read the title"}"#).unwrap();
+        assert!(minimal.url.is_none() && minimal.session_id.is_none());
+        assert!(!minimal.options.continue_on_error && !minimal.dry_run);
+
+        let full: InferenceRequest = serde_json::from_str(
+            r#"{"prompt": "log in", "url": "https://example.com/login", "session_id": "s1",
+                "options": {"timeout_secs": 60, "command_timeout_secs": 10,
+                            "continue_on_error": true, "frame_capture": "on_failure"}}"#,
+        )
+        .unwrap();
+        let options = full
+            .options
+            .execution_options(Path::new("/artifacts/inference/run-1"), None);
+        assert_eq!(options.frame_capture, FrameCapture::OnFailure);
+        assert_eq!(options.command_timeout, Some(Duration::from_secs(10)));
+        assert!(options.continue_on_error);
+        assert_eq!(
+            options.capture.screenshot_dir,
+            Path::new("/artifacts/inference/run-1/screenshots")
+        );
+    }
+
+    #[test]
+    fn test_dry_run_response() {
+        let script: CdpScript = serde_json::from_str(
+            r#"{"name": "title", "description": "Read the title", "cdp_commands": [
+                {"method": "Page.navigate", "params": {"url": "https://example.com"}}
+            ]}"#,
+        )
+        .unwrap();
+        let preview = InferenceResponse::dry_run(script.clone());
+        assert_eq!(preview.status, "success");
+        assert_eq!(preview.script_steps, Some(1));
+        assert!(preview.validation.unwrap().is_valid);
+        assert!(preview.run_id.is_none() && preview.execution_report.is_none());
+
+        let unnamed = CdpScript {
+            name: String::new(),
+            ..script
+        };
+        let preview = InferenceResponse::dry_run(unnamed);
+        assert_eq!(preview.status, "error");
+        assert!(!preview.validation.unwrap().errors.is_empty());
+    }
+
+    #[test]
+    fn test_artifact_path() {
+        let root = Path::new("/var/artifacts");
+        assert_eq!(
+            artifact_path(root, "inference/run-1/screenshots/frame_0000.png"),
+            Some(root.join("inference/run-1/screenshots/frame_0000.png"))
+        );
+        assert_eq!(artifact_path(root, "../etc/passwd"), None);
+        assert_eq!(artifact_path(root, "/etc/passwd"), None);
+        assert_eq!(artifact_path(root, ""), None);
+    }
+
+    #[test]
+    fn test_escaping_save_as() {
+        let script = |save_as: &str| -> CdpScript {
+            serde_json::from_value(
+                serde_json::json!({"name": "shot", "description": "Screenshot", "cdp_commands": [
+                    {"method": "Page.captureScreenshot", "params": {}, "save_as": save_as}
+                ]}),
+            )
+            .unwrap()
+        };
+        assert!(escaping_save_as(&script("shots/home.png")).is_none());
+        for escaping in ["/etc/cron.d/x", "../../x", "shots/../../x"] {
+            let message = escaping_save_as(&script(escaping)).unwrap();
+            assert!(message.starts_with("Script 'shot' rejected"), "{}", message);
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Sitemap Batch Capture
+//!
+//! Site-wide audits capture every page a site lists rather than what a crawl
+//! happens to reach. [`load_urls`] reads the page list from a `sitemap.xml`
+//! (following sitemap index files) or a plain URL list, one URL per line, from a
+//! file or over HTTP. [`BatchCapture`] then visits each URL on a browser pool,
+//! saving a step frame (screenshot, DOM, optionally VisualDom) and extraction
+//! result per page, and writes two files to the output directory:
+//!
+//! - `index.json`: one entry per URL with its status and artifact paths
+//! - `failures.json`: the URLs that failed to load or returned a 4xx/5xx status
+
+use crate::browser::pool::BrowserPool;
+use crate::crawl::{CrawlOptions, CrawlReport, CrawledPage, Crawler};
+use crate::step_frame::CaptureOptions;
+use anyhow::Context;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+/// File listing every captured URL, in the output directory
+pub const INDEX_FILE: &str = "index.json";
+
+/// File listing the URLs that failed, in the output directory
+pub const FAILURES_FILE: &str = "failures.json";
+
+/// How deep sitemap index files may nest
+const MAX_SITEMAP_NESTING: usize = 3;
+
+/// Page and sitemap URLs listed in a sitemap document
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sitemap {
+    /// `<url><loc>` entries
+    pub urls: Vec<String>,
+
+    /// `<sitemap><loc>` entries of a sitemap index
+    pub sitemaps: Vec<String>,
+}
+
+impl Sitemap {
+    /// Parse a `sitemap.xml` or sitemap index document
+    pub fn parse(xml: &str) -> Self {
+        let mut sitemap = Self::default();
+        let index = xml.contains("<sitemapindex");
+        let mut rest = xml;
+        while let Some(start) = rest.find("<loc>") {
+            let after = &rest[start + "<loc>".len()..];
+            let Some(end) = after.find("</loc>") else {
+                break;
+            };
+            let loc = unescape_xml(strip_cdata(after[..end].trim()));
+            if !loc.is_empty() {
+                if index {
+                    sitemap.sitemaps.push(loc);
+                } else {
+                    sitemap.urls.push(loc);
+                }
+            }
+            rest = &after[end..];
+        }
+        sitemap
+    }
+}
+
+/// Page URLs from a sitemap or URL list at `source` (a path or an http(s) URL)
+///
+/// Documents starting with `<` are read as sitemaps; anything else as one URL per
+/// line, ignoring blank lines and `#` comments. Duplicates are dropped, keeping
+/// the first occurrence.
+pub async fn load_urls(source: &str) -> anyhow::Result<Vec<String>> {
+    let client = reqwest::Client::new();
+    let mut urls = Vec::new();
+    let mut pending = vec![(source.to_string(), 0)];
+    let mut read = HashSet::new();
+
+    while let Some((location, nesting)) = pending.pop() {
+        if !read.insert(location.clone()) {
+            continue;
+        }
+        let text = read_source(&client, &location).await?;
+        if !text.trim_start().starts_with('<') {
+            urls.extend(parse_url_list(&text));
+            continue;
+        }
+
+        let sitemap = Sitemap::parse(&text);
+        urls.extend(sitemap.urls);
+        if !sitemap.sitemaps.is_empty() {
+            if nesting >= MAX_SITEMAP_NESTING {
+                anyhow::bail!(
+                    "Sitemap indexes nested more than {} deep",
+                    MAX_SITEMAP_NESTING
+                );
+            }
+            // Reversed so child sitemaps are read in listed order
+            pending.extend(
+                sitemap
+                    .sitemaps
+                    .into_iter()
+                    .rev()
+                    .map(|child| (child, nesting + 1)),
+            );
+        }
+    }
+
+    let mut seen = HashSet::new();
+    urls.retain(|url| seen.insert(url.clone()));
+    Ok(urls)
+}
+
+/// URLs of a plain list, one per line
+pub fn parse_url_list(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+async fn read_source(client: &reqwest::Client, location: &str) -> anyhow::Result<String> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        let response = client
+            .get(location)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch {}", location))?
+            .error_for_status()
+            .with_context(|| format!("Failed to fetch {}", location))?;
+        Ok(response.text().await?)
+    } else {
+        tokio::fs::read_to_string(location)
+            .await
+            .with_context(|| format!("Failed to read {}", location))
+    }
+}
+
+/// Captures a list of pages into one output directory
+#[derive(Debug, Clone)]
+pub struct BatchCapture {
+    /// Directory for `screenshots/`, `dom/`, `visualdom/`, and the index files
+    pub output_dir: PathBuf,
+
+    /// Also capture VisualDom snapshots
+    pub capture_visual_dom: bool,
+
+    /// JavaScript expression evaluated on each page, recorded in the index
+    pub extract_script: Option<String>,
+
+    /// Skip pages disallowed by `robots.txt`
+    pub respect_robots: bool,
+}
+
+impl BatchCapture {
+    pub fn new(output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            capture_visual_dom: false,
+            extract_script: None,
+            respect_robots: false,
+        }
+    }
+
+    /// Visit and capture every URL, then write the index and failure report
+    pub async fn run(
+        &self,
+        pool: &BrowserPool,
+        urls: &[String],
+        cancel_token: &CancellationToken,
+    ) -> anyhow::Result<BatchSummary> {
+        let options = CrawlOptions {
+            max_depth: 0,
+            max_pages: urls.len(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            respect_robots: self.respect_robots,
+            capture: Some(CaptureOptions {
+                screenshot_dir: self.output_dir.join("screenshots"),
+                dom_dir: Some(self.output_dir.join("dom")),
+                visual_dom_dir: Some(self.output_dir.join("visualdom")),
+                capture_visual_dom: self.capture_visual_dom,
+                cancel_token: Some(cancel_token.clone()),
+                ..Default::default()
+            }),
+            extract_script: self.extract_script.clone(),
+            check_links: None,
+        };
+        let report = Crawler::new(pool, options)
+            .crawl_cancellable(urls, cancel_token)
+            .await?;
+        write_report(&self.output_dir, &report)
+    }
+}
+
+/// A captured page in `index.json`
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexEntry {
+    pub url: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visual_dom: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extracted: Option<Value>,
+
+    /// Load or capture error, or `HTTP <status>` for 4xx/5xx responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<&CrawledPage> for IndexEntry {
+    fn from(page: &CrawledPage) -> Self {
+        let frame = page.frame.as_ref();
+        let http_error = page
+            .status
+            .filter(|status| *status >= 400)
+            .map(|status| format!("HTTP {}", status));
+        Self {
+            url: page.url.clone(),
+            final_url: page.final_url.clone(),
+            status: page.status,
+            title: frame.map(|f| f.dom.title.clone()),
+            screenshot: frame.map(|f| f.screenshot.path.clone()),
+            html: frame.and_then(|f| f.dom.html_path.clone()),
+            visual_dom: frame.and_then(|f| f.visual_dom.as_ref().map(|v| v.path.clone())),
+            extracted: page.extracted.clone(),
+            error: page.error.clone().or(http_error),
+        }
+    }
+}
+
+/// Totals of a batch capture
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchSummary {
+    /// Pages visited
+    pub total: usize,
+
+    /// Pages captured without error
+    pub captured: usize,
+
+    /// Pages listed in the failure report
+    pub failed: usize,
+
+    /// URLs skipped because `robots.txt` disallows them
+    pub disallowed: Vec<String>,
+
+    /// Whether the run was cancelled before every URL was visited
+    pub cancelled: bool,
+
+    pub index_path: PathBuf,
+    pub failures_path: PathBuf,
+}
+
+impl BatchSummary {
+    pub fn is_success(&self) -> bool {
+        self.failed == 0 && !self.cancelled
+    }
+}
+
+/// Write `index.json` and `failures.json` for a finished batch into `dir`
+pub fn write_report(dir: &Path, report: &CrawlReport) -> anyhow::Result<BatchSummary> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let entries: Vec<IndexEntry> = report.pages.iter().map(IndexEntry::from).collect();
+    let failures: Vec<&IndexEntry> = entries.iter().filter(|e| e.error.is_some()).collect();
+
+    let index_path = dir.join(INDEX_FILE);
+    std::fs::write(&index_path, serde_json::to_vec_pretty(&entries)?)
+        .with_context(|| format!("Failed to write {}", index_path.display()))?;
+    let failures_path = dir.join(FAILURES_FILE);
+    std::fs::write(&failures_path, serde_json::to_vec_pretty(&failures)?)
+        .with_context(|| format!("Failed to write {}", failures_path.display()))?;
+
+    tracing::info!(
+        total = entries.len(),
+        failed = failures.len(),
+        index = %index_path.display(),
+        "Batch capture finished"
+    );
+    Ok(BatchSummary {
+        total: entries.len(),
+        captured: entries.len() - failures.len(),
+        failed: failures.len(),
+        disallowed: report.disallowed.clone(),
+        cancelled: report.cancelled,
+        index_path,
+        failures_path,
+    })
+}
+
+fn strip_cdata(text: &str) -> &str {
+    text.strip_prefix("<![CDATA[")
+        .and_then(|inner| inner.strip_suffix("]]>"))
+        .unwrap_or(text)
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sitemap() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://example.com/</loc><lastmod>2024-01-01</lastmod></url>
+  <url>
+    <loc> https://example.com/search?q=a&amp;page=2 </loc>
+  </url>
+  <url><loc><![CDATA[https://example.com/about]]></loc></url>
+</urlset>"#;
+        let sitemap = Sitemap::parse(xml);
+        assert_eq!(
+            sitemap.urls,
+            vec![
+                "https://example.com/",
+                "https://example.com/search?q=a&page=2",
+                "https://example.com/about",
+            ]
+        );
+        assert!(sitemap.sitemaps.is_empty());
+
+        let index = Sitemap::parse(
+            r#"<sitemapindex><sitemap><loc>https://example.com/a.xml</loc></sitemap></sitemapindex>"#,
+        );
+        assert_eq!(index.sitemaps, vec!["https://example.com/a.xml"]);
+        assert!(index.urls.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_load_urls_follows_index() {
+        let dir = std::env::temp_dir().join(format!("robert-sitemap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let child = dir.join("pages.xml");
+        std::fs::write(
+            &child,
+            "<urlset><url><loc>https://example.com/a</loc></url>\
+             <url><loc>https://example.com/b</loc></url></urlset>",
+        )
+        .unwrap();
+        let list = dir.join("more.txt");
+        std::fs::write(
+            &list,
+            "# extra pages\nhttps://example.com/b\n\nhttps://example.com/c\n",
+        )
+        .unwrap();
+        let index = dir.join("sitemap.xml");
+        std::fs::write(
+            &index,
+            format!(
+                "<sitemapindex><sitemap><loc>{}</loc></sitemap><sitemap><loc>{}</loc></sitemap></sitemapindex>",
+                child.display(),
+                list.display()
+            ),
+        )
+        .unwrap();
+
+        let urls = load_urls(index.to_str().unwrap()).await.unwrap();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/a",
+                "https://example.com/b",
+                "https://example.com/c",
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -5,370 +5,82 @@
 //!
 //! Based on the Step Frame Schema specification in agent-formats/specs/STEP_FRAME_SCHEMA.md
 
+use crate::artifacts::ArtifactQuota;
 use crate::error::{BrowserError, Result};
+use crate::frame_store::FrameStore;
+use crate::html::{capture_html, HtmlOptions};
+use crate::redaction::{Redaction, ResolvedRedaction};
+use crate::visual_dom_delta::{reconstruct_visual_dom, VisualDomDeltas};
 use crate::ChromeDriver;
-use serde::{Deserialize, Serialize};
+use base64::Engine;
+use std::io::{BufRead, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 // ===== STEP FRAME STRUCTS =====
 
-/// A complete step frame capturing a moment in a browser workflow
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StepFrame {
-    /// Unique frame identifier (sequential starting from 0)
-    pub frame_id: usize,
+pub use crate::model::frame::*;
+pub use crate::model::salience::{SalienceSignals, SalientElement};
 
-    /// ISO 8601 timestamp when frame was captured
-    pub timestamp: String,
-
-    /// Milliseconds elapsed since workflow start
-    pub elapsed_ms: u64,
-
-    /// Visual state (screenshot)
-    pub screenshot: ScreenshotInfo,
-
-    /// DOM state
-    pub dom: DomInfo,
-
-    /// VisualDom state (optional)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub visual_dom: Option<VisualDomInfo>,
-
-    /// User/Agent action being performed
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub action: Option<ActionInfo>,
-
-    /// Natural language transcript
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub transcript: Option<TranscriptInfo>,
-}
-
-/// Screenshot information
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ScreenshotInfo {
-    /// Relative or absolute path to screenshot file
-    pub path: String,
-
-    /// Image format (png, jpeg, webp)
-    pub format: String,
-
-    /// File size in bytes
-    pub size_bytes: usize,
-
-    /// Image dimensions
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub dimensions: Option<Dimensions>,
-
-    /// SHA-256 hash for deduplication
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub hash: Option<String>,
-}
-
-/// Image or viewport dimensions
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Dimensions {
-    pub width: u32,
-    pub height: u32,
-}
-
-/// DOM state information
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DomInfo {
-    /// Current page URL
-    pub url: String,
-
-    /// Page title
-    pub title: String,
-
-    /// Path to saved HTML file (optional)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub html_path: Option<String>,
-
-    /// SHA-256 hash of HTML content
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub html_hash: Option<String>,
-
-    /// Interactive elements on the page (optional, can be expensive to collect)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub interactive_elements: Option<Vec<InteractiveElement>>,
-}
-
-/// An interactive element on the page
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InteractiveElement {
-    pub selector: String,
-    pub tag: String,
-    pub text: String,
-    pub is_visible: bool,
-    pub is_enabled: bool,
-}
-
-// ===== VISUALDOM STRUCTS =====
-
-/// VisualDom snapshot information
-///
-/// VisualDom is a custom format we created that provides a structured representation
-/// of the DOM with layout and visual information. It combines data from Chrome DevTools
-/// Protocol's DOMSnapshot.captureSnapshot with embedded base64 images.
-///
-/// This format allows AI agents to understand page structure, layout, and content
-/// without requiring expensive OCR on screenshots.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VisualDomInfo {
-    /// Path to saved VisualDom JSON file
-    pub path: String,
-
-    /// File size in bytes
-    pub size_bytes: usize,
-
-    /// Number of DOM nodes in the snapshot
-    pub node_count: usize,
-
-    /// SHA-256 hash for deduplication
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub hash: Option<String>,
-}
-
-/// CDP DOMSnapshot captureSnapshot response
-///
-/// This is the raw response from CDP's DOMSnapshot.captureSnapshot command
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CaptureSnapshotResponse {
-    /// Array of document snapshots (usually one, but can include iframes)
-    pub documents: Vec<DocumentSnapshot>,
-
-    /// String table - all strings are stored as indexes into this array for efficiency
-    pub strings: Vec<String>,
-}
-
-/// A snapshot of a single document (page or iframe)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DocumentSnapshot {
-    /// Index of document URL in string table
-    #[serde(rename = "documentURL")]
-    pub document_url: i64,
-
-    /// Index of title in string table
-    pub title: i64,
-
-    /// Index of base URL in string table
-    #[serde(rename = "baseURL")]
-    pub base_url: i64,
-
-    /// Index of content language in string table
-    #[serde(rename = "contentLanguage")]
-    pub content_language: i64,
-
-    /// Index of encoding name in string table
-    #[serde(rename = "encodingName")]
-    pub encoding_name: i64,
-
-    /// Index of public ID in string table
-    #[serde(rename = "publicId")]
-    pub public_id: i64,
-
-    /// Index of system ID in string table
-    #[serde(rename = "systemId")]
-    pub system_id: i64,
-
-    /// Index of frame ID in string table
-    #[serde(rename = "frameId")]
-    pub frame_id: i64,
-
-    /// DOM node tree
-    pub nodes: NodeTreeSnapshot,
-
-    /// Layout tree (positions, styles, text)
-    pub layout: LayoutTreeSnapshot,
-
-    /// Text boxes
-    #[serde(rename = "textBoxes")]
-    pub text_boxes: TextBoxSnapshot,
-
-    /// Scroll offset X
-    #[serde(rename = "scrollOffsetX")]
-    pub scroll_offset_x: Option<f64>,
-
-    /// Scroll offset Y
-    #[serde(rename = "scrollOffsetY")]
-    pub scroll_offset_y: Option<f64>,
-
-    /// Content width
-    #[serde(rename = "contentWidth")]
-    pub content_width: Option<f64>,
-
-    /// Content height
-    #[serde(rename = "contentHeight")]
-    pub content_height: Option<f64>,
-}
-
-/// Snapshot of the DOM node tree structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NodeTreeSnapshot {
-    /// Parent node index (parallel array)
-    #[serde(rename = "parentIndex", default)]
-    pub parent_index: Option<Vec<i64>>,
-
-    /// Node type (parallel array)
-    #[serde(rename = "nodeType", default)]
-    pub node_type: Option<Vec<i64>>,
-
-    /// Node name (string index, parallel array)
-    #[serde(rename = "nodeName", default)]
-    pub node_name: Option<Vec<i64>>,
-
-    /// Node value (string index, parallel array)
-    #[serde(rename = "nodeValue", default)]
-    pub node_value: Option<Vec<i64>>,
-
-    /// Backend node ID (parallel array)
-    #[serde(rename = "backendNodeId", default)]
-    pub backend_node_id: Option<Vec<i64>>,
-
-    /// Attributes (array of string index arrays)
-    #[serde(default)]
-    pub attributes: Option<Vec<Vec<i64>>>,
-
-    /// Text value for text nodes
-    #[serde(rename = "textValue", default)]
-    pub text_value: Option<RareStringData>,
-
-    /// Input value for input elements
-    #[serde(rename = "inputValue", default)]
-    pub input_value: Option<RareStringData>,
-
-    /// Current source URL for images/media
-    #[serde(rename = "currentSourceURL", default)]
-    pub current_source_url: Option<RareStringData>,
-
-    /// Origin URL
-    #[serde(rename = "originURL", default)]
-    pub origin_url: Option<RareStringData>,
-
-    /// Is clickable
-    #[serde(rename = "isClickable", default)]
-    pub is_clickable: Option<RareBooleanData>,
-}
-
-/// Layout tree snapshot with visual information
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LayoutTreeSnapshot {
-    /// Node index (maps to NodeTreeSnapshot, parallel array)
-    #[serde(rename = "nodeIndex")]
-    pub node_index: Vec<i64>,
-
-    /// Computed styles (array of string index arrays)
-    pub styles: Vec<Vec<i64>>,
-
-    /// Bounding rectangles (parallel array)
-    pub bounds: Vec<Rectangle>,
-
-    /// Text content (string index, parallel array)
-    pub text: Vec<i64>,
-
-    /// Stacking contexts
-    #[serde(rename = "stackingContexts", default)]
-    pub stacking_contexts: Option<RareBooleanData>,
-
-    /// Paint orders (parallel array)
-    #[serde(rename = "paintOrders", default)]
-    pub paint_orders: Option<Vec<i64>>,
-
-    /// Offset rectangles
-    #[serde(rename = "offsetRects", default)]
-    pub offset_rects: Option<Vec<Rectangle>>,
-
-    /// Scroll rectangles
-    #[serde(rename = "scrollRects", default)]
-    pub scroll_rects: Option<Vec<Rectangle>>,
-
-    /// Client rectangles
-    #[serde(rename = "clientRects", default)]
-    pub client_rects: Option<Vec<Rectangle>>,
-
-    /// Blended background colors (string index array)
-    #[serde(rename = "blendedBackgroundColors", default)]
-    pub blended_background_colors: Option<Vec<i64>>,
-
-    /// Text color opacities
-    #[serde(rename = "textColorOpacities", default)]
-    pub text_color_opacities: Option<Vec<f64>>,
-}
-
-/// Text box snapshot
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TextBoxSnapshot {
-    /// Layout index (maps to LayoutTreeSnapshot)
-    #[serde(rename = "layoutIndex")]
-    pub layout_index: Vec<i64>,
-
-    /// Start position in text
-    pub start: Vec<i64>,
-
-    /// Length of text
-    pub length: Vec<i64>,
-
-    /// Bounding rectangles
-    pub bounds: Vec<Rectangle>,
-}
-
-/// Rectangle [x, y, width, height]
-pub type Rectangle = Vec<f64>;
-
-/// Sparse boolean data (only stores true values with their indexes)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RareBooleanData {
-    /// Indexes where the value is true
-    pub index: Vec<i64>,
-}
-
-/// Sparse integer data
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RareIntegerData {
-    /// Indexes
-    pub index: Vec<i64>,
-
-    /// Values at those indexes
-    pub value: Vec<i64>,
-}
-
-/// Sparse string data
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RareStringData {
-    /// Indexes
-    pub index: Vec<i64>,
+impl StepFrame {
+    /// Element under screenshot pixel `(x, y)`, looked up in this frame's VisualDom
+    ///
+    /// Grounds coordinate outputs of vision models ("click at 412,305") in the page
+    /// as it was captured. Returns None for pixels outside the screenshot or over no
+    /// element; fails if the frame has no VisualDom or viewport metadata. See
+    /// [`StepFrame::element_in`] for an already loaded snapshot.
+    pub async fn element_at(&self, x: f64, y: f64) -> Result<Option<FrameElement>> {
+        if self.viewport.is_none() || self.screenshot.dimensions.is_none() {
+            return Err(BrowserError::Other(
+                "Frame has no viewport metadata or screenshot dimensions".to_string(),
+            ));
+        }
+        let Some(visual_dom) = &self.visual_dom else {
+            return Err(BrowserError::Other("Frame has no VisualDom".to_string()));
+        };
+        let snapshot = CaptureSnapshotResponse::from_file(Path::new(&visual_dom.path)).await?;
+        Ok(self.element_in(&snapshot, x, y))
+    }
 
-    /// String table indexes at those indexes
-    pub value: Vec<i64>,
+    /// Interactive elements of this frame's page, most salient first, ranked from its
+    /// VisualDom (see [`CaptureSnapshotResponse::rank_elements`])
+    ///
+    /// Fails if the frame has no VisualDom or viewport metadata.
+    pub async fn rank_elements(&self) -> Result<Vec<SalientElement>> {
+        let Some(viewport) = &self.viewport else {
+            return Err(BrowserError::Other(
+                "Frame has no viewport metadata".to_string(),
+            ));
+        };
+        let Some(visual_dom) = &self.visual_dom else {
+            return Err(BrowserError::Other("Frame has no VisualDom".to_string()));
+        };
+        let snapshot = CaptureSnapshotResponse::from_file(Path::new(&visual_dom.path)).await?;
+        Ok(snapshot.rank_elements(viewport))
+    }
 }
 
-/// Action being performed
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ActionInfo {
-    /// Action type
-    pub action_type: String,
-
-    /// High-level description of intent
-    pub intent: String,
-
-    /// CSS selector or description of target
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub target: Option<String>,
+impl CaptureSnapshotResponse {
+    /// Load a saved VisualDom file (any format or compression; see [`read_visual_dom`])
+    ///
+    /// Delta files are rebuilt into the full snapshot (see [`reconstruct_visual_dom`]).
+    pub async fn from_file(path: &Path) -> Result<Self> {
+        let value = reconstruct_visual_dom(path).await?;
+        serde_json::from_value(value)
+            .map_err(|e| BrowserError::Other(format!("Invalid VisualDom snapshot: {}", e)))
+    }
 }
 
-/// Natural language transcript
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TranscriptInfo {
-    /// Description of what is happening
-    pub action_description: String,
-
-    /// Why this action was chosen
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub reasoning: Option<String>,
-
-    /// What should happen next
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub expected_outcome: Option<String>,
+/// Where [`capture_step_frame`] puts target element screenshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetScreenshotMode {
+    /// Don't capture them
+    Off,
+    /// Save them next to the screenshot (`frame_0001.target.png`)
+    File,
+    /// Embed them in `ActionInfo` as base64
+    Inline,
 }
 
 // ===== CAPTURE OPTIONS =====
@@ -388,6 +100,19 @@ pub struct CaptureOptions {
     /// Screenshot format (png, jpeg)
     pub screenshot_format: ScreenshotFormat,
 
+    /// Downscale screenshots larger than this to fit (aspect ratio kept)
+    pub max_screenshot_size: Option<Dimensions>,
+
+    /// Save a thumbnail this many pixels wide next to each screenshot
+    pub thumbnail_width: Option<u32>,
+
+    /// Crop the element an action targets out of the screenshot into its `ActionInfo`
+    pub target_screenshot: TargetScreenshotMode,
+
+    /// Also save a copy of the screenshot with the interactive elements labelled, and
+    /// a JSON file mapping the labels to elements (see [`crate::browser::marks`])
+    pub set_of_marks: bool,
+
     /// Whether to save the HTML DOM
     pub save_html: bool,
 
@@ -406,11 +131,49 @@ pub struct CaptureOptions {
     /// Whether to include images as base64 in VisualDom
     pub visual_dom_include_images: bool,
 
+    /// Size and time limits for images embedded in VisualDom
+    pub visual_dom_image_budget: ImageBudget,
+
+    /// Encoding of saved VisualDom files
+    pub visual_dom_format: VisualDomFormat,
+
+    /// Compression of saved VisualDom files
+    pub visual_dom_compression: VisualDomCompression,
+
+    /// Save VisualDom frames as deltas against the previous frame (shared across captures)
+    pub visual_dom_deltas: Option<VisualDomDeltas>,
+
+    /// What to strip, inline, and cap in saved HTML
+    pub html: HtmlOptions,
+
+    /// Whether to save an MHTML archive (page plus subresources) in `dom_dir`
+    ///
+    /// Skipped for frames with a `redaction`, since the archive can't be masked.
+    pub save_mhtml: bool,
+
     /// Whether to compute SHA-256 hashes
     pub compute_hashes: bool,
 
     /// Whether to extract interactive elements (expensive)
     pub extract_interactive_elements: bool,
+
+    /// Elements and regions hidden in everything saved for the frame
+    pub redaction: Option<Redaction>,
+
+    /// Where to move the frame's files once captured (see [`crate::frame_store`]);
+    /// None keeps them in the directories above
+    pub store: Option<Arc<dyn FrameStore>>,
+
+    /// Index to record the frame in, under the driver's session id
+    #[cfg(feature = "sqlite")]
+    pub index: Option<Arc<crate::frame_index::FrameIndex>>,
+
+    /// Disk limits checked before anything is written, and charged with the frame's
+    /// files (unless they go to a `store`)
+    pub quota: Option<Arc<ArtifactQuota>>,
+
+    /// Token checked between capture stages to abort a capture early
+    pub cancel_token: Option<CancellationToken>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -419,6 +182,26 @@ pub enum ScreenshotFormat {
     Jpeg,
 }
 
+/// Encoding of saved VisualDom files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualDomFormat {
+    /// Pretty-printed JSON (`.visualdom.json`)
+    Json,
+    /// MessagePack, a compact binary encoding of the same data (`.visualdom.msgpack`)
+    MessagePack,
+}
+
+/// Compression of saved VisualDom files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualDomCompression {
+    /// Uncompressed
+    None,
+    /// gzip (`.gz`), readable by standard tools
+    Gzip,
+    /// zstd (`.zst`), smaller and faster than gzip
+    Zstd,
+}
+
 impl Default for CaptureOptions {
     fn default() -> Self {
         Self {
@@ -426,14 +209,30 @@ impl Default for CaptureOptions {
             dom_dir: Some(PathBuf::from("./dom")),
             visual_dom_dir: Some(PathBuf::from("./visualdom")),
             screenshot_format: ScreenshotFormat::Png,
+            max_screenshot_size: None,
+            thumbnail_width: None,
+            target_screenshot: TargetScreenshotMode::File,
+            set_of_marks: false,
             save_html: true,
             capture_visual_dom: false, // Opt-in only
             visual_dom_computed_styles: Self::balanced_computed_styles(),
             visual_dom_include_dom_rects: true,
             visual_dom_include_paint_order: true,
             visual_dom_include_images: true,
+            visual_dom_image_budget: ImageBudget::default(),
+            visual_dom_format: VisualDomFormat::Json,
+            visual_dom_compression: VisualDomCompression::None,
+            visual_dom_deltas: None,
+            html: HtmlOptions::default(),
+            save_mhtml: false,
             compute_hashes: true,
             extract_interactive_elements: false,
+            redaction: None,
+            store: None,
+            #[cfg(feature = "sqlite")]
+            index: None,
+            quota: None,
+            cancel_token: None,
         }
     }
 }
@@ -485,6 +284,24 @@ impl CaptureOptions {
     pub fn all_computed_styles() -> Vec<String> {
         vec![]
     }
+
+    /// Whether the capture has been cancelled via `cancel_token`
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel_token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+    }
+
+    /// `Err(Cancelled)` if the capture has been cancelled, so it stops before its next step
+    // Same error type as the capture functions it's called from with `?`
+    #[allow(clippy::result_large_err)]
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            tracing::info!("Step frame capture cancelled");
+            return Err(BrowserError::Cancelled);
+        }
+        Ok(())
+    }
 }
 
 // ===== CAPTURE FUNCTION =====
@@ -532,6 +349,7 @@ impl CaptureOptions {
 ///     action_type: "navigate".to_string(),
 ///     intent: "Navigate to example.com".to_string(),
 ///     target: None,
+///     target_screenshot: None,
 /// });
 ///
 /// let frame = capture_step_frame(&driver, 0, 0, &options, None, action).await?;
@@ -539,33 +357,33 @@ impl CaptureOptions {
 /// # Ok(())
 /// # }
 /// ```
+#[tracing::instrument(
+    name = "capture_step_frame",
+    skip_all,
+    fields(session_id = %driver.session_id(), frame_id)
+)]
 pub async fn capture_step_frame(
     driver: &ChromeDriver,
     frame_id: usize,
     elapsed_ms: u64,
     options: &CaptureOptions,
     user_instruction: Option<String>,
-    action_info: Option<ActionInfo>,
+    mut action_info: Option<ActionInfo>,
 ) -> Result<StepFrame> {
-    log::info!("╔═══════════════════════════════════════════════════════════╗");
-    log::info!(
-        "║  📸 CAPTURING STEP FRAME {}                              ║",
-        frame_id
+    tracing::info!(
+        elapsed_ms,
+        instruction = user_instruction.as_deref(),
+        action_type = action_info.as_ref().map(|a| a.action_type.as_str()),
+        intent = action_info.as_ref().map(|a| a.intent.as_str()),
+        "Capturing step frame"
     );
-    log::info!("╚═══════════════════════════════════════════════════════════╝");
 
-    if let Some(ref instruction) = user_instruction {
-        log::info!("📝 User instruction: {}", instruction);
-    }
-    if let Some(ref action) = action_info {
-        log::info!("🎯 Action: {} - {}", action.action_type, action.intent);
-    }
-    log::info!("⏱️  Elapsed: {}ms", elapsed_ms);
+    options.check_cancelled()?;
 
     // 1. FAIL FAST: Access current page to verify connection
-    log::debug!("🔍 Verifying browser connection...");
+    tracing::debug!("Verifying browser connection...");
     let page = driver.current_page().await.map_err(|e| {
-        log::error!("❌ Failed to access browser page: {}", e);
+        tracing::error!(error = %e, "Failed to access browser page");
         BrowserError::Other(format!(
             "Failed to access browser page (connection failed): {}",
             e
@@ -574,60 +392,247 @@ pub async fn capture_step_frame(
 
     // Verify page is accessible by getting URL
     let _ = page.url().await.map_err(|e| {
-        log::error!("❌ Failed to get page URL: {}", e);
+        tracing::error!(error = %e, "Failed to get page URL");
         BrowserError::Other(format!(
             "Failed to get page URL (browser not responding): {}",
             e
         ))
     })?;
 
-    log::debug!("✓ Browser connection verified");
+    tracing::debug!("Browser connection verified");
+
+    // Locate redacted elements before anything is captured
+    let redaction = match &options.redaction {
+        Some(redaction) => Some(redaction.resolve(driver).await?),
+        None => None,
+    };
 
     // 2. TAKE SCREENSHOT
-    log::info!("📸 Capturing screenshot...");
+    options.check_cancelled()?;
+    tracing::debug!("Capturing screenshot...");
     let screenshot_filename = format!(
         "frame_{:04}.{}",
         frame_id,
         format_extension(options.screenshot_format)
     );
     let screenshot_path = options.screenshot_dir.join(&screenshot_filename);
-    log::debug!("Screenshot path: {:?}", screenshot_path);
+    tracing::debug!("Screenshot path: {:?}", screenshot_path);
+
+    // Refuse before writing anything rather than leave a partial frame behind
+    if let Some(quota) = &options.quota {
+        quota.check(&options.screenshot_dir).await?;
+    }
 
     // Ensure screenshot directory exists
     tokio::fs::create_dir_all(&options.screenshot_dir)
         .await
         .map_err(|e| {
-            log::error!("❌ Failed to create screenshot directory: {}", e);
+            tracing::error!(error = %e, "Failed to create screenshot directory");
             BrowserError::Other(format!("Failed to create screenshot directory: {}", e))
         })?;
 
-    // Capture screenshot
-    driver.screenshot_to_file(&screenshot_path).await?;
-    log::info!("✓ Screenshot captured: {}", screenshot_filename);
+    // Viewport geometry goes with the screenshot, so read it right before taking it
+    let viewport = match capture_viewport(&page).await {
+        Ok(viewport) => Some(viewport),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to read viewport metrics");
+            None
+        }
+    };
+
+    // Measure the action's target with the viewport, so its box matches the screenshot
+    let target = action_info
+        .as_ref()
+        .and_then(|action| action.target.as_deref())
+        .filter(|_| options.target_screenshot != TargetScreenshotMode::Off);
+    let target_rect = match target {
+        // Targets can be descriptions rather than selectors, so failures only skip the crop
+        Some(target) => match crate::browser::geometry::element_rect(&page, target).await {
+            Ok(rect) if rect.visible && rect.in_viewport => Some(rect.viewport),
+            Ok(_) => {
+                tracing::debug!(target, "Action target is not in view");
+                None
+            }
+            Err(e) => {
+                tracing::debug!(target, error = %e, "Action target not measured");
+                None
+            }
+        },
+        None => None,
+    };
+
+    // Capture screenshot into memory once; size, dimensions, and hash come from the buffer
+    let screenshot_data = driver.screenshot().await?;
+    tracing::debug!(file = %screenshot_filename, "Screenshot captured");
+
+    // Label the interactive elements in a second screenshot (set-of-marks)
+    let marked = if options.set_of_marks {
+        let redacted = redaction
+            .as_ref()
+            .map_or(&[][..], ResolvedRedaction::selectors);
+        let (data, marks) = driver.screenshot_with_marks(redacted).await?;
+        tracing::debug!(count = marks.len(), "Set-of-marks screenshot captured");
+        Some((data, marks))
+    } else {
+        None
+    };
+
+    // Redact before the screenshots go anywhere else
+    let (screenshot_data, marked) = match &redaction {
+        Some(redaction) => {
+            let format = options.screenshot_format;
+            let screenshot_data = redact_screenshot(redaction, screenshot_data, format).await?;
+            let marked = match marked {
+                Some((data, marks)) => {
+                    Some((redact_screenshot(redaction, data, format).await?, marks))
+                }
+                None => None,
+            };
+            (screenshot_data, marked)
+        }
+        None => (screenshot_data, marked),
+    };
+
+    // Crop the target out of the full-resolution (redacted) screenshot
+    let target_crop = match (target_rect, &viewport) {
+        (Some(rect), Some(viewport)) => {
+            let data = screenshot_data.clone();
+            let viewport_width = viewport.width;
+            let format = options.screenshot_format;
+            match tokio::task::spawn_blocking(move || {
+                crop_target(&data, rect, viewport_width, format)
+            })
+            .await
+            {
+                Ok(Ok(crop)) => crop.map(|(data, dimensions)| (data, dimensions, rect)),
+                Ok(Err(e)) => {
+                    tracing::warn!(error = %e, "Failed to crop target screenshot");
+                    None
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Target screenshot crop panicked");
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
 
-    // Get screenshot file size
-    let screenshot_metadata = tokio::fs::metadata(&screenshot_path)
+    // Downscale and make a thumbnail if requested
+    let ResizedScreenshot {
+        data: screenshot_data,
+        original_dimensions,
+        thumbnail,
+    } = if options.max_screenshot_size.is_some() || options.thumbnail_width.is_some() {
+        let format = options.screenshot_format;
+        let max_size = options.max_screenshot_size.clone();
+        let thumbnail_width = options.thumbnail_width;
+        tokio::task::spawn_blocking(move || {
+            resize_screenshot(screenshot_data, format, max_size.as_ref(), thumbnail_width)
+        })
         .await
-        .map_err(|e| BrowserError::Other(format!("Failed to read screenshot metadata: {}", e)))?;
+        .map_err(|e| BrowserError::Other(format!("Screenshot resize panicked: {}", e)))?
+        .map_err(|e| BrowserError::Other(format!("Failed to resize screenshot: {}", e)))?
+    } else {
+        ResizedScreenshot {
+            data: screenshot_data,
+            original_dimensions: None,
+            thumbnail: None,
+        }
+    };
 
-    let screenshot_size = screenshot_metadata.len() as usize;
+    let screenshot_size = screenshot_data.len();
+    let screenshot_dimensions = image_dimensions(&screenshot_data);
 
     // Optionally compute screenshot hash
-    let screenshot_hash = if options.compute_hashes {
-        Some(compute_file_hash(&screenshot_path).await?)
-    } else {
-        None
+    let screenshot_hash = options
+        .compute_hashes
+        .then(|| compute_bytes_hash(&screenshot_data));
+
+    // Write the files in the background while the DOM is captured
+    let mut screenshot_writes = vec![tokio::spawn(tokio::fs::write(
+        screenshot_path.clone(),
+        screenshot_data,
+    ))];
+    let thumbnail_info = thumbnail.map(|(data, dimensions)| {
+        let path = options.screenshot_dir.join(format!(
+            "frame_{:04}.thumb.{}",
+            frame_id,
+            format_extension(options.screenshot_format)
+        ));
+        let info = ThumbnailInfo {
+            path: path.to_string_lossy().to_string(),
+            size_bytes: data.len(),
+            dimensions,
+        };
+        screenshot_writes.push(tokio::spawn(tokio::fs::write(path, data)));
+        info
+    });
+    let marked_info = match marked {
+        Some((data, marks)) => {
+            let path = options.screenshot_dir.join(format!(
+                "frame_{:04}.marks.{}",
+                frame_id,
+                format_extension(options.screenshot_format)
+            ));
+            let marks_path = options
+                .screenshot_dir
+                .join(format!("frame_{:04}.marks.json", frame_id));
+            let json = serde_json::to_vec_pretty(&marks)
+                .map_err(|e| BrowserError::Other(format!("Failed to serialize marks: {}", e)))?;
+            let info = MarkedScreenshot {
+                path: path.to_string_lossy().to_string(),
+                marks_path: marks_path.to_string_lossy().to_string(),
+                size_bytes: data.len(),
+                count: marks.len(),
+            };
+            screenshot_writes.push(tokio::spawn(tokio::fs::write(path, data)));
+            screenshot_writes.push(tokio::spawn(tokio::fs::write(marks_path, json)));
+            Some(info)
+        }
+        None => None,
     };
+    if let (Some(action), Some((data, dimensions, rect))) = (action_info.as_mut(), target_crop) {
+        let size_bytes = data.len();
+        let (path, data) = match options.target_screenshot {
+            TargetScreenshotMode::Inline => (
+                None,
+                Some(base64::engine::general_purpose::STANDARD.encode(&data)),
+            ),
+            _ => {
+                let path = options.screenshot_dir.join(format!(
+                    "frame_{:04}.target.{}",
+                    frame_id,
+                    format_extension(options.screenshot_format)
+                ));
+                let path_string = path.to_string_lossy().to_string();
+                screenshot_writes.push(tokio::spawn(tokio::fs::write(path, data)));
+                (Some(path_string), None)
+            }
+        };
+        action.target_screenshot = Some(TargetScreenshot {
+            path,
+            data,
+            size_bytes,
+            dimensions,
+            rect,
+        });
+    }
 
     // 3. SAVE DOM
-    log::info!("📄 Extracting DOM...");
+    options.check_cancelled()?;
+    tracing::debug!("Extracting DOM...");
     let url = driver.current_url().await?;
     let title = driver.title().await?;
-    log::debug!("URL: {}", url);
-    log::debug!("Title: {}", title);
-    let html_content = driver.get_page_source().await?;
-    log::info!("✓ DOM extracted ({} KB)", html_content.len() / 1024);
+    tracing::debug!(url = %url, title = %title, "Page identified");
+    let captured_html = capture_html(driver, &options.html, redaction.as_ref()).await?;
+    let html_content = captured_html.html;
+    tracing::debug!(html_kb = html_content.len() / 1024, "DOM extracted");
+    if let Some(original_size) = captured_html.original_size {
+        tracing::debug!(original_kb = original_size / 1024, "HTML truncated");
+    }
 
+    let mut text_path = None;
     let (html_path, html_hash) = if options.save_html {
         if let Some(dom_dir) = &options.dom_dir {
             // Ensure DOM directory exists
@@ -643,6 +648,14 @@ pub async fn capture_step_frame(
                 .await
                 .map_err(|e| BrowserError::Other(format!("Failed to write HTML file: {}", e)))?;
 
+            if let Some(text) = &captured_html.text {
+                let text_file_path = dom_dir.join(format!("frame_{:04}.txt", frame_id));
+                tokio::fs::write(&text_file_path, text).await.map_err(|e| {
+                    BrowserError::Other(format!("Failed to write text file: {}", e))
+                })?;
+                text_path = Some(text_file_path.to_string_lossy().to_string());
+            }
+
             // Compute hash if requested
             let hash = if options.compute_hashes {
                 Some(compute_string_hash(&html_content))
@@ -664,29 +677,64 @@ pub async fn capture_step_frame(
         (None, None)
     };
 
+    // MHTML archives can't be masked, so they're never saved for redacted frames
+    let mhtml_path = match &options.dom_dir {
+        Some(_) if options.save_mhtml && redaction.is_some() => {
+            tracing::warn!("Skipping MHTML archive for a redacted step frame");
+            None
+        }
+        Some(dom_dir) if options.save_mhtml => {
+            tracing::debug!("Capturing MHTML archive...");
+            tokio::fs::create_dir_all(dom_dir).await.map_err(|e| {
+                BrowserError::Other(format!("Failed to create DOM directory: {}", e))
+            })?;
+            let mhtml_file_path = dom_dir.join(format!("frame_{:04}.mhtml", frame_id));
+            driver.capture_mhtml(&mhtml_file_path).await?;
+            Some(mhtml_file_path.to_string_lossy().to_string())
+        }
+        _ => None,
+    };
+
     // 4. EXTRACT INTERACTIVE ELEMENTS (optional, expensive)
+    options.check_cancelled()?;
     let interactive_elements = if options.extract_interactive_elements {
-        log::info!("🔍 Extracting interactive elements...");
-        let elements = extract_interactive_elements_from_page(driver).await?;
-        log::info!("✓ Found {} interactive elements", elements.len());
+        tracing::debug!("Extracting interactive elements...");
+        let elements = extract_interactive_elements_from_page(
+            driver,
+            redaction.as_ref().map_or(&[], ResolvedRedaction::selectors),
+        )
+        .await?;
+        tracing::debug!(count = elements.len(), "Interactive elements extracted");
         Some(elements)
     } else {
         None
     };
 
     // 5. CAPTURE VISUALDOM (optional)
+    options.check_cancelled()?;
     let visual_dom_info = if options.capture_visual_dom {
-        log::info!("🗺️  Capturing VisualDom...");
+        tracing::debug!("Capturing VisualDom...");
 
         // Capture the VisualDom data
-        let visual_dom_data = driver
-            .capture_visual_dom(
+        let mut visual_dom_data = driver
+            .capture_visual_dom_with_budget(
                 &options.visual_dom_computed_styles,
                 options.visual_dom_include_dom_rects,
                 options.visual_dom_include_paint_order,
                 options.visual_dom_include_images,
+                &options.visual_dom_image_budget,
             )
             .await?;
+        if let Some(redaction) = &redaction {
+            redaction.redact_visual_dom(&mut visual_dom_data);
+        }
+        let image_stats = ImageStats::from_snapshot(&visual_dom_data);
+        let truncated = image_stats
+            .as_ref()
+            .and_then(|stats| stats.truncation(&options.visual_dom_image_budget));
+        if let Some(reason) = &truncated {
+            tracing::warn!(reason = %reason, "VisualDom images truncated");
+        }
 
         // Parse to get node count
         let node_count = visual_dom_data
@@ -699,7 +747,7 @@ pub async fn capture_step_frame(
             .map(|arr| arr.len())
             .unwrap_or(0);
 
-        log::info!("✓ VisualDom captured ({} nodes)", node_count);
+        tracing::debug!(node_count, "VisualDom captured");
 
         // Save to file if directory specified
         if let Some(visual_dom_dir) = &options.visual_dom_dir {
@@ -710,43 +758,59 @@ pub async fn capture_step_frame(
                     BrowserError::Other(format!("Failed to create VisualDom directory: {}", e))
                 })?;
 
-            let visual_dom_filename = format!("frame_{:04}.visualdom.json", frame_id);
+            let visual_dom_filename = format!(
+                "frame_{:04}.{}",
+                frame_id,
+                visual_dom_extension(options.visual_dom_format, options.visual_dom_compression)
+            );
             let visual_dom_file_path = visual_dom_dir.join(&visual_dom_filename);
 
-            // Save VisualDom to file
-            let visual_dom_json = serde_json::to_string_pretty(&visual_dom_data).map_err(|e| {
-                BrowserError::Other(format!("Failed to serialize VisualDom: {}", e))
-            })?;
-
-            tokio::fs::write(&visual_dom_file_path, &visual_dom_json)
-                .await
-                .map_err(|e| {
-                    BrowserError::Other(format!("Failed to write VisualDom file: {}", e))
-                })?;
+            // Replace the snapshot with a delta against the previous frame if enabled
+            let (visual_dom_data, delta_frame) = match &options.visual_dom_deltas {
+                Some(deltas) => {
+                    let deltas = deltas.clone();
+                    let (data, frame) =
+                        tokio::task::spawn_blocking(move || deltas.encode(visual_dom_data))
+                            .await
+                            .map_err(|e| {
+                                BrowserError::Other(format!("VisualDom delta panicked: {}", e))
+                            })?;
+                    (data, Some(frame))
+                }
+                None => (visual_dom_data, None),
+            };
+            let delta_base = delta_frame.as_ref().and_then(|frame| frame.base.clone());
+
+            // Stream VisualDom to file (never holding the serialized data in memory)
+            let (visual_dom_size, hash) = write_visual_dom(
+                visual_dom_file_path.clone(),
+                visual_dom_data,
+                options.visual_dom_format,
+                options.visual_dom_compression,
+            )
+            .await?;
 
-            // Get file size
-            let visual_dom_metadata =
-                tokio::fs::metadata(&visual_dom_file_path)
-                    .await
-                    .map_err(|e| {
-                        BrowserError::Other(format!("Failed to read VisualDom metadata: {}", e))
-                    })?;
-            let visual_dom_size = visual_dom_metadata.len() as usize;
+            if let (Some(deltas), Some(frame)) = (&options.visual_dom_deltas, delta_frame) {
+                deltas.record(visual_dom_filename.clone(), frame);
+            }
 
-            // Compute hash if requested
-            let visual_dom_hash = if options.compute_hashes {
-                Some(compute_string_hash(&visual_dom_json))
-            } else {
-                None
-            };
+            // Keep hash if requested
+            let visual_dom_hash = options.compute_hashes.then_some(hash);
 
-            log::info!("   VisualDom: {} KB", visual_dom_size / 1024);
+            tracing::debug!(
+                visual_dom_kb = visual_dom_size / 1024,
+                delta_base = delta_base.as_deref(),
+                "VisualDom saved"
+            );
 
             Some(VisualDomInfo {
                 path: visual_dom_file_path.to_string_lossy().to_string(),
                 size_bytes: visual_dom_size,
                 node_count,
                 hash: visual_dom_hash,
+                images: image_stats,
+                truncated,
+                delta_base,
             })
         } else {
             // No directory specified, skip saving
@@ -772,19 +836,23 @@ pub async fn capture_step_frame(
     };
 
     // 7. CONSTRUCT STEP FRAME
-    log::info!("✅ Step frame {} captured successfully", frame_id);
-    log::info!("   Screenshot: {} KB", screenshot_size / 1024);
-    log::info!("   DOM: {} KB", html_content.len() / 1024);
-    if let Some(ref vd) = visual_dom_info {
-        log::info!(
-            "   VisualDom: {} KB ({} nodes)",
-            vd.size_bytes / 1024,
-            vd.node_count
-        );
+    for write in screenshot_writes {
+        write
+            .await
+            .map_err(|e| BrowserError::Other(format!("Screenshot writer panicked: {}", e)))?
+            .map_err(|e| BrowserError::Other(format!("Failed to write screenshot: {}", e)))?;
     }
-    log::info!("   URL: {}", url);
 
-    Ok(StepFrame {
+    tracing::info!(
+        url = %url,
+        screenshot_kb = screenshot_size / 1024,
+        dom_kb = html_content.len() / 1024,
+        visual_dom_kb = visual_dom_info.as_ref().map(|vd| vd.size_bytes / 1024),
+        visual_dom_nodes = visual_dom_info.as_ref().map(|vd| vd.node_count),
+        "Step frame captured"
+    );
+
+    let mut frame = StepFrame {
         frame_id,
         timestamp: chrono::Utc::now().to_rfc3339(),
         elapsed_ms,
@@ -792,24 +860,148 @@ pub async fn capture_step_frame(
             path: screenshot_path.to_string_lossy().to_string(),
             format: format_string(options.screenshot_format),
             size_bytes: screenshot_size,
-            dimensions: None, // Could be extracted from image metadata
+            dimensions: screenshot_dimensions,
             hash: screenshot_hash,
+            original_dimensions,
+            thumbnail: thumbnail_info,
+            marked: marked_info,
         },
+        viewport,
         dom: DomInfo {
             url,
             title,
             html_path,
             html_hash,
+            html_original_size: captured_html.original_size,
+            text_path,
+            mhtml_path,
             interactive_elements,
         },
         visual_dom: visual_dom_info,
         action: action_info,
         transcript,
-    })
+        browser: Some(driver.browser_info().clone()),
+    };
+
+    // 8. MOVE ARTIFACTS TO THE STORE (optional), or count them against the quota
+    if let Some(store) = &options.store {
+        store_artifacts(&mut frame, store.as_ref()).await?;
+    } else if let Some(quota) = &options.quota {
+        let mut written = 0;
+        for path in artifact_paths(&mut frame) {
+            if let Ok(metadata) = tokio::fs::metadata(path.as_str()).await {
+                written += metadata.len();
+            }
+        }
+        quota.record(written);
+    }
+
+    // 9. RECORD IN THE INDEX (optional)
+    #[cfg(feature = "sqlite")]
+    if let Some(index) = &options.index {
+        index
+            .record_frame(driver.session_id(), &frame)
+            .map_err(|e| BrowserError::Other(format!("Failed to index step frame: {:#}", e)))?;
+    }
+
+    Ok(frame)
 }
 
 // ===== HELPER FUNCTIONS =====
 
+/// Obscure the redacted regions of an encoded screenshot off the async runtime
+async fn redact_screenshot(
+    redaction: &ResolvedRedaction,
+    data: Vec<u8>,
+    format: ScreenshotFormat,
+) -> Result<Vec<u8>> {
+    let redaction = redaction.clone();
+    tokio::task::spawn_blocking(move || redaction.redact_screenshot(&data, format))
+        .await
+        .map_err(|e| BrowserError::Other(format!("Screenshot redaction panicked: {}", e)))?
+        .map_err(|e| BrowserError::Other(format!("Failed to redact screenshot: {}", e)))
+}
+
+/// Move the frame's files into `store`, replacing their paths with the store's locations
+async fn store_artifacts(frame: &mut StepFrame, store: &dyn FrameStore) -> Result<()> {
+    for path in artifact_paths(frame) {
+        let local = PathBuf::from(path.as_str());
+        let key = artifact_key(&local);
+        let data = tokio::fs::read(&local)
+            .await
+            .map_err(|e| BrowserError::Other(format!("Failed to read {}: {}", path, e)))?;
+        *path = store
+            .put(&key, data)
+            .await
+            .map_err(|e| BrowserError::Other(format!("Failed to store {}: {:#}", key, e)))?;
+        if let Err(e) = tokio::fs::remove_file(&local).await {
+            tracing::warn!(path = %local.display(), error = %e, "Failed to remove stored artifact");
+        }
+    }
+    tracing::debug!(frame_id = frame.frame_id, "Step frame artifacts stored");
+    Ok(())
+}
+
+/// Paths of every file saved for the frame
+fn artifact_paths(frame: &mut StepFrame) -> Vec<&mut String> {
+    let mut paths = vec![&mut frame.screenshot.path];
+    paths.extend(frame.screenshot.thumbnail.as_mut().map(|t| &mut t.path));
+    if let Some(marked) = frame.screenshot.marked.as_mut() {
+        paths.push(&mut marked.path);
+        paths.push(&mut marked.marks_path);
+    }
+    paths.extend(frame.dom.html_path.as_mut());
+    paths.extend(frame.dom.text_path.as_mut());
+    paths.extend(frame.dom.mhtml_path.as_mut());
+    paths.extend(frame.visual_dom.as_mut().map(|v| &mut v.path));
+    paths.extend(
+        frame
+            .action
+            .as_mut()
+            .and_then(|a| a.target_screenshot.as_mut())
+            .and_then(|t| t.path.as_mut()),
+    );
+    paths
+}
+
+/// Store key of an artifact file: its directory name and file name
+fn artifact_key(path: &Path) -> String {
+    let name = |p: Option<&Path>| {
+        p.and_then(Path::file_name)
+            .map(|n| n.to_string_lossy().to_string())
+    };
+    match (name(path.parent()), name(Some(path))) {
+        (Some(dir), Some(file)) => format!("{}/{}", dir, file),
+        (None, Some(file)) => file,
+        _ => path.to_string_lossy().to_string(),
+    }
+}
+
+/// Viewport size, scroll position, and document size from `Page.getLayoutMetrics`
+pub(crate) async fn capture_viewport(page: &chromiumoxide::page::Page) -> Result<ViewportInfo> {
+    use chromiumoxide::cdp::browser_protocol::page::GetLayoutMetricsParams;
+
+    let metrics = page.execute(GetLayoutMetricsParams::default()).await?;
+    let layout = &metrics.result.css_layout_viewport;
+    let visual = &metrics.result.css_visual_viewport;
+    let content = &metrics.result.css_content_size;
+    let device_pixel_ratio: f64 = page
+        .evaluate("window.devicePixelRatio")
+        .await?
+        .into_value()
+        .unwrap_or(1.0);
+    Ok(ViewportInfo {
+        width: layout.client_width as f64,
+        height: layout.client_height as f64,
+        device_pixel_ratio,
+        scroll_x: layout.page_x as f64,
+        scroll_y: layout.page_y as f64,
+        scale: visual.scale,
+        document_width: content.width,
+        document_height: content.height,
+    })
+}
+
 fn format_extension(format: ScreenshotFormat) -> &'static str {
     match format {
         ScreenshotFormat::Png => "png",
@@ -824,41 +1016,315 @@ fn format_string(format: ScreenshotFormat) -> String {
     }
 }
 
-/// Compute SHA-256 hash of a file
-async fn compute_file_hash(path: &Path) -> Result<String> {
-    use sha2::{Digest, Sha256};
+/// Write `value` to `path` without building the whole encoded file in memory
+///
+/// Returns the number of bytes written and their SHA-256 hash.
+async fn write_visual_dom(
+    path: PathBuf,
+    value: serde_json::Value,
+    format: VisualDomFormat,
+    compression: VisualDomCompression,
+) -> Result<(usize, String)> {
+    let written = tokio::task::spawn_blocking(move || -> std::io::Result<(usize, String)> {
+        let file = std::fs::File::create(&path)?;
+        let mut writer = HashingWriter::new(std::io::BufWriter::new(file));
+        match compression {
+            VisualDomCompression::None => encode_visual_dom(&mut writer, &value, format)?,
+            VisualDomCompression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(&mut writer, flate2::Compression::default());
+                encode_visual_dom(&mut encoder, &value, format)?;
+                encoder.finish()?;
+            }
+            VisualDomCompression::Zstd => {
+                let mut encoder = zstd::stream::write::Encoder::new(&mut writer, 0)?;
+                encode_visual_dom(&mut encoder, &value, format)?;
+                encoder.finish()?;
+            }
+        }
+        writer.flush()?;
+        Ok(writer.finish())
+    })
+    .await
+    .map_err(|e| BrowserError::Other(format!("VisualDom writer panicked: {}", e)))?;
 
-    let contents = tokio::fs::read(path)
-        .await
-        .map_err(|e| BrowserError::Other(format!("Failed to read file for hashing: {}", e)))?;
+    written.map_err(|e| BrowserError::Other(format!("Failed to write VisualDom file: {}", e)))
+}
 
-    let mut hasher = Sha256::new();
-    hasher.update(&contents);
-    let hash = hasher.finalize();
+fn encode_visual_dom(
+    writer: &mut impl Write,
+    value: &serde_json::Value,
+    format: VisualDomFormat,
+) -> std::io::Result<()> {
+    match format {
+        VisualDomFormat::Json => serde_json::to_writer_pretty(writer, value)?,
+        VisualDomFormat::MessagePack => {
+            rmp_serde::encode::write(writer, value).map_err(std::io::Error::other)?
+        }
+    }
+    Ok(())
+}
 
-    Ok(format!("{:x}", hash))
+/// Read a saved VisualDom file, whatever its encoding and compression
+///
+/// gzip and zstd files are recognized by their magic bytes, MessagePack by not
+/// starting with JSON, so files from any [`CaptureOptions`] load the same way.
+pub async fn read_visual_dom(path: &Path) -> Result<serde_json::Value> {
+    let path = path.to_path_buf();
+    let value = tokio::task::spawn_blocking(move || -> std::io::Result<serde_json::Value> {
+        let file = std::io::BufReader::new(std::fs::File::open(&path)?);
+        decode_visual_dom(file)
+    })
+    .await
+    .map_err(|e| BrowserError::Other(format!("VisualDom reader panicked: {}", e)))?;
+
+    value.map_err(|e| BrowserError::Other(format!("Failed to read VisualDom file: {}", e)))
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+fn decode_visual_dom(reader: impl BufRead + 'static) -> std::io::Result<serde_json::Value> {
+    let mut reader = decompress(reader)?;
+    let head = reader.fill_buf()?;
+    let is_json = head
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_none_or(|b| *b == b'{' || *b == b'[');
+    if is_json {
+        Ok(serde_json::from_reader(reader)?)
+    } else {
+        rmp_serde::from_read(reader).map_err(std::io::Error::other)
+    }
+}
+
+/// Wrap `reader` in a decoder if it starts with gzip or zstd magic bytes
+fn decompress(mut reader: impl BufRead + 'static) -> std::io::Result<Box<dyn BufRead>> {
+    let head = reader.fill_buf()?;
+    if head.starts_with(GZIP_MAGIC) {
+        Ok(Box::new(std::io::BufReader::new(
+            flate2::read::GzDecoder::new(reader),
+        )))
+    } else if head.starts_with(ZSTD_MAGIC) {
+        Ok(Box::new(std::io::BufReader::new(
+            zstd::stream::read::Decoder::new(reader)?,
+        )))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// File extension for a VisualDom file in `format` with `compression`
+fn visual_dom_extension(format: VisualDomFormat, compression: VisualDomCompression) -> String {
+    let base = match format {
+        VisualDomFormat::Json => "visualdom.json",
+        VisualDomFormat::MessagePack => "visualdom.msgpack",
+    };
+    match compression {
+        VisualDomCompression::None => base.to_string(),
+        VisualDomCompression::Gzip => format!("{}.gz", base),
+        VisualDomCompression::Zstd => format!("{}.zst", base),
+    }
+}
+
+/// Writer that counts and hashes everything passing through it
+struct HashingWriter<W> {
+    inner: W,
+    hasher: sha2::Sha256,
+    written: usize,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        use sha2::Digest;
+
+        Self {
+            inner,
+            hasher: sha2::Sha256::new(),
+            written: 0,
+        }
+    }
+
+    /// Bytes written and their SHA-256 hash (hex)
+    fn finish(self) -> (usize, String) {
+        use sha2::Digest;
+
+        (self.written, format!("{:x}", self.hasher.finalize()))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        use sha2::Digest;
+
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.written += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
 }
 
 /// Compute SHA-256 hash of a string
 fn compute_string_hash(content: &str) -> String {
+    compute_bytes_hash(content.as_bytes())
+}
+
+/// Compute SHA-256 hash of a byte buffer
+fn compute_bytes_hash(content: &[u8]) -> String {
     use sha2::{Digest, Sha256};
 
     let mut hasher = Sha256::new();
-    hasher.update(content.as_bytes());
+    hasher.update(content);
     let hash = hasher.finalize();
 
     format!("{:x}", hash)
 }
 
+/// A screenshot after [`resize_screenshot`]
+struct ResizedScreenshot {
+    /// Screenshot to save (re-encoded only if it was downscaled)
+    data: Vec<u8>,
+    /// Size before downscaling, if it was downscaled
+    original_dimensions: Option<Dimensions>,
+    /// Encoded thumbnail and its size
+    thumbnail: Option<(Vec<u8>, Dimensions)>,
+}
+
+/// Downscale `data` to fit `max_size` and make a thumbnail `thumbnail_width` wide
+///
+/// Images are never upscaled. Resized images are encoded in `format`.
+fn resize_screenshot(
+    data: Vec<u8>,
+    format: ScreenshotFormat,
+    max_size: Option<&Dimensions>,
+    thumbnail_width: Option<u32>,
+) -> image::ImageResult<ResizedScreenshot> {
+    let image = image::load_from_memory(&data)?;
+    let size = Dimensions {
+        width: image.width(),
+        height: image.height(),
+    };
+
+    let thumbnail = match thumbnail_width {
+        Some(width) if width < size.width => {
+            let thumbnail = image.thumbnail(width, u32::MAX);
+            let dimensions = Dimensions {
+                width: thumbnail.width(),
+                height: thumbnail.height(),
+            };
+            Some((encode_image(&thumbnail, format)?, dimensions))
+        }
+        Some(_) => Some((encode_image(&image, format)?, size.clone())),
+        None => None,
+    };
+
+    let (data, original_dimensions) = match max_size {
+        Some(max) if size.width > max.width || size.height > max.height => {
+            let resized =
+                image.resize(max.width, max.height, image::imageops::FilterType::Triangle);
+            (encode_image(&resized, format)?, Some(size))
+        }
+        _ => (data, None),
+    };
+
+    Ok(ResizedScreenshot {
+        data,
+        original_dimensions,
+        thumbnail,
+    })
+}
+
+/// Margin kept around a target element when cropping it, in CSS pixels
+const TARGET_PADDING: f64 = 8.0;
+
+/// Largest target screenshot; bigger crops are shrunk to fit
+const TARGET_MAX_SIZE: u32 = 512;
+
+/// Crop `rect` (viewport CSS pixels, plus [`TARGET_PADDING`]) out of a screenshot of a
+/// viewport `viewport_width` CSS pixels wide
+///
+/// Returns None if the region lies outside the screenshot.
+fn crop_target(
+    data: &[u8],
+    rect: crate::browser::geometry::Rect,
+    viewport_width: f64,
+    format: ScreenshotFormat,
+) -> image::ImageResult<Option<(Vec<u8>, Dimensions)>> {
+    let image = image::load_from_memory(data)?.to_rgba8();
+    let ratio = image.width() as f64 / viewport_width;
+    let left = ((rect.x - TARGET_PADDING) * ratio).floor().max(0.0);
+    let top = ((rect.y - TARGET_PADDING) * ratio).floor().max(0.0);
+    let right = ((rect.x + rect.width + TARGET_PADDING) * ratio)
+        .ceil()
+        .min(image.width() as f64);
+    let bottom = ((rect.y + rect.height + TARGET_PADDING) * ratio)
+        .ceil()
+        .min(image.height() as f64);
+    if right <= left || bottom <= top {
+        return Ok(None);
+    }
+
+    let mut crop = image::DynamicImage::ImageRgba8(
+        image::imageops::crop_imm(
+            &image,
+            left as u32,
+            top as u32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        )
+        .to_image(),
+    );
+    if crop.width() > TARGET_MAX_SIZE || crop.height() > TARGET_MAX_SIZE {
+        crop = crop.thumbnail(TARGET_MAX_SIZE, TARGET_MAX_SIZE);
+    }
+    let dimensions = Dimensions {
+        width: crop.width(),
+        height: crop.height(),
+    };
+    Ok(Some((encode_image(&crop, format)?, dimensions)))
+}
+
+pub(crate) fn encode_image(
+    image: &image::DynamicImage,
+    format: ScreenshotFormat,
+) -> image::ImageResult<Vec<u8>> {
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    match format {
+        ScreenshotFormat::Png => image.write_to(&mut encoded, image::ImageFormat::Png)?,
+        // JPEG has no alpha channel
+        ScreenshotFormat::Jpeg => image::DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_to(&mut encoded, image::ImageFormat::Jpeg)?,
+    }
+    Ok(encoded.into_inner())
+}
+
+/// Image dimensions read from the encoded header (without decoding the pixels)
+fn image_dimensions(data: &[u8]) -> Option<Dimensions> {
+    let (width, height) = image::ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+    Some(Dimensions { width, height })
+}
+
 /// Extract interactive elements from the current page
+///
+/// Text of elements inside (or containing) a match of `redacted` is masked.
 async fn extract_interactive_elements_from_page(
     driver: &ChromeDriver,
+    redacted: &[String],
 ) -> Result<Vec<InteractiveElement>> {
     // JavaScript to extract interactive elements
     let js_code = r#"
-        (() => {
+        ((redacted) => {
             const selectors = ['button', 'a', 'input', 'select', 'textarea'];
             const elements = [];
+            const mask = (s) => s.replace(/\S/g, '█');
 
             selectors.forEach(tag => {
                 const nodes = document.querySelectorAll(tag);
@@ -866,10 +1332,12 @@ async fn extract_interactive_elements_from_page(
                     if (idx < 50) { // Limit to first 50 of each type
                         const rect = el.getBoundingClientRect();
                         const isVisible = rect.width > 0 && rect.height > 0;
+                        const text = el.textContent ? el.textContent.trim().substring(0, 100) : '';
+                        const hidden = redacted.some(s => el.closest(s) || el.querySelector(s));
                         elements.push({
                             selector: `${tag}:nth-of-type(${idx + 1})`,
                             tag: tag,
-                            text: el.textContent ? el.textContent.trim().substring(0, 100) : '',
+                            text: hidden ? mask(text) : text,
                             is_visible: isVisible,
                             is_enabled: !el.disabled
                         });
@@ -878,10 +1346,13 @@ async fn extract_interactive_elements_from_page(
             });
 
             return elements;
-        })()
+        })
     "#;
 
-    let result = driver.execute_script(js_code).await?;
+    let redacted = serde_json::to_string(redacted).unwrap_or_default();
+    let result = driver
+        .execute_script(&format!("{}({})", js_code.trim_end(), redacted))
+        .await?;
 
     // Parse the result
     let elements: Vec<InteractiveElement> = serde_json::from_value(result).unwrap_or_default();
@@ -901,6 +1372,25 @@ mod tests {
         assert!(options.save_html);
         assert!(options.compute_hashes);
         assert!(!options.extract_interactive_elements);
+        assert!(options.cancel_token.is_none());
+    }
+
+    #[test]
+    fn test_check_cancelled() {
+        let token = CancellationToken::new();
+        let options = CaptureOptions {
+            cancel_token: Some(token.clone()),
+            ..Default::default()
+        };
+
+        assert!(!options.is_cancelled());
+        assert!(options.check_cancelled().is_ok());
+        token.cancel();
+        assert!(options.is_cancelled());
+        assert!(matches!(
+            options.check_cancelled(),
+            Err(BrowserError::Cancelled)
+        ));
     }
 
     #[test]
@@ -932,59 +1422,62 @@ mod tests {
     }
 
     #[test]
-    fn test_step_frame_serialization() {
-        let frame = StepFrame {
-            frame_id: 0,
-            timestamp: "2025-10-11T12:00:00Z".to_string(),
-            elapsed_ms: 0,
-            screenshot: ScreenshotInfo {
-                path: "./screenshots/frame_0000.png".to_string(),
-                format: "png".to_string(),
-                size_bytes: 12345,
-                dimensions: Some(Dimensions {
-                    width: 1920,
-                    height: 1080,
-                }),
-                hash: Some("abc123".to_string()),
-            },
-            dom: DomInfo {
-                url: "https://example.com".to_string(),
-                title: "Example".to_string(),
-                html_path: Some("./dom/frame_0000.html".to_string()),
-                html_hash: Some("def456".to_string()),
-                interactive_elements: None,
-            },
-            visual_dom: Some(VisualDomInfo {
-                path: "./visualdom/frame_0000.visualdom.json".to_string(),
-                size_bytes: 54321,
-                node_count: 150,
-                hash: Some("ghi789".to_string()),
-            }),
-            action: Some(ActionInfo {
-                action_type: "navigate".to_string(),
-                intent: "Navigate to example.com".to_string(),
-                target: None,
-            }),
-            transcript: Some(TranscriptInfo {
-                action_description: "Navigating to example.com".to_string(),
-                reasoning: Some("User requested navigation".to_string()),
-                expected_outcome: Some("Page should load".to_string()),
-            }),
-        };
+    fn test_image_dimensions() {
+        let mut png = Vec::new();
+        image::RgbaImage::new(640, 480)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let dimensions = image_dimensions(&png).unwrap();
+        assert_eq!((dimensions.width, dimensions.height), (640, 480));
+        assert!(image_dimensions(b"not an image").is_none());
+    }
 
-        // Test serialization
-        let json = serde_json::to_string_pretty(&frame).unwrap();
-        assert!(json.contains("frame_id"));
-        assert!(json.contains("screenshot"));
-        assert!(json.contains("dom"));
-        assert!(json.contains("visual_dom"));
+    #[test]
+    fn test_resize_screenshot() {
+        let png = encode_image(
+            &image::DynamicImage::ImageRgba8(image::RgbaImage::new(1600, 1200)),
+            ScreenshotFormat::Png,
+        )
+        .unwrap();
+        let size = |width, height| Dimensions { width, height };
+
+        let resized = resize_screenshot(
+            png.clone(),
+            ScreenshotFormat::Png,
+            Some(&size(800, 800)),
+            Some(320),
+        )
+        .unwrap();
+        assert_eq!(image_dimensions(&resized.data), Some(size(800, 600)));
+        assert_eq!(resized.original_dimensions, Some(size(1600, 1200)));
+        let (thumbnail, dimensions) = resized.thumbnail.unwrap();
+        assert_eq!(dimensions, size(320, 240));
+        assert_eq!(image_dimensions(&thumbnail), Some(dimensions));
+
+        // Already small enough: saved as captured, never upscaled
+        let kept = resize_screenshot(
+            png.clone(),
+            ScreenshotFormat::Png,
+            Some(&size(4000, 4000)),
+            Some(4000),
+        )
+        .unwrap();
+        assert_eq!(kept.data, png);
+        assert!(kept.original_dimensions.is_none());
+        assert_eq!(kept.thumbnail.unwrap().1, size(1600, 1200));
+
+        // JPEG output works from an image with alpha
+        let jpeg = resize_screenshot(png, ScreenshotFormat::Jpeg, Some(&size(800, 800)), None);
+        assert!(jpeg.unwrap().original_dimensions.is_some());
+    }
 
-        // Test deserialization
-        let deserialized: StepFrame = serde_json::from_str(&json).unwrap();
-        assert_eq!(deserialized.frame_id, 0);
-        assert_eq!(deserialized.screenshot.size_bytes, 12345);
-        assert!(deserialized.visual_dom.is_some());
-        assert_eq!(deserialized.visual_dom.unwrap().node_count, 150);
+    #[test]
+    fn test_compute_bytes_hash() {
+        assert_eq!(
+            compute_bytes_hash(b"hello world"),
+            compute_string_hash("hello world")
+        );
     }
 
     #[test]
@@ -1012,4 +1505,137 @@ mod tests {
         let all = CaptureOptions::all_computed_styles();
         assert!(all.is_empty()); // Empty vec means capture all
     }
+
+    #[tokio::test]
+    async fn test_write_and_read_visual_dom() {
+        let dir = std::env::temp_dir().join(format!(
+            "robert-visualdom-test-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let value = serde_json::json!({ "documents": [], "strings": ["a", "b"] });
+
+        // Plain JSON is unchanged: pretty-printed, hashed over the file contents
+        let path = dir.join(visual_dom_extension(
+            VisualDomFormat::Json,
+            VisualDomCompression::None,
+        ));
+        let (size, hash) = write_visual_dom(
+            path.clone(),
+            value.clone(),
+            VisualDomFormat::Json,
+            VisualDomCompression::None,
+        )
+        .await
+        .unwrap();
+        let expected = serde_json::to_string_pretty(&value).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+        assert_eq!(size, expected.len());
+        assert_eq!(hash, compute_string_hash(&expected));
+
+        for format in [VisualDomFormat::Json, VisualDomFormat::MessagePack] {
+            for compression in [
+                VisualDomCompression::None,
+                VisualDomCompression::Gzip,
+                VisualDomCompression::Zstd,
+            ] {
+                let path = dir.join(visual_dom_extension(format, compression));
+                let (size, _) = write_visual_dom(path.clone(), value.clone(), format, compression)
+                    .await
+                    .unwrap();
+                assert_eq!(std::fs::metadata(&path).unwrap().len() as usize, size);
+                assert_eq!(read_visual_dom(&path).await.unwrap(), value);
+            }
+        }
+
+        let snapshot = CaptureSnapshotResponse::from_file(&dir.join("visualdom.msgpack.zst"))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.strings, vec!["a", "b"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_visual_dom_extension() {
+        assert_eq!(
+            visual_dom_extension(VisualDomFormat::Json, VisualDomCompression::None),
+            "visualdom.json"
+        );
+        assert_eq!(
+            visual_dom_extension(VisualDomFormat::MessagePack, VisualDomCompression::Gzip),
+            "visualdom.msgpack.gz"
+        );
+        assert_eq!(
+            visual_dom_extension(VisualDomFormat::Json, VisualDomCompression::Zstd),
+            "visualdom.json.zst"
+        );
+    }
+
+    #[test]
+    fn test_crop_target() {
+        // A 100x50 CSS pixel viewport at 2x
+        let image = image::DynamicImage::ImageRgba8(image::RgbaImage::new(200, 100));
+        let data = encode_image(&image, ScreenshotFormat::Png).unwrap();
+        let rect = |x, y, width, height| crate::browser::geometry::Rect {
+            x,
+            y,
+            width,
+            height,
+        };
+
+        let (_, dimensions) = crop_target(
+            &data,
+            rect(10.0, 10.0, 20.0, 10.0),
+            100.0,
+            ScreenshotFormat::Png,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            dimensions,
+            Dimensions {
+                width: 72,
+                height: 52
+            }
+        );
+
+        // Clipped to the screenshot at the edges
+        let (_, dimensions) = crop_target(
+            &data,
+            rect(90.0, 0.0, 20.0, 10.0),
+            100.0,
+            ScreenshotFormat::Png,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            dimensions,
+            Dimensions {
+                width: 36,
+                height: 36
+            }
+        );
+
+        assert!(crop_target(
+            &data,
+            rect(200.0, 0.0, 20.0, 10.0),
+            100.0,
+            ScreenshotFormat::Png
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    #[test]
+    fn test_artifact_key() {
+        assert_eq!(
+            artifact_key(Path::new("/tmp/run/screenshots/frame_0001.png")),
+            "screenshots/frame_0001.png"
+        );
+        assert_eq!(artifact_key(Path::new("frame_0001.png")), "frame_0001.png");
+    }
 }
@@ -0,0 +1,206 @@
+//! Tracing Setup
+//!
+//! The driver emits `tracing` spans for launch, navigation, every CDP command, and
+//! step-frame capture. This module installs a subscriber that prints them as
+//! human-readable text or JSON lines, and (with the `otlp` feature) exports them
+//! to an OpenTelemetry collector.
+//!
+//! Embedding applications that install their own subscriber don't need this module.
+
+use std::str::FromStr;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+/// Output format for log records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// No log output (spans are still exported when OTLP is enabled)
+    Quiet,
+    /// Human-readable text on stderr
+    #[default]
+    Pretty,
+    /// One JSON object per line on stderr, for log collectors
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "quiet" | "off" | "none" => Ok(LogFormat::Quiet),
+            "pretty" | "text" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!(
+                "Unknown log format '{}' (expected quiet, pretty, or json)",
+                other
+            )),
+        }
+    }
+}
+
+/// Logging configuration
+#[derive(Debug, Clone, Default)]
+pub struct LoggingConfig {
+    /// Output format
+    pub format: LogFormat,
+
+    /// Filter directive (e.g. `debug` or `robert_webdriver=trace`)
+    ///
+    /// Overrides `RUST_LOG` when set; otherwise `RUST_LOG` is used, defaulting to `info`.
+    pub level: Option<String>,
+}
+
+impl LoggingConfig {
+    fn filter(&self) -> anyhow::Result<EnvFilter> {
+        match &self.level {
+            Some(level) => Ok(EnvFilter::try_new(level)?),
+            None => {
+                Ok(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+            }
+        }
+    }
+
+    fn fmt_layer(&self) -> Option<Box<dyn Layer<Registry> + Send + Sync>> {
+        match self.format {
+            LogFormat::Quiet => None,
+            LogFormat::Pretty => Some(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(std::io::stderr)
+                    .boxed(),
+            ),
+            LogFormat::Json => Some(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(false)
+                    .with_writer(std::io::stderr)
+                    .boxed(),
+            ),
+        }
+    }
+}
+
+/// Keeps tracing exporters alive; flushes pending spans when dropped
+pub struct TracingGuard {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush OTLP spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Install the global tracing subscriber with the default (pretty) configuration
+///
+/// See [`init_tracing_with`].
+pub fn init_tracing(service_name: &str) -> anyhow::Result<TracingGuard> {
+    init_tracing_with(service_name, &LoggingConfig::default())
+}
+
+/// Install the global tracing subscriber
+///
+/// Log level is controlled by `config.level`, falling back to `RUST_LOG` (defaults
+/// to `info`). When built with the `otlp` feature and `OTEL_EXPORTER_OTLP_ENDPOINT`
+/// is set, spans are also exported over OTLP/HTTP. Records emitted through the
+/// `log` crate are captured as well.
+///
+/// Keep the returned guard alive for the lifetime of the program.
+pub fn init_tracing_with(
+    service_name: &str,
+    config: &LoggingConfig,
+) -> anyhow::Result<TracingGuard> {
+    let filter = config.filter()?;
+    let registry = tracing_subscriber::registry()
+        .with(config.fmt_layer())
+        .with(filter);
+
+    #[cfg(feature = "otlp")]
+    {
+        if std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_some() {
+            let provider = otlp::build_provider(service_name)?;
+            let tracer =
+                opentelemetry::trace::TracerProvider::tracer(&provider, "robert-webdriver");
+            registry
+                .with(tracing_opentelemetry::layer().with_tracer(tracer))
+                .try_init()?;
+            tracing::debug!(
+                service = service_name,
+                "Tracing initialized with OTLP export"
+            );
+            return Ok(TracingGuard {
+                provider: Some(provider),
+            });
+        }
+    }
+
+    registry.try_init()?;
+    tracing::debug!(service = service_name, "Tracing initialized");
+
+    Ok(TracingGuard {
+        #[cfg(feature = "otlp")]
+        provider: None,
+    })
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+
+    /// Build a tracer provider exporting over OTLP/HTTP
+    ///
+    /// Endpoint, headers, and timeouts come from the standard
+    /// `OTEL_EXPORTER_OTLP_*` environment variables.
+    pub fn build_provider(service_name: &str) -> anyhow::Result<SdkTracerProvider> {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .build()?;
+
+        Ok(SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(service_name.to_string())
+                    .build(),
+            )
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_format_from_str() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("Pretty".parse::<LogFormat>().unwrap(), LogFormat::Pretty);
+        assert_eq!("quiet".parse::<LogFormat>().unwrap(), LogFormat::Quiet);
+        assert!("xml".parse::<LogFormat>().is_err());
+        assert_eq!(LogFormat::default(), LogFormat::Pretty);
+    }
+
+    #[test]
+    fn test_level_overrides_env() {
+        let config = LoggingConfig {
+            format: LogFormat::Json,
+            level: Some("debug".to_string()),
+        };
+        assert!(config.filter().is_ok());
+
+        let bad = LoggingConfig {
+            format: LogFormat::Json,
+            level: Some("=[".to_string()),
+        };
+        assert!(bad.filter().is_err());
+    }
+}
@@ -0,0 +1,700 @@
+//! Test Fixtures
+//!
+//! Helpers for testing code built on this crate without external websites or, with
+//! [`MockDriver`], without Chrome at all.
+//!
+//! A local HTTP server for hermetic browser tests: each [`FixtureServer`] listens on a random port of 127.0.0.1 and serves
+//! the [`Fixture`]s registered for its paths, which can set the status code and
+//! headers, delay the response, or be computed from the request:
+//!
+//! ```no_run
+//! use robert_webdriver::testing::{Fixture, FixtureServer};
+//! use std::time::Duration;
+//!
+//! # async fn example() -> robert_webdriver::error::Result<()> {
+//! let server = FixtureServer::builder()
+//!     .route("/", Fixture::page("Home", r#"<a href="/slow">Slow</a>"#))
+//!     .route("/slow", Fixture::text("done").delay(Duration::from_secs(2)))
+//!     .route("/gone", Fixture::html("<h1>Gone</h1>").status(410))
+//!     .route("/api/user", Fixture::json(&serde_json::json!({ "name": "Ada" })))
+//!     .route_fn("/echo/*", |request| Fixture::text(request.path.clone()))
+//!     .start()
+//!     .await?;
+//! let url = server.url_for("/slow");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Bodies are templates: `{{base_url}}` (`http://127.0.0.1:<port>`), `{{path}}`, and
+//! `{{query.<name>}}` are replaced when the fixture is served.
+//!
+//! [`MockDriver`] implements [`Driver`] with canned pages, script results, and
+//! screenshots, and records the calls made to it, for unit tests of logic that takes
+//! a `&dyn Driver`:
+//!
+//! ```
+//! use robert_webdriver::browser::Driver;
+//! use robert_webdriver::testing::{MockDriver, MockPage};
+//!
+//! async fn heading(driver: &dyn Driver) -> robert_webdriver::error::Result<String> {
+//!     driver.navigate("https://example.com").await?;
+//!     let heading = driver.execute_script("document.querySelector('h1').innerText").await?;
+//!     Ok(heading.as_str().unwrap_or_default().to_string())
+//! }
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let driver = MockDriver::new()
+//!     .page("https://example.com", MockPage::new("Example", "<h1>Hello</h1>"))
+//!     .script("document.querySelector('h1').innerText", "Hello");
+//! assert_eq!(heading(&driver).await.unwrap(), "Hello");
+//! # }
+//! ```
+
+use crate::browser::{Driver, NavigationResult};
+use crate::error::{BrowserError, Result};
+use futures::future::BoxFuture;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use warp::http::{HeaderMap, Method, Response, StatusCode};
+use warp::hyper::body::Bytes;
+use warp::path::FullPath;
+use warp::Filter;
+
+/// A response served by a [`FixtureServer`]
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+    delay: Duration,
+}
+
+impl Fixture {
+    /// A 200 response with `content_type`
+    pub fn new(content_type: &str, body: impl Into<String>) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("content-type".to_string(), content_type.to_string())],
+            body: body.into(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// An HTML response
+    pub fn html(body: impl Into<String>) -> Self {
+        Self::new("text/html; charset=utf-8", body)
+    }
+
+    /// An HTML document titled `title` with `body` as its `<body>` (and a mobile
+    /// viewport)
+    pub fn page(title: &str, body: &str) -> Self {
+        Self::html(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <title>{}</title>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+</head>
+<body>
+{}
+</body>
+</html>"#,
+            title, body
+        ))
+    }
+
+    /// A JSON response
+    pub fn json(value: &serde_json::Value) -> Self {
+        Self::new("application/json", value.to_string())
+    }
+
+    /// A plain text response
+    pub fn text(body: impl Into<String>) -> Self {
+        Self::new("text/plain; charset=utf-8", body)
+    }
+
+    /// Respond with `status` instead of 200
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Add a response header (replacing the content type if `name` is
+    /// `content-type`)
+    pub fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .retain(|(existing, _)| name != "content-type" || existing.as_str() != "content-type");
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// Wait `delay` before responding (for slow pages and timeouts)
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// The body with its placeholders filled in from `request`
+    fn render(&self, request: &FixtureRequest) -> String {
+        let mut body = self
+            .body
+            .replace("{{base_url}}", &request.base_url)
+            .replace("{{path}}", &request.path);
+        while let Some(start) = body.find("{{query.") {
+            let Some(end) = body[start..].find("}}").map(|end| start + end) else {
+                break;
+            };
+            let name = &body[start + "{{query.".len()..end];
+            let value = request.query.get(name).cloned().unwrap_or_default();
+            body.replace_range(start..end + 2, &value);
+        }
+        body
+    }
+}
+
+/// A request to a [`FixtureServer`], for fixtures computed with
+/// [`FixtureServerBuilder::route_fn`]
+#[derive(Debug, Clone)]
+pub struct FixtureRequest {
+    /// `GET`, `POST`, ...
+    pub method: String,
+
+    /// Path without the query (`/search`)
+    pub path: String,
+
+    /// Query parameters
+    pub query: HashMap<String, String>,
+
+    /// Headers, with lowercase names
+    pub headers: HashMap<String, String>,
+
+    pub body: Bytes,
+
+    /// URL of the server (`http://127.0.0.1:<port>`)
+    pub base_url: String,
+}
+
+type Handler = Arc<dyn Fn(&FixtureRequest) -> Fixture + Send + Sync>;
+
+/// Routes of a [`FixtureServer`]
+pub struct FixtureServerBuilder {
+    routes: Vec<(String, Handler)>,
+    not_found: Fixture,
+}
+
+impl FixtureServerBuilder {
+    /// Serve `fixture` at `path`
+    ///
+    /// Paths match exactly (ignoring the query), except that a path ending in `/*`
+    /// matches everything under it. Exact matches win over `/*` routes, and earlier
+    /// `/*` routes over later ones.
+    pub fn route(self, path: impl Into<String>, fixture: Fixture) -> Self {
+        self.route_fn(path, move |_| fixture.clone())
+    }
+
+    /// Serve the fixture `handler` returns for each request to `path`
+    pub fn route_fn<F>(mut self, path: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(&FixtureRequest) -> Fixture + Send + Sync + 'static,
+    {
+        self.routes.push((path.into(), Arc::new(handler)));
+        self
+    }
+
+    /// Serve `fixture` for paths without a route (default: an empty 404)
+    pub fn not_found(mut self, fixture: Fixture) -> Self {
+        self.not_found = fixture;
+        self
+    }
+
+    /// Start the server on a random port
+    pub async fn start(self) -> Result<FixtureServer> {
+        let routes = Arc::new(self);
+        let filter = warp::method()
+            .and(warp::path::full())
+            .and(warp::query::<HashMap<String, String>>())
+            .and(warp::header::headers_cloned())
+            .and(warp::body::bytes())
+            .and_then(
+                move |method: Method,
+                      path: FullPath,
+                      query: HashMap<String, String>,
+                      headers: HeaderMap,
+                      body: Bytes| {
+                    let routes = routes.clone();
+                    async move {
+                        let headers: HashMap<String, String> = headers
+                            .iter()
+                            .filter_map(|(name, value)| {
+                                Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+                            })
+                            .collect();
+                        let request = FixtureRequest {
+                            method: method.to_string(),
+                            path: path.as_str().to_string(),
+                            query,
+                            base_url: format!(
+                                "http://{}",
+                                headers.get("host").map(String::as_str).unwrap_or_default()
+                            ),
+                            headers,
+                            body,
+                        };
+                        Ok::<_, Infallible>(routes.respond(&request).await)
+                    }
+                },
+            );
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let (addr, server) = warp::serve(filter)
+            .try_bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
+                shutdown_rx.await.ok();
+            })
+            .map_err(|e| BrowserError::Other(format!("Failed to start fixture server: {}", e)))?;
+        tokio::spawn(server);
+
+        Ok(FixtureServer {
+            addr,
+            shutdown_tx: Some(shutdown_tx),
+        })
+    }
+
+    /// The fixture for `request`, rendered
+    async fn respond(&self, request: &FixtureRequest) -> Response<String> {
+        let exact = self.routes.iter().find(|(path, _)| *path == request.path);
+        let prefix = || {
+            self.routes.iter().find(|(path, _)| {
+                path.strip_suffix("/*").is_some_and(|prefix| {
+                    request.path == prefix
+                        || request
+                            .path
+                            .strip_prefix(prefix)
+                            .is_some_and(|rest| rest.starts_with('/'))
+                })
+            })
+        };
+        let fixture = match exact.or_else(prefix) {
+            Some((_, handler)) => handler(request),
+            None => self.not_found.clone(),
+        };
+
+        if !fixture.delay.is_zero() {
+            tokio::time::sleep(fixture.delay).await;
+        }
+        let mut response = Response::builder()
+            .status(StatusCode::from_u16(fixture.status).unwrap_or(StatusCode::OK));
+        for (name, value) in &fixture.headers {
+            response = response.header(name, value);
+        }
+        response.body(fixture.render(request)).unwrap_or_else(|e| {
+            let mut response = Response::new(format!("Invalid fixture: {}", e));
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            response
+        })
+    }
+}
+
+/// A local HTTP server serving fixtures, stopped when dropped
+pub struct FixtureServer {
+    addr: SocketAddr,
+    shutdown_tx: Option<oneshot::Sender<()>>,
+}
+
+impl FixtureServer {
+    /// A server with no routes yet
+    pub fn builder() -> FixtureServerBuilder {
+        FixtureServerBuilder {
+            routes: Vec::new(),
+            not_found: Fixture::text("").status(404),
+        }
+    }
+
+    /// Base URL of the server (`http://127.0.0.1:<port>`)
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// URL of `path` on the server
+    pub fn url_for(&self, path: &str) -> String {
+        format!("{}/{}", self.url(), path.trim_start_matches('/'))
+    }
+
+    /// Address the server listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Wait until the server answers requests (any status counts), up to `timeout`
+    pub async fn wait_ready(&self, timeout: Duration) -> Result<()> {
+        let started = std::time::Instant::now();
+        loop {
+            match reqwest::get(self.url()).await {
+                Ok(_) => return Ok(()),
+                Err(e) if started.elapsed() >= timeout => {
+                    return Err(BrowserError::Other(format!(
+                        "Fixture server on {} did not become ready: {}",
+                        self.url(),
+                        e
+                    )))
+                }
+                Err(_) => tokio::time::sleep(Duration::from_millis(50)).await,
+            }
+        }
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+/// A page a [`MockDriver`] can navigate to
+#[derive(Debug, Clone)]
+pub struct MockPage {
+    pub title: String,
+    pub html: String,
+
+    /// HTTP status reported by `navigate`
+    pub status: i64,
+}
+
+impl MockPage {
+    /// A 200 page titled `title` with `body` as its `<body>`
+    pub fn new(title: &str, body: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            html: format!(
+                "<html><head><title>{}</title></head><body>{}</body></html>",
+                title, body
+            ),
+            status: 200,
+        }
+    }
+
+    /// Report `status` instead of 200
+    pub fn status(mut self, status: i64) -> Self {
+        self.status = status;
+        self
+    }
+}
+
+/// A call made to a [`MockDriver`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockCall {
+    Navigate(String),
+    CurrentUrl,
+    Title,
+    PageSource,
+    ExecuteScript(String),
+    Screenshot,
+}
+
+/// A canned result; errors are kept as messages since [`BrowserError`] isn't `Clone`
+#[derive(Debug, Clone)]
+enum Canned<T> {
+    Ok(T),
+    Err(String),
+}
+
+/// A [`Driver`] with programmed responses, for tests that shouldn't launch Chrome
+///
+/// Navigating to a URL with no [`MockPage`] fails with
+/// `BrowserError::NavigationFailed`; scripts without a programmed result return
+/// `null` (see [`MockDriver::default_script_result`]). Before the first navigation
+/// the current page is an empty `about:blank`.
+#[derive(Debug)]
+pub struct MockDriver {
+    pages: HashMap<String, Canned<MockPage>>,
+    scripts: HashMap<String, Canned<Value>>,
+    default_script_result: Value,
+    screenshot: Canned<Vec<u8>>,
+    current: Mutex<(String, MockPage)>,
+    calls: Mutex<Vec<MockCall>>,
+}
+
+impl Default for MockDriver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockDriver {
+    /// A driver with no pages, returning `null` from scripts and a blank 1x1 PNG
+    /// screenshot
+    pub fn new() -> Self {
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(image::RgbaImage::new(1, 1))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .expect("encoding a 1x1 PNG can't fail");
+        Self {
+            pages: HashMap::new(),
+            scripts: HashMap::new(),
+            default_script_result: Value::Null,
+            screenshot: Canned::Ok(png),
+            current: Mutex::new((
+                "about:blank".to_string(),
+                MockPage {
+                    title: String::new(),
+                    html: "<html><head></head><body></body></html>".to_string(),
+                    status: 200,
+                },
+            )),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Serve `page` at `url`
+    pub fn page(mut self, url: impl Into<String>, page: MockPage) -> Self {
+        self.pages.insert(url.into(), Canned::Ok(page));
+        self
+    }
+
+    /// Fail navigation to `url` with `BrowserError::NavigationFailed(message)`
+    pub fn navigation_error(mut self, url: impl Into<String>, message: impl Into<String>) -> Self {
+        self.pages.insert(url.into(), Canned::Err(message.into()));
+        self
+    }
+
+    /// Return `result` when exactly `script` is executed
+    pub fn script(mut self, script: impl Into<String>, result: impl Into<Value>) -> Self {
+        self.scripts
+            .insert(script.into(), Canned::Ok(result.into()));
+        self
+    }
+
+    /// Fail when exactly `script` is executed
+    pub fn script_error(mut self, script: impl Into<String>, message: impl Into<String>) -> Self {
+        self.scripts
+            .insert(script.into(), Canned::Err(message.into()));
+        self
+    }
+
+    /// Return `result` from scripts with no programmed result
+    pub fn default_script_result(mut self, result: impl Into<Value>) -> Self {
+        self.default_script_result = result.into();
+        self
+    }
+
+    /// Return `png` from screenshots
+    pub fn screenshot(mut self, png: Vec<u8>) -> Self {
+        self.screenshot = Canned::Ok(png);
+        self
+    }
+
+    /// Fail screenshots
+    pub fn screenshot_error(mut self, message: impl Into<String>) -> Self {
+        self.screenshot = Canned::Err(message.into());
+        self
+    }
+
+    /// Calls made so far, oldest first
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn record(&self, call: MockCall) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+impl Driver for MockDriver {
+    fn navigate<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<NavigationResult>> {
+        Box::pin(async move {
+            self.record(MockCall::Navigate(url.to_string()));
+            match self.pages.get(url) {
+                Some(Canned::Ok(page)) => {
+                    *self.current.lock().unwrap() = (url.to_string(), page.clone());
+                    Ok(NavigationResult {
+                        url: url.to_string(),
+                        status: Some(page.status),
+                        status_text: None,
+                        mime_type: Some("text/html".to_string()),
+                    })
+                }
+                Some(Canned::Err(message)) => Err(BrowserError::NavigationFailed(message.clone())),
+                None => Err(BrowserError::NavigationFailed(format!(
+                    "No mock page for {}",
+                    url
+                ))),
+            }
+        })
+    }
+
+    fn current_url(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            self.record(MockCall::CurrentUrl);
+            Ok(self.current.lock().unwrap().0.clone())
+        })
+    }
+
+    fn title(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            self.record(MockCall::Title);
+            Ok(self.current.lock().unwrap().1.title.clone())
+        })
+    }
+
+    fn get_page_source(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            self.record(MockCall::PageSource);
+            Ok(self.current.lock().unwrap().1.html.clone())
+        })
+    }
+
+    fn execute_script<'a>(&'a self, script: &'a str) -> BoxFuture<'a, Result<Value>> {
+        Box::pin(async move {
+            self.record(MockCall::ExecuteScript(script.to_string()));
+            match self.scripts.get(script) {
+                Some(Canned::Ok(value)) => Ok(value.clone()),
+                Some(Canned::Err(message)) => Err(BrowserError::Other(format!(
+                    "Script execution failed: {}",
+                    message
+                ))),
+                None => Ok(self.default_script_result.clone()),
+            }
+        })
+    }
+
+    fn screenshot(&self) -> BoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(async move {
+            self.record(MockCall::Screenshot);
+            match &self.screenshot {
+                Canned::Ok(png) => Ok(png.clone()),
+                Canned::Err(message) => Err(BrowserError::Other(message.clone())),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fixture_server() {
+        let server = FixtureServer::builder()
+            .route("/", Fixture::page("Home", "<h1>{{path}}</h1>"))
+            .route(
+                "/slow",
+                Fixture::text("done").delay(Duration::from_millis(200)),
+            )
+            .route(
+                "/gone",
+                Fixture::html("gone").status(410).header("x-test", "1"),
+            )
+            .route("/links", Fixture::html("{{base_url}}/a?q={{query.q}}"))
+            .route_fn("/echo/*", |request| {
+                Fixture::text(format!("{} {}", request.method, request.path))
+            })
+            .start()
+            .await
+            .unwrap();
+        server.wait_ready(Duration::from_secs(5)).await.unwrap();
+
+        let get = |path: &str| reqwest::get(server.url_for(path));
+        let home = get("/").await.unwrap();
+        assert!(home.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html"));
+        let body = home.text().await.unwrap();
+        assert!(body.contains("<title>Home</title>"));
+        assert!(body.contains("<h1>/</h1>"));
+
+        let started = std::time::Instant::now();
+        assert_eq!(get("/slow").await.unwrap().text().await.unwrap(), "done");
+        assert!(started.elapsed() >= Duration::from_millis(200));
+
+        let gone = get("/gone").await.unwrap();
+        assert_eq!(gone.status(), 410);
+        assert_eq!(gone.headers()["x-test"], "1");
+
+        assert_eq!(
+            get("/links?q=tea").await.unwrap().text().await.unwrap(),
+            format!("{}/a?q=tea", server.url())
+        );
+        assert_eq!(
+            get("/echo/a/b").await.unwrap().text().await.unwrap(),
+            "GET /echo/a/b"
+        );
+        assert_eq!(get("/echoes").await.unwrap().status(), 404);
+        assert_eq!(get("/missing").await.unwrap().status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_mock_driver() {
+        let driver = MockDriver::new()
+            .page(
+                "https://example.com",
+                MockPage::new("Example", "<h1>Hi</h1>"),
+            )
+            .page(
+                "https://example.com/gone",
+                MockPage::new("Gone", "").status(410),
+            )
+            .navigation_error("https://down.example.com", "net::ERR_CONNECTION_REFUSED")
+            .script("document.title", "Example")
+            .script_error("throw 1", "Uncaught 1");
+        let driver: &dyn Driver = &driver;
+
+        assert_eq!(driver.current_url().await.unwrap(), "about:blank");
+        let result = driver.navigate("https://example.com").await.unwrap();
+        assert_eq!(result.status, Some(200));
+        assert_eq!(driver.title().await.unwrap(), "Example");
+        assert!(driver
+            .get_page_source()
+            .await
+            .unwrap()
+            .contains("<h1>Hi</h1>"));
+        assert_eq!(
+            driver.execute_script("document.title").await.unwrap(),
+            "Example"
+        );
+        assert_eq!(driver.execute_script("1 + 1").await.unwrap(), Value::Null);
+        assert!(driver.execute_script("throw 1").await.is_err());
+        assert!(driver.screenshot().await.unwrap().starts_with(b"\x89PNG"));
+
+        let result = driver.navigate("https://example.com/gone").await.unwrap();
+        assert!(result.is_http_error());
+        assert!(matches!(
+            driver.navigate("https://down.example.com").await,
+            Err(BrowserError::NavigationFailed(_))
+        ));
+        assert!(driver
+            .navigate("https://unknown.example.com")
+            .await
+            .is_err());
+        assert_eq!(
+            driver.current_url().await.unwrap(),
+            "https://example.com/gone"
+        );
+    }
+
+    #[test]
+    fn test_mock_driver_records_calls() {
+        let driver = MockDriver::new().page("https://example.com", MockPage::new("Example", ""));
+        futures::executor::block_on(async {
+            driver.navigate("https://example.com").await.unwrap();
+            driver.execute_script("window.x").await.unwrap();
+            driver.title().await.unwrap();
+        });
+        assert_eq!(
+            driver.calls(),
+            [
+                MockCall::Navigate("https://example.com".to_string()),
+                MockCall::ExecuteScript("window.x".to_string()),
+                MockCall::Title,
+            ]
+        );
+    }
+}
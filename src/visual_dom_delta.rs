@@ -0,0 +1,896 @@
+//! Incremental VisualDom Snapshots
+//!
+//! Consecutive VisualDom snapshots of the same page are mostly identical, yet each one
+//! repeats every node. With delta capture enabled (see [`VisualDomDeltas`]), a full
+//! snapshot (keyframe) is saved every few frames and the frames in between store only
+//! the nodes whose data or layout changed since the previous frame, keyed by
+//! `backendNodeId`.
+//!
+//! A delta file is an ordinary VisualDom file with a top-level `delta` object naming
+//! the file it applies to. [`reconstruct_visual_dom`] follows that chain back to the
+//! keyframe and rebuilds the full snapshot.
+
+use crate::error::{BrowserError, Result};
+use crate::step_frame::read_visual_dom;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Frames between full snapshots by default
+pub const DEFAULT_KEYFRAME_INTERVAL: usize = 10;
+
+/// Longest delta chain followed before giving up (guards against cycles)
+const MAX_CHAIN_LENGTH: usize = 10_000;
+
+/// Document fields holding string table indexes
+const DOCUMENT_STRINGS: &[&str] = &[
+    "documentURL",
+    "title",
+    "baseURL",
+    "contentLanguage",
+    "encodingName",
+    "publicId",
+    "systemId",
+    "frameId",
+];
+
+/// How a column of a node or layout table is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    /// One value per row
+    Raw,
+    /// One string table index per row
+    String,
+    /// One array of string table indexes per row
+    StringList,
+    /// Sparse `{index, value}`
+    RareRaw,
+    /// Sparse `{index, value}` of string table indexes
+    RareString,
+    /// Sparse `{index}` of rows where the flag is set
+    RareBoolean,
+}
+
+impl Column {
+    fn for_node(key: &str, value: &Value) -> Self {
+        match key {
+            "nodeName" | "nodeValue" => Column::String,
+            "attributes" => Column::StringList,
+            "contentDocumentIndex" => Column::RareRaw,
+            "shadowRootType" | "textValue" | "inputValue" | "pseudoType" | "pseudoIdentifier"
+            | "currentSourceURL" | "originURL" => Column::RareString,
+            "inputChecked" | "optionSelected" | "isClickable" => Column::RareBoolean,
+            _ => Self::from_shape(value),
+        }
+    }
+
+    fn for_layout(key: &str, value: &Value) -> Self {
+        match key {
+            "text" | "blendedBackgroundColors" => Column::String,
+            "styles" => Column::StringList,
+            "stackingContexts" => Column::RareBoolean,
+            _ => Self::from_shape(value),
+        }
+    }
+
+    /// Columns Chrome may add in the future are kept verbatim
+    fn from_shape(value: &Value) -> Self {
+        match value {
+            Value::Object(rare) if rare.contains_key("value") => Column::RareRaw,
+            Value::Object(_) => Column::RareBoolean,
+            _ => Column::Raw,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Column::Raw => "raw",
+            Column::String => "string",
+            Column::StringList => "string_list",
+            Column::RareRaw => "rare_raw",
+            Column::RareString => "rare_string",
+            Column::RareBoolean => "rare_boolean",
+        }
+    }
+
+    fn parse(name: &str) -> Self {
+        match name {
+            "string" => Column::String,
+            "string_list" => Column::StringList,
+            "rare_raw" => Column::RareRaw,
+            "rare_string" => Column::RareString,
+            "rare_boolean" => Column::RareBoolean,
+            _ => Column::Raw,
+        }
+    }
+
+    fn is_rare(self) -> bool {
+        matches!(
+            self,
+            Column::RareRaw | Column::RareString | Column::RareBoolean
+        )
+    }
+
+    /// Row value with string indexes resolved
+    fn decode(self, value: &Value, strings: &[String]) -> Value {
+        match self {
+            Column::String | Column::RareString => resolve(value, strings),
+            Column::StringList => Value::Array(
+                value
+                    .as_array()
+                    .map(|items| items.iter().map(|i| resolve(i, strings)).collect())
+                    .unwrap_or_default(),
+            ),
+            Column::Raw | Column::RareRaw | Column::RareBoolean => value.clone(),
+        }
+    }
+
+    /// Row value with strings interned into `strings`
+    fn encode(self, value: Option<&Value>, strings: &mut StringTable) -> Value {
+        match self {
+            Column::String | Column::RareString => strings.intern(value.unwrap_or(&Value::Null)),
+            Column::StringList => Value::Array(
+                value
+                    .and_then(|v| v.as_array())
+                    .map(|items| items.iter().map(|s| strings.intern(s)).collect())
+                    .unwrap_or_default(),
+            ),
+            Column::Raw | Column::RareRaw | Column::RareBoolean => {
+                value.cloned().unwrap_or(Value::Null)
+            }
+        }
+    }
+}
+
+fn resolve(index: &Value, strings: &[String]) -> Value {
+    index
+        .as_i64()
+        .and_then(|i| usize::try_from(i).ok())
+        .and_then(|i| strings.get(i))
+        .map_or(Value::Null, |s| Value::String(s.clone()))
+}
+
+/// String table being rebuilt from resolved strings
+#[derive(Default)]
+struct StringTable {
+    strings: Vec<String>,
+    index: HashMap<String, i64>,
+}
+
+impl StringTable {
+    fn intern(&mut self, value: &Value) -> Value {
+        let Some(s) = value.as_str() else {
+            return json!(-1);
+        };
+        if let Some(i) = self.index.get(s) {
+            return json!(i);
+        }
+        let i = self.strings.len() as i64;
+        self.strings.push(s.to_string());
+        self.index.insert(s.to_string(), i);
+        json!(i)
+    }
+}
+
+/// One document of a snapshot as per-node records
+#[derive(Debug, Clone, Default, PartialEq)]
+struct DocumentRecords {
+    /// Document-level fields (strings resolved) and the columns present
+    header: Value,
+    /// `backendNodeId`s in snapshot order
+    order: Vec<i64>,
+    /// `{node, layout}` per `backendNodeId`
+    nodes: HashMap<i64, Value>,
+}
+
+/// A VisualDom snapshot decomposed into per-node records keyed by `backendNodeId`
+///
+/// Strings are resolved, so records from snapshots with different string tables
+/// compare equal when the node is unchanged.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SnapshotRecords {
+    documents: Vec<DocumentRecords>,
+    /// Top-level entries other than `documents` and `strings` (e.g. `images`)
+    extra: Map<String, Value>,
+}
+
+impl SnapshotRecords {
+    pub(crate) fn from_snapshot(snapshot: &Value) -> Self {
+        let strings: Vec<String> = snapshot
+            .get("strings")
+            .and_then(|s| s.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|s| s.as_str().unwrap_or_default().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let documents = snapshot
+            .get("documents")
+            .and_then(|d| d.as_array())
+            .map(|docs| {
+                docs.iter()
+                    .map(|doc| explode_document(doc, &strings))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let extra = snapshot
+            .as_object()
+            .map(|obj| {
+                obj.iter()
+                    .filter(|(key, _)| !matches!(key.as_str(), "documents" | "strings"))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { documents, extra }
+    }
+
+    /// Rebuild a snapshot in `DOMSnapshot.captureSnapshot` form
+    pub(crate) fn to_snapshot(&self) -> Value {
+        let mut strings = StringTable::default();
+        let documents: Vec<Value> = self
+            .documents
+            .iter()
+            .map(|doc| implode_document(doc, &mut strings))
+            .collect();
+        let mut snapshot = self.extra.clone();
+        snapshot.insert("documents".to_string(), Value::Array(documents));
+        snapshot.insert("strings".to_string(), json!(strings.strings));
+        Value::Object(snapshot)
+    }
+
+    /// Delta from `previous` to `self`, and the number of nodes it carries
+    fn diff(&self, previous: &SnapshotRecords) -> (Value, usize) {
+        let mut changed_nodes = 0;
+        let documents: Vec<Value> = self
+            .documents
+            .iter()
+            .enumerate()
+            .map(|(i, doc)| {
+                let base = previous.documents.get(i);
+                let mut entry = Map::new();
+                if base.is_none_or(|b| b.header != doc.header) {
+                    entry.insert("header".to_string(), doc.header.clone());
+                }
+                if base.is_none_or(|b| b.order != doc.order) {
+                    entry.insert("order".to_string(), json!(doc.order));
+                }
+                let changed: Map<String, Value> = doc
+                    .nodes
+                    .iter()
+                    .filter(|(id, record)| base.and_then(|b| b.nodes.get(id)) != Some(record))
+                    .map(|(id, record)| (id.to_string(), record.clone()))
+                    .collect();
+                changed_nodes += changed.len();
+                entry.insert("changed".to_string(), Value::Object(changed));
+                Value::Object(entry)
+            })
+            .collect();
+
+        let extra: Map<String, Value> = self
+            .extra
+            .iter()
+            .filter(|(key, value)| previous.extra.get(*key) != Some(value))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let removed: Vec<&String> = previous
+            .extra
+            .keys()
+            .filter(|key| !self.extra.contains_key(*key))
+            .collect();
+
+        let delta = json!({
+            "documents": documents,
+            "extra": extra,
+            "removedExtra": removed,
+        });
+        (delta, changed_nodes)
+    }
+
+    /// Apply a delta produced by [`SnapshotRecords::diff`]
+    fn apply(&mut self, delta: &Value) -> std::result::Result<(), &'static str> {
+        let documents = delta
+            .get("documents")
+            .and_then(|d| d.as_array())
+            .ok_or("missing documents")?;
+        self.documents
+            .resize_with(documents.len(), Default::default);
+        for (doc, entry) in self.documents.iter_mut().zip(documents) {
+            if let Some(header) = entry.get("header") {
+                doc.header = header.clone();
+            }
+            if let Some(order) = entry.get("order") {
+                doc.order = serde_json::from_value(order.clone())
+                    .map_err(|_| "order is not a list of node IDs")?;
+                let present: HashSet<i64> = doc.order.iter().copied().collect();
+                doc.nodes.retain(|id, _| present.contains(id));
+            }
+            if let Some(changed) = entry.get("changed").and_then(|c| c.as_object()) {
+                for (id, record) in changed {
+                    let id = id.parse().map_err(|_| "bad node ID")?;
+                    doc.nodes.insert(id, record.clone());
+                }
+            }
+        }
+
+        if let Some(extra) = delta.get("extra").and_then(|e| e.as_object()) {
+            for (key, value) in extra {
+                self.extra.insert(key.clone(), value.clone());
+            }
+        }
+        if let Some(removed) = delta.get("removedExtra").and_then(|r| r.as_array()) {
+            for key in removed.iter().filter_map(|k| k.as_str()) {
+                self.extra.remove(key);
+            }
+        }
+        Ok(())
+    }
+
+    fn node_count(&self) -> usize {
+        self.documents.iter().map(|doc| doc.order.len()).sum()
+    }
+}
+
+fn explode_document(doc: &Value, strings: &[String]) -> DocumentRecords {
+    let empty = Map::new();
+    let nodes = doc
+        .get("nodes")
+        .and_then(|n| n.as_object())
+        .unwrap_or(&empty);
+    let layout = doc
+        .get("layout")
+        .and_then(|l| l.as_object())
+        .unwrap_or(&empty);
+    let text_boxes = doc
+        .get("textBoxes")
+        .and_then(|t| t.as_object())
+        .unwrap_or(&empty);
+
+    let order: Vec<i64> = column(nodes, "backendNodeId")
+        .iter()
+        .map(|id| id.as_i64().unwrap_or(-1))
+        .collect();
+
+    // Node columns
+    let mut node_fields = vec![Map::new(); order.len()];
+    let mut node_columns = Map::new();
+    for (key, value) in nodes {
+        match key.as_str() {
+            "backendNodeId" => {}
+            "parentIndex" => {
+                for (fields, parent) in node_fields.iter_mut().zip(column(nodes, key)) {
+                    let parent = parent
+                        .as_i64()
+                        .and_then(|p| usize::try_from(p).ok())
+                        .and_then(|p| order.get(p));
+                    fields.insert("parent".to_string(), json!(parent));
+                }
+            }
+            _ => {
+                let kind = Column::for_node(key, value);
+                node_columns.insert(key.clone(), json!(kind.as_str()));
+                scatter(key, value, kind, strings, &mut node_fields);
+            }
+        }
+    }
+
+    // Layout columns, with text boxes attached to their layout entry
+    let node_index = column(layout, "nodeIndex");
+    let mut layout_fields = vec![Map::new(); node_index.len()];
+    let mut layout_columns = Map::new();
+    for (key, value) in layout {
+        if key != "nodeIndex" {
+            let kind = Column::for_layout(key, value);
+            layout_columns.insert(key.clone(), json!(kind.as_str()));
+            scatter(key, value, kind, strings, &mut layout_fields);
+        }
+    }
+    let mut boxes = vec![Vec::new(); layout_fields.len()];
+    for (t, layout_index) in column(text_boxes, "layoutIndex").iter().enumerate() {
+        if let Some(entry) = layout_index
+            .as_i64()
+            .and_then(|i| usize::try_from(i).ok())
+            .and_then(|i| boxes.get_mut(i))
+        {
+            let text_box: Map<String, Value> = ["bounds", "start", "length"]
+                .iter()
+                .map(|key| {
+                    let value = column(text_boxes, key).get(t).cloned();
+                    (key.to_string(), value.unwrap_or(Value::Null))
+                })
+                .collect();
+            entry.push(Value::Object(text_box));
+        }
+    }
+    let mut layouts = vec![Vec::new(); order.len()];
+    for ((mut fields, text_boxes), node) in layout_fields.into_iter().zip(boxes).zip(node_index) {
+        if let Some(entries) = node
+            .as_i64()
+            .and_then(|i| usize::try_from(i).ok())
+            .and_then(|i| layouts.get_mut(i))
+        {
+            if !text_boxes.is_empty() {
+                fields.insert("textBoxes".to_string(), Value::Array(text_boxes));
+            }
+            entries.push(Value::Object(fields));
+        }
+    }
+
+    let fields: Map<String, Value> = doc
+        .as_object()
+        .unwrap_or(&empty)
+        .iter()
+        .filter(|(key, _)| !matches!(key.as_str(), "nodes" | "layout" | "textBoxes"))
+        .map(|(key, value)| {
+            let value = if DOCUMENT_STRINGS.contains(&key.as_str()) {
+                resolve(value, strings)
+            } else {
+                value.clone()
+            };
+            (key.clone(), value)
+        })
+        .collect();
+
+    let nodes = order
+        .iter()
+        .zip(node_fields.into_iter().zip(layouts))
+        .map(|(id, (node, layout))| (*id, json!({ "node": node, "layout": layout })))
+        .collect();
+
+    DocumentRecords {
+        header: json!({
+            "fields": fields,
+            "nodeColumns": node_columns,
+            "layoutColumns": layout_columns,
+        }),
+        order,
+        nodes,
+    }
+}
+
+fn implode_document(doc: &DocumentRecords, strings: &mut StringTable) -> Value {
+    let position: HashMap<i64, usize> = doc
+        .order
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (*id, i))
+        .collect();
+    let records: Vec<&Value> = doc
+        .order
+        .iter()
+        .map(|id| doc.nodes.get(id).unwrap_or(&Value::Null))
+        .collect();
+
+    // Node columns
+    let mut nodes = Map::new();
+    let parents: Vec<Value> = records
+        .iter()
+        .map(|record| {
+            let parent = record["node"]["parent"]
+                .as_i64()
+                .and_then(|id| position.get(&id));
+            json!(parent.map_or(-1, |p| *p as i64))
+        })
+        .collect();
+    nodes.insert("parentIndex".to_string(), Value::Array(parents));
+    nodes.insert("backendNodeId".to_string(), json!(doc.order));
+    let node_rows: Vec<&Value> = records.iter().map(|record| &record["node"]).collect();
+    for (key, kind) in columns(&doc.header["nodeColumns"]) {
+        nodes.insert(key.clone(), gather(&key, kind, &node_rows, strings));
+    }
+
+    // Layout columns, in node order
+    let mut node_index = Vec::new();
+    let mut layout_rows = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        for entry in record["layout"].as_array().into_iter().flatten() {
+            node_index.push(i);
+            layout_rows.push(entry);
+        }
+    }
+    let mut layout = Map::new();
+    layout.insert("nodeIndex".to_string(), json!(node_index));
+    for (key, kind) in columns(&doc.header["layoutColumns"]) {
+        layout.insert(key.clone(), gather(&key, kind, &layout_rows, strings));
+    }
+
+    let mut text_boxes: Map<String, Value> = ["layoutIndex", "bounds", "start", "length"]
+        .iter()
+        .map(|key| (key.to_string(), json!([])))
+        .collect();
+    for (layout_index, entry) in layout_rows.iter().enumerate() {
+        for text_box in entry["textBoxes"].as_array().into_iter().flatten() {
+            push(&mut text_boxes, "layoutIndex", json!(layout_index));
+            for key in ["bounds", "start", "length"] {
+                push(&mut text_boxes, key, text_box[key].clone());
+            }
+        }
+    }
+
+    let mut document = Map::new();
+    if let Some(fields) = doc.header["fields"].as_object() {
+        for (key, value) in fields {
+            let value = if DOCUMENT_STRINGS.contains(&key.as_str()) {
+                strings.intern(value)
+            } else {
+                value.clone()
+            };
+            document.insert(key.clone(), value);
+        }
+    }
+    document.insert("nodes".to_string(), Value::Object(nodes));
+    document.insert("layout".to_string(), Value::Object(layout));
+    document.insert("textBoxes".to_string(), Value::Object(text_boxes));
+    Value::Object(document)
+}
+
+/// Column `key` of a table as a slice (empty if absent)
+fn column<'a>(table: &'a Map<String, Value>, key: &str) -> &'a [Value] {
+    table
+        .get(key)
+        .and_then(|c| c.as_array())
+        .map_or(&[], |c| c.as_slice())
+}
+
+/// Column names and kinds recorded in a document header
+fn columns(header: &Value) -> Vec<(String, Column)> {
+    header
+        .as_object()
+        .map(|cols| {
+            cols.iter()
+                .map(|(key, kind)| (key.clone(), Column::parse(kind.as_str().unwrap_or("raw"))))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Spread column `key` over per-row field maps
+fn scatter(
+    key: &str,
+    value: &Value,
+    kind: Column,
+    strings: &[String],
+    rows: &mut [Map<String, Value>],
+) {
+    if kind.is_rare() {
+        let indexes = value["index"].as_array().map_or(&[][..], |i| i.as_slice());
+        let values = value["value"].as_array();
+        for (pos, index) in indexes.iter().enumerate() {
+            let Some(row) = index
+                .as_i64()
+                .and_then(|i| usize::try_from(i).ok())
+                .and_then(|i| rows.get_mut(i))
+            else {
+                continue;
+            };
+            let value = match (kind, values.and_then(|v| v.get(pos))) {
+                (Column::RareBoolean, _) => Value::Bool(true),
+                (_, Some(value)) => kind.decode(value, strings),
+                (_, None) => Value::Null,
+            };
+            row.insert(key.to_string(), value);
+        }
+    } else {
+        for (row, value) in rows.iter_mut().zip(value.as_array().into_iter().flatten()) {
+            row.insert(key.to_string(), kind.decode(value, strings));
+        }
+    }
+}
+
+/// Collect column `key` back from per-row field maps
+fn gather(key: &str, kind: Column, rows: &[&Value], strings: &mut StringTable) -> Value {
+    if !kind.is_rare() {
+        return Value::Array(
+            rows.iter()
+                .map(|row| kind.encode(row.get(key), strings))
+                .collect(),
+        );
+    }
+
+    let mut index = Vec::new();
+    let mut values = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        if let Some(value) = row.get(key) {
+            index.push(i);
+            if kind != Column::RareBoolean {
+                values.push(kind.encode(Some(value), strings));
+            }
+        }
+    }
+    if kind == Column::RareBoolean {
+        json!({ "index": index })
+    } else {
+        json!({ "index": index, "value": values })
+    }
+}
+
+fn push(table: &mut Map<String, Value>, key: &str, value: Value) {
+    if let Some(Value::Array(items)) = table.get_mut(key) {
+        items.push(value);
+    }
+}
+
+/// Shared state for delta capture across the frames of a session
+///
+/// Put a clone in [`CaptureOptions::visual_dom_deltas`](crate::step_frame::CaptureOptions);
+/// each capture then saves a delta against the previous frame's VisualDom, with a full
+/// keyframe every `keyframe_interval` frames, or whenever most nodes changed (e.g. after
+/// a navigation).
+#[derive(Clone)]
+pub struct VisualDomDeltas {
+    keyframe_interval: usize,
+    state: Arc<Mutex<DeltaState>>,
+}
+
+#[derive(Default)]
+struct DeltaState {
+    /// File name and records of the last saved frame
+    previous: Option<(String, SnapshotRecords)>,
+    /// Deltas saved since the last keyframe
+    since_keyframe: usize,
+}
+
+/// A snapshot prepared for saving by [`VisualDomDeltas::encode`]
+pub(crate) struct EncodedFrame {
+    /// File name of the frame this one is a delta against, or None for a keyframe
+    pub base: Option<String>,
+    records: SnapshotRecords,
+}
+
+impl VisualDomDeltas {
+    /// Save a keyframe every `keyframe_interval` frames (1 disables deltas)
+    pub fn new(keyframe_interval: usize) -> Self {
+        Self {
+            keyframe_interval: keyframe_interval.max(1),
+            state: Arc::default(),
+        }
+    }
+
+    /// Prepare `snapshot` for saving: either unchanged (keyframe) or as a delta
+    pub(crate) fn encode(&self, snapshot: Value) -> (Value, EncodedFrame) {
+        let records = SnapshotRecords::from_snapshot(&snapshot);
+        let state = self.state.lock().unwrap();
+
+        if let Some((base, previous)) = &state.previous {
+            if state.since_keyframe + 1 < self.keyframe_interval {
+                let (mut delta, changed) = records.diff(previous);
+                // A delta replacing most nodes is no smaller and only lengthens the chain
+                if changed * 2 <= records.node_count() {
+                    delta["delta"] = json!({ "base": base });
+                    let base = Some(base.clone());
+                    return (delta, EncodedFrame { base, records });
+                }
+            }
+        }
+        (
+            snapshot,
+            EncodedFrame {
+                base: None,
+                records,
+            },
+        )
+    }
+
+    /// Record a frame saved as `file_name`, so the next frame can be a delta against it
+    pub(crate) fn record(&self, file_name: String, frame: EncodedFrame) {
+        let mut state = self.state.lock().unwrap();
+        state.since_keyframe = match frame.base {
+            Some(_) => state.since_keyframe + 1,
+            None => 0,
+        };
+        state.previous = Some((file_name, frame.records));
+    }
+}
+
+impl Default for VisualDomDeltas {
+    fn default() -> Self {
+        Self::new(DEFAULT_KEYFRAME_INTERVAL)
+    }
+}
+
+impl std::fmt::Debug for VisualDomDeltas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VisualDomDeltas")
+            .field("keyframe_interval", &self.keyframe_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Whether a saved VisualDom value is a delta rather than a full snapshot
+pub fn is_delta(value: &Value) -> bool {
+    value.get("delta").is_some()
+}
+
+/// Load a saved VisualDom file as a full snapshot, applying deltas if it is one
+///
+/// Base files are looked up next to `path`.
+pub async fn reconstruct_visual_dom(path: &Path) -> Result<Value> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut value = read_visual_dom(path).await?;
+    let mut deltas = Vec::new();
+    while is_delta(&value) {
+        if deltas.len() >= MAX_CHAIN_LENGTH {
+            return Err(BrowserError::Other(format!(
+                "VisualDom delta chain from {} is too long",
+                path.display()
+            )));
+        }
+        let base = value["delta"]["base"]
+            .as_str()
+            .ok_or_else(|| BrowserError::Other("Invalid VisualDom delta: missing base".into()))?
+            .to_string();
+        deltas.push(value);
+        value = read_visual_dom(&dir.join(base)).await?;
+    }
+    if deltas.is_empty() {
+        return Ok(value);
+    }
+
+    let mut records = SnapshotRecords::from_snapshot(&value);
+    for delta in deltas.iter().rev() {
+        records
+            .apply(delta)
+            .map_err(|e| BrowserError::Other(format!("Invalid VisualDom delta: {}", e)))?;
+    }
+    Ok(records.to_snapshot())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two nodes (html > body) with layout, a text box, and rare data
+    fn snapshot(body_width: f64, title: &str) -> Value {
+        json!({
+            "documents": [{
+                "documentURL": 0, "title": 1, "baseURL": 0, "contentLanguage": -1,
+                "encodingName": -1, "publicId": -1, "systemId": -1, "frameId": -1,
+                "scrollOffsetX": 0.0, "scrollOffsetY": 0.0,
+                "nodes": {
+                    "parentIndex": [-1, 0],
+                    "nodeType": [1, 1],
+                    "nodeName": [2, 3],
+                    "nodeValue": [-1, -1],
+                    "backendNodeId": [10, 11],
+                    "attributes": [[], [4, 5]],
+                    "isClickable": { "index": [1] },
+                    "textValue": { "index": [1], "value": [6] },
+                },
+                "layout": {
+                    "nodeIndex": [0, 1],
+                    "styles": [[7], [7]],
+                    "bounds": [[0.0, 0.0, 800.0, 600.0], [0.0, 0.0, body_width, 20.0]],
+                    "text": [-1, 6],
+                },
+                "textBoxes": {
+                    "layoutIndex": [1],
+                    "bounds": [[0.0, 0.0, 50.0, 20.0]],
+                    "start": [0],
+                    "length": [5],
+                },
+            }],
+            "strings": [
+                "https://example.com/", title, "HTML", "BODY", "class", "main", "hello", "block",
+            ],
+            "images": [],
+        })
+    }
+
+    #[test]
+    fn test_records_round_trip() {
+        let original = snapshot(800.0, "Example");
+        let records = SnapshotRecords::from_snapshot(&original);
+        assert_eq!(records.node_count(), 2);
+
+        let body = &records.documents[0].nodes[&11];
+        assert_eq!(body["node"]["parent"], json!(10));
+        assert_eq!(body["node"]["attributes"], json!(["class", "main"]));
+        assert_eq!(body["node"]["isClickable"], json!(true));
+        assert_eq!(body["node"]["textValue"], json!("hello"));
+        assert_eq!(body["layout"][0]["text"], json!("hello"));
+        assert_eq!(body["layout"][0]["textBoxes"][0]["length"], json!(5));
+
+        // Rebuilt snapshots use a new string table but hold the same data
+        let rebuilt = records.to_snapshot();
+        assert_eq!(SnapshotRecords::from_snapshot(&rebuilt), records);
+        let parsed: crate::step_frame::CaptureSnapshotResponse =
+            serde_json::from_value(rebuilt).unwrap();
+        assert_eq!(
+            parsed.documents[0].nodes.backend_node_id,
+            Some(vec![10, 11])
+        );
+    }
+
+    #[test]
+    fn test_delta_contains_only_changed_nodes() {
+        let first = SnapshotRecords::from_snapshot(&snapshot(800.0, "Example"));
+        let second = SnapshotRecords::from_snapshot(&snapshot(640.0, "Example"));
+
+        let (delta, changed) = second.diff(&first);
+        assert_eq!(changed, 1);
+        assert!(delta["documents"][0]["changed"].get("11").is_some());
+        assert!(delta["documents"][0].get("order").is_none());
+        assert!(delta["documents"][0].get("header").is_none());
+
+        let mut applied = first.clone();
+        applied.apply(&delta).unwrap();
+        assert_eq!(applied, second);
+
+        // Removed nodes and document changes carry over too
+        let mut third = snapshot(640.0, "Updated");
+        third["documents"][0]["nodes"]["backendNodeId"] = json!([10, 12]);
+        let third = SnapshotRecords::from_snapshot(&third);
+        let (delta, _) = third.diff(&second);
+        let mut applied = second.clone();
+        applied.apply(&delta).unwrap();
+        assert_eq!(applied, third);
+        assert!(!applied.documents[0].nodes.contains_key(&11));
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let deltas = VisualDomDeltas::new(2);
+        let save = |name: &str, snapshot: Value| {
+            let (value, frame) = deltas.encode(snapshot);
+            let base = frame.base.clone();
+            deltas.record(name.to_string(), frame);
+            (is_delta(&value), base)
+        };
+
+        assert_eq!(save("frame_0000", snapshot(800.0, "A")), (false, None));
+        assert_eq!(
+            save("frame_0001", snapshot(640.0, "A")),
+            (true, Some("frame_0000".to_string()))
+        );
+        assert_eq!(save("frame_0002", snapshot(640.0, "A")), (false, None));
+
+        // Mostly different page: keyframe even inside the interval
+        let mut other = snapshot(640.0, "A");
+        other["documents"][0]["nodes"]["backendNodeId"] = json!([20, 21]);
+        assert_eq!(save("frame_0003", other), (false, None));
+    }
+
+    #[tokio::test]
+    async fn test_reconstruct_visual_dom() {
+        let dir = std::env::temp_dir().join(format!(
+            "robert-visualdom-delta-test-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let deltas = VisualDomDeltas::default();
+        let frames = [
+            snapshot(800.0, "A"),
+            snapshot(640.0, "A"),
+            snapshot(320.0, "B"),
+        ];
+        for (i, frame) in frames.iter().enumerate() {
+            let name = format!("frame_{:04}.visualdom.json", i);
+            let (value, encoded) = deltas.encode(frame.clone());
+            std::fs::write(dir.join(&name), value.to_string()).unwrap();
+            deltas.record(name, encoded);
+        }
+
+        let last = reconstruct_visual_dom(&dir.join("frame_0002.visualdom.json"))
+            .await
+            .unwrap();
+        assert_eq!(
+            SnapshotRecords::from_snapshot(&last),
+            SnapshotRecords::from_snapshot(&frames[2])
+        );
+
+        // Keyframes load unchanged
+        let first = reconstruct_visual_dom(&dir.join("frame_0000.visualdom.json"))
+            .await
+            .unwrap();
+        assert_eq!(first, frames[0]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
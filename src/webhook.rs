@@ -0,0 +1,410 @@
+//! Run Completion Webhooks
+//!
+//! POSTs a [`RunNotification`] (including the [`ExecutionReport`]) to each configured
+//! `[[webhooks]]` URL when a CLI run, inference, job, or scheduled run finishes, so
+//! external systems can react without polling.
+//!
+//! Deliveries are retried with exponential backoff on network errors, `429`, and
+//! `5xx` responses. When a webhook has a `secret`, the body is signed with
+//! HMAC-SHA256 and sent as `X-Robert-Signature: sha256=<hex>`; receivers should
+//! recompute the signature over the raw body and compare.
+
+use crate::cdp::summary::DEFAULT_KEY_FRAMES;
+use crate::cdp::{ExecutionReport, ExecutionSummary, GenerationUsage};
+use crate::changes::ChangeReport;
+use crate::config::{Config, WebhookConfig};
+use crate::crypto::{hex, hmac_sha256};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Header carrying the HMAC-SHA256 signature of the body
+pub const SIGNATURE_HEADER: &str = "X-Robert-Signature";
+
+/// Header carrying the event name
+pub const EVENT_HEADER: &str = "X-Robert-Event";
+
+/// Per-attempt request timeout
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome that triggered a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEvent {
+    /// Every command succeeded
+    Completed,
+    /// The script could not be run or a command failed
+    Failed,
+    /// Stopped by the client or daemon shutdown
+    Cancelled,
+}
+
+impl WebhookEvent {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::Completed => "completed",
+            WebhookEvent::Failed => "failed",
+            WebhookEvent::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// What kind of run finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RunSource {
+    /// `robert-webdriver run`
+    Cli,
+    /// `POST /inference`
+    Inference,
+    /// `POST /jobs`
+    Job,
+    /// One item of `POST /batch` (recorded in the run history, never sent to webhooks)
+    Batch,
+    /// A configured schedule
+    Schedule,
+}
+
+/// Payload POSTed to webhooks
+#[derive(Debug, Clone, Serialize)]
+pub struct RunNotification {
+    /// Outcome of the run
+    pub event: WebhookEvent,
+
+    /// What kind of run finished
+    pub source: RunSource,
+
+    /// Job or scheduled run identifier
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// Script name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+
+    /// ISO 8601 timestamp when the notification was created
+    pub timestamp: String,
+
+    /// Execution report (None if the script never ran)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<ExecutionReport>,
+
+    /// Error that prevented the script from running or completing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Short digest of the report, with its key screenshots (None if the script never ran)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<ExecutionSummary>,
+
+    /// Files written by the run (screenshots, extracted data, reports)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<String>,
+
+    /// Differences from the previous run (scheduled runs only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<ChangeReport>,
+
+    /// Tokens and cost of generating the script (None if it wasn't generated, or
+    /// came from the script cache)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<GenerationUsage>,
+}
+
+impl RunNotification {
+    /// Notification for a run that produced a report (event derived from the report)
+    pub fn from_report(source: RunSource, report: ExecutionReport) -> Self {
+        let event = if report.cancelled {
+            WebhookEvent::Cancelled
+        } else if report.is_success() {
+            WebhookEvent::Completed
+        } else {
+            WebhookEvent::Failed
+        };
+        let artifacts = report
+            .results
+            .iter()
+            .filter_map(|r| r.saved_file.clone())
+            .collect();
+
+        Self {
+            event,
+            source,
+            id: None,
+            script: Some(report.script_name.clone()),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            summary: Some(ExecutionSummary::from_report(
+                &report,
+                &[],
+                DEFAULT_KEY_FRAMES,
+            )),
+            report: Some(report),
+            error: None,
+            artifacts,
+            changes: None,
+            usage: None,
+        }
+    }
+
+    /// Notification for a run that failed without a report
+    pub fn failed(source: RunSource, error: impl Into<String>) -> Self {
+        Self {
+            event: WebhookEvent::Failed,
+            source,
+            id: None,
+            script: None,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            report: None,
+            error: Some(error.into()),
+            summary: None,
+            artifacts: Vec::new(),
+            changes: None,
+            usage: None,
+        }
+    }
+
+    /// Set the job or run identifier
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set the script name
+    pub fn with_script(mut self, script: impl Into<String>) -> Self {
+        self.script = Some(script.into());
+        self
+    }
+
+    /// Add an artifact path (e.g. a written report)
+    pub fn with_artifact(mut self, path: impl Into<String>) -> Self {
+        self.artifacts.push(path.into());
+        self
+    }
+
+    /// Attach the comparison with the previous run
+    pub fn with_changes(mut self, changes: ChangeReport) -> Self {
+        self.changes = Some(changes);
+        self
+    }
+
+    /// Attach the usage of generating the script
+    pub fn with_usage(mut self, usage: Option<GenerationUsage>) -> Self {
+        self.usage = usage;
+        self
+    }
+}
+
+/// Delivers run notifications to the configured webhooks
+pub struct Notifier {
+    hooks: Vec<WebhookConfig>,
+    artifacts_root: PathBuf,
+    client: reqwest::Client,
+}
+
+impl Notifier {
+    /// Notifier for the config's `[[webhooks]]` (does nothing if there are none)
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            hooks: config.webhooks.clone(),
+            artifacts_root: config.artifacts.root.clone(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Whether any webhooks are configured
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Deliver `notification` to every webhook subscribed to its event
+    ///
+    /// Waits for all deliveries, including retries. Failures are logged, never returned.
+    pub async fn notify(&self, notification: &RunNotification) {
+        let deliveries = self
+            .hooks
+            .iter()
+            .filter(|hook| hook.events.is_empty() || hook.events.contains(&notification.event))
+            .map(|hook| self.deliver(hook, notification));
+        futures::future::join_all(deliveries).await;
+    }
+
+    /// Deliver `notification` in the background
+    pub fn spawn_notify(self: &Arc<Self>, notification: RunNotification) {
+        if self.is_empty() {
+            return;
+        }
+        let notifier = self.clone();
+        tokio::spawn(async move { notifier.notify(&notification).await });
+    }
+
+    async fn deliver(&self, hook: &WebhookConfig, notification: &RunNotification) {
+        let body = match self.payload(hook, notification) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::warn!(url = %hook.url, error = %e, "Failed to serialize webhook payload");
+                return;
+            }
+        };
+        let signature = hook
+            .secret
+            .as_ref()
+            .map(|secret| format!("sha256={}", hex(&hmac_sha256(secret.as_bytes(), &body))));
+
+        let mut backoff = Duration::from_millis(hook.retry_backoff_ms);
+        for attempt in 0..=hook.max_retries {
+            if attempt > 0 {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+
+            let mut request = self
+                .client
+                .post(&hook.url)
+                .timeout(REQUEST_TIMEOUT)
+                .header("Content-Type", "application/json")
+                .header(EVENT_HEADER, notification.event.as_str())
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+
+            match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    tracing::debug!(url = %hook.url, attempt, "Webhook delivered");
+                    return;
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let retryable = status.is_server_error()
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                    tracing::warn!(url = %hook.url, attempt, %status, "Webhook rejected");
+                    if !retryable {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(url = %hook.url, attempt, error = %e, "Webhook delivery failed");
+                }
+            }
+        }
+        tracing::error!(url = %hook.url, attempts = hook.max_retries + 1, "Giving up on webhook");
+    }
+
+    /// Serialize the notification for one hook, applying its artifact settings
+    fn payload(
+        &self,
+        hook: &WebhookConfig,
+        notification: &RunNotification,
+    ) -> serde_json::Result<Vec<u8>> {
+        if hook.include_artifacts && hook.artifact_base_url.is_none() {
+            return serde_json::to_vec(notification);
+        }
+
+        let mut notification = notification.clone();
+        let key_frames = notification
+            .summary
+            .as_mut()
+            .map(|summary| &mut summary.key_frames);
+        if !hook.include_artifacts {
+            notification.artifacts.clear();
+            key_frames.map(Vec::clear);
+        } else if let Some(base) = &hook.artifact_base_url {
+            notification.artifacts = notification
+                .artifacts
+                .iter()
+                .filter_map(|path| artifact_link(base, &self.artifacts_root, path))
+                .collect();
+            if let Some(key_frames) = key_frames {
+                key_frames.retain_mut(|frame| {
+                    match artifact_link(base, &self.artifacts_root, &frame.screenshot) {
+                        Some(link) => {
+                            frame.screenshot = link;
+                            true
+                        }
+                        None => false,
+                    }
+                });
+            }
+        }
+        serde_json::to_vec(&notification)
+    }
+}
+
+/// URL for an artifact under `root`, or None for paths outside the root (which
+/// would otherwise reveal where files live on the server)
+pub(crate) fn artifact_link(base: &str, root: &Path, path: &str) -> Option<String> {
+    let path = Path::new(path);
+    let relative = path
+        .strip_prefix(root)
+        .ok()
+        .or_else(|| path.strip_prefix(root.strip_prefix(".").ok()?).ok())?;
+    let relative = relative
+        .components()
+        .map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if relative.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        relative.join("/")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifact_links() {
+        let root = Path::new("./artifacts");
+        assert_eq!(
+            artifact_link(
+                "https://files.example.com/robert/",
+                root,
+                "./artifacts/schedules/nightly/run-1/page.png"
+            ),
+            Some("https://files.example.com/robert/schedules/nightly/run-1/page.png".to_string())
+        );
+        assert_eq!(
+            artifact_link("https://files.example.com", root, "artifacts/report.json"),
+            Some("https://files.example.com/report.json".to_string())
+        );
+        // Files outside the root aren't linked, nor is their server path revealed
+        assert_eq!(
+            artifact_link("https://files.example.com", root, "/tmp/shot.png"),
+            None
+        );
+        assert_eq!(
+            artifact_link("https://files.example.com", root, "artifacts/../etc/passwd"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_notification_event() {
+        let mut report = ExecutionReport::new("example".to_string(), 2);
+        report.successful = 2;
+        let notification = RunNotification::from_report(RunSource::Job, report.clone());
+        assert_eq!(notification.event, WebhookEvent::Completed);
+
+        report.successful = 1;
+        report.failed = 1;
+        let notification =
+            RunNotification::from_report(RunSource::Schedule, report).with_id("run-1");
+        assert_eq!(notification.event, WebhookEvent::Failed);
+
+        let json = serde_json::to_value(&notification).unwrap();
+        assert_eq!(json["event"], "failed");
+        assert_eq!(json["source"], "schedule");
+        assert_eq!(json["id"], "run-1");
+        assert_eq!(json["script"], "example");
+        assert_eq!(json["summary"]["headline"], "example: ran 2 of 2 steps");
+    }
+}
@@ -11,7 +11,15 @@
 
 mod test_server;
 
-use robert_webdriver::{ChromeDriver, ConnectionMode};
+use robert_webdriver::browser::{
+    AccessibilityOptions, FormValue, Impact, LinkCheckOptions, MatchSource, PatchOperation,
+    PseudoState, RewriteRule, ScriptStrategy, SelectorCandidates, SeoRules,
+};
+use robert_webdriver::{
+    BrowserError, ChromeDriver, ConnectionMode, DriverConfig, DriverOptions, ElementFingerprint,
+};
+use std::sync::Arc;
+use std::time::Duration;
 use test_server::TestServer;
 
 #[tokio::test]
@@ -282,3 +290,1033 @@ async fn test_current_page() {
 
     driver.close().await.expect("Failed to close browser");
 }
+
+#[tokio::test]
+async fn test_get_element_rect() {
+    // Test get_element_rect() and get_elements_rects()
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+    let url = server.url();
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+
+    driver.navigate(&url).await.expect("Failed to navigate");
+
+    let link = driver
+        .get_element_rect("a")
+        .await
+        .expect("Failed to get link rect");
+    println!("✅ Link rect: {:?}", link);
+    assert!(link.visible);
+    assert!(link.in_viewport);
+    assert!(link.viewport.width > 0.0 && link.viewport.height > 0.0);
+    // Not scrolled, so page and viewport coordinates match
+    assert_eq!(link.page, link.viewport);
+
+    // Elements outside the viewport are visible but not in view
+    driver
+        .execute_script(
+            "document.body.insertAdjacentHTML('beforeend', \
+            '<p id=\"far\" style=\"margin-top: 5000px\">Far</p><p id=\"hidden\" style=\"display: none\">Hidden</p>')",
+        )
+        .await
+        .expect("Failed to add elements");
+    let far = driver
+        .get_element_rect("#far")
+        .await
+        .expect("Failed to get rect");
+    assert!(far.visible);
+    assert!(!far.in_viewport);
+    let hidden = driver
+        .get_element_rect("#hidden")
+        .await
+        .expect("Failed to get rect");
+    assert!(!hidden.visible);
+
+    // Page coordinates don't change when scrolling
+    driver
+        .execute_script("window.scrollTo(0, 4000)")
+        .await
+        .expect("Failed to scroll");
+    let scrolled = driver
+        .get_element_rect("#far")
+        .await
+        .expect("Failed to get rect");
+    assert!((scrolled.page.y - far.page.y).abs() < 1.0);
+    assert!((scrolled.viewport.y - (far.viewport.y - 4000.0)).abs() < 1.0);
+    assert!(scrolled.in_viewport);
+
+    let paragraphs = driver
+        .get_elements_rects("p")
+        .await
+        .expect("Failed to get paragraph rects");
+    assert_eq!(paragraphs.len(), 4);
+    assert!(driver
+        .get_elements_rects(".missing")
+        .await
+        .expect("Failed to query")
+        .is_empty());
+
+    let missing = driver.get_element_rect(".missing").await;
+    assert!(matches!(
+        missing,
+        Err(robert_webdriver::BrowserError::ElementNotFound(_))
+    ));
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_force_element_state() {
+    // Test force_element_state() and clear_forced_states()
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+    let url = server.url();
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+
+    driver.navigate(&url).await.expect("Failed to navigate");
+    driver
+        .execute_script(
+            "document.head.insertAdjacentHTML('beforeend', \
+            '<style>a:hover { color: rgb(255, 0, 0); } a:focus { outline: 3px solid rgb(0, 0, 255); }</style>')",
+        )
+        .await
+        .expect("Failed to add style");
+
+    let color = "getComputedStyle(document.querySelector('a')).color";
+    let outline = "getComputedStyle(document.querySelector('a')).outlineColor";
+    assert_ne!(
+        driver.execute_script(color).await.unwrap(),
+        "rgb(255, 0, 0)"
+    );
+
+    driver
+        .force_element_state("a", &[PseudoState::Hover, PseudoState::Focus])
+        .await
+        .expect("Failed to force state");
+    assert_eq!(
+        driver.execute_script(color).await.unwrap(),
+        "rgb(255, 0, 0)"
+    );
+    assert_eq!(
+        driver.execute_script(outline).await.unwrap(),
+        "rgb(0, 0, 255)"
+    );
+    println!("✅ Forced :hover and :focus styling applied");
+
+    // Forcing again replaces the previous states
+    driver
+        .force_element_state("a", &[PseudoState::Focus])
+        .await
+        .expect("Failed to force state");
+    assert_ne!(
+        driver.execute_script(color).await.unwrap(),
+        "rgb(255, 0, 0)"
+    );
+
+    driver
+        .clear_forced_states()
+        .await
+        .expect("Failed to clear states");
+    assert_ne!(
+        driver.execute_script(outline).await.unwrap(),
+        "rgb(0, 0, 255)"
+    );
+
+    assert!(driver.force_element_state("a", &[]).await.is_err());
+    assert!(driver
+        .force_element_state(".missing", &[PseudoState::Hover])
+        .await
+        .is_err());
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_emulate_media() {
+    // Test emulate_media() and clear_emulated_media()
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+    let url = server.url();
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+
+    driver.navigate(&url).await.expect("Failed to navigate");
+
+    let matches = |query: &str| format!("window.matchMedia('{}').matches", query);
+
+    driver
+        .emulate_media(true, true, true)
+        .await
+        .expect("Failed to emulate media");
+    for query in [
+        "(prefers-color-scheme: dark)",
+        "(prefers-reduced-motion: reduce)",
+        "print",
+    ] {
+        assert_eq!(
+            driver.execute_script(&matches(query)).await.unwrap(),
+            true,
+            "{} should match",
+            query
+        );
+    }
+    println!("✅ Dark mode, reduced motion, and print media emulated");
+
+    driver
+        .emulate_media(false, false, false)
+        .await
+        .expect("Failed to emulate media");
+    assert_eq!(
+        driver
+            .execute_script(&matches("(prefers-color-scheme: light)"))
+            .await
+            .unwrap(),
+        true
+    );
+    assert_eq!(
+        driver.execute_script(&matches("print")).await.unwrap(),
+        false
+    );
+
+    driver
+        .clear_emulated_media()
+        .await
+        .expect("Failed to clear media emulation");
+    assert_eq!(
+        driver.execute_script(&matches("screen")).await.unwrap(),
+        true
+    );
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_tap_responses() {
+    // Test tap_responses() with and without a JSONPath filter
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+    let url = server.url();
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+
+    driver.navigate(&url).await.expect("Failed to navigate");
+
+    let fetch_products = "fetch('/api/products').then((r) => r.json()).then(() => true)";
+    let timeout = Duration::from_secs(10);
+
+    let mut names = driver
+        .tap_responses("*/api/products", Some("$.data.products[*].name"))
+        .await
+        .expect("Failed to tap responses");
+    let mut bodies = driver
+        .tap_responses("*/api/*", None)
+        .await
+        .expect("Failed to tap responses");
+    let mut unmatched = driver
+        .tap_responses("*/graphql*", None)
+        .await
+        .expect("Failed to tap responses");
+
+    driver
+        .execute_script(fetch_products)
+        .await
+        .expect("Failed to fetch");
+
+    let response = names
+        .next_within(timeout)
+        .await
+        .expect("No filtered response");
+    assert!(response.url.ends_with("/api/products"));
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.values,
+        vec![serde_json::json!("Widget"), serde_json::json!("Gadget")]
+    );
+    println!("✅ Filtered response: {:?}", response.values);
+
+    let response = bodies.next_within(timeout).await.expect("No response");
+    assert_eq!(response.values.len(), 1);
+    assert_eq!(response.values[0]["data"]["products"][1]["id"], 2);
+
+    assert!(unmatched
+        .next_within(Duration::from_millis(500))
+        .await
+        .is_none());
+
+    assert!(driver.tap_responses("*", Some("$.data[")).await.is_err());
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_rewrite_responses() {
+    // Test rewrite_responses() with JSON Patch, regex, and status override rules
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+    let url = server.url();
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+
+    driver.navigate(&url).await.expect("Failed to navigate");
+
+    let fetch = |path: &str| {
+        format!(
+            "fetch('{}').then(async (r) => ({{ status: r.status, body: await r.json() }}))",
+            path
+        )
+    };
+
+    let rewriter = driver
+        .rewrite_responses(vec![
+            RewriteRule::json_patch(
+                "*/api/flags",
+                vec![PatchOperation::Replace {
+                    path: "/new_checkout".to_string(),
+                    value: serde_json::json!(true),
+                }],
+            ),
+            RewriteRule::regex("*/api/flags", "\"old\"", "\"new\""),
+            RewriteRule::body("*/api/products", r#"{"error":"unavailable"}"#).with_status(503),
+        ])
+        .await
+        .expect("Failed to rewrite responses");
+
+    let flags = driver
+        .execute_script(&fetch("/api/flags"))
+        .await
+        .expect("Failed to fetch flags");
+    assert_eq!(flags["status"], 200);
+    assert_eq!(
+        flags["body"],
+        serde_json::json!({"new_checkout": true, "banner": "new"})
+    );
+
+    let products = driver
+        .execute_script(&fetch("/api/products"))
+        .await
+        .expect("Failed to fetch products");
+    assert_eq!(products["status"], 503);
+    assert_eq!(products["body"]["error"], "unavailable");
+    assert_eq!(rewriter.rewritten(), 2);
+    println!("✅ Feature flag forced and error payload simulated");
+
+    rewriter.stop().await.expect("Failed to stop rewriting");
+    let flags = driver
+        .execute_script(&fetch("/api/flags"))
+        .await
+        .expect("Failed to fetch flags");
+    assert_eq!(flags["body"]["new_checkout"], false);
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_navigation_http_status() {
+    // Test the NavigationResult returned by navigate() and fail_on_http_error
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+    let url = server.url();
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+
+    let result = driver.navigate(&url).await.expect("Failed to navigate");
+    assert_eq!(result.status, Some(200));
+    assert_eq!(result.mime_type.as_deref(), Some("text/html"));
+    assert!(!result.is_http_error());
+
+    let result = driver
+        .navigate(&format!("{}/missing", url))
+        .await
+        .expect("A 404 is not an error by default");
+    assert_eq!(result.status, Some(404));
+    assert!(result.is_http_error());
+    assert!(result.url.ends_with("/missing"));
+
+    let result = driver
+        .navigate(&format!("{}/broken", url))
+        .await
+        .expect("A 500 is not an error by default");
+    assert_eq!(result.status, Some(500));
+    println!("✅ HTTP status reported: {:?}", result.status_text);
+
+    let result = driver
+        .navigate("about:blank")
+        .await
+        .expect("Failed to navigate");
+    assert_eq!(result.status, None);
+    driver.close().await.expect("Failed to close browser");
+
+    let driver = ChromeDriver::new_with_options(
+        ConnectionMode::Sandboxed {
+            chrome_path: None,
+            no_sandbox: true,
+            headless: true,
+        },
+        DriverOptions {
+            fail_on_http_error: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .expect("Failed to launch Chrome");
+
+    driver.navigate(&url).await.expect("Failed to navigate");
+    match driver.navigate(&format!("{}/missing", url)).await {
+        Err(BrowserError::HttpStatus { status, url }) => {
+            assert_eq!(status, 404);
+            assert!(url.ends_with("/missing"));
+        }
+        other => panic!("Expected HttpStatus error, got {:?}", other),
+    }
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_history_navigation() {
+    // Test go_back(), go_forward(), and reload() without history entry ids
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+    let url = server.url();
+
+    let driver = ChromeDriver::launch(DriverConfig::new().headless(true).sandbox(false))
+        .await
+        .expect("Failed to launch Chrome");
+
+    driver.navigate(&url).await.expect("Failed to navigate");
+    driver
+        .navigate(&format!("{}/missing", url))
+        .await
+        .expect("Failed to navigate");
+
+    let result = driver.go_back().await.expect("Failed to go back");
+    assert_eq!(result.status, Some(200));
+    assert!(!result.url.ends_with("/missing"));
+
+    let result = driver.go_forward().await.expect("Failed to go forward");
+    assert!(result.url.ends_with("/missing"));
+    assert_eq!(result.status, Some(404));
+    match driver.go_forward().await {
+        Err(BrowserError::NavigationFailed(message)) => assert!(message.contains("next")),
+        other => panic!(
+            "Expected NavigationFailed at the end of history, got {:?}",
+            other
+        ),
+    }
+
+    // Same-document entries don't wait for a load that never comes
+    driver
+        .execute_script("history.pushState({}, '', '#details')")
+        .await
+        .expect("Failed to push state");
+    let result = driver.go_back().await.expect("Failed to go back");
+    assert!(result.url.ends_with("/missing"));
+
+    let result = driver.reload(true).await.expect("Failed to reload");
+    assert_eq!(result.status, Some(404));
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_get_security_info() {
+    // Test get_security_info() on a plain HTTP page
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+    let url = server.url();
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+
+    driver.navigate(&url).await.expect("Failed to navigate");
+
+    let info = driver
+        .get_security_info()
+        .await
+        .expect("Failed to get security info");
+    assert!(!info.is_secure());
+    assert!(info.certificate.is_none());
+    println!("✅ Security state: {} {:?}", info.state, info.issues);
+
+    // Reading the state twice works (the Security domain is re-enabled each time)
+    let again = driver
+        .get_security_info()
+        .await
+        .expect("Failed to get security info");
+    assert_eq!(again.state, info.state);
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_audit_links() {
+    // Test audit_links() reporting broken subresources and anchor targets
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+    let url = server.url();
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+
+    let report = driver
+        .audit_links(&format!("{}/dead-links", url), &LinkCheckOptions::default())
+        .await
+        .expect("Failed to audit links");
+    assert_eq!(report.status, Some(200));
+    assert!(!report.is_clean());
+
+    let mut resources: Vec<(&str, Option<i64>)> = report
+        .resources
+        .iter()
+        .map(|r| (r.resource_type.as_str(), r.status))
+        .collect();
+    resources.sort();
+    assert_eq!(resources, vec![("Image", Some(404)), ("Script", Some(404))]);
+
+    // /site and /site#top are one target; mailto: is not checked
+    assert_eq!(report.links_checked, 2);
+    assert_eq!(report.links.len(), 1);
+    assert_eq!(report.links[0].url, format!("{}/missing", url));
+    assert_eq!(report.links[0].text, "Gone");
+    assert_eq!(report.links[0].status, Some(404));
+    println!("✅ Broken links: {:?}", report.links);
+
+    let report = driver
+        .audit_links(&url, &LinkCheckOptions::default())
+        .await
+        .expect("Failed to audit links");
+    assert!(report.resources.is_empty());
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_audit_accessibility_with_page_axe() {
+    // Test audit_accessibility() against a page that provides its own (stub) axe
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+    driver
+        .navigate(&server.url())
+        .await
+        .expect("Failed to navigate");
+
+    // Stands in for axe-core, which isn't available offline in tests
+    driver
+        .execute_script(
+            r#"window.axe = { run: async (context, options) => ({
+                url: location.href,
+                testEngine: { version: "0.0.0-stub" },
+                violations: [{
+                    id: "image-alt", impact: "critical", description: "Images need alt text",
+                    help: "Add alt text", helpUrl: "https://example.com/image-alt",
+                    tags: options.runOnly ? options.runOnly.values : [],
+                    nodes: [{ target: [["iframe", "img"]], html: "<img>", failureSummary: "No alt", impact: "critical" }],
+                }],
+                passes: [{}], incomplete: [], inapplicable: [{}, {}],
+            }) }; true"#,
+        )
+        .await
+        .expect("Failed to install stub axe");
+
+    let report = driver
+        .audit_accessibility(&AccessibilityOptions {
+            tags: vec!["wcag2a".to_string()],
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to audit accessibility");
+    assert_eq!(report.axe_version, "0.0.0-stub");
+    assert_eq!(report.passes, 1);
+    assert_eq!(report.inapplicable, 2);
+    let violation = &report.violations[0];
+    assert_eq!(violation.impact, Some(Impact::Critical));
+    assert_eq!(violation.tags, vec!["wcag2a".to_string()]);
+    assert_eq!(
+        violation.nodes[0].target,
+        vec!["iframe >>> img".to_string()]
+    );
+    assert_eq!(report.violations_at_least(Impact::Serious).count(), 1);
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_audit_performance() {
+    // Test audit_performance() on a freshly loaded page
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+    driver
+        .navigate(&server.url())
+        .await
+        .expect("Failed to navigate");
+
+    let report = driver
+        .audit_performance()
+        .await
+        .expect("Failed to audit performance");
+    assert!(report.score <= 100);
+    assert!(report.timings.ttfb_ms.is_some());
+    assert!(report.request_count >= 1);
+    assert!(report
+        .resources
+        .iter()
+        .any(|r| r.resource_type == "document"));
+    assert!(report.metrics.iter().any(|m| m.id == "ttfb"));
+    assert!(report.runtime.dom_nodes > 0);
+    println!(
+        "✅ Performance score {} ({:?})",
+        report.score, report.timings
+    );
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_extract_seo() {
+    // Test extract_seo() and validation of the result
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+    let url = format!("{}/seo", server.url());
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+    driver.navigate(&url).await.expect("Failed to navigate");
+
+    let seo = driver
+        .extract_seo()
+        .await
+        .expect("Failed to extract SEO metadata");
+    assert_eq!(
+        seo.title.as_deref(),
+        Some("Widgets for every occasion | Example Shop")
+    );
+    assert_eq!(seo.canonical.as_deref(), Some(url.as_str()));
+    assert_eq!(seo.robots, vec!["index", "follow"]);
+    assert!(!seo.is_noindex());
+    assert_eq!(seo.lang.as_deref(), Some("en"));
+    assert_eq!(seo.h1, vec!["Widgets"]);
+    assert_eq!(seo.open_graph["og:type"], "website");
+    assert_eq!(seo.twitter["twitter:card"], "summary");
+    assert_eq!(seo.structured_data.len(), 1);
+    assert_eq!(seo.structured_data[0]["@type"], "Product");
+    assert_eq!(seo.structured_data_errors.len(), 1);
+
+    let issues = seo.validate(&SeoRules::default());
+    let fields: Vec<&str> = issues.iter().map(|i| i.field.as_str()).collect();
+    assert_eq!(fields, vec!["description", "structured_data"]);
+    println!("✅ SEO issues: {:?}", issues);
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_fill_form() {
+    // Test fill_form() matching fields by name, label, placeholder, and legend
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+    driver
+        .navigate(&format!("{}/form", server.url()))
+        .await
+        .expect("Failed to navigate");
+
+    let result = driver
+        .fill_form([
+            ("Email address", FormValue::from("alice@example.com")),
+            ("Full name", FormValue::from("Alice Example")),
+            ("country", FormValue::from("Canada")),
+            ("Subscribe to newsletter", FormValue::from(true)),
+            ("Plan", FormValue::from("Pro")),
+            ("token", FormValue::from("stolen")),
+            ("country", FormValue::from("Mars")),
+        ])
+        .await
+        .expect("Failed to fill form");
+    println!("✅ Fill result: {:?}", result);
+    assert_eq!(
+        result.filled,
+        vec![
+            "Email address",
+            "Full name",
+            "country",
+            "Subscribe to newsletter",
+            "Plan"
+        ]
+    );
+    // Hidden fields are never filled
+    assert_eq!(result.unmatched, vec!["token"]);
+    assert_eq!(result.invalid.len(), 1);
+    assert_eq!(result.invalid[0].field, "country");
+    assert_eq!(result.invalid[0].reason, "no option 'Mars'");
+
+    let form = driver
+        .execute_script(
+            "(() => { const f = document.getElementById('signup'); return { \
+                 email: f.user_email.value, name: f.full_name.value, country: f.country.value, \
+                 newsletter: f.newsletter.checked, plan: f.plan.value, token: f.token.value, \
+                 events: window.events }; })()",
+        )
+        .await
+        .expect("Failed to read form");
+    assert_eq!(form["email"], "alice@example.com");
+    assert_eq!(form["name"], "Alice Example");
+    assert_eq!(form["country"], "ca");
+    assert_eq!(form["newsletter"], true);
+    assert_eq!(form["plan"], "pro");
+    assert_eq!(form["token"], "secret");
+    // Values arrive through input events, not just property assignment
+    assert!(form["events"]["user_email"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("input")));
+    assert!(form["events"]["country"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("change")));
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_type_text_widgets() {
+    // Test type_text() choosing widget strategies, including a custom one
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+    driver
+        .navigate(&format!("{}/widgets", server.url()))
+        .await
+        .expect("Failed to navigate");
+
+    driver.widgets().register(Arc::new(ScriptStrategy::new(
+        "acme",
+        "function() { return this.classList.contains('acme-picker'); }",
+        "function(text) { this.acme.set(text); this.dataset.value = this.acme.value; return null; }",
+    )));
+
+    for (selector, text, strategy) in [
+        ("#date", "2024-05-01", "native-date"),
+        ("#editor", "New text", "contenteditable"),
+        ("#fruit", "Apr", "combobox"),
+        ("#acme", "blue", "acme"),
+    ] {
+        let used = driver
+            .type_text(selector, text)
+            .await
+            .unwrap_or_else(|e| panic!("Failed to type into {}: {}", selector, e));
+        assert_eq!(used.as_deref(), Some(strategy));
+    }
+
+    let state = driver
+        .execute_script(
+            "({ date: document.getElementById('date').value, \
+                editor: document.getElementById('editor').textContent, \
+                editorInputs: window.editorInputs, \
+                picked: document.getElementById('picked').textContent, \
+                acme: document.getElementById('acme').dataset.value })",
+        )
+        .await
+        .expect("Failed to read widgets");
+    println!("✅ Widget state: {}", state);
+    assert_eq!(state["date"], "2024-05-01");
+    assert_eq!(state["editor"], "New text");
+    assert!(state["editorInputs"].as_u64().unwrap() > 0);
+    assert_eq!(state["picked"], "Apricot");
+    assert_eq!(state["acme"], "blue");
+
+    // Options that don't match fail instead of leaving free text behind
+    let error = driver.type_text("#fruit", "Cherry").await.unwrap_err();
+    assert!(error.to_string().contains("no option matching 'Cherry'"));
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_type_text_ime() {
+    // Test that CJK text is composed through IME events and arrives intact
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+    driver
+        .navigate(&format!("{}/ime", server.url()))
+        .await
+        .expect("Failed to navigate");
+
+    driver
+        .type_text("#query", "東京タワー tower 한국")
+        .await
+        .expect("Failed to type");
+
+    let value = driver
+        .execute_script("document.getElementById('query').value")
+        .await
+        .expect("Failed to read value");
+    assert_eq!(value, "東京タワー tower 한국");
+
+    let compositions = driver
+        .execute_script("window.compositions")
+        .await
+        .expect("Failed to read compositions");
+    println!("✅ Compositions: {}", compositions);
+    assert_eq!(
+        compositions,
+        serde_json::json!(["start", "東京タワー", "start", "한", "start", "국"])
+    );
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_touch_gestures() {
+    // Test tap(), swipe(), and pinch_zoom() against touch-only handlers
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+    driver
+        .navigate(&format!("{}/touch", server.url()))
+        .await
+        .expect("Failed to navigate");
+
+    driver.tap("#like").await.expect("Failed to tap");
+    driver
+        .swipe((300.0, 150.0), (50.0, 150.0), Duration::from_millis(200))
+        .await
+        .expect("Failed to swipe");
+
+    let gestures = driver
+        .execute_script("window.gestures")
+        .await
+        .expect("Failed to read gestures");
+    assert_eq!(gestures["taps"], 1);
+    assert_eq!(gestures["slide"], 1);
+
+    driver.pinch_zoom(2.0).await.expect("Failed to pinch");
+    let gestures = driver
+        .execute_script("window.gestures")
+        .await
+        .expect("Failed to read gestures");
+    println!("✅ Gestures: {}", gestures);
+    assert_eq!(gestures["fingers"], 2);
+    let spread: Vec<f64> = serde_json::from_value(gestures["spread"].clone()).unwrap();
+    assert!(spread.last().unwrap() > spread.first().unwrap());
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_fallback_selectors() {
+    // Test resolve_selector() falling back to other selectors, then to the text
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+    driver
+        .navigate(&format!("{}/touch", server.url()))
+        .await
+        .expect("Failed to navigate");
+
+    let mut candidates = SelectorCandidates {
+        selector: "#like".to_string(),
+        fallbacks: vec!["#dislike".to_string()],
+        text: Some("like".to_string()),
+        ..Default::default()
+    };
+    let matched = driver.resolve_selector(&candidates).await.unwrap();
+    assert_eq!(matched.selector, "#like");
+    assert_eq!(matched.source, MatchSource::Selector);
+
+    candidates.selector = "#renamed".to_string();
+    candidates.fallbacks.push("button".to_string());
+    let matched = driver.resolve_selector(&candidates).await.unwrap();
+    assert_eq!(matched.selector, "button");
+    assert_eq!(matched.source, MatchSource::Fallback { index: 1 });
+
+    candidates.fallbacks.clear();
+    let matched = driver.resolve_selector(&candidates).await.unwrap();
+    assert_eq!(matched.source, MatchSource::Text);
+    driver.tap(&matched.selector).await.expect("Failed to tap");
+    let gestures = driver
+        .execute_script("window.gestures")
+        .await
+        .expect("Failed to read gestures");
+    assert_eq!(gestures["taps"], 1);
+
+    candidates.text = Some("Subscribe".to_string());
+    match driver.resolve_selector(&candidates).await {
+        Err(BrowserError::ElementNotFound(tried)) => {
+            assert_eq!(tried, "#renamed, text 'Subscribe'")
+        }
+        other => panic!("Expected ElementNotFound, got {:?}", other),
+    }
+
+    driver.close().await.expect("Failed to close browser");
+}
+
+#[tokio::test]
+async fn test_fingerprint_selectors() {
+    // Test recording an element fingerprint and finding the element by it
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .expect("Failed to launch Chrome");
+    driver
+        .navigate(&format!("{}/touch", server.url()))
+        .await
+        .expect("Failed to navigate");
+
+    let fingerprint = driver
+        .element_fingerprint("#like")
+        .await
+        .expect("Failed to fingerprint");
+    assert_eq!(fingerprint.tag, "button");
+    assert_eq!(fingerprint.text, "Like");
+    assert_eq!(fingerprint.attributes["id"], "like");
+    assert!(fingerprint.rect.is_some());
+
+    let mut candidates = SelectorCandidates {
+        selector: "#renamed".to_string(),
+        fingerprint: Some(fingerprint.clone()),
+        ..Default::default()
+    };
+    let matched = driver.resolve_selector(&candidates).await.unwrap();
+    match &matched.source {
+        MatchSource::Fingerprint {
+            confidence,
+            suggested,
+        } => {
+            assert!(*confidence > 0.9, "{}", confidence);
+            assert_eq!(suggested.as_deref(), Some("#like"));
+        }
+        other => panic!("Expected a fingerprint match, got {:?}", other),
+    }
+    driver.tap(&matched.selector).await.expect("Failed to tap");
+    let gestures = driver
+        .execute_script("window.gestures")
+        .await
+        .expect("Failed to read gestures");
+    assert_eq!(gestures["taps"], 1);
+
+    // Nothing on the page looks like this
+    candidates.fingerprint = Some(ElementFingerprint {
+        text: "Subscribe".to_string(),
+        attributes: [("id".to_string(), "subscribe".to_string())].into(),
+        ..fingerprint
+    });
+    match driver.resolve_selector(&candidates).await {
+        Err(BrowserError::ElementNotFound(tried)) => {
+            assert_eq!(tried, "#renamed, fingerprint of <button>")
+        }
+        other => panic!("Expected ElementNotFound, got {:?}", other),
+    }
+
+    driver.close().await.expect("Failed to close browser");
+}
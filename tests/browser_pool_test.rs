@@ -0,0 +1,65 @@
+//! Integration tests for BrowserPool parallel execution
+
+mod test_server;
+
+use robert_webdriver::{BrowserPool, CdpCommand, CdpScript, Config};
+use test_server::TestServer;
+
+fn title_script(name: &str, url: &str) -> CdpScript {
+    CdpScript {
+        name: name.to_string(),
+        description: "Navigate and read the title".to_string(),
+        created: None,
+        author: Some("Test".to_string()),
+        version: None,
+        tags: vec![],
+        cdp_commands: vec![
+            CdpCommand {
+                method: "Page.navigate".to_string(),
+                params: serde_json::json!({ "url": url }),
+                save_as: None,
+                description: None,
+            },
+            CdpCommand {
+                method: "Runtime.evaluate".to_string(),
+                params: serde_json::json!({ "expression": "document.title", "returnByValue": true }),
+                save_as: None,
+                description: None,
+            },
+        ],
+    }
+}
+
+#[tokio::test]
+async fn test_run_scripts_parallel() {
+    let server = TestServer::start().await;
+    server.wait_ready().await.expect("Server failed to start");
+    let url = server.url();
+
+    let mut config = Config::default();
+    config.chrome.headless = true;
+    config.chrome.no_sandbox = true;
+
+    let pool = BrowserPool::launch(2, &config)
+        .await
+        .expect("Failed to launch browser pool");
+    assert_eq!(pool.size(), 2);
+
+    let scripts: Vec<CdpScript> = (0..5)
+        .map(|i| title_script(&format!("title-{}", i), &url))
+        .collect();
+
+    let outcomes = pool.run_scripts_parallel(&scripts).await;
+
+    assert_eq!(outcomes.len(), 5);
+    for (i, outcome) in outcomes.iter().enumerate() {
+        assert_eq!(outcome.index, i, "Outcomes should be in input order");
+        assert_eq!(outcome.script, format!("title-{}", i));
+        assert!(outcome.is_success(), "Script {} failed: {:?}", i, outcome);
+    }
+
+    // Every session is back in the pool
+    assert_eq!(pool.available(), 2);
+
+    pool.close().await.expect("Failed to close pool");
+}
@@ -0,0 +1,88 @@
+//! Integration tests for captcha detection
+
+mod test_server;
+
+use robert_webdriver::browser::captcha::{CaptchaKind, FailOnCaptcha};
+use robert_webdriver::{
+    CdpCommand, CdpExecutor, CdpScript, ChromeDriver, CommandStatus, ConnectionMode,
+};
+use std::sync::Arc;
+use test_server::TestServer;
+
+async fn create_headless_driver() -> anyhow::Result<ChromeDriver> {
+    ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to launch Chrome: {}", e))
+}
+
+fn navigate_script(url: &str) -> CdpScript {
+    CdpScript {
+        name: "navigate".to_string(),
+        description: "Navigate to a page".to_string(),
+        created: None,
+        author: Some("Test".to_string()),
+        version: None,
+        tags: vec![],
+        cdp_commands: vec![CdpCommand {
+            method: "Page.navigate".to_string(),
+            params: serde_json::json!({ "url": url }),
+            save_as: None,
+            description: None,
+        }],
+    }
+}
+
+#[tokio::test]
+async fn test_detect_captcha() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&server.url()).await?;
+    assert!(driver.detect_captcha().await?.is_none());
+
+    driver
+        .navigate(&format!("{}/captcha", server.url()))
+        .await?;
+    let event = driver
+        .detect_captcha()
+        .await?
+        .expect("hCaptcha frame should be detected");
+    assert_eq!(event.kind, CaptchaKind::Hcaptcha);
+    assert_eq!(
+        event.site_key.as_deref(),
+        Some("10000000-ffff-ffff-ffff-000000000001")
+    );
+
+    driver.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_executor_stops_on_captcha() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    let page = driver.current_page().await?;
+    let executor = CdpExecutor::new(page).with_captcha_handler(Arc::new(FailOnCaptcha));
+
+    let report = executor
+        .execute_script(&navigate_script(&server.url()))
+        .await?;
+    assert!(report.is_success());
+
+    let report = executor
+        .execute_script(&navigate_script(&format!("{}/captcha", server.url())))
+        .await?;
+    assert_eq!(report.results[0].status, CommandStatus::Failed);
+    let error = report.results[0].error.as_deref().unwrap_or_default();
+    assert!(error.contains("Captcha detected"), "{}", error);
+
+    driver.close().await?;
+    Ok(())
+}
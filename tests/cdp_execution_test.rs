@@ -50,6 +50,7 @@ async fn test_cdp_navigation() {
         description: "Test CDP navigation".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["cdp".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -116,6 +117,7 @@ async fn test_send_cdp_command_evaluate() {
         description: "Setup page for send_cdp_command test".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec![],
         cdp_commands: vec![CdpCommand {
             method: "Page.navigate".to_string(),
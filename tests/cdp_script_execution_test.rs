@@ -27,6 +27,7 @@ async fn test_execute_navigation_and_screenshot() {
         description: "Navigate and take screenshot".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["screenshot".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -96,6 +97,7 @@ async fn test_execute_data_extraction() {
         description: "Extract page data".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["extraction".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -185,6 +187,7 @@ async fn test_execute_programmatic_script() {
         description: "Test script created in code".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["test".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -238,6 +241,7 @@ async fn test_invalid_cdp_command() {
         description: "Test with invalid command".to_string(),
         created: None,
         author: None,
+        version: None,
         tags: vec![],
         cdp_commands: vec![CdpCommand {
             method: "Invalid.command".to_string(),
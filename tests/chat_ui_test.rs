@@ -218,10 +218,10 @@ async fn test_chat_ui_can_be_disabled() -> anyhow::Result<()> {
     server.wait_ready().await?;
     let url = server.url();
 
-    let mut driver = create_headless_driver().await?;
+    let driver = create_headless_driver().await?;
 
     // Disable chat UI
-    driver.chat_ui_mut().disable();
+    driver.chat_ui().disable();
 
     // Navigate - chat UI should not be injected
     driver.navigate(&url).await?;
@@ -247,10 +247,10 @@ async fn test_manual_chat_ui_injection() -> anyhow::Result<()> {
     server.wait_ready().await?;
     let url = server.url();
 
-    let mut driver = create_headless_driver().await?;
+    let driver = create_headless_driver().await?;
 
     // Disable chat UI for automatic injection
-    driver.chat_ui_mut().disable();
+    driver.chat_ui().disable();
 
     // Navigate - chat UI should not be injected
     driver.navigate(&url).await?;
@@ -266,7 +266,7 @@ async fn test_manual_chat_ui_injection() -> anyhow::Result<()> {
     assert_eq!(has_chat_ui_before, serde_json::json!(false));
 
     // Enable chat UI and manually inject
-    driver.chat_ui_mut().enable();
+    driver.chat_ui().enable();
     driver.inject_chat_ui().await?;
 
     // Verify chat UI is now present
@@ -0,0 +1,70 @@
+//! Integration tests for page crash detection
+
+mod test_server;
+
+use robert_webdriver::browser::PageEventKind;
+use robert_webdriver::{CdpCommand, CdpExecutor, CdpScript, ChromeDriver, ConnectionMode};
+use std::time::Duration;
+use test_server::TestServer;
+
+async fn create_headless_driver() -> anyhow::Result<ChromeDriver> {
+    ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to launch Chrome: {}", e))
+}
+
+#[tokio::test]
+async fn test_crash_report() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&server.url()).await?;
+    driver
+        .execute_script("console.log('about to crash')")
+        .await?;
+    assert!(driver.crash_report().await?.is_none());
+
+    let page = driver.current_page().await?;
+    let monitor = driver
+        .crash_monitor(&page)
+        .expect("pages used by the driver are watched");
+    let crash = CdpScript {
+        name: "crash".to_string(),
+        description: "Crash the renderer".to_string(),
+        created: None,
+        author: Some("Test".to_string()),
+        version: None,
+        tags: vec![],
+        cdp_commands: vec![CdpCommand {
+            method: "Page.navigate".to_string(),
+            params: serde_json::json!({ "url": "chrome://crash" }),
+            save_as: None,
+            description: None,
+        }],
+    };
+    let _ = CdpExecutor::new(page.clone())
+        .with_driver(&driver)
+        .execute_script(&crash)
+        .await;
+    tokio::time::timeout(Duration::from_secs(10), monitor.crashed()).await?;
+
+    let report = monitor.report().expect("crash should be reported");
+    assert!(report
+        .url
+        .as_deref()
+        .unwrap_or_default()
+        .starts_with(&server.url()));
+    assert!(report
+        .recent_events
+        .iter()
+        .any(|e| e.kind == PageEventKind::Console && e.message.contains("about to crash")));
+    assert!(report.screenshot.is_some());
+
+    driver.close().await?;
+    Ok(())
+}
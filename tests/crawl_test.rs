@@ -0,0 +1,175 @@
+//! Integration tests for the crawler and sitemap batch capture
+
+mod test_server;
+
+use robert_webdriver::browser::pool::BrowserPool;
+use robert_webdriver::crawl::{CrawlOptions, Crawler};
+use robert_webdriver::sitemap::{self, BatchCapture};
+use robert_webdriver::step_frame::CaptureOptions;
+use robert_webdriver::{ChromeDriver, ConnectionMode};
+use test_server::TestServer;
+
+async fn create_pool(size: usize) -> anyhow::Result<BrowserPool> {
+    let mut drivers = Vec::new();
+    for _ in 0..size {
+        drivers.push(
+            ChromeDriver::new(ConnectionMode::Sandboxed {
+                chrome_path: None,
+                no_sandbox: true,
+                headless: true,
+            })
+            .await?,
+        );
+    }
+    Ok(BrowserPool::from_drivers(drivers))
+}
+
+#[tokio::test]
+async fn test_crawl_same_origin_with_robots() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let pool = create_pool(2).await?;
+    let output = tempfile_dir("crawl");
+
+    let options = CrawlOptions {
+        max_depth: 2,
+        capture: Some(CaptureOptions {
+            screenshot_dir: output.join("screenshots"),
+            dom_dir: Some(output.join("dom")),
+            ..Default::default()
+        }),
+        extract_script: Some("document.title".to_string()),
+        ..Default::default()
+    };
+    let report = Crawler::new(&pool, options)
+        .crawl(&[format!("{}/site", server.url())])
+        .await?;
+    pool.close().await?;
+
+    let mut titles: Vec<&str> = report
+        .pages
+        .iter()
+        .map(|page| page.extracted.as_ref().unwrap().as_str().unwrap())
+        .collect();
+    titles.sort();
+    // Private is disallowed, the external link and the fragment duplicate are skipped
+    assert_eq!(titles, vec!["A", "B", "C", "Site"]);
+    assert_eq!(
+        report.disallowed,
+        vec![format!("{}/site/private", server.url())]
+    );
+    assert_eq!(report.failures().count(), 0);
+
+    let seed = &report.pages[0];
+    assert_eq!(seed.depth, 0);
+    assert_eq!(seed.status, Some(200));
+    assert_eq!(seed.queued_links, 3);
+    let c = report
+        .pages
+        .iter()
+        .find(|page| page.url.ends_with("/site/c"))
+        .unwrap();
+    assert_eq!(c.depth, 2);
+
+    for page in &report.pages {
+        let frame = page.frame.as_ref().expect("frame captured");
+        assert!(std::path::Path::new(&frame.screenshot.path).exists());
+    }
+
+    let _ = std::fs::remove_dir_all(&output);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_crawl_limits() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let pool = create_pool(1).await?;
+
+    let options = CrawlOptions {
+        max_depth: 1,
+        exclude: vec!["*/site/b".to_string()],
+        respect_robots: false,
+        ..Default::default()
+    };
+    let report = Crawler::new(&pool, options)
+        .crawl(&[format!("{}/site", server.url())])
+        .await?;
+    let mut urls: Vec<&str> = report.pages.iter().map(|page| page.url.as_str()).collect();
+    urls.sort();
+    let base = server.url();
+    assert_eq!(
+        urls,
+        vec![
+            format!("{}/site", base),
+            format!("{}/site/a", base),
+            format!("{}/site/private", base),
+        ]
+    );
+    assert!(report.disallowed.is_empty());
+
+    let options = CrawlOptions {
+        max_pages: 2,
+        ..Default::default()
+    };
+    let report = Crawler::new(&pool, options)
+        .crawl(&[format!("{}/site", server.url())])
+        .await?;
+    assert_eq!(report.pages.len(), 2);
+    assert!(report.truncated);
+
+    pool.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sitemap_batch_capture() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let urls = sitemap::load_urls(&format!("{}/sitemap.xml", server.url())).await?;
+    assert_eq!(urls.len(), 3);
+
+    let pool = create_pool(2).await?;
+    let output = tempfile_dir("sitemap");
+    let mut batch = BatchCapture::new(&output);
+    batch.extract_script = Some("document.title".to_string());
+    let summary = batch.run(&pool, &urls, &Default::default()).await?;
+    pool.close().await?;
+
+    assert_eq!(summary.total, 3);
+    assert_eq!(summary.failed, 1);
+    assert!(!summary.is_success());
+
+    let index: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(output.join(sitemap::INDEX_FILE))?)?;
+    let entries = index.as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+    for entry in entries {
+        let screenshot = entry["screenshot"].as_str().expect("screenshot captured");
+        assert!(std::path::Path::new(screenshot).exists());
+    }
+
+    let failures: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(output.join(sitemap::FAILURES_FILE))?)?;
+    let failures = failures.as_array().unwrap();
+    assert_eq!(failures.len(), 1);
+    assert!(failures[0]["url"].as_str().unwrap().ends_with("/missing"));
+    assert_eq!(failures[0]["status"], 404);
+    assert_eq!(failures[0]["error"], "HTTP 404");
+
+    let _ = std::fs::remove_dir_all(&output);
+    Ok(())
+}
+
+fn tempfile_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "robert-{}-{}",
+        name,
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -28,6 +28,7 @@ async fn test_navigate_and_get_title() {
         description: "Navigate and get title and text".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["navigation".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -124,6 +125,7 @@ async fn test_get_element_text() {
         description: "Navigate and get element text".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["element".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -201,6 +203,7 @@ async fn test_get_page_source() {
         description: "Navigate and get page source".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["page-source".to_string()],
         cdp_commands: vec![
             CdpCommand {
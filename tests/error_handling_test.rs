@@ -77,6 +77,7 @@ async fn test_screenshot_to_nonexistent_directory() {
         description: "Test screenshot to invalid path".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec![],
         cdp_commands: vec![
             CdpCommand {
@@ -163,6 +164,7 @@ async fn test_script_to_file() {
         description: "Test saving script".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["test".to_string()],
         cdp_commands: vec![CdpCommand {
             method: "Page.navigate".to_string(),
@@ -214,6 +216,7 @@ async fn test_data_extraction_with_save() {
         description: "Test data extraction with file save".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec![],
         cdp_commands: vec![
             CdpCommand {
@@ -0,0 +1,75 @@
+//! Integration tests for failure forensics bundles
+
+mod test_server;
+
+use robert_webdriver::cdp::{ExecutionOptions, ForensicsOptions};
+use robert_webdriver::{
+    CdpCommand, CdpExecutor, CdpScript, ChromeDriver, CommandStatus, ConnectionMode, FileStore,
+};
+use std::sync::Arc;
+use test_server::TestServer;
+
+#[tokio::test]
+async fn test_failed_command_saves_bundle() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await?;
+
+    let dir = std::env::temp_dir().join(format!("robert-forensics-{}", std::process::id()));
+    let script = CdpScript {
+        name: "forensics".to_string(),
+        description: "Navigate, log, then fail".to_string(),
+        created: None,
+        author: Some("Test".to_string()),
+        version: None,
+        tags: vec![],
+        cdp_commands: vec![
+            CdpCommand {
+                method: "Page.navigate".to_string(),
+                params: serde_json::json!({ "url": server.url() }),
+                save_as: None,
+                description: None,
+            },
+            CdpCommand {
+                method: "Runtime.evaluate".to_string(),
+                params: serde_json::json!({ "expression": "console.error('about to fail')" }),
+                save_as: None,
+                description: None,
+            },
+            CdpCommand {
+                method: "Page.notACommand".to_string(),
+                params: serde_json::json!({}),
+                save_as: None,
+                description: None,
+            },
+        ],
+    };
+
+    let page = driver.current_page().await?;
+    let executor = CdpExecutor::new(page)
+        .with_driver(&driver)
+        .with_options(ExecutionOptions {
+            forensics: Some(ForensicsOptions::new(Arc::new(FileStore::new(&dir)))),
+            ..Default::default()
+        });
+    let report = executor.execute_script(&script).await?;
+
+    let failed = &report.results[2];
+    assert_eq!(failed.status, CommandStatus::Failed);
+    let error = failed.error.as_deref().unwrap_or_default();
+    let (_, location) = error
+        .split_once("; forensics: ")
+        .expect("error references the bundle");
+    assert!(location.ends_with(".zip"), "{}", location);
+    let bundle = std::fs::read(location)?;
+    assert!(bundle.starts_with(b"PK"));
+
+    driver.close().await?;
+    tokio::fs::remove_dir_all(&dir).await.ok();
+    Ok(())
+}
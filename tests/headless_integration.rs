@@ -35,6 +35,7 @@ async fn test_basic_navigation_headless() -> anyhow::Result<()> {
         description: "Navigate and verify title".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["navigation".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -108,6 +109,7 @@ async fn test_cdp_script_execution_headless() -> anyhow::Result<()> {
         description: "Test CDP script execution in headless mode".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["test".to_string(), "headless".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -169,6 +171,7 @@ async fn test_screenshot_capture_headless() -> anyhow::Result<()> {
         description: "Capture screenshot in headless mode".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["screenshot".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -237,6 +240,7 @@ async fn test_data_extraction_headless() -> anyhow::Result<()> {
         description: "Extract data in headless mode".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["extraction".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -302,6 +306,7 @@ async fn test_multiple_commands_headless() -> anyhow::Result<()> {
         description: "Test multiple CDP commands".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["multi".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -0,0 +1,81 @@
+//! Integration tests for the LoginFlow helper
+
+mod test_server;
+
+use robert_webdriver::browser::login::{LoginFlow, SuccessCondition};
+use robert_webdriver::{BrowserError, ChromeDriver, ConnectionMode};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use test_server::TestServer;
+
+async fn create_headless_driver() -> anyhow::Result<ChromeDriver> {
+    ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to launch Chrome: {}", e))
+}
+
+#[tokio::test]
+async fn test_login_with_two_factor() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    let hook_calls = Arc::new(AtomicUsize::new(0));
+    let calls = hook_calls.clone();
+
+    let outcome = LoginFlow::new("alice", "secret")
+        .url(format!("{}/login", server.url()))
+        .username_selector("#username")
+        .submit_selector("#submit")
+        .failure_selector(".error")
+        .two_factor("#otp", move || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Ok("123456".to_string()) }
+        })
+        .success(SuccessCondition::UrlContains("/page2".to_string()))
+        .timeout(Duration::from_secs(10))
+        .execute(&driver)
+        .await?;
+
+    assert!(
+        outcome.url.ends_with("/page2"),
+        "Unexpected URL: {}",
+        outcome.url
+    );
+    assert!(outcome.two_factor_used);
+    assert_eq!(hook_calls.load(Ordering::SeqCst), 1);
+
+    driver.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_login_rejected() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    let result = LoginFlow::new("alice", "wrong")
+        .url(format!("{}/login", server.url()))
+        .username_selector("#username")
+        .failure_selector(".error")
+        .success(SuccessCondition::UrlContains("/page2".to_string()))
+        .timeout(Duration::from_secs(10))
+        .execute(&driver)
+        .await;
+
+    match result {
+        Err(BrowserError::LoginFailed(message)) => {
+            assert!(message.contains("Invalid credentials"), "{}", message)
+        }
+        other => panic!("Expected LoginFailed, got {:?}", other),
+    }
+
+    driver.close().await?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+//! Integration tests for popup and new-window handling
+
+mod test_server;
+
+use robert_webdriver::{
+    BrowserError, CdpCommand, CdpExecutor, CdpScript, ChromeDriver, ConnectionMode,
+};
+use std::time::Duration;
+use test_server::TestServer;
+
+async fn create_headless_driver() -> anyhow::Result<ChromeDriver> {
+    ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to launch Chrome: {}", e))
+}
+
+fn command(method: &str, params: serde_json::Value) -> CdpCommand {
+    CdpCommand {
+        method: method.to_string(),
+        params,
+        save_as: None,
+        description: None,
+    }
+}
+
+#[tokio::test]
+async fn test_wait_for_popup() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&format!("{}/popup", server.url())).await?;
+    let missing = driver.wait_for_popup(Duration::from_millis(300)).await;
+    assert!(matches!(missing, Err(BrowserError::NoPopup(_))));
+
+    driver
+        .execute_script("document.getElementById('open').click()")
+        .await?;
+    let popup = driver.wait_for_popup(Duration::from_secs(10)).await?;
+    assert!(popup.url().await?.unwrap_or_default().ends_with("/page2"));
+
+    // Adopted but not active until switched to
+    assert_eq!(driver.tabs().await?.len(), 2);
+    assert_eq!(driver.title().await?, "Popup Opener");
+
+    driver.switch_to_page(&popup).await?;
+    assert_eq!(driver.title().await?, "Test Page 2");
+
+    // Closing the popup falls back to the opener
+    popup.close().await?;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(driver.title().await?, "Popup Opener");
+
+    driver.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_script_continues_in_popup() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    let script = CdpScript {
+        name: "follow-popup".to_string(),
+        description: "Open a link in a new tab and read its title".to_string(),
+        created: None,
+        author: Some("Test".to_string()),
+        version: None,
+        tags: vec![],
+        cdp_commands: vec![
+            command(
+                "Page.navigate",
+                serde_json::json!({ "url": format!("{}/popup", server.url()) }),
+            ),
+            command(
+                "Runtime.evaluate",
+                serde_json::json!({ "expression": "document.getElementById('open').click()" }),
+            ),
+            command(
+                "Target.waitForPopup",
+                serde_json::json!({ "timeout": 10000 }),
+            ),
+            command(
+                "Runtime.evaluate",
+                serde_json::json!({ "expression": "document.title", "returnByValue": true }),
+            ),
+            command("Target.switchToOpener", serde_json::json!({})),
+            command(
+                "Runtime.evaluate",
+                serde_json::json!({ "expression": "document.title", "returnByValue": true }),
+            ),
+        ],
+    };
+
+    let page = driver.current_page().await?;
+    let report = CdpExecutor::new(page)
+        .with_driver(&driver)
+        .execute_script(&script)
+        .await?;
+    assert!(report.is_success(), "{:?}", report);
+
+    let title =
+        |step: usize| report.results[step].response.as_ref().unwrap()["result"]["value"].clone();
+    assert_eq!(title(3), "Test Page 2");
+    assert_eq!(title(5), "Popup Opener");
+
+    driver.close().await?;
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Integration tests for recording and replaying runs
+
+mod test_server;
+
+use robert_webdriver::cdp::{CdpTrace, ExecutionOptions, TraceEntry, TraceRecorder};
+use robert_webdriver::{
+    CdpCommand, CdpExecutor, CdpScript, ChromeDriver, CommandStatus, ConnectionMode,
+};
+use std::sync::Arc;
+use test_server::TestServer;
+
+fn evaluate_script(url: &str, expression: &str) -> CdpScript {
+    CdpScript {
+        name: "replay".to_string(),
+        description: "Navigate and read the title".to_string(),
+        created: None,
+        author: Some("Test".to_string()),
+        version: None,
+        tags: vec![],
+        cdp_commands: vec![
+            CdpCommand {
+                method: "Page.navigate".to_string(),
+                params: serde_json::json!({ "url": url }),
+                save_as: None,
+                description: None,
+            },
+            CdpCommand {
+                method: "Runtime.evaluate".to_string(),
+                params: serde_json::json!({ "expression": expression, "returnByValue": true }),
+                save_as: None,
+                description: None,
+            },
+        ],
+    }
+}
+
+#[tokio::test]
+async fn test_record_and_replay() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await?;
+
+    let script = evaluate_script(&server.url(), "document.title");
+    let recorder = Arc::new(TraceRecorder::new());
+    let recorded = CdpExecutor::new(driver.current_page().await?)
+        .with_step_hooks(recorder.clone())
+        .execute_script(&script)
+        .await?;
+    driver.close().await?;
+    assert!(recorded.is_success(), "{:?}", recorded.results);
+
+    let path = std::env::temp_dir().join(format!("robert-trace-{}.json", std::process::id()));
+    recorder.trace().save(&path).await?;
+    let trace = CdpTrace::load(&path).await?;
+    assert_eq!(trace.script, "replay");
+    assert_eq!(trace.entries.len(), 2);
+
+    // The same script replays with the recorded responses
+    let replayed = CdpExecutor::from_trace(trace.clone())
+        .execute_script(&script)
+        .await?;
+    assert!(replayed.is_success(), "{:?}", replayed.results);
+    assert_eq!(replayed.results[1].response, recorded.results[1].response);
+
+    // A changed script diverges
+    let changed = evaluate_script(&server.url(), "document.body.innerHTML");
+    let replayed = CdpExecutor::from_trace(trace)
+        .execute_script(&changed)
+        .await?;
+    let failed = &replayed.results[1];
+    assert_eq!(failed.status, CommandStatus::Failed);
+    let error = failed.error.as_deref().unwrap_or_default();
+    assert!(error.starts_with("Replay diverged"), "{}", error);
+
+    tokio::fs::remove_file(&path).await.ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_replay_continue_on_error() -> anyhow::Result<()> {
+    let script = evaluate_script("https://example.com", "document.title");
+    let entry = |method: &str, params: serde_json::Value, error: Option<&str>| TraceEntry {
+        method: method.to_string(),
+        params,
+        response: error.is_none().then(|| serde_json::json!({})),
+        error: error.map(str::to_string),
+        saved_file: None,
+    };
+    let trace = CdpTrace {
+        script: "replay".to_string(),
+        recorded: "2026-01-01T00:00:00Z".to_string(),
+        entries: vec![
+            entry(
+                "Page.navigate",
+                script.cdp_commands[0].params.clone(),
+                Some("net::ERR_NAME_NOT_RESOLVED"),
+            ),
+            entry(
+                "Runtime.evaluate",
+                script.cdp_commands[1].params.clone(),
+                None,
+            ),
+        ],
+    };
+
+    // By default the run stops at the failed navigation
+    let stopped = CdpExecutor::from_trace(trace.clone())
+        .execute_script(&script)
+        .await?;
+    assert_eq!(stopped.results.len(), 1);
+
+    let continued = CdpExecutor::from_trace(trace)
+        .with_options(ExecutionOptions {
+            continue_on_error: true,
+            ..Default::default()
+        })
+        .execute_script(&script)
+        .await?;
+    let statuses: Vec<_> = continued.results.iter().map(|r| r.status).collect();
+    assert_eq!(statuses, [CommandStatus::Failed, CommandStatus::Success]);
+    Ok(())
+}
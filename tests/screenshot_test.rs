@@ -9,7 +9,8 @@
 
 mod test_server;
 
-use robert_webdriver::{CdpCommand, CdpScript, ChromeDriver, ConnectionMode};
+use robert_webdriver::browser::DeterministicRendering;
+use robert_webdriver::{CdpCommand, CdpScript, ChromeDriver, ConnectionMode, DriverOptions};
 use std::path::PathBuf;
 use test_server::TestServer;
 
@@ -184,6 +185,7 @@ async fn test_cdp_capture_screenshot_command() -> anyhow::Result<()> {
         description: "Test Page.captureScreenshot command".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["screenshot".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -251,6 +253,7 @@ async fn test_cdp_screenshot_with_different_formats() -> anyhow::Result<()> {
         description: "Test PNG format".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec![],
         cdp_commands: vec![CdpCommand {
             method: "Page.captureScreenshot".to_string(),
@@ -279,6 +282,7 @@ async fn test_cdp_screenshot_with_different_formats() -> anyhow::Result<()> {
         description: "Test JPEG format".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec![],
         cdp_commands: vec![CdpCommand {
             method: "Page.captureScreenshot".to_string(),
@@ -505,6 +509,7 @@ async fn test_step_frame_with_cdp_workflow() -> anyhow::Result<()> {
         description: "Complete workflow with step frame".to_string(),
         created: None,
         author: Some("Test".to_string()),
+        version: None,
         tags: vec!["workflow".to_string()],
         cdp_commands: vec![
             CdpCommand {
@@ -606,3 +611,52 @@ async fn test_screenshot_to_invalid_path() -> anyhow::Result<()> {
     driver.close().await?;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_deterministic_screenshots_are_stable() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let url = server.url();
+
+    // Time, randomness, and a running animation all end up in the screenshot
+    const DECORATE: &str = r#"(() => {
+        document.body.insertAdjacentHTML('beforeend',
+            '<p id="now"></p><p id="random"></p>'
+            + '<div id="spinner" style="width: 40px; height: 40px; background: red; '
+            + 'animation: spin 1s linear infinite"></div>'
+            + '<style>@keyframes spin { to { transform: rotate(360deg); } }</style>');
+        document.getElementById('now').textContent = new Date().toISOString();
+        document.getElementById('random').textContent = Math.random();
+        return Date.now();
+    })()"#;
+
+    let mut screenshots = Vec::new();
+    for _ in 0..2 {
+        let driver = ChromeDriver::new_with_options(
+            ConnectionMode::Sandboxed {
+                chrome_path: None,
+                no_sandbox: true,
+                headless: true,
+            },
+            DriverOptions {
+                deterministic: Some(DeterministicRendering::default()),
+                ..Default::default()
+            },
+        )
+        .await?;
+        driver.navigate(&url).await?;
+
+        let now = driver.execute_script(DECORATE).await?;
+        assert_eq!(now, DeterministicRendering::default().frozen_time_ms);
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+
+        screenshots.push(driver.screenshot().await?);
+        driver.close().await?;
+    }
+
+    assert!(
+        screenshots[0] == screenshots[1],
+        "Deterministic screenshots should be byte-identical"
+    );
+    Ok(())
+}
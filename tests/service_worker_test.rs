@@ -0,0 +1,82 @@
+//! Integration tests for service worker control and offline emulation
+
+mod test_server;
+
+use robert_webdriver::{ChromeDriver, ConnectionMode};
+use test_server::TestServer;
+
+async fn create_headless_driver() -> anyhow::Result<ChromeDriver> {
+    ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to launch Chrome: {}", e))
+}
+
+/// Fetch /version from the page ("stale" from the worker's cache, "fresh" from the network)
+async fn fetch_version(driver: &ChromeDriver) -> anyhow::Result<String> {
+    let version = driver
+        .execute_script("fetch('/version').then((r) => r.text(), () => 'offline')")
+        .await?;
+    Ok(version.as_str().unwrap_or_default().to_string())
+}
+
+#[tokio::test]
+async fn test_service_worker_control() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&format!("{}/pwa", server.url())).await?;
+    driver
+        .execute_script("window.swReady.then(() => true)")
+        .await?;
+
+    let workers = driver.service_workers().await?;
+    assert_eq!(workers.len(), 1);
+    assert_eq!(workers[0].scope, format!("{}/", server.url()));
+    assert!(workers[0]
+        .script_url
+        .as_deref()
+        .unwrap()
+        .ends_with("/sw.js"));
+
+    // The worker serves its cached copy until bypassed
+    assert_eq!(fetch_version(&driver).await?, "stale");
+    driver.bypass_service_workers(true).await?;
+    assert_eq!(fetch_version(&driver).await?, "fresh");
+    driver.bypass_service_workers(false).await?;
+    assert_eq!(fetch_version(&driver).await?, "stale");
+
+    // Without its cache the worker falls through to the network
+    driver.clear_service_worker_caches().await?;
+    assert_eq!(fetch_version(&driver).await?, "fresh");
+
+    driver.stop_service_workers().await?;
+    assert_eq!(driver.unregister_service_workers().await?, 1);
+    assert!(driver.service_workers().await?.is_empty());
+
+    driver.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_offline_emulation() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&server.url()).await?;
+    assert_eq!(fetch_version(&driver).await?, "fresh");
+
+    driver.set_offline(true).await?;
+    assert_eq!(fetch_version(&driver).await?, "offline");
+
+    driver.set_offline(false).await?;
+    assert_eq!(fetch_version(&driver).await?, "fresh");
+
+    driver.close().await?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Integration tests for snapshot assertions
+
+mod test_server;
+
+use robert_webdriver::cdp::ExecutionOptions;
+use robert_webdriver::{
+    CdpCommand, CdpExecutor, CdpScript, ChromeDriver, CommandStatus, ConnectionMode,
+};
+use test_server::TestServer;
+
+fn script(url: &str, expression: &str) -> CdpScript {
+    CdpScript {
+        name: "snapshot".to_string(),
+        description: "Navigate and snapshot the title".to_string(),
+        created: None,
+        author: Some("Test".to_string()),
+        version: None,
+        tags: vec![],
+        cdp_commands: vec![
+            CdpCommand {
+                method: "Page.navigate".to_string(),
+                params: serde_json::json!({ "url": url }),
+                save_as: None,
+                description: None,
+            },
+            CdpCommand {
+                method: "Assert.snapshot".to_string(),
+                params: serde_json::json!({
+                    "name": "title",
+                    "expression": expression,
+                    "ignorePaths": ["$.now"],
+                }),
+                save_as: None,
+                description: None,
+            },
+        ],
+    }
+}
+
+#[tokio::test]
+async fn test_snapshot_stored_matched_and_failed() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await?;
+
+    let dir = std::env::temp_dir().join(format!("robert-snapshots-{}", std::process::id()));
+    let run = |expression: &'static str| {
+        let script = script(&server.url(), expression);
+        let driver = &driver;
+        let dir = dir.clone();
+        async move {
+            let page = driver.current_page().await?;
+            CdpExecutor::new(page)
+                .with_output_dir(&dir)
+                .with_options(ExecutionOptions {
+                    snapshot_dir: Some(dir.join("snapshots")),
+                    ..Default::default()
+                })
+                .execute_script(&script)
+                .await
+        }
+    };
+
+    // The first run stores the snapshot, the second matches it despite `now`
+    let expression = "({ title: document.title, now: Date.now() })";
+    let report = run(expression).await?;
+    assert!(report.is_success(), "{:?}", report.results);
+    assert_eq!(
+        report.results[1].response.as_ref().unwrap()["status"],
+        "stored"
+    );
+    let stored: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("snapshots/title.json"))?)?;
+    assert_eq!(stored["now"], "[ignored]");
+
+    let report = run(expression).await?;
+    assert_eq!(
+        report.results[1].response.as_ref().unwrap()["status"],
+        "matched"
+    );
+
+    let report = run("({ title: 'Changed', now: Date.now() })").await?;
+    let failed = &report.results[1];
+    assert_eq!(failed.status, CommandStatus::Failed);
+    let error = failed.error.as_deref().unwrap_or_default();
+    assert!(
+        error.contains("Snapshot 'title' doesn't match"),
+        "{}",
+        error
+    );
+    let diff = std::fs::read_to_string(dir.join("title.snapshot.diff"))?;
+    assert!(diff.contains("title\n-  "), "{}", diff);
+    assert!(diff.contains("+  \"Changed\""), "{}", diff);
+
+    driver.close().await?;
+    tokio::fs::remove_dir_all(&dir).await.ok();
+    Ok(())
+}
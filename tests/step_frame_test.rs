@@ -10,11 +10,18 @@
 
 mod test_server;
 
+use robert_webdriver::cdp::{ExecutionOptions, FrameCapture};
+use robert_webdriver::html::HtmlOptions;
+use robert_webdriver::redaction::Redaction;
 use robert_webdriver::step_frame::{
-    capture_step_frame, ActionInfo, CaptureOptions, ScreenshotFormat,
+    capture_step_frame, ActionInfo, CaptureOptions, Dimensions, ScreenshotFormat,
+    TargetScreenshotMode,
+};
+use robert_webdriver::{
+    CdpCommand, CdpExecutor, CdpScript, ChromeDriver, ConnectionMode, FileStore, FrameStore,
 };
-use robert_webdriver::{ChromeDriver, ConnectionMode};
 use std::path::PathBuf;
+use std::sync::Arc;
 use test_server::TestServer;
 
 /// Helper to create a headless driver for testing
@@ -63,6 +70,7 @@ async fn test_capture_basic_step_frame() -> anyhow::Result<()> {
         action_type: "navigate".to_string(),
         intent: "Navigate to test page".to_string(),
         target: Some(url.clone()),
+        target_screenshot: None,
     });
 
     // Capture frame
@@ -83,6 +91,17 @@ async fn test_capture_basic_step_frame() -> anyhow::Result<()> {
     let screenshot_path = PathBuf::from(&frame.screenshot.path);
     assert!(screenshot_path.exists(), "Screenshot file should exist");
 
+    // Verify viewport metadata matches the screenshot
+    let viewport = frame.viewport.expect("Viewport should be captured");
+    let dimensions = frame
+        .screenshot
+        .dimensions
+        .expect("Dimensions should be read");
+    assert!(viewport.width > 0.0 && viewport.device_pixel_ratio > 0.0);
+    assert_eq!(viewport.scroll_y, 0.0);
+    assert!(viewport.document_height >= viewport.height);
+    assert_eq!(viewport.to_document(0.0, 0.0, &dimensions), (0.0, 0.0));
+
     // Verify DOM
     assert!(
         frame.dom.url.starts_with(&url),
@@ -406,6 +425,260 @@ async fn test_jpeg_format() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_downscale_and_thumbnail() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let url = server.url();
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&url).await?;
+
+    let test_dir = create_temp_test_dir("thumbnail");
+    let options = CaptureOptions {
+        screenshot_dir: test_dir.join("screenshots"),
+        dom_dir: Some(test_dir.join("dom")),
+        max_screenshot_size: Some(Dimensions {
+            width: 400,
+            height: 400,
+        }),
+        thumbnail_width: Some(160),
+        ..Default::default()
+    };
+
+    let frame = capture_step_frame(&driver, 0, 0, &options, None, None).await?;
+
+    let dimensions = frame.screenshot.dimensions.expect("dimensions measured");
+    assert!(dimensions.width <= 400 && dimensions.height <= 400);
+    assert!(
+        frame.screenshot.original_dimensions.is_some(),
+        "Default viewport is larger than 400x400"
+    );
+
+    let thumbnail = frame.screenshot.thumbnail.expect("thumbnail saved");
+    assert_eq!(thumbnail.dimensions.width, 160);
+    assert!(thumbnail.path.ends_with("frame_0000.thumb.png"));
+    assert_eq!(
+        std::fs::metadata(&thumbnail.path)?.len() as usize,
+        thumbnail.size_bytes
+    );
+
+    driver.close().await?;
+    tokio::fs::remove_dir_all(&test_dir).await.ok();
+
+    Ok(())
+}
+
+// ===== REDACTION TESTS =====
+
+#[tokio::test]
+async fn test_redaction_masks_saved_artifacts() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let url = server.url();
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&url).await?;
+
+    let test_dir = create_temp_test_dir("redaction");
+    let options = CaptureOptions {
+        screenshot_dir: test_dir.join("screenshots"),
+        dom_dir: Some(test_dir.join("dom")),
+        visual_dom_dir: Some(test_dir.join("visualdom")),
+        capture_visual_dom: true,
+        extract_interactive_elements: true,
+        redaction: Some(Redaction::selectors(["a"])),
+        ..Default::default()
+    };
+
+    let frame = capture_step_frame(&driver, 0, 0, &options, None, None).await?;
+
+    let html = std::fs::read_to_string(frame.dom.html_path.as_ref().unwrap())?;
+    assert!(!html.contains("Go to Page 2"), "Link text masked");
+    assert!(!html.contains("/page2"), "Link target masked");
+    assert!(html.contains("for use in documentation"), "Other text kept");
+
+    let visual_dom = std::fs::read_to_string(&frame.visual_dom.as_ref().unwrap().path)?;
+    assert!(!visual_dom.contains("Go to Page 2"));
+    assert!(!visual_dom.contains("/page2"));
+    assert!(visual_dom.contains("for use in documentation"));
+
+    let elements = frame.dom.interactive_elements.unwrap();
+    assert!(elements.iter().all(|el| !el.text.contains("Page 2")));
+
+    // The live page is untouched
+    assert_eq!(
+        driver
+            .execute_script("document.querySelector('a').textContent")
+            .await?,
+        "Go to Page 2"
+    );
+
+    driver.close().await?;
+    tokio::fs::remove_dir_all(&test_dir).await.ok();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_html_sanitization_and_text() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&format!("{}/login", server.url())).await?;
+
+    let test_dir = create_temp_test_dir("html_sanitize");
+    let mut options = CaptureOptions {
+        screenshot_dir: test_dir.join("screenshots"),
+        dom_dir: Some(test_dir.join("dom")),
+        html: HtmlOptions::for_llm(),
+        ..Default::default()
+    };
+
+    let frame = capture_step_frame(&driver, 0, 0, &options, None, None).await?;
+    let html = std::fs::read_to_string(frame.dom.html_path.as_ref().unwrap())?;
+    assert!(!html.contains("<script"), "Scripts stripped");
+    assert!(html.contains("id=\"login\""), "Markup kept");
+    assert!(frame.dom.html_original_size.is_none());
+
+    let text = std::fs::read_to_string(frame.dom.text_path.as_ref().unwrap())?;
+    assert!(text.contains("Sign in"));
+    assert!(!text.contains("preventDefault"));
+
+    options.html = HtmlOptions {
+        max_bytes: Some(200),
+        ..Default::default()
+    };
+    let frame = capture_step_frame(&driver, 1, 0, &options, None, None).await?;
+    let html = std::fs::read_to_string(frame.dom.html_path.as_ref().unwrap())?;
+    assert!(html.len() <= 200);
+    assert!(html.contains("<!-- truncated: 200 of"));
+    assert!(frame.dom.html_original_size.unwrap() > 200);
+    assert!(frame.dom.text_path.is_none());
+
+    driver.close().await?;
+    tokio::fs::remove_dir_all(&test_dir).await.ok();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mhtml_archive() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let url = server.url();
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&url).await?;
+
+    let test_dir = create_temp_test_dir("mhtml");
+    let mut options = CaptureOptions {
+        screenshot_dir: test_dir.join("screenshots"),
+        dom_dir: Some(test_dir.join("dom")),
+        save_mhtml: true,
+        ..Default::default()
+    };
+
+    let frame = capture_step_frame(&driver, 0, 0, &options, None, None).await?;
+    let mhtml_path = frame.dom.mhtml_path.expect("MHTML archive saved");
+    assert!(mhtml_path.ends_with("frame_0000.mhtml"));
+    let archive = std::fs::read_to_string(&mhtml_path)?;
+    assert!(archive.contains("multipart/related"));
+    assert!(archive.contains("Example Domain"));
+
+    // Redacted frames never get an archive
+    options.redaction = Some(Redaction::selectors(["a"]));
+    let frame = capture_step_frame(&driver, 1, 0, &options, None, None).await?;
+    assert!(frame.dom.mhtml_path.is_none());
+
+    driver.close().await?;
+    tokio::fs::remove_dir_all(&test_dir).await.ok();
+
+    Ok(())
+}
+
+// ===== EXECUTOR CAPTURE TESTS =====
+
+#[tokio::test]
+async fn test_executor_captures_frames() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let url = server.url();
+    let driver = create_headless_driver().await?;
+
+    let test_dir = create_temp_test_dir("executor");
+    let capture = CaptureOptions {
+        screenshot_dir: test_dir.join("screenshots"),
+        dom_dir: Some(test_dir.join("dom")),
+        ..Default::default()
+    };
+    let script = CdpScript {
+        name: "frames".to_string(),
+        description: "Navigate, then fail".to_string(),
+        created: None,
+        author: Some("Test".to_string()),
+        version: None,
+        tags: vec![],
+        cdp_commands: vec![
+            CdpCommand {
+                method: "Page.navigate".to_string(),
+                params: serde_json::json!({ "url": url }),
+                save_as: None,
+                description: Some("Open the test page".to_string()),
+            },
+            CdpCommand {
+                method: "Page.notACommand".to_string(),
+                params: serde_json::json!({}),
+                save_as: None,
+                description: None,
+            },
+        ],
+    };
+
+    let page = driver.current_page().await?;
+    let executor = CdpExecutor::new(page)
+        .with_driver(&driver)
+        .with_options(ExecutionOptions {
+            frame_capture: FrameCapture::BeforeAndAfter,
+            capture: capture.clone(),
+            ..Default::default()
+        });
+    let report = executor.execute_script(&script).await?;
+
+    let frames = &report.results[0].frames;
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].frame_id, 0);
+    assert_eq!(frames[1].frame_id, 1);
+    let action = frames[1]
+        .action
+        .as_ref()
+        .expect("frame has the command as action");
+    assert_eq!(action.action_type, "Page.navigate");
+    assert_eq!(action.intent, "Open the test page");
+    assert!(PathBuf::from(&frames[1].screenshot.path).exists());
+    assert!(frames[1].dom.url.starts_with(&url));
+    assert_eq!(report.results[1].frames.len(), 2);
+
+    // Only the failed command gets a frame
+    let page = driver.current_page().await?;
+    let executor = CdpExecutor::new(page)
+        .with_driver(&driver)
+        .with_options(ExecutionOptions {
+            frame_capture: FrameCapture::OnFailure,
+            capture,
+            ..Default::default()
+        });
+    let report = executor.execute_script(&script).await?;
+    assert!(report.results[0].frames.is_empty());
+    assert_eq!(report.results[1].frames.len(), 1);
+
+    driver.close().await?;
+    tokio::fs::remove_dir_all(&test_dir).await.ok();
+
+    Ok(())
+}
+
 // ===== INTERACTIVE ELEMENTS TESTS =====
 
 #[tokio::test]
@@ -465,6 +738,147 @@ async fn test_extract_interactive_elements() -> anyhow::Result<()> {
     Ok(())
 }
 
+// ===== COORDINATE MAPPING TESTS =====
+
+#[tokio::test]
+async fn test_element_at_screenshot_point() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&server.url()).await?;
+
+    let test_dir = create_temp_test_dir("element-at");
+    let options = CaptureOptions {
+        screenshot_dir: test_dir.join("screenshots"),
+        dom_dir: Some(test_dir.join("dom")),
+        capture_visual_dom: true,
+        ..Default::default()
+    };
+    let frame = capture_step_frame(&driver, 0, 0, &options, None, None).await?;
+
+    // The live page and the captured frame agree on what is under the heading
+    let (x, y) = driver.get_element_rect("h1").await?.viewport.center();
+    let live = driver
+        .node_at_point(x, y)
+        .await?
+        .expect("Heading should be under its center");
+    assert_eq!(live.tag, "h1");
+    assert_eq!(live.selector, "html > body > div > h1");
+    assert_eq!(live.text, "Example Domain");
+
+    let viewport = frame.viewport.expect("Viewport should be captured");
+    let dimensions = frame.screenshot.dimensions.clone().unwrap();
+    let ratio = dimensions.width as f64 / viewport.width;
+    let element = frame
+        .element_at(x * ratio, y * ratio)
+        .await?
+        .expect("Heading should be in the VisualDom");
+    assert_eq!(element.tag, "h1");
+    assert_eq!(element.selector, live.selector);
+
+    // Pixels outside the screenshot map to nothing
+    assert!(frame
+        .element_at(dimensions.width as f64 + 10.0, 0.0)
+        .await?
+        .is_none());
+
+    driver.close().await?;
+    tokio::fs::remove_dir_all(&test_dir).await.ok();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_target_screenshot() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&server.url()).await?;
+
+    let test_dir = create_temp_test_dir("target-screenshot");
+    let mut options = CaptureOptions {
+        screenshot_dir: test_dir.join("screenshots"),
+        dom_dir: None,
+        ..Default::default()
+    };
+    let click = |target: &str| {
+        Some(ActionInfo {
+            action_type: "click".to_string(),
+            intent: "Click the link".to_string(),
+            target: Some(target.to_string()),
+            target_screenshot: None,
+        })
+    };
+
+    // Saved next to the screenshot by default
+    let frame = capture_step_frame(&driver, 0, 0, &options, None, click("a")).await?;
+    let target = frame
+        .action
+        .and_then(|a| a.target_screenshot)
+        .expect("Link should be cropped");
+    let path = PathBuf::from(target.path.expect("Crop should be saved to a file"));
+    assert!(path.exists());
+    assert_eq!(std::fs::metadata(&path)?.len() as usize, target.size_bytes);
+    let screenshot = frame.screenshot.dimensions.unwrap();
+    assert!(target.dimensions.width < screenshot.width);
+    assert!(target.rect.width > 0.0);
+
+    // Embedded as base64 when inline
+    options.target_screenshot = TargetScreenshotMode::Inline;
+    let frame = capture_step_frame(&driver, 1, 0, &options, None, click("a")).await?;
+    let target = frame.action.and_then(|a| a.target_screenshot).unwrap();
+    assert!(target.path.is_none());
+    assert!(!target.data.unwrap().is_empty());
+
+    // Targets that aren't elements on the page are left without a crop
+    let frame = capture_step_frame(&driver, 2, 0, &options, None, click("#missing")).await?;
+    assert!(frame.action.unwrap().target_screenshot.is_none());
+
+    driver.close().await?;
+    tokio::fs::remove_dir_all(&test_dir).await.ok();
+
+    Ok(())
+}
+
+// ===== STORAGE TESTS =====
+
+#[tokio::test]
+async fn test_frame_store() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&server.url()).await?;
+
+    let test_dir = create_temp_test_dir("store");
+    let store = Arc::new(FileStore::new(test_dir.join("store")));
+    let options = CaptureOptions {
+        screenshot_dir: test_dir.join("screenshots"),
+        dom_dir: Some(test_dir.join("dom")),
+        store: Some(store.clone()),
+        ..Default::default()
+    };
+    let frame = capture_step_frame(&driver, 0, 0, &options, None, None).await?;
+
+    // Files are moved into the store and the frame points at them
+    let screenshot = PathBuf::from(&frame.screenshot.path);
+    assert_eq!(
+        screenshot,
+        test_dir.join("store/screenshots/frame_0000.png")
+    );
+    assert!(screenshot.exists());
+    assert!(!test_dir.join("screenshots/frame_0000.png").exists());
+    let html = store.get("dom/frame_0000.html").await?;
+    assert!(String::from_utf8(html)?.contains("Example Domain"));
+
+    driver.close().await?;
+    tokio::fs::remove_dir_all(&test_dir).await.ok();
+
+    Ok(())
+}
+
 // ===== FAIL-FAST TESTS =====
 
 #[tokio::test]
@@ -535,6 +949,7 @@ async fn test_frame_json_serialization() -> anyhow::Result<()> {
         action_type: "click".to_string(),
         intent: "Click the button".to_string(),
         target: Some("#my-button".to_string()),
+        target_screenshot: None,
     });
 
     let frame = capture_step_frame(&driver, 0, 0, &options, None, action).await?;
@@ -611,3 +1026,51 @@ async fn test_rapid_frame_capture() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Average capture latency allowed per frame (screenshot, hash, HTML, file writes)
+const FRAME_LATENCY_BUDGET: std::time::Duration = std::time::Duration::from_millis(1000);
+
+#[tokio::test]
+async fn test_frame_capture_latency_budget() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let url = server.url();
+    let driver = create_headless_driver().await?;
+
+    driver.navigate(&url).await?;
+
+    let test_dir = create_temp_test_dir("latency");
+    let options = CaptureOptions {
+        screenshot_dir: test_dir.join("screenshots"),
+        dom_dir: Some(test_dir.join("dom")),
+        ..Default::default()
+    };
+
+    // Warm up (first screenshot includes compositor startup)
+    capture_step_frame(&driver, 0, 0, &options, None, None).await?;
+
+    const FRAMES: usize = 5;
+    let start = std::time::Instant::now();
+    for i in 1..=FRAMES {
+        let frame = capture_step_frame(&driver, i, 0, &options, None, None).await?;
+
+        // Everything is measured from the in-memory buffer and the file is complete
+        let on_disk = std::fs::read(&frame.screenshot.path)?;
+        assert_eq!(on_disk.len(), frame.screenshot.size_bytes);
+        assert!(frame.screenshot.dimensions.is_some());
+        assert!(frame.screenshot.hash.is_some());
+    }
+    let average = start.elapsed() / FRAMES as u32;
+    println!("Average capture latency: {:?}", average);
+    assert!(
+        average < FRAME_LATENCY_BUDGET,
+        "Average frame capture took {:?}, budget is {:?}",
+        average,
+        FRAME_LATENCY_BUDGET
+    );
+
+    driver.close().await?;
+    tokio::fs::remove_dir_all(&test_dir).await.ok();
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//! Integration tests for executor step hooks
+
+mod test_server;
+
+use futures::future::BoxFuture;
+use robert_webdriver::cdp::{StepContext, StepHooks};
+use robert_webdriver::{
+    CdpCommand, CdpExecutor, CdpScript, ChromeDriver, CommandResult, ConnectionMode,
+};
+use std::sync::{Arc, Mutex};
+use test_server::TestServer;
+
+/// Records every hook call as "<hook> <step> <method>"
+#[derive(Default)]
+struct RecordingHooks {
+    calls: Mutex<Vec<String>>,
+}
+
+impl RecordingHooks {
+    fn record(&self, hook: &str, step: &StepContext<'_>) {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("{} {} {}", hook, step.step, step.command.method));
+    }
+}
+
+impl StepHooks for RecordingHooks {
+    fn on_step_start<'a>(&'a self, step: &'a StepContext<'a>) -> BoxFuture<'a, ()> {
+        Box::pin(async move { self.record("start", step) })
+    }
+
+    fn on_step_success<'a>(
+        &'a self,
+        step: &'a StepContext<'a>,
+        _result: &'a CommandResult,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            // Hooks can inspect the page the command ran against
+            let url = step.page.url().await.ok().flatten().unwrap_or_default();
+            assert!(url.starts_with("http://"), "{}", url);
+            self.record("success", step)
+        })
+    }
+
+    fn on_step_failure<'a>(
+        &'a self,
+        step: &'a StepContext<'a>,
+        result: &'a CommandResult,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            assert!(result.error.is_some());
+            self.record("failure", step)
+        })
+    }
+}
+
+fn command(method: &str, params: serde_json::Value) -> CdpCommand {
+    CdpCommand {
+        method: method.to_string(),
+        params,
+        save_as: None,
+        description: None,
+    }
+}
+
+#[tokio::test]
+async fn test_step_hooks_called_per_command() -> anyhow::Result<()> {
+    let server = TestServer::start().await;
+    server.wait_ready().await?;
+    let driver = ChromeDriver::new(ConnectionMode::Sandboxed {
+        chrome_path: None,
+        no_sandbox: true,
+        headless: true,
+    })
+    .await?;
+
+    let script = CdpScript {
+        name: "hooks".to_string(),
+        description: "Navigate, evaluate, then fail".to_string(),
+        created: None,
+        author: Some("Test".to_string()),
+        version: None,
+        tags: vec![],
+        cdp_commands: vec![
+            command("Page.navigate", serde_json::json!({ "url": server.url() })),
+            command(
+                "Runtime.evaluate",
+                serde_json::json!({ "expression": "document.title" }),
+            ),
+            command("Page.notACommand", serde_json::json!({})),
+            command("Page.reload", serde_json::json!({})),
+        ],
+    };
+
+    let hooks = Arc::new(RecordingHooks::default());
+    let page = driver.current_page().await?;
+    let executor = CdpExecutor::new(page).with_step_hooks(hooks.clone());
+    let report = executor.execute_script(&script).await?;
+    assert!(!report.is_success());
+
+    assert_eq!(
+        *hooks.calls.lock().unwrap(),
+        [
+            "start 1 Page.navigate",
+            "success 1 Page.navigate",
+            "start 2 Runtime.evaluate",
+            "success 2 Runtime.evaluate",
+            "start 3 Page.notACommand",
+            "failure 3 Page.notACommand",
+        ]
+    );
+
+    driver.close().await?;
+    Ok(())
+}
@@ -4,26 +4,37 @@
 //! for testing Chrome automation without relying on external websites.
 //!
 //! Each server instance runs on a random available port for perfect test isolation.
+//! The pages are served by the public [`FixtureServer`], which downstream crates can
+//! use for their own tests.
 
+use robert_webdriver::testing::{Fixture, FixtureServer};
 use std::net::SocketAddr;
-use tokio::sync::oneshot;
-use warp::Filter;
+
+/// Page of the crawl test site titled `title` linking to `links`
+fn site_page(title: &str, links: &[&str]) -> Fixture {
+    let links: String = links
+        .iter()
+        .map(|href| format!(r#"<a href="{}">{}</a> "#, href, href))
+        .collect();
+    Fixture::html(format!(
+        "<!DOCTYPE html><html><head><title>{}</title></head><body><p>{}</p></body></html>",
+        title, links
+    ))
+}
 
 /// Test server that serves simple HTML pages
 pub struct TestServer {
-    addr: SocketAddr,
-    shutdown_tx: Option<oneshot::Sender<()>>,
+    server: FixtureServer,
 }
 
 impl TestServer {
     /// Start a new test server on a random available port
     pub async fn start() -> Self {
-        let (shutdown_tx, shutdown_rx) = oneshot::channel();
-
-        // Routes
-        let index = warp::path::end().map(|| {
-            warp::reply::html(
-                r#"<!DOCTYPE html>
+        let server = FixtureServer::builder()
+            .route(
+                "/",
+                Fixture::html(
+                    r#"<!DOCTYPE html>
 <html lang="en">
 <head>
     <title>Example Domain</title>
@@ -37,12 +48,12 @@ impl TestServer {
     </div>
 </body>
 </html>"#,
+                ),
             )
-        });
-
-        let page2 = warp::path("page2").map(|| {
-            warp::reply::html(
-                r#"<!DOCTYPE html>
+            .route(
+                "/page2",
+                Fixture::html(
+                    r#"<!DOCTYPE html>
 <html lang="en">
 <head>
     <title>Test Page 2</title>
@@ -56,12 +67,12 @@ impl TestServer {
     </div>
 </body>
 </html>"#,
+                ),
             )
-        });
-
-        let page3 = warp::path("page3").map(|| {
-            warp::reply::html(
-                r#"<!DOCTYPE html>
+            .route(
+                "/page3",
+                Fixture::html(
+                    r#"<!DOCTYPE html>
 <html lang="en">
 <head>
     <title>Test Page 3</title>
@@ -75,35 +86,335 @@ impl TestServer {
     </div>
 </body>
 </html>"#,
+                ),
             )
+            // Client-side login form: alice/secret, then 2FA code 123456, then /page2
+            .route(
+                "/login",
+                Fixture::html(
+                    r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <title>Sign In</title>
+</head>
+<body>
+    <form id="login">
+        <input id="username" name="username">
+        <input id="password" type="password">
+        <button id="submit" type="submit">Sign in</button>
+    </form>
+    <form id="otp-form" style="display: none">
+        <input id="otp" name="otp">
+        <button type="submit">Verify</button>
+    </form>
+    <p class="error" style="display: none"></p>
+    <script>
+        const error = document.querySelector('.error');
+        document.getElementById('login').addEventListener('submit', (e) => {
+            e.preventDefault();
+            const ok = document.getElementById('username').value === 'alice'
+                && document.getElementById('password').value === 'secret';
+            if (ok) {
+                // 2FA prompt appears after a short delay
+                setTimeout(() => {
+                    document.getElementById('login').remove();
+                    document.getElementById('otp-form').style.display = 'block';
+                }, 200);
+            } else {
+                error.textContent = 'Invalid credentials';
+                error.style.display = 'block';
+            }
         });
+        document.getElementById('otp-form').addEventListener('submit', (e) => {
+            e.preventDefault();
+            if (document.getElementById('otp').value === '123456') {
+                window.location.href = '/page2';
+            } else {
+                error.textContent = 'Invalid code';
+                error.style.display = 'block';
+            }
+        });
+    </script>
+</body>
+</html>"#,
+                ),
+            )
+            // Page blocked by a (never-loading) hCaptcha checkbox frame
+            .route(
+                "/captcha",
+                Fixture::html(
+                    r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <title>Verify You Are Human</title>
+</head>
+<body>
+    <div class="h-captcha" data-sitekey="10000000-ffff-ffff-ffff-000000000001">
+        <iframe src="https://newassets.hcaptcha.com/captcha/v1/static/hcaptcha.html#frame=checkbox" width="300" height="80"></iframe>
+    </div>
+</body>
+</html>"#,
+                ),
+            )
+            // Link that opens page 2 in a new tab
+            .route(
+                "/popup",
+                Fixture::html(
+                    r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <title>Popup Opener</title>
+</head>
+<body>
+    <a id="open" href="/page2" target="_blank">Open Page 2 in a new tab</a>
+</body>
+</html>"#,
+                ),
+            )
+            // PWA whose service worker answers /version from its cache ("stale")
+            .route(
+                "/pwa",
+                Fixture::html(
+                    r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <title>Offline App</title>
+</head>
+<body>
+    <p>Offline-capable app</p>
+    <script>
+        window.swReady = navigator.serviceWorker.register('/sw.js')
+            .then(() => navigator.serviceWorker.ready)
+            .then(() => navigator.serviceWorker.controller || new Promise((resolve) =>
+                navigator.serviceWorker.addEventListener('controllerchange', resolve)));
+    </script>
+</body>
+</html>"#,
+                ),
+            )
+            .route(
+                "/sw.js",
+                Fixture::new(
+                    "application/javascript",
+                    r#"self.addEventListener('install', (event) => {
+    event.waitUntil(caches.open('pwa-v1').then((cache) => cache.put('/version', new Response('stale'))));
+    self.skipWaiting();
+});
+self.addEventListener('activate', (event) => event.waitUntil(self.clients.claim()));
+self.addEventListener('fetch', (event) => {
+    if (new URL(event.request.url).pathname === '/version') {
+        event.respondWith(caches.match('/version').then((cached) => cached || fetch(event.request)));
+    }
+});"#,
+                ),
+            )
+            .route("/version", Fixture::text("fresh"))
+            .route(
+                "/api/products",
+                Fixture::json(&serde_json::json!({
+                    "data": {"products": [{"id": 1, "name": "Widget"}, {"id": 2, "name": "Gadget"}]}
+                })),
+            )
+            .route(
+                "/api/flags",
+                Fixture::json(&serde_json::json!({"new_checkout": false, "banner": "old"})),
+            )
+            // Error pages with a body, as real servers send them
+            .route(
+                "/missing",
+                Fixture::html("<html><head><title>Not Found</title></head><body>404</body></html>")
+                    .status(404),
+            )
+            .route(
+                "/broken",
+                Fixture::html("<html><head><title>Error</title></head><body>500</body></html>")
+                    .status(500),
+            )
+            // Page with a missing image and script, and one dead link among good ones
+            .route(
+                "/dead-links",
+                Fixture::html(
+                    r#"<!DOCTYPE html><html><head><title>Dead Links</title>
+<script src="/assets/missing.js"></script></head>
+<body><img src="/assets/missing.png" alt="missing">
+<a href="/site">Site</a> <a href="/site#top">Site again</a> <a href="/missing">Gone</a>
+<a href="mailto:team@example.com">Mail</a></body></html>"#,
+                ),
+            )
+            // Page with SEO metadata, including an invalid JSON-LD block
+            .route(
+                "/seo",
+                Fixture::html(
+                    r#"<!DOCTYPE html><html lang="en"><head>
+<title>Widgets for every occasion | Example Shop</title>
+<meta name="description" content="Short description">
+<meta name="robots" content="index, follow">
+<link rel="canonical" href="/seo">
+<meta property="og:title" content="Widgets">
+<meta property="og:type" content="website">
+<meta name="twitter:card" content="summary">
+<script type="application/ld+json">{"@context": "https://schema.org", "@type": "Product", "name": "Widget"}</script>
+<script type="application/ld+json">{"@type": </script>
+</head><body><h1>Widgets</h1></body></html>"#,
+                ),
+            )
+            // Form for autofill, recording the input and change events each field receives
+            .route(
+                "/form",
+                Fixture::html(
+                    r#"<!DOCTYPE html><html><head><title>Signup</title></head><body>
+<form id="signup">
+<label for="email">Email address *</label> <input id="email" name="user_email" type="email">
+<input name="full_name" placeholder="Full name" value="old value">
+<label>Country <select name="country"><option value="us">United States</option><option value="ca">Canada</option></select></label>
+<label><input type="checkbox" name="newsletter"> Subscribe to newsletter</label>
+<fieldset><legend>Plan</legend>
+<label><input type="radio" name="plan" value="free"> Free</label>
+<label><input type="radio" name="plan" value="pro"> Pro</label>
+</fieldset>
+<input type="hidden" name="token" value="secret">
+</form>
+<script>
+window.events = {};
+document.getElementById('signup').addEventListener('input', (e) => {
+    (window.events[e.target.name] = window.events[e.target.name] || []).push('input');
+});
+document.getElementById('signup').addEventListener('change', (e) => {
+    (window.events[e.target.name] = window.events[e.target.name] || []).push('change');
+});
+</script></body></html>"#,
+                ),
+            )
+            // Widgets that plain typing doesn't fill: a date input, a rich text editor that
+            // only takes text through beforeinput, a combobox, and a custom picker
+            .route(
+                "/widgets",
+                Fixture::html(
+                    r#"<!DOCTYPE html><html><head><title>Widgets</title></head><body>
+<input id="date" type="date">
+<div id="editor" contenteditable="true"><p>Old text</p></div>
+<input id="fruit" role="combobox" aria-controls="fruits" autocomplete="off">
+<ul id="fruits" role="listbox" hidden></ul>
+<span id="picked"></span>
+<div id="acme" class="acme-picker"></div>
+<script>
+window.editorInputs = 0;
+document.getElementById('editor').addEventListener('beforeinput', () => window.editorInputs++);
+const fruit = document.getElementById('fruit');
+const list = document.getElementById('fruits');
+fruit.addEventListener('input', () => {
+    const query = fruit.value.toLowerCase();
+    // Render options a moment later, like a framework would
+    setTimeout(() => {
+        list.innerHTML = ['Apple', 'Apricot', 'Banana']
+            .filter((f) => f.toLowerCase().startsWith(query))
+            .map((f) => `<li role="option">${f}</li>`).join('');
+        list.hidden = false;
+    }, 200);
+});
+list.addEventListener('click', (e) => {
+    document.getElementById('picked').textContent = e.target.textContent;
+    fruit.value = e.target.textContent;
+    list.hidden = true;
+});
+document.getElementById('acme').acme = { set(value) { this.value = value; } };
+</script></body></html>"#,
+                ),
+            )
+            // Search box recording the IME composition events it receives
+            .route(
+                "/ime",
+                Fixture::html(
+                    r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>IME</title></head><body>
+<input id="query">
+<script>
+window.compositions = [];
+const query = document.getElementById('query');
+query.addEventListener('compositionstart', () => window.compositions.push('start'));
+query.addEventListener('compositionend', (e) => window.compositions.push(e.data));
+</script></body></html>"#,
+                ),
+            )
+            // Touch-only carousel recording the gestures it sees
+            .route(
+                "/touch",
+                Fixture::html(
+                    r#"<!DOCTYPE html><html><head><title>Touch</title>
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<style>body { margin: 0; } #carousel { width: 100vw; height: 100vh; touch-action: none; }</style>
+</head><body>
+<button id="like">Like</button>
+<div id="carousel">Slide <span id="slide">0</span></div>
+<script>
+window.gestures = { taps: 0, slide: 0, fingers: 0, spread: [] };
+let startX = null;
+const distance = (touches) => Math.abs(touches[0].clientX - touches[1].clientX);
+document.getElementById('like').addEventListener('touchend', () => window.gestures.taps++);
+const carousel = document.getElementById('carousel');
+carousel.addEventListener('touchstart', (e) => {
+    startX = e.touches[0].clientX;
+    window.gestures.fingers = Math.max(window.gestures.fingers, e.touches.length);
+    if (e.touches.length === 2) window.gestures.spread.push(distance(e.touches));
+});
+carousel.addEventListener('touchmove', (e) => {
+    if (e.touches.length === 2) window.gestures.spread.push(distance(e.touches));
+});
+carousel.addEventListener('touchend', (e) => {
+    if (window.gestures.fingers > 1) return;
+    const dx = e.changedTouches[0].clientX - startX;
+    if (Math.abs(dx) > 50) window.gestures.slide += dx < 0 ? 1 : -1;
+    document.getElementById('slide').textContent = window.gestures.slide;
+});
+</script></body></html>"#,
+                ),
+            )
+            // Small site for crawling: /site links to a, b (twice, once with a fragment),
+            // a private page excluded by robots.txt, and an external page
+            .route(
+                "/site",
+                site_page(
+                    "Site",
+                    &[
+                        "/site/a",
+                        "/site/b",
+                        "/site/b#details",
+                        "/site/private",
+                        "https://example.com/",
+                    ],
+                ),
+            )
+            .route("/site/a", site_page("A", &["/site/c", "/site"]))
+            .route("/site/b", site_page("B", &[]))
+            .route("/site/c", site_page("C", &[]))
+            .route("/site/private", site_page("Private", &[]))
+            .route(
+                "/robots.txt",
+                Fixture::text("User-agent: *\nDisallow: /site/private\n"),
+            )
+            // Sitemap of the site plus a page that 404s; locs need the absolute host
+            .route(
+                "/sitemap.xml",
+                Fixture::new(
+                    "application/xml",
+                    r#"<?xml version="1.0" encoding="UTF-8"?><urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"><url><loc>{{base_url}}/site</loc></url><url><loc>{{base_url}}/site/b</loc></url><url><loc>{{base_url}}/missing</loc></url></urlset>"#,
+                ),
+            )
+            .start()
+            .await
+            .expect("Failed to start test server");
 
-        let routes = index.or(page2).or(page3);
-
-        // Bind to random port
-        let (addr, server) =
-            warp::serve(routes).bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
-                shutdown_rx.await.ok();
-            });
-
-        // Spawn server in background
-        tokio::spawn(server);
-
-        Self {
-            addr,
-            shutdown_tx: Some(shutdown_tx),
-        }
+        Self { server }
     }
 
     /// Get the base URL for this server (e.g., "http://127.0.0.1:12345")
     pub fn url(&self) -> String {
-        format!("http://{}", self.addr)
+        self.server.url()
     }
 
     /// Get the socket address (for meta tests)
     #[allow(dead_code)]
     pub fn addr(&self) -> SocketAddr {
-        self.addr
+        self.server.addr()
     }
 
     /// Wait for the server to be ready by making a test request
@@ -140,12 +451,3 @@ impl TestServer {
         )
     }
 }
-
-impl Drop for TestServer {
-    fn drop(&mut self) {
-        // Signal server to shutdown
-        if let Some(tx) = self.shutdown_tx.take() {
-            let _ = tx.send(());
-        }
-    }
-}
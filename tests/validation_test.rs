@@ -551,6 +551,10 @@ fn test_all_supported_commands() {
             r#"{"width": 1920, "height": 1080, "deviceScaleFactor": 1.0, "mobile": false}"#,
         ),
         ("Emulation.clearGeolocationOverride", r#"{}"#),
+        (
+            "Emulation.setEmulatedMedia",
+            r#"{"media": "print", "features": [{"name": "prefers-color-scheme", "value": "dark"}]}"#,
+        ),
     ];
 
     for (method, params) in supported_commands {